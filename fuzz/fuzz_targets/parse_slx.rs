@@ -0,0 +1,16 @@
+//! Raw bytes as an `.slx` archive: exercises the ZIP reader and size limits.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustylink::parser::{SimulinkParser, ZipSource};
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(mut source) = ZipSource::new(Cursor::new(data)) else {
+        return;
+    };
+    let _ = source.protection();
+    let _ = source.extract_assets();
+    let mut parser = SimulinkParser::new("", source);
+    let _ = parser.parse_system_file("simulink/systems/system_root.xml");
+});
@@ -0,0 +1,33 @@
+//! Splits the input into system XML files and packs them into a valid
+//! archive, so mutations reach reference linking instead of failing in the
+//! ZIP reader.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rustylink::parser::{SimulinkParser, ZipSource};
+use std::io::{Cursor, Write};
+
+fuzz_target!(|data: &[u8]| {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (i, part) in data.split(|&b| b == 0).take(16).enumerate() {
+        let name = match i {
+            0 => "simulink/systems/system_root.xml".to_string(),
+            i => format!("simulink/systems/system_{i}.xml"),
+        };
+        if zip
+            .start_file(name, zip::write::FileOptions::default())
+            .and_then(|()| Ok(zip.write_all(part)?))
+            .is_err()
+        {
+            return;
+        }
+    }
+    let Ok(archive) = zip.finish() else {
+        return;
+    };
+    let Ok(source) = ZipSource::new(Cursor::new(archive.into_inner())) else {
+        return;
+    };
+    let mut parser = SimulinkParser::new("", source);
+    let _ = parser.parse_system_file("simulink/systems/system_root.xml");
+});
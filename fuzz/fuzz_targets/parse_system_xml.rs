@@ -0,0 +1,20 @@
+//! Raw bytes as a system XML file: exercises the XML limits and the shallow
+//! system parser.
+#![no_main]
+
+use camino::Utf8Path;
+use libfuzzer_sys::fuzz_target;
+use rustylink::parser::ParseLimits;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(doc) = ParseLimits::default().parse_xml(text, "fuzz.xml") else {
+        return;
+    };
+    if let Some(system) = doc.descendants().find(|n| n.has_tag_name("System")) {
+        let _ = rustylink::block::parse_system_shallow(system, Utf8Path::new(""));
+    }
+    let _ = rustylink::parser::chart::parse_chart_from_text(text, None);
+});
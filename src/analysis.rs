@@ -0,0 +1,737 @@
+//! Model analysis.
+//!
+//! [`connections`] flattens a system's lines and branches into port-to-port
+//! connections as drawn. [`effective_connections`] applies Simulink's comment
+//! semantics on top: commented-out blocks drop out together with their
+//! signals, while commented-through blocks are bypassed so that whatever
+//! feeds input *n* drives the signals leaving output *n*.
+//!
+//! [`find_magic_numbers`] reports numeric literals in block parameters that
+//! should refer to named workspace constants instead.
+//!
+//! [`find_clones`] detects copy-pasted subsystems by their structure (block
+//! types and wiring, ignoring names, positions and parameters), as
+//! candidates for extraction into a library.
+//!
+//! [`rate_transition_issues`] checks multirate wiring against the propagated
+//! sample times from [`crate::sample_time`].
+
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::model::{Block, CommentMode, EndpointRef, Line, System};
+use crate::overlay::block_path;
+use crate::sample_time::{SampleTime, SampleTimes, declared};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// One source-to-destination connection in a system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    pub src: EndpointRef,
+    pub dst: EndpointRef,
+}
+
+/// All connections drawn in `system`, one per destination port, in line
+/// order. Lines without a source or destination are skipped.
+pub fn connections(system: &System) -> Vec<Connection> {
+    system
+        .lines
+        .iter()
+        .flat_map(Line::connections)
+        .filter_map(|c| {
+            Some(Connection {
+                src: c.src?,
+                dst: c.dst,
+            })
+        })
+        .collect()
+}
+
+/// Connections of `system` after resolving commented blocks.
+///
+/// Connections into commented blocks are dropped. A connection leaving a
+/// commented-through block is rerouted to the source feeding the matching
+/// input (input 1 if the block has a single input), following chains of
+/// commented-through blocks; it is dropped if that input is unconnected or
+/// the chain ends at a commented-out block.
+pub fn effective_connections(system: &System) -> Vec<Connection> {
+    let modes: HashMap<&str, CommentMode> = system
+        .blocks
+        .iter()
+        .filter_map(|b| Some((b.sid.as_deref()?, b.comment_mode())))
+        .collect();
+    let mode = |sid: &str| modes.get(sid).copied().unwrap_or_default();
+    let all = connections(system);
+
+    // Data inputs of commented-through blocks: (sid, port) -> source.
+    let mut through_inputs: HashMap<(&str, u32), &EndpointRef> = HashMap::new();
+    // Input counts from `PortCounts`, else the highest connected input.
+    let mut input_counts: HashMap<&str, u32> = system
+        .blocks
+        .iter()
+        .filter_map(|b| Some((b.sid.as_deref()?, b.port_counts.as_ref()?.ins?)))
+        .collect();
+    for c in &all {
+        if c.dst.port_type == "in" && mode(&c.dst.sid) == CommentMode::Through {
+            through_inputs.insert((c.dst.sid.as_str(), c.dst.port_index), &c.src);
+        }
+    }
+    for &(sid, port) in through_inputs.keys() {
+        let n = input_counts.entry(sid).or_default();
+        *n = (*n).max(port);
+    }
+
+    let resolve = |src: &EndpointRef| -> Option<EndpointRef> {
+        let mut current = src;
+        // Each hop passes through a distinct block, so a longer chain is a cycle.
+        for _ in 0..=modes.len() {
+            match mode(&current.sid) {
+                CommentMode::Off => return Some(current.clone()),
+                CommentMode::Out => return None,
+                CommentMode::Through => {
+                    let sid = current.sid.as_str();
+                    let port = if input_counts.get(sid) == Some(&1) {
+                        1
+                    } else {
+                        current.port_index
+                    };
+                    current = through_inputs.get(&(sid, port))?;
+                }
+            }
+        }
+        None
+    };
+
+    all.iter()
+        .filter(|c| mode(&c.dst.sid) == CommentMode::Off)
+        .filter_map(|c| {
+            Some(Connection {
+                src: resolve(&c.src)?,
+                dst: c.dst.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Numeric parameters checked by [`find_magic_numbers`], per block type.
+pub const MAGIC_NUMBER_PARAMS: &[(&str, &[&str])] = &[
+    ("Gain", &["Gain"]),
+    ("Constant", &["Value"]),
+    ("Saturate", &["UpperLimit", "LowerLimit"]),
+    ("DeadZone", &["LowerValue", "UpperValue"]),
+    (
+        "Relay",
+        &[
+            "OnSwitchValue",
+            "OffSwitchValue",
+            "OnOutputValue",
+            "OffOutputValue",
+        ],
+    ),
+    ("Switch", &["Threshold"]),
+];
+
+/// Options for [`find_magic_numbers`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct MagicNumberOptions {
+    /// Literal values that are always acceptable.
+    pub allowed: Vec<f64>,
+}
+
+impl Default for MagicNumberOptions {
+    /// Allows `0`, `1` and `-1`.
+    fn default() -> Self {
+        Self {
+            allowed: vec![0.0, 1.0, -1.0],
+        }
+    }
+}
+
+/// A block parameter containing numeric literals instead of a symbolic
+/// workspace reference.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MagicNumber {
+    /// Block path from the root, e.g. `"Controller/Kp"`.
+    pub path: String,
+    pub block_type: String,
+    pub parameter: String,
+    /// The full parameter expression.
+    pub expression: String,
+    /// The offending literals, in order of appearance.
+    pub literals: Vec<String>,
+}
+
+/// Find numeric literals in the parameters listed in [`MAGIC_NUMBER_PARAMS`]
+/// and in the threshold of Compare To Constant blocks, across `system` and
+/// all its subsystems. Commented-out blocks are skipped.
+pub fn find_magic_numbers(system: &System, options: &MagicNumberOptions) -> Vec<MagicNumber> {
+    let mut found = Vec::new();
+    let mut path = Vec::new();
+    system.walk_blocks(&mut path, &mut |p, block| {
+        if block.commented {
+            return;
+        }
+        let mut params: Vec<(&str, &str)> = MAGIC_NUMBER_PARAMS
+            .iter()
+            .filter(|(t, _)| *t == block.block_type)
+            .flat_map(|(_, names)| names.iter())
+            .filter_map(|&n| Some((n, block.properties.get(n)?.as_str())))
+            .collect();
+        if is_compare_to_constant(block) {
+            let value = block
+                .instance_data
+                .as_ref()
+                .and_then(|d| d.properties.get("const"));
+            params.extend(value.map(|v| ("const", v.as_str())));
+        }
+        for (parameter, expression) in params {
+            let literals: Vec<String> = numeric_literals(expression)
+                .into_iter()
+                .filter(|l| !is_allowed(l, &options.allowed))
+                .collect();
+            if !literals.is_empty() {
+                found.push(MagicNumber {
+                    path: crate::overlay::block_path(p, &block.name),
+                    block_type: block.block_type.clone(),
+                    parameter: parameter.to_string(),
+                    expression: expression.to_string(),
+                    literals,
+                });
+            }
+        }
+    });
+    found
+}
+
+fn is_compare_to_constant(block: &crate::model::Block) -> bool {
+    let source = block
+        .library_block_path
+        .as_deref()
+        .or_else(|| block.properties.get("SourceBlock").map(String::as_str));
+    source.is_some_and(|s| {
+        let name = s.rsplit('/').next().unwrap_or(s);
+        name.split_whitespace().collect::<Vec<_>>().join(" ") == "Compare To Constant"
+    })
+}
+
+fn is_allowed(literal: &str, allowed: &[f64]) -> bool {
+    literal.parse::<f64>().is_ok_and(|v| allowed.contains(&v))
+}
+
+/// Numeric literals in a MATLAB expression, with a directly preceding unary
+/// minus attached (`-2` in `[1 -2]`, but not in `a-2`). Digits inside
+/// identifiers (`k2`) and string literals are ignored.
+pub fn numeric_literals(expr: &str) -> Vec<String> {
+    let chars: Vec<char> = expr.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    // Last significant character before the current position.
+    let mut prev: Option<char> = None;
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\''
+            && !prev
+                .is_some_and(|p| p.is_alphanumeric() || matches!(p, ')' | ']' | '_' | '.' | '\''))
+        {
+            // String literal (a quote after an operand is a transpose).
+            i += 1;
+            while i < chars.len() && chars[i] != '\'' {
+                i += 1;
+            }
+            i += 1;
+            prev = Some('\'');
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            prev = Some('a');
+            continue;
+        }
+        let starts_number =
+            c.is_ascii_digit() || c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit);
+        if starts_number {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                let mut j = i + 1;
+                if j < chars.len() && matches!(chars[j], '+' | '-') {
+                    j += 1;
+                }
+                if j < chars.len() && chars[j].is_ascii_digit() {
+                    i = j;
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+            }
+            let mut literal: String = chars[start..i].iter().collect();
+            if is_unary_minus(&chars, start) {
+                literal.insert(0, '-');
+            }
+            out.push(literal);
+            prev = Some('0');
+            continue;
+        }
+        if !c.is_whitespace() {
+            prev = Some(c);
+        }
+        i += 1;
+    }
+    out
+}
+
+/// Whether the number starting at `start` is directly preceded by a minus
+/// that negates it rather than subtracts.
+fn is_unary_minus(chars: &[char], start: usize) -> bool {
+    if start == 0 || chars[start - 1] != '-' {
+        return false;
+    }
+    let mut k = start - 1;
+    let mut saw_space = false;
+    while k > 0 {
+        k -= 1;
+        let c = chars[k];
+        if c.is_whitespace() {
+            saw_space = true;
+            continue;
+        }
+        let operand = c.is_alphanumeric() || matches!(c, ')' | ']' | '}' | '_' | '.' | '\'');
+        // `[1 -2]` lists two elements, so a spaced-off minus is a sign.
+        return !operand || saw_space;
+    }
+    true
+}
+
+/// Minimum similarity for two subsystems to be reported as near clones.
+pub const CLONE_SIMILARITY_THRESHOLD: f32 = 0.7;
+
+/// Refinement rounds of the structural hash; each round lets a block's label
+/// take one more hop of its neighborhood into account.
+const CLONE_HASH_ROUNDS: usize = 3;
+
+/// Rounds whose labels feed the similarity score. Later rounds react to any
+/// change anywhere in small subsystems, so only block types and their direct
+/// wiring are compared.
+const CLONE_FEATURE_ROUNDS: usize = 1;
+
+/// Subsystems with the same or nearly the same structure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CloneGroup {
+    /// Paths of the subsystem blocks from the root, e.g. `"Left/Filter"`.
+    pub paths: Vec<String>,
+    /// Number of blocks directly inside the largest member.
+    pub block_count: usize,
+    /// Lowest pairwise similarity in the group, `1.0` for exact clones.
+    pub similarity: f32,
+}
+
+/// Find groups of structurally matching subsystems with at least
+/// `min_blocks` blocks directly inside them.
+///
+/// Subsystems are compared by block types (plus port numbers of Inport and
+/// Outport blocks, library links and the structure of nested subsystems)
+/// and connectivity. Exact matches form groups with similarity `1.0`;
+/// groups whose similarity is at least [`CLONE_SIMILARITY_THRESHOLD`] are
+/// merged as near clones. Library-linked subsystems and Stateflow charts
+/// are skipped. Groups are ordered by size, largest first.
+pub fn find_clones(system: &System, min_blocks: usize) -> Vec<CloneGroup> {
+    find_clones_with_cancel(system, min_blocks, &CancellationToken::new())
+        .expect("a fresh token is never cancelled")
+}
+
+/// [`find_clones`], stopping with [`Cancelled`] once `cancel` is cancelled.
+pub fn find_clones_with_cancel(
+    system: &System,
+    min_blocks: usize,
+    cancel: &CancellationToken,
+) -> Result<Vec<CloneGroup>, Cancelled> {
+    let mut candidates: Vec<(String, Fingerprint)> = Vec::new();
+    let mut path = Vec::new();
+    system.walk_blocks(&mut path, &mut |p, block| {
+        let Some(sub) = block.subsystem.as_deref() else {
+            return;
+        };
+        if block.block_type == "Reference" || sub.chart.is_some() || sub.blocks.len() < min_blocks {
+            return;
+        }
+        let path = crate::overlay::block_path(p, &block.name);
+        candidates.push((path, Fingerprint::of(sub)));
+    });
+    cancel.check()?;
+
+    // Exact clones share a hash; near clones are merged between those sets.
+    let mut exact: Vec<(Fingerprint, Vec<String>)> = Vec::new();
+    for (path, fp) in candidates {
+        match exact.iter_mut().find(|(f, _)| f.hash == fp.hash) {
+            Some((_, paths)) => paths.push(path),
+            None => exact.push((fp, vec![path])),
+        }
+    }
+    let mut parent: Vec<usize> = (0..exact.len()).collect();
+    fn root(parent: &mut [usize], mut i: usize) -> usize {
+        while parent[i] != i {
+            parent[i] = parent[parent[i]];
+            i = parent[i];
+        }
+        i
+    }
+    for i in 0..exact.len() {
+        cancel.check()?;
+        for j in i + 1..exact.len() {
+            if exact[i].0.similarity(&exact[j].0) >= CLONE_SIMILARITY_THRESHOLD {
+                let (a, b) = (root(&mut parent, i), root(&mut parent, j));
+                parent[b] = a;
+            }
+        }
+    }
+    let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for i in 0..exact.len() {
+        members.entry(root(&mut parent, i)).or_default().push(i);
+    }
+
+    let mut groups: Vec<CloneGroup> = members
+        .into_values()
+        .filter_map(|set| {
+            let paths: Vec<String> = set.iter().flat_map(|&i| exact[i].1.clone()).collect();
+            if paths.len() < 2 {
+                return None;
+            }
+            let mut similarity = 1.0f32;
+            for (n, &i) in set.iter().enumerate() {
+                for &j in &set[n + 1..] {
+                    similarity = similarity.min(exact[i].0.similarity(&exact[j].0));
+                }
+            }
+            let block_count = set.iter().map(|&i| exact[i].0.block_count).max()?;
+            Some(CloneGroup {
+                paths,
+                block_count,
+                similarity,
+            })
+        })
+        .collect();
+    groups.sort_by(|a, b| {
+        b.block_count
+            .cmp(&a.block_count)
+            .then_with(|| b.paths.len().cmp(&a.paths.len()))
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    Ok(groups)
+}
+
+/// Structural summary of a system used by [`find_clones`].
+#[derive(Debug, Clone)]
+struct Fingerprint {
+    hash: u64,
+    /// Multiset of block labels from the first refinement rounds.
+    features: BTreeMap<u64, usize>,
+    block_count: usize,
+}
+
+impl Fingerprint {
+    fn of(system: &System) -> Self {
+        let index: HashMap<&str, usize> = system
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, b)| Some((b.sid.as_deref()?, i)))
+            .collect();
+        let conns = connections(system);
+        let edges: Vec<(usize, u32, usize, &str, u32)> = conns
+            .iter()
+            .filter_map(|c| {
+                let src = *index.get(c.src.sid.as_str())?;
+                let dst = *index.get(c.dst.sid.as_str())?;
+                Some((
+                    src,
+                    c.src.port_index,
+                    dst,
+                    c.dst.port_type.as_str(),
+                    c.dst.port_index,
+                ))
+            })
+            .collect();
+
+        let mut labels: Vec<u64> = system.blocks.iter().map(initial_label).collect();
+        let mut features: BTreeMap<u64, usize> = BTreeMap::new();
+        for round in 0..=CLONE_HASH_ROUNDS {
+            if round <= CLONE_FEATURE_ROUNDS {
+                for &l in &labels {
+                    *features.entry(l).or_default() += 1;
+                }
+            }
+            if round == CLONE_HASH_ROUNDS {
+                break;
+            }
+            labels = (0..labels.len())
+                .map(|i| {
+                    let mut neighborhood: Vec<(bool, u32, &str, u32, u64)> = edges
+                        .iter()
+                        .filter_map(|&(s, sp, d, dt, dp)| {
+                            if s == i {
+                                Some((true, sp, dt, dp, labels[d]))
+                            } else if d == i {
+                                Some((false, sp, dt, dp, labels[s]))
+                            } else {
+                                None
+                            }
+                        })
+                        .collect();
+                    neighborhood.sort_unstable();
+                    hash_of(&(labels[i], neighborhood))
+                })
+                .collect();
+        }
+
+        let mut final_labels = labels;
+        final_labels.sort_unstable();
+        Self {
+            hash: hash_of(&(final_labels, edges.len())),
+            features,
+            block_count: system.blocks.len(),
+        }
+    }
+
+    /// Weighted Jaccard similarity of the label multisets.
+    fn similarity(&self, other: &Self) -> f32 {
+        let (mut common, mut total) = (0usize, 0usize);
+        let keys: std::collections::BTreeSet<&u64> =
+            self.features.keys().chain(other.features.keys()).collect();
+        for k in keys {
+            let a = self.features.get(k).copied().unwrap_or(0);
+            let b = other.features.get(k).copied().unwrap_or(0);
+            common += a.min(b);
+            total += a.max(b);
+        }
+        if total == 0 {
+            1.0
+        } else {
+            common as f32 / total as f32
+        }
+    }
+}
+
+/// Label of a block before looking at its neighbors.
+fn initial_label(block: &Block) -> u64 {
+    let port = matches!(block.block_type.as_str(), "Inport" | "Outport").then(|| {
+        block
+            .properties
+            .get("Port")
+            .map(String::as_str)
+            .unwrap_or("1")
+    });
+    let library = block
+        .library_block_path
+        .as_deref()
+        .or_else(|| block.properties.get("SourceBlock").map(String::as_str))
+        .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "));
+    let nested = block.subsystem.as_deref().map(|s| Fingerprint::of(s).hash);
+    hash_of(&(&block.block_type, port, library, nested))
+}
+
+fn hash_of<T: Hash>(value: &T) -> u64 {
+    let mut h = DefaultHasher::new();
+    value.hash(&mut h);
+    h.finish()
+}
+
+/// Block types that may combine signals of different rates without a Rate
+/// Transition, because they do not execute (virtual blocks) or only observe.
+const MULTIRATE_SAFE_BLOCK_TYPES: &[&str] = &[
+    "SubSystem",
+    "Mux",
+    "Demux",
+    "BusCreator",
+    "BusSelector",
+    "Goto",
+    "From",
+    "Outport",
+    "Terminator",
+    "Scope",
+    "Display",
+];
+
+/// What [`rate_transition_issues`] found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RateIssueKind {
+    /// A discrete signal feeds a block declaring a different discrete rate.
+    MissingRateTransition,
+    /// An inheriting block is fed by several discrete rates.
+    MixedRateInputs,
+    /// A Rate Transition has `Integrity` off: in multitasking mode the
+    /// faster task can preempt the transfer and read inconsistent data.
+    IntegrityDisabled,
+    /// A Rate Transition has `Deterministic` off: the transfer latency
+    /// depends on task scheduling.
+    NondeterministicTransfer,
+    /// A deterministic Rate Transition between rates that are not integer
+    /// multiples of each other (or have different offsets).
+    NonIntegerRateRatio,
+    /// A Rate Transition whose input and output run at the same rate.
+    RedundantRateTransition,
+}
+
+/// A multirate wiring problem at one block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateIssue {
+    /// Block path from the root, e.g. `"Controller/RT"`.
+    pub path: String,
+    pub kind: RateIssueKind,
+    pub message: String,
+}
+
+/// Check the rates at every block of `system` (recursively), using
+/// [`crate::sample_time::propagate`]. Blocks whose rates cannot be resolved
+/// are skipped, as are commented blocks.
+pub fn rate_transition_issues(system: &System) -> Vec<RateIssue> {
+    let times = crate::sample_time::propagate(system);
+    let mut issues = Vec::new();
+    rate_issues_in(system, &mut Vec::new(), &times, &mut issues);
+    issues
+}
+
+fn rate_issues_in(
+    system: &System,
+    path: &mut Vec<String>,
+    times: &SampleTimes,
+    issues: &mut Vec<RateIssue>,
+) {
+    let conns = effective_connections(system);
+    let by_sid: HashMap<&str, &Block> = system
+        .blocks
+        .iter()
+        .filter_map(|b| Some((b.sid.as_deref()?, b)))
+        .collect();
+    for block in &system.blocks {
+        if let Some(sub) = block.subsystem.as_deref() {
+            path.push(block.name.clone());
+            rate_issues_in(sub, path, times, issues);
+            path.pop();
+        }
+        let Some(sid) = block.sid.as_deref() else {
+            continue;
+        };
+        if block.commented {
+            continue;
+        }
+        let here = block_path(path, &block.name);
+        // (input port, source block, rate) of every driven data input.
+        let inputs: Vec<(u32, &Block, SampleTime)> = conns
+            .iter()
+            .filter(|c| c.dst.sid == sid && c.dst.port_type == "in")
+            .filter_map(|c| {
+                let src = *by_sid.get(c.src.sid.as_str())?;
+                let rate = times.output(&block_path(path, &src.name), c.src.port_index)?;
+                Some((c.dst.port_index, src, rate))
+            })
+            .collect();
+        let mut issue = |kind, message: String| {
+            issues.push(RateIssue {
+                path: here.clone(),
+                kind,
+                message,
+            })
+        };
+
+        if block.block_type == "RateTransition" {
+            let (Some(&(_, _, input)), Some(output)) = (inputs.first(), times.block(&here)) else {
+                continue;
+            };
+            let enabled = |param: &str| block.properties.get(param).is_none_or(|v| v != "off");
+            if input == output {
+                issue(
+                    RateIssueKind::RedundantRateTransition,
+                    format!("input and output both run at {}", input),
+                );
+                continue;
+            }
+            if !enabled("Integrity") {
+                issue(
+                    RateIssueKind::IntegrityDisabled,
+                    format!(
+                        "data integrity is not ensured between {} and {}",
+                        input, output
+                    ),
+                );
+            }
+            if let (SampleTime::Discrete { .. }, SampleTime::Discrete { .. }) = (input, output) {
+                if !enabled("Deterministic") {
+                    issue(
+                        RateIssueKind::NondeterministicTransfer,
+                        format!(
+                            "transfer latency between {} and {} depends on task scheduling",
+                            input, output
+                        ),
+                    );
+                } else if !is_integer_multiple(input, output) {
+                    issue(
+                        RateIssueKind::NonIntegerRateRatio,
+                        format!(
+                            "deterministic transfer needs integer multiple rates, got {} and {}",
+                            input, output
+                        ),
+                    );
+                }
+            }
+            continue;
+        }
+        if MULTIRATE_SAFE_BLOCK_TYPES.contains(&block.block_type.as_str()) {
+            continue;
+        }
+        let crossing = |src: &Block| src.block_type != "RateTransition";
+        match declared(block) {
+            Some(own @ SampleTime::Discrete { .. }) => {
+                for &(port, src, rate) in &inputs {
+                    if rate.period().is_some() && rate != own && crossing(src) {
+                        issue(
+                            RateIssueKind::MissingRateTransition,
+                            format!(
+                                "input {} from {} runs at {} but the block runs at {}",
+                                port, src.name, rate, own
+                            ),
+                        );
+                    }
+                }
+            }
+            Some(_) => {}
+            None => {
+                let mut rates: Vec<SampleTime> = Vec::new();
+                for &(_, _, rate) in &inputs {
+                    if rate.period().is_some() && !rates.contains(&rate) {
+                        rates.push(rate);
+                    }
+                }
+                if rates.len() > 1 {
+                    let list: Vec<String> = rates.iter().map(|r| r.to_string()).collect();
+                    issue(
+                        RateIssueKind::MixedRateInputs,
+                        format!("inputs run at different rates ({})", list.join(", ")),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Whether the slower of two discrete rates is an integer multiple of the
+/// faster one, with matching offsets.
+fn is_integer_multiple(a: SampleTime, b: SampleTime) -> bool {
+    let (
+        SampleTime::Discrete {
+            period: pa,
+            offset: oa,
+        },
+        SampleTime::Discrete {
+            period: pb,
+            offset: ob,
+        },
+    ) = (a, b)
+    else {
+        return false;
+    };
+    let ratio = pa.max(pb) / pa.min(pb);
+    (ratio - ratio.round()).abs() < 1e-9 && oa == ob
+}
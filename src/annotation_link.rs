@@ -0,0 +1,75 @@
+//! Targets of annotation hyperlinks and click callbacks.
+//!
+//! Simulink annotations open subsystems through MATLAB commands, either as a
+//! `ClickFcn` callback or as a `matlab:` hyperlink in rich text, e.g.
+//! `matlab:open_system('model/Controller/PID')`. [`parse_link_target`]
+//! recognizes `open_system` and `hilite_system` with a literal path or
+//! `[bdroot '/...']`; everything else is left to the host.
+
+use crate::block_path::BlockPath;
+
+/// Where an annotation link leads.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkTarget {
+    /// A block or subsystem, relative to the model root (the model name is
+    /// dropped).
+    Block(BlockPath),
+    /// A URL or MATLAB code that does not name a model element.
+    Other(String),
+}
+
+/// Commands that take a block path as first argument.
+const PATH_COMMANDS: [&str; 2] = ["open_system", "hilite_system"];
+
+/// Read an annotation link or `ClickFcn` callback.
+pub fn parse_link_target(link: &str) -> LinkTarget {
+    let trimmed = link.trim();
+    let code = trimmed.strip_prefix("matlab:").unwrap_or(trimmed);
+    code.split([';', '\n'])
+        .find_map(parse_command)
+        .map_or_else(|| LinkTarget::Other(trimmed.to_string()), LinkTarget::Block)
+}
+
+/// The path of a `open_system(...)`-like command.
+fn parse_command(command: &str) -> Option<BlockPath> {
+    let command = command.trim();
+    let rest = PATH_COMMANDS
+        .iter()
+        .find_map(|name| command.strip_prefix(name))?
+        .trim_start()
+        .strip_prefix('(')?
+        .trim_start();
+    if let Some(concat) = rest.strip_prefix('[') {
+        // [bdroot '/Sub/Block']
+        let concat = concat.trim_start().strip_prefix("bdroot")?;
+        let concat = concat.trim_start().strip_prefix("()").unwrap_or(concat);
+        let (relative, _) = parse_string(concat.trim_start())?;
+        let relative = relative.strip_prefix('/')?;
+        relative.parse().ok()
+    } else {
+        let (full, _) = parse_string(rest)?;
+        let path: BlockPath = full.parse().ok()?;
+        // Full paths start with the model name
+        path.segments().get(1..).map(BlockPath::from)
+    }
+}
+
+/// A MATLAB string literal at the start of `text` and the text after it.
+fn parse_string(text: &str) -> Option<(String, &str)> {
+    let quote = text.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let mut value = String::new();
+    let mut chars = text[1..].char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            // A doubled quote is an escaped one
+            if chars.peek().is_some_and(|(_, next)| *next == quote) {
+                chars.next();
+                value.push(quote);
+                continue;
+            }
+            return Some((value, &text[i + 2..]));
+        }
+        value.push(c);
+    }
+    None
+}
@@ -0,0 +1,95 @@
+//! Desktop viewer for Simulink models (built with `--features egui`).
+//!
+//! Usage:
+//!   rustylink-viewer [file.slx|system.xml] [-s "/path/to/subsystem"] [-L libdir]...
+
+use anyhow::{Context, Result};
+use camino::Utf8PathBuf;
+use clap::Parser;
+use eframe::egui;
+use rustylink::block_path::BlockPath;
+use rustylink::egui_app::viewer::ViewerApp;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about = "View Simulink models", long_about = None)]
+struct Args {
+    /// Simulink .slx file or System XML file to open
+    #[arg(value_name = "SIMULINK_FILE")]
+    file: Option<String>,
+
+    /// Full path of subsystem to show first (e.g. "/Top/Sub"). If omitted,
+    /// the subsystem shown when the model was last closed
+    #[arg(short = 's', long = "system")]
+    system: Option<String>,
+
+    /// Additional directories to search for library `.slx` files. Can be repeated.
+    #[arg(short = 'L', long = "lib")]
+    lib: Vec<String>,
+
+    /// Directory of custom block icons (`<BlockType>.svg` or `<Library>/<Block>.svg`).
+    #[arg(long = "icons")]
+    icons: Option<String>,
+
+    /// Block type config file (`.toml` or `.json`) with colors, icons and port label settings.
+    #[arg(long = "block-config")]
+    block_config: Option<String>,
+
+    /// Block catalog file (`.toml` or `.json`) adding blocks to the editor's
+    /// block browser, see `rustylink catalog export`. Can be repeated.
+    #[arg(long = "catalog")]
+    catalog: Vec<String>,
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if let Some(dir) = &args.icons {
+        rustylink::register_icon_dir(dir)
+            .with_context(|| format!("Failed to load icons from {dir}"))?;
+    }
+    if let Some(config) = &args.block_config {
+        rustylink::load_config(config)?;
+    }
+    for catalog in &args.catalog {
+        rustylink::editor::block_catalog::load_catalog(catalog)?;
+    }
+
+    let mut app = ViewerApp::new(args.lib.iter().map(Utf8PathBuf::from).collect());
+    let file = args.file.map(Utf8PathBuf::from);
+    let initial_path: Vec<String> = args
+        .system
+        .as_deref()
+        .unwrap_or_default()
+        .parse::<BlockPath>()?
+        .into_segments();
+    // An explicit `-s` wins over the subsystem remembered from last time.
+    let restore_session = args.system.is_none();
+
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_maximized(true)
+        .with_drag_and_drop(true);
+    if let Ok(icon) =
+        eframe::icon_data::from_png_bytes(include_bytes!("../../docs/RustyLinkIconSmall.png"))
+    {
+        viewport = viewport.with_icon(icon);
+    }
+    let options = eframe::NativeOptions {
+        viewport,
+        ..Default::default()
+    };
+    eframe::run_native(
+        "rustylink viewer",
+        options,
+        Box::new(move |cc| {
+            cc.egui_ctx.set_visuals(egui::Visuals::light());
+            if let Some(storage) = cc.storage {
+                app.restore(storage);
+            }
+            if let Some(file) = &file {
+                app.open_in_background(file, initial_path, restore_session, &cc.egui_ctx);
+            }
+            Ok(Box::new(app))
+        }),
+    )
+    .map_err(|e| anyhow::anyhow!("{e}"))?;
+    Ok(())
+}
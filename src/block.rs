@@ -40,6 +40,14 @@ pub fn parse_annotation_node(node: Node) -> Result<Annotation> {
         }
     }
 
+    let prop = |name: &str| properties.get(name).map(String::as_str);
+    let foreground_color = prop("ForegroundColor").and_then(crate::color::parse_simulink_color);
+    let background_color = prop("BackgroundColor").and_then(crate::color::parse_simulink_color);
+    let drop_shadow = prop("DropShadow").is_some_and(|v| v.eq_ignore_ascii_case("on"));
+    let font_name = prop("FontName").and_then(parse_font_name);
+    let font_size = prop("FontSize").and_then(|v| v.parse::<u32>().ok());
+    let font_weight = prop("FontWeight").map(str::to_string);
+
     Ok(Annotation {
         sid,
         text,
@@ -47,9 +55,25 @@ pub fn parse_annotation_node(node: Node) -> Result<Annotation> {
         zorder,
         interpreter,
         properties,
+        foreground_color,
+        background_color,
+        drop_shadow,
+        font_name,
+        font_size,
+        font_weight,
     })
 }
 
+/// `FontName` value, or `None` for Simulink's `"auto"` (use the default font).
+fn parse_font_name(value: &str) -> Option<String> {
+    let value = value.trim();
+    if value.is_empty() || value.eq_ignore_ascii_case("auto") {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
 // ────────────────────────────────────────────────────────────────────────────
 // Mask
 // ────────────────────────────────────────────────────────────────────────────
@@ -386,7 +410,7 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
     let name = node.attribute("Name").unwrap_or("").to_string();
     let sid = node.attribute("SID").map(|s| s.to_string());
 
-    let mut properties: IndexMap<String, String> = IndexMap::new();
+    let mut properties = PropertyBag::new();
     let mut ref_properties = std::collections::BTreeSet::new();
     let mut ports = Vec::new();
     let mut position = None;
@@ -394,7 +418,7 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
     let mut port_counts: Option<PortCounts> = None;
     let mut subsystem: Option<Box<System>> = None;
     let mut system_ref: Option<String> = None;
-    let mut commented = false;
+    let mut comment_mode = CommentMode::Off;
     let mut is_matlab_function = false;
     let mut c_output_code: Option<String> = None;
     let mut c_start_code: Option<String> = None;
@@ -406,14 +430,18 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
     let mut instance_data: Option<InstanceData> = None;
     let mut link_data: Option<LinkData> = None;
     let mut annotations: Vec<Annotation> = Vec::new();
-    let mut background_color: Option<String> = None;
+    let mut background_color: Option<crate::color::Rgba> = None;
     let mut show_name: Option<bool> = None;
     let mut font_size: Option<u32> = None;
     let mut font_weight: Option<String> = None;
+    let mut foreground_color: Option<crate::color::Rgba> = None;
+    let mut drop_shadow = false;
+    let mut font_name: Option<String> = None;
     let mut block_value: Option<String> = None;
     let mut name_location: NameLocation = NameLocation::Bottom;
     let mut current_setting: Option<String> = None;
     let mut block_mirror: Option<bool> = None;
+    let mut orientation: Option<crate::model::BlockOrientation> = None;
     let mut value_kind = ValueKind::Unknown;
     let mut value_rows: Option<u32> = None;
     let mut value_cols: Option<u32> = None;
@@ -443,7 +471,7 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
                         "Position" => position = Some(value),
                         "ZOrder" => zorder = Some(value),
                         "Commented" => {
-                            commented = value.eq_ignore_ascii_case("on");
+                            comment_mode = CommentMode::from_param(&value);
                         }
                         "SFBlockType" => {
                             if value == "MATLAB Function" {
@@ -457,7 +485,7 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
                         "CodegenStartCode" => c_codegen_start = Some(value),
                         "CodegenTerminateCode" => c_codegen_term = Some(value),
                         "BackgroundColor" => {
-                            background_color = crate::color::parse_color(&value);
+                            background_color = crate::color::parse_simulink_color(&value);
                         }
                         "ShowName" => {
                             show_name = Some(!value.eq_ignore_ascii_case("off"));
@@ -468,12 +496,29 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
                                 || value.eq_ignore_ascii_case("true");
                             block_mirror = Some(on);
                         }
+                        // `BlockRotation` supersedes the legacy MDL `Orientation`.
+                        "BlockRotation" => {
+                            if let Some(o) =
+                                crate::model::BlockOrientation::from_rotation_degrees(&value)
+                            {
+                                orientation = Some(o);
+                            }
+                        }
+                        "Orientation" if !properties.contains_key("BlockRotation") => {
+                            orientation =
+                                crate::model::BlockOrientation::from_orientation_str(&value);
+                        }
                         "FontSize" => {
                             font_size = value.parse::<u32>().ok();
                         }
                         "FontWeight" => {
                             font_weight = Some(value);
                         }
+                        "FontName" => font_name = parse_font_name(&value),
+                        "ForegroundColor" => {
+                            foreground_color = crate::color::parse_simulink_color(&value);
+                        }
+                        "DropShadow" => drop_shadow = value.eq_ignore_ascii_case("on"),
                         "NameLocation" => {
                             name_location = match value.trim().to_ascii_lowercase().as_str() {
                                 "top" => NameLocation::Top,
@@ -496,7 +541,12 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
             "PortCounts" => {
                 let ins = child.attribute("in").and_then(|s| s.parse::<u32>().ok());
                 let outs = child.attribute("out").and_then(|s| s.parse::<u32>().ok());
-                port_counts = Some(PortCounts { ins, outs });
+                let special = child
+                    .attributes()
+                    .filter(|a| a.name() != "in" && a.name() != "out")
+                    .filter_map(|a| Some((PortKind::from(a.name()), a.value().parse().ok()?)))
+                    .collect();
+                port_counts = Some(PortCounts { ins, outs, special });
                 child_order.push(BlockChildKind::PortCounts);
             }
             "PortProperties" => {
@@ -639,6 +689,11 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
         None
     };
 
+    let library_overrides = properties
+        .contains_key("SourceBlock")
+        .then(|| LibraryOverrides::new(instance_data.as_ref(), link_data.as_ref()))
+        .filter(|o| !o.is_empty());
+
     let mut blk = Block {
         block_type,
         name,
@@ -646,7 +701,8 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
         tag_name,
         position,
         zorder,
-        commented,
+        commented: comment_mode != CommentMode::Off,
+        comment_through: comment_mode == CommentMode::Through,
         name_location,
         is_matlab_function,
         properties,
@@ -664,6 +720,9 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
         show_name,
         font_size,
         font_weight,
+        foreground_color,
+        drop_shadow,
+        font_name,
         mask_display_text: None,
         value: block_value,
         value_kind,
@@ -671,9 +730,12 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
         value_cols,
         current_setting,
         block_mirror,
+        orientation: orientation.unwrap_or_default(),
         library_source: None,
         library_block_path: None,
+        library_overrides,
         dashboard_binding: None,
+        workspace_values: Default::default(),
         child_order,
     };
 
@@ -702,6 +764,7 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
                 blk.port_counts = Some(crate::model::PortCounts {
                     ins: Some(ins),
                     outs: Some(outs),
+                    special: Vec::new(),
                 });
                 for i in 1..=ins {
                     let mut p = crate::model::Port {
@@ -745,6 +808,7 @@ pub fn parse_block_shallow(node: Node, base_dir: &Utf8Path) -> Result<Block> {
         blk.port_counts = Some(crate::model::PortCounts {
             ins: Some(ins),
             outs: Some(outs),
+            special: Vec::new(),
         });
         for i in 1..=ins {
             let mut p = crate::model::Port {
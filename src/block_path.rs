@@ -0,0 +1,150 @@
+//! Hierarchical block paths.
+//!
+//! A [`BlockPath`] is the list of block names from a root system down to a
+//! block or subsystem. Its text form follows Simulink: names are joined by
+//! `/`, and a `/` inside a name is doubled, so `Ctrl/a//b` is block `a/b`
+//! inside `Ctrl`. A single leading `/` marks an absolute path and is
+//! optional; the root itself is the empty string.
+//!
+//! The text form cannot tell a name ending in `/` from the next name
+//! starting with one (`a///b`); parsing reads it as `a/` followed by `b`.
+
+use anyhow::{Result, bail};
+use std::borrow::Cow;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+
+/// Block names from the root system down, outermost first.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BlockPath(Vec<String>);
+
+impl BlockPath {
+    /// The root system.
+    pub fn root() -> Self {
+        BlockPath(Vec::new())
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn segments(&self) -> &[String] {
+        &self.0
+    }
+
+    pub fn into_segments(self) -> Vec<String> {
+        self.0
+    }
+
+    /// Name of the last block, or `None` for the root.
+    pub fn name(&self) -> Option<&str> {
+        self.0.last().map(String::as_str)
+    }
+
+    /// The path of the enclosing system, or `None` for the root.
+    pub fn parent(&self) -> Option<BlockPath> {
+        let (_, parent) = self.0.split_last()?;
+        Some(BlockPath(parent.to_vec()))
+    }
+
+    /// The path of block `name` inside this system.
+    pub fn child(&self, name: &str) -> BlockPath {
+        let mut path = self.clone();
+        path.push(name);
+        path
+    }
+
+    pub fn push(&mut self, name: &str) {
+        self.0.push(name.to_string());
+    }
+
+    pub fn pop(&mut self) -> Option<String> {
+        self.0.pop()
+    }
+
+    /// Whether `self` is `ancestor` or lies inside it, comparing whole names.
+    pub fn starts_with(&self, ancestor: &BlockPath) -> bool {
+        self.0.starts_with(&ancestor.0)
+    }
+}
+
+/// `name` with every `/` doubled, for use as one segment of a path string.
+pub fn escape_name(name: &str) -> Cow<'_, str> {
+    if name.contains('/') {
+        Cow::Owned(name.replace('/', "//"))
+    } else {
+        Cow::Borrowed(name)
+    }
+}
+
+impl fmt::Display for BlockPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, name) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str("/")?;
+            }
+            f.write_str(&escape_name(name))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for BlockPath {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        // An odd run of leading slashes starts with the absolute marker; an
+        // even run is the escaped start of the first name.
+        let leading = s.len() - s.trim_start_matches('/').len();
+        let rest = if leading % 2 == 1 { &s[1..] } else { s };
+        if rest.is_empty() {
+            return Ok(BlockPath::root());
+        }
+        let mut names = Vec::new();
+        let mut name = String::new();
+        let mut chars = rest.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '/' {
+                name.push(c);
+            } else if chars.next_if_eq(&'/').is_some() {
+                name.push('/');
+            } else if name.is_empty() {
+                bail!("Empty block name in path '{}'", s);
+            } else {
+                names.push(std::mem::take(&mut name));
+            }
+        }
+        if name.is_empty() {
+            bail!("Empty block name in path '{}'", s);
+        }
+        names.push(name);
+        Ok(BlockPath(names))
+    }
+}
+
+impl Deref for BlockPath {
+    type Target = [String];
+
+    fn deref(&self) -> &[String] {
+        &self.0
+    }
+}
+
+impl From<Vec<String>> for BlockPath {
+    fn from(names: Vec<String>) -> Self {
+        BlockPath(names)
+    }
+}
+
+impl From<&[String]> for BlockPath {
+    fn from(names: &[String]) -> Self {
+        BlockPath(names.to_vec())
+    }
+}
+
+impl From<BlockPath> for Vec<String> {
+    fn from(path: BlockPath) -> Self {
+        path.0
+    }
+}
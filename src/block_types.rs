@@ -8,7 +8,8 @@
 #![cfg(feature = "egui")]
 
 use std::collections::HashMap;
-use std::sync::RwLock;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
 
 pub use crate::builtin_libraries::virtual_library::BlockShape;
 use once_cell::sync::OnceCell;
@@ -18,10 +19,28 @@ use once_cell::sync::OnceCell;
 pub struct Rgb(pub u8, pub u8, pub u8);
 
 /// Icon specification for a block type.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, PartialEq, Eq, Hash)]
 pub enum IconSpec {
+    /// Text glyph drawn at the block center.
     Utf8(&'static str),
+    /// Path of an SVG in the embedded `icons/` assets.
     Svg(&'static str),
+    /// SVG document supplied by the user, e.g. loaded with
+    /// [`register_icon_dir`] or embedded with `include_bytes!`.
+    SvgBytes(Arc<[u8]>),
+    /// Texture already uploaded by the application.
+    Image(eframe::egui::TextureHandle),
+}
+
+impl std::fmt::Debug for IconSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Utf8(glyph) => f.debug_tuple("Utf8").field(glyph).finish(),
+            Self::Svg(path) => f.debug_tuple("Svg").field(path).finish(),
+            Self::SvgBytes(bytes) => write!(f, "SvgBytes(<{} bytes>)", bytes.len()),
+            Self::Image(texture) => f.debug_tuple("Image").field(&texture.id()).finish(),
+        }
+    }
 }
 
 /// Configuration for a specific block type.
@@ -123,7 +142,7 @@ fn register_virtual_keys(
             if let Some(existing) = m.get(&k) {
                 if existing.icon.is_some() {
                     let mut merged = cfg.clone();
-                    merged.icon = existing.icon;
+                    merged.icon = existing.icon.clone();
                     m.insert(k, merged);
                     continue;
                 }
@@ -453,6 +472,197 @@ where
     }
 }
 
+/// Register every `.svg` file below `dir` as the icon of a block type.
+///
+/// The key is the file's path relative to `dir` without the extension, so
+/// `MyLib/Valve.svg` applies to blocks whose library path is `MyLib/Valve`
+/// and `Gain.svg` to all `Gain` blocks. Other settings of existing entries
+/// are kept. Returns the number of icons registered.
+pub fn register_icon_dir(dir: impl AsRef<Path>) -> std::io::Result<usize> {
+    let dir = dir.as_ref();
+    let mut count = 0;
+    for entry in walkdir::WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(std::io::Error::other)?;
+        let path = entry.path();
+        if !entry.file_type().is_file()
+            || !path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("svg"))
+        {
+            continue;
+        }
+        let Ok(rel) = path
+            .with_extension("")
+            .strip_prefix(dir)
+            .map(Path::to_path_buf)
+        else {
+            continue;
+        };
+        let key = rel
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let bytes: Arc<[u8]> = std::fs::read(path)?.into();
+        update_block_type_config(&key, |cfg| {
+            cfg.icon = Some(IconSpec::SvgBytes(bytes));
+            cfg.known = true;
+        });
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// One block type entry of a config file for [`load_config`]. Fields that
+/// are absent keep their current value.
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BlockTypeConfigEntry {
+    background: Option<ConfigColor>,
+    border: Option<ConfigColor>,
+    icon: Option<ConfigIcon>,
+    show_input_port_labels: Option<bool>,
+    show_output_port_labels: Option<bool>,
+    shape: Option<BlockShape>,
+    input_port_names: Option<Vec<String>>,
+    output_port_names: Option<Vec<String>>,
+}
+
+/// A color as `"#rrggbb"` or `[r, g, b]`.
+#[derive(Debug, serde::Deserialize)]
+#[serde(untagged)]
+enum ConfigColor {
+    Hex(String),
+    Components([u8; 3]),
+}
+
+impl ConfigColor {
+    fn to_rgb(&self) -> anyhow::Result<Rgb> {
+        match self {
+            Self::Components([r, g, b]) => Ok(Rgb(*r, *g, *b)),
+            Self::Hex(s) => {
+                let hex = s.trim().trim_start_matches('#');
+                let channel = |i: usize| {
+                    hex.get(i..i + 2)
+                        .and_then(|c| u8::from_str_radix(c, 16).ok())
+                };
+                match (hex.len(), channel(0), channel(2), channel(4)) {
+                    (6, Some(r), Some(g), Some(b)) => Ok(Rgb(r, g, b)),
+                    _ => anyhow::bail!("invalid color {s:?}, expected \"#rrggbb\""),
+                }
+            }
+        }
+    }
+}
+
+/// An icon as `{ glyph = "K" }` or `{ svg = "icons/gain.svg" }`; SVG paths
+/// are relative to the config file.
+#[derive(Debug, serde::Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+enum ConfigIcon {
+    Glyph(String),
+    Svg(String),
+}
+
+#[derive(Debug, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct ConfigFile {
+    block_types: HashMap<String, BlockTypeConfigEntry>,
+}
+
+/// Merge block type settings from a TOML or JSON file (chosen by extension)
+/// into the registry. Returns the number of block types configured.
+///
+/// ```toml
+/// [block_types.Gain]
+/// background = "#ffe0a0"
+/// icon = { glyph = "K" }
+/// show_input_port_labels = false
+///
+/// [block_types."acme_lib/Valve"]
+/// icon = { svg = "icons/valve.svg" }
+/// shape = "circle"
+/// ```
+///
+/// The JSON form is the same structure: `{"block_types": {"Gain": {...}}}`.
+/// Glyph strings live for the rest of the program, so load configs once at
+/// startup.
+pub fn load_config(path: impl AsRef<Path>) -> anyhow::Result<usize> {
+    use anyhow::Context;
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase());
+    let file: ConfigFile = match ext.as_deref() {
+        Some("toml") => {
+            toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))?
+        }
+        Some("json") => serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse {}", path.display()))?,
+        _ => anyhow::bail!(
+            "Unsupported config file {}, expected .toml or .json",
+            path.display()
+        ),
+    };
+    let base = path.parent().unwrap_or(Path::new(""));
+
+    // Resolve everything first so a bad entry leaves the registry untouched.
+    let mut resolved = Vec::with_capacity(file.block_types.len());
+    for (key, mut entry) in file.block_types {
+        let ctx = || format!("block type {key:?} in {}", path.display());
+        let background = entry.background.as_ref().map(ConfigColor::to_rgb);
+        let background = background.transpose().with_context(ctx)?;
+        let border = entry.border.as_ref().map(ConfigColor::to_rgb);
+        let border = border.transpose().with_context(ctx)?;
+        let icon = match entry.icon.take() {
+            None => None,
+            Some(ConfigIcon::Glyph(g)) => Some(IconSpec::Utf8(Box::leak(g.into_boxed_str()))),
+            Some(ConfigIcon::Svg(p)) => {
+                let svg_path = base.join(&p);
+                let bytes = std::fs::read(&svg_path)
+                    .with_context(|| format!("Failed to read icon {}", svg_path.display()))
+                    .with_context(ctx)?;
+                Some(IconSpec::SvgBytes(bytes.into()))
+            }
+        };
+        resolved.push((key, entry, background, border, icon));
+    }
+
+    let count = resolved.len();
+    for (key, entry, background, border, icon) in resolved {
+        update_block_type_config(&key, |cfg| {
+            if background.is_some() {
+                cfg.background = background;
+            }
+            if border.is_some() {
+                cfg.border = border;
+            }
+            if icon.is_some() {
+                cfg.icon = icon;
+                cfg.known = true;
+            }
+            if let Some(v) = entry.show_input_port_labels {
+                cfg.show_input_port_labels = v;
+            }
+            if let Some(v) = entry.show_output_port_labels {
+                cfg.show_output_port_labels = v;
+            }
+            if let Some(shape) = entry.shape {
+                cfg.shape = shape;
+            }
+            if let Some(names) = entry.input_port_names {
+                cfg.input_port_names = names;
+            }
+            if let Some(names) = entry.output_port_names {
+                cfg.output_port_names = names;
+            }
+        });
+    }
+    Ok(count)
+}
+
 /// Register icon configurations for all currently-registered user virtual
 /// libraries.
 ///
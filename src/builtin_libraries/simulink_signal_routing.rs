@@ -19,6 +19,22 @@ pub const BLOCKS: &[VirtualBlock] = &[
         shape: BlockShape::FilledBlack,
         ..VirtualBlock::DEFAULT
     },
+    VirtualBlock {
+        name: "Mux",
+        aliases: &[],
+        ins: 2,
+        outs: 1,
+        shape: BlockShape::Trapezoid,
+        ..VirtualBlock::DEFAULT
+    },
+    VirtualBlock {
+        name: "Demux",
+        aliases: &[],
+        ins: 1,
+        outs: 2,
+        shape: BlockShape::Trapezoid,
+        ..VirtualBlock::DEFAULT
+    },
 ];
 
 pub fn get_blocks() -> &'static [VirtualBlock] {
@@ -15,15 +15,21 @@ use crate::model::{Block, Port, PortCounts, System};
 ///
 /// Determines how the background fill and border stroke of a block are drawn
 /// in the egui viewer.  The default is [`BlockShape::Rectangle`].
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum BlockShape {
     /// Standard rectangular block (default).
     #[default]
     Rectangle,
-    /// Right-pointing triangle (e.g. Gain blocks).
+    /// Rectangle with strongly rounded corners.
+    Rounded,
+    /// Triangle pointing in the signal direction (e.g. Gain blocks).
     Triangle,
     /// Circle (e.g. Sum blocks).
     Circle,
+    /// Trapezoid narrowing towards the side with fewer ports (e.g. Mux and
+    /// Demux).
+    Trapezoid,
     /// Solid black rectangle with no interior label (e.g. Bus Creator/Selector).
     FilledBlack,
 }
@@ -223,6 +229,7 @@ pub fn create_stub_block(name: &str, ins: u32, outs: u32) -> Block {
         Some(PortCounts {
             ins: Some(ins),
             outs: Some(outs),
+            special: Vec::new(),
         })
     } else {
         None
@@ -245,13 +252,14 @@ pub fn create_stub_block(name: &str, ins: u32, outs: u32) -> Block {
         position: None,
         zorder: None,
         commented: false,
+        comment_through: false,
         name_location: Default::default(),
         is_matlab_function: false,
         value: None,
         value_kind: Default::default(),
         value_rows: None,
         value_cols: None,
-        properties: indexmap::IndexMap::new(),
+        properties: crate::model::PropertyBag::new(),
         ref_properties: Default::default(),
         port_counts,
         ports,
@@ -266,12 +274,18 @@ pub fn create_stub_block(name: &str, ins: u32, outs: u32) -> Block {
         show_name: None,
         font_size: None,
         font_weight: None,
+        foreground_color: None,
+        drop_shadow: false,
+        font_name: None,
         mask_display_text: None,
         current_setting: None,
         block_mirror: None,
+        orientation: Default::default(),
         library_source: None,
         library_block_path: None,
+        library_overrides: None,
         dashboard_binding: None,
+        workspace_values: Default::default(),
         child_order,
     }
 }
@@ -0,0 +1,154 @@
+//! Bus hierarchy of signals, traced back to the `BusCreator` blocks that
+//! build them.
+//!
+//! Element names are the names of the signals entering a `BusCreator`, or
+//! `signal<N>` for unnamed ones, as Simulink names them. An element that is
+//! itself a bus has children. Buses are followed through blocks that pass
+//! their input through unchanged and through `BusSelector`s that output a
+//! bus; subsystem boundaries are not crossed.
+
+use crate::model::{Block, Branch, EndpointRef, Line, System};
+
+/// One element of a bus.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BusElement {
+    pub name: String,
+    /// Elements of a nested bus; empty for plain signals.
+    pub children: Vec<BusElement>,
+}
+
+/// Blocks whose output is their first input, bus or not.
+const PASS_THROUGH: &[&str] = &[
+    "SignalConversion",
+    "SignalSpecification",
+    "UnitDelay",
+    "Memory",
+    "ZeroOrderHold",
+    "RateTransition",
+];
+
+/// Nested buses deeper than this are cut off, which also ends cycles.
+const MAX_DEPTH: usize = 16;
+
+/// The elements of the bus on the signal leaving `src`, or `None` if it
+/// can't be traced to a `BusCreator`.
+pub fn bus_elements(system: &System, src: &EndpointRef) -> Option<Vec<BusElement>> {
+    elements_at(system, src, 0)
+}
+
+/// The elements of the bus entering input `port` of the block with SID
+/// `sid`, e.g. a `BusSelector`.
+pub fn input_bus_elements(system: &System, sid: &str, port: u32) -> Option<Vec<BusElement>> {
+    let line = line_into(&system.lines, sid, port)?;
+    bus_elements(system, line.src.as_ref()?)
+}
+
+fn elements_at(system: &System, src: &EndpointRef, depth: usize) -> Option<Vec<BusElement>> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    let block = block_by_sid(system, &src.sid)?;
+    match block.block_type.as_str() {
+        "BusCreator" => {
+            let count = block.port_counts.as_ref().and_then(|c| c.ins).unwrap_or(0);
+            let elements = (1..=count)
+                .map(|port| {
+                    let line = line_into(&system.lines, &src.sid, port);
+                    let name = line
+                        .and_then(|l| l.name.clone())
+                        .filter(|n| !n.is_empty())
+                        .unwrap_or_else(|| format!("signal{}", port));
+                    let children = line
+                        .and_then(|l| l.src.as_ref())
+                        .and_then(|s| elements_at(system, s, depth + 1))
+                        .unwrap_or_default();
+                    BusElement { name, children }
+                })
+                .collect();
+            Some(elements)
+        }
+        "BusSelector" if block.properties.get("OutputAsBus").map(String::as_str) == Some("on") => {
+            let input = input_elements(system, block, depth)?;
+            let selected = block.properties.get("OutputSignals")?;
+            Some(
+                selected
+                    .split(',')
+                    .filter_map(|path| find_element(&input, path.trim()).cloned())
+                    .collect(),
+            )
+        }
+        t if PASS_THROUGH.contains(&t) => input_elements(system, block, depth),
+        _ => None,
+    }
+}
+
+fn input_elements(system: &System, block: &Block, depth: usize) -> Option<Vec<BusElement>> {
+    let line = line_into(&system.lines, block.sid.as_deref()?, 1)?;
+    elements_at(system, line.src.as_ref()?, depth + 1)
+}
+
+fn block_by_sid<'a>(system: &'a System, sid: &str) -> Option<&'a Block> {
+    system.blocks.iter().find(|b| b.sid.as_deref() == Some(sid))
+}
+
+/// The line among `lines` whose trunk or one of whose branches ends at input
+/// `port` of block `sid`.
+pub(crate) fn line_into<'a>(lines: &'a [Line], sid: &str, port: u32) -> Option<&'a Line> {
+    fn ends_at(dst: &Option<EndpointRef>, sid: &str, port: u32) -> bool {
+        dst.as_ref()
+            .is_some_and(|d| d.sid == sid && d.port_type == "in" && d.port_index == port)
+    }
+    fn in_branches(branches: &[Branch], sid: &str, port: u32) -> bool {
+        branches
+            .iter()
+            .any(|b| ends_at(&b.dst, sid, port) || in_branches(&b.branches, sid, port))
+    }
+    lines
+        .iter()
+        .find(|l| ends_at(&l.dst, sid, port) || in_branches(&l.branches, sid, port))
+}
+
+/// The element at a dotted `path` such as `bus1.a`.
+pub fn find_element<'a>(elements: &'a [BusElement], path: &str) -> Option<&'a BusElement> {
+    let (name, rest) = match path.split_once('.') {
+        Some((name, rest)) => (name, Some(rest)),
+        None => (path, None),
+    };
+    let element = elements.iter().find(|e| e.name == name)?;
+    match rest {
+        Some(rest) => find_element(&element.children, rest),
+        None => Some(element),
+    }
+}
+
+/// Dotted paths of all elements, parents before their children, as listed
+/// in a `BusSelector`'s `OutputSignals`.
+pub fn element_paths(elements: &[BusElement]) -> Vec<String> {
+    fn walk(elements: &[BusElement], prefix: &str, out: &mut Vec<String>) {
+        for e in elements {
+            let path = format!("{}{}", prefix, e.name);
+            out.push(path.clone());
+            walk(&e.children, &format!("{}.", path), out);
+        }
+    }
+    let mut out = Vec::new();
+    walk(elements, "", &mut out);
+    out
+}
+
+/// The hierarchy in the cell array syntax of a `BusSelector`'s
+/// `InputSignals` parameter, e.g. `{'a',{'bus1',{'x','y'}}}`.
+pub fn input_signals_value(elements: &[BusElement]) -> String {
+    let items: Vec<String> = elements
+        .iter()
+        .map(|e| {
+            let name = format!("'{}'", e.name.replace('\'', "''"));
+            if e.children.is_empty() {
+                name
+            } else {
+                format!("{{{},{}}}", name, input_signals_value(&e.children))
+            }
+        })
+        .collect();
+    format!("{{{}}}", items.join(","))
+}
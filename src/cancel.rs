@@ -0,0 +1,80 @@
+//! Cooperative cancellation of long-running work.
+//!
+//! Parsing a large model, resolving its library links or running an analysis
+//! over it can take long enough that a frontend wants to give up on the
+//! result, e.g. when the user closes the tab or an HTTP client disconnects.
+//! The work checks a [`CancellationToken`] between files, blocks or
+//! comparisons and stops with a [`Cancelled`] error once any clone of the
+//! token is cancelled.
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// A flag shared between the frontend that may cancel and the work that
+/// checks it. Clones share the flag; [`Default`] gives a fresh token.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the work using this token to stop.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// `Err(Cancelled)` once the token is cancelled.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// A guard that cancels the token when dropped, e.g. together with the
+    /// request or tab that wants the result.
+    pub fn drop_guard(&self) -> DropGuard {
+        DropGuard(self.clone())
+    }
+}
+
+/// Tokens are equal when they share the same flag.
+impl PartialEq for CancellationToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancellationToken {}
+
+/// Cancels its token when dropped. See [`CancellationToken::drop_guard`].
+#[derive(Debug)]
+pub struct DropGuard(CancellationToken);
+
+impl Drop for DropGuard {
+    fn drop(&mut self) {
+        self.0.cancel();
+    }
+}
+
+/// Error returned by work stopped through its [`CancellationToken`].
+///
+/// Recover it with `err.downcast_ref::<Cancelled>()` to tell an aborted
+/// parse from a failed one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
@@ -1,46 +1,173 @@
-/// Utility for parsing Simulink background color strings (named or [r,g,b] arrays)
-pub fn parse_color(val: &str) -> Option<String> {
-    let val = val.trim();
-    if val.starts_with('[') && val.ends_with(']') {
-        // Parse [r,g,b] array, e.g. [1.0, 0.411765, 0.380392]
-        let inner = &val[1..val.len() - 1];
-        let parts: Vec<&str> = inner.split(',').map(|s| s.trim()).collect();
-        if parts.len() == 3 {
-            let r = parts[0].parse::<f32>().unwrap_or(0.0);
-            let g = parts[1].parse::<f32>().unwrap_or(0.0);
-            let b = parts[2].parse::<f32>().unwrap_or(0.0);
-            Some(format!("rgb({:.3},{:.3},{:.3})", r, g, b))
+//! Parsing of Simulink color specifications.
+//!
+//! Blocks store `BackgroundColor`/`ForegroundColor` either as one of the
+//! Simulink color names (`"lightBlue"`, `"darkGreen"`, ...), as a normalized
+//! `[r,g,b]` triple, or as a hex string. Every consumer (model, viewer,
+//! exporters) goes through [`parse_simulink_color`] so they agree on the
+//! result.
+
+use serde::{Deserialize, Serialize};
+
+/// An 8-bit RGBA color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Rgba {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Rgba {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
+        Self { r, g, b, a: 255 }
+    }
+
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    /// `[r, g, b, a]`, e.g. for raw pixel buffers.
+    pub const fn to_array(self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    /// `#rrggbb`, or `#rrggbbaa` if the color is not opaque.
+    pub fn to_hex(self) -> String {
+        if self.a == 255 {
+            format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
         } else {
-            None
+            format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, self.a)
+        }
+    }
+
+    /// Normalized `[r,g,b]` triple as written by Simulink.
+    pub fn to_simulink_triple(self) -> String {
+        let n = |v: u8| v as f32 / 255.0;
+        format!("[{:.6}, {:.6}, {:.6}]", n(self.r), n(self.g), n(self.b))
+    }
+}
+
+/// Look up a color name (case-insensitive).
+///
+/// Covers the names offered in Simulink's color menus plus a few common
+/// extras seen in hand-edited models.
+pub fn named_color(name: &str) -> Option<Rgba> {
+    let c = match name.trim().to_ascii_lowercase().as_str() {
+        "white" => Rgba::rgb(255, 255, 255),
+        "black" => Rgba::rgb(0, 0, 0),
+        "red" => Rgba::rgb(255, 0, 0),
+        "green" => Rgba::rgb(0, 255, 0),
+        "blue" => Rgba::rgb(0, 0, 255),
+        "yellow" => Rgba::rgb(255, 255, 0),
+        "orange" => Rgba::rgb(255, 165, 0),
+        "cyan" => Rgba::rgb(0, 255, 255),
+        "magenta" => Rgba::rgb(255, 0, 255),
+        "lightblue" => Rgba::rgb(173, 216, 230),
+        "darkgreen" => Rgba::rgb(0, 100, 0),
+        "gray" | "grey" => Rgba::rgb(128, 128, 128),
+        "lightgray" | "lightgrey" => Rgba::rgb(211, 211, 211),
+        "darkgray" | "darkgrey" => Rgba::rgb(169, 169, 169),
+        "brown" => Rgba::rgb(165, 42, 42),
+        "purple" => Rgba::rgb(128, 0, 128),
+        "pink" => Rgba::rgb(255, 192, 203),
+        "lime" => Rgba::rgb(0, 255, 0),
+        "navy" => Rgba::rgb(0, 0, 128),
+        "teal" => Rgba::rgb(0, 128, 128),
+        "olive" => Rgba::rgb(128, 128, 0),
+        "maroon" => Rgba::rgb(128, 0, 0),
+        "silver" => Rgba::rgb(192, 192, 192),
+        _ => return None,
+    };
+    Some(c)
+}
+
+/// Parse `#rgb`, `#rrggbb` or `#rrggbbaa` (the `#` is optional).
+pub fn parse_hex_color(val: &str) -> Option<Rgba> {
+    let hex = val.trim();
+    let hex = hex.strip_prefix('#').unwrap_or(hex);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match hex.len() {
+        3 => {
+            let d: Vec<u8> = hex
+                .chars()
+                .map(|c| c.to_digit(16).unwrap() as u8 * 17)
+                .collect();
+            Some(Rgba::rgb(d[0], d[1], d[2]))
         }
+        6 => Some(Rgba::rgb(byte(0)?, byte(2)?, byte(4)?)),
+        8 => Some(Rgba::new(byte(0)?, byte(2)?, byte(4)?, byte(6)?)),
+        _ => None,
+    }
+}
+
+/// Parse a bracketed triple (or quadruple with alpha).
+///
+/// Components may be separated by commas, semicolons or whitespace. Values
+/// are normalized (`0.0..=1.0`) unless any component exceeds 1, in which case
+/// the whole triple is read as `0..=255`.
+fn parse_triple(val: &str) -> Option<Rgba> {
+    let inner = val.strip_prefix('[')?.strip_suffix(']')?;
+    let parts: Vec<f32> = inner
+        .split(|c: char| c == ',' || c == ';' || c.is_whitespace())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<f32>().ok())
+        .collect::<Option<_>>()?;
+    if !(3..=4).contains(&parts.len()) {
+        return None;
+    }
+    let scale = if parts.iter().any(|v| *v > 1.0) {
+        1.0
     } else {
-        // Accept extended named colors
-        let named = match val.to_ascii_lowercase().as_str() {
-            "white" => Some("#ffffff".to_string()),
-            "black" => Some("#000000".to_string()),
-            "red" => Some("#ff0000".to_string()),
-            "green" => Some("#00ff00".to_string()),
-            "blue" => Some("#0000ff".to_string()),
-            "yellow" => Some("#ffff00".to_string()),
-            "orange" => Some("#ffa500".to_string()),
-            "cyan" => Some("#00ffff".to_string()),
-            "magenta" => Some("#ff00ff".to_string()),
-            "lightblue" => Some("#add8e6".to_string()),
-            "darkgreen" => Some("#006400".to_string()),
-            "gray" | "grey" => Some("#808080".to_string()),
-            "lightgray" | "lightgrey" => Some("#d3d3d3".to_string()),
-            "darkgray" | "darkgrey" => Some("#a9a9a9".to_string()),
-            "brown" => Some("#a52a2a".to_string()),
-            "purple" => Some("#800080".to_string()),
-            "pink" => Some("#ffc0cb".to_string()),
-            "lime" => Some("#00ff00".to_string()),
-            "navy" => Some("#000080".to_string()),
-            "teal" => Some("#008080".to_string()),
-            "olive" => Some("#808000".to_string()),
-            "maroon" => Some("#800000".to_string()),
-            "silver" => Some("#c0c0c0".to_string()),
-            _ => None,
-        };
-        named.or_else(|| Some(val.to_string()))
+        255.0
+    };
+    let to_u8 = |v: f32| (v * scale).round().clamp(0.0, 255.0) as u8;
+    Some(Rgba::new(
+        to_u8(parts[0]),
+        to_u8(parts[1]),
+        to_u8(parts[2]),
+        parts.get(3).map_or(255, |a| to_u8(*a)),
+    ))
+}
+
+/// Parse a Simulink color specification.
+///
+/// Accepts color names, `[r,g,b]` triples, hex strings and the
+/// `custom([r,g,b])` form used by some exporters. `"automatic"` and a bare
+/// `"custom"` carry no color of their own and yield `None`.
+pub fn parse_simulink_color(val: &str) -> Option<Rgba> {
+    let val = val.trim();
+    if val.is_empty() || val.eq_ignore_ascii_case("automatic") || val.eq_ignore_ascii_case("custom")
+    {
+        return None;
+    }
+    if let Some(rest) = val
+        .get(..7)
+        .filter(|p| p.eq_ignore_ascii_case("custom("))
+        .and(val.get(7..))
+        .and_then(|r| r.strip_suffix(')'))
+    {
+        return parse_simulink_color(rest);
+    }
+    if val.starts_with('[') {
+        return parse_triple(val);
+    }
+    if val.starts_with('#') {
+        return parse_hex_color(val);
+    }
+    named_color(val)
+}
+
+/// Utility for parsing Simulink background color strings (named or [r,g,b] arrays)
+///
+/// Returns a `#rrggbb` string for recognized colors and the input unchanged
+/// otherwise. Prefer [`parse_simulink_color`] in new code.
+pub fn parse_color(val: &str) -> Option<String> {
+    match parse_simulink_color(val) {
+        Some(c) => Some(c.to_hex()),
+        None if val.trim().starts_with('[') => None,
+        None => Some(val.trim().to_string()),
     }
 }
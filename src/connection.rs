@@ -0,0 +1,206 @@
+//! Lines as a flat list of source-to-destination connections.
+//!
+//! A Simulink line is a tree: the trunk leaves the source port and either
+//! ends at a destination or splits into nested [`Branch`]es. Finding "the
+//! branch going to SID 42" means walking that tree. [`Line::connections`]
+//! flattens it into one [`Connection`] per destination, and the mutation
+//! helpers ([`Line::add_connection`], [`Line::remove_connection`],
+//! [`Line::set_connection_dst`]) edit a line by destination while keeping
+//! the tree consistent: every branch leads to a destination, and the `Dst`
+//! and `Points` parameters match the typed fields.
+
+use crate::model::{Branch, EndpointRef, Line, Point};
+use indexmap::IndexMap;
+
+/// One destination of a line.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Connection {
+    pub src: Option<EndpointRef>,
+    pub dst: EndpointRef,
+    /// Branch indices from the trunk to the segment ending at `dst`; empty
+    /// for the trunk itself.
+    pub branch_path: Vec<usize>,
+    /// Routing points from the source to `dst`: the trunk's points followed
+    /// by those of every branch on the way, as stored in the file.
+    pub points: Vec<Point>,
+}
+
+impl Line {
+    /// One connection per destination, depth first in branch order.
+    pub fn connections(&self) -> Vec<Connection> {
+        fn collect(
+            src: &Option<EndpointRef>,
+            dst: &Option<EndpointRef>,
+            branches: &[Branch],
+            path: &mut Vec<usize>,
+            points: &mut Vec<Point>,
+            out: &mut Vec<Connection>,
+        ) {
+            if let Some(dst) = dst {
+                out.push(Connection {
+                    src: src.clone(),
+                    dst: dst.clone(),
+                    branch_path: path.clone(),
+                    points: points.clone(),
+                });
+            }
+            for (i, branch) in branches.iter().enumerate() {
+                let len = points.len();
+                path.push(i);
+                points.extend(branch.points.iter().cloned());
+                collect(src, &branch.dst, &branch.branches, path, points, out);
+                points.truncate(len);
+                path.pop();
+            }
+        }
+        let mut out = Vec::new();
+        collect(
+            &self.src,
+            &self.dst,
+            &self.branches,
+            &mut Vec::new(),
+            &mut self.points.clone(),
+            &mut out,
+        );
+        out
+    }
+
+    /// The connection ending at `dst`.
+    pub fn connection_to(&self, dst: &EndpointRef) -> Option<Connection> {
+        self.connections().into_iter().find(|c| &c.dst == dst)
+    }
+
+    /// Connect the line to `dst` as well, routed through `points` after the
+    /// split. An unconnected trunk gets `dst` itself; a trunk ending at a
+    /// destination is split into two branches first.
+    pub fn add_connection(&mut self, dst: EndpointRef, points: Vec<Point>) {
+        with_trunk(self, |trunk| {
+            if trunk.dst.is_none() && trunk.branches.is_empty() {
+                trunk.points.extend(points);
+                set_points_property(&mut trunk.properties, &trunk.points);
+                set_dst(trunk, Some(dst));
+                return;
+            }
+            if let Some(old) = trunk.dst.take() {
+                trunk.properties.shift_remove("Dst");
+                trunk.branches.push(new_branch(old, Vec::new()));
+            }
+            trunk.branches.push(new_branch(dst, points));
+        });
+    }
+
+    /// Disconnect the line from `dst`, removing branches left without
+    /// destinations. Returns `false` if the line does not reach `dst`. A line left without
+    /// any destination keeps its source; callers usually delete it.
+    pub fn remove_connection(&mut self, dst: &EndpointRef) -> bool {
+        with_trunk(self, |trunk| remove(trunk, dst))
+    }
+
+    /// Move the end of the connection at `from` to `to`, keeping its route.
+    /// Returns `false` if the line does not reach `from`.
+    pub fn set_connection_dst(&mut self, from: &EndpointRef, to: EndpointRef) -> bool {
+        fn retarget(segment: &mut Branch, from: &EndpointRef, to: &EndpointRef) -> bool {
+            if segment.dst.as_ref() == Some(from) {
+                set_dst(segment, Some(to.clone()));
+                return true;
+            }
+            segment
+                .branches
+                .iter_mut()
+                .any(|branch| retarget(branch, from, to))
+        }
+        with_trunk(self, |trunk| retarget(trunk, from, &to))
+    }
+}
+
+/// Run `f` on the trunk of `line` viewed as a branch, so that the trunk and
+/// branches share one implementation.
+fn with_trunk<R>(line: &mut Line, f: impl FnOnce(&mut Branch) -> R) -> R {
+    let mut trunk = Branch {
+        name: None,
+        zorder: None,
+        dst: line.dst.take(),
+        points: std::mem::take(&mut line.points),
+        labels: None,
+        branches: std::mem::take(&mut line.branches),
+        properties: std::mem::take(&mut line.properties),
+    };
+    let result = f(&mut trunk);
+    line.dst = trunk.dst;
+    line.points = trunk.points;
+    line.branches = trunk.branches;
+    line.properties = trunk.properties;
+    result
+}
+
+fn remove(segment: &mut Branch, dst: &EndpointRef) -> bool {
+    let found = if segment.dst.as_ref() == Some(dst) {
+        set_dst(segment, None);
+        true
+    } else {
+        segment
+            .branches
+            .iter_mut()
+            .any(|branch| remove(branch, dst))
+    };
+    if found {
+        segment
+            .branches
+            .retain(|branch| branch.dst.is_some() || !branch.branches.is_empty());
+    }
+    found
+}
+
+fn new_branch(dst: EndpointRef, points: Vec<Point>) -> Branch {
+    let mut branch = Branch {
+        name: None,
+        zorder: None,
+        dst: None,
+        points,
+        labels: None,
+        branches: Vec::new(),
+        properties: IndexMap::new(),
+    };
+    set_points_property(&mut branch.properties, &branch.points);
+    set_dst(&mut branch, Some(dst));
+    branch
+}
+
+fn set_dst(segment: &mut Branch, dst: Option<EndpointRef>) {
+    match &dst {
+        Some(dst) => {
+            segment
+                .properties
+                .insert("Dst".to_string(), dst.to_string());
+        }
+        None => {
+            segment.properties.shift_remove("Dst");
+        }
+    }
+    segment.dst = dst;
+}
+
+/// Write `points` to the `Points` parameter, before `Dst` if it is new, or
+/// remove it if there are none.
+pub(crate) fn set_points_property(properties: &mut IndexMap<String, String>, points: &[Point]) {
+    if points.is_empty() {
+        properties.shift_remove("Points");
+        return;
+    }
+    let value = format!(
+        "[{}]",
+        points
+            .iter()
+            .map(|p| format!("{}, {}", p.x, p.y))
+            .collect::<Vec<_>>()
+            .join("; ")
+    );
+    match properties.get_index_of("Dst") {
+        Some(at) if !properties.contains_key("Points") => {
+            properties.shift_insert(at, "Points".to_string(), value);
+        }
+        _ => {
+            properties.insert("Points".to_string(), value);
+        }
+    }
+}
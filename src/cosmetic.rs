@@ -0,0 +1,186 @@
+//! Which model content only affects how a model looks.
+//!
+//! A [`CosmeticFilter`] decides which parameters and model fields count as
+//! cosmetic. The same filter drives [`diff`](crate::diff::diff_systems_filtered),
+//! [`System::structural_hash_with`], the parameter override report and
+//! `rustylink textconv`, so a team that wants layout changes to count as
+//! changes switches off one group and gets the same answer from all of them.
+//!
+//! Parameters are grouped; each group can be switched off:
+//!
+//! | group         | parameters |
+//! |---------------|------------|
+//! | `layout`      | `Position`, `ZOrder`, `Points`, `Labels`, `BlockMirror`, `BlockRotation`, `Orientation`, `NameLocation`, `ShowName`, `HideAutomaticName` |
+//! | `fonts`       | `FontName`, `FontSize`, `FontWeight`, `FontAngle` |
+//! | `colors`      | `BackgroundColor`, `ForegroundColor`, `DropShadow`, `ScreenColor` |
+//! | `window`      | `Location`, `Open`, `ZoomFactor`, `ReportName`, `PaperOrientation`, `PaperPositionMode`, `PaperType`, `PaperUnits`, `TiledPaperMargins`, `TiledPageScale`, `ShowPageBoundaries` |
+//! | `annotations` | annotation text and placement |
+//!
+//! A filter is read from a JSON file such as
+//!
+//! ```json
+//! { "layout": false, "parameters": ["Description"], "keep": ["ShowName"] }
+//! ```
+//!
+//! where omitted groups stay on, `parameters` adds cosmetic parameters and
+//! `keep` takes parameters out of their group.
+
+use crate::model::System;
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+
+const LAYOUT_PARAMS: &[&str] = &[
+    "Position",
+    "ZOrder",
+    "Points",
+    "Labels",
+    "BlockMirror",
+    "BlockRotation",
+    "Orientation",
+    "NameLocation",
+    "ShowName",
+    "HideAutomaticName",
+];
+/// Model JSON fields derived from layout parameters, plus the element order
+/// of blocks in the files.
+const LAYOUT_FIELDS: &[&str] = &[
+    "position",
+    "zorder",
+    "points",
+    "labels",
+    "block_mirror",
+    "orientation",
+    "name_location",
+    "show_name",
+    "child_order",
+];
+const FONT_PARAMS: &[&str] = &["FontName", "FontSize", "FontWeight", "FontAngle"];
+const FONT_FIELDS: &[&str] = &["font_name", "font_size", "font_weight"];
+const COLOR_PARAMS: &[&str] = &[
+    "BackgroundColor",
+    "ForegroundColor",
+    "DropShadow",
+    "ScreenColor",
+];
+const COLOR_FIELDS: &[&str] = &["background_color", "foreground_color", "drop_shadow"];
+const WINDOW_PARAMS: &[&str] = &[
+    "Location",
+    "Open",
+    "ZoomFactor",
+    "ReportName",
+    "PaperOrientation",
+    "PaperPositionMode",
+    "PaperType",
+    "PaperUnits",
+    "TiledPaperMargins",
+    "TiledPageScale",
+    "ShowPageBoundaries",
+];
+
+/// The parameters and fields treated as cosmetic. The default switches on
+/// every group; [`CosmeticFilter::none`] treats nothing as cosmetic.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CosmeticFilter {
+    /// Positions, line points, z-order, rotation, mirroring and name
+    /// placement.
+    pub layout: bool,
+    pub fonts: bool,
+    /// Colors and drop shadows.
+    pub colors: bool,
+    /// Editor window and printing settings of systems.
+    pub window: bool,
+    pub annotations: bool,
+    /// Further parameters treated as cosmetic.
+    pub parameters: BTreeSet<String>,
+    /// Parameters never treated as cosmetic, even if their group is on.
+    pub keep: BTreeSet<String>,
+}
+
+impl Default for CosmeticFilter {
+    fn default() -> Self {
+        CosmeticFilter {
+            layout: true,
+            fonts: true,
+            colors: true,
+            window: true,
+            annotations: true,
+            parameters: BTreeSet::new(),
+            keep: BTreeSet::new(),
+        }
+    }
+}
+
+impl CosmeticFilter {
+    /// A filter under which every change counts.
+    pub fn none() -> Self {
+        CosmeticFilter {
+            layout: false,
+            fonts: false,
+            colors: false,
+            window: false,
+            annotations: false,
+            parameters: BTreeSet::new(),
+            keep: BTreeSet::new(),
+        }
+    }
+
+    /// Read a filter from a JSON file, as described in the
+    /// [module docs](self).
+    pub fn from_file(path: &Utf8Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Read {}", path))?;
+        serde_json::from_str(&text).with_context(|| format!("Parse {}", path))
+    }
+
+    fn groups(&self) -> impl Iterator<Item = (&'static [&'static str], &'static [&'static str])> {
+        [
+            (self.layout, LAYOUT_PARAMS, LAYOUT_FIELDS),
+            (self.fonts, FONT_PARAMS, FONT_FIELDS),
+            (self.colors, COLOR_PARAMS, COLOR_FIELDS),
+            (self.window, WINDOW_PARAMS, &[][..]),
+        ]
+        .into_iter()
+        .filter(|(on, _, _)| *on)
+        .map(|(_, params, fields)| (params, fields))
+    }
+
+    /// Whether the block, line or system parameter `name` is cosmetic.
+    pub fn is_cosmetic(&self, name: &str) -> bool {
+        if self.keep.contains(name) {
+            return false;
+        }
+        self.parameters.contains(name) || self.groups().any(|(params, _)| params.contains(&name))
+    }
+
+    fn is_cosmetic_field(&self, name: &str) -> bool {
+        name == "annotations" && self.annotations
+            || self.groups().any(|(_, fields)| fields.contains(&name))
+    }
+
+    /// Remove cosmetic fields and parameters from the JSON form of a model
+    /// or any part of it.
+    pub fn strip(&self, value: &mut Value) {
+        match value {
+            Value::Object(map) => {
+                map.retain(|key, _| !self.is_cosmetic_field(key));
+                if let Some(Value::Object(properties)) = map.get_mut("properties") {
+                    properties.retain(|key, _| !self.is_cosmetic(key));
+                }
+                map.values_mut().for_each(|v| self.strip(v));
+            }
+            Value::Array(items) => items.iter_mut().for_each(|v| self.strip(v)),
+            _ => {}
+        }
+    }
+
+    /// `system` in [stable JSON](crate::stable_json) form without its
+    /// cosmetic content.
+    pub fn to_value(&self, system: &System) -> serde_json::Result<Value> {
+        let mut value = crate::stable_json::to_value(system)?;
+        self.strip(&mut value);
+        Ok(value)
+    }
+}
@@ -0,0 +1,679 @@
+//! Structural comparison of two versions of a model.
+//!
+//! [`diff_systems`] matches blocks level by level, by SID where both sides
+//! have one and by name otherwise, so renamed blocks show up as a `Name`
+//! parameter change rather than a removal plus an addition. Models without
+//! SIDs (converted from MDL files) lose that anchor on every rename, so
+//! leftover blocks of the same type are paired by name similarity,
+//! connectivity and position, with a confidence score per match. Subsystems
+//! present on both sides are compared recursively. [`diff_systems_filtered`]
+//! leaves out changes to parameters a [`CosmeticFilter`] treats as cosmetic.
+//! Models with the same [`System::structural_hash_with`] that filter have no
+//! differences and are not compared further. [`to_html`]
+//! renders the result as a standalone report with side-by-side diagrams of
+//! every changed system level.
+
+use crate::analysis::{Connection, connections};
+use crate::block_path::BlockPath;
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::color::Rgba;
+use crate::cosmetic::CosmeticFilter;
+use crate::generator::thumbnail::block_rect;
+use crate::model::{Block, LineId, System};
+use crate::overlay::{block_path, render_svg_highlighted};
+use html_escape::encode_safe;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt::Write as _;
+
+/// Highlight colors of the HTML report and the viewer's diff view.
+pub const ADDED_COLOR: Rgba = Rgba::rgb(140, 220, 140);
+pub const REMOVED_COLOR: Rgba = Rgba::rgb(240, 140, 140);
+pub const MODIFIED_COLOR: Rgba = Rgba::rgb(250, 200, 110);
+
+/// Lowest score at which two blocks are paired by structure.
+pub const MIN_MATCH_CONFIDENCE: f32 = 0.5;
+
+/// How a block or connection differs between the two models.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    Modified,
+}
+
+/// A parameter whose value differs. `None` means the parameter is absent on
+/// that side.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParameterChange {
+    pub name: String,
+    pub old: Option<String>,
+    pub new: Option<String>,
+}
+
+/// A block that was added, removed or modified.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockChange {
+    /// Path in the new model. For removed blocks, the path the block would
+    /// have under its parent's new name.
+    pub path: String,
+    pub block_type: String,
+    pub kind: ChangeKind,
+    /// Path in the old model when it differs from `path` (the block or one
+    /// of its parents was renamed).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    /// Differing parameters; empty for added and removed blocks.
+    pub parameters: Vec<ParameterChange>,
+    /// Score in `[MIN_MATCH_CONFIDENCE, 1]` when the block was paired with
+    /// its old version by structure rather than by SID or name.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+}
+
+/// A connection that exists in only one of the models.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ConnectionChange {
+    /// Path of the system level containing the connection in the new model;
+    /// empty for the root.
+    pub system: String,
+    /// `block:port` of the source.
+    pub src: String,
+    /// `block:port` of the destination.
+    pub dst: String,
+    /// The line carrying the connection, in the model it exists in.
+    pub line: LineId,
+    /// [`ChangeKind::Added`] or [`ChangeKind::Removed`].
+    pub kind: ChangeKind,
+}
+
+/// The differences between two models.
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemDiff {
+    pub blocks: Vec<BlockChange>,
+    pub connections: Vec<ConnectionChange>,
+    /// The compared models, kept for rendering.
+    #[serde(skip)]
+    old: System,
+    #[serde(skip)]
+    new: System,
+    /// Old paths of system levels whose path changed, by new path.
+    #[serde(skip)]
+    renamed_systems: HashMap<String, String>,
+}
+
+impl SystemDiff {
+    /// Whether the models are structurally identical.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty() && self.connections.is_empty()
+    }
+
+    /// Paths of the system levels containing changes, parents first.
+    pub fn changed_systems(&self) -> Vec<String> {
+        let mut systems = BTreeSet::new();
+        for change in &self.blocks {
+            systems.insert(parent_path(&change.path));
+        }
+        for change in &self.connections {
+            systems.insert(change.system.clone());
+        }
+        systems.into_iter().collect()
+    }
+
+    /// The old model.
+    pub fn old_model(&self) -> &System {
+        &self.old
+    }
+
+    /// The new model.
+    pub fn new_model(&self) -> &System {
+        &self.new
+    }
+
+    /// Path in the old model of the system level at `system` in the new one.
+    pub fn old_system_path<'a>(&'a self, system: &'a str) -> &'a str {
+        self.renamed_systems
+            .get(system)
+            .map(String::as_str)
+            .unwrap_or(system)
+    }
+
+    /// Highlight colors of the blocks at level `system` on one side of the
+    /// comparison (`Removed` for the old model, `Added` for the new one),
+    /// keyed by the block paths of that side.
+    pub fn highlights(&self, system: &str, side: ChangeKind) -> HashMap<String, Rgba> {
+        let changes: Vec<&BlockChange> = self
+            .blocks
+            .iter()
+            .filter(|c| parent_path(&c.path) == system)
+            .collect();
+        side_highlights(&changes, side)
+    }
+}
+
+impl BlockChange {
+    /// Path of the system level containing the block in the new model.
+    pub fn system(&self) -> String {
+        parent_path(&self.path)
+    }
+}
+
+/// Compare `old` against `new`.
+pub fn diff_systems(old: &System, new: &System) -> SystemDiff {
+    diff_systems_with_cancel(old, new, &CancellationToken::new())
+        .expect("a fresh token is never cancelled")
+}
+
+/// [`diff_systems`], stopping with [`Cancelled`] once `cancel` is cancelled.
+pub fn diff_systems_with_cancel(
+    old: &System,
+    new: &System,
+    cancel: &CancellationToken,
+) -> Result<SystemDiff, Cancelled> {
+    diff_systems_filtered(old, new, &CosmeticFilter::none(), cancel)
+}
+
+/// [`diff_systems_with_cancel`] without changes to parameters that `filter`
+/// treats as cosmetic.
+pub fn diff_systems_filtered(
+    old: &System,
+    new: &System,
+    filter: &CosmeticFilter,
+    cancel: &CancellationToken,
+) -> Result<SystemDiff, Cancelled> {
+    let mut diff = SystemDiff {
+        blocks: Vec::new(),
+        connections: Vec::new(),
+        old: old.clone(),
+        new: new.clone(),
+        renamed_systems: HashMap::new(),
+    };
+    cancel.check()?;
+    if old.structural_hash_with(filter) != new.structural_hash_with(filter) {
+        diff_level(old, new, &[], &[], &mut diff, filter, cancel)?;
+    }
+    Ok(diff)
+}
+
+/// Key matching a block across the two models.
+fn block_key(block: &Block) -> String {
+    match &block.sid {
+        Some(sid) => format!("#{sid}"),
+        None => block.name.clone(),
+    }
+}
+
+fn diff_level(
+    old: &System,
+    new: &System,
+    old_path: &[String],
+    path: &[String],
+    diff: &mut SystemDiff,
+    filter: &CosmeticFilter,
+    cancel: &CancellationToken,
+) -> Result<(), Cancelled> {
+    cancel.check()?;
+    let old_blocks: HashMap<String, &Block> =
+        old.blocks.iter().map(|b| (block_key(b), b)).collect();
+    let new_keys: BTreeSet<String> = new.blocks.iter().map(block_key).collect();
+    // Old block index -> (new block index, confidence) for structural pairs.
+    let structural = structural_matches(old, new, &old_blocks, &new_keys);
+    let by_new: HashMap<usize, (usize, f32)> = structural
+        .iter()
+        .map(|(&o, &(n, score))| (n, (o, score)))
+        .collect();
+
+    for (i, block) in old.blocks.iter().enumerate() {
+        if !new_keys.contains(&block_key(block)) && !structural.contains_key(&i) {
+            let (old, new) = (
+                block_path(old_path, &block.name),
+                block_path(path, &block.name),
+            );
+            diff.blocks.push(BlockChange {
+                old_path: (old != new).then_some(old),
+                path: new,
+                block_type: block.block_type.clone(),
+                kind: ChangeKind::Removed,
+                parameters: Vec::new(),
+                confidence: None,
+            });
+        }
+    }
+    for (i, block) in new.blocks.iter().enumerate() {
+        let new_path = block_path(path, &block.name);
+        let (old_block, confidence) = match old_blocks.get(&block_key(block)) {
+            Some(old_block) => (*old_block, None),
+            None => match by_new.get(&i) {
+                Some(&(o, score)) => (&old.blocks[o], Some(score)),
+                None => {
+                    diff.blocks.push(BlockChange {
+                        path: new_path,
+                        block_type: block.block_type.clone(),
+                        kind: ChangeKind::Added,
+                        old_path: None,
+                        parameters: Vec::new(),
+                        confidence: None,
+                    });
+                    continue;
+                }
+            },
+        };
+        let old_block_path = block_path(old_path, &old_block.name);
+        let parameters = parameter_changes(old_block, block, filter);
+        if !parameters.is_empty() {
+            diff.blocks.push(BlockChange {
+                old_path: (old_block_path != new_path).then(|| old_block_path.clone()),
+                path: new_path.clone(),
+                block_type: block.block_type.clone(),
+                kind: ChangeKind::Modified,
+                parameters,
+                confidence,
+            });
+        }
+        if let (Some(old_sub), Some(new_sub)) = (&old_block.subsystem, &block.subsystem) {
+            if old_block_path != new_path {
+                diff.renamed_systems.insert(new_path, old_block_path);
+            }
+            let mut old_inner = old_path.to_vec();
+            old_inner.push(old_block.name.clone());
+            let mut inner = path.to_vec();
+            inner.push(block.name.clone());
+            diff_level(old_sub, new_sub, &old_inner, &inner, diff, filter, cancel)?;
+        }
+    }
+
+    let system = BlockPath::from(path).to_string();
+    // Old connections are keyed by the endpoints of the new blocks their
+    // ends were paired with, so structural matches keep their connections.
+    let renamed: HashMap<&str, &str> = structural
+        .iter()
+        .map(|(&o, &(n, _))| (endpoint_id(&old.blocks[o]), endpoint_id(&new.blocks[n])))
+        .collect();
+    let old_conns = connection_labels(old, &renamed);
+    let new_conns = connection_labels(new, &HashMap::new());
+    for (labels, others, kind) in [
+        (&old_conns, &new_conns, ChangeKind::Removed),
+        (&new_conns, &old_conns, ChangeKind::Added),
+    ] {
+        for (key, (src, dst, line)) in labels {
+            if !others.contains_key(key) {
+                diff.connections.push(ConnectionChange {
+                    system: system.clone(),
+                    src: src.clone(),
+                    dst: dst.clone(),
+                    line: line.clone(),
+                    kind,
+                });
+            }
+        }
+    }
+    Ok(())
+}
+
+/// What line endpoints call `block`: its SID, or its name in models
+/// without SIDs.
+fn endpoint_id(block: &Block) -> &str {
+    block.sid.as_deref().unwrap_or(&block.name)
+}
+
+/// Pair the blocks left over after key matching. Only blocks of the same
+/// type are considered, and only where at least one side lacks a SID; two
+/// different SIDs are two different blocks. Candidates are scored by
+/// [`match_score`] and taken greedily, best first, down to
+/// [`MIN_MATCH_CONFIDENCE`].
+fn structural_matches(
+    old: &System,
+    new: &System,
+    old_blocks: &HashMap<String, &Block>,
+    new_keys: &BTreeSet<String>,
+) -> HashMap<usize, (usize, f32)> {
+    let old_left: Vec<usize> = (0..old.blocks.len())
+        .filter(|&i| !new_keys.contains(&block_key(&old.blocks[i])))
+        .collect();
+    let new_left: Vec<usize> = (0..new.blocks.len())
+        .filter(|&i| !old_blocks.contains_key(&block_key(&new.blocks[i])))
+        .collect();
+    if old_left.is_empty() || new_left.is_empty() {
+        return HashMap::new();
+    }
+    let (old_ctx, new_ctx) = (neighbours(old), neighbours(new));
+    let mut candidates = Vec::new();
+    for &o in &old_left {
+        for &n in &new_left {
+            let (a, b) = (&old.blocks[o], &new.blocks[n]);
+            if a.block_type != b.block_type || (a.sid.is_some() && b.sid.is_some()) {
+                continue;
+            }
+            let score = match_score(a, b, &old_ctx[o], &new_ctx[n]);
+            if score >= MIN_MATCH_CONFIDENCE {
+                candidates.push((score, o, n));
+            }
+        }
+    }
+    candidates.sort_by(|x, y| y.0.total_cmp(&x.0).then((x.1, x.2).cmp(&(y.1, y.2))));
+    let mut matches = HashMap::new();
+    let mut taken = BTreeSet::new();
+    for (score, o, n) in candidates {
+        if !matches.contains_key(&o) && taken.insert(n) {
+            matches.insert(o, (n, score));
+        }
+    }
+    matches
+}
+
+/// Connection signature of every block of `system`, by block index: one
+/// entry per connected port naming the port and the type and port of the
+/// block at the other end.
+fn neighbours(system: &System) -> Vec<BTreeSet<String>> {
+    let index: HashMap<&str, usize> = system
+        .blocks
+        .iter()
+        .enumerate()
+        .map(|(i, b)| (endpoint_id(b), i))
+        .collect();
+    let mut sigs = vec![BTreeSet::new(); system.blocks.len()];
+    for Connection { src, dst } in connections(system) {
+        let (Some(&s), Some(&d)) = (index.get(src.sid.as_str()), index.get(dst.sid.as_str()))
+        else {
+            continue;
+        };
+        let (src_type, dst_type) = (&system.blocks[s].block_type, &system.blocks[d].block_type);
+        sigs[s].insert(format!(
+            "{}:{}>{}:{}:{}",
+            src.port_type, src.port_index, dst_type, dst.port_type, dst.port_index
+        ));
+        sigs[d].insert(format!(
+            "{}:{}<{}:{}:{}",
+            dst.port_type, dst.port_index, src_type, src.port_type, src.port_index
+        ));
+    }
+    sigs
+}
+
+/// Similarity of two blocks of the same type in `[0, 1]`: name similarity,
+/// shared connection signatures and distance between their centers,
+/// weighted 0.4, 0.35 and 0.25. Unconnected pairs are scored on name and
+/// position alone.
+fn match_score(
+    old: &Block,
+    new: &Block,
+    old_sig: &BTreeSet<String>,
+    new_sig: &BTreeSet<String>,
+) -> f32 {
+    let name = name_similarity(&old.name, &new.name);
+    let proximity = match (block_rect(old), block_rect(new)) {
+        (Some(a), Some(b)) => {
+            let center = |r: [f32; 4]| ((r[0] + r[2]) / 2.0, (r[1] + r[3]) / 2.0);
+            let ((ax, ay), (bx, by)) = (center(a), center(b));
+            // Half score at 100 units, about one block spacing.
+            1.0 / (1.0 + (ax - bx).hypot(ay - by) / 100.0)
+        }
+        _ => 0.5,
+    };
+    if old_sig.is_empty() && new_sig.is_empty() {
+        return (0.4 * name + 0.25 * proximity) / 0.65;
+    }
+    let shared = old_sig.intersection(new_sig).count() as f32;
+    let connectivity = shared / old_sig.union(new_sig).count() as f32;
+    0.4 * name + 0.35 * connectivity + 0.25 * proximity
+}
+
+/// One minus the edit distance between `a` and `b`, ignoring case, relative
+/// to the longer name.
+fn name_similarity(a: &str, b: &str) -> f32 {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let longest = a.len().max(b.len());
+    if longest == 0 {
+        return 1.0;
+    }
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    1.0 - row[b.len()] as f32 / longest as f32
+}
+
+/// Block type, name and parameter differences of a matched block pair.
+fn parameter_changes(old: &Block, new: &Block, filter: &CosmeticFilter) -> Vec<ParameterChange> {
+    let mut changes = Vec::new();
+    let mut push = |name: &str, old: Option<&String>, new: Option<&String>| {
+        if old != new && !filter.is_cosmetic(name) {
+            changes.push(ParameterChange {
+                name: name.to_string(),
+                old: old.cloned(),
+                new: new.cloned(),
+            });
+        }
+    };
+    push("BlockType", Some(&old.block_type), Some(&new.block_type));
+    push("Name", Some(&old.name), Some(&new.name));
+    for (name, value) in &old.properties {
+        push(name, Some(value), new.properties.get(name));
+    }
+    for (name, value) in &new.properties {
+        if !old.properties.contains_key(name) {
+            push(name, None, Some(value));
+        }
+    }
+    changes
+}
+
+/// Connections of one level keyed by SIDs and ports, with `block:port`
+/// labels for display, in key order for a stable report. Endpoints listed
+/// in `renamed` are keyed by their replacement.
+fn connection_labels(
+    system: &System,
+    renamed: &HashMap<&str, &str>,
+) -> BTreeMap<String, (String, String, LineId)> {
+    let names: HashMap<&str, &str> = system
+        .blocks
+        .iter()
+        .filter_map(|b| Some((b.sid.as_deref()?, b.name.as_str())))
+        .collect();
+    let label = |sid: &str, port_type: &str, index: u32| {
+        let name = names.get(sid).copied().unwrap_or(sid);
+        match port_type {
+            "in" | "out" => format!("{name}:{index}"),
+            other => format!("{name}:{other}"),
+        }
+    };
+    connections(system)
+        .iter()
+        .map(|Connection { src, dst }| {
+            let id = |sid: &str| renamed.get(sid).map_or(sid.to_string(), |n| n.to_string());
+            let key = format!(
+                "{}#{}:{}>{}#{}:{}",
+                id(&src.sid),
+                src.port_type,
+                src.port_index,
+                id(&dst.sid),
+                dst.port_type,
+                dst.port_index
+            );
+            let labels = (
+                label(&src.sid, src.port_type.as_str(), src.port_index),
+                label(&dst.sid, dst.port_type.as_str(), dst.port_index),
+                LineId::from_source(src),
+            );
+            (key, labels)
+        })
+        .collect()
+}
+
+/// Parent system of the block at `path`, in the same text form.
+fn parent_path(path: &str) -> String {
+    parse_path(path)
+        .parent()
+        .map(|p| p.to_string())
+        .unwrap_or_default()
+}
+
+/// Paths in a diff come from [`block_path`] and always parse.
+fn parse_path(path: &str) -> BlockPath {
+    path.parse().unwrap_or_default()
+}
+
+/// Render `diff` as a standalone HTML page: a summary, then for every changed
+/// system level the old and new diagrams side by side with added, removed
+/// and modified blocks highlighted, followed by tables of the changes.
+pub fn to_html(diff: &SystemDiff) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>Model comparison</title>\n<style>\n\
+         body { font-family: sans-serif; margin: 2em; }\n\
+         .side { display: flex; gap: 1em; }\n\
+         .side figure { flex: 1; margin: 0; border: 1px solid #ccc; padding: 0.5em; overflow: auto; }\n\
+         table { border-collapse: collapse; margin: 1em 0; }\n\
+         td, th { border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }\n\
+         .swatch { display: inline-block; width: 1em; height: 1em; vertical-align: middle; }\n\
+         </style>\n</head>\n<body>\n<h1>Model comparison</h1>\n",
+    );
+    let count = |kind| diff.blocks.iter().filter(|c| c.kind == kind).count();
+    let _ = writeln!(
+        html,
+        "<p>{} added, {} removed, {} modified blocks; {} connection changes.</p>",
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed),
+        count(ChangeKind::Modified),
+        diff.connections.len()
+    );
+    if diff.is_empty() {
+        html.push_str("<p>No differences.</p>\n</body>\n</html>\n");
+        return html;
+    }
+    let _ = writeln!(
+        html,
+        "<p><span class=\"swatch\" style=\"background:{}\"></span> added \
+         <span class=\"swatch\" style=\"background:{}\"></span> removed \
+         <span class=\"swatch\" style=\"background:{}\"></span> modified</p>",
+        ADDED_COLOR.to_hex(),
+        REMOVED_COLOR.to_hex(),
+        MODIFIED_COLOR.to_hex()
+    );
+
+    for system in diff.changed_systems() {
+        let title = if system.is_empty() { "(root)" } else { &system };
+        let _ = writeln!(html, "<h2>{}</h2>", encode_safe(title));
+        let changes: Vec<&BlockChange> = diff
+            .blocks
+            .iter()
+            .filter(|c| parent_path(&c.path) == system)
+            .collect();
+
+        html.push_str("<div class=\"side\">\n");
+        for (label, model, level_path, side) in [
+            (
+                "Old",
+                &diff.old,
+                diff.old_system_path(&system),
+                ChangeKind::Removed,
+            ),
+            ("New", &diff.new, system.as_str(), ChangeKind::Added),
+        ] {
+            let _ = writeln!(html, "<figure>\n<figcaption>{label}</figcaption>");
+            let path = parse_path(level_path);
+            match model.system_at(&path) {
+                Some(level) => {
+                    let highlights = side_highlights(&changes, side);
+                    html.push_str(&render_svg_highlighted(level, &path, &highlights));
+                }
+                None => html.push_str("<p>Not present.</p>\n"),
+            }
+            html.push_str("</figure>\n");
+        }
+        html.push_str("</div>\n");
+
+        if !changes.is_empty() {
+            html.push_str(
+                "<table>\n<tr><th>Block</th><th>Type</th><th>Change</th><th>Parameter</th><th>Old</th><th>New</th></tr>\n",
+            );
+            for change in &changes {
+                let rows = change.parameters.len().max(1);
+                let kind = match change.confidence {
+                    Some(c) => format!("{:?} ({:.0}% match)", change.kind, c * 100.0),
+                    None => format!("{:?}", change.kind),
+                };
+                let _ = write!(
+                    html,
+                    "<tr><td rowspan=\"{rows}\">{}</td><td rowspan=\"{rows}\">{}</td><td rowspan=\"{rows}\">{kind}</td>",
+                    encode_safe(&change.path),
+                    encode_safe(&change.block_type),
+                );
+                if change.parameters.is_empty() {
+                    html.push_str("<td></td><td></td><td></td></tr>\n");
+                }
+                for (i, p) in change.parameters.iter().enumerate() {
+                    if i > 0 {
+                        html.push_str("<tr>");
+                    }
+                    let value = |v: &Option<String>| {
+                        encode_safe(v.as_deref().unwrap_or_default()).into_owned()
+                    };
+                    let _ = writeln!(
+                        html,
+                        "<td>{}</td><td>{}</td><td>{}</td></tr>",
+                        encode_safe(&p.name),
+                        value(&p.old),
+                        value(&p.new)
+                    );
+                }
+            }
+            html.push_str("</table>\n");
+        }
+
+        let conns: Vec<&ConnectionChange> = diff
+            .connections
+            .iter()
+            .filter(|c| c.system == system)
+            .collect();
+        if !conns.is_empty() {
+            html.push_str("<table>\n<tr><th>Connection</th><th>Change</th></tr>\n");
+            for c in conns {
+                let _ = writeln!(
+                    html,
+                    "<tr><td>{} &rarr; {}</td><td>{:?}</td></tr>",
+                    encode_safe(&c.src),
+                    encode_safe(&c.dst),
+                    c.kind
+                );
+            }
+            html.push_str("</table>\n");
+        }
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// Highlight colors for one side of the comparison (`Removed` for the old
+/// model, `Added` for the new one), keyed by the block paths of that side.
+/// Modified blocks are highlighted on both sides.
+fn side_highlights(changes: &[&BlockChange], side: ChangeKind) -> HashMap<String, Rgba> {
+    let mut highlights = HashMap::new();
+    for change in changes {
+        match change.kind {
+            ChangeKind::Added if side == ChangeKind::Added => {
+                highlights.insert(change.path.clone(), ADDED_COLOR);
+            }
+            ChangeKind::Removed if side == ChangeKind::Removed => {
+                let path = change.old_path.as_ref().unwrap_or(&change.path);
+                highlights.insert(path.clone(), REMOVED_COLOR);
+            }
+            ChangeKind::Modified => {
+                let path = match &change.old_path {
+                    Some(old_path) if side == ChangeKind::Removed => old_path,
+                    _ => &change.path,
+                };
+                highlights.insert(path.clone(), MODIFIED_COLOR);
+            }
+            _ => {}
+        }
+    }
+    highlights
+}
@@ -0,0 +1,402 @@
+//! Backend-agnostic drawing of diagrams.
+//!
+//! A [`DisplayList`] is a flat list of [`Primitive`]s (rectangles, ellipses,
+//! polygons, polylines and text) in model coordinates. Layout code builds it once and the
+//! backends draw it: [`DisplayList::to_svg`] writes an SVG document,
+//! [`DisplayList::to_png`] rasterizes that document (feature `egui`) and
+//! the viewer paints it with `egui` (see
+//! [`SystemView`](crate::egui_app::SystemView)).
+//!
+//! Stroke widths, corner radii, arrowheads and text sizes are given in
+//! points. The SVG and PNG backends draw one point per model unit; the
+//! egui backend keeps strokes, radii and arrowheads at their size on screen
+//! and scales text with the zoom.
+
+use crate::color::Rgba;
+use crate::generator::system_xml::xml_escape;
+use std::fmt::Write as _;
+
+/// A point in model coordinates.
+pub type Point = (f32, f32);
+
+/// Length of the arrowhead at the end of a signal, in points.
+pub const ARROW_LENGTH: f32 = 7.0;
+
+/// Distance between lines of text, relative to the text size.
+const LINE_SPACING: f32 = 1.2;
+
+/// Alignment of text relative to its anchor point, per axis.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Align {
+    /// Left or top.
+    Min,
+    Center,
+    /// Right or bottom.
+    Max,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub color: Rgba,
+    /// Dash and gap lengths; `None` draws a solid line.
+    pub dash: Option<(f32, f32)>,
+}
+
+impl Stroke {
+    pub fn new(width: f32, color: Rgba) -> Self {
+        Self {
+            width,
+            color,
+            dash: None,
+        }
+    }
+
+    pub fn dashed(mut self, dash: f32, gap: f32) -> Self {
+        self.dash = Some((dash, gap));
+        self
+    }
+}
+
+/// One drawing operation.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Primitive {
+    /// Rectangle `[left, top, right, bottom]`.
+    Rect {
+        rect: [f32; 4],
+        corner_radius: f32,
+        fill: Option<Rgba>,
+        stroke: Option<Stroke>,
+        /// Tooltip, where the backend supports one.
+        title: Option<String>,
+    },
+    /// Rectangle filled with a horizontal gradient through `stops`
+    /// (position in `[0, 1]`, color).
+    GradientRect {
+        rect: [f32; 4],
+        stops: Vec<(f32, Rgba)>,
+        stroke: Option<Stroke>,
+    },
+    /// Ellipse inscribed in `rect`.
+    Ellipse {
+        rect: [f32; 4],
+        fill: Option<Rgba>,
+        stroke: Option<Stroke>,
+    },
+    /// Closed polygon.
+    Polygon {
+        points: Vec<Point>,
+        fill: Option<Rgba>,
+        stroke: Option<Stroke>,
+    },
+    /// Open polyline, with an arrowhead at its last point if `arrow`.
+    Polyline {
+        points: Vec<Point>,
+        stroke: Stroke,
+        arrow: bool,
+    },
+    /// Text placed at `pos` according to `anchor` (horizontal, vertical),
+    /// moved by `offset` points. Lines are separated by `\n`.
+    Text {
+        pos: Point,
+        offset: (f32, f32),
+        text: String,
+        size: f32,
+        color: Rgba,
+        anchor: [Align; 2],
+    },
+}
+
+/// Primitives in drawing order, later ones on top.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DisplayList {
+    /// Fill behind everything; `None` leaves the background transparent.
+    pub background: Option<Rgba>,
+    pub primitives: Vec<Primitive>,
+}
+
+impl DisplayList {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_background(mut self, background: Rgba) -> Self {
+        self.background = Some(background);
+        self
+    }
+
+    pub fn push(&mut self, primitive: Primitive) {
+        self.primitives.push(primitive);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.primitives.is_empty()
+    }
+
+    /// Area `[left, top, right, bottom]` covered by shapes, polylines and
+    /// text anchors; `None` for an empty list.
+    pub fn bounds(&self) -> Option<[f32; 4]> {
+        let mut bounds: Option<[f32; 4]> = None;
+        let mut grow = |(x, y): Point| {
+            let b = bounds.get_or_insert([x, y, x, y]);
+            b[0] = b[0].min(x);
+            b[1] = b[1].min(y);
+            b[2] = b[2].max(x);
+            b[3] = b[3].max(y);
+        };
+        for primitive in &self.primitives {
+            match primitive {
+                Primitive::Rect { rect, .. }
+                | Primitive::GradientRect { rect, .. }
+                | Primitive::Ellipse { rect, .. } => {
+                    grow((rect[0], rect[1]));
+                    grow((rect[2], rect[3]));
+                }
+                Primitive::Polygon { points, .. } | Primitive::Polyline { points, .. } => {
+                    points.iter().copied().for_each(&mut grow)
+                }
+                Primitive::Text { pos, offset, .. } => grow((pos.0 + offset.0, pos.1 + offset.1)),
+            }
+        }
+        bounds
+    }
+
+    /// SVG document showing [`Self::bounds`] plus `margin` on every side, at
+    /// one pixel per model unit.
+    pub fn to_svg(&self, margin: f32) -> String {
+        let b = self.bounds().unwrap_or([0.0, 0.0, 0.0, 0.0]);
+        let (min_x, min_y) = (b[0] - margin, b[1] - margin);
+        let width = b[2] - b[0] + 2.0 * margin;
+        let height = b[3] - b[1] + 2.0 * margin;
+
+        let mut svg = String::new();
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="{min_x} {min_y} {width} {height}" font-family="sans-serif">"#
+        );
+        if let Some(background) = self.background {
+            let _ = writeln!(
+                svg,
+                r#"<rect x="{min_x}" y="{min_y}" width="{width}" height="{height}" fill="{}"/>"#,
+                background.to_hex()
+            );
+        }
+        let mut gradients = 0;
+        for primitive in &self.primitives {
+            match primitive {
+                Primitive::Rect {
+                    rect,
+                    corner_radius,
+                    fill,
+                    stroke,
+                    title,
+                } => {
+                    let [x, y, w, h] = svg_rect(rect);
+                    let radius = if *corner_radius > 0.0 {
+                        format!(r#" rx="{corner_radius}""#)
+                    } else {
+                        String::new()
+                    };
+                    let fill = fill.map_or("none".to_string(), |c| c.to_hex());
+                    let _ = write!(
+                        svg,
+                        r#"<rect x="{x}" y="{y}" width="{w}" height="{h}"{radius} fill="{fill}"{}"#,
+                        svg_stroke(stroke.as_ref())
+                    );
+                    match title {
+                        Some(title) => {
+                            let _ = writeln!(svg, "><title>{}</title></rect>", xml_escape(title));
+                        }
+                        None => svg.push_str("/>\n"),
+                    }
+                }
+                Primitive::GradientRect {
+                    rect,
+                    stops,
+                    stroke,
+                } => {
+                    gradients += 1;
+                    let id = format!("gradient-{gradients}");
+                    let _ = writeln!(svg, r#"<defs><linearGradient id="{id}">"#);
+                    for (t, c) in stops {
+                        let _ = writeln!(
+                            svg,
+                            r#"<stop offset="{t}" stop-color="{}"/>"#,
+                            Rgba { a: 255, ..*c }.to_hex()
+                        );
+                    }
+                    let _ = writeln!(svg, "</linearGradient></defs>");
+                    let [x, y, w, h] = svg_rect(rect);
+                    let _ = writeln!(
+                        svg,
+                        r#"<rect x="{x}" y="{y}" width="{w}" height="{h}" fill="url(#{id})"{}/>"#,
+                        svg_stroke(stroke.as_ref())
+                    );
+                }
+                Primitive::Ellipse { rect, fill, stroke } => {
+                    let [x, y, w, h] = svg_rect(rect);
+                    let fill = fill.map_or("none".to_string(), |c| c.to_hex());
+                    let _ = writeln!(
+                        svg,
+                        r#"<ellipse cx="{}" cy="{}" rx="{}" ry="{}" fill="{fill}"{}/>"#,
+                        x + w / 2.0,
+                        y + h / 2.0,
+                        w / 2.0,
+                        h / 2.0,
+                        svg_stroke(stroke.as_ref())
+                    );
+                }
+                Primitive::Polygon {
+                    points,
+                    fill,
+                    stroke,
+                } => {
+                    let coords: Vec<String> =
+                        points.iter().map(|(x, y)| format!("{x},{y}")).collect();
+                    let fill = fill.map_or("none".to_string(), |c| c.to_hex());
+                    let _ = writeln!(
+                        svg,
+                        r#"<polygon points="{}" fill="{fill}"{}/>"#,
+                        coords.join(" "),
+                        svg_stroke(stroke.as_ref())
+                    );
+                }
+                Primitive::Polyline {
+                    points,
+                    stroke,
+                    arrow,
+                } => {
+                    let coords: Vec<String> =
+                        points.iter().map(|(x, y)| format!("{x},{y}")).collect();
+                    let _ = writeln!(
+                        svg,
+                        r#"<polyline points="{}" fill="none"{}/>"#,
+                        coords.join(" "),
+                        svg_stroke(Some(stroke))
+                    );
+                    if *arrow && let Some(head) = arrow_head(points, ARROW_LENGTH) {
+                        let coords: Vec<String> =
+                            head.iter().map(|(x, y)| format!("{x},{y}")).collect();
+                        let _ = writeln!(
+                            svg,
+                            r#"<polygon points="{}" fill="{}"/>"#,
+                            coords.join(" "),
+                            stroke.color.to_hex()
+                        );
+                    }
+                }
+                Primitive::Text {
+                    pos,
+                    offset,
+                    text,
+                    size,
+                    color,
+                    anchor,
+                } => {
+                    let h = match anchor[0] {
+                        Align::Min => "start",
+                        Align::Center => "middle",
+                        Align::Max => "end",
+                    };
+                    let v = match anchor[1] {
+                        Align::Min => "hanging",
+                        Align::Center => "central",
+                        Align::Max => "auto",
+                    };
+                    // Stack the lines from the anchored one
+                    let lines: Vec<&str> = text.lines().collect();
+                    let step = size * LINE_SPACING;
+                    let block = step * lines.len().saturating_sub(1) as f32;
+                    let first = pos.1 + offset.1
+                        - match anchor[1] {
+                            Align::Min => 0.0,
+                            Align::Center => block / 2.0,
+                            Align::Max => block,
+                        };
+                    for (i, line) in lines.iter().enumerate() {
+                        let _ = writeln!(
+                            svg,
+                            r#"<text x="{}" y="{}" font-size="{size}" fill="{}" text-anchor="{h}" dominant-baseline="{v}">{}</text>"#,
+                            pos.0 + offset.0,
+                            first + step * i as f32,
+                            color.to_hex(),
+                            xml_escape(line)
+                        );
+                    }
+                }
+            }
+        }
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// PNG image of [`Self::to_svg`] at `scale` pixels per model unit. Text
+    /// uses the viewer's embedded font.
+    #[cfg(feature = "egui")]
+    pub fn to_png(&self, margin: f32, scale: f32) -> anyhow::Result<Vec<u8>> {
+        use anyhow::Context as _;
+        use resvg::{tiny_skia, usvg};
+
+        let mut options = usvg::Options::default();
+        if let Some(db) = crate::egui_app::embedded_egui_sans_fontdb() {
+            options.fontdb = db;
+            options.font_family = "sans-serif".to_owned();
+        }
+        let tree = usvg::Tree::from_str(&self.to_svg(margin), &options)?;
+        let size = tree
+            .size()
+            .to_int_size()
+            .scale_by(scale)
+            .context("empty image")?;
+        let mut pixmap =
+            tiny_skia::Pixmap::new(size.width(), size.height()).context("image too large")?;
+        resvg::render(
+            &tree,
+            tiny_skia::Transform::from_scale(scale, scale),
+            &mut pixmap.as_mut(),
+        );
+        Ok(pixmap.encode_png()?)
+    }
+}
+
+/// Triangle of an arrowhead `length` long at the last point of `points`,
+/// pointing along the last segment.
+pub fn arrow_head(points: &[Point], length: f32) -> Option<[Point; 3]> {
+    let [.., tail, tip] = points else {
+        return None;
+    };
+    let (dx, dy) = (tip.0 - tail.0, tip.1 - tail.1);
+    let len = (dx * dx + dy * dy).sqrt();
+    if len <= f32::EPSILON {
+        return None;
+    }
+    let (dx, dy) = (dx / len * length, dy / len * length);
+    let (nx, ny) = (-dy * 0.5, dx * 0.5);
+    let base = (tip.0 - dx, tip.1 - dy);
+    Some([*tip, (base.0 + nx, base.1 + ny), (base.0 - nx, base.1 - ny)])
+}
+
+/// `[x, y, width, height]` of a rectangle given by two corners.
+fn svg_rect(r: &[f32; 4]) -> [f32; 4] {
+    [
+        r[0].min(r[2]),
+        r[1].min(r[3]),
+        (r[2] - r[0]).abs(),
+        (r[3] - r[1]).abs(),
+    ]
+}
+
+fn svg_stroke(stroke: Option<&Stroke>) -> String {
+    let Some(stroke) = stroke else {
+        return String::new();
+    };
+    let mut out = format!(
+        r#" stroke="{}" stroke-width="{}""#,
+        stroke.color.to_hex(),
+        stroke.width
+    );
+    if let Some((dash, gap)) = stroke.dash {
+        let _ = write!(out, r#" stroke-dasharray="{dash} {gap}""#);
+    }
+    out
+}
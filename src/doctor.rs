@@ -0,0 +1,379 @@
+//! First-line triage for models that do not open.
+//!
+//! [`diagnose`] runs a fixed list of quick checks on an `.slx` archive and
+//! reports each as a [`Check`] with a status, the evidence and a hint on what
+//! to do next: whether the ZIP container and its members are intact, which
+//! release saved the model, whether parts are protected, whether the root
+//! system parses, and whether the libraries, referenced models and
+//! S-functions it needs can be found in the search directories. Checks run
+//! on whatever is readable, so a damaged system still gets its archive and
+//! release reported.
+
+use crate::model::System;
+use crate::parser::library::{LibraryResolver, split_source_block_reference};
+use crate::parser::protected::is_protected_model_marker;
+use crate::parser::{ProtectionKind, SimulinkParser, ZipSource};
+use crate::report::{MexPlatform, sfunction_dependencies};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Seek};
+
+/// Members every regular model archive has.
+const REQUIRED_MEMBERS: [&str; 2] = ["simulink/blockdiagram.xml", ROOT_SYSTEM];
+const ROOT_SYSTEM: &str = "simulink/systems/system_root.xml";
+const CORE_PROPERTIES: &str = "metadata/coreProperties.xml";
+
+/// Outcome of one check, ordered from best to worst.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Status {
+    Ok,
+    /// The model opens, but something it needs is missing.
+    Warning,
+    /// The model does not open.
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Check {
+    /// Short check name, e.g. `archive` or `libraries`.
+    pub name: &'static str,
+    pub status: Status,
+    pub summary: String,
+    /// One line per affected member, library, block, ...
+    pub details: Vec<String>,
+    /// What to do about a warning or error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, summary: impl Into<String>) -> Self {
+        Check {
+            name,
+            status,
+            summary: summary.into(),
+            details: Vec::new(),
+            hint: None,
+        }
+    }
+
+    fn hint(mut self, hint: &str) -> Self {
+        self.hint = Some(hint.to_string());
+        self
+    }
+}
+
+/// The checks of one model, in the order they ran.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Diagnosis {
+    pub checks: Vec<Check>,
+}
+
+impl Diagnosis {
+    /// The worst status of any check.
+    pub fn status(&self) -> Status {
+        self.checks
+            .iter()
+            .map(|c| c.status)
+            .max()
+            .unwrap_or(Status::Ok)
+    }
+}
+
+/// Check the `.slx` archive in `data`. Libraries, referenced models and
+/// S-functions are looked up in `search_paths`, in order.
+pub fn diagnose(data: &[u8], search_paths: &[Utf8PathBuf]) -> Diagnosis {
+    let mut checks = Vec::new();
+    checks.push(check_archive(data));
+    // Damaged members still leave the others readable
+    let Ok(mut source) = ZipSource::new(Cursor::new(data)) else {
+        return Diagnosis { checks };
+    };
+    checks.push(check_release(&mut source));
+
+    let protection = source.protection();
+    let protected = protection
+        .as_ref()
+        .is_some_and(|p| p.kind == ProtectionKind::ProtectedModel);
+    checks.push(match &protection {
+        None => Check::new("protection", Status::Ok, "no protected or encrypted parts"),
+        Some(p) if protected => {
+            let mut check = Check::new("protection", Status::Error, p.to_string()).hint(
+                "Protected models only open as Model blocks with the author's license or \
+                 password; ask the author for the unprotected model",
+            );
+            check.details = p.encrypted_parts.clone();
+            check
+        }
+        Some(p) => {
+            let mut check = Check::new("protection", Status::Warning, p.to_string())
+                .hint("Encrypted parts need the password they were saved with");
+            check.details = p.encrypted_parts.clone();
+            check
+        }
+    });
+    if protected {
+        return Diagnosis { checks };
+    }
+
+    let mut parser = SimulinkParser::new("", source);
+    let system = match parser.parse_system_file(ROOT_SYSTEM) {
+        Ok(system) => system,
+        Err(e) => {
+            checks.push(
+                Check::new("system", Status::Error, format!("{:#}", e)).hint(
+                    "The system XML is damaged; restore the model from version control or a \
+                     backup (MATLAB keeps a .slx.autosave next to the model)",
+                ),
+            );
+            return Diagnosis { checks };
+        }
+    };
+    let mut blocks = 0;
+    let mut subsystems = 0;
+    system.walk_blocks(&mut Vec::new(), &mut |_, block| {
+        blocks += 1;
+        subsystems += usize::from(block.subsystem.is_some());
+    });
+    checks.push(Check::new(
+        "system",
+        Status::Ok,
+        format!("{} block(s), {} subsystem(s)", blocks, subsystems),
+    ));
+
+    checks.push(check_libraries(&system, search_paths));
+    checks.push(check_model_references(&system, search_paths));
+    checks.push(check_sfunctions(&system, search_paths));
+    Diagnosis { checks }
+}
+
+/// Open the ZIP container and inflate every member, which verifies its
+/// checksum.
+fn check_archive(data: &[u8]) -> Check {
+    let mut zip = match zip::ZipArchive::new(Cursor::new(data)) {
+        Ok(zip) => zip,
+        Err(e) => {
+            return Check::new(
+                "archive",
+                Status::Error,
+                format!("not a ZIP archive: {}", e),
+            )
+            .hint(
+                "The file is truncated or not an .slx file (an .mdl file is plain text); copy \
+                 it again or restore it from version control",
+            );
+        }
+    };
+    let mut names = Vec::new();
+    let mut damaged = Vec::new();
+    for i in 0..zip.len() {
+        let name = match zip.by_index_raw(i) {
+            Ok(member) => member.name().to_string(),
+            Err(e) => {
+                damaged.push(format!("member {}: {}", i, e));
+                continue;
+            }
+        };
+        match zip.by_index(i) {
+            Ok(mut member) => {
+                if let Err(e) = std::io::copy(&mut member, &mut std::io::sink()) {
+                    damaged.push(format!("{}: {}", name, e));
+                }
+            }
+            // Encrypted members are reported by the protection check.
+            Err(zip::result::ZipError::UnsupportedArchive(msg))
+                if msg == zip::result::ZipError::PASSWORD_REQUIRED => {}
+            Err(e) => damaged.push(format!("{}: {}", name, e)),
+        }
+        names.push(name);
+    }
+    let protected = names.iter().any(|n| is_protected_model_marker(n));
+    let missing: Vec<String> = REQUIRED_MEMBERS
+        .iter()
+        .filter(|m| !protected && !names.iter().any(|n| n.trim_start_matches('/') == **m))
+        .map(|m| format!("{}: missing", m))
+        .collect();
+    if damaged.is_empty() && missing.is_empty() {
+        return Check::new(
+            "archive",
+            Status::Ok,
+            format!("{} member(s), all intact", names.len()),
+        );
+    }
+    let mut check = Check::new(
+        "archive",
+        Status::Error,
+        format!(
+            "{} damaged and {} missing member(s) of {}",
+            damaged.len(),
+            missing.len(),
+            zip.len()
+        ),
+    )
+    .hint(
+        "The archive was damaged in transfer or by an interrupted save; restore it from \
+         version control, or check whether the file is stored with Git LFS",
+    );
+    check.details = damaged.into_iter().chain(missing).collect();
+    check
+}
+
+/// The release, date and author from `metadata/coreProperties.xml`.
+fn check_release<R: Read + Seek>(source: &mut ZipSource<R>) -> Check {
+    let properties = source
+        .read_bytes(Utf8Path::new(CORE_PROPERTIES))
+        .ok()
+        .and_then(|data| String::from_utf8(data).ok());
+    let field = |name: &str| -> Option<String> {
+        let doc = roxmltree::Document::parse(properties.as_deref()?).ok()?;
+        let node = doc.descendants().find(|n| n.tag_name().name() == name)?;
+        Some(node.text()?.trim().to_string())
+    };
+    let Some(release) = field("version") else {
+        return Check::new("release", Status::Warning, "no release recorded").hint(
+            "The model was not saved by Simulink (e.g. generated or repacked); open it in a \
+             recent release and save it again",
+        );
+    };
+    let mut summary = format!("saved with {}", release);
+    if let Some(modified) = field("modified") {
+        summary += &format!(" on {}", modified.split('T').next().unwrap_or(&modified));
+    }
+    if let Some(author) = field("lastModifiedBy") {
+        summary += &format!(" by {}", author);
+    }
+    Check::new("release", Status::Ok, summary)
+}
+
+/// Non-virtual libraries linked from blocks, with the number of links.
+fn check_libraries(system: &System, search_paths: &[Utf8PathBuf]) -> Check {
+    let mut links: BTreeMap<String, usize> = BTreeMap::new();
+    system.walk_blocks(&mut Vec::new(), &mut |_, block| {
+        if let Some((library, _)) = block
+            .properties
+            .get("SourceBlock")
+            .and_then(|s| split_source_block_reference(s))
+        {
+            *links.entry(library).or_default() += 1;
+        }
+    });
+    let lookup = LibraryResolver::new(search_paths).locate(links.keys().map(String::as_str));
+    if lookup.not_found.is_empty() {
+        return Check::new(
+            "libraries",
+            Status::Ok,
+            format!("{} library(ies) found", lookup.found.len()),
+        );
+    }
+    let mut check = Check::new(
+        "libraries",
+        Status::Warning,
+        format!(
+            "{} of {} library(ies) not found",
+            lookup.not_found.len(),
+            lookup.found.len() + lookup.not_found.len()
+        ),
+    )
+    .hint("Add the folders holding these libraries with -L, and to the MATLAB path");
+    check.details = lookup
+        .not_found
+        .iter()
+        .map(|lib| {
+            let count = links.get(lib).copied().unwrap_or_default();
+            format!("{}.slx ({} linked block(s))", lib, count)
+        })
+        .collect();
+    check
+}
+
+/// Models referenced by Model blocks, looked up as `.slx`, `.slxp` or `.mdl`.
+fn check_model_references(system: &System, search_paths: &[Utf8PathBuf]) -> Check {
+    let mut models: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    system.walk_blocks(&mut Vec::new(), &mut |path, block| {
+        if let Some(name) = block.referenced_model() {
+            models
+                .entry(name.to_string())
+                .or_default()
+                .push(crate::overlay::block_path(path, &block.name));
+        }
+    });
+    let found = |model: &str| {
+        search_paths.iter().any(|dir| {
+            ["slx", "slxp", "mdl"]
+                .iter()
+                .any(|ext| dir.join(format!("{}.{}", model, ext)).is_file())
+        })
+    };
+    let missing: Vec<String> = models
+        .iter()
+        .filter(|(model, _)| !found(model))
+        .map(|(model, blocks)| format!("{} (used by {})", model, blocks.join(", ")))
+        .collect();
+    if missing.is_empty() {
+        return Check::new(
+            "model-references",
+            Status::Ok,
+            format!("{} referenced model(s) found", models.len()),
+        );
+    }
+    let mut check = Check::new(
+        "model-references",
+        Status::Warning,
+        format!(
+            "{} of {} referenced model(s) not found",
+            missing.len(),
+            models.len()
+        ),
+    )
+    .hint("Add the folders holding these models with -L, and to the MATLAB path");
+    check.details = missing;
+    check
+}
+
+/// MEX files (or MATLAB files) of the S-functions for the current platform.
+fn check_sfunctions(system: &System, search_paths: &[Utf8PathBuf]) -> Check {
+    let deps = sfunction_dependencies(system);
+    let Some(platform) = MexPlatform::current() else {
+        return Check::new(
+            "s-functions",
+            Status::Ok,
+            format!(
+                "{} S-function(s); MATLAB does not support this platform",
+                deps.len()
+            ),
+        );
+    };
+    let missing: Vec<String> = deps
+        .iter()
+        .filter(|d| d.find(search_paths, platform).is_none())
+        .map(|d| {
+            format!(
+                "{} (used by {})",
+                d.expected_file(platform),
+                d.blocks.join(", ")
+            )
+        })
+        .collect();
+    if missing.is_empty() {
+        return Check::new(
+            "s-functions",
+            Status::Ok,
+            format!("{} S-function(s) found for {}", deps.len(), platform.name()),
+        );
+    }
+    let mut check = Check::new(
+        "s-functions",
+        Status::Warning,
+        format!(
+            "{} of {} S-function(s) missing for {}",
+            missing.len(),
+            deps.len(),
+            platform.name()
+        ),
+    )
+    .hint("Build the MEX files (`rustylink sfunctions` lists them) or add their folder with -L");
+    check.details = missing;
+    check
+}
@@ -2,8 +2,13 @@
 //!
 //! The catalog provides a searchable, categorized list of block types that can
 //! be added to a model. Each entry specifies the block type name, a human-readable
-//! display name, the category it belongs to, default port counts, and an optional
-//! icon hint.
+//! display name, the category it belongs to, default port counts, an optional
+//! icon hint and default parameters.
+//!
+//! Organizations add their own library blocks with [`load_catalog`], which
+//! reads a TOML or JSON file in the format of [`CatalogFile`]; `rustylink
+//! catalog export` writes the built-in catalog in the same format as a
+//! starting point.
 //!
 //! # Usage
 //!
@@ -20,10 +25,15 @@
 
 #![cfg(feature = "egui")]
 
+use indexmap::IndexMap;
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::RwLock;
 
 /// A single entry in the block catalog.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct BlockCatalogEntry {
     /// Internal block type name (e.g., `"Gain"`, `"SubSystem"`).
     pub block_type: String,
@@ -32,11 +42,21 @@ pub struct BlockCatalogEntry {
     /// Category path (e.g., `"Math Operations"`, `"Signal Routing"`).
     pub category: String,
     /// Default number of input ports.
+    #[serde(default)]
     pub default_inputs: u32,
     /// Default number of output ports.
+    #[serde(default)]
     pub default_outputs: u32,
     /// Brief description of the block's function.
+    #[serde(default)]
     pub description: String,
+    /// Icon hint, e.g. a glyph or an SVG file name.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+    /// Parameters set on inserted blocks, e.g. `SourceBlock` of a library
+    /// block.
+    #[serde(default, skip_serializing_if = "IndexMap::is_empty")]
+    pub parameters: IndexMap<String, String>,
 }
 
 impl BlockCatalogEntry {
@@ -63,6 +83,29 @@ pub struct BlockCatalogCategory {
     pub entries: Vec<BlockCatalogEntry>,
 }
 
+/// Catalog file contents, loaded by [`load_catalog`]:
+///
+/// ```toml
+/// [[blocks]]
+/// block_type = "SubSystem"
+/// display_name = "PI Controller"
+/// category = "ACME Control"
+/// default_inputs = 1
+/// default_outputs = 1
+/// description = "PI controller with anti-windup"
+/// icon = "pi.svg"
+///
+/// [blocks.parameters]
+/// SourceBlock = "acme_lib/PI Controller"
+/// ```
+///
+/// The JSON form is the same structure: `{"blocks": [{...}]}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CatalogFile {
+    pub blocks: Vec<BlockCatalogEntry>,
+}
+
 /// Helper to create a catalog entry concisely.
 fn entry(
     block_type: &str,
@@ -79,36 +122,103 @@ fn entry(
         default_inputs: inputs,
         default_outputs: outputs,
         description: description.to_string(),
+        icon: None,
+        parameters: IndexMap::new(),
     }
 }
 
-/// Returns the complete block catalog with 750+ entries.
-///
-/// The catalog is lazily initialized on first access and cached for the
-/// lifetime of the process.
-pub fn get_block_catalog() -> &'static [BlockCatalogEntry] {
-    static CATALOG: Lazy<Vec<BlockCatalogEntry>> = Lazy::new(build_catalog);
-    &CATALOG
+/// The current catalog and its categories. Both live for the rest of the
+/// program and are replaced as a whole when entries are registered.
+struct Catalog {
+    entries: &'static [BlockCatalogEntry],
+    categories: &'static [BlockCatalogCategory],
 }
 
-/// Returns the catalog organized by category.
-pub fn get_block_catalog_by_category() -> &'static [BlockCatalogCategory] {
-    static CATEGORIES: Lazy<Vec<BlockCatalogCategory>> = Lazy::new(|| {
-        let catalog = get_block_catalog();
-        let mut cat_map: indexmap::IndexMap<String, Vec<BlockCatalogEntry>> =
-            indexmap::IndexMap::new();
-        for e in catalog {
+impl Catalog {
+    fn new(entries: Vec<BlockCatalogEntry>) -> Self {
+        let mut cat_map: IndexMap<String, Vec<BlockCatalogEntry>> = IndexMap::new();
+        for e in &entries {
             cat_map
                 .entry(e.category.clone())
                 .or_default()
                 .push(e.clone());
         }
-        cat_map
+        let categories: Vec<BlockCatalogCategory> = cat_map
             .into_iter()
             .map(|(name, entries)| BlockCatalogCategory { name, entries })
-            .collect()
-    });
-    &CATEGORIES
+            .collect();
+        Catalog {
+            entries: Box::leak(entries.into_boxed_slice()),
+            categories: Box::leak(categories.into_boxed_slice()),
+        }
+    }
+}
+
+static CATALOG: Lazy<RwLock<Catalog>> = Lazy::new(|| RwLock::new(Catalog::new(build_catalog())));
+
+/// Returns the complete block catalog: the 750+ built-in entries followed by
+/// registered ones.
+///
+/// The catalog is lazily initialized on first access and cached for the
+/// lifetime of the process.
+pub fn get_block_catalog() -> &'static [BlockCatalogEntry] {
+    CATALOG.read().unwrap_or_else(|e| e.into_inner()).entries
+}
+
+/// Returns the catalog organized by category.
+pub fn get_block_catalog_by_category() -> &'static [BlockCatalogCategory] {
+    CATALOG.read().unwrap_or_else(|e| e.into_inner()).categories
+}
+
+/// The built-in entries only, e.g. to export them for editing.
+pub fn builtin_catalog() -> Vec<BlockCatalogEntry> {
+    build_catalog()
+}
+
+/// Add `entries` to the catalog. An entry with the block type and display
+/// name of an existing one replaces it; others are appended.
+///
+/// Every call keeps the previous catalog alive for the rest of the program,
+/// so register entries once at startup.
+pub fn register_catalog_entries(entries: impl IntoIterator<Item = BlockCatalogEntry>) {
+    let mut catalog = CATALOG.write().unwrap_or_else(|e| e.into_inner());
+    let mut all = catalog.entries.to_vec();
+    for new in entries {
+        match all
+            .iter_mut()
+            .find(|e| e.block_type == new.block_type && e.display_name == new.display_name)
+        {
+            Some(existing) => *existing = new,
+            None => all.push(new),
+        }
+    }
+    *catalog = Catalog::new(all);
+}
+
+/// Register the entries of a TOML or JSON [`CatalogFile`] (chosen by
+/// extension). Returns the number of entries read.
+pub fn load_catalog(path: impl AsRef<Path>) -> anyhow::Result<usize> {
+    use anyhow::Context;
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase());
+    let file: CatalogFile = match ext.as_deref() {
+        Some("toml") => {
+            toml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))?
+        }
+        Some("json") => serde_json::from_str(&text)
+            .with_context(|| format!("Failed to parse {}", path.display()))?,
+        _ => anyhow::bail!(
+            "Unsupported catalog file {}, expected .toml or .json",
+            path.display()
+        ),
+    };
+    let count = file.blocks.len();
+    register_catalog_entries(file.blocks);
+    Ok(count)
 }
 
 fn build_catalog() -> Vec<BlockCatalogEntry> {
@@ -11,6 +11,7 @@
 //! - **Subsystem creation**: Group selected blocks into a new subsystem
 //! - **Commenting**: Toggle commented state on blocks
 //! - **Labels**: Add/edit names on signal lines
+//! - **Annotations**: Add, edit (text, interpreter, font, position) and delete annotations
 //! - **Context menus**: Rich context menus for blocks, lines, and canvas
 //! - **ID management**: Automatic SID assignment and reassignment
 //! - **Undo/Redo**: Full undo/redo stack for all editing operations
@@ -25,12 +26,18 @@ pub mod ui;
 
 pub use block_catalog::{BlockCatalogCategory, BlockCatalogEntry, get_block_catalog};
 pub use operations::{
-    EditorCommand, EditorHistory, add_block, add_line, assign_sids, branch_line, comment_blocks,
-    create_subsystem_from_selection, delete_blocks, delete_lines, mirror_blocks, move_block,
-    move_blocks, rename_line, rotate_blocks,
+    EditorCommand, EditorHistory, add_annotation, add_block, add_line, assign_sids, branch_line,
+    comment_blocks, comment_through_blocks, configure_bus_selector, create_annotation,
+    create_catalog_block, create_subsystem_from_selection, delete_annotations, delete_blocks,
+    delete_lines, disable_link, insert_block_on_line, insert_port, mirror_blocks, move_block,
+    move_blocks, order_ports, port_count_parameter, remove_port, rename_line, restore_link,
+    rotate_blocks, set_port_count, update_annotation,
 };
 pub use selection::{EditorSelection, SelectionRect};
-pub use state::EditorState;
+pub use state::{
+    AnnotationEditorState, BusAssistantState, EditorState, LintState, SignalValues,
+    SimulationPreview, SimulationRunner,
+};
 pub use ui::{
     compute_line_colors,
     editor_update,
@@ -12,9 +12,15 @@
 
 #![cfg(feature = "egui")]
 
+use super::block_catalog::BlockCatalogEntry;
+use crate::bus::BusElement;
+use crate::connection::set_points_property;
 use crate::model::{
-    Block, BlockChildKind, Branch, EndpointRef, Line, NameLocation, Point, Port, PortCounts, System,
+    Annotation, Block, BlockChildKind, BlockOrientation, Branch, CommentMode, EndpointRef, Line,
+    NameLocation, Point, Port, PortCounts, PortKind, PropertyBag, System,
 };
+use crate::transform::PortOrder;
+use anyhow::{Result, anyhow};
 use indexmap::IndexMap;
 use std::collections::BTreeSet;
 
@@ -53,13 +59,15 @@ pub enum EditorCommand {
     AddLine { line_index: usize, line: Box<Line> },
     /// Delete lines at given indices (sorted descending).
     DeleteLines { removed: Vec<(usize, Line)> },
-    /// Toggle the commented state of blocks.
-    CommentBlocks { block_indices: Vec<usize> },
+    /// Set the comment mode of blocks (block index, new mode).
+    SetCommentModes { modes: Vec<(usize, CommentMode)> },
     /// Rotate blocks 90° clockwise (changes port layout by swapping width/height).
     RotateBlocks {
         block_indices: Vec<usize>,
         /// Old positions before rotation, matching `block_indices` order.
         old_positions: Vec<String>,
+        /// Old orientations before rotation, matching `block_indices` order.
+        old_orientations: Vec<BlockOrientation>,
     },
     /// Mirror blocks (flip input/output sides).
     MirrorBlocks { block_indices: Vec<usize> },
@@ -82,8 +90,6 @@ pub enum EditorCommand {
         /// Lines that were added to replace removed connections.
         added_lines: Vec<(usize, Line)>,
     },
-    /// Add a branch to an existing line.
-    BranchLine { line_index: usize, branch: Branch },
     /// Resize a block to a new position rect.
     ResizeBlock {
         block_index: usize,
@@ -127,6 +133,28 @@ pub enum EditorCommand {
         /// The removed point (saved for undo).
         removed_point: Point,
     },
+    /// Insert annotations at the given indices (ascending).
+    AddAnnotations { added: Vec<(usize, Annotation)> },
+    /// Delete annotations at given indices (sorted descending).
+    DeleteAnnotations { removed: Vec<(usize, Annotation)> },
+    /// Replace an annotation (text, position, interpreter or font change).
+    UpdateAnnotation {
+        annotation_index: usize,
+        old: Box<Annotation>,
+        new: Box<Annotation>,
+    },
+    /// Replace a block wholesale (library link disabled or restored).
+    ReplaceBlock {
+        block_index: usize,
+        old: Box<Block>,
+        new: Box<Block>,
+    },
+    /// Replace a line wholesale (endpoints re-indexed after a port change).
+    ReplaceLine {
+        line_index: usize,
+        old: Box<Line>,
+        new: Box<Line>,
+    },
 }
 
 // ────────────────────────────────────────────────────────────────────────────
@@ -149,6 +177,7 @@ pub struct EditorHistory {
     undo_stack: Vec<EditorCommand>,
     redo_stack: Vec<EditorCommand>,
     max_size: usize,
+    revision: u64,
 }
 
 impl EditorHistory {
@@ -158,6 +187,7 @@ impl EditorHistory {
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
             max_size,
+            revision: 0,
         }
     }
 
@@ -165,6 +195,7 @@ impl EditorHistory {
     pub fn push(&mut self, cmd: EditorCommand) {
         self.undo_stack.push(cmd);
         self.redo_stack.clear();
+        self.revision += 1;
         if self.undo_stack.len() > self.max_size {
             self.undo_stack.remove(0);
         }
@@ -175,6 +206,7 @@ impl EditorHistory {
         if let Some(cmd) = self.undo_stack.pop() {
             let inverse = apply_inverse(system, &cmd);
             self.redo_stack.push(inverse);
+            self.revision += 1;
             true
         } else {
             false
@@ -186,6 +218,7 @@ impl EditorHistory {
         if let Some(cmd) = self.redo_stack.pop() {
             let inverse = apply_inverse(system, &cmd);
             self.undo_stack.push(inverse);
+            self.revision += 1;
             true
         } else {
             false
@@ -202,6 +235,12 @@ impl EditorHistory {
         !self.redo_stack.is_empty()
     }
 
+    /// Counts the pushes, undos and redos so far, to tell whether the model
+    /// changed since an earlier call.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
     /// Clear all history.
     pub fn clear(&mut self) {
         self.undo_stack.clear();
@@ -263,13 +302,14 @@ fn apply_inverse(system: &mut System, cmd: &EditorCommand) -> EditorCommand {
                         position: None,
                         zorder: None,
                         commented: false,
+                        comment_through: false,
                         name_location: NameLocation::Bottom,
                         is_matlab_function: false,
                         value: None,
                         value_kind: crate::model::ValueKind::Unknown,
                         value_rows: None,
                         value_cols: None,
-                        properties: IndexMap::new(),
+                        properties: PropertyBag::new(),
                         ref_properties: BTreeSet::new(),
                         port_counts: None,
                         ports: Vec::new(),
@@ -284,12 +324,18 @@ fn apply_inverse(system: &mut System, cmd: &EditorCommand) -> EditorCommand {
                         show_name: None,
                         font_size: None,
                         font_weight: None,
+                        foreground_color: None,
+                        drop_shadow: false,
+                        font_name: None,
                         mask_display_text: None,
                         current_setting: None,
                         block_mirror: None,
+                        orientation: BlockOrientation::Right,
                         library_source: None,
                         library_block_path: None,
+                        library_overrides: None,
                         dashboard_binding: None,
+                        workspace_values: Default::default(),
                         child_order: Vec::new(),
                     }),
                 };
@@ -323,13 +369,14 @@ fn apply_inverse(system: &mut System, cmd: &EditorCommand) -> EditorCommand {
                         position: None,
                         zorder: None,
                         commented: false,
+                        comment_through: false,
                         name_location: NameLocation::Bottom,
                         is_matlab_function: false,
                         value: None,
                         value_kind: crate::model::ValueKind::Unknown,
                         value_rows: None,
                         value_cols: None,
-                        properties: IndexMap::new(),
+                        properties: PropertyBag::new(),
                         ref_properties: BTreeSet::new(),
                         port_counts: None,
                         ports: Vec::new(),
@@ -344,12 +391,18 @@ fn apply_inverse(system: &mut System, cmd: &EditorCommand) -> EditorCommand {
                         show_name: None,
                         font_size: None,
                         font_weight: None,
+                        foreground_color: None,
+                        drop_shadow: false,
+                        font_name: None,
                         mask_display_text: None,
                         current_setting: None,
                         block_mirror: None,
+                        orientation: BlockOrientation::Right,
                         library_source: None,
                         library_block_path: None,
+                        library_overrides: None,
                         dashboard_binding: None,
+                        workspace_values: Default::default(),
                         child_order: Vec::new(),
                     },
                     |(_, b)| b.clone(),
@@ -388,28 +441,14 @@ fn apply_inverse(system: &mut System, cmd: &EditorCommand) -> EditorCommand {
                 line: Box::new(first_line),
             }
         }
-        EditorCommand::CommentBlocks { block_indices } => {
-            for &idx in block_indices {
-                if let Some(block) = system.blocks.get_mut(idx) {
-                    block.commented = !block.commented;
-                    if block.commented {
-                        block
-                            .properties
-                            .insert("Commented".to_string(), "on".to_string());
-                    } else {
-                        block.properties.swap_remove("Commented");
-                    }
-                }
-            }
-            EditorCommand::CommentBlocks {
-                block_indices: block_indices.clone(),
-            }
-        }
+        EditorCommand::SetCommentModes { modes } => set_comment_modes(system, modes),
         EditorCommand::RotateBlocks {
             block_indices,
             old_positions,
+            old_orientations,
         } => {
             let mut current_positions = Vec::new();
+            let mut current_orientations = Vec::new();
             for (i, &idx) in block_indices.iter().enumerate() {
                 if let Some(block) = system.blocks.get_mut(idx) {
                     current_positions.push(
@@ -418,17 +457,22 @@ fn apply_inverse(system: &mut System, cmd: &EditorCommand) -> EditorCommand {
                             .clone()
                             .unwrap_or_else(|| "[0, 0, 30, 30]".to_string()),
                     );
+                    current_orientations.push(block.orientation);
                     if let Some(old_pos) = old_positions.get(i) {
                         block.position = Some(old_pos.clone());
                         if let Some(v) = block.properties.get_mut("Position") {
                             *v = old_pos.clone();
                         }
                     }
+                    if let Some(&old) = old_orientations.get(i) {
+                        set_block_orientation(block, old);
+                    }
                 }
             }
             EditorCommand::RotateBlocks {
                 block_indices: block_indices.clone(),
                 old_positions: current_positions,
+                old_orientations: current_orientations,
             }
         }
         EditorCommand::MirrorBlocks { block_indices } => {
@@ -515,15 +559,6 @@ fn apply_inverse(system: &mut System, cmd: &EditorCommand) -> EditorCommand {
                 added_lines: added_lines.clone(),
             }
         }
-        EditorCommand::BranchLine {
-            line_index,
-            branch: _,
-        } => {
-            if let Some(line) = system.lines.get_mut(*line_index) {
-                line.branches.pop();
-            }
-            cmd.clone()
-        }
         EditorCommand::ResizeBlock {
             block_index,
             old_position,
@@ -674,6 +709,66 @@ fn apply_inverse(system: &mut System, cmd: &EditorCommand) -> EditorCommand {
                 offset: removed_point.clone(),
             }
         }
+        EditorCommand::AddAnnotations { added } => {
+            let mut removed = Vec::new();
+            for (idx, _) in added.iter().rev() {
+                if *idx < system.annotations.len() {
+                    removed.push((*idx, system.annotations.remove(*idx)));
+                }
+            }
+            EditorCommand::DeleteAnnotations { removed }
+        }
+        EditorCommand::DeleteAnnotations { removed } => {
+            let mut added: Vec<_> = removed.clone();
+            added.sort_by_key(|(i, _)| *i);
+            for (idx, ann) in &added {
+                let at = (*idx).min(system.annotations.len());
+                system.annotations.insert(at, ann.clone());
+            }
+            EditorCommand::AddAnnotations { added }
+        }
+        EditorCommand::UpdateAnnotation {
+            annotation_index,
+            old,
+            new,
+        } => {
+            if let Some(ann) = system.annotations.get_mut(*annotation_index) {
+                *ann = (**old).clone();
+            }
+            EditorCommand::UpdateAnnotation {
+                annotation_index: *annotation_index,
+                old: new.clone(),
+                new: old.clone(),
+            }
+        }
+        EditorCommand::ReplaceBlock {
+            block_index,
+            old,
+            new,
+        } => {
+            if let Some(block) = system.blocks.get_mut(*block_index) {
+                *block = (**old).clone();
+            }
+            EditorCommand::ReplaceBlock {
+                block_index: *block_index,
+                old: new.clone(),
+                new: old.clone(),
+            }
+        }
+        EditorCommand::ReplaceLine {
+            line_index,
+            old,
+            new,
+        } => {
+            if let Some(line) = system.lines.get_mut(*line_index) {
+                *line = (**old).clone();
+            }
+            EditorCommand::ReplaceLine {
+                line_index: *line_index,
+                old: new.clone(),
+                new: old.clone(),
+            }
+        }
     }
 }
 
@@ -755,7 +850,7 @@ pub fn create_default_block(
     let width = 30;
     let height = 30;
     let pos = format_position(x, y, x + width, y + height);
-    let mut properties = IndexMap::new();
+    let mut properties = PropertyBag::new();
     properties.insert("Position".to_string(), pos.clone());
     properties.insert("BlockType".to_string(), block_type.to_string());
 
@@ -763,6 +858,7 @@ pub fn create_default_block(
         Some(PortCounts {
             ins: if inputs > 0 { Some(inputs) } else { None },
             outs: if outputs > 0 { Some(outputs) } else { None },
+            special: Vec::new(),
         })
     } else {
         None
@@ -825,6 +921,7 @@ pub fn create_default_block(
         position: Some(pos),
         zorder: None,
         commented: false,
+        comment_through: false,
         name_location: NameLocation::Bottom,
         is_matlab_function: block_type == "MATLAB Function",
         value: None,
@@ -850,16 +947,124 @@ pub fn create_default_block(
         show_name: None,
         font_size: None,
         font_weight: None,
+        foreground_color: None,
+        drop_shadow: false,
+        font_name: None,
         mask_display_text: None,
         current_setting: None,
         block_mirror: None,
+        orientation: BlockOrientation::Right,
         library_source: None,
         library_block_path: None,
+        library_overrides: None,
         dashboard_binding: None,
+        workspace_values: Default::default(),
         child_order,
     }
 }
 
+/// Size and parameters a newly inserted block of one type starts with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockTemplate {
+    /// Width and height of the block.
+    pub size: (i32, i32),
+    /// `<P>` parameters, in the order they are written.
+    pub parameters: &'static [(&'static str, &'static str)],
+}
+
+/// The template for `block_type`, or `None` for types that start with a
+/// 30×30 block and no parameters.
+pub fn block_template(block_type: &str) -> Option<BlockTemplate> {
+    let (size, parameters): ((i32, i32), &[(&str, &str)]) = match block_type {
+        "Gain" => ((30, 32), &[("Gain", "1")]),
+        "Sum" => ((30, 32), &[("IconShape", "rectangular"), ("Inputs", "++")]),
+        "Product" => ((30, 32), &[("Inputs", "2")]),
+        "Constant" => ((30, 30), &[("Value", "1")]),
+        "Inport" | "Outport" => ((30, 14), &[("Port", "1")]),
+        "Scope" => ((30, 32), &[("NumInputPorts", "1")]),
+        "Integrator" => ((30, 32), &[("InitialCondition", "0")]),
+        "UnitDelay" => ((30, 32), &[("InitialCondition", "0"), ("SampleTime", "-1")]),
+        "Delay" => ((30, 32), &[("DelayLength", "1"), ("InitialCondition", "0")]),
+        "Saturate" | "Saturation" => ((30, 32), &[("UpperLimit", "0.5"), ("LowerLimit", "-0.5")]),
+        "Switch" => (
+            (30, 50),
+            &[("Criteria", "u2 >= Threshold"), ("Threshold", "0")],
+        ),
+        "Step" => (
+            (30, 30),
+            &[
+                ("Time", "1"),
+                ("Before", "0"),
+                ("After", "1"),
+                ("SampleTime", "0"),
+            ],
+        ),
+        "RelationalOperator" => ((30, 32), &[("Operator", ">=")]),
+        "Logic" => ((30, 32), &[("Operator", "AND"), ("Inputs", "2")]),
+        "Mux" => ((5, 40), &[("Inputs", "2")]),
+        "Demux" => ((5, 40), &[("Outputs", "2")]),
+        "Goto" | "From" => ((40, 14), &[("GotoTag", "A")]),
+        "Terminator" | "Ground" => ((20, 20), &[]),
+        "SubSystem" => ((100, 60), &[]),
+        _ => return None,
+    };
+    Some(BlockTemplate { size, parameters })
+}
+
+/// Create the block for a catalog entry at `(x, y)`: a
+/// [`create_default_block`] sized and filled in from the type's
+/// [`block_template`], with the entry's own parameters applied on top.
+pub fn create_catalog_block(entry: &BlockCatalogEntry, x: i32, y: i32) -> Block {
+    let mut block = create_default_block(
+        &entry.block_type,
+        &entry.display_name,
+        x,
+        y,
+        entry.default_inputs,
+        entry.default_outputs,
+    );
+    let template = block_template(&entry.block_type);
+    if let Some(BlockTemplate {
+        size: (width, height),
+        ..
+    }) = template
+    {
+        let pos = format_position(x, y, x + width, y + height);
+        block.position = Some(pos.clone());
+        block.properties.insert("Position".to_string(), pos);
+    }
+    for &(name, value) in template.map_or(&[][..], |t| t.parameters) {
+        set_block_parameter(&mut block, name, value);
+    }
+    for (name, value) in &entry.parameters {
+        set_block_parameter(&mut block, name, value);
+    }
+    block
+}
+
+/// Set a block parameter, keeping `child_order` and the convenience fields
+/// in step with `properties`.
+fn set_block_parameter(block: &mut Block, name: &str, value: &str) {
+    if block
+        .properties
+        .insert(name.to_string(), value.to_string())
+        .is_none()
+        && !block.child_order.is_empty()
+    {
+        let kind = BlockChildKind::P(name.to_string());
+        // Parameters go before `<PortProperties>`, like in saved models.
+        let at = block
+            .child_order
+            .iter()
+            .position(|k| *k == BlockChildKind::PortProperties)
+            .unwrap_or(block.child_order.len());
+        block.child_order.insert(at, kind);
+    }
+    if name == "Value" {
+        block.value = Some(value.to_string());
+    }
+}
+
 // ────────────────────────────────────────────────────────────────────────────
 // Public operation functions
 // ────────────────────────────────────────────────────────────────────────────
@@ -1040,12 +1245,12 @@ pub fn add_line(
         zorder: None,
         src: Some(EndpointRef {
             sid: src_sid.to_string(),
-            port_type: "out".to_string(),
+            port_type: PortKind::Out,
             port_index: src_port,
         }),
         dst: Some(EndpointRef {
             sid: dst_sid.to_string(),
-            port_type: "in".to_string(),
+            port_type: PortKind::In,
             port_index: dst_port,
         }),
         points,
@@ -1086,28 +1291,55 @@ pub fn delete_lines(system: &mut System, indices: &[usize]) -> EditorCommand {
     EditorCommand::DeleteLines { removed }
 }
 
-/// Toggle the commented state of the given blocks.
+/// Toggle the given blocks between commented out and uncommented.
+///
+/// Commented-through blocks are uncommented as well.
 pub fn comment_blocks(system: &mut System, indices: &[usize]) -> EditorCommand {
-    for &idx in indices {
+    toggle_comment_mode(system, indices, CommentMode::Out)
+}
+
+/// Toggle the given blocks between commented through and uncommented.
+///
+/// Commented-out blocks are switched to commented through.
+pub fn comment_through_blocks(system: &mut System, indices: &[usize]) -> EditorCommand {
+    toggle_comment_mode(system, indices, CommentMode::Through)
+}
+
+/// Toggle each block between `mode` and [`CommentMode::Off`]; blocks in a
+/// different commented mode are first treated as uncommented.
+fn toggle_comment_mode(system: &mut System, indices: &[usize], mode: CommentMode) -> EditorCommand {
+    let modes: Vec<(usize, CommentMode)> = indices
+        .iter()
+        .filter_map(|&idx| {
+            let current = system.blocks.get(idx)?.comment_mode();
+            let next = match (current, mode) {
+                (CommentMode::Off, _) => mode,
+                (CommentMode::Out, CommentMode::Through) => CommentMode::Through,
+                _ => CommentMode::Off,
+            };
+            Some((idx, next))
+        })
+        .collect();
+    set_comment_modes(system, &modes)
+}
+
+/// Apply `modes` and return the command restoring the previous ones.
+fn set_comment_modes(system: &mut System, modes: &[(usize, CommentMode)]) -> EditorCommand {
+    let mut previous = Vec::new();
+    for &(idx, mode) in modes {
         if let Some(block) = system.blocks.get_mut(idx) {
-            block.commented = !block.commented;
-            if block.commented {
-                block
-                    .properties
-                    .insert("Commented".to_string(), "on".to_string());
-            } else {
-                block.properties.swap_remove("Commented");
-            }
+            previous.push((idx, block.comment_mode()));
+            block.set_comment_mode(mode);
         }
     }
-    EditorCommand::CommentBlocks {
-        block_indices: indices.to_vec(),
-    }
+    EditorCommand::SetCommentModes { modes: previous }
 }
 
-/// Rotate blocks 90° clockwise by swapping width and height around the center.
+/// Rotate blocks 90° clockwise by swapping width and height around the center
+/// and advancing their `BlockRotation`.
 pub fn rotate_blocks(system: &mut System, indices: &[usize]) -> EditorCommand {
     let mut old_positions = Vec::new();
+    let mut old_orientations = Vec::new();
     for &idx in indices {
         if let Some(block) = system.blocks.get_mut(idx) {
             let pos = block
@@ -1115,6 +1347,8 @@ pub fn rotate_blocks(system: &mut System, indices: &[usize]) -> EditorCommand {
                 .clone()
                 .unwrap_or_else(|| "[0, 0, 30, 30]".to_string());
             old_positions.push(pos.clone());
+            old_orientations.push(block.orientation);
+            set_block_orientation(block, block.orientation.rotated_clockwise());
 
             if let Some((l, t, r, b)) = parse_position(&pos) {
                 let cx = (l + r) / 2;
@@ -1135,6 +1369,36 @@ pub fn rotate_blocks(system: &mut System, indices: &[usize]) -> EditorCommand {
     EditorCommand::RotateBlocks {
         block_indices: indices.to_vec(),
         old_positions,
+        old_orientations,
+    }
+}
+
+/// Set a block's orientation and the `BlockRotation` parameter backing it.
+///
+/// The default orientation removes the parameter, as Simulink omits it. A
+/// legacy MDL `Orientation` parameter is kept in sync when present.
+fn set_block_orientation(block: &mut Block, orientation: BlockOrientation) {
+    block.orientation = orientation;
+    if orientation == BlockOrientation::Right {
+        block.properties.swap_remove("BlockRotation");
+    } else {
+        block.properties.insert(
+            "BlockRotation".to_string(),
+            orientation.rotation_degrees().to_string(),
+        );
+        // The writer only emits parameters listed in `child_order`.
+        let kind = BlockChildKind::P("BlockRotation".to_string());
+        if !block.child_order.is_empty() && !block.child_order.contains(&kind) {
+            let at = block
+                .child_order
+                .iter()
+                .position(|k| *k == BlockChildKind::P("Position".to_string()))
+                .map_or(block.child_order.len(), |i| i + 1);
+            block.child_order.insert(at, kind);
+        }
+    }
+    if let Some(v) = block.properties.get_mut("Orientation") {
+        *v = orientation.as_orientation_str().to_string();
     }
 }
 
@@ -1177,7 +1441,8 @@ pub fn rename_line(
     }
 }
 
-/// Add a branch to an existing line, connecting to a new destination.
+/// Add a branch to an existing line, connecting to a new destination. A
+/// line that already ends at a destination is split into two branches.
 ///
 /// # Arguments
 ///
@@ -1193,27 +1458,21 @@ pub fn branch_line(
     dst_port: u32,
     points: Vec<Point>,
 ) -> EditorCommand {
-    let branch = Branch {
-        name: None,
-        zorder: None,
-        dst: Some(EndpointRef {
+    let old = system.lines[line_index].clone();
+    let line = &mut system.lines[line_index];
+    line.add_connection(
+        EndpointRef {
             sid: dst_sid.to_string(),
-            port_type: "in".to_string(),
+            port_type: PortKind::In,
             port_index: dst_port,
-        }),
-        points,
-        labels: None,
-        branches: Vec::new(),
-        properties: {
-            let mut p = IndexMap::new();
-            p.insert("Dst".to_string(), format!("{}#in:{}", dst_sid, dst_port));
-            p
         },
-    };
-
-    system.lines[line_index].branches.push(branch.clone());
-
-    EditorCommand::BranchLine { line_index, branch }
+        points,
+    );
+    EditorCommand::ReplaceLine {
+        line_index,
+        old: Box::new(old),
+        new: Box::new(line.clone()),
+    }
 }
 
 /// Create a subsystem from a set of selected blocks and their interconnecting lines.
@@ -1483,6 +1742,763 @@ pub fn assign_sids(system: &mut System) -> EditorCommand {
     EditorCommand::ReassignSids { old_sids }
 }
 
+// ────────────────────────────────────────────────────────────────────────────
+// Annotations
+// ────────────────────────────────────────────────────────────────────────────
+
+/// Create a plain-text annotation occupying `[l, t, r, b]`.
+///
+/// The annotation has no SID yet; [`add_annotation`] assigns one.
+pub fn create_annotation(text: &str, l: i32, t: i32, r: i32, b: i32) -> Annotation {
+    let mut ann = Annotation::default();
+    set_annotation_text(&mut ann, text);
+    set_annotation_position(&mut ann, l, t, r, b);
+    ann
+}
+
+/// Set the annotation text (`Name` parameter). For the `rich` interpreter
+/// this is HTML.
+pub fn set_annotation_text(ann: &mut Annotation, text: &str) {
+    ann.text = Some(text.to_string());
+    ann.properties.insert("Name".to_string(), text.to_string());
+}
+
+/// Set the annotation rectangle.
+pub fn set_annotation_position(ann: &mut Annotation, l: i32, t: i32, r: i32, b: i32) {
+    let pos = format_position(l, t, r, b);
+    ann.position = Some(pos.clone());
+    ann.properties.insert("Position".to_string(), pos);
+}
+
+/// Set the text interpreter (`off`, `tex` or `rich`); `None` removes it,
+/// which Simulink reads as `off`.
+pub fn set_annotation_interpreter(ann: &mut Annotation, interpreter: Option<&str>) {
+    ann.interpreter = interpreter.map(str::to_string);
+    match interpreter {
+        Some(i) => {
+            ann.properties
+                .insert("Interpreter".to_string(), i.to_string());
+        }
+        None => {
+            ann.properties.swap_remove("Interpreter");
+        }
+    }
+}
+
+/// Set the annotation font. `None` leaves Simulink's default (`auto` name,
+/// default size, normal weight) by removing the parameter.
+pub fn set_annotation_font(
+    ann: &mut Annotation,
+    name: Option<&str>,
+    size: Option<u32>,
+    weight: Option<&str>,
+) {
+    ann.font_name = name.map(str::to_string);
+    ann.font_size = size;
+    ann.font_weight = weight.map(str::to_string);
+    let params = [
+        ("FontName", name.map(str::to_string)),
+        ("FontSize", size.map(|s| s.to_string())),
+        ("FontWeight", weight.map(str::to_string)),
+    ];
+    for (key, value) in params {
+        match value {
+            Some(v) => {
+                ann.properties.insert(key.to_string(), v);
+            }
+            None => {
+                ann.properties.swap_remove(key);
+            }
+        }
+    }
+}
+
+/// Append an annotation, assigning it the next free SID if it has none.
+pub fn add_annotation(system: &mut System, mut annotation: Annotation) -> EditorCommand {
+    if annotation.sid.is_none() {
+        annotation.sid = Some(next_free_sid(system).to_string());
+    }
+    let idx = system.annotations.len();
+    system.annotations.push(annotation.clone());
+    EditorCommand::AddAnnotations {
+        added: vec![(idx, annotation)],
+    }
+}
+
+/// Delete annotations at the given indices, returning the command for undo.
+pub fn delete_annotations(system: &mut System, indices: &[usize]) -> EditorCommand {
+    let mut sorted: Vec<usize> = indices.to_vec();
+    sorted.sort_unstable();
+    sorted.dedup();
+    sorted.reverse();
+
+    let mut removed = Vec::new();
+    for idx in sorted {
+        if idx < system.annotations.len() {
+            removed.push((idx, system.annotations.remove(idx)));
+        }
+    }
+    EditorCommand::DeleteAnnotations { removed }
+}
+
+/// Replace the annotation at `index` with `annotation`, e.g. a copy edited
+/// with the `set_annotation_*` helpers. Returns `None` if the index is out
+/// of range.
+pub fn update_annotation(
+    system: &mut System,
+    index: usize,
+    annotation: Annotation,
+) -> Option<EditorCommand> {
+    let slot = system.annotations.get_mut(index)?;
+    let old = std::mem::replace(slot, annotation.clone());
+    Some(EditorCommand::UpdateAnnotation {
+        annotation_index: index,
+        old: Box::new(old),
+        new: Box::new(annotation),
+    })
+}
+
+/// Disable the library link of the block at `index`, keeping its resolved
+/// library content as a local copy. See [`crate::transform::disable_link`].
+pub fn disable_link(system: &mut System, index: usize) -> Result<EditorCommand> {
+    replace_block(system, index, crate::transform::disable_link)
+}
+
+/// Relink the disabled library link at `index`, discarding its local
+/// content. See [`crate::transform::restore_link`].
+pub fn restore_link(system: &mut System, index: usize) -> Result<EditorCommand> {
+    replace_block(system, index, crate::transform::restore_link)
+}
+
+/// Renumber or move the Inport and Outport blocks inside the subsystem at
+/// `index` so that their numbers and vertical order agree, re-indexing the
+/// lines on its ports. See [`crate::transform::order_ports`].
+pub fn order_ports(system: &mut System, index: usize, by: PortOrder) -> Result<EditorCommand> {
+    let old_lines = system.lines.clone();
+    let old = system
+        .blocks
+        .get(index)
+        .cloned()
+        .ok_or_else(|| anyhow!("No block at index {}", index))?;
+    crate::transform::order_subsystem_ports(system, index, by)?;
+    let mut cmds = vec![EditorCommand::ReplaceBlock {
+        block_index: index,
+        old: Box::new(old),
+        new: Box::new(system.blocks[index].clone()),
+    }];
+    let ends = |line: &Line| {
+        let dsts: Vec<EndpointRef> = line.connections().into_iter().map(|c| c.dst).collect();
+        (line.src.clone(), dsts)
+    };
+    for (line_index, (old, new)) in old_lines.into_iter().zip(&system.lines).enumerate() {
+        if ends(&old) != ends(new) {
+            cmds.push(EditorCommand::ReplaceLine {
+                line_index,
+                old: Box::new(old),
+                new: Box::new(new.clone()),
+            });
+        }
+    }
+    Ok(EditorCommand::Batch(cmds))
+}
+
+/// Apply `edit` to a copy of the block at `index` and swap it in on success.
+fn replace_block(
+    system: &mut System,
+    index: usize,
+    edit: impl FnOnce(&mut Block) -> Result<()>,
+) -> Result<EditorCommand> {
+    let slot = system
+        .blocks
+        .get_mut(index)
+        .ok_or_else(|| anyhow!("No block at index {}", index))?;
+    let mut new = slot.clone();
+    edit(&mut new)?;
+    let old = std::mem::replace(slot, new.clone());
+    Ok(EditorCommand::ReplaceBlock {
+        block_index: index,
+        old: Box::new(old),
+        new: Box::new(new),
+    })
+}
+
+/// Insert a block for `entry` into the line at `line_index`, splitting it
+/// into a line from the old source to the block's first input and a new line
+/// from its first output to the old destination.
+///
+/// The block is centered on the middle of the line's path; both segments are
+/// re-routed with [`auto_route`]. The first segment keeps the line's name and
+/// parameters. Lines with branches are not supported.
+pub fn insert_block_on_line(
+    system: &mut System,
+    line_index: usize,
+    entry: &BlockCatalogEntry,
+) -> Result<EditorCommand> {
+    if entry.default_inputs == 0 || entry.default_outputs == 0 {
+        return Err(anyhow!(
+            "{} has no input or no output to insert into a line",
+            entry.display_name
+        ));
+    }
+    let line = system
+        .lines
+        .get(line_index)
+        .ok_or_else(|| anyhow!("No line at index {}", line_index))?;
+    if !line.branches.is_empty() {
+        return Err(anyhow!("Cannot insert a block into a branched line"));
+    }
+    let (Some(src), Some(dst)) = (line.src.clone(), line.dst.clone()) else {
+        return Err(anyhow!("Line {} is not connected at both ends", line_index));
+    };
+    let src_pos = endpoint_model_pos(system, &src)
+        .ok_or_else(|| anyhow!("No block with SID {} and a position", src.sid))?;
+    let dst_pos = endpoint_model_pos(system, &dst)
+        .ok_or_else(|| anyhow!("No block with SID {} and a position", dst.sid))?;
+
+    let mut path = vec![src_pos];
+    for p in &line.points {
+        let (x, y) = path[path.len() - 1];
+        path.push((x + p.x as f32, y + p.y as f32));
+    }
+    path.push(dst_pos);
+    let (cx, cy) = path_midpoint(&path);
+
+    let mut block = create_catalog_block(entry, 0, 0);
+    let (width, height) = block
+        .position
+        .as_deref()
+        .and_then(parse_position)
+        .map_or((30, 30), |(l, t, r, b)| (r - l, b - t));
+    let (l, t) = (cx as i32 - width / 2, cy as i32 - height / 2);
+    let pos = format_position(l, t, l + width, t + height);
+    block.position = Some(pos.clone());
+    block.properties.insert("Position".to_string(), pos);
+    let sid = next_free_sid(system).to_string();
+    block.sid = Some(sid.clone());
+    block.name = unique_block_name(system, &block.name);
+
+    let (l, t, r, b) = (l as f32, t as f32, (l + width) as f32, (t + height) as f32);
+    let (in_x, in_y) = port_model_pos(l, t, r, b, "in", 1, entry.default_inputs, &block);
+    let (out_x, out_y) = port_model_pos(l, t, r, b, "out", 1, entry.default_outputs, &block);
+
+    let mut first = line.clone();
+    first.dst = Some(EndpointRef {
+        sid: sid.clone(),
+        port_type: PortKind::In,
+        port_index: 1,
+    });
+    first
+        .properties
+        .insert("Dst".to_string(), format!("{}#in:1", sid));
+    first.points = auto_route(
+        src_pos.0,
+        src_pos.1,
+        in_x,
+        in_y,
+        src.port_type.as_str(),
+        "in",
+    );
+    set_points_property(&mut first.properties, &first.points);
+
+    let mut second = Line::default();
+    second
+        .properties
+        .insert("Src".to_string(), format!("{}#out:1", sid));
+    second.properties.insert(
+        "Dst".to_string(),
+        format!("{}#{}:{}", dst.sid, dst.port_type, dst.port_index),
+    );
+    second.src = Some(EndpointRef {
+        sid,
+        port_type: PortKind::Out,
+        port_index: 1,
+    });
+    second.dst = Some(dst.clone());
+    second.points = auto_route(
+        out_x,
+        out_y,
+        dst_pos.0,
+        dst_pos.1,
+        "out",
+        dst.port_type.as_str(),
+    );
+    set_points_property(&mut second.properties, &second.points);
+
+    let mut cmds = vec![add_block(system, block)];
+    let old = std::mem::replace(&mut system.lines[line_index], first.clone());
+    cmds.push(EditorCommand::ReplaceLine {
+        line_index,
+        old: Box::new(old),
+        new: Box::new(first),
+    });
+    cmds.push(EditorCommand::AddLine {
+        line_index: system.lines.len(),
+        line: Box::new(second.clone()),
+    });
+    system.lines.push(second);
+    Ok(EditorCommand::Batch(cmds))
+}
+
+/// Model position of the port an endpoint refers to.
+fn endpoint_model_pos(system: &System, endpoint: &EndpointRef) -> Option<(f32, f32)> {
+    let block = system
+        .blocks
+        .iter()
+        .find(|b| b.sid.as_deref() == Some(endpoint.sid.as_str()))?;
+    let (l, t, r, b) = parse_position(block.position.as_deref()?)?;
+    let counts = block.port_counts.as_ref();
+    let count = match endpoint.port_type.as_str() {
+        "out" => counts.and_then(|c| c.outs),
+        _ => counts.and_then(|c| c.ins),
+    }
+    .unwrap_or(1);
+    Some(port_model_pos(
+        l as f32,
+        t as f32,
+        r as f32,
+        b as f32,
+        endpoint.port_type.as_str(),
+        endpoint.port_index,
+        count,
+        block,
+    ))
+}
+
+/// The point halfway along a polyline.
+fn path_midpoint(path: &[(f32, f32)]) -> (f32, f32) {
+    let length = |(ax, ay): (f32, f32), (bx, by): (f32, f32)| (bx - ax).hypot(by - ay);
+    let total: f32 = path.windows(2).map(|w| length(w[0], w[1])).sum();
+    let mut remaining = total / 2.0;
+    for w in path.windows(2) {
+        let segment = length(w[0], w[1]);
+        if segment >= remaining && segment > 0.0 {
+            let f = remaining / segment;
+            return (
+                w[0].0 + f * (w[1].0 - w[0].0),
+                w[0].1 + f * (w[1].1 - w[0].1),
+            );
+        }
+        remaining -= segment;
+    }
+    path[0]
+}
+
+/// `name`, or `name` with the smallest number appended that no block in
+/// `system` uses yet.
+fn unique_block_name(system: &System, name: &str) -> String {
+    let taken = |n: &str| system.blocks.iter().any(|b| b.name == n);
+    if !taken(name) {
+        return name.to_string();
+    }
+    (1..)
+        .map(|i| format!("{}{}", name, i))
+        .find(|n| !taken(n))
+        .unwrap_or_default()
+}
+
+/// Set the signals a `BusSelector` outputs, choosing from `elements`, the
+/// bus entering it (see [`crate::bus::input_bus_elements`]).
+///
+/// Writes `InputSignals` and `OutputSignals` and sets the output count: one
+/// per selected signal, or one if the block outputs a bus. Signals that were
+/// already selected keep their output port and lines; deselected ones are
+/// removed with their lines and newly selected ones are appended in the
+/// order given.
+pub fn configure_bus_selector(
+    system: &mut System,
+    block_index: usize,
+    elements: &[BusElement],
+    selected: &[String],
+) -> Result<EditorCommand> {
+    let block = system
+        .blocks
+        .get(block_index)
+        .ok_or_else(|| anyhow!("No block at index {}", block_index))?;
+    if block.block_type != "BusSelector" {
+        return Err(anyhow!("{} is not a BusSelector", block.name));
+    }
+    if selected.is_empty() {
+        return Err(anyhow!("Select at least one bus signal"));
+    }
+    if let Some(path) = selected
+        .iter()
+        .find(|p| crate::bus::find_element(elements, p).is_none())
+    {
+        return Err(anyhow!("{} is not an element of the bus", path));
+    }
+    let as_bus = block.properties.get("OutputAsBus").map(String::as_str) == Some("on");
+    let old: Vec<String> = block
+        .properties
+        .get("OutputSignals")
+        .map(|v| v.split(',').map(|s| s.trim().to_string()).collect())
+        .unwrap_or_default();
+
+    let mut cmds = Vec::new();
+    let mut outputs = Vec::new();
+    if as_bus {
+        outputs = selected.to_vec();
+        cmds.push(set_port_count(system, block_index, "out", 1)?);
+    } else {
+        for (i, signal) in old.iter().enumerate().rev() {
+            if !selected.contains(signal) {
+                cmds.push(remove_port(system, block_index, "out", i as u32 + 1)?);
+            }
+        }
+        outputs.extend(old.into_iter().filter(|s| selected.contains(s)));
+        for signal in selected {
+            if !outputs.contains(signal) {
+                outputs.push(signal.clone());
+            }
+        }
+        cmds.push(set_port_count(
+            system,
+            block_index,
+            "out",
+            outputs.len() as u32,
+        )?);
+    }
+    cmds.push(replace_block(system, block_index, |block| {
+        set_block_parameter(
+            block,
+            "InputSignals",
+            &crate::bus::input_signals_value(elements),
+        );
+        set_block_parameter(block, "OutputSignals", &outputs.join(","));
+        Ok(())
+    })?);
+    Ok(EditorCommand::Batch(cmds))
+}
+
+/// How [`edit_ports`] changes the ports of one kind.
+#[derive(Debug, Clone, Copy)]
+enum PortEdit {
+    /// Keep the first ports, dropping or appending ports at the end.
+    Resize(u32),
+    /// Remove the port with this index, shifting the later ones down.
+    Remove(u32),
+    /// Insert a port at this index, shifting the later ones up.
+    Insert(u32),
+}
+
+impl PortEdit {
+    /// The port count after the edit, or `None` if it does not apply to
+    /// `count` ports.
+    fn new_count(self, count: u32) -> Option<u32> {
+        match self {
+            PortEdit::Resize(n) => Some(n),
+            PortEdit::Remove(k) => (1..=count).contains(&k).then(|| count - 1),
+            PortEdit::Insert(k) => (1..=count + 1).contains(&k).then_some(count + 1),
+        }
+    }
+
+    /// The new index of old port `index`, or `None` if it is removed.
+    fn map(self, index: u32) -> Option<u32> {
+        match self {
+            PortEdit::Resize(n) => (index <= n).then_some(index),
+            PortEdit::Remove(k) if index == k => None,
+            PortEdit::Remove(k) if index > k => Some(index - 1),
+            PortEdit::Insert(k) if index >= k => Some(index + 1),
+            _ => Some(index),
+        }
+    }
+}
+
+/// The parameter that sets how many `port_type` ports a block of
+/// `block_type` has, e.g. `Inputs` of a Sum or `Outputs` of a Demux.
+pub fn port_count_parameter(block_type: &str, port_type: &str) -> Option<&'static str> {
+    match (block_type, port_type) {
+        ("Sum" | "Product" | "Mux" | "Logic" | "BusCreator" | "Merge" | "MinMax", "in") => {
+            Some("Inputs")
+        }
+        ("Concatenate", "in") => Some("NumInputs"),
+        ("Scope", "in") => Some("NumInputPorts"),
+        ("Demux", "out") => Some("Outputs"),
+        _ => None,
+    }
+}
+
+/// Set the number of `port_type` (`"in"` or `"out"`) ports of the block at
+/// `block_index`. Ports are added or dropped at the end; see
+/// [`remove_port`] for the line handling.
+pub fn set_port_count(
+    system: &mut System,
+    block_index: usize,
+    port_type: &str,
+    count: u32,
+) -> Result<EditorCommand> {
+    edit_ports(system, block_index, port_type, PortEdit::Resize(count))
+}
+
+/// Insert a `port_type` port at 1-based `index` of the block at
+/// `block_index`. Lines on later ports move up with their port.
+pub fn insert_port(
+    system: &mut System,
+    block_index: usize,
+    port_type: &str,
+    index: u32,
+) -> Result<EditorCommand> {
+    edit_ports(system, block_index, port_type, PortEdit::Insert(index))
+}
+
+/// Remove the `port_type` port at 1-based `index` of the block at
+/// `block_index`.
+///
+/// The block's `PortCounts`, `PortProperties` and port count parameter are
+/// updated; a Sum or Product keeps the signs of the remaining inputs. Lines
+/// on later ports move down with their port. A line starting at the removed
+/// port is deleted; a line or branch ending there loses that end, and is
+/// deleted if nothing is left of it.
+pub fn remove_port(
+    system: &mut System,
+    block_index: usize,
+    port_type: &str,
+    index: u32,
+) -> Result<EditorCommand> {
+    edit_ports(system, block_index, port_type, PortEdit::Remove(index))
+}
+
+fn edit_ports(
+    system: &mut System,
+    block_index: usize,
+    port_type: &str,
+    edit: PortEdit,
+) -> Result<EditorCommand> {
+    let block = system
+        .blocks
+        .get(block_index)
+        .ok_or_else(|| anyhow!("No block at index {}", block_index))?;
+    let counts = block.port_counts.clone().unwrap_or(PortCounts {
+        ins: None,
+        outs: None,
+        special: Vec::new(),
+    });
+    let count = match port_type {
+        "in" => counts.ins,
+        "out" => counts.outs,
+        _ => return Err(anyhow!("Cannot change the number of {} ports", port_type)),
+    }
+    .unwrap_or(0);
+    let new_count = edit.new_count(count).ok_or_else(|| {
+        anyhow!(
+            "{:?} is out of range for block {} with {} {} ports",
+            edit,
+            block.name,
+            count,
+            port_type
+        )
+    })?;
+
+    let mut new = block.clone();
+    let counts = new.port_counts.get_or_insert(counts);
+    let slot = if port_type == "in" {
+        &mut counts.ins
+    } else {
+        &mut counts.outs
+    };
+    *slot = (new_count > 0).then_some(new_count);
+    if !new.child_order.is_empty() && !new.child_order.contains(&BlockChildKind::PortCounts) {
+        new.child_order.insert(0, BlockChildKind::PortCounts);
+    }
+    renumber_port_properties(&mut new.ports, port_type, count, new_count, edit);
+    if let Some(name) = port_count_parameter(&new.block_type, port_type) {
+        let value = new
+            .properties
+            .get(name)
+            .and_then(|v| edit_sign_list(&new.block_type, v, edit))
+            .unwrap_or_else(|| new_count.to_string());
+        set_block_parameter(&mut new, name, &value);
+    }
+
+    let mut cmds = Vec::new();
+    let mut deleted = Vec::new();
+    if let Some(sid) = block.sid.clone() {
+        for (line_index, line) in system.lines.iter_mut().enumerate() {
+            if !line_uses_port(line, &sid, port_type) {
+                continue;
+            }
+            let mut edited = line.clone();
+            if !reindex_line(&mut edited, &sid, port_type, edit) {
+                deleted.push(line_index);
+            } else {
+                let old = std::mem::replace(line, edited.clone());
+                cmds.push(EditorCommand::ReplaceLine {
+                    line_index,
+                    old: Box::new(old),
+                    new: Box::new(edited),
+                });
+            }
+        }
+    }
+    let old = std::mem::replace(&mut system.blocks[block_index], new.clone());
+    cmds.insert(
+        0,
+        EditorCommand::ReplaceBlock {
+            block_index,
+            old: Box::new(old),
+            new: Box::new(new),
+        },
+    );
+    // One command per line: the inverse of `DeleteLines` re-adds only one.
+    for line_index in deleted.into_iter().rev() {
+        cmds.push(delete_lines(system, &[line_index]));
+    }
+    Ok(EditorCommand::Batch(cmds))
+}
+
+/// Whether `line` or one of its branches ends at a `port_type` port of
+/// block `sid`.
+fn line_uses_port(line: &Line, sid: &str, port_type: &str) -> bool {
+    let on_port = |e: &EndpointRef| e.sid == sid && e.port_type == port_type;
+    line.src.as_ref().is_some_and(on_port) || line.connections().iter().any(|c| on_port(&c.dst))
+}
+
+/// Apply `edit` to the `PortProperties` entries of `port_type`. If every
+/// port had an entry, new ports get an empty one too.
+fn renumber_port_properties(
+    ports: &mut Vec<Port>,
+    port_type: &str,
+    count: u32,
+    new_count: u32,
+    edit: PortEdit,
+) {
+    let Some(first) = ports.iter().position(|p| p.port_type == port_type) else {
+        return;
+    };
+    let complete = ports.iter().filter(|p| p.port_type == port_type).count() == count as usize;
+    let mut edited: Vec<Port> = Vec::new();
+    ports.retain(|p| {
+        if p.port_type != port_type {
+            return true;
+        }
+        if let Some(index) = p.index.map_or(Some(None), |i| edit.map(i).map(Some)) {
+            edited.push(Port { index, ..p.clone() });
+        }
+        false
+    });
+    if complete {
+        for index in 1..=new_count {
+            if !edited.iter().any(|p| p.index == Some(index)) {
+                edited.push(Port {
+                    port_type: port_type.to_string(),
+                    index: Some(index),
+                    properties: IndexMap::new(),
+                });
+            }
+        }
+    }
+    edited.sort_by_key(|p| p.index);
+    ports.splice(first..first, edited);
+}
+
+/// Apply `edit` to a Sum or Product sign list such as `|+-` or `**/`,
+/// keeping the signs of the remaining inputs. `None` if `value` is a plain
+/// input count.
+fn edit_sign_list(block_type: &str, value: &str, edit: PortEdit) -> Option<String> {
+    let new_sign = match block_type {
+        "Sum" => '+',
+        "Product" => '*',
+        _ => return None,
+    };
+    if value.trim().parse::<u32>().is_ok() {
+        return None;
+    }
+    let mut signs: Vec<char> = value.chars().collect();
+    let sign_positions: Vec<usize> = signs
+        .iter()
+        .enumerate()
+        .filter(|(_, c)| **c != '|')
+        .map(|(i, _)| i)
+        .collect();
+    let nth = |k: u32| sign_positions.get(k as usize - 1).copied();
+    match edit {
+        PortEdit::Resize(n) => {
+            if let Some(cut) = nth(n + 1) {
+                signs.truncate(cut);
+            }
+            for _ in sign_positions.len()..n as usize {
+                signs.push(new_sign);
+            }
+        }
+        PortEdit::Remove(k) => {
+            signs.remove(nth(k)?);
+        }
+        PortEdit::Insert(k) => {
+            let at = nth(k).unwrap_or(signs.len());
+            signs.insert(at, new_sign);
+        }
+    }
+    Some(signs.into_iter().collect())
+}
+
+/// Re-index the endpoints of `line` on `port_type` ports of block `sid`.
+/// Returns `false` if the line should be deleted.
+fn reindex_line(line: &mut Line, sid: &str, port_type: &str, edit: PortEdit) -> bool {
+    if let Some(src) = &mut line.src
+        && !reindex_endpoint(src, &mut line.properties, "Src", sid, port_type, edit)
+    {
+        return false;
+    }
+    // Disconnect removed ports first so that the branches stay well formed
+    for connection in line.connections() {
+        let dst = &connection.dst;
+        if dst.sid == sid && dst.port_type == port_type && edit.map(dst.port_index).is_none() {
+            line.remove_connection(dst);
+        }
+    }
+    if let Some(dst) = &mut line.dst {
+        reindex_endpoint(dst, &mut line.properties, "Dst", sid, port_type, edit);
+    }
+    reindex_branches(&mut line.branches, sid, port_type, edit);
+    line.dst.is_some() || !line.branches.is_empty()
+}
+
+fn reindex_branches(branches: &mut [Branch], sid: &str, port_type: &str, edit: PortEdit) {
+    for branch in branches {
+        if let Some(dst) = &mut branch.dst {
+            reindex_endpoint(dst, &mut branch.properties, "Dst", sid, port_type, edit);
+        }
+        reindex_branches(&mut branch.branches, sid, port_type, edit);
+    }
+}
+
+/// Re-index one endpoint and its `key` parameter. Returns `false`, and
+/// removes the parameter, if its port is removed.
+fn reindex_endpoint(
+    endpoint: &mut EndpointRef,
+    properties: &mut IndexMap<String, String>,
+    key: &str,
+    sid: &str,
+    port_type: &str,
+    edit: PortEdit,
+) -> bool {
+    if endpoint.sid != sid || endpoint.port_type != port_type {
+        return true;
+    }
+    match edit.map(endpoint.port_index) {
+        Some(index) => {
+            endpoint.port_index = index;
+            properties.insert(key.to_string(), format!("{}#{}:{}", sid, port_type, index));
+            true
+        }
+        None => {
+            properties.shift_remove(key);
+            false
+        }
+    }
+}
+
+/// Smallest numeric SID greater than every block and annotation SID.
+fn next_free_sid(system: &System) -> u32 {
+    let block_sids = system.blocks.iter().filter_map(|b| b.sid.as_deref());
+    let annotation_sids = system.annotations.iter().filter_map(|a| a.sid.as_deref());
+    block_sids
+        .chain(annotation_sids)
+        .filter_map(|s| s.parse::<u32>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
 /// Find a snap target port near the given screen position.
 ///
 /// Returns `(block_index, port_type, port_index, snap_position)` if a port
@@ -1525,12 +2541,10 @@ pub fn find_snap_port(
                         block.ports.iter().filter(|p| p.port_type == "out").count() as u32
                     });
 
-                let mirrored = block.block_mirror.unwrap_or(false);
-
                 // Check input ports
                 for i in 1..=n_in {
                     let (px, py) =
-                        port_model_pos(rect_l, rect_t, rect_r, rect_b, "in", i, n_in, mirrored);
+                        port_model_pos(rect_l, rect_t, rect_r, rect_b, "in", i, n_in, block);
                     let dist = ((pos_x - px).powi(2) + (pos_y - py).powi(2)).sqrt();
                     if dist < snap_radius {
                         if best.as_ref().map_or(true, |b| dist < b.5) {
@@ -1542,7 +2556,7 @@ pub fn find_snap_port(
                 // Check output ports
                 for i in 1..=n_out {
                     let (px, py) =
-                        port_model_pos(rect_l, rect_t, rect_r, rect_b, "out", i, n_out, mirrored);
+                        port_model_pos(rect_l, rect_t, rect_r, rect_b, "out", i, n_out, block);
                     let dist = ((pos_x - px).powi(2) + (pos_y - py).powi(2)).sqrt();
                     if dist < snap_radius {
                         if best.as_ref().map_or(true, |b| dist < b.5) {
@@ -1557,7 +2571,8 @@ pub fn find_snap_port(
     best.map(|(idx, pt, pi, px, py, _)| (idx, pt, pi, px, py))
 }
 
-/// Compute port position in model coordinates.
+/// Compute port position in model coordinates, honouring the block's
+/// `BlockMirror` and rotation.
 fn port_model_pos(
     l: f32,
     t: f32,
@@ -1566,19 +2581,23 @@ fn port_model_pos(
     port_type: &str,
     port_index: u32,
     num_ports: u32,
-    mirrored: bool,
+    block: &Block,
 ) -> (f32, f32) {
     let n = num_ports.max(port_index);
     let total_segments = n * 2 + 1;
-    let dy = (b - t) / (total_segments as f32);
-    let y = t + ((2 * port_index) as f32 - 0.5) * dy;
-
-    let is_left = match (port_type, mirrored) {
-        ("in", false) | ("out", true) => true,
-        _ => false,
-    };
-
-    if is_left { (l, y) } else { (r, y) }
+    let f = ((2 * port_index) as f32 - 0.5) / (total_segments as f32);
+    let x = l + f * (r - l);
+    let y = t + f * (b - t);
+
+    let mirrored = block.block_mirror.unwrap_or(false);
+    let upstream = matches!((port_type, mirrored), ("in", false) | ("out", true));
+
+    match (block.orientation, upstream) {
+        (BlockOrientation::Right, true) | (BlockOrientation::Left, false) => (l, y),
+        (BlockOrientation::Right, false) | (BlockOrientation::Left, true) => (r, y),
+        (BlockOrientation::Down, true) | (BlockOrientation::Up, false) => (x, t),
+        (BlockOrientation::Down, false) | (BlockOrientation::Up, true) => (x, b),
+    }
 }
 
 /// Compute an auto-routing path between two points using orthogonal segments.
@@ -17,7 +17,7 @@
 
 #![cfg(feature = "egui")]
 
-use crate::model::System;
+use crate::model::{LineId, System};
 
 /// A rectangle used for drag-selection in screen coordinates.
 #[derive(Debug, Clone, Copy)]
@@ -224,6 +224,20 @@ impl EditorSelection {
         }
     }
 
+    /// Ids of the selected lines of `system`, for [`Self::reselect_lines`].
+    pub fn selected_line_ids(&self, system: &System) -> Vec<LineId> {
+        self.selected_lines
+            .iter()
+            .filter_map(|&i| system.lines.get(i)?.id())
+            .collect()
+    }
+
+    /// Select the lines with `ids` again after `system` was edited, dropping
+    /// those that no longer exist.
+    pub fn reselect_lines(&mut self, system: &System, ids: &[LineId]) {
+        self.selected_lines = ids.iter().filter_map(|id| system.line_index(id)).collect();
+    }
+
     /// Get the number of selected items (blocks + lines).
     pub fn count(&self) -> usize {
         self.selected_blocks.len() + self.selected_lines.len()
@@ -2,15 +2,23 @@
 //!
 //! [`EditorState`] wraps the existing [`SubsystemApp`] with additional editing
 //! state: selection, undo/redo history, drag state, connection drawing,
-//! block browser state, code editor, and clipboard.
+//! block browser state, code editor, annotation editor, bus assistant, live
+//! lint, and clipboard.
 
 #![cfg(feature = "egui")]
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, mpsc};
+use std::time::{Duration, Instant};
 
-use crate::model::{Block, Chart, System};
+use crate::bus::BusElement;
+use crate::lint::{Finding, Severity};
+use crate::model::{Annotation, Block, Chart, EndpointRef, Line, PortKind, System};
+use crate::transform::PortOrder;
 
-use super::block_catalog::{BlockCatalogCategory, get_block_catalog_by_category};
+use super::block_catalog::{
+    BlockCatalogCategory, BlockCatalogEntry, get_block_catalog_by_category,
+};
 use super::operations::EditorHistory;
 use super::selection::EditorSelection;
 use crate::egui_app::SubsystemApp;
@@ -140,6 +148,9 @@ pub struct CodeEditorState {
     pub code: String,
     /// The original code (to detect changes).
     pub original_code: String,
+    /// Chart holding the code of a MATLAB Function block; the code is
+    /// applied to the chart instead of the block's parameters.
+    pub chart_id: Option<u32>,
 }
 
 impl Default for CodeEditorState {
@@ -150,6 +161,7 @@ impl Default for CodeEditorState {
             block_name: String::new(),
             code: String::new(),
             original_code: String::new(),
+            chart_id: None,
         }
     }
 }
@@ -162,6 +174,14 @@ impl CodeEditorState {
         self.block_name = name.to_string();
         self.code = code.to_string();
         self.original_code = code.to_string();
+        self.chart_id = None;
+    }
+
+    /// Open the code editor for a MATLAB Function block whose script is
+    /// stored in chart `chart_id`.
+    pub fn open_for_chart(&mut self, index: usize, name: &str, chart_id: u32, code: &str) {
+        self.open_for_block(index, name, code);
+        self.chart_id = Some(chart_id);
     }
 
     /// Returns true if the code has been modified.
@@ -175,6 +195,468 @@ impl CodeEditorState {
     }
 }
 
+// ────────────────────────────────────────────────────────────────────────────
+// Annotation editor state
+// ────────────────────────────────────────────────────────────────────────────
+
+/// Default size of a newly created annotation (model coordinates).
+const NEW_ANNOTATION_SIZE: (i32, i32) = (120, 30);
+
+/// State for the annotation editor window.
+#[derive(Debug, Clone, Default)]
+pub struct AnnotationEditorState {
+    /// Whether the editor is visible.
+    pub open: bool,
+    /// Index of the annotation in the current system, or `None` for a new one.
+    pub annotation_index: Option<usize>,
+    /// Annotation text; HTML when the interpreter is `rich`.
+    pub text: String,
+    /// Text interpreter: `off`, `tex` or `rich`.
+    pub interpreter: String,
+    /// Font name; empty means Simulink's default (`auto`).
+    pub font_name: String,
+    /// Font size in points; 0 means the default size.
+    pub font_size: u32,
+    /// Bold font weight.
+    pub bold: bool,
+    /// Rectangle `[l, t, r, b]` in model coordinates.
+    pub position: [i32; 4],
+    /// Last selected character range in the text, used by the B/I buttons.
+    pub selection: Option<(usize, usize)>,
+}
+
+impl AnnotationEditorState {
+    /// Open the editor for an existing annotation.
+    pub fn open_for_annotation(&mut self, index: usize, annotation: &Annotation) {
+        *self = Self {
+            open: true,
+            annotation_index: Some(index),
+            text: annotation.text.clone().unwrap_or_default(),
+            interpreter: annotation
+                .interpreter
+                .clone()
+                .unwrap_or_else(|| "off".to_string()),
+            font_name: annotation
+                .font_name
+                .clone()
+                .filter(|n| n != "auto")
+                .unwrap_or_default(),
+            font_size: annotation.font_size.unwrap_or(0),
+            bold: crate::egui_app::text::is_bold_weight(annotation.font_weight.as_deref()),
+            position: annotation
+                .position
+                .as_deref()
+                .and_then(super::operations::parse_position)
+                .map(|(l, t, r, b)| [l, t, r, b])
+                .unwrap_or([0, 0, NEW_ANNOTATION_SIZE.0, NEW_ANNOTATION_SIZE.1]),
+            selection: None,
+        };
+    }
+
+    /// Open the editor for a new annotation at `(x, y)`.
+    pub fn open_new(&mut self, x: i32, y: i32) {
+        *self = Self {
+            open: true,
+            interpreter: "off".to_string(),
+            position: [x, y, x + NEW_ANNOTATION_SIZE.0, y + NEW_ANNOTATION_SIZE.1],
+            ..Default::default()
+        };
+    }
+
+    /// Close the editor without applying.
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    /// Switch the interpreter, converting the text between plain text and
+    /// rich HTML so the visible content is preserved.
+    pub fn set_interpreter(&mut self, interpreter: &str) {
+        let was_rich = self.interpreter == "rich";
+        let is_rich = interpreter == "rich";
+        if was_rich && !is_rich {
+            self.text = rich_to_plain(&self.text);
+        } else if !was_rich && is_rich {
+            self.text = plain_to_rich(&self.text);
+        }
+        self.interpreter = interpreter.to_string();
+        self.selection = None;
+    }
+
+    /// Wrap the selected text (or the whole text without a selection) in
+    /// `<tag>…</tag>`. Only meaningful for the `rich` interpreter.
+    pub fn wrap_selection(&mut self, tag: &str) {
+        let len = self.text.chars().count();
+        let (start, end) = match self.selection {
+            Some((a, b)) if a != b => (a.min(b).min(len), a.max(b).min(len)),
+            _ => (0, len),
+        };
+        let byte = |c: usize| {
+            self.text
+                .char_indices()
+                .nth(c)
+                .map_or(self.text.len(), |(i, _)| i)
+        };
+        let (bs, be) = (byte(start), byte(end));
+        self.text = format!(
+            "{}<{tag}>{}</{tag}>{}",
+            &self.text[..bs],
+            &self.text[bs..be],
+            &self.text[be..]
+        );
+        self.selection = None;
+    }
+
+    /// Apply the editor fields to a copy of `base`.
+    pub fn to_annotation(&self, base: &Annotation) -> Annotation {
+        use super::operations::{
+            set_annotation_font, set_annotation_interpreter, set_annotation_position,
+            set_annotation_text,
+        };
+        let mut ann = base.clone();
+        set_annotation_text(&mut ann, &self.text);
+        let [l, t, r, b] = self.position;
+        set_annotation_position(&mut ann, l, t, r, b);
+        let interpreter = (self.interpreter != "off").then_some(self.interpreter.as_str());
+        set_annotation_interpreter(&mut ann, interpreter);
+        let name = self.font_name.trim();
+        set_annotation_font(
+            &mut ann,
+            (!name.is_empty()).then_some(name),
+            (self.font_size > 0).then_some(self.font_size),
+            self.bold.then_some("bold"),
+        );
+        ann
+    }
+}
+
+/// Escape plain text as rich-text HTML, keeping line breaks.
+fn plain_to_rich(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('\n', "<br/>")
+}
+
+/// Strip rich-text HTML back to plain text, turning `<br>` and paragraph
+/// ends into line breaks.
+///
+/// Entities are decoded after the tags are removed, so escaped `<` in the
+/// text survives (the viewer's parser decodes first and gives up on it).
+fn rich_to_plain(html: &str) -> String {
+    let mut out = String::new();
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('>') else {
+            rest = &rest[start..];
+            break;
+        };
+        let tag = rest[start + 1..start + len]
+            .trim()
+            .trim_end_matches('/')
+            .to_ascii_lowercase();
+        if tag == "br" || tag == "/p" && !out.is_empty() {
+            out.push('\n');
+        }
+        rest = &rest[start + len + 1..];
+        // Simulink's rich text carries a <style> sheet in the head.
+        if tag.starts_with("style") {
+            rest = rest.find("</style>").map_or("", |end| &rest[end..]);
+        }
+    }
+    out.push_str(rest);
+    html_escape::decode_html_entities(out.trim_end_matches('\n')).into_owned()
+}
+
+// ────────────────────────────────────────────────────────────────────────────
+// Bus assistant state
+// ────────────────────────────────────────────────────────────────────────────
+
+/// State for the bus assistant window of a `BusSelector` or `BusCreator`.
+#[derive(Debug, Clone, Default)]
+pub struct BusAssistantState {
+    /// Whether the window is visible.
+    pub open: bool,
+    /// Index of the block in the current system.
+    pub block_index: usize,
+    /// The block name (for display in the title bar).
+    pub block_name: String,
+    /// Whether the block is a `BusSelector` (else a `BusCreator`).
+    pub selector: bool,
+    /// Elements of the bus entering a selector, or built by a creator.
+    pub elements: Vec<BusElement>,
+    /// Dotted paths of `elements` with whether each is selected.
+    pub signals: Vec<(String, bool)>,
+    /// Number of inputs of a creator.
+    pub inputs: u32,
+}
+
+impl BusAssistantState {
+    /// Open the assistant for the block at `index` of `system`. Does nothing
+    /// for other blocks than `BusSelector` and `BusCreator`.
+    pub fn open_for_block(&mut self, system: &System, index: usize) {
+        let Some(block) = system.blocks.get(index) else {
+            return;
+        };
+        let sid = block.sid.as_deref().unwrap_or_default();
+        let selector = match block.block_type.as_str() {
+            "BusSelector" => true,
+            "BusCreator" => false,
+            _ => return,
+        };
+        let elements = if selector {
+            crate::bus::input_bus_elements(system, sid, 1)
+        } else {
+            let src = EndpointRef {
+                sid: sid.to_string(),
+                port_type: PortKind::Out,
+                port_index: 1,
+            };
+            crate::bus::bus_elements(system, &src)
+        }
+        .unwrap_or_default();
+        let selected: Vec<&str> = block
+            .properties
+            .get("OutputSignals")
+            .map(|v| v.split(',').map(str::trim).collect())
+            .unwrap_or_default();
+        *self = Self {
+            open: true,
+            block_index: index,
+            block_name: block.name.clone(),
+            selector,
+            signals: crate::bus::element_paths(&elements)
+                .into_iter()
+                .map(|p| {
+                    let on = selected.contains(&p.as_str());
+                    (p, on)
+                })
+                .collect(),
+            elements,
+            inputs: block.port_counts.as_ref().and_then(|c| c.ins).unwrap_or(0),
+        };
+    }
+
+    /// The selected signal paths, in list order.
+    pub fn selected(&self) -> Vec<String> {
+        self.signals
+            .iter()
+            .filter(|(_, on)| *on)
+            .map(|(p, _)| p.clone())
+            .collect()
+    }
+
+    /// Close the window without applying.
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+}
+
+// ────────────────────────────────────────────────────────────────────────────
+// Live lint state
+// ────────────────────────────────────────────────────────────────────────────
+
+/// Quiet time after the last edit before the model is linted again.
+const LINT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Result of one lint run: the structural hash of the model and its
+/// findings, or `None` if the hash matched the previous run.
+type LintResult = (u64, Option<Vec<Finding>>);
+
+/// Findings of the live lint, recomputed on a background thread once edits
+/// pause. Edits that keep the model's
+/// [structural hash](crate::model::System::structural_hash), such as moving
+/// blocks, keep the findings without re-running the checks.
+#[derive(Debug, Default)]
+pub struct LintState {
+    /// Findings for the whole model, by block path from the root.
+    pub findings: Vec<Finding>,
+    /// Whether the problems panel is shown.
+    pub show_panel: bool,
+    /// History revision the findings are for or being computed for.
+    revision: Option<u64>,
+    /// When to start the next run.
+    due: Option<Instant>,
+    /// Structural hash of the model last linted.
+    hash: Option<u64>,
+    /// The running job.
+    job: Option<mpsc::Receiver<LintResult>>,
+}
+
+impl Clone for LintState {
+    /// Copies the findings; the clone re-lints on its next poll.
+    fn clone(&self) -> Self {
+        Self {
+            findings: self.findings.clone(),
+            show_panel: self.show_panel,
+            ..Default::default()
+        }
+    }
+}
+
+impl LintState {
+    /// Collect a finished run and start a new one when `root` changed at
+    /// history `revision` and edits have paused. The first run starts right
+    /// away. Returns `true` while a run is pending, so the caller keeps
+    /// repainting.
+    pub fn poll(&mut self, root: &System, revision: u64) -> bool {
+        let now = Instant::now();
+        if self.revision != Some(revision) {
+            let first = self.revision.is_none();
+            self.revision = Some(revision);
+            self.due = Some(if first { now } else { now + LINT_DEBOUNCE });
+        }
+        if let Some(job) = &self.job {
+            match job.try_recv() {
+                Ok((hash, findings)) => {
+                    self.hash = Some(hash);
+                    if let Some(findings) = findings {
+                        self.findings = findings;
+                    }
+                    self.job = None;
+                }
+                Err(mpsc::TryRecvError::Empty) => return true,
+                Err(mpsc::TryRecvError::Disconnected) => self.job = None,
+            }
+        }
+        if self.due.is_some_and(|due| now >= due) {
+            self.due = None;
+            let system = root.clone();
+            let previous = self.hash;
+            let (tx, rx) = mpsc::channel();
+            std::thread::spawn(move || {
+                let hash = system.structural_hash();
+                let findings = (previous != Some(hash)).then(|| crate::lint::lint(&system));
+                let _ = tx.send((hash, findings));
+            });
+            self.job = Some(rx);
+        }
+        self.due.is_some() || self.job.is_some()
+    }
+
+    /// The most severe finding at the block at `path` or inside it, and the
+    /// number of findings there.
+    pub fn worst_at(&self, path: &str) -> Option<(Severity, usize)> {
+        let mut found = crate::lint::findings_at(&self.findings, path).peekable();
+        found.peek()?;
+        let (worst, count) = found.fold((Severity::Information, 0), |(worst, n), f| {
+            (worst.min(f.severity), n + 1)
+        });
+        Some((worst, count))
+    }
+}
+
+// ────────────────────────────────────────────────────────────────────────────
+// Simulation preview
+// ────────────────────────────────────────────────────────────────────────────
+
+/// Final values of the simulated signals, by source block SID and output
+/// port number.
+pub type SignalValues = HashMap<(String, u32), f64>;
+
+/// Simulates the root system for the given stop time in seconds.
+pub type SimulationRunner = Arc<dyn Fn(&System, f64) -> anyhow::Result<SignalValues> + Send + Sync>;
+
+/// Simulation run from the editor's "Run" button. The crate has no
+/// interpreter of its own; the host sets one with
+/// [`EditorState::set_simulation_runner`], and the button is only shown once
+/// it has.
+#[derive(Default)]
+pub struct SimulationPreview {
+    runner: Option<SimulationRunner>,
+    /// Simulated time in seconds.
+    pub stop_time: f64,
+    /// Signal values of the last successful run.
+    pub values: SignalValues,
+    /// Error of the last run.
+    pub error: Option<String>,
+    /// The running job.
+    job: Option<mpsc::Receiver<anyhow::Result<SignalValues>>>,
+}
+
+impl Clone for SimulationPreview {
+    /// Copies the runner and the values; a running job stays with the
+    /// original.
+    fn clone(&self) -> Self {
+        Self {
+            runner: self.runner.clone(),
+            stop_time: self.stop_time,
+            values: self.values.clone(),
+            error: self.error.clone(),
+            job: None,
+        }
+    }
+}
+
+impl SimulationPreview {
+    fn new() -> Self {
+        Self {
+            stop_time: 10.0,
+            ..Default::default()
+        }
+    }
+
+    /// Whether a runner is set.
+    pub fn is_available(&self) -> bool {
+        self.runner.is_some()
+    }
+
+    /// Whether a run is in progress.
+    pub fn is_running(&self) -> bool {
+        self.job.is_some()
+    }
+
+    /// Simulate a copy of `root` on a background thread. Does nothing
+    /// without a runner or while a run is in progress.
+    pub fn run(&mut self, root: &System) {
+        let Some(runner) = self.runner.clone() else {
+            return;
+        };
+        if self.job.is_some() {
+            return;
+        }
+        let system = root.clone();
+        let stop_time = self.stop_time;
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(runner(&system, stop_time));
+        });
+        self.job = Some(rx);
+    }
+
+    /// Collect a finished run. Returns `true` while the run is in progress,
+    /// so the caller keeps repainting.
+    pub fn poll(&mut self) -> bool {
+        let Some(job) = &self.job else {
+            return false;
+        };
+        match job.try_recv() {
+            Ok(Ok(values)) => {
+                self.values = values;
+                self.error = None;
+            }
+            Ok(Err(e)) => self.error = Some(format!("{:#}", e)),
+            Err(mpsc::TryRecvError::Empty) => return true,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.error = Some("simulation stopped unexpectedly".to_string())
+            }
+        }
+        self.job = None;
+        false
+    }
+
+    /// Value of the signal leaving `src`.
+    pub fn value(&self, src: &EndpointRef) -> Option<f64> {
+        self.values.get(&(src.sid.clone(), src.port_index)).copied()
+    }
+
+    /// Value of the signal among `lines` entering input `port` of block `sid`.
+    pub fn input_value(&self, lines: &[Line], sid: &str, port: u32) -> Option<f64> {
+        let line = crate::bus::line_into(lines, sid, port)?;
+        self.value(line.src.as_ref()?)
+    }
+}
+
 // ────────────────────────────────────────────────────────────────────────────
 // Clipboard
 // ────────────────────────────────────────────────────────────────────────────
@@ -239,6 +721,14 @@ pub struct EditorState {
     pub block_browser: BlockBrowserState,
     /// Code editor state.
     pub code_editor: CodeEditorState,
+    /// Annotation editor state.
+    pub annotation_editor: AnnotationEditorState,
+    /// Bus assistant state.
+    pub bus_assistant: BusAssistantState,
+    /// Live lint findings.
+    pub lint: LintState,
+    /// Simulation preview.
+    pub simulation: SimulationPreview,
     /// Clipboard.
     pub clipboard: EditorClipboard,
     /// Whether the model has been modified since last save.
@@ -266,6 +756,10 @@ impl EditorState {
             drag_mode: DragMode::None,
             block_browser: BlockBrowserState::default(),
             code_editor: CodeEditorState::default(),
+            annotation_editor: AnnotationEditorState::default(),
+            bus_assistant: BusAssistantState::default(),
+            lint: LintState::default(),
+            simulation: SimulationPreview::new(),
             clipboard: EditorClipboard::default(),
             dirty: false,
             snap_to_grid: true,
@@ -284,6 +778,14 @@ impl EditorState {
         resolve_subsystem_by_vec_mut(&mut self.app.root, &self.app.path)
     }
 
+    /// Set the function the "Run" button simulates the model with.
+    pub fn set_simulation_runner<F>(&mut self, f: F)
+    where
+        F: Fn(&System, f64) -> anyhow::Result<SignalValues> + Send + Sync + 'static,
+    {
+        self.simulation.runner = Some(Arc::new(f));
+    }
+
     /// Snap a coordinate to the grid if snapping is enabled.
     pub fn snap(&self, value: i32) -> i32 {
         if self.snap_to_grid && self.grid_size > 0 {
@@ -381,6 +883,19 @@ impl EditorState {
         self.dirty = true;
     }
 
+    /// Comment through/uncomment selected blocks.
+    pub fn comment_through_selection(&mut self) {
+        if self.selection.selected_blocks.is_empty() {
+            return;
+        }
+        let indices = self.selection.selected_blocks.clone();
+        if let Some(system) = resolve_subsystem_by_vec_mut(&mut self.app.root, &self.app.path) {
+            let cmd = super::operations::comment_through_blocks(system, &indices);
+            self.history.push(cmd);
+        }
+        self.dirty = true;
+    }
+
     /// Rotate selected blocks.
     pub fn rotate_selection(&mut self) {
         if self.selection.selected_blocks.is_empty() {
@@ -407,6 +922,46 @@ impl EditorState {
         self.dirty = true;
     }
 
+    /// Set the number of `port_type` ports of block `block_idx`, keeping the
+    /// lines on the remaining ports connected.
+    pub fn set_block_port_count(&mut self, block_idx: usize, port_type: &str, count: u32) {
+        if let Some(system) = resolve_subsystem_by_vec_mut(&mut self.app.root, &self.app.path)
+            && let Ok(cmd) = super::operations::set_port_count(system, block_idx, port_type, count)
+        {
+            self.history.push(cmd);
+            self.dirty = true;
+        }
+    }
+
+    /// Renumber or move the port blocks inside subsystem `block_idx` so that
+    /// their numbers and vertical order agree.
+    pub fn order_block_ports(&mut self, block_idx: usize, by: PortOrder) {
+        let Some(system) = resolve_subsystem_by_vec_mut(&mut self.app.root, &self.app.path) else {
+            return;
+        };
+        match super::operations::order_ports(system, block_idx, by) {
+            Ok(cmd) => {
+                self.history.push(cmd);
+                self.dirty = true;
+            }
+            Err(e) => self.app.show_notification(e.to_string(), 3000),
+        }
+    }
+
+    /// Insert a block for `entry` into line `line_idx` of the current system.
+    pub fn insert_block_on_line(&mut self, line_idx: usize, entry: &BlockCatalogEntry) {
+        let Some(system) = resolve_subsystem_by_vec_mut(&mut self.app.root, &self.app.path) else {
+            return;
+        };
+        match super::operations::insert_block_on_line(system, line_idx, entry) {
+            Ok(cmd) => {
+                self.history.push(cmd);
+                self.dirty = true;
+            }
+            Err(e) => self.app.show_notification(e.to_string(), 3000),
+        }
+    }
+
     /// Create a subsystem from selected blocks.
     pub fn create_subsystem_from_selection(&mut self, name: &str) {
         if self.selection.selected_blocks.is_empty() {
@@ -421,10 +976,63 @@ impl EditorState {
         self.dirty = true;
     }
 
+    /// Open the annotation editor for annotation `index` of the current system.
+    pub fn open_annotation_editor(&mut self, index: usize) {
+        let ann = self
+            .current_system()
+            .and_then(|s| s.annotations.get(index))
+            .cloned();
+        if let Some(ann) = ann {
+            self.annotation_editor.open_for_annotation(index, &ann);
+        }
+    }
+
+    /// Apply the annotation editor: update the edited annotation, or add a
+    /// new one (which the editor then keeps editing).
+    pub fn apply_annotation_editor(&mut self) {
+        let editor = self.annotation_editor.clone();
+        let Some(system) = resolve_subsystem_by_vec_mut(&mut self.app.root, &self.app.path) else {
+            return;
+        };
+        let cmd = match editor.annotation_index {
+            Some(idx) => {
+                let Some(base) = system.annotations.get(idx) else {
+                    return;
+                };
+                let ann = editor.to_annotation(base);
+                super::operations::update_annotation(system, idx, ann)
+            }
+            None => {
+                let ann = editor.to_annotation(&Annotation::default());
+                self.annotation_editor.annotation_index = Some(system.annotations.len());
+                Some(super::operations::add_annotation(system, ann))
+            }
+        };
+        if let Some(cmd) = cmd {
+            self.history.push(cmd);
+            self.dirty = true;
+        }
+    }
+
+    /// Delete annotation `index` of the current system.
+    pub fn delete_annotation(&mut self, index: usize) {
+        let system = resolve_subsystem_by_vec_mut(&mut self.app.root, &self.app.path);
+        if let Some(system) = system.filter(|s| index < s.annotations.len()) {
+            let cmd = super::operations::delete_annotations(system, &[index]);
+            self.history.push(cmd);
+            self.dirty = true;
+        }
+        if self.annotation_editor.annotation_index == Some(index) {
+            self.annotation_editor.close();
+        }
+    }
+
     /// Undo the last operation.
     pub fn undo(&mut self) {
         if let Some(system) = resolve_subsystem_by_vec_mut(&mut self.app.root, &self.app.path) {
+            let lines = self.selection.selected_line_ids(system);
             if self.history.undo(system) {
+                self.selection.reselect_lines(system, &lines);
                 self.dirty = true;
             }
         }
@@ -433,7 +1041,9 @@ impl EditorState {
     /// Redo the last undone operation.
     pub fn redo(&mut self) {
         if let Some(system) = resolve_subsystem_by_vec_mut(&mut self.app.root, &self.app.path) {
+            let lines = self.selection.selected_line_ids(system);
             if self.history.redo(system) {
+                self.selection.reselect_lines(system, &lines);
                 self.dirty = true;
             }
         }
@@ -5,6 +5,7 @@
 //!
 //! - Block dragging with arrow-key support
 //! - Connection drawing with auto-snap to ports
+//! - Port hover information and click-to-select of the attached line
 //! - Rectangle selection of blocks and lines
 //! - Block browser popup (hotkey "A")
 //! - Context menus for blocks, lines, and canvas
@@ -18,12 +19,15 @@ use std::collections::HashMap;
 
 use eframe::egui::{self, Align2, Color32, Pos2, Rect, RichText, Sense, Stroke, Vec2};
 
-use crate::model::EndpointRef;
+use crate::block_path::BlockPath;
+use crate::lint::Severity;
+use crate::model::{EndpointRef, PortKind};
+use crate::transform::PortOrder;
 
 use crate::egui_app::{
-    BlockDialog, SignalDialog, endpoint_pos_maybe_mirrored, get_block_type_cfg,
-    highlight_query_job, parse_block_rect, parse_rect_str, render_block_icon,
-    wrap_text_to_max_width,
+    BlockDialog, PortAnchors, SignalDialog, ViewTransform, c_syntax_job_styled, canvas_navigation,
+    get_block_type_cfg, highlight_query_job, matlab_syntax_job_styled, paint_pass_through_arrow,
+    parse_block_rect, parse_rect_str, render_block_icon, wrap_text_to_max_width,
 };
 
 use super::operations;
@@ -52,6 +56,8 @@ pub fn editor_update_with_info(state: &mut EditorState, ui: &mut egui::Ui) {
     editor_update_internal(state, ui);
     show_block_browser(state, ui);
     show_code_editor(state, ui);
+    show_annotation_editor(state, ui);
+    show_bus_assistant(state, ui);
 }
 
 // ────────────────────────────────────────────────────────────────────────────
@@ -60,6 +66,11 @@ pub fn editor_update_with_info(state: &mut EditorState, ui: &mut egui::Ui) {
 
 fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
     let path_snapshot = state.app.path.clone();
+    let lint_pending = state.lint.poll(&state.app.root, state.history.revision());
+    if state.simulation.poll() || lint_pending {
+        ui.ctx()
+            .request_repaint_after(std::time::Duration::from_millis(100));
+    }
 
     // Top panel: breadcrumbs + search + edit toolbar
     egui::TopBottomPanel::top("editor_top").show_inside(ui, |ui| {
@@ -111,6 +122,15 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
             if comment_btn.clicked() {
                 state.comment_selection();
             }
+            let through_btn = ui
+                .add_enabled(
+                    !state.selection.selected_blocks.is_empty(),
+                    egui::Button::new("↦ Comment Through"),
+                )
+                .on_hover_text("Comment out, passing inputs through to outputs");
+            if through_btn.clicked() {
+                state.comment_through_selection();
+            }
             let rotate_btn = ui.add_enabled(
                 !state.selection.selected_blocks.is_empty(),
                 egui::Button::new("🔄 Rotate"),
@@ -151,6 +171,31 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                     .range(1..=50),
             );
 
+            ui.separator();
+            let problems = state.lint.findings.len();
+            ui.toggle_value(
+                &mut state.lint.show_panel,
+                format!("⚠ Problems ({})", problems),
+            );
+            if state.simulation.is_available() {
+                ui.separator();
+                ui.add(
+                    egui::DragValue::new(&mut state.simulation.stop_time)
+                        .prefix("Stop time: ")
+                        .suffix(" s")
+                        .speed(0.1)
+                        .range(0.0..=f64::MAX),
+                );
+                let running = state.simulation.is_running();
+                let label = if running { "⏳ Running…" } else { "▶ Run" };
+                if ui.add_enabled(!running, egui::Button::new(label)).clicked() {
+                    state.simulation.run(&state.app.root);
+                }
+                if let Some(error) = &state.simulation.error {
+                    ui.colored_label(Color32::RED, "Simulation failed")
+                        .on_hover_text(error);
+                }
+            }
             ui.separator();
             ui.checkbox(&mut state.app.show_block_names_default, "Block names");
             ui.label("Name size");
@@ -194,7 +239,7 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                     .max_height(200.0)
                     .show(ui, |ui| {
                         for p in state.app.search_matches.clone() {
-                            let label = format!("/{}", p.join("/"));
+                            let label = format!("/{}", BlockPath::from(&p[..]));
                             let job = highlight_query_job(&label, &state.app.search_query);
                             let resp = ui.add(egui::Label::new(job).sense(Sense::click()));
                             if resp.clicked() {
@@ -209,6 +254,10 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
         }
     });
 
+    if state.lint.show_panel {
+        show_problems_panel(state, ui);
+    }
+
     // Resolve current system
     let entities_opt = state.app.current_entities();
     if entities_opt.is_none() {
@@ -230,22 +279,22 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
     for b in &entities.blocks {
         let mut bc = b.clone();
         bc.properties
-            .entry("SystemName".to_string())
-            .or_insert(system_name.clone());
+            .get_or_insert_with("SystemName", || system_name.clone());
         enriched_blocks.push(bc);
     }
     let blocks: Vec<(&crate::model::Block, Rect)> = enriched_blocks
         .iter()
         .filter_map(|b| parse_block_rect(b).map(|r| (b, r)))
         .collect();
-    let annotations: Vec<(&crate::model::Annotation, Rect)> = entities
+    let annotations: Vec<(usize, &crate::model::Annotation, Rect)> = entities
         .annotations
         .iter()
-        .filter_map(|a| {
+        .enumerate()
+        .filter_map(|(i, a)| {
             a.position
                 .as_deref()
                 .and_then(|s| parse_rect_str(s))
-                .map(|pos| (a, pos))
+                .map(|pos| (i, a, pos))
         })
         .collect();
 
@@ -263,12 +312,12 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
     let mut bb = blocks
         .first()
         .map(|x| x.1)
-        .or_else(|| annotations.first().map(|x| x.1))
+        .or_else(|| annotations.first().map(|x| x.2))
         .unwrap();
     for (_, r) in &blocks {
         bb = bb.union(*r);
     }
-    for (_, r) in &annotations {
+    for (_, _, r) in &annotations {
         bb = bb.union(*r);
     }
 
@@ -301,23 +350,22 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
         // Handle keyboard shortcuts
         handle_keyboard_shortcuts(state, ui, &avail, base_scale, &bb);
 
-        // Zoom with scroll
-        let scroll_y = ui.input(|i| i.raw_scroll_delta.y);
-        if scroll_y.abs() > 0.0 && canvas_resp.hovered() {
-            let factor = (1.0 + scroll_y * 0.001).max(0.1);
-            let old_zoom = state.app.zoom;
-            let new_zoom = (old_zoom * factor).clamp(0.2, 10.0);
-            if (new_zoom - old_zoom).abs() > f32::EPSILON {
-                let origin = Pos2::new(avail.left() + margin, avail.top() + margin);
-                let s_old = base_scale * old_zoom;
-                let s_new = base_scale * new_zoom;
-                let cursor = canvas_resp.hover_pos().unwrap_or(avail.center());
-                let world_x = (cursor.x - origin.x - state.app.pan.x) / s_old + bb.left();
-                let world_y = (cursor.y - origin.y - state.app.pan.y) / s_old + bb.top();
-                state.app.zoom = new_zoom;
-                state.app.pan.x = cursor.x - ((world_x - bb.left()) * s_new + origin.x);
-                state.app.pan.y = cursor.y - ((world_y - bb.top()) * s_new + origin.y);
-            }
+        // Zoom with the wheel or a pinch, pan with trackpad/touch gestures
+        let nav = ui.input(canvas_navigation);
+        if !nav.is_none() && (canvas_resp.hovered() || nav.multi_touch) {
+            let vt = ViewTransform {
+                bb,
+                avail,
+                margin,
+                base_scale,
+                zoom: state.app.zoom,
+                pan: state.app.pan + nav.pan,
+            };
+            let center = nav
+                .zoom_center
+                .or(canvas_resp.hover_pos())
+                .unwrap_or(avail.center());
+            (state.app.zoom, state.app.pan) = vt.zoom_at(center, nav.zoom_factor);
         }
 
         let zoom = state.app.zoom;
@@ -459,6 +507,9 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                 } else {
                     render_block_icon(ui.painter(), b, &r_screen, font_scale, None);
                 }
+                if b.comment_through {
+                    paint_pass_through_arrow(ui.painter(), b, &r_screen, font_scale);
+                }
             } else {
                 ui.painter().rect_filled(r_screen, 6.0, bg);
                 let fg = contrast_color(bg);
@@ -482,6 +533,23 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                 );
             }
 
+            // Lint badge
+            let path = crate::overlay::block_path(&path_snapshot, &b.name);
+            if let Some((severity, count)) = state.lint.worst_at(&path) {
+                paint_lint_badge(ui.painter(), &r_screen, severity, count);
+            }
+
+            // Simulated value on Display blocks
+            if b.block_type == "Display" {
+                let value = b
+                    .sid
+                    .as_deref()
+                    .and_then(|sid| state.simulation.input_value(&entities.lines, sid, 1));
+                if let Some(value) = value {
+                    paint_display_value(ui.painter(), &r_screen, value, font_scale);
+                }
+            }
+
             // Block label (deferred)
 
             let show_name = b.show_name.unwrap_or(state.app.show_block_names_default);
@@ -712,6 +780,17 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                                         );
                                         state.history.push(cmd);
                                         state.dirty = true;
+                                        // Offer the bus signals when wiring a bus block.
+                                        let bus_block = if src_port_type == "out" {
+                                            dst_idx
+                                        } else {
+                                            sys_mut
+                                                .blocks
+                                                .iter()
+                                                .position(|b| b.sid.as_ref() == Some(src_sid))
+                                                .unwrap_or(dst_idx)
+                                        };
+                                        state.bus_assistant.open_for_block(sys_mut, bus_block);
                                         state.app.show_notification("Connection created", 1500);
                                     }
                                 }
@@ -725,7 +804,7 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
         // Cancel connection on Escape (handled in keyboard shortcuts)
 
         // Draw annotations
-        for (a, r_model) in &annotations {
+        for (ann_idx, a, r_model) in &annotations {
             let r_screen = Rect::from_min_max(to_screen(r_model.min), to_screen(r_model.max));
             let raw = a.text.clone().unwrap_or_default();
             let parsed =
@@ -736,13 +815,31 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
             let galley = ui.painter().layout_job(job);
             ui.painter()
                 .galley(r_screen.left_top(), galley, Color32::WHITE);
+
+            let editing = state.annotation_editor.open
+                && state.annotation_editor.annotation_index == Some(*ann_idx);
+            if editing {
+                ui.painter().rect_stroke(
+                    r_screen,
+                    0.0,
+                    Stroke::new(1.0, Color32::from_rgb(0, 120, 255)),
+                    egui::StrokeKind::Outside,
+                );
+            }
+            let resp = ui.allocate_rect(r_screen, Sense::click());
+            if resp.double_clicked() {
+                state.open_annotation_editor(*ann_idx);
+            }
+            resp.context_menu(|ui| {
+                annotation_context_menu(state, ui, *ann_idx);
+            });
         }
 
         // Draw lines
-        let mut sid_mirrored: HashMap<String, bool> = HashMap::new();
+        let mut sid_anchors: HashMap<String, PortAnchors> = HashMap::new();
         for (b, _r) in &blocks {
             if let Some(sid) = &b.sid {
-                sid_mirrored.insert(sid.clone(), b.block_mirror.unwrap_or(false));
+                sid_anchors.insert(sid.clone(), PortAnchors::of(b));
             }
         }
         let mut port_counts: HashMap<(String, u8), u32> = HashMap::new();
@@ -787,8 +884,8 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
             let num_src = port_counts
                 .get(&(src.sid.clone(), if src.port_type == "out" { 1 } else { 0 }))
                 .copied();
-            let mirrored_src = sid_mirrored.get(&src.sid).copied().unwrap_or(false);
-            let mut cur = endpoint_pos_maybe_mirrored(*sr, src, num_src, mirrored_src);
+            let anchors_src = sid_anchors.get(&src.sid).cloned().unwrap_or_default();
+            let mut cur = anchors_src.endpoint_pos(*sr, src, num_src);
             let mut offsets_pts = vec![cur];
             for off in &line.points {
                 cur = Pos2::new(cur.x + off.x as f32, cur.y + off.y as f32);
@@ -802,8 +899,8 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                     let num_dst = port_counts
                         .get(&(dst.sid.clone(), if dst.port_type == "out" { 1 } else { 0 }))
                         .copied();
-                    let mirrored_dst = sid_mirrored.get(&dst.sid).copied().unwrap_or(false);
-                    let dst_pt = endpoint_pos_maybe_mirrored(*dr, dst, num_dst, mirrored_dst);
+                    let anchors_dst = sid_anchors.get(&dst.sid).cloned().unwrap_or_default();
+                    let dst_pt = anchors_dst.endpoint_pos(*dr, dst, num_dst);
                     screen_pts.push(to_screen(dst_pt));
                 }
             }
@@ -835,7 +932,7 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                     br,
                     stroke,
                     color,
-                    &sid_mirrored,
+                    &sid_anchors,
                 );
             }
 
@@ -918,9 +1015,6 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                         start: Pos2,
                         out: &mut Vec<(Pos2, Pos2)>,
                         to_screen: &dyn Fn(Pos2) -> Pos2,
-                        sid_map: &std::collections::HashMap<String, Rect>,
-                        port_counts: &std::collections::HashMap<(String, u8), u32>,
-                        sid_mirrored: &std::collections::HashMap<String, bool>,
                     ) {
                         let mut cur = start;
                         for off in &br.points {
@@ -929,15 +1023,7 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                             cur = next;
                         }
                         for child in &br.branches {
-                            collect_branch_segments_editor(
-                                child,
-                                cur,
-                                out,
-                                to_screen,
-                                sid_map,
-                                port_counts,
-                                sid_mirrored,
-                            );
+                            collect_branch_segments_editor(child, cur, out, to_screen);
                         }
                     }
                     let main_anchor = offsets_pts
@@ -945,15 +1031,7 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                         .copied()
                         .unwrap_or(offsets_pts.first().copied().unwrap_or(Pos2::ZERO));
                     for br in &line.branches {
-                        collect_branch_segments_editor(
-                            br,
-                            main_anchor,
-                            &mut segments,
-                            &to_screen,
-                            &sid_map,
-                            &port_counts,
-                            &sid_mirrored,
-                        );
+                        collect_branch_segments_editor(br, main_anchor, &mut segments, &to_screen);
                     }
 
                     for (a, b) in &segments {
@@ -977,6 +1055,13 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
                 }
 
                 if is_near_segment {
+                    if let Some(value) = line
+                        .src
+                        .as_ref()
+                        .and_then(|src| state.simulation.value(src))
+                    {
+                        line_resp.clone().on_hover_text(format_signal_value(value));
+                    }
                     line_resp.context_menu(|ui| {
                         line_context_menu(state, ui, li, line);
                     });
@@ -1274,16 +1359,16 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
         {
             // Find start position from the actual port
             let start_screen = if let Some(sr) = sid_map.get(src_sid) {
-                let mirrored = sid_mirrored.get(src_sid).copied().unwrap_or(false);
+                let anchors = sid_anchors.get(src_sid).cloned().unwrap_or_default();
                 let ep = EndpointRef {
                     sid: src_sid.clone(),
-                    port_type: src_port_type.clone(),
+                    port_type: PortKind::from(src_port_type.as_str()),
                     port_index: src_port_index,
                 };
                 let num_ports = port_counts
                     .get(&(src_sid.clone(), if src_port_type == "out" { 1 } else { 0 }))
                     .copied();
-                let model_pos = endpoint_pos_maybe_mirrored(*sr, &ep, num_ports, mirrored);
+                let model_pos = anchors.endpoint_pos(*sr, &ep, num_ports);
                 Some(to_screen(model_pos))
             } else {
                 sid_screen_map.get(src_sid).map(|sr| {
@@ -1403,7 +1488,7 @@ fn editor_update_internal(state: &mut EditorState, ui: &mut egui::Ui) {
             }
             state.drag_mode = DragMode::None;
         }
-        if matches!(state.drag_mode, DragMode::Pan) && canvas_resp.dragged() {
+        if matches!(state.drag_mode, DragMode::Pan) && canvas_resp.dragged() && !nav.multi_touch {
             state.app.pan += canvas_resp.drag_delta();
         }
         if matches!(state.drag_mode, DragMode::Pan) && canvas_resp.drag_stopped() {
@@ -1453,18 +1538,26 @@ fn handle_keyboard_shortcuts(
             i.key_pressed(egui::Key::ArrowLeft),
             i.key_pressed(egui::Key::ArrowRight),
             i.key_pressed(egui::Key::Escape),
+            i.key_pressed(egui::Key::X),
         )
     });
-    let (ctrl, _shift, z, y, delete, a, c, v, r, m, up, down, left, right, escape) = input;
+    let (ctrl, shift, z, y, delete, a, c, v, r, m, up, down, left, right, escape, x) = input;
 
     // Ctrl+Z: Undo
     if ctrl && z {
         state.undo();
     }
     // Ctrl+Y: Redo
-    if ctrl && y {
+    if ctrl && !shift && y {
         state.redo();
     }
+    // Ctrl+Shift+X / Ctrl+Shift+Y: Comment out / through (as in Simulink)
+    if ctrl && shift && x {
+        state.comment_selection();
+    }
+    if ctrl && shift && y {
+        state.comment_through_selection();
+    }
     // Delete: Delete selection
     if delete {
         state.delete_selection();
@@ -1515,6 +1608,7 @@ fn handle_keyboard_shortcuts(
         state.selection.clear();
         state.block_browser.close();
         state.code_editor.close();
+        state.annotation_editor.close();
     }
 }
 
@@ -1533,11 +1627,16 @@ fn block_context_menu(
         state.delete_selection();
         ui.close();
     }
-    if ui.button("Comment / Uncomment").clicked() {
+    if ui.button("Comment Out / Uncomment").clicked() {
         state.selection.select_block(block_idx);
         state.comment_selection();
         ui.close();
     }
+    if ui.button("Comment Through / Uncomment").clicked() {
+        state.selection.select_block(block_idx);
+        state.comment_through_selection();
+        ui.close();
+    }
     if ui.button("Rotate").clicked() {
         state.selection.select_block(block_idx);
         state.rotate_selection();
@@ -1548,12 +1647,42 @@ fn block_context_menu(
         state.mirror_selection();
         ui.close();
     }
+    for (port_type, label) in [("in", "Input"), ("out", "Output")] {
+        if operations::port_count_parameter(&block.block_type, port_type).is_none() {
+            continue;
+        }
+        let counts = block.port_counts.as_ref();
+        let count = if port_type == "in" {
+            counts.and_then(|c| c.ins)
+        } else {
+            counts.and_then(|c| c.outs)
+        }
+        .unwrap_or(0);
+        if ui.button(format!("Add {}", label)).clicked() {
+            state.set_block_port_count(block_idx, port_type, count + 1);
+            ui.close();
+        }
+        if count > 1 && ui.button(format!("Remove Last {}", label)).clicked() {
+            state.set_block_port_count(block_idx, port_type, count - 1);
+            ui.close();
+        }
+    }
     ui.separator();
     if ui.button("Copy").clicked() {
         state.selection.select_block(block_idx);
         state.copy_selection();
         ui.close();
     }
+    if matches!(block.block_type.as_str(), "BusSelector" | "BusCreator")
+        && ui.button("Bus Signals…").clicked()
+    {
+        if let Some(system) =
+            crate::egui_app::resolve_subsystem_by_vec(&state.app.root, &state.app.path)
+        {
+            state.bus_assistant.open_for_block(system, block_idx);
+        }
+        ui.close();
+    }
     ui.separator();
     if is_code_block(block) {
         if ui.button("Edit Code…").clicked() {
@@ -1568,6 +1697,14 @@ fn block_context_menu(
             state.selection.clear();
             ui.close();
         }
+        if ui.button("Number Ports Top to Bottom").clicked() {
+            state.order_block_ports(block_idx, PortOrder::ByPosition);
+            ui.close();
+        }
+        if ui.button("Arrange Ports by Number").clicked() {
+            state.order_block_ports(block_idx, PortOrder::ByNumber);
+            ui.close();
+        }
     }
     if !state.selection.selected_blocks.is_empty() && state.selection.selected_blocks.len() > 1 {
         if ui.button("Create Subsystem from Selection…").clicked() {
@@ -1602,6 +1739,28 @@ fn line_context_menu(
         state.delete_selection();
         ui.close();
     }
+    if line.branches.is_empty() {
+        ui.menu_button("Insert Block", |ui| {
+            for category in super::block_catalog::get_block_catalog_by_category() {
+                let entries: Vec<_> = category
+                    .entries
+                    .iter()
+                    .filter(|e| e.default_inputs > 0 && e.default_outputs > 0)
+                    .collect();
+                if entries.is_empty() {
+                    continue;
+                }
+                ui.menu_button(&category.name, |ui| {
+                    for entry in entries {
+                        if ui.button(&entry.display_name).clicked() {
+                            state.insert_block_on_line(line_idx, entry);
+                            ui.close();
+                        }
+                    }
+                });
+            }
+        });
+    }
     ui.separator();
     // Rename label
     if ui.button("Rename Label…").clicked() {
@@ -1629,6 +1788,17 @@ fn line_context_menu(
     }
 }
 
+fn annotation_context_menu(state: &mut EditorState, ui: &mut egui::Ui, ann_idx: usize) {
+    if ui.button("Edit Annotation…").clicked() {
+        state.open_annotation_editor(ann_idx);
+        ui.close();
+    }
+    if ui.button("Delete Annotation").clicked() {
+        state.delete_annotation(ann_idx);
+        ui.close();
+    }
+}
+
 fn canvas_context_menu(
     state: &mut EditorState,
     ui: &mut egui::Ui,
@@ -1643,6 +1813,15 @@ fn canvas_context_menu(
         state.block_browser.open_at(pos.x as i32, pos.y as i32);
         ui.close();
     }
+    if ui.button("Add Annotation…").clicked() {
+        let pos = canvas_resp
+            .hover_pos()
+            .map(from_screen)
+            .unwrap_or(Pos2::new(200.0, 200.0));
+        let (x, y) = (state.snap(pos.x as i32), state.snap(pos.y as i32));
+        state.annotation_editor.open_new(x, y);
+        ui.close();
+    }
     if ui.button("Paste").clicked() {
         state.paste();
         ui.close();
@@ -1728,14 +1907,8 @@ fn show_block_browser(state: &mut EditorState, ui: &mut egui::Ui) {
                                     &mut state.app.root,
                                     &state.app.path,
                                 ) {
-                                    let block = operations::create_default_block(
-                                        &entry.block_type,
-                                        &entry.display_name,
-                                        insert_x,
-                                        insert_y,
-                                        entry.default_inputs,
-                                        entry.default_outputs,
-                                    );
+                                    let block =
+                                        operations::create_catalog_block(entry, insert_x, insert_y);
                                     let cmd = operations::add_block(system, block);
                                     state.history.push(cmd);
                                     state.dirty = true;
@@ -1786,15 +1959,19 @@ fn show_code_editor(state: &mut EditorState, ui: &mut egui::Ui) {
                     // Save code back to block
                     let block_index = state.code_editor.block_index;
                     let code = state.code_editor.code.clone();
-                    if let Some(system) = super::state::resolve_subsystem_by_vec_mut(
-                        &mut state.app.root,
-                        &state.app.path,
-                    ) {
-                        if let Some(block) = system.blocks.get_mut(block_index) {
-                            set_block_code(block, &code);
+                    if let Some(chart_id) = state.code_editor.chart_id {
+                        if state.app.set_chart_script(chart_id, &code) {
                             state.mark_dirty();
                             state.app.show_notification("Code applied", 1500);
                         }
+                    } else if let Some(system) = super::state::resolve_subsystem_by_vec_mut(
+                        &mut state.app.root,
+                        &state.app.path,
+                    ) && let Some(block) = system.blocks.get_mut(block_index)
+                    {
+                        set_block_code(block, &code);
+                        state.mark_dirty();
+                        state.app.show_notification("Code applied", 1500);
                     }
                     state.code_editor.original_code = code;
                 }
@@ -1808,16 +1985,324 @@ fn show_code_editor(state: &mut EditorState, ui: &mut egui::Ui) {
             ui.separator();
 
             // Code text area with syntax highlighting
+            let is_c = state.code_editor.chart_id.is_none()
+                && state
+                    .app
+                    .current_system()
+                    .and_then(|s| s.blocks.get(state.code_editor.block_index))
+                    .is_some_and(|b| b.block_type == "CFunction");
+            let style = state.app.code_style.clone();
+            let dark_mode = ui.visuals().dark_mode;
+            let mut layouter = |ui: &egui::Ui, text: &dyn egui::TextBuffer, wrap_width: f32| {
+                let mut job = if is_c {
+                    c_syntax_job_styled(text.as_str(), &style, dark_mode)
+                } else {
+                    matlab_syntax_job_styled(text.as_str(), &style, dark_mode)
+                };
+                job.wrap.max_width = wrap_width;
+                ui.painter().layout_job(job)
+            };
             let theme = egui::TextEdit::multiline(&mut state.code_editor.code)
                 .font(egui::TextStyle::Monospace)
                 .desired_width(f32::INFINITY)
-                .desired_rows(20);
+                .desired_rows(20)
+                .layouter(&mut layouter);
             ui.add(theme);
         });
 
     state.code_editor.open = open;
 }
 
+// ────────────────────────────────────────────────────────────────────────────
+// Simulation preview
+// ────────────────────────────────────────────────────────────────────────────
+
+fn format_signal_value(value: f64) -> String {
+    format!("{:.4}", value)
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// The value in a white box over the middle of a Display block.
+fn paint_display_value(painter: &egui::Painter, rect: &Rect, value: f64, font_scale: f32) {
+    let font_id = egui::FontId::monospace(11.0 * font_scale);
+    let galley = painter.layout_no_wrap(format_signal_value(value), font_id, Color32::BLACK);
+    let text_rect = Rect::from_center_size(rect.center(), galley.size()).expand(2.0);
+    painter.rect_filled(text_rect, 2.0, Color32::WHITE);
+    painter.galley(text_rect.min + Vec2::splat(2.0), galley, Color32::BLACK);
+}
+
+// ────────────────────────────────────────────────────────────────────────────
+// Live lint
+// ────────────────────────────────────────────────────────────────────────────
+
+fn severity_color(severity: Severity) -> Color32 {
+    match severity {
+        Severity::Error => Color32::from_rgb(220, 50, 50),
+        Severity::Warning => Color32::from_rgb(240, 170, 0),
+        Severity::Information => Color32::from_rgb(60, 140, 230),
+    }
+}
+
+/// A colored circle with the finding count on the top-right block corner.
+fn paint_lint_badge(painter: &egui::Painter, rect: &Rect, severity: Severity, count: usize) {
+    let center = rect.right_top();
+    let radius = 7.0;
+    painter.circle_filled(center, radius, severity_color(severity));
+    painter.circle_stroke(center, radius, Stroke::new(1.0, Color32::WHITE));
+    let text = if count > 9 {
+        "9+".to_string()
+    } else {
+        count.to_string()
+    };
+    painter.text(
+        center,
+        Align2::CENTER_CENTER,
+        text,
+        egui::FontId::proportional(9.0),
+        Color32::WHITE,
+    );
+}
+
+/// Bottom panel listing the findings; clicking one opens the system holding
+/// the block and selects it.
+fn show_problems_panel(state: &mut EditorState, ui: &mut egui::Ui) {
+    let mut target = None;
+    egui::TopBottomPanel::bottom("editor_problems")
+        .resizable(true)
+        .default_height(140.0)
+        .show_inside(ui, |ui| {
+            ui.label(RichText::new("Problems").strong());
+            if state.lint.findings.is_empty() {
+                ui.label("No problems found.");
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for finding in &state.lint.findings {
+                    ui.horizontal(|ui| {
+                        ui.colored_label(severity_color(finding.severity), "●");
+                        let link = ui.link(&finding.block);
+                        ui.label(RichText::new(&finding.source).weak());
+                        ui.label(&finding.message);
+                        if link.clicked() {
+                            target = Some(finding.block.clone());
+                        }
+                    });
+                }
+            });
+        });
+    if let Some(path) = target
+        && let Ok(path) = path.parse::<BlockPath>()
+    {
+        let mut segments: Vec<String> = path.into();
+        let Some(name) = segments.pop() else {
+            return;
+        };
+        state.app.navigate_to_path(segments);
+        state.selection.clear();
+        if let Some(index) = state
+            .current_system()
+            .and_then(|s| s.blocks.iter().position(|b| b.name == name))
+        {
+            state.selection.select_block(index);
+        }
+    }
+}
+
+// ────────────────────────────────────────────────────────────────────────────
+// Bus assistant window
+// ────────────────────────────────────────────────────────────────────────────
+
+fn show_bus_assistant(state: &mut EditorState, ui: &mut egui::Ui) {
+    if !state.bus_assistant.open {
+        return;
+    }
+    let mut open = state.bus_assistant.open;
+    let mut apply = false;
+    let assistant = &mut state.bus_assistant;
+    egui::Window::new(format!("Bus: {}", assistant.block_name))
+        .open(&mut open)
+        .default_size([300.0, 300.0])
+        .resizable(true)
+        .show(ui.ctx(), |ui| {
+            if assistant.selector {
+                if assistant.signals.is_empty() {
+                    ui.label("The input is not traced to a BusCreator.");
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for (path, on) in &mut assistant.signals {
+                            let depth = path.matches('.').count();
+                            ui.horizontal(|ui| {
+                                ui.add_space(depth as f32 * 12.0);
+                                ui.checkbox(on, path.rsplit('.').next().unwrap_or(path));
+                            });
+                        }
+                    });
+            } else {
+                ui.horizontal(|ui| {
+                    ui.label("Inputs:");
+                    ui.add(egui::DragValue::new(&mut assistant.inputs).range(1..=64));
+                });
+                for (path, _) in &assistant.signals {
+                    ui.label(RichText::new(path).monospace());
+                }
+            }
+            ui.separator();
+            apply = ui.button("Apply").clicked();
+        });
+
+    if apply {
+        let assistant = state.bus_assistant.clone();
+        if let Some(system) =
+            super::state::resolve_subsystem_by_vec_mut(&mut state.app.root, &state.app.path)
+        {
+            let result = if assistant.selector {
+                operations::configure_bus_selector(
+                    system,
+                    assistant.block_index,
+                    &assistant.elements,
+                    &assistant.selected(),
+                )
+            } else {
+                operations::set_port_count(system, assistant.block_index, "in", assistant.inputs)
+            };
+            match result {
+                Ok(cmd) => {
+                    state.history.push(cmd);
+                    state.mark_dirty();
+                    open = false;
+                }
+                Err(e) => state.app.show_notification(e.to_string(), 3000),
+            }
+        }
+    }
+    state.bus_assistant.open = open;
+}
+
+// ────────────────────────────────────────────────────────────────────────────
+// Annotation editor window
+// ────────────────────────────────────────────────────────────────────────────
+
+const ANNOTATION_INTERPRETERS: [&str; 3] = ["off", "tex", "rich"];
+
+fn show_annotation_editor(state: &mut EditorState, ui: &mut egui::Ui) {
+    if !state.annotation_editor.open {
+        return;
+    }
+
+    let mut open = state.annotation_editor.open;
+    let title = if state.annotation_editor.annotation_index.is_some() {
+        "Edit Annotation"
+    } else {
+        "New Annotation"
+    };
+
+    egui::Window::new(title)
+        .id(egui::Id::new("editor_annotation_window"))
+        .open(&mut open)
+        .default_size([420.0, 360.0])
+        .resizable(true)
+        .show(ui.ctx(), |ui| {
+            let editor = &mut state.annotation_editor;
+            ui.horizontal(|ui| {
+                ui.label("Interpreter:");
+                let mut interpreter = editor.interpreter.clone();
+                egui::ComboBox::from_id_salt("annotation_interpreter")
+                    .selected_text(&interpreter)
+                    .show_ui(ui, |ui| {
+                        for i in ANNOTATION_INTERPRETERS {
+                            ui.selectable_value(&mut interpreter, i.to_string(), i);
+                        }
+                    });
+                if interpreter != editor.interpreter {
+                    editor.set_interpreter(&interpreter);
+                }
+                let rich = editor.interpreter == "rich";
+                if ui
+                    .add_enabled(rich, egui::Button::new(RichText::new("B").strong()))
+                    .on_hover_text("Bold selection")
+                    .clicked()
+                {
+                    editor.wrap_selection("b");
+                }
+                if ui
+                    .add_enabled(rich, egui::Button::new(RichText::new("I").italics()))
+                    .on_hover_text("Italic selection")
+                    .clicked()
+                {
+                    editor.wrap_selection("i");
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.label("Font:");
+                ui.add(
+                    egui::TextEdit::singleline(&mut editor.font_name)
+                        .hint_text("auto")
+                        .desired_width(120.0),
+                );
+                ui.label("Size:");
+                ui.add(egui::DragValue::new(&mut editor.font_size).range(0..=200))
+                    .on_hover_text("0 uses the default size");
+                ui.checkbox(&mut editor.bold, "Bold");
+            });
+            ui.horizontal(|ui| {
+                ui.label("Position:");
+                for v in editor.position.iter_mut() {
+                    ui.add(egui::DragValue::new(v));
+                }
+            });
+            ui.separator();
+
+            let output = egui::TextEdit::multiline(&mut editor.text)
+                .desired_width(f32::INFINITY)
+                .desired_rows(6)
+                .show(ui);
+            if let Some(range) = output.cursor_range {
+                let r = range.as_sorted_char_range();
+                editor.selection = Some((r.start, r.end));
+            }
+
+            ui.separator();
+            ui.label(RichText::new("Preview").weak());
+            let interpreter = (editor.interpreter != "off").then_some(editor.interpreter.as_str());
+            let parsed = crate::egui_app::text::annotation_to_rich_text(&editor.text, interpreter);
+            let size = if editor.font_size > 0 {
+                editor.font_size as f32
+            } else {
+                12.0
+            };
+            let mut job = parsed.to_layout_job(ui.style(), 1.0, size);
+            if editor.bold {
+                // egui has no bold faces; mirror the viewer's strong-color emphasis.
+                let strong = ui.visuals().strong_text_color();
+                for section in &mut job.sections {
+                    section.format.color = strong;
+                }
+            }
+            job.wrap.max_width = ui.available_width();
+            ui.label(job);
+
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button("Apply").clicked() {
+                    state.apply_annotation_editor();
+                    state.app.show_notification("Annotation applied", 1500);
+                }
+                let index = state.annotation_editor.annotation_index;
+                if let Some(idx) = index
+                    && ui.button("Delete").clicked()
+                {
+                    state.delete_annotation(idx);
+                    state.app.show_notification("Annotation deleted", 1500);
+                }
+            });
+        });
+
+    state.annotation_editor.open &= open;
+}
+
 // ────────────────────────────────────────────────────────────────────────────
 // Helper functions
 // ────────────────────────────────────────────────────────────────────────────
@@ -1839,10 +2324,23 @@ pub fn is_subsystem_block(block: &crate::model::Block) -> bool {
 }
 
 fn open_code_editor(state: &mut EditorState, block_idx: usize, block: &crate::model::Block) {
-    let code = get_block_code(block);
-    state
-        .code_editor
-        .open_for_block(block_idx, &block.name, &code);
+    let chart = state.app.chart_id_for_block(block).and_then(|id| {
+        let script = state.app.charts.get(&id)?.script.clone();
+        Some((id, script.unwrap_or_default()))
+    });
+    match chart {
+        Some((chart_id, script)) => {
+            state
+                .code_editor
+                .open_for_chart(block_idx, &block.name, chart_id, &script)
+        }
+        None => {
+            let code = get_block_code(block);
+            state
+                .code_editor
+                .open_for_block(block_idx, &block.name, &code);
+        }
+    }
 }
 
 pub fn get_block_code(block: &crate::model::Block) -> String {
@@ -2048,7 +2546,7 @@ fn draw_branch_rec(
     br: &crate::model::Branch,
     stroke: Stroke,
     color: Color32,
-    sid_mirrored: &HashMap<String, bool>,
+    sid_anchors: &HashMap<String, PortAnchors>,
 ) {
     let mut pts: Vec<Pos2> = vec![start];
     let mut cur = start;
@@ -2068,8 +2566,8 @@ fn draw_branch_rec(
                 if dstb.port_type == "out" { 1 } else { 0 },
             );
             let num_dst = port_counts.get(&key).copied();
-            let mirrored_dst = sid_mirrored.get(&dstb.sid).copied().unwrap_or(false);
-            let end_pt = endpoint_pos_maybe_mirrored(*dr, dstb, num_dst, mirrored_dst);
+            let anchors_dst = sid_anchors.get(&dstb.sid).cloned().unwrap_or_default();
+            let end_pt = anchors_dst.endpoint_pos(*dr, dstb, num_dst);
             let a = to_screen(*pts.last().unwrap_or(&cur));
             let b = to_screen(end_pt);
             if dstb.port_type == "in" {
@@ -2089,7 +2587,7 @@ fn draw_branch_rec(
             sub,
             stroke,
             color,
-            sid_mirrored,
+            sid_anchors,
         );
     }
 }
@@ -2391,7 +2889,9 @@ fn compute_resized_rect(
 // Port interaction areas (for initiating connection drag)
 // ────────────────────────────────────────────────────────────────────────────
 
-/// Draw invisible interaction areas over port chevrons to initiate connection dragging.
+/// Draw invisible interaction areas over port chevrons. Hovering shows the
+/// port's name, data type and signal, clicking selects the attached line and
+/// dragging starts a new connection.
 fn draw_port_interaction_areas(
     ui: &mut egui::Ui,
     block: &crate::model::Block,
@@ -2402,13 +2902,6 @@ fn draw_port_interaction_areas(
 ) {
     let in_count = block.port_counts.as_ref().and_then(|p| p.ins).unwrap_or(0);
     let out_count = block.port_counts.as_ref().and_then(|p| p.outs).unwrap_or(0);
-    let mirrored = block.block_mirror.unwrap_or(false);
-
-    let (in_x, out_x) = if mirrored {
-        (r_screen.right(), r_screen.left())
-    } else {
-        (r_screen.left(), r_screen.right())
-    };
 
     let scale = font_scale.max(0.2);
     let hit_size = (12.0 * scale * 4.0).max(8.0);
@@ -2418,38 +2911,48 @@ fn draw_port_interaction_areas(
         None => return,
     };
 
-    // Input ports
-    for i in 0..in_count {
-        let n = in_count.max(1);
-        let y = r_screen.top() + r_screen.height() * ((i as f32 + 1.0) / (n as f32 + 1.0));
-        let port_center = Pos2::new(in_x, y);
+    let (ins, outs) = PortAnchors::of(block).indicator_positions(*r_screen, in_count, out_count);
+    let ports = ins
+        .into_iter()
+        .enumerate()
+        .map(|(i, p)| (p, i as u32 + 1, true))
+        .chain(
+            outs.into_iter()
+                .enumerate()
+                .map(|(i, p)| (p, i as u32 + 1, false)),
+        );
+
+    for (port_center, port_index, is_input) in ports {
         let hit_rect = Rect::from_center_size(port_center, Vec2::splat(hit_size));
-        let resp = ui.allocate_rect(hit_rect, Sense::click_and_drag());
+        let mut resp = ui.allocate_rect(hit_rect, Sense::click_and_drag());
 
-        if resp.drag_started() {
-            state.drag_mode = DragMode::Connection {
-                src_sid: sid.clone(),
-                src_port_type: "in".to_string(),
-                src_port_index: i + 1,
-                current_x: port_center.x,
-                current_y: port_center.y,
-            };
+        if resp.hovered()
+            && let Some(system) = state.current_system()
+        {
+            let text = crate::port_info::hover_text(
+                &system.blocks,
+                &system.lines,
+                block,
+                port_index,
+                is_input,
+            );
+            resp = resp.on_hover_text(text);
         }
-    }
 
-    // Output ports
-    for i in 0..out_count {
-        let n = out_count.max(1);
-        let y = r_screen.top() + r_screen.height() * ((i as f32 + 1.0) / (n as f32 + 1.0));
-        let port_center = Pos2::new(out_x, y);
-        let hit_rect = Rect::from_center_size(port_center, Vec2::splat(hit_size));
-        let resp = ui.allocate_rect(hit_rect, Sense::click_and_drag());
+        if resp.clicked() {
+            let line = state.current_system().and_then(|system| {
+                crate::port_info::attached_line(&system.lines, &sid, port_index, is_input)
+            });
+            if let Some(li) = line {
+                state.selection.select_line(li);
+            }
+        }
 
         if resp.drag_started() {
             state.drag_mode = DragMode::Connection {
                 src_sid: sid.clone(),
-                src_port_type: "out".to_string(),
-                src_port_index: i + 1,
+                src_port_type: if is_input { "in" } else { "out" }.to_string(),
+                src_port_index: port_index,
                 current_x: port_center.x,
                 current_y: port_center.y,
             };
@@ -0,0 +1,157 @@
+//! Bookmarked subsystems, blocks and signals of a model.
+//!
+//! A [`Bookmark`] names a subsystem by path and, for blocks and signals, the
+//! element inside it: blocks by SID (falling back to the name for models
+//! without SIDs) and signals by the output port they leave, since line
+//! indices change when lines are added or removed. Besides the user's
+//! bookmarks the viewer keeps the signals whose dialogs were opened last.
+//! Both lists are stored through `eframe::Storage` under a key derived from
+//! the model path only, so unlike the session they survive edits to the
+//! model.
+
+use crate::block_path::BlockPath;
+use crate::model::{Block, Line};
+use serde::{Deserialize, Serialize};
+
+/// Current on-disk format version.
+pub const BOOKMARKS_VERSION: u32 = 1;
+
+/// Prefix of the storage keys used for bookmarks.
+const STORAGE_PREFIX: &str = "rustylink.bookmarks.";
+
+/// Number of recently opened signals remembered.
+pub const RECENT_SIGNALS_LEN: usize = 10;
+
+/// What a bookmark points at inside its subsystem.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum BookmarkTarget {
+    /// The subsystem itself.
+    Subsystem,
+    Block {
+        sid: Option<String>,
+        name: String,
+    },
+    /// The signal leaving output `port` of the block with SID `sid`.
+    Signal {
+        sid: String,
+        port: u32,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Bookmark {
+    /// Text shown in the bookmarks panel.
+    pub label: String,
+    /// The bookmarked subsystem, or the one containing the block or signal.
+    pub path: Vec<String>,
+    pub target: BookmarkTarget,
+}
+
+impl Bookmark {
+    /// The subsystem at `path`.
+    pub fn subsystem(path: Vec<String>) -> Self {
+        let label = if path.is_empty() {
+            "Root".to_string()
+        } else {
+            format!("/{}", BlockPath::from(&path[..]))
+        };
+        Self {
+            label,
+            path,
+            target: BookmarkTarget::Subsystem,
+        }
+    }
+
+    /// `block` in the subsystem at `path`.
+    pub fn block(path: Vec<String>, block: &Block) -> Self {
+        Self {
+            label: format!("/{}", BlockPath::from(&path[..]).child(&block.name)),
+            path,
+            target: BookmarkTarget::Block {
+                sid: block.sid.clone(),
+                name: block.name.clone(),
+            },
+        }
+    }
+
+    /// The block at `path`, e.g. `Controller/Kp`, matched by name. `None`
+    /// for the root path.
+    pub fn block_at(path: &BlockPath) -> Option<Self> {
+        let name = path.name()?.to_string();
+        Some(Self {
+            label: format!("/{}", path),
+            path: path.parent()?.into_segments(),
+            target: BookmarkTarget::Block { sid: None, name },
+        })
+    }
+
+    /// The signal of `line` in the subsystem at `path` holding `blocks`.
+    /// `None` for lines without a source.
+    pub fn signal(path: Vec<String>, line: &Line, blocks: &[Block]) -> Option<Self> {
+        let src = line.src.as_ref()?;
+        let label = match line.name.as_deref().filter(|n| !n.is_empty()) {
+            Some(name) => name.to_string(),
+            None => {
+                let block = blocks
+                    .iter()
+                    .find(|b| b.sid.as_deref() == Some(src.sid.as_str()))
+                    .map_or(src.sid.as_str(), |b| b.name.as_str());
+                format!("{}:{}", block, src.port_index)
+            }
+        };
+        Some(Self {
+            label,
+            path,
+            target: BookmarkTarget::Signal {
+                sid: src.sid.clone(),
+                port: src.port_index,
+            },
+        })
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self
+    }
+
+    /// Whether both point at the same element, whatever their labels.
+    pub fn same_target(&self, other: &Bookmark) -> bool {
+        self.path == other.path && self.target == other.target
+    }
+}
+
+/// Persisted bookmarks of one model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SavedBookmarks {
+    pub version: u32,
+    pub bookmarks: Vec<Bookmark>,
+    /// Most recent first.
+    pub recent_signals: Vec<Bookmark>,
+}
+
+impl SavedBookmarks {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Parse stored bookmarks; other format versions are ignored.
+    pub fn from_json(text: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(text)
+            .ok()
+            .filter(|s| s.version == BOOKMARKS_VERSION)
+    }
+}
+
+/// `eframe::Storage` key of the bookmarks of the model at `model_path`.
+pub fn storage_key(model_path: &str) -> String {
+    format!("{STORAGE_PREFIX}{model_path}")
+}
+
+/// Add `signal` to the front of `recent`, dropping an older entry for the
+/// same signal and trimming the list to [`RECENT_SIGNALS_LEN`] entries.
+pub fn push_recent_signal(recent: &mut Vec<Bookmark>, signal: Bookmark) {
+    recent.retain(|b| !b.same_target(&signal));
+    recent.insert(0, signal);
+    recent.truncate(RECENT_SIGNALS_LEN);
+}
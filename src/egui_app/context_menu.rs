@@ -0,0 +1,208 @@
+//! Host-defined context menu entries for blocks and signals.
+//!
+//! A [`ContextMenuItem`] is an action, a check item, a submenu or a
+//! separator. Every entry can carry an icon glyph drawn before its label, a
+//! filter deciding whether it is shown for the clicked element, and an
+//! enabled predicate; disabled entries are shown greyed out. Entries are
+//! registered with [`SubsystemApp::add_block_context_menu_entry`] and
+//! [`SubsystemApp::add_signal_context_menu_entry`].
+//!
+//! [`SubsystemApp::add_block_context_menu_entry`]: super::SubsystemApp::add_block_context_menu_entry
+//! [`SubsystemApp::add_signal_context_menu_entry`]: super::SubsystemApp::add_signal_context_menu_entry
+
+use crate::model::{Block, Line};
+use eframe::egui;
+use std::sync::Arc;
+
+/// Predicate on the element a menu was opened for.
+pub type MenuPredicate<T> = Arc<dyn Fn(&T) -> bool + Send + Sync>;
+
+/// Called with the new state of a check box entry.
+pub type MenuToggle<T> = Arc<dyn Fn(&T, bool) + Send + Sync>;
+
+/// Context menu entry for blocks.
+pub type BlockContextMenuItem = ContextMenuItem<Block>;
+
+/// Context menu entry for signals.
+pub type SignalContextMenuItem = ContextMenuItem<Line>;
+
+/// What a context menu entry does.
+pub enum ContextMenuKind<T: ?Sized> {
+    Action(Arc<dyn Fn(&T) + Send + Sync>),
+    /// A check box showing `checked`; clicking it calls `on_toggle` with the
+    /// new state.
+    Check {
+        checked: MenuPredicate<T>,
+        on_toggle: MenuToggle<T>,
+    },
+    /// Nested entries, hidden when none of them is shown.
+    Submenu(Vec<ContextMenuItem<T>>),
+    Separator,
+}
+
+impl<T: ?Sized> Clone for ContextMenuKind<T> {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Action(on_click) => Self::Action(on_click.clone()),
+            Self::Check { checked, on_toggle } => Self::Check {
+                checked: checked.clone(),
+                on_toggle: on_toggle.clone(),
+            },
+            Self::Submenu(items) => Self::Submenu(items.clone()),
+            Self::Separator => Self::Separator,
+        }
+    }
+}
+
+/// One entry of a block or signal context menu.
+pub struct ContextMenuItem<T: ?Sized> {
+    pub label: String,
+    /// Glyph drawn before the label, e.g. `"📈"`.
+    pub icon: Option<String>,
+    pub kind: ContextMenuKind<T>,
+    /// Whether the entry is shown for an element.
+    pub filter: MenuPredicate<T>,
+    /// Whether the entry can be clicked.
+    pub enabled: MenuPredicate<T>,
+}
+
+impl<T: ?Sized> Clone for ContextMenuItem<T> {
+    fn clone(&self) -> Self {
+        Self {
+            label: self.label.clone(),
+            icon: self.icon.clone(),
+            kind: self.kind.clone(),
+            filter: self.filter.clone(),
+            enabled: self.enabled.clone(),
+        }
+    }
+}
+
+impl<T: ?Sized> ContextMenuItem<T> {
+    fn new(label: impl Into<String>, kind: ContextMenuKind<T>) -> Self {
+        Self {
+            label: label.into(),
+            icon: None,
+            kind,
+            filter: Arc::new(|_| true),
+            enabled: Arc::new(|_| true),
+        }
+    }
+
+    pub fn action(label: impl Into<String>, on_click: impl Fn(&T) + Send + Sync + 'static) -> Self {
+        Self::new(label, ContextMenuKind::Action(Arc::new(on_click)))
+    }
+
+    pub fn check(
+        label: impl Into<String>,
+        checked: impl Fn(&T) -> bool + Send + Sync + 'static,
+        on_toggle: impl Fn(&T, bool) + Send + Sync + 'static,
+    ) -> Self {
+        Self::new(
+            label,
+            ContextMenuKind::Check {
+                checked: Arc::new(checked),
+                on_toggle: Arc::new(on_toggle),
+            },
+        )
+    }
+
+    pub fn submenu(label: impl Into<String>, items: Vec<ContextMenuItem<T>>) -> Self {
+        Self::new(label, ContextMenuKind::Submenu(items))
+    }
+
+    pub fn separator() -> Self {
+        Self::new("", ContextMenuKind::Separator)
+    }
+
+    pub fn with_icon(mut self, icon: impl Into<String>) -> Self {
+        self.icon = Some(icon.into());
+        self
+    }
+
+    pub fn with_filter(mut self, filter: impl Fn(&T) -> bool + Send + Sync + 'static) -> Self {
+        self.filter = Arc::new(filter);
+        self
+    }
+
+    pub fn with_enabled(mut self, enabled: impl Fn(&T) -> bool + Send + Sync + 'static) -> Self {
+        self.enabled = Arc::new(enabled);
+        self
+    }
+
+    /// Label with the icon in front.
+    pub fn text(&self) -> String {
+        match &self.icon {
+            Some(icon) => format!("{icon} {}", self.label),
+            None => self.label.clone(),
+        }
+    }
+
+    /// Whether the entry is shown for `target`; submenus also need a shown
+    /// entry other than a separator.
+    pub fn is_shown(&self, target: &T) -> bool {
+        (self.filter)(target)
+            && match &self.kind {
+                ContextMenuKind::Submenu(items) => items.iter().any(|item| {
+                    !matches!(item.kind, ContextMenuKind::Separator) && item.is_shown(target)
+                }),
+                _ => true,
+            }
+    }
+}
+
+/// Draw `items` for `target` into an open context menu below the built-in
+/// entries, if `after_entries`. Separators are drawn only between entries:
+/// at the start of a submenu, at the end or next to another separator they
+/// are dropped.
+pub(crate) fn show_context_menu_items<T: ?Sized>(
+    ui: &mut egui::Ui,
+    items: &[ContextMenuItem<T>],
+    target: &T,
+    after_entries: bool,
+) {
+    // A separator is drawn once an entry follows it
+    let mut drawn_any = after_entries;
+    let mut pending_separator = false;
+    for item in items.iter().filter(|item| item.is_shown(target)) {
+        if matches!(item.kind, ContextMenuKind::Separator) {
+            pending_separator = drawn_any;
+            continue;
+        }
+        if pending_separator {
+            ui.separator();
+            pending_separator = false;
+        }
+        drawn_any = true;
+        let enabled = (item.enabled)(target);
+        match &item.kind {
+            ContextMenuKind::Action(on_click) => {
+                if ui
+                    .add_enabled(enabled, egui::Button::new(item.text()))
+                    .clicked()
+                {
+                    on_click(target);
+                    ui.close();
+                }
+            }
+            ContextMenuKind::Check { checked, on_toggle } => {
+                let mut value = checked(target);
+                if ui
+                    .add_enabled(enabled, egui::Checkbox::new(&mut value, item.text()))
+                    .clicked()
+                {
+                    on_toggle(target, value);
+                    ui.close();
+                }
+            }
+            ContextMenuKind::Submenu(children) => {
+                ui.add_enabled_ui(enabled, |ui| {
+                    ui.menu_button(item.text(), |ui| {
+                        show_context_menu_items(ui, children, target, false);
+                    });
+                });
+            }
+            ContextMenuKind::Separator => {}
+        }
+    }
+}
@@ -0,0 +1,284 @@
+//! Side-by-side comparison of two versions of a model.
+//!
+//! [`DiffView`] shows one system level of a [`SystemDiff`] as two
+//! [`SystemView`]s, the old model on the left and the new one on the right,
+//! fitted to the same area so zooming or panning either moves both. Blocks
+//! are colored as in the HTML report: added green, removed red, modified
+//! amber. The change list on the side opens the level of a change and
+//! centers its block; Previous and Next step through the changes.
+
+use eframe::egui::{self, Color32, Rect, RichText};
+
+use super::system_view::{SystemView, SystemViewState, system_bounds};
+use super::ui::colors::rgba_to_color32;
+use crate::block_path::BlockPath;
+use crate::diff::{ADDED_COLOR, ChangeKind, MODIFIED_COLOR, REMOVED_COLOR, SystemDiff};
+use crate::model::System;
+use crate::overlay::block_path;
+
+/// Two-pane view of a [`SystemDiff`].
+pub struct DiffView {
+    diff: SystemDiff,
+    /// Path of the shown level in the new model; empty for the root.
+    pub system: String,
+    /// Index into the diff's `blocks` of the selected change.
+    pub selected: Option<usize>,
+    /// Center the selected block once the views know their size.
+    focus_pending: bool,
+    id: egui::Id,
+}
+
+impl DiffView {
+    /// Show `diff`, starting at its first changed level.
+    pub fn new(diff: SystemDiff) -> Self {
+        let system = diff
+            .changed_systems()
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        Self {
+            diff,
+            system,
+            selected: None,
+            focus_pending: false,
+            id: egui::Id::new("rustylink_diff_view"),
+        }
+    }
+
+    pub fn diff(&self) -> &SystemDiff {
+        &self.diff
+    }
+
+    /// Select the block change at `index`, open its level and center it.
+    pub fn select(&mut self, index: usize) {
+        let Some(change) = self.diff.blocks.get(index) else {
+            return;
+        };
+        let system = change.system();
+        if system != self.system {
+            self.system = system;
+            self.selected = None;
+        }
+        if self.selected != Some(index) {
+            self.selected = Some(index);
+            self.focus_pending = true;
+        }
+    }
+
+    /// Select the change after the selected one, wrapping around.
+    pub fn select_next(&mut self) {
+        let count = self.diff.blocks.len();
+        if count > 0 {
+            self.select(self.selected.map_or(0, |i| (i + 1) % count));
+        }
+    }
+
+    /// Select the change before the selected one, wrapping around.
+    pub fn select_previous(&mut self) {
+        let count = self.diff.blocks.len();
+        if count > 0 {
+            self.select(self.selected.map_or(count - 1, |i| (i + count - 1) % count));
+        }
+    }
+
+    /// The shown level in the old and in the new model.
+    pub fn levels(&self) -> (Option<&System>, Option<&System>) {
+        let old: BlockPath = self
+            .diff
+            .old_system_path(&self.system)
+            .parse()
+            .unwrap_or_default();
+        let new: BlockPath = self.system.parse().unwrap_or_default();
+        (
+            self.diff.old_model().system_at(&old),
+            self.diff.new_model().system_at(&new),
+        )
+    }
+
+    pub fn show(&mut self, ui: &mut egui::Ui) {
+        egui::TopBottomPanel::top("rustylink_diff_toolbar").show_inside(ui, |ui| {
+            self.toolbar(ui);
+        });
+        egui::SidePanel::left("rustylink_diff_changes")
+            .resizable(true)
+            .default_width(260.0)
+            .show_inside(ui, |ui| {
+                egui::ScrollArea::vertical().show(ui, |ui| self.change_list(ui));
+            });
+        egui::CentralPanel::default().show_inside(ui, |ui| self.diagrams(ui));
+    }
+
+    fn toolbar(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            let count = |kind| self.diff.blocks.iter().filter(|c| c.kind == kind).count();
+            ui.label(format!(
+                "{} added, {} removed, {} modified blocks; {} connection changes",
+                count(ChangeKind::Added),
+                count(ChangeKind::Removed),
+                count(ChangeKind::Modified),
+                self.diff.connections.len()
+            ));
+            ui.separator();
+            let has_changes = !self.diff.blocks.is_empty();
+            if ui
+                .add_enabled(has_changes, egui::Button::new("⬅ Previous"))
+                .clicked()
+            {
+                self.select_previous();
+            }
+            if ui
+                .add_enabled(has_changes, egui::Button::new("Next ➡"))
+                .clicked()
+            {
+                self.select_next();
+            }
+            ui.separator();
+            let mut chosen = None;
+            egui::ComboBox::from_id_salt("rustylink_diff_level")
+                .selected_text(level_title(&self.system))
+                .show_ui(ui, |ui| {
+                    for system in self.diff.changed_systems() {
+                        let selected = system == self.system;
+                        if ui
+                            .selectable_label(selected, level_title(&system))
+                            .clicked()
+                        {
+                            chosen = Some(system);
+                        }
+                    }
+                });
+            if let Some(system) = chosen {
+                self.system = system;
+                self.selected = None;
+                SystemViewState::default().store(ui.ctx(), self.id);
+            }
+            if ui.button("Fit").clicked() {
+                SystemViewState::default().store(ui.ctx(), self.id);
+            }
+        });
+    }
+
+    fn change_list(&mut self, ui: &mut egui::Ui) {
+        if self.diff.is_empty() {
+            ui.label("No differences.");
+            return;
+        }
+        let mut clicked = None;
+        for (i, change) in self.diff.blocks.iter().enumerate() {
+            let text = RichText::new(format!("● {}", change.path)).color(kind_color(change.kind));
+            let resp = ui.selectable_label(self.selected == Some(i), text);
+            if resp.clicked() {
+                clicked = Some(i);
+            }
+            if !change.parameters.is_empty() {
+                resp.on_hover_ui(|ui| {
+                    for p in &change.parameters {
+                        ui.label(format!(
+                            "{}: {} → {}",
+                            p.name,
+                            p.old.as_deref().unwrap_or("—"),
+                            p.new.as_deref().unwrap_or("—")
+                        ));
+                    }
+                });
+            }
+        }
+        if let Some(i) = clicked {
+            self.select(i);
+        }
+        let connections: Vec<_> = self
+            .diff
+            .connections
+            .iter()
+            .filter(|c| c.system == self.system)
+            .collect();
+        if !connections.is_empty() {
+            ui.separator();
+            ui.label(RichText::new("Connections").strong());
+            for c in connections {
+                ui.colored_label(kind_color(c.kind), format!("{} → {}", c.src, c.dst));
+            }
+        }
+    }
+
+    fn diagrams(&mut self, ui: &mut egui::Ui) {
+        let (old, new) = self.levels();
+        let bounds = [old, new]
+            .into_iter()
+            .flatten()
+            .filter_map(system_bounds)
+            .reduce(|a, b| a.union(b));
+        let old_path = self.diff.old_system_path(&self.system).to_string();
+        let selected = self.selected.and_then(|i| self.diff.blocks.get(i));
+        // Side to center the selected block on and its name there.
+        let focus = selected.map(|change| match change.kind {
+            ChangeKind::Removed => (0, change.old_path.as_ref().unwrap_or(&change.path)),
+            _ => (1, &change.path),
+        });
+        let mut focus_rect: Option<(Rect, Rect)> = None;
+        ui.columns(2, |cols| {
+            for (col, (label, level, level_path, side)) in [
+                ("Old", old, old_path.as_str(), ChangeKind::Removed),
+                ("New", new, self.system.as_str(), ChangeKind::Added),
+            ]
+            .into_iter()
+            .enumerate()
+            {
+                let ui = &mut cols[col];
+                ui.label(RichText::new(label).strong());
+                let Some(level) = level else {
+                    ui.label("Not present.");
+                    continue;
+                };
+                let path = level_path
+                    .parse::<BlockPath>()
+                    .unwrap_or_default()
+                    .into_segments();
+                let highlights = self.diff.highlights(&self.system, side);
+                let mut view = SystemView::new(level, self.id);
+                if let Some(bounds) = bounds {
+                    view = view.bounds(bounds);
+                }
+                let mut focused = None;
+                for (i, b) in level.blocks.iter().enumerate() {
+                    let p = block_path(&path, &b.name);
+                    if let Some(color) = highlights.get(&p) {
+                        view = view.highlight_block(i, rgba_to_color32(*color));
+                    }
+                    if focus.is_some_and(|(c, f)| c == col && *f == p) {
+                        focused = super::geometry::parse_block_rect(b);
+                    }
+                }
+                let shown = view.show(ui);
+                if let Some(r) = focused {
+                    let screen = Rect::from_min_max(
+                        shown.transform.to_screen(r.min),
+                        shown.transform.to_screen(r.max),
+                    );
+                    focus_rect = Some((screen, shown.response.rect));
+                }
+            }
+        });
+        if self.focus_pending {
+            self.focus_pending = false;
+            if let Some((block, view)) = focus_rect {
+                let mut state = SystemViewState::load(ui.ctx(), self.id);
+                state.pan += view.center() - block.center();
+                state.store(ui.ctx(), self.id);
+                ui.ctx().request_repaint();
+            }
+        }
+    }
+}
+
+fn level_title(system: &str) -> &str {
+    if system.is_empty() { "(root)" } else { system }
+}
+
+fn kind_color(kind: ChangeKind) -> Color32 {
+    rgba_to_color32(match kind {
+        ChangeKind::Added => ADDED_COLOR,
+        ChangeKind::Removed => REMOVED_COLOR,
+        ChangeKind::Modified => MODIFIED_COLOR,
+    })
+}
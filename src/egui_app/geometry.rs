@@ -1,7 +1,9 @@
 #![cfg(feature = "egui")]
 
-use crate::model::{Block, EndpointRef};
-use eframe::egui::{Pos2, Rect};
+use crate::block_types::BlockTypeConfig;
+use crate::builtin_libraries::virtual_library::{BlockShape, PortPlacement, PortPositionOverride};
+use crate::model::{Block, BlockOrientation, EndpointRef, PortKind, SumParams, SumSign};
+use eframe::egui::{Pos2, Rect, Vec2};
 
 /// Side of a block where a port resides.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -10,6 +12,62 @@ pub enum PortSide {
     Out,
 }
 
+/// How a block's ports are laid out on screen: `BlockMirror` plus rotation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PortFrame {
+    pub mirrored: bool,
+    pub orientation: BlockOrientation,
+}
+
+impl PortFrame {
+    pub fn of(b: &Block) -> Self {
+        Self {
+            mirrored: b.block_mirror.unwrap_or(false),
+            orientation: b.orientation,
+        }
+    }
+
+    /// `true` when inputs sit on the right edge of a horizontal block
+    /// (mirrored, or rotated by 180°, but not both).
+    pub fn flips_sides(self) -> bool {
+        self.mirrored ^ (self.orientation == BlockOrientation::Left)
+    }
+
+    pub fn is_vertical(self) -> bool {
+        self.orientation.is_vertical()
+    }
+
+    /// Block edge carrying ports of `kind`: control ports on the top edge
+    /// and the state port on the bottom edge, turned with the block;
+    /// everything else on the input or output edge.
+    pub fn placement_of(self, kind: &PortKind) -> PortPlacement {
+        if kind.is_control() {
+            rotate_placement(PortPlacement::Top, self.orientation)
+        } else if *kind == PortKind::State {
+            rotate_placement(PortPlacement::Bottom, self.orientation)
+        } else {
+            self.placement(kind.is_input())
+        }
+    }
+
+    /// Block edge carrying the inputs (`is_input`) or outputs.
+    ///
+    /// Mirroring reverses the signal flow along the block's rotated axis.
+    pub fn placement(self, is_input: bool) -> PortPlacement {
+        let upstream = is_input ^ self.mirrored;
+        match (self.orientation, upstream) {
+            (BlockOrientation::Right, true) | (BlockOrientation::Left, false) => {
+                PortPlacement::Left
+            }
+            (BlockOrientation::Right, false) | (BlockOrientation::Left, true) => {
+                PortPlacement::Right
+            }
+            (BlockOrientation::Down, true) | (BlockOrientation::Up, false) => PortPlacement::Top,
+            (BlockOrientation::Down, false) | (BlockOrientation::Up, true) => PortPlacement::Bottom,
+        }
+    }
+}
+
 /// Parse the block rectangle from a Simulink block's `Position` property.
 /// Expects a string of the form "[l, t, r, b]".
 pub fn parse_block_rect(b: &Block) -> Option<Rect> {
@@ -52,6 +110,27 @@ pub fn port_anchor_pos(r: Rect, side: PortSide, port_index: u32, num_ports: Opti
     }
 }
 
+/// Compute a port anchor position on the given edge of a block's rectangle.
+///
+/// Ports are numbered top-to-bottom on the left/right edges and
+/// left-to-right on the top/bottom edges.
+pub fn port_anchor_on_edge(
+    r: Rect,
+    placement: PortPlacement,
+    port_index: u32,
+    num_ports: Option<u32>,
+) -> Pos2 {
+    let idx1 = if port_index == 0 { 1 } else { port_index };
+    let n = num_ports.unwrap_or(idx1).max(idx1);
+    let f = ((2 * idx1) as f32 - 0.5) / ((n * 2 + 1) as f32);
+    match placement {
+        PortPlacement::Left => Pos2::new(r.left(), r.top() + f * r.height()),
+        PortPlacement::Right => Pos2::new(r.right(), r.top() + f * r.height()),
+        PortPlacement::Top => Pos2::new(r.left() + f * r.width(), r.top()),
+        PortPlacement::Bottom => Pos2::new(r.left() + f * r.width(), r.bottom()),
+    }
+}
+
 /// Determine the port side on screen for a given endpoint type, considering mirroring.
 pub fn port_side_for(port_type: &str, mirrored: bool) -> PortSide {
     match (port_type, mirrored) {
@@ -68,10 +147,286 @@ pub fn endpoint_pos_maybe_mirrored(
     num_ports: Option<u32>,
     mirrored: bool,
 ) -> Pos2 {
-    let side = port_side_for(&ep.port_type, mirrored);
+    let side = port_side_for(ep.port_type.as_str(), mirrored);
     port_anchor_pos(r, side, ep.port_index, num_ports)
 }
 
+/// Compute endpoint position considering both BlockMirror and block rotation.
+///
+/// Control and state ports sit in the middle of their edge; `num_ports`
+/// only spreads data ports.
+pub fn endpoint_pos_in_frame(
+    r: Rect,
+    ep: &EndpointRef,
+    num_ports: Option<u32>,
+    frame: PortFrame,
+) -> Pos2 {
+    let placement = frame.placement_of(&ep.port_type);
+    if ep.port_type.is_control() || ep.port_type == PortKind::State {
+        return port_anchor_on_edge(r, placement, 1, Some(1));
+    }
+    port_anchor_on_edge(r, placement, ep.port_index, num_ports)
+}
+
+/// How a block spreads its data ports over its outline, chosen from the
+/// shape of its block type.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum AnchorStrategy {
+    /// Ports spread over the input and output edges (see
+    /// [`port_anchor_on_edge`]), honouring the block type's overrides.
+    #[default]
+    Edges,
+    /// Bars and trapezoids such as bus blocks, Mux and Demux: ports split
+    /// the edge into equal parts.
+    Bar,
+    /// Round Sum blocks: one input slot per character of the sign list,
+    /// spread over the left half of the outline from top to bottom, where
+    /// `|` leaves its slot empty. The output sits on the right.
+    Round {
+        /// `true` for slots holding a port.
+        slots: Vec<bool>,
+    },
+}
+
+impl AnchorStrategy {
+    /// Strategy for `block` drawn with `shape`. Sum blocks set to
+    /// `IconShape` `rectangular` use the edges.
+    pub fn for_block(block: &Block, shape: BlockShape) -> Self {
+        match shape {
+            BlockShape::Circle => {
+                let params = SumParams::from_block(block);
+                if params.rectangular {
+                    return Self::Edges;
+                }
+                Self::Round {
+                    slots: params.signs.iter().map(|s| *s != SumSign::Spacer).collect(),
+                }
+            }
+            BlockShape::FilledBlack | BlockShape::Trapezoid => Self::Bar,
+            _ => Self::Edges,
+        }
+    }
+}
+
+/// Port positions of one block: its frame, anchor strategy, the
+/// placement overrides of its block type and its control ports.
+#[derive(Debug, Clone, Default)]
+pub struct PortAnchors {
+    pub frame: PortFrame,
+    pub strategy: AnchorStrategy,
+    pub overrides: Vec<PortPositionOverride>,
+    /// Control ports spread over the top edge, see [`Block::control_ports`].
+    pub controls: Vec<PortKind>,
+}
+
+impl PortAnchors {
+    pub fn new(block: &Block, cfg: &BlockTypeConfig) -> Self {
+        let strategy = AnchorStrategy::for_block(block, cfg.shape);
+        // Overrides describe the edge layout
+        let overrides = match strategy {
+            AnchorStrategy::Edges => cfg.port_position_overrides.clone(),
+            _ => Vec::new(),
+        };
+        Self {
+            frame: PortFrame::of(block),
+            strategy,
+            overrides,
+            controls: block.control_ports(),
+        }
+    }
+
+    /// Anchors of `block` with its registered block type configuration.
+    pub fn of(block: &Block) -> Self {
+        Self::new(block, &super::render::get_block_type_cfg(block))
+    }
+
+    /// Position of data port `port_index` (1-based) of `count` inputs or
+    /// outputs on block rectangle `r`.
+    pub fn port_pos(&self, r: Rect, is_input: bool, port_index: u32, count: Option<u32>) -> Pos2 {
+        let idx1 = port_index.max(1);
+        if let Some(ovr) = self
+            .overrides
+            .iter()
+            .find(|o| o.is_input == is_input && o.port_index == idx1)
+        {
+            let placement = rotate_placement(ovr.placement, self.frame.orientation);
+            return placement_pos(r, placement, ovr.fraction);
+        }
+        let placement = self.frame.placement(is_input);
+        match &self.strategy {
+            AnchorStrategy::Edges => port_anchor_on_edge(r, placement, idx1, count),
+            AnchorStrategy::Bar => {
+                let n = count.unwrap_or(idx1).max(idx1);
+                placement_pos(r, placement, (idx1 as f32 - 0.5) / n as f32)
+            }
+            AnchorStrategy::Round { slots } => {
+                if !is_input {
+                    return self.on_outline(r, Vec2::new(1.0, 0.0));
+                }
+                let Some(slot) = slots
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, used)| **used)
+                    .nth(idx1 as usize - 1)
+                    .map(|(i, _)| i)
+                else {
+                    return port_anchor_on_edge(r, placement, idx1, count);
+                };
+                // Counterclockwise from the top (90°) to the bottom (270°)
+                let angle = if slots.len() > 1 {
+                    std::f32::consts::FRAC_PI_2
+                        + std::f32::consts::PI * slot as f32 / (slots.len() - 1) as f32
+                } else {
+                    std::f32::consts::PI
+                };
+                self.on_outline(r, Vec2::new(angle.cos(), -angle.sin()))
+            }
+        }
+    }
+
+    /// Position of the port `ep` refers to on block rectangle `r`; `count`
+    /// is the number of ports of its kind, for data ports.
+    pub fn endpoint_pos(&self, r: Rect, ep: &EndpointRef, count: Option<u32>) -> Pos2 {
+        match &ep.port_type {
+            PortKind::In | PortKind::Out => {
+                self.port_pos(r, ep.port_type.is_input(), ep.port_index, count)
+            }
+            kind if kind.is_control() => {
+                let i = self.controls.iter().position(|k| k == kind).unwrap_or(0);
+                self.control_pos(r, kind, i)
+            }
+            _ => endpoint_pos_in_frame(r, ep, count, self.frame),
+        }
+    }
+
+    /// The control ports with their positions on block rectangle `r`.
+    pub fn control_positions(&self, r: Rect) -> Vec<(PortKind, Pos2)> {
+        self.controls
+            .iter()
+            .enumerate()
+            .map(|(i, kind)| (kind.clone(), self.control_pos(r, kind, i)))
+            .collect()
+    }
+
+    fn control_pos(&self, r: Rect, kind: &PortKind, i: usize) -> Pos2 {
+        let count = self.controls.len().max(1) as u32;
+        port_anchor_on_edge(r, self.frame.placement_of(kind), i as u32 + 1, Some(count))
+    }
+
+    /// Port indicator positions for `in_count` inputs and `out_count`
+    /// outputs; see [`port_indicator_positions_in_frame`].
+    pub fn indicator_positions(
+        &self,
+        r: Rect,
+        in_count: u32,
+        out_count: u32,
+    ) -> (Vec<Pos2>, Vec<Pos2>) {
+        let ins = (1..=in_count)
+            .map(|i| self.port_pos(r, true, i, Some(in_count)))
+            .collect();
+        let outs = (1..=out_count)
+            .map(|i| self.port_pos(r, false, i, Some(out_count)))
+            .collect();
+        (ins, outs)
+    }
+
+    /// Point of the ellipse inscribed in `r` in direction `dir` of the
+    /// unrotated block, mirrored and turned with the block.
+    fn on_outline(&self, r: Rect, dir: Vec2) -> Pos2 {
+        let dir = if self.frame.mirrored {
+            Vec2::new(-dir.x, dir.y)
+        } else {
+            dir
+        };
+        let dir = match self.frame.orientation {
+            BlockOrientation::Right => dir,
+            BlockOrientation::Down => Vec2::new(-dir.y, dir.x),
+            BlockOrientation::Left => -dir,
+            BlockOrientation::Up => Vec2::new(dir.y, -dir.x),
+        };
+        r.center() + dir * r.size() / 2.0
+    }
+}
+
+/// Polyline of the symbol drawn inside a block next to the control port at
+/// `anchor` on edge `placement`: a pulse for enable ports and a rising edge
+/// for trigger ports, `size` wide. `None` for other ports.
+pub fn control_port_symbol(
+    kind: &PortKind,
+    anchor: Pos2,
+    placement: PortPlacement,
+    size: f32,
+) -> Option<Vec<Pos2>> {
+    let shape: &[(f32, f32)] = match kind {
+        PortKind::Enable => &[
+            (-0.5, 0.25),
+            (-0.25, 0.25),
+            (-0.25, -0.25),
+            (0.25, -0.25),
+            (0.25, 0.25),
+            (0.5, 0.25),
+        ],
+        PortKind::Trigger => &[(-0.5, 0.25), (0.0, 0.25), (0.0, -0.25), (0.5, -0.25)],
+        _ => return None,
+    };
+    let inward = match placement {
+        PortPlacement::Left => Vec2::X,
+        PortPlacement::Right => -Vec2::X,
+        PortPlacement::Top => Vec2::Y,
+        PortPlacement::Bottom => -Vec2::Y,
+    };
+    let center = anchor + inward * size * 0.5;
+    Some(
+        shape
+            .iter()
+            .map(|(x, y)| center + Vec2::new(*x, *y) * size)
+            .collect(),
+    )
+}
+
+/// Corners of the outline of `block` drawn with `cfg` in `r`, for the
+/// polygonal shapes: a triangle pointing at the output edge, or a trapezoid
+/// whose edge with fewer ports is half as long. `None` for shapes drawn as
+/// rectangles or circles.
+pub fn block_outline(block: &Block, cfg: &BlockTypeConfig, r: Rect) -> Option<Vec<Pos2>> {
+    let frame = PortFrame::of(block);
+    let counts = block.port_counts.as_ref();
+    let ins = counts.and_then(|pc| pc.ins).unwrap_or(cfg.default_ins);
+    let outs = counts.and_then(|pc| pc.outs).unwrap_or(cfg.default_outs);
+    let (narrow, inset) = match cfg.shape {
+        BlockShape::Triangle => (frame.placement(false), None),
+        BlockShape::Trapezoid => (frame.placement(outs > ins), Some(0.25)),
+        _ => return None,
+    };
+    let (a, b) = edge_corners(r, opposite(narrow));
+    let (c, d) = edge_corners(r, narrow);
+    let mut points = vec![a, b];
+    match inset {
+        Some(inset) => points.extend([d.lerp(c, inset), c.lerp(d, inset)]),
+        None => points.push(c.lerp(d, 0.5)),
+    }
+    Some(points)
+}
+
+/// End points of an edge of `r`, top before bottom and left before right.
+fn edge_corners(r: Rect, edge: PortPlacement) -> (Pos2, Pos2) {
+    match edge {
+        PortPlacement::Left => (r.left_top(), r.left_bottom()),
+        PortPlacement::Right => (r.right_top(), r.right_bottom()),
+        PortPlacement::Top => (r.left_top(), r.right_top()),
+        PortPlacement::Bottom => (r.left_bottom(), r.right_bottom()),
+    }
+}
+
+fn opposite(edge: PortPlacement) -> PortPlacement {
+    match edge {
+        PortPlacement::Left => PortPlacement::Right,
+        PortPlacement::Right => PortPlacement::Left,
+        PortPlacement::Top => PortPlacement::Bottom,
+        PortPlacement::Bottom => PortPlacement::Top,
+    }
+}
+
 /// Compute the positions of port indicators to draw for a block.
 ///
 /// These indicators are purely visual (useful even when the model has no
@@ -97,38 +452,62 @@ pub fn port_indicator_positions_with_overrides(
     mirrored: bool,
     overrides: &[crate::builtin_libraries::virtual_library::PortPositionOverride],
 ) -> (Vec<Pos2>, Vec<Pos2>) {
-    let (in_side, out_side) = if mirrored {
-        (PortSide::Out, PortSide::In)
-    } else {
-        (PortSide::In, PortSide::Out)
+    let frame = PortFrame {
+        mirrored,
+        orientation: BlockOrientation::Right,
     };
+    port_indicator_positions_in_frame(r, in_count, out_count, frame, overrides)
+}
+
+/// Like [`port_indicator_positions_with_overrides`], for a rotated block.
+///
+/// Overrides are specified for the unrotated block and are turned with it.
+pub fn port_indicator_positions_in_frame(
+    r: Rect,
+    in_count: u32,
+    out_count: u32,
+    frame: PortFrame,
+    overrides: &[crate::builtin_libraries::virtual_library::PortPositionOverride],
+) -> (Vec<Pos2>, Vec<Pos2>) {
+    let in_side = frame.placement(true);
+    let out_side = frame.placement(false);
 
     let mut ins = Vec::new();
     for i in 1..=in_count {
         if let Some(ovr) = overrides.iter().find(|o| o.is_input && o.port_index == i) {
-            ins.push(placement_pos(r, ovr.placement, ovr.fraction));
+            let placement = rotate_placement(ovr.placement, frame.orientation);
+            ins.push(placement_pos(r, placement, ovr.fraction));
         } else {
-            ins.push(port_anchor_pos(r, in_side, i, Some(in_count.max(1))));
+            ins.push(port_anchor_on_edge(r, in_side, i, Some(in_count.max(1))));
         }
     }
     let mut outs = Vec::new();
     for i in 1..=out_count {
         if let Some(ovr) = overrides.iter().find(|o| !o.is_input && o.port_index == i) {
-            outs.push(placement_pos(r, ovr.placement, ovr.fraction));
+            let placement = rotate_placement(ovr.placement, frame.orientation);
+            outs.push(placement_pos(r, placement, ovr.fraction));
         } else {
-            outs.push(port_anchor_pos(r, out_side, i, Some(out_count.max(1))));
+            outs.push(port_anchor_on_edge(r, out_side, i, Some(out_count.max(1))));
         }
     }
     (ins, outs)
 }
 
+/// Turn a block edge clockwise by the block's rotation.
+pub fn rotate_placement(placement: PortPlacement, orientation: BlockOrientation) -> PortPlacement {
+    const CLOCKWISE: [PortPlacement; 4] = [
+        PortPlacement::Left,
+        PortPlacement::Top,
+        PortPlacement::Right,
+        PortPlacement::Bottom,
+    ];
+    let start = CLOCKWISE.iter().position(|p| *p == placement).unwrap_or(0);
+    let steps = (orientation.rotation_degrees() / 90) as usize;
+    CLOCKWISE[(start + steps) % 4]
+}
+
 /// Convert a [`PortPlacement`] + fraction to a concrete position on a block rect.
-fn placement_pos(
-    r: Rect,
-    placement: crate::builtin_libraries::virtual_library::PortPlacement,
-    fraction: f32,
-) -> Pos2 {
-    use crate::builtin_libraries::virtual_library::PortPlacement;
+fn placement_pos(r: Rect, placement: PortPlacement, fraction: f32) -> Pos2 {
     let f = fraction.clamp(0.0, 1.0);
     match placement {
         PortPlacement::Left => Pos2::new(r.left(), r.top() + f * r.height()),
@@ -147,11 +526,7 @@ fn placement_pos(
 /// For standard Left/Right placements the result is the same as the
 /// `is_left_side` flag used for the default layout.  For Top/Bottom
 /// overrides the value is always `true` (the chevron faces inward).
-pub fn port_override_is_left_side(
-    placement: crate::builtin_libraries::virtual_library::PortPlacement,
-    _mirrored: bool,
-) -> bool {
-    use crate::builtin_libraries::virtual_library::PortPlacement;
+pub fn port_override_is_left_side(placement: PortPlacement, _mirrored: bool) -> bool {
     match placement {
         PortPlacement::Left => true,
         PortPlacement::Right => false,
@@ -0,0 +1,247 @@
+//! Loading models for display.
+//!
+//! [`load_model`] parses an `.slx` archive or a system XML file, resolves
+//! library references against a list of search directories and collects the
+//! Stateflow charts, producing everything [`super::SubsystemApp::new`] needs
+//! plus a report of the libraries and library blocks that could not be found.
+//! [`load_model_with_progress`] additionally reports parse progress and can
+//! be cancelled, for loading on a background thread.
+
+use crate::cancel::CancellationToken;
+use crate::model::{Chart, System};
+use crate::parser::{
+    ContentSource, FsSource, LibraryResolver, ParseProgress, ParserOptions, SimulinkParser,
+    ZipSource, is_virtual_library,
+};
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::mpsc::Sender;
+
+/// A library block a model links to that could not be resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnresolvedBlock {
+    pub library: String,
+    /// Block path inside the library.
+    pub block: String,
+    /// Path of the linking block in the model, starting with `/`.
+    pub host: String,
+    /// Whether the library file itself is missing (rather than the block).
+    pub library_missing: bool,
+}
+
+/// A parsed model ready to be shown.
+#[derive(Debug, Clone)]
+pub struct LoadedModel {
+    pub path: Utf8PathBuf,
+    pub root: System,
+    pub charts: BTreeMap<u32, Chart>,
+    pub chart_map: BTreeMap<String, u32>,
+    /// Directories searched for libraries.
+    pub library_search_paths: Vec<Utf8PathBuf>,
+    /// Referenced libraries not found in the search paths.
+    pub missing_libraries: Vec<String>,
+    pub unresolved_blocks: Vec<UnresolvedBlock>,
+    /// Raw file contents, used to key the viewer session.
+    pub contents: Vec<u8>,
+}
+
+/// Library search paths for a model: its own directory followed by `extra`.
+pub fn library_search_paths(path: &Utf8Path, extra: &[Utf8PathBuf]) -> Vec<Utf8PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(parent) = path.parent()
+        && !parent.as_str().is_empty()
+    {
+        paths.push(parent.to_path_buf());
+    }
+    paths.extend(extra.iter().cloned());
+    paths
+}
+
+/// Parse the model at `path` (`.slx` or system XML) and resolve its library
+/// links against `lib_paths`.
+pub fn load_model(path: &Utf8Path, lib_paths: &[Utf8PathBuf]) -> Result<LoadedModel> {
+    load_model_with_progress(path, lib_paths, None, &CancellationToken::new())
+}
+
+/// [`load_model`], sending parse progress to `progress`. Parsing and library
+/// resolution stop with a [`Cancelled`](crate::cancel::Cancelled) error once
+/// `cancel` is cancelled.
+pub fn load_model_with_progress(
+    path: &Utf8Path,
+    lib_paths: &[Utf8PathBuf],
+    progress: Option<Sender<ParseProgress>>,
+    cancel: &CancellationToken,
+) -> Result<LoadedModel> {
+    let contents = std::fs::read(path).with_context(|| format!("Open {}", path))?;
+    let mut referenced: BTreeSet<String> = BTreeSet::new();
+
+    let options = ParserOptions {
+        cancel: cancel.clone(),
+        ..ParserOptions::default()
+    };
+    let parsed = if path.extension() == Some("slx") {
+        let source = ZipSource::new(std::io::Cursor::new(&contents))?;
+        let parser = SimulinkParser::with_options("", source, options);
+        parse(
+            parser,
+            Utf8Path::new("simulink/systems/system_root.xml"),
+            progress,
+        )
+    } else {
+        parse(
+            SimulinkParser::with_options(".", FsSource, options),
+            path,
+            progress,
+        )
+    };
+    let Parsed {
+        mut root,
+        charts,
+        chart_map,
+        libraries,
+    } = parsed.with_context(|| format!("Failed to parse {}", path))?;
+    referenced.extend(libraries);
+
+    // Includes virtual libraries like `matrix_library`.
+    SimulinkParser::<FsSource>::resolve_library_references_with_cancel(
+        &mut root, lib_paths, cancel,
+    )
+    .context("Failed to resolve library references")?;
+
+    root.walk_blocks(&mut Vec::new(), &mut |_, block| {
+        if let Some((lib, _)) = block
+            .properties
+            .get("SourceBlock")
+            .and_then(|s| s.split_once('/'))
+        {
+            referenced.insert(lib.to_string());
+        }
+    });
+    // Virtual libraries have no file and would be reported as missing.
+    referenced.retain(|lib| !is_virtual_library(lib));
+    let missing_libraries = LibraryResolver::new(lib_paths)
+        .locate(referenced.iter().map(String::as_str))
+        .not_found;
+
+    let mut unresolved_blocks = Vec::new();
+    root.walk_blocks(&mut Vec::new(), &mut |parent, block| {
+        if block.library_block_path.is_some() {
+            return;
+        }
+        let Some((lib, blk)) = block
+            .properties
+            .get("SourceBlock")
+            .and_then(|s| s.split_once('/'))
+        else {
+            return;
+        };
+        let mut host = String::new();
+        for name in parent.iter().chain([&block.name]) {
+            host.push('/');
+            host.push_str(name);
+        }
+        unresolved_blocks.push(UnresolvedBlock {
+            library: lib.to_string(),
+            block: blk.to_string(),
+            host,
+            library_missing: missing_libraries.iter().any(|n| n == lib),
+        });
+    });
+
+    Ok(LoadedModel {
+        path: path.to_path_buf(),
+        root,
+        charts,
+        chart_map,
+        library_search_paths: lib_paths.to_vec(),
+        missing_libraries,
+        unresolved_blocks,
+        contents,
+    })
+}
+
+impl LoadedModel {
+    /// A viewer for this model, showing the subsystem at `path` if it
+    /// exists and the root otherwise.
+    pub fn into_app(self, path: Vec<String>) -> super::SubsystemApp {
+        let path = if super::resolve_subsystem_by_vec(&self.root, &path).is_some() {
+            path
+        } else {
+            Vec::new()
+        };
+        let mut app = super::SubsystemApp::new(self.root, path, self.charts, self.chart_map);
+        app.set_layout_source_path(self.path.clone());
+        // Sessions are keyed by path and contents, so edited models start fresh.
+        app.set_session_source(self.path.as_str(), &self.contents);
+        app.library_search_paths = self.library_search_paths;
+        app
+    }
+
+    /// Print the missing libraries and unresolved library blocks to stderr.
+    pub fn report_unresolved(&self) {
+        if !self.missing_libraries.is_empty() {
+            eprintln!("[rustylink] Libraries referenced by model but NOT found in search paths:");
+            for lib in &self.missing_libraries {
+                eprintln!("  - {}", lib);
+            }
+        }
+        if !self.unresolved_blocks.is_empty() {
+            eprintln!("[rustylink] Blocks referenced from libraries but NOT found:");
+            for u in &self.unresolved_blocks {
+                // Names may contain newlines or tabs.
+                let clean = crate::parser::helpers::clean_whitespace;
+                let reason = if u.library_missing {
+                    "library not found"
+                } else {
+                    "library found but block missing"
+                };
+                eprintln!(
+                    "  - {}/{} referenced by {} ({})",
+                    clean(&u.library),
+                    clean(&u.block),
+                    clean(&u.host),
+                    reason
+                );
+            }
+        }
+    }
+}
+
+/// What [`parse`] gets out of a model.
+struct Parsed {
+    root: System,
+    charts: BTreeMap<u32, Chart>,
+    chart_map: BTreeMap<String, u32>,
+    /// Library names from `graphicalInterface.json`.
+    libraries: Vec<String>,
+}
+
+fn parse<S: ContentSource>(
+    mut parser: SimulinkParser<S>,
+    root_file: &Utf8Path,
+    progress: Option<Sender<ParseProgress>>,
+) -> Result<Parsed> {
+    if let Some(sender) = progress {
+        parser.set_progress_sender(sender);
+    }
+    let root = parser.parse_system_file(root_file)?;
+    let libraries = parser
+        .graphical_interface_library_names("simulink/graphicalInterface.json")
+        .unwrap_or_default();
+    let charts = parser.get_charts().clone();
+    let mut chart_map: BTreeMap<String, u32> = parser
+        .get_sid_to_chart_map()
+        .iter()
+        .map(|(sid, cid)| (sid.to_string(), *cid))
+        .collect();
+    for (name, cid) in parser.get_system_to_chart_map() {
+        chart_map.entry(name.clone()).or_insert(*cid);
+    }
+    Ok(Parsed {
+        root,
+        charts,
+        chart_map,
+        libraries,
+    })
+}
@@ -5,44 +5,66 @@
 
 #![cfg(feature = "egui")]
 
+pub mod bookmarks;
+pub mod context_menu;
 pub mod dashboard_widgets;
+pub mod diff_view;
 mod geometry;
 pub mod icon_assets;
+pub mod loader;
 mod navigation;
+mod paint;
 mod render;
 pub mod scope_widget;
+pub mod session;
 mod state;
+pub mod system_view;
 pub mod text;
 mod ui;
+pub mod viewer;
 
 // Re-export geometry items needed by the editor module
 pub use geometry::{
-    PortSide, endpoint_pos_maybe_mirrored, parse_block_rect, parse_rect_str, port_anchor_pos,
-    port_indicator_positions,
+    AnchorStrategy, PortAnchors, PortFrame, PortSide, block_outline, control_port_symbol,
+    endpoint_pos_in_frame, endpoint_pos_maybe_mirrored, parse_block_rect, parse_rect_str,
+    port_anchor_pos, port_indicator_positions, port_indicator_positions_in_frame,
 };
 pub use navigation::{
-    collect_subsystems_paths, resolve_subsystem_by_path, resolve_subsystem_by_vec,
+    child_subsystems, collect_subsystems_paths, resolve_subsystem_by_path, resolve_subsystem_by_vec,
+};
+pub use render::{
+    get_block_type_cfg, paint_pass_through_arrow, render_block_icon, wrap_text_to_max_width,
 };
-pub use render::{get_block_type_cfg, render_block_icon, wrap_text_to_max_width};
 
 // Helpers which are useful for integration tests
 pub use render::{PortLabelMaxWidths, compute_icon_available_rect};
 // Interior renderer registry access (needed by dashboard visualization tests)
+pub use bookmarks::{Bookmark, BookmarkTarget};
+pub use context_menu::{ContextMenuItem, ContextMenuKind};
+pub use diff_view::DiffView;
 pub use render::{InteriorRendererFn, get_interior_renderer};
 #[cfg(feature = "dashboard")]
 pub use state::ScopePopout;
 pub use state::{
-    BlockContextMenuItem, BlockDialog, BlockDialogButton, ChartView, SignalContextMenuItem,
-    SignalDialog, SignalDialogButton, SubsystemApp, SubsystemEntities,
+    BlockClickHandler, BlockContextMenuItem, BlockDialog, BlockDialogButton, ChartView,
+    LinkHandler, SignalContextMenuItem, SignalDialog, SignalDialogButton, StyleOverride,
+    SubsystemApp, SubsystemEntities,
 };
 #[cfg(feature = "dashboard")]
 pub use state::{DashboardControlEvent, DashboardControlValue};
-pub use text::{highlight_query_job, matlab_syntax_job};
+pub use system_view::{SystemView, SystemViewResponse, SystemViewState, system_bounds};
+pub use text::{
+    CodeStyle, c_syntax_job, c_syntax_job_styled, highlight_query_job, matlab_syntax_job,
+    matlab_syntax_job_styled,
+};
 pub use ui::{
-    ClickAction, UpdateResponse, apply_update_response, show_info_windows, update, update_with_info,
+    ClickAction, UpdateResponse, ViewerEvent, apply_update_response, show_info_windows, update,
+    update_with_info,
 };
 // Expose the canonical color utility module for reuse by the editor.
 pub use ui::colors;
+// Canvas zoom/pan handling shared with the editor.
+pub use ui::view_transform::{CanvasNavigation, ViewTransform, canvas_navigation};
 
 // Expose a couple of internal helpers for use by integration tests.
 pub use ui::helpers::{block_dialog_title, clean_display_string};
@@ -1,29 +1,13 @@
 #![cfg(feature = "egui")]
 
-use crate::model::System;
+use crate::block_path::BlockPath;
+use crate::model::{Block, System};
 
-/// Resolve a subsystem by an absolute path string, e.g. "/Top/Sub".
+/// Resolve a subsystem by an absolute [`BlockPath`] string, e.g. "/Top/Sub".
 /// Returns `Some(&System)` when the path resolves within `root`, otherwise `None`.
 pub fn resolve_subsystem_by_path<'a>(root: &'a System, path: &str) -> Option<&'a System> {
-    let mut cur: &System = root;
-    let p = path.trim();
-    let mut parts = p
-        .trim_start_matches('/')
-        .split('/')
-        .filter(|s| !s.is_empty());
-    for name in parts.by_ref() {
-        let mut found = None;
-        for b in &cur.blocks {
-            if (b.block_type == "SubSystem" || b.block_type == "Reference") && b.name == name {
-                if let Some(sub) = &b.subsystem {
-                    found = Some(sub.as_ref());
-                    break;
-                }
-            }
-        }
-        cur = found?;
-    }
-    Some(cur)
+    let path: BlockPath = path.trim().parse().ok()?;
+    resolve_subsystem_by_vec(root, &path)
 }
 
 /// Resolve a subsystem by a vector of names relative to the `root` system.
@@ -67,4 +51,16 @@ pub fn collect_subsystems_paths(root: &System) -> Vec<Vec<String>> {
     out
 }
 
+/// Subsystem blocks directly inside `system` with their contents, in block
+/// order. Unlike [`collect_subsystems_paths`], charts are included.
+pub fn child_subsystems(system: &System) -> impl Iterator<Item = (&Block, &System)> {
+    system.blocks.iter().filter_map(|b| {
+        if b.block_type == "SubSystem" || b.block_type == "Reference" {
+            b.subsystem.as_deref().map(|sub| (b, sub))
+        } else {
+            None
+        }
+    })
+}
+
 // tests moved to tests/ module
@@ -0,0 +1,155 @@
+//! egui backend of [`DisplayList`].
+
+use eframe::egui::{self, Align2, Pos2, Rect, Shape};
+
+use super::ui::colors::rgba_to_color32;
+use super::ui::view_transform::ViewTransform;
+use crate::display_list::{self, ARROW_LENGTH, Align, DisplayList, Primitive};
+
+impl DisplayList {
+    /// Paint the primitives with `painter`, mapping model coordinates
+    /// through `transform`. The background is left to the caller.
+    pub fn paint(&self, painter: &egui::Painter, transform: &ViewTransform) {
+        let to_screen = |(x, y): display_list::Point| transform.to_screen(Pos2::new(x, y));
+        let screen_rect =
+            |r: &[f32; 4]| Rect::from_two_pos(to_screen((r[0], r[1])), to_screen((r[2], r[3])));
+        for primitive in &self.primitives {
+            match primitive {
+                Primitive::Rect {
+                    rect,
+                    corner_radius,
+                    fill,
+                    stroke,
+                    ..
+                } => {
+                    let r = screen_rect(rect);
+                    if let Some(fill) = fill {
+                        painter.rect_filled(r, *corner_radius, rgba_to_color32(*fill));
+                    }
+                    if let Some(stroke) = stroke {
+                        painter.rect_stroke(
+                            r,
+                            *corner_radius,
+                            egui_stroke(stroke),
+                            egui::StrokeKind::Inside,
+                        );
+                    }
+                }
+                Primitive::GradientRect {
+                    rect,
+                    stops,
+                    stroke,
+                } => {
+                    let r = screen_rect(rect);
+                    let mut mesh = egui::Mesh::default();
+                    for (t, color) in stops {
+                        let x = egui::lerp(r.left()..=r.right(), *t);
+                        let color = rgba_to_color32(*color);
+                        mesh.colored_vertex(Pos2::new(x, r.top()), color);
+                        mesh.colored_vertex(Pos2::new(x, r.bottom()), color);
+                    }
+                    for i in 1..stops.len() as u32 {
+                        let base = 2 * (i - 1);
+                        mesh.add_triangle(base, base + 1, base + 2);
+                        mesh.add_triangle(base + 1, base + 2, base + 3);
+                    }
+                    painter.add(Shape::mesh(mesh));
+                    if let Some(stroke) = stroke {
+                        painter.rect_stroke(r, 0.0, egui_stroke(stroke), egui::StrokeKind::Inside);
+                    }
+                }
+                Primitive::Ellipse { rect, fill, stroke } => {
+                    let r = screen_rect(rect);
+                    let radius = r.size() / 2.0;
+                    if let Some(fill) = fill {
+                        painter.add(Shape::ellipse_filled(
+                            r.center(),
+                            radius,
+                            rgba_to_color32(*fill),
+                        ));
+                    }
+                    if let Some(stroke) = stroke {
+                        painter.add(Shape::ellipse_stroke(
+                            r.center(),
+                            radius,
+                            egui_stroke(stroke),
+                        ));
+                    }
+                }
+                Primitive::Polygon {
+                    points,
+                    fill,
+                    stroke,
+                } => {
+                    let screen: Vec<Pos2> = points.iter().map(|p| to_screen(*p)).collect();
+                    let mut path = egui::epaint::PathShape::closed_line(
+                        screen,
+                        stroke.as_ref().map_or(egui::Stroke::NONE, egui_stroke),
+                    );
+                    path.fill = fill.map_or(egui::Color32::TRANSPARENT, rgba_to_color32);
+                    painter.add(Shape::Path(path));
+                }
+                Primitive::Polyline {
+                    points,
+                    stroke,
+                    arrow,
+                } => {
+                    let screen: Vec<Pos2> = points.iter().map(|p| to_screen(*p)).collect();
+                    let egui_stroke = egui_stroke(stroke);
+                    match stroke.dash {
+                        Some((dash, gap)) => {
+                            painter.extend(Shape::dashed_line(&screen, egui_stroke, dash, gap));
+                        }
+                        None => {
+                            for w in screen.windows(2) {
+                                painter.line_segment([w[0], w[1]], egui_stroke);
+                            }
+                        }
+                    }
+                    let tips: Vec<display_list::Point> = screen
+                        .iter()
+                        .rev()
+                        .take(2)
+                        .rev()
+                        .map(|p| (p.x, p.y))
+                        .collect();
+                    if *arrow && let Some(head) = display_list::arrow_head(&tips, ARROW_LENGTH) {
+                        painter.add(Shape::convex_polygon(
+                            head.iter().map(|(x, y)| Pos2::new(*x, *y)).collect(),
+                            egui_stroke.color,
+                            egui::Stroke::NONE,
+                        ));
+                    }
+                }
+                Primitive::Text {
+                    pos,
+                    offset,
+                    text,
+                    size,
+                    color,
+                    anchor,
+                } => {
+                    painter.text(
+                        to_screen(*pos) + egui::vec2(offset.0, offset.1),
+                        Align2([egui_align(anchor[0]), egui_align(anchor[1])]),
+                        text,
+                        egui::FontId::proportional(size * transform.font_scale()),
+                        rgba_to_color32(*color),
+                    );
+                }
+            }
+        }
+    }
+}
+
+fn egui_stroke(stroke: &display_list::Stroke) -> egui::Stroke {
+    egui::Stroke::new(stroke.width, rgba_to_color32(stroke.color))
+}
+
+fn egui_align(align: Align) -> egui::Align {
+    match align {
+        Align::Min => egui::Align::Min,
+        Align::Center => egui::Align::Center,
+        Align::Max => egui::Align::Max,
+    }
+}
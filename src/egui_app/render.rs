@@ -1,9 +1,10 @@
 #![cfg(feature = "egui")]
 
 use crate::block_types::{self, BlockTypeConfig};
-use crate::model::Block;
+use crate::model::{Block, BlockOrientation, SumParams};
 use eframe::egui::{self, Align2, Color32, Pos2, Rect, Stroke, Vec2};
 
+use super::geometry::{AnchorStrategy, PortAnchors};
 use super::icon_assets;
 use std::collections::HashSet;
 use std::sync::{Arc, Mutex, OnceLock};
@@ -168,7 +169,13 @@ pub(crate) fn port_label_display_name(
         } else {
             &cfg.output_port_names
         };
-        if index > 0 && (index as usize) <= names.len() {
+        let condition = (!logical_is_input && index > 0)
+            .then(|| block.condition_labels())
+            .flatten()
+            .and_then(|labels| labels.into_iter().nth(index as usize - 1));
+        if let Some(label) = condition {
+            label
+        } else if index > 0 && (index as usize) <= names.len() {
             names[(index - 1) as usize].clone()
         } else {
             format!("{}{}", if is_input { "In" } else { "Out" }, index)
@@ -416,9 +423,18 @@ pub fn render_center_glyph_maximized(
     );
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+/// Where an SVG icon's document comes from.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+enum SvgSource {
+    /// Path in the embedded `icons/` assets.
+    Embedded(&'static str),
+    /// User-supplied document; hashed by content.
+    Bytes(Arc<[u8]>),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 struct SvgCacheKey {
-    path: &'static str,
+    source: SvgSource,
     request_w: usize,
     request_h: usize,
 }
@@ -476,12 +492,12 @@ fn svg_dest_size_points(avail_points: Vec2, px_size: [usize; 2], pixels_per_poin
 
 fn get_or_create_svg_texture(
     ctx: &egui::Context,
-    path: &'static str,
+    source: SvgSource,
     request_px: [usize; 2],
 ) -> Option<SvgCachedTexture> {
     let cache_id = egui::Id::new("rustylink_svg_icon_cache");
     let key = SvgCacheKey {
-        path,
+        source,
         request_w: request_px[0],
         request_h: request_px[1],
     };
@@ -498,7 +514,18 @@ fn get_or_create_svg_texture(
         return Some(hit);
     }
 
-    let bytes = icon_assets::get(path)?;
+    let (bytes, texture_name) = match &key.source {
+        SvgSource::Embedded(path) => (icon_assets::get(path)?, path.to_string()),
+        SvgSource::Bytes(bytes) => {
+            use std::hash::{Hash, Hasher};
+            let mut h = std::collections::hash_map::DefaultHasher::new();
+            bytes.hash(&mut h);
+            (
+                std::borrow::Cow::Owned(bytes.to_vec()),
+                format!("user-{:016x}", h.finish()),
+            )
+        }
+    };
     let mut options = resvg::usvg::Options::default();
     // usvg's font database is empty by default; populate it from egui's embedded fonts.
     // This avoids relying on system-installed fonts.
@@ -520,7 +547,10 @@ fn get_or_create_svg_texture(
     let px_size = image.size;
 
     let texture = ctx.load_texture(
-        format!("rustylink_svg:{path}:{}x{}", request_px[0], request_px[1]),
+        format!(
+            "rustylink_svg:{texture_name}:{}x{}",
+            request_px[0], request_px[1]
+        ),
         image,
         egui::TextureOptions::LINEAR,
     );
@@ -536,6 +566,43 @@ fn get_or_create_svg_texture(
     }))
 }
 
+/// Color of the pass-through arrow drawn on commented-through blocks.
+const PASS_THROUGH_COLOR: Color32 = Color32::from_rgb(120, 120, 120);
+
+/// Draw the arrow marking a commented-through block: a gray line across the
+/// block in its signal-flow direction (accounting for rotation and mirroring).
+pub fn paint_pass_through_arrow(
+    painter: &egui::Painter,
+    block: &Block,
+    rect: &Rect,
+    font_scale: f32,
+) {
+    let mirrored = block.block_mirror.unwrap_or(false);
+    let dir = match (block.orientation, mirrored) {
+        (BlockOrientation::Right, false) | (BlockOrientation::Left, true) => Vec2::X,
+        (BlockOrientation::Left, false) | (BlockOrientation::Right, true) => -Vec2::X,
+        (BlockOrientation::Down, false) | (BlockOrientation::Up, true) => Vec2::Y,
+        (BlockOrientation::Up, false) | (BlockOrientation::Down, true) => -Vec2::Y,
+    };
+    let half = if dir.x != 0.0 {
+        rect.width() / 2.0
+    } else {
+        rect.height() / 2.0
+    };
+    let c = rect.center();
+    let (from, to) = (c - dir * half, c + dir * half);
+    let stroke = Stroke::new((1.5 * font_scale).clamp(1.0, 3.0), PASS_THROUGH_COLOR);
+    painter.line_segment([from, to], stroke);
+    let head = (6.0 * font_scale).clamp(3.0, half.max(3.0));
+    let normal = dir.rot90() * head * 0.6;
+    let base = to - dir * head;
+    painter.add(egui::Shape::convex_polygon(
+        vec![to, base + normal, base - normal],
+        PASS_THROUGH_COLOR,
+        Stroke::NONE,
+    ));
+}
+
 /// Emit a one-time-per-block-type warning when no icon can be resolved.
 static ICON_WARNED: OnceLock<Mutex<HashSet<String>>> = OnceLock::new();
 
@@ -556,6 +623,9 @@ fn warn_missing_icon(block_type: &str, block_path: &str) {
 /// The rendered glyph is maximized to fill the available center area while:
 /// - leaving at least 10% margin to the block border on all sides
 /// - avoiding overlap with optional inside-block port labels (left/right)
+///
+/// SVG icons are turned with the block's rotation; text glyphs stay upright,
+/// as in Simulink.
 pub fn render_block_icon(
     painter: &egui::Painter,
     block: &Block,
@@ -580,31 +650,36 @@ pub fn render_block_icon(
                 );
             }
             block_types::IconSpec::Svg(path) => {
+                render_svg_icon(
+                    painter,
+                    block,
+                    rect,
+                    font_scale,
+                    port_label_widths,
+                    SvgSource::Embedded(path),
+                );
+            }
+            block_types::IconSpec::SvgBytes(bytes) => {
+                render_svg_icon(
+                    painter,
+                    block,
+                    rect,
+                    font_scale,
+                    port_label_widths,
+                    SvgSource::Bytes(bytes),
+                );
+            }
+            block_types::IconSpec::Image(texture) => {
                 let avail_rect = compute_icon_available_rect(rect, font_scale, port_label_widths);
-                let avail_points = avail_rect.size();
-                if avail_points.x <= 1.0 || avail_points.y <= 1.0 {
+                let avail_points = icon_frame_size(block, avail_rect);
+                let [w, h] = texture.size();
+                if avail_points.x <= 1.0 || avail_points.y <= 1.0 || w == 0 || h == 0 {
                     return;
                 }
-
-                let ctx = painter.ctx();
-                let pixels_per_point = ctx.pixels_per_point();
-                let request_px = [
-                    (avail_points.x * pixels_per_point).round().max(1.0) as usize,
-                    (avail_points.y * pixels_per_point).round().max(1.0) as usize,
-                ];
-
-                let Some(svg) = get_or_create_svg_texture(ctx, path, request_px) else {
-                    return;
-                };
-
-                let dest_size = svg_dest_size_points(avail_points, svg.px_size, pixels_per_point);
-                if dest_size.x <= 1.0 || dest_size.y <= 1.0 {
-                    return;
-                }
-
-                let dest_rect = Rect::from_center_size(avail_rect.center(), dest_size);
-                let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
-                painter.image(svg.texture.id(), dest_rect, uv, Color32::WHITE);
+                // Scale to fit, keeping the aspect ratio.
+                let scale = (avail_points.x / w as f32).min(avail_points.y / h as f32);
+                let dest_size = Vec2::new(w as f32, h as f32) * scale;
+                paint_icon_texture(painter, block, texture.id(), avail_rect, dest_size);
             }
         }
     } else {
@@ -626,6 +701,72 @@ pub fn render_block_icon(
         render_center_glyph_maximized(painter, rect, font_scale, "?", dark_icon, port_label_widths);
     }
 }
+/// Size of the icon area in the block's own frame; a rotated block swaps
+/// the available width and height.
+fn icon_frame_size(block: &Block, avail_rect: Rect) -> Vec2 {
+    if block.orientation.is_vertical() {
+        Vec2::new(avail_rect.height(), avail_rect.width())
+    } else {
+        avail_rect.size()
+    }
+}
+
+/// Rasterize an SVG icon for the block's current size and paint it.
+fn render_svg_icon(
+    painter: &egui::Painter,
+    block: &Block,
+    rect: &Rect,
+    font_scale: f32,
+    port_label_widths: Option<PortLabelMaxWidths>,
+    source: SvgSource,
+) {
+    let avail_rect = compute_icon_available_rect(rect, font_scale, port_label_widths);
+    let avail_points = icon_frame_size(block, avail_rect);
+    if avail_points.x <= 1.0 || avail_points.y <= 1.0 {
+        return;
+    }
+
+    let ctx = painter.ctx();
+    let pixels_per_point = ctx.pixels_per_point();
+    let request_px = [
+        (avail_points.x * pixels_per_point).round().max(1.0) as usize,
+        (avail_points.y * pixels_per_point).round().max(1.0) as usize,
+    ];
+
+    let Some(svg) = get_or_create_svg_texture(ctx, source, request_px) else {
+        return;
+    };
+
+    let dest_size = svg_dest_size_points(avail_points, svg.px_size, pixels_per_point);
+    if dest_size.x <= 1.0 || dest_size.y <= 1.0 {
+        return;
+    }
+    paint_icon_texture(painter, block, svg.texture.id(), avail_rect, dest_size);
+}
+
+/// Paint a texture of `dest_size` (in the block's frame) centered in
+/// `avail_rect`, turned with the block's rotation.
+fn paint_icon_texture(
+    painter: &egui::Painter,
+    block: &Block,
+    texture_id: egui::TextureId,
+    avail_rect: Rect,
+    dest_size: Vec2,
+) {
+    let dest_rect = Rect::from_center_size(avail_rect.center(), dest_size);
+    let uv = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(1.0, 1.0));
+    let degrees = block.orientation.rotation_degrees();
+    if degrees == 0 {
+        painter.image(texture_id, dest_rect, uv, Color32::WHITE);
+    } else {
+        let mut mesh = egui::Mesh::with_texture(texture_id);
+        mesh.add_rect_with_uv(dest_rect, uv, Color32::WHITE);
+        let rot = egui::emath::Rot2::from_angle((degrees as f32).to_radians());
+        mesh.rotate(rot, dest_rect.center());
+        painter.add(egui::Shape::mesh(mesh));
+    }
+}
+
 /// Screen-space Y coordinates computed for a block's ports (as used by the UI when placing
 /// port labels and clamped within the block rect). Keys are 1-based port indices.
 #[derive(Clone, Debug, Default)]
@@ -885,45 +1026,47 @@ pub fn get_interior_renderer(block_type: &str) -> Option<InteriorRendererFn> {
 ///
 /// The surrounding circle fill and stroke are drawn in the main ui loop's
 /// background-fill and border-stroke passes.  This function only adds the
-/// operator characters at their respective input-port positions inside the
-/// circle – the left-edge operator for input 1 and the bottom-edge operator
-/// for input 2.
-///
-/// The `Inputs` property format used by Simulink is e.g. `|++`: the first
-/// character is an ignored spacer, the subsequent characters are the per-port
-/// operators in order ('+' or '-').
+/// operator characters just inside the block next to their input ports, so
+/// they follow the slots of the `Inputs` sign list around round blocks (see
+/// [`AnchorStrategy::Round`]) and
+/// the input edge of rectangular ones.
 pub fn render_sum_block(painter: &egui::Painter, block: &Block, rect: &Rect, font_scale: f32) {
-    let operators: Vec<char> = block
-        .properties
-        .get("Inputs")
-        .map(|s| s.chars().skip(1).collect())
-        .unwrap_or_default();
-    let left_op = operators.first().copied().unwrap_or('+');
-    let bottom_op = operators.get(1).copied().unwrap_or('+');
-
-    let font_size = (rect.height() * 0.32).clamp(8.0, 22.0) * font_scale;
+    let params = SumParams::from_block(block);
+    let signs = params.port_signs();
+    let anchors = PortAnchors::of(block);
+    let (ins, _) = anchors.indicator_positions(*rect, signs.len() as u32, 1);
+
+    let font_size = (rect.size().min_elem() * 0.32).clamp(8.0, 22.0) * font_scale;
     let color = Color32::from_rgb(32, 32, 32);
     let font_id = egui::FontId::monospace(font_size);
+    let round = matches!(anchors.strategy, AnchorStrategy::Round { .. });
+    for (sign, anchor) in signs.iter().zip(ins) {
+        let inward = if round {
+            (rect.center() - anchor).normalized()
+        } else {
+            edge_normal_inward(rect, anchor)
+        };
+        painter.text(
+            anchor + inward * font_size * 0.8,
+            egui::Align2::CENTER_CENTER,
+            sign.as_char().to_string(),
+            font_id.clone(),
+            color,
+        );
+    }
+}
 
-    // Left input operator – left quarter of the circle, vertically centred
-    let left_pos = Pos2::new(rect.left() + rect.width() * 0.22, rect.center().y);
-    painter.text(
-        left_pos,
-        egui::Align2::CENTER_CENTER,
-        left_op.to_string(),
-        font_id.clone(),
-        color,
-    );
-
-    // Bottom input operator – horizontally centred, bottom quarter of the circle
-    let bottom_pos = Pos2::new(rect.center().x, rect.bottom() - rect.height() * 0.22);
-    painter.text(
-        bottom_pos,
-        egui::Align2::CENTER_CENTER,
-        bottom_op.to_string(),
-        font_id,
-        color,
-    );
+/// Inward normal of the edge of `rect` closest to `p`.
+fn edge_normal_inward(rect: &Rect, p: Pos2) -> Vec2 {
+    [
+        (p.x - rect.left(), Vec2::X),
+        (rect.right() - p.x, -Vec2::X),
+        (p.y - rect.top(), Vec2::Y),
+        (rect.bottom() - p.y, -Vec2::Y),
+    ]
+    .into_iter()
+    .min_by(|a, b| a.0.abs().total_cmp(&b.0.abs()))
+    .map_or(Vec2::X, |(_, n)| n)
 }
 
 // no re-exports; keep this module focused on rendering helpers
@@ -0,0 +1,87 @@
+//! Viewer session persistence.
+//!
+//! A [`ViewerSession`] captures what the user was looking at: the current
+//! subsystem, zoom and pan per visited subsystem, open block/signal dialogs
+//! and recent searches. [`super::SubsystemApp`] stores it as JSON through
+//! `eframe::Storage` under a key derived from the model path and a checksum
+//! of its contents (see [`session_key`]), so reopening an unchanged model
+//! returns to the same place while an edited model starts fresh.
+//!
+//! eframe only provides storage when its `persistence` feature is enabled;
+//! hosts that want sessions to survive restarts enable it on their `eframe`
+//! dependency.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Current on-disk format version.
+pub const SESSION_VERSION: u32 = 1;
+
+/// Prefix of the storage keys used for sessions.
+const STORAGE_PREFIX: &str = "rustylink.session.";
+
+/// Number of search queries remembered.
+pub const SEARCH_HISTORY_LEN: usize = 20;
+
+/// Zoom and pan of one subsystem view.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedView {
+    pub zoom: f32,
+    pub pan: [f32; 2],
+}
+
+/// Persisted viewer state for one model.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ViewerSession {
+    pub version: u32,
+    /// Path of the subsystem shown last.
+    pub path: Vec<String>,
+    /// Views keyed by subsystem path joined with `/` (`""` for the root).
+    pub views: BTreeMap<String, SavedView>,
+    /// SID of the block whose dialog was open, in the subsystem at `path`.
+    pub open_block: Option<String>,
+    /// Index of the line whose signal dialog was open.
+    pub open_signal: Option<usize>,
+    /// Recent search queries, most recent first.
+    pub search_history: Vec<String>,
+}
+
+impl ViewerSession {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// Parse a stored session; sessions from other format versions are
+    /// ignored.
+    pub fn from_json(text: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(text)
+            .ok()
+            .filter(|s| s.version == SESSION_VERSION)
+    }
+}
+
+/// Session key for a model: its path plus a checksum of its contents.
+pub fn session_key(model_path: &str, contents: &[u8]) -> String {
+    // FNV-1a; stable across builds, unlike `DefaultHasher`.
+    let hash = contents.iter().fold(0xcbf2_9ce4_8422_2325u64, |h, &b| {
+        (h ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    });
+    format!("{model_path}#{hash:016x}")
+}
+
+/// `eframe::Storage` key under which the session for `key` is stored.
+pub fn storage_key(key: &str) -> String {
+    format!("{STORAGE_PREFIX}{key}")
+}
+
+/// Add `query` to the front of `history`, dropping duplicates and trimming
+/// it to [`SEARCH_HISTORY_LEN`] entries. Blank queries are ignored.
+pub fn push_search_history(history: &mut Vec<String>, query: &str) {
+    let query = query.trim();
+    if query.is_empty() {
+        return;
+    }
+    history.retain(|q| q != query);
+    history.insert(0, query.to_string());
+    history.truncate(SEARCH_HISTORY_LEN);
+}
@@ -7,8 +7,12 @@ use std::sync::atomic::{AtomicU64, Ordering};
 use camino::Utf8PathBuf;
 use eframe::egui::{self, Vec2};
 
+use crate::annotation_link::{LinkTarget, parse_link_target};
+use crate::block_path::BlockPath;
 use crate::editor::operations::EditorHistory;
-use crate::model::{Annotation, Block, Chart, Line, System};
+use crate::focus_nav::{self, FocusDirection};
+use crate::model::{Annotation, Block, CFunctionCode, Chart, Line, LineId, System};
+use crate::overlay::{MetricOverlay, block_path};
 
 #[derive(Clone, serde::Serialize, serde::Deserialize)]
 struct LayoutSnapshot {
@@ -17,7 +21,13 @@ struct LayoutSnapshot {
 }
 
 // use super::geometry::parse_block_rect;
+pub use super::context_menu::{BlockContextMenuItem, SignalContextMenuItem};
+
+use super::bookmarks::{self, BOOKMARKS_VERSION, Bookmark, BookmarkTarget, SavedBookmarks};
 use super::navigation::{collect_subsystems_paths, resolve_subsystem_by_vec};
+use super::session::{self, SESSION_VERSION, SavedView, ViewerSession};
+use super::text::CodeStyle;
+use super::ui::ViewerEvent;
 // use super::render::get_block_type_cfg;
 // use super::text::highlight_query_job;
 // use crate::label_place::{self};
@@ -26,8 +36,20 @@ use super::navigation::{collect_subsystems_paths, resolve_subsystem_by_vec};
 #[derive(Clone)]
 pub struct ChartView {
     pub title: String,
+    /// The script as edited in the window.
     pub script: String,
     pub open: bool,
+    /// Id of the shown chart in [`SubsystemApp::charts`].
+    pub chart_id: Option<u32>,
+    /// The script when the window opened or was last applied.
+    pub original_script: String,
+}
+
+impl ChartView {
+    /// Returns true if the script has edits not yet applied.
+    pub fn is_modified(&self) -> bool {
+        self.script != self.original_script
+    }
 }
 
 /// Data for a selected signal information dialog.
@@ -62,21 +84,11 @@ pub struct BlockDialogButton {
     pub on_click: Arc<dyn Fn(&Block) + Send + Sync>,
 }
 
-/// Context menu item specification for signals.
-#[derive(Clone)]
-pub struct SignalContextMenuItem {
-    pub label: String,
-    pub filter: Arc<dyn Fn(&crate::model::Line) -> bool + Send + Sync>,
-    pub on_click: Arc<dyn Fn(&crate::model::Line) + Send + Sync>,
-}
+/// Handler for block double-clicks; returns true if it handled the click.
+pub type BlockClickHandler = Arc<dyn Fn(&mut SubsystemApp, &Block) -> bool + Send + Sync>;
 
-/// Context menu item specification for blocks.
-#[derive(Clone)]
-pub struct BlockContextMenuItem {
-    pub label: String,
-    pub filter: Arc<dyn Fn(&Block) -> bool + Send + Sync>,
-    pub on_click: Arc<dyn Fn(&Block) + Send + Sync>,
-}
+/// Handler for annotation links; returns true if it handled the link.
+pub type LinkHandler = Arc<dyn Fn(&mut SubsystemApp, &str) -> bool + Send + Sync>;
 
 /// Snapshot of all entities within the currently displayed subsystem.
 #[derive(Clone)]
@@ -115,6 +127,20 @@ pub struct DashboardControlEvent {
     pub value: DashboardControlValue,
 }
 
+/// Host-provided visual override for one block, drawn on top of its normal
+/// style without touching the model (e.g. coverage results).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct StyleOverride {
+    /// Color blended over the block fill; its alpha sets the strength.
+    pub tint: Option<egui::Color32>,
+    /// Replacement border color.
+    pub border: Option<egui::Color32>,
+    /// Short text (usually one glyph) shown in a badge at the top-right corner.
+    pub badge: Option<String>,
+    /// Badge background; defaults to dark gray.
+    pub badge_color: Option<egui::Color32>,
+}
+
 /// Active drag interaction inside the viewer move mode.
 #[derive(Clone, Default)]
 pub enum ViewerDragState {
@@ -165,10 +191,14 @@ pub struct ComputedViewCache {
     pub generation: u64,
     /// Pre-computed line colors (one per line in the current subsystem).
     pub line_colors: Vec<egui::Color32>,
+    /// Inferred signal kinds (one per line in the current subsystem).
+    pub line_kinds: Vec<crate::signal_kind::SignalKind>,
     /// Port-count map: (SID, port_type_byte) → count.
     pub port_counts: std::collections::HashMap<(String, u8), u32>,
     /// Set of (SID, port_index, is_input) triples that have a connected signal.
     pub connected_ports: std::collections::HashSet<(String, u32, bool)>,
+    /// Signal label placements, keyed by zoom bucket and geometry.
+    pub label_placements: crate::label_place::PlacementCache,
     /// The subsystem path for which this cache was computed.
     cached_path: Vec<String>,
     /// Model generation at which the cache was computed.
@@ -181,8 +211,10 @@ impl Default for ComputedViewCache {
             // Start at 1 so the initial cached_gen=0 never matches: cache always starts invalid.
             generation: 1,
             line_colors: Vec::new(),
+            line_kinds: Vec::new(),
             port_counts: std::collections::HashMap::new(),
             connected_ports: std::collections::HashSet::new(),
+            label_placements: crate::label_place::PlacementCache::default(),
             cached_path: Vec::new(),
             cached_gen: 0,
         }
@@ -206,6 +238,7 @@ impl ComputedViewCache {
     /// Bump the generation counter, invalidating the cache.
     pub fn invalidate(&mut self) {
         self.generation += 1;
+        self.label_placements.clear();
     }
 }
 
@@ -245,13 +278,46 @@ pub struct SubsystemApp {
     subsystem_change_listeners: Vec<Arc<dyn Fn(&[String], &SubsystemEntities) + Send + Sync>>, // private to encourage using the API
     /// Optional click handler to override default action when clicking a block.
     /// Return true from the handler to indicate the click was handled and suppress the default behavior.
-    pub block_click_handler: Option<Arc<dyn Fn(&mut SubsystemApp, &Block) -> bool + Send + Sync>>,
+    pub block_click_handler: Option<BlockClickHandler>,
+    /// Double-click handlers by block type, run before `block_click_handler`;
+    /// see [`SubsystemApp::set_block_double_click_handler`].
+    pub block_double_click_handlers: HashMap<String, BlockClickHandler>,
+    /// Events for [`SubsystemApp::take_events`]; `None` until it is called.
+    events: Option<Vec<ViewerEvent>>,
+    /// Signal under the pointer in the last frame.
+    pub(crate) hovered_line: Option<usize>,
+    /// Optional handler for annotation links the viewer does not resolve
+    /// itself; see [`SubsystemApp::open_link`].
+    pub link_handler: Option<LinkHandler>,
+
+    /// Whether the subsystem tree panel is shown left of the canvas.
+    pub show_subsystem_tree: bool,
+
+    /// Path the subsystem tree last expanded and scrolled to.
+    pub(crate) tree_revealed_path: Option<Vec<String>>,
+
+    /// Bookmarked subsystems, blocks and signals; see
+    /// [`SubsystemApp::add_bookmark`].
+    pub bookmarks: Vec<Bookmark>,
+
+    /// Signals whose dialogs were opened, most recent first.
+    pub recent_signals: Vec<Bookmark>,
+
+    /// Whether the bookmarks panel is shown right of the canvas.
+    pub show_bookmarks: bool,
+
+    /// Theme and font size of code in the chart and block windows.
+    pub code_style: CodeStyle,
 
     /// Global default for showing block names.
     ///
     /// Per-block override: `Block::show_name = Some(true/false)`.
     pub show_block_names_default: bool,
 
+    /// Whether signal labels are placed together with
+    /// [`crate::label_place::place_all`] instead of one after another.
+    pub joint_label_placement: bool,
+
     /// Block-name font size as a factor of the port chevron height.
     ///
     /// A value of ~1.0 makes the text approximately the same height as the chevrons.
@@ -268,12 +334,22 @@ pub struct SubsystemApp {
     /// Used when avoiding collisions with other elements.
     pub block_name_min_font_factor: f32,
 
+    /// Stroke width (in points) for nonscalar signal lines.
+    ///
+    /// Scalar lines are drawn 2 points wide; bus lines use a double-line style
+    /// and control (function-call/action) lines are dashed.
+    pub vector_line_width: f32,
+
     /// Selected block SIDs in the current view (supports multi-selection).
     pub selected_block_sids: BTreeSet<String>,
 
     /// Selected line indices in the current subsystem view.
     pub selected_line_indices: BTreeSet<usize>,
 
+    /// SID of the block with keyboard focus in the current view; moved with
+    /// the arrow keys, see [`SubsystemApp::move_focus`].
+    pub focused_block_sid: Option<String>,
+
     /// Whether interactive move/resize mode is enabled.
     pub move_mode_enabled: bool,
 
@@ -298,6 +374,13 @@ pub struct SubsystemApp {
     /// Whether the in-memory layout differs from the last loaded/saved layout.
     pub layout_dirty: bool,
 
+    /// Key identifying the model for session persistence; see
+    /// [`SubsystemApp::set_session_source`]. Sessions are not saved without it.
+    pub session_key: Option<String>,
+
+    /// Recent search queries, most recent first.
+    pub search_history: Vec<String>,
+
     /// Persistent model-space bounds used for viewer auto-fit.
     ///
     /// This avoids recomputing the fit from edited block positions every frame,
@@ -338,6 +421,22 @@ pub struct SubsystemApp {
     /// Pending live dashboard control update for the host application.
     #[cfg(feature = "dashboard")]
     pub pending_dashboard_control: Option<DashboardControlEvent>,
+
+    /// Per-block style overrides keyed by SID or block path; see
+    /// [`SubsystemApp::set_block_style`].
+    block_styles: HashMap<String, StyleOverride>,
+
+    /// Metric overlays by name and the one currently shown; see
+    /// [`SubsystemApp::set_overlay`].
+    overlays: BTreeMap<String, MetricOverlay>,
+    active_overlay: Option<String>,
+
+    /// Zoom and pan of subsystems visited before, keyed by path.
+    saved_views: HashMap<Vec<String>, SavedView>,
+
+    /// Storage key of the bookmarks; set by
+    /// [`SubsystemApp::set_session_source`].
+    bookmarks_key: Option<String>,
 }
 
 impl SubsystemApp {
@@ -374,18 +473,33 @@ impl SubsystemApp {
             library_search_paths: Vec::new(),
             subsystem_change_listeners: Vec::new(),
             block_click_handler: None,
+            block_double_click_handlers: HashMap::new(),
+            events: None,
+            hovered_line: None,
+            link_handler: None,
+            show_subsystem_tree: false,
+            tree_revealed_path: None,
+            bookmarks: Vec::new(),
+            recent_signals: Vec::new(),
+            show_bookmarks: false,
+            code_style: CodeStyle::default(),
             show_block_names_default: true,
+            joint_label_placement: false,
             block_name_font_factor: 0.85,
             block_name_max_char_width_factor: 1.0 / 8.0,
             block_name_min_font_factor: 0.5,
+            vector_line_width: 3.5,
             selected_block_sids: BTreeSet::new(),
             selected_line_indices: BTreeSet::new(),
+            focused_block_sid: None,
             move_mode_enabled: false,
             add_mode_enabled: false,
             live_mode_enabled: false,
             live_values: HashMap::new(),
             layout_file_path: None,
             layout_dirty: false,
+            session_key: None,
+            search_history: Vec::new(),
             view_bounds: None,
             viewer_drag_state: ViewerDragState::None,
             view_cache: ComputedViewCache::default(),
@@ -398,7 +512,76 @@ impl SubsystemApp {
             constant_edits: std::collections::HashMap::new(),
             #[cfg(feature = "dashboard")]
             pending_dashboard_control: None,
+            block_styles: HashMap::new(),
+            overlays: BTreeMap::new(),
+            active_overlay: None,
+            saved_views: HashMap::new(),
+            bookmarks_key: None,
+        }
+    }
+
+    /// Id of the Stateflow chart holding the script of the MATLAB Function
+    /// `block` in the shown subsystem, looked up by SID and then by path.
+    pub fn chart_id_for_block(&self, block: &Block) -> Option<u32> {
+        let is_chart_block = block.block_type == "MATLAB Function"
+            || (block.block_type == "SubSystem" && block.is_matlab_function);
+        if !is_chart_block {
+            return None;
         }
+        let by_sid = block
+            .sid
+            .as_ref()
+            .and_then(|sid| self.chart_map.get(sid))
+            .copied();
+        by_sid.or_else(|| {
+            let instance_name = BlockPath::from(&self.path[..])
+                .child(&block.name)
+                .to_string();
+            self.chart_map.get(&instance_name).copied()
+        })
+    }
+
+    /// Replace the script of chart `chart_id`. Returns false if there is no
+    /// such chart. Save the change with
+    /// [`SlxArchive::update_chart_scripts`](crate::model::SlxArchive::update_chart_scripts).
+    pub fn set_chart_script(&mut self, chart_id: u32, script: &str) -> bool {
+        let Some(chart) = self.charts.get_mut(&chart_id) else {
+            return false;
+        };
+        chart.script = Some(script.to_string());
+        true
+    }
+
+    /// Replace the code of the CFunction `block` in the shown subsystem and
+    /// in the open block dialog. The block is looked up by SID, then by
+    /// name; returns false if it is not found.
+    pub fn set_c_function_code(&mut self, block: &Block, code: CFunctionCode) -> bool {
+        let Some(system) = self.current_system_mut() else {
+            return false;
+        };
+        let index = block
+            .sid
+            .as_ref()
+            .and_then(|sid| {
+                system
+                    .blocks
+                    .iter()
+                    .position(|b| b.sid.as_deref() == Some(sid.as_str()))
+            })
+            .or_else(|| system.blocks.iter().position(|b| b.name == block.name));
+        let Some(target) = index.map(|i| &mut system.blocks[i]) else {
+            return false;
+        };
+        target.set_c_function_code(code);
+        let updated = target.clone();
+        if let Some(dialog) = &mut self.block_view
+            && dialog.block.sid == updated.sid
+            && dialog.block.name == updated.name
+        {
+            dialog.block = updated;
+        }
+        self.view_cache.invalidate();
+        true
     }
 
     /// Return a snapshot of entities (blocks, lines, annotations) in the current subsystem.
@@ -459,10 +642,27 @@ impl SubsystemApp {
         self.pending_dashboard_control.take()
     }
 
-    fn notify_subsystem_changed(&self) {
+    fn notify_subsystem_changed(&mut self) {
+        self.hovered_line = None;
+        self.push_event(ViewerEvent::SubsystemEntered {
+            path: self.path.clone(),
+        });
         self.emit_subsystem_changed();
     }
 
+    /// Events since the last call, oldest first. Events are collected once
+    /// this has been called, so the first call returns nothing; hosts call it
+    /// after each `update`.
+    pub fn take_events(&mut self) -> Vec<ViewerEvent> {
+        self.events.replace(Vec::new()).unwrap_or_default()
+    }
+
+    pub(crate) fn push_event(&mut self, event: ViewerEvent) {
+        if let Some(events) = &mut self.events {
+            events.push(event);
+        }
+    }
+
     /// Override the default block click action. If set, the handler is called on each
     /// block click; return true to consume the event and skip the default action.
     pub fn set_block_click_handler<F>(&mut self, f: F)
@@ -477,6 +677,173 @@ impl SubsystemApp {
         self.block_click_handler = None;
     }
 
+    /// Handle double-clicks on blocks of `block_type`, e.g. `"Scope"` or
+    /// `"ModelReference"`, replacing an earlier handler for that type. Return
+    /// true from the handler to skip the default action, like opening a
+    /// subsystem.
+    pub fn set_block_double_click_handler<F>(&mut self, block_type: impl Into<String>, f: F)
+    where
+        F: Fn(&mut SubsystemApp, &Block) -> bool + Send + Sync + 'static,
+    {
+        self.block_double_click_handlers
+            .insert(block_type.into(), Arc::new(f));
+    }
+
+    pub fn clear_block_double_click_handler(&mut self, block_type: &str) {
+        self.block_double_click_handlers.remove(block_type);
+    }
+
+    /// Run the host handlers for a double-click on `block`: the one for its
+    /// block type, then `block_click_handler`. Returns true if one of them
+    /// handled the click.
+    pub fn run_block_double_click_handlers(&mut self, block: &Block) -> bool {
+        if let Some(handler) = self
+            .block_double_click_handlers
+            .get(&block.block_type)
+            .cloned()
+            && handler(self, block)
+        {
+            return true;
+        }
+        match self.block_click_handler.clone() {
+            Some(handler) => handler(self, block),
+            None => false,
+        }
+    }
+
+    /// Handle annotation links and click callbacks that do not open a model
+    /// element, e.g. custom URI schemes. Return true if the link was handled.
+    pub fn set_link_handler<F>(&mut self, f: F)
+    where
+        F: Fn(&mut SubsystemApp, &str) -> bool + Send + Sync + 'static,
+    {
+        self.link_handler = Some(Arc::new(f));
+    }
+
+    pub fn clear_link_handler(&mut self) {
+        self.link_handler = None;
+    }
+
+    /// Follow an annotation link or `ClickFcn` callback: paths recognized by
+    /// [`parse_link_target`] open their subsystem, or select their block in
+    /// the enclosing one; anything else goes to the link handler. Returns
+    /// false if nobody handled the link.
+    pub fn open_link(&mut self, link: &str) -> bool {
+        if let LinkTarget::Block(path) = parse_link_target(link) {
+            let bookmark = if resolve_subsystem_by_vec(&self.root, path.segments()).is_some() {
+                Some(Bookmark::subsystem(path.into_segments()))
+            } else {
+                Bookmark::block_at(&path)
+            };
+            if bookmark.is_some_and(|b| self.go_to_bookmark(&b)) {
+                return true;
+            }
+        }
+        match self.link_handler.clone() {
+            Some(handler) => handler(self, link),
+            None => false,
+        }
+    }
+
+    /// Override how a block is drawn. `sid_or_path` is a block SID or its
+    /// path from the root, e.g. `"Controller/Gain"` (a leading `/` is
+    /// ignored). Replaces any previous override for the same key.
+    pub fn set_block_style(&mut self, sid_or_path: impl Into<String>, style: StyleOverride) {
+        let key = sid_or_path.into();
+        let key = key.trim_start_matches('/').to_string();
+        self.block_styles.insert(key, style);
+    }
+
+    /// Remove the override set for `sid_or_path`.
+    pub fn clear_block_style(&mut self, sid_or_path: &str) {
+        self.block_styles
+            .remove(sid_or_path.trim_start_matches('/'));
+    }
+
+    /// Remove all block style overrides.
+    pub fn clear_block_styles(&mut self) {
+        self.block_styles.clear();
+    }
+
+    /// The override applying to `block` in the current subsystem, if any.
+    /// An override keyed by SID wins over one keyed by path.
+    pub fn block_style(&self, block: &Block) -> Option<&StyleOverride> {
+        if self.block_styles.is_empty() {
+            return None;
+        }
+        if let Some(style) = block
+            .sid
+            .as_ref()
+            .and_then(|sid| self.block_styles.get(sid))
+        {
+            return Some(style);
+        }
+        self.block_styles.get(&block_path(&self.path, &block.name))
+    }
+
+    /// Add or replace the metric overlay named `overlay.name`. The first
+    /// overlay added becomes active.
+    pub fn set_overlay(&mut self, overlay: MetricOverlay) {
+        if self.active_overlay.is_none() {
+            self.active_overlay = Some(overlay.name.clone());
+        }
+        self.overlays.insert(overlay.name.clone(), overlay);
+    }
+
+    /// Remove the overlay called `name`, deactivating it if it was shown.
+    pub fn remove_overlay(&mut self, name: &str) -> Option<MetricOverlay> {
+        if self.active_overlay.as_deref() == Some(name) {
+            self.active_overlay = None;
+        }
+        self.overlays.remove(name)
+    }
+
+    /// Remove all overlays.
+    pub fn clear_overlays(&mut self) {
+        self.overlays.clear();
+        self.active_overlay = None;
+    }
+
+    /// Names of the registered overlays, sorted.
+    pub fn overlay_names(&self) -> impl Iterator<Item = &str> {
+        self.overlays.keys().map(String::as_str)
+    }
+
+    /// Show the overlay called `name`, or none. Returns `false` if no such
+    /// overlay exists, leaving the current selection unchanged.
+    pub fn set_active_overlay(&mut self, name: Option<&str>) -> bool {
+        match name {
+            Some(n) if !self.overlays.contains_key(n) => false,
+            _ => {
+                self.active_overlay = name.map(str::to_string);
+                true
+            }
+        }
+    }
+
+    /// The overlay currently shown, if any.
+    pub fn active_overlay(&self) -> Option<&MetricOverlay> {
+        self.overlays.get(self.active_overlay.as_deref()?)
+    }
+
+    /// Overlay color for `block` in the current subsystem, if the active
+    /// overlay has a value for it.
+    pub fn overlay_color(&self, block: &Block) -> Option<crate::color::Rgba> {
+        self.active_overlay()?
+            .color_for(&block_path(&self.path, &block.name))
+    }
+
+    /// Write the current subsystem with the active overlay and its legend to
+    /// an SVG file.
+    pub fn export_overlay_svg(&self, path: impl AsRef<std::path::Path>) -> anyhow::Result<()> {
+        let Some(system) = self.current_system() else {
+            anyhow::bail!("Current subsystem not found");
+        };
+        let svg = crate::overlay::render_svg(system, &self.path, self.active_overlay());
+        std::fs::write(path, svg)?;
+        Ok(())
+    }
+
     pub fn egui_id(&self, key: impl std::hash::Hash) -> egui::Id {
         egui::Id::new(("rustylink_viewer", self.instance_id, key))
     }
@@ -535,11 +902,14 @@ impl SubsystemApp {
         F: Fn(&crate::model::Line) -> bool + Send + Sync + 'static,
         G: Fn(&crate::model::Line) + Send + Sync + 'static,
     {
-        self.signal_menu_items.push(SignalContextMenuItem {
-            label: label.into(),
-            filter: Arc::new(filter),
-            on_click: Arc::new(on_click),
-        });
+        self.signal_menu_items
+            .push(SignalContextMenuItem::action(label, on_click).with_filter(filter));
+    }
+
+    /// Register a context menu entry for signals: a submenu, a check item,
+    /// a separator or an action with an icon or enabled state.
+    pub fn add_signal_context_menu_entry(&mut self, item: SignalContextMenuItem) {
+        self.signal_menu_items.push(item);
     }
 
     /// Register a custom context menu item for blocks.
@@ -552,11 +922,14 @@ impl SubsystemApp {
         F: Fn(&Block) -> bool + Send + Sync + 'static,
         G: Fn(&Block) + Send + Sync + 'static,
     {
-        self.block_menu_items.push(BlockContextMenuItem {
-            label: label.into(),
-            filter: Arc::new(filter),
-            on_click: Arc::new(on_click),
-        });
+        self.block_menu_items
+            .push(BlockContextMenuItem::action(label, on_click).with_filter(filter));
+    }
+
+    /// Register a context menu entry for blocks: a submenu, a check item, a
+    /// separator or an action with an icon or enabled state.
+    pub fn add_block_context_menu_entry(&mut self, item: BlockContextMenuItem) {
+        self.block_menu_items.push(item);
     }
 
     /// Get the current subsystem based on `self.path`.
@@ -613,6 +986,7 @@ impl SubsystemApp {
         self.view_bounds = None;
         self.selected_block_sids.clear();
         self.selected_line_indices.clear();
+        self.focused_block_sid = None;
         self.viewer_drag_state = ViewerDragState::None;
         self.layout_dirty = false;
         self.viewer_history.clear();
@@ -631,6 +1005,7 @@ impl SubsystemApp {
         self.view_bounds = None;
         self.selected_block_sids.clear();
         self.selected_line_indices.clear();
+        self.focused_block_sid = None;
         self.viewer_drag_state = ViewerDragState::None;
         self.layout_dirty = false;
         self.view_cache.invalidate();
@@ -638,14 +1013,54 @@ impl SubsystemApp {
         self.notify_subsystem_changed();
     }
 
+    /// Move the keyboard focus to the next block in `direction`, preferring
+    /// blocks connected to the focused one. Without a focused block, the
+    /// top-left block gets the focus. Returns whether the focus changed.
+    pub fn move_focus(&mut self, direction: FocusDirection) -> bool {
+        let Some(system) = self.current_system() else {
+            return false;
+        };
+        let current = self
+            .focused_block_sid
+            .as_deref()
+            .filter(|sid| system.blocks.iter().any(|b| b.sid.as_deref() == Some(*sid)));
+        let next = match current {
+            Some(sid) => focus_nav::neighbor(system, sid, direction).map(str::to_string),
+            None => focus_nav::first_block(system).and_then(|b| b.sid.clone()),
+        };
+        match next {
+            Some(sid) if self.focused_block_sid.as_deref() != Some(sid.as_str()) => {
+                self.focused_block_sid = Some(sid);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The block with keyboard focus, if it is in the current subsystem.
+    pub fn focused_block(&self) -> Option<&Block> {
+        let sid = self.focused_block_sid.as_deref()?;
+        self.current_system()?
+            .blocks
+            .iter()
+            .find(|b| b.sid.as_deref() == Some(sid))
+    }
+
     /// Navigate one level up, if possible.
     pub fn go_up(&mut self) {
-        if !self.path.is_empty() {
-            self.path.pop();
-            self.reset_view = true;
-            self.view_bounds = None;
+        if self.path.is_empty() {
+            return;
+        }
+        self.remember_view();
+        if let Some(left) = self.path.pop() {
+            self.restore_view();
             self.selected_block_sids.clear();
             self.selected_line_indices.clear();
+            // Keep the keyboard focus on the subsystem we came from.
+            self.focused_block_sid = self
+                .current_system()
+                .and_then(|s| s.blocks.iter().find(|b| b.name == left))
+                .and_then(|b| b.sid.clone());
             self.viewer_drag_state = ViewerDragState::None;
             self.viewer_history.clear();
             self.notify_subsystem_changed();
@@ -655,11 +1070,12 @@ impl SubsystemApp {
     /// Navigate to the given path, if it resolves.
     pub fn navigate_to_path(&mut self, p: Vec<String>) {
         if resolve_subsystem_by_vec(&self.root, &p).is_some() {
+            self.remember_view();
             self.path = p;
-            self.reset_view = true;
-            self.view_bounds = None;
+            self.restore_view();
             self.selected_block_sids.clear();
             self.selected_line_indices.clear();
+            self.focused_block_sid = None;
             self.viewer_drag_state = ViewerDragState::None;
             self.viewer_history.clear();
             self.notify_subsystem_changed();
@@ -671,11 +1087,12 @@ impl SubsystemApp {
         if b.block_type == "SubSystem" || b.block_type == "Reference" {
             if let Some(sub) = &b.subsystem {
                 if sub.chart.is_none() {
+                    self.remember_view();
                     self.path.push(b.name.clone());
-                    self.reset_view = true;
-                    self.view_bounds = None;
+                    self.restore_view();
                     self.selected_block_sids.clear();
                     self.selected_line_indices.clear();
+                    self.focused_block_sid = None;
                     self.viewer_drag_state = ViewerDragState::None;
                     self.viewer_history.clear();
                     self.notify_subsystem_changed();
@@ -686,6 +1103,302 @@ impl SubsystemApp {
         false
     }
 
+    /// Remember the zoom and pan of the shown subsystem before leaving it.
+    fn remember_view(&mut self) {
+        if !self.reset_view {
+            self.saved_views.insert(
+                self.path.clone(),
+                SavedView {
+                    zoom: self.zoom,
+                    pan: [self.pan.x, self.pan.y],
+                },
+            );
+        }
+    }
+
+    /// Show the subsystem at `path` with the zoom and pan it had when it was
+    /// last left, or fitted to the window if it was not visited before.
+    fn restore_view(&mut self) {
+        self.view_bounds = None;
+        match self.saved_views.get(&self.path) {
+            Some(view) => {
+                self.zoom = view.zoom;
+                self.pan = Vec2::new(view.pan[0], view.pan[1]);
+                self.reset_view = false;
+            }
+            None => self.reset_view = true,
+        }
+    }
+
+    /// Identify the model for session persistence by its path and contents,
+    /// so an edited model does not reuse a stale session. Bookmarks are
+    /// keyed by the path alone.
+    pub fn set_session_source(&mut self, model_path: impl AsRef<str>, contents: &[u8]) {
+        self.session_key = Some(session::session_key(model_path.as_ref(), contents));
+        self.bookmarks_key = Some(bookmarks::storage_key(model_path.as_ref()));
+    }
+
+    /// Snapshot of the state that [`SubsystemApp::save_session`] persists.
+    pub fn session(&self) -> ViewerSession {
+        let mut views: BTreeMap<String, SavedView> = self
+            .saved_views
+            .iter()
+            .map(|(p, v)| (BlockPath::from(&p[..]).to_string(), *v))
+            .collect();
+        if !self.reset_view {
+            views.insert(
+                BlockPath::from(&self.path[..]).to_string(),
+                SavedView {
+                    zoom: self.zoom,
+                    pan: [self.pan.x, self.pan.y],
+                },
+            );
+        }
+        ViewerSession {
+            version: SESSION_VERSION,
+            path: self.path.clone(),
+            views,
+            open_block: self
+                .block_view
+                .as_ref()
+                .filter(|d| d.open)
+                .and_then(|d| d.block.sid.clone()),
+            open_signal: self
+                .signal_view
+                .as_ref()
+                .filter(|d| d.open)
+                .map(|d| d.line_idx),
+            search_history: self.search_history.clone(),
+        }
+    }
+
+    /// Return to the state captured in `session`. Parts that no longer match
+    /// the model (missing subsystem, block or line) are skipped.
+    pub fn apply_session(&mut self, session: ViewerSession) {
+        self.saved_views = session
+            .views
+            .into_iter()
+            .filter_map(|(p, v)| Some((p.parse::<BlockPath>().ok()?.into_segments(), v)))
+            .collect();
+        if resolve_subsystem_by_vec(&self.root, &session.path).is_some() {
+            self.path = session.path;
+        }
+        self.restore_view();
+        self.selected_block_sids.clear();
+        self.selected_line_indices.clear();
+        self.focused_block_sid = None;
+        self.viewer_drag_state = ViewerDragState::None;
+        self.viewer_history.clear();
+        self.search_history = session.search_history;
+
+        self.block_view = session.open_block.and_then(|sid| {
+            let block = self
+                .current_system()?
+                .blocks
+                .iter()
+                .find(|b| b.sid.as_deref() == Some(sid.as_str()))?;
+            Some(BlockDialog {
+                title: super::ui::helpers::block_dialog_title(block),
+                block: block.clone(),
+                open: true,
+            })
+        });
+        self.signal_view = session.open_signal.and_then(|line_idx| {
+            let line = self.current_system()?.lines.get(line_idx)?;
+            Some(SignalDialog {
+                title: line.name.clone().unwrap_or("<signal>".into()),
+                line_idx,
+                open: true,
+            })
+        });
+        self.notify_subsystem_changed();
+    }
+
+    /// Store the session in `storage` under [`SubsystemApp::session_key`].
+    /// Does nothing if no session key is set.
+    pub fn save_session(&self, storage: &mut dyn eframe::Storage) {
+        if let Some(key) = &self.session_key {
+            storage.set_string(&session::storage_key(key), self.session().to_json());
+        }
+    }
+
+    /// Restore the session stored for [`SubsystemApp::session_key`], if any.
+    /// Returns whether a session was applied.
+    pub fn restore_session(&mut self, storage: &dyn eframe::Storage) -> bool {
+        let Some(key) = &self.session_key else {
+            return false;
+        };
+        let Some(session) = storage
+            .get_string(&session::storage_key(key))
+            .and_then(|text| ViewerSession::from_json(&text))
+        else {
+            return false;
+        };
+        self.apply_session(session);
+        true
+    }
+
+    /// Store the bookmarks and recent signals in `storage`. Does nothing if
+    /// no session source is set, or if there is nothing to store and nothing
+    /// stored before.
+    pub fn save_bookmarks(&self, storage: &mut dyn eframe::Storage) {
+        if let Some(key) = &self.bookmarks_key {
+            let empty = self.bookmarks.is_empty() && self.recent_signals.is_empty();
+            if empty && storage.get_string(key).is_none() {
+                return;
+            }
+            let saved = SavedBookmarks {
+                version: BOOKMARKS_VERSION,
+                bookmarks: self.bookmarks.clone(),
+                recent_signals: self.recent_signals.clone(),
+            };
+            storage.set_string(key, saved.to_json());
+        }
+    }
+
+    /// Add the bookmarks stored for the model to the current ones, e.g.
+    /// those a host set up, and restore the recent signals. Returns whether
+    /// any were found.
+    pub fn restore_bookmarks(&mut self, storage: &dyn eframe::Storage) -> bool {
+        let Some(saved) = self
+            .bookmarks_key
+            .as_ref()
+            .and_then(|key| storage.get_string(key))
+            .and_then(|text| SavedBookmarks::from_json(&text))
+        else {
+            return false;
+        };
+        for bookmark in saved.bookmarks {
+            self.add_bookmark(bookmark);
+        }
+        self.recent_signals = saved.recent_signals;
+        true
+    }
+
+    /// Add `bookmark` unless one for the same target exists. Returns whether
+    /// it was added.
+    pub fn add_bookmark(&mut self, bookmark: Bookmark) -> bool {
+        if self.bookmarks.iter().any(|b| b.same_target(&bookmark)) {
+            return false;
+        }
+        self.bookmarks.push(bookmark);
+        true
+    }
+
+    pub fn remove_bookmark(&mut self, index: usize) -> Option<Bookmark> {
+        (index < self.bookmarks.len()).then(|| self.bookmarks.remove(index))
+    }
+
+    /// Bookmark the shown subsystem.
+    pub fn bookmark_current_subsystem(&mut self) -> bool {
+        self.add_bookmark(Bookmark::subsystem(self.path.clone()))
+    }
+
+    /// Ids of the selected lines of the shown subsystem. Unlike
+    /// `selected_line_indices` they stay valid when lines are added or
+    /// removed.
+    pub fn selected_line_ids(&self) -> Vec<LineId> {
+        let Some(system) = self.current_system() else {
+            return Vec::new();
+        };
+        self.selected_line_indices
+            .iter()
+            .filter_map(|&i| system.lines.get(i)?.id())
+            .collect()
+    }
+
+    /// Add the line `id` of the shown subsystem to the selection. Returns
+    /// false if there is no such line.
+    pub fn select_line(&mut self, id: &LineId) -> bool {
+        let Some(index) = self.current_system().and_then(|s| s.line_index(id)) else {
+            return false;
+        };
+        self.selected_line_indices.insert(index);
+        true
+    }
+
+    /// Open the signal dialog of line `id` of the shown subsystem. Returns
+    /// false if there is no such line.
+    pub fn open_signal_dialog(&mut self, id: &LineId) -> bool {
+        let Some((line_idx, line)) = self
+            .current_system()
+            .and_then(|s| s.line_index(id).map(|i| (i, &s.lines[i])))
+        else {
+            return false;
+        };
+        self.signal_view = Some(SignalDialog {
+            title: line.name.clone().unwrap_or("<signal>".into()),
+            line_idx,
+            open: true,
+        });
+        self.record_recent_signal(line_idx);
+        true
+    }
+
+    /// Remember the signal of line `line_idx` of the shown subsystem as
+    /// recently opened.
+    pub fn record_recent_signal(&mut self, line_idx: usize) {
+        let Some(system) = self.current_system() else {
+            return;
+        };
+        let Some(signal) = system
+            .lines
+            .get(line_idx)
+            .and_then(|line| Bookmark::signal(self.path.clone(), line, &system.blocks))
+        else {
+            return;
+        };
+        bookmarks::push_recent_signal(&mut self.recent_signals, signal);
+    }
+
+    /// Open the subsystem of `bookmark` and select its block or signal.
+    /// Returns false if the target no longer exists in the model.
+    pub fn go_to_bookmark(&mut self, bookmark: &Bookmark) -> bool {
+        let Some(system) = resolve_subsystem_by_vec(&self.root, &bookmark.path) else {
+            return false;
+        };
+        let selection = match &bookmark.target {
+            BookmarkTarget::Subsystem => None,
+            BookmarkTarget::Block { sid, name } => {
+                let by_sid = sid.as_ref().and_then(|sid| {
+                    system
+                        .blocks
+                        .iter()
+                        .find(|b| b.sid.as_deref() == Some(sid.as_str()))
+                });
+                let Some(block) = by_sid.or_else(|| system.blocks.iter().find(|b| b.name == *name))
+                else {
+                    return false;
+                };
+                Some((block.sid.clone(), None))
+            }
+            BookmarkTarget::Signal { sid, port } => {
+                let Some(line_idx) = system.lines.iter().position(|l| {
+                    l.src
+                        .as_ref()
+                        .is_some_and(|s| s.sid == *sid && s.port_index == *port)
+                }) else {
+                    return false;
+                };
+                Some((None, Some(line_idx)))
+            }
+        };
+        self.navigate_to_path(bookmark.path.clone());
+        if let Some((sid, line_idx)) = selection {
+            if let Some(sid) = sid {
+                self.selected_block_sids.insert(sid.clone());
+                self.focused_block_sid = Some(sid);
+            }
+            self.selected_line_indices.extend(line_idx);
+        }
+        true
+    }
+
+    /// Add `query` to the search history.
+    pub fn record_search(&mut self, query: &str) {
+        session::push_search_history(&mut self.search_history, query);
+    }
+
     /// Update `search_matches` based on `search_query`.
     pub fn update_search_matches(&mut self) {
         let q = self.search_query.trim();
@@ -716,6 +1429,11 @@ impl eframe::App for SubsystemApp {
             super::ui::update_with_info(self, ui);
         });
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.save_session(storage);
+        self.save_bookmarks(storage);
+    }
 }
 
 /// Resolve a mutable reference to a subsystem by path.
@@ -790,4 +1508,13 @@ mod tests {
         cache.mark_valid(&path, cache.generation);
         assert!(cache.is_valid(&path, cache.generation));
     }
+
+    #[test]
+    fn invalidate_drops_label_placements() {
+        let mut cache = ComputedViewCache::default();
+        cache.label_placements.store(0, 7, Vec::new());
+        assert!(cache.label_placements.get(0, 7).is_some());
+        cache.invalidate();
+        assert!(cache.label_placements.get(0, 7).is_none());
+    }
 }
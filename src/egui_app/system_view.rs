@@ -0,0 +1,460 @@
+//! Read-only diagram widget for embedding systems in a host `Ui`.
+//!
+//! [`update`](super::update) drives one [`SubsystemApp`](super::SubsystemApp)
+//! with navigation, dialogs and editing. [`SystemView`] only draws a given
+//! `&System` (blocks, lines and annotations) into the space it is given and
+//! keeps its zoom and pan in egui memory under its own id, so a window can
+//! hold several diagrams, e.g. two revisions of a model side by side.
+//!
+//! ```rust,ignore
+//! ui.columns(2, |cols| {
+//!     SystemView::new(&old, "diff-left").show(&mut cols[0]);
+//!     SystemView::new(&new, "diff-right").show(&mut cols[1]);
+//! });
+//! ```
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use eframe::egui::{self, Color32, Pos2, Rect, Sense, Vec2};
+
+use super::geometry::{
+    PortAnchors, block_outline, control_port_symbol, parse_block_rect, parse_rect_str,
+};
+use super::render::{get_block_type_cfg, render_block_icon};
+use super::ui::colors::{block_base_color, block_foreground_color, color32_to_rgba, luminance};
+use super::ui::line_coloring::{assign_line_colors, compute_line_adjacency};
+use super::ui::signal_routing::{compute_port_info, orthogonalize_polyline};
+use super::ui::view_transform::{ViewTransform, canvas_navigation};
+use crate::block_types::BlockShape;
+use crate::color::Rgba;
+use crate::display_list::{Align, DisplayList, Primitive, Stroke};
+use crate::model::{Branch, EndpointRef, System};
+
+/// Margin in screen points between the widget edge and the fitted diagram.
+const MARGIN: f32 = 20.0;
+
+/// Zoom and pan of one [`SystemView`], kept in egui memory.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SystemViewState {
+    /// Zoom factor; `1.0` fits the whole system.
+    pub zoom: f32,
+    /// Pan offset in screen points.
+    pub pan: Vec2,
+}
+
+impl Default for SystemViewState {
+    fn default() -> Self {
+        Self {
+            zoom: 1.0,
+            pan: Vec2::ZERO,
+        }
+    }
+}
+
+impl SystemViewState {
+    /// The state stored under `id`, or the fitted default.
+    pub fn load(ctx: &egui::Context, id: egui::Id) -> Self {
+        ctx.data(|d| d.get_temp(id)).unwrap_or_default()
+    }
+
+    pub fn store(self, ctx: &egui::Context, id: egui::Id) {
+        ctx.data_mut(|d| d.insert_temp(id, self));
+    }
+}
+
+/// What happened in a [`SystemView`] this frame.
+pub struct SystemViewResponse {
+    /// Response of the whole widget area.
+    pub response: egui::Response,
+    /// Index into `system.blocks` of the block under the pointer.
+    pub hovered_block: Option<usize>,
+    /// Index into `system.blocks` of the block clicked this frame.
+    pub clicked_block: Option<usize>,
+    /// Model-to-screen transform the diagram was drawn with.
+    pub transform: ViewTransform,
+}
+
+/// Draws one system into a `Ui`. Scrolling and pinching zoom, dragging pans
+/// and double-clicking the background fits the diagram again.
+pub struct SystemView<'a> {
+    system: &'a System,
+    id: egui::Id,
+    size: Option<Vec2>,
+    show_block_names: bool,
+    bounds: Option<Rect>,
+    highlights: HashMap<usize, Color32>,
+}
+
+impl<'a> SystemView<'a> {
+    /// A view of `system` whose zoom and pan are stored under `id_salt`.
+    /// Views created with the same salt share them.
+    pub fn new(system: &'a System, id_salt: impl Hash) -> Self {
+        Self {
+            system,
+            id: egui::Id::new(id_salt),
+            size: None,
+            show_block_names: true,
+            bounds: None,
+            highlights: HashMap::new(),
+        }
+    }
+
+    /// Id the [`SystemViewState`] is stored under.
+    pub fn id(&self) -> egui::Id {
+        self.id
+    }
+
+    /// Size of the widget; defaults to all available space.
+    pub fn size(mut self, size: Vec2) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    pub fn show_block_names(mut self, show: bool) -> Self {
+        self.show_block_names = show;
+        self
+    }
+
+    /// Model area fitted into the widget at zoom 1, instead of the
+    /// system's own [`system_bounds`]. Views with the same id and bounds
+    /// show the same area, so their zoom and pan stay locked together.
+    pub fn bounds(mut self, bounds: Rect) -> Self {
+        self.bounds = Some(bounds);
+        self
+    }
+
+    /// Outline the block at `index` in `system.blocks` in `color`.
+    pub fn highlight_block(mut self, index: usize, color: Color32) -> Self {
+        self.highlights.insert(index, color);
+        self
+    }
+
+    pub fn show(self, ui: &mut egui::Ui) -> SystemViewResponse {
+        let size = self.size.unwrap_or_else(|| ui.available_size());
+        let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+        let system = self.system;
+
+        let blocks: Vec<(usize, Rect)> = system
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, b)| parse_block_rect(b).map(|r| (i, r)))
+            .collect();
+        let bb = self
+            .bounds
+            .or_else(|| system_bounds(system))
+            .unwrap_or(Rect::from_min_size(Pos2::ZERO, Vec2::splat(100.0)));
+
+        // Zoom and pan
+        let mut state = SystemViewState::load(ui.ctx(), self.id);
+        let before = state;
+        let transform = ViewTransform::new(bb, rect, MARGIN, state.zoom, state.pan);
+        if response.double_clicked() && hit_block(&blocks, &transform, &response).is_none() {
+            state = SystemViewState::default();
+        } else {
+            if response.dragged() {
+                state.pan += response.drag_delta();
+            }
+            if response.hovered() {
+                let nav = ui.input(canvas_navigation);
+                if nav.zoom_factor != 1.0 {
+                    let center = nav.zoom_center.or(response.hover_pos());
+                    if let Some(center) = center {
+                        let (zoom, pan) = ViewTransform {
+                            pan: state.pan,
+                            ..transform
+                        }
+                        .zoom_at(center, nav.zoom_factor);
+                        state.zoom = zoom;
+                        state.pan = pan;
+                    }
+                }
+                state.pan += nav.pan;
+            }
+        }
+        if state != before {
+            state.store(ui.ctx(), self.id);
+        }
+        let transform = ViewTransform::new(bb, rect, MARGIN, state.zoom, state.pan);
+
+        let painter = ui.painter_at(rect);
+        painter.rect_filled(rect, 0.0, ui.visuals().extreme_bg_color);
+        self.display_list(ui.visuals()).paint(&painter, &transform);
+        self.paint_icons(&painter, &transform, &blocks);
+
+        let hovered_block = hit_block(&blocks, &transform, &response);
+        let clicked_block = if response.clicked() {
+            hovered_block
+        } else {
+            None
+        };
+        SystemViewResponse {
+            response,
+            hovered_block,
+            clicked_block,
+            transform,
+        }
+    }
+
+    /// Blocks, lines and annotations of the view in model coordinates, in
+    /// the colors of `visuals`. Block icons are left out: they are drawn
+    /// from the block type registry by egui only.
+    ///
+    /// ```rust,ignore
+    /// let svg = SystemView::new(&system, "export")
+    ///     .display_list(&egui::Visuals::light())
+    ///     .to_svg(20.0);
+    /// ```
+    pub fn display_list(&self, visuals: &egui::Visuals) -> DisplayList {
+        let bg = visuals.extreme_bg_color;
+        let mut list = DisplayList::new().with_background(color32_to_rgba(bg));
+        self.layout_lines(&mut list, luminance(bg));
+        self.layout_blocks(&mut list, color32_to_rgba(visuals.text_color()));
+        layout_annotations(
+            &mut list,
+            self.system,
+            color32_to_rgba(visuals.text_color()),
+        );
+        list
+    }
+
+    fn layout_blocks(&self, list: &mut DisplayList, text_color: Rgba) {
+        for (i, b) in self.system.blocks.iter().enumerate() {
+            let Some(r) = parse_block_rect(b) else {
+                continue;
+            };
+            let rect = [r.left(), r.top(), r.right(), r.bottom()];
+            let cfg = get_block_type_cfg(b);
+            let fill = if b.commented {
+                Color32::from_rgb(230, 230, 230)
+            } else {
+                block_base_color(b, &cfg)
+            };
+            let shape_fill = Some(color32_to_rgba(fill));
+            let stroke = Some(Stroke::new(1.0, Rgba::rgb(90, 90, 90)));
+            list.push(match cfg.shape {
+                BlockShape::Rectangle => Primitive::Rect {
+                    rect,
+                    corner_radius: 4.0,
+                    fill: shape_fill,
+                    stroke,
+                    title: None,
+                },
+                BlockShape::Rounded => Primitive::Rect {
+                    rect,
+                    corner_radius: r.size().min_elem() / 4.0,
+                    fill: shape_fill,
+                    stroke,
+                    title: None,
+                },
+                BlockShape::Circle => {
+                    let c = Rect::from_center_size(r.center(), Vec2::splat(r.size().min_elem()));
+                    Primitive::Ellipse {
+                        rect: [c.left(), c.top(), c.right(), c.bottom()],
+                        fill: shape_fill,
+                        stroke,
+                    }
+                }
+                BlockShape::Triangle | BlockShape::Trapezoid => Primitive::Polygon {
+                    points: block_outline(b, &cfg, r)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .map(|p| (p.x, p.y))
+                        .collect(),
+                    fill: shape_fill,
+                    stroke,
+                },
+                BlockShape::FilledBlack => Primitive::Rect {
+                    rect,
+                    corner_radius: 0.0,
+                    fill: Some(Rgba::rgb(0, 0, 0)),
+                    stroke: None,
+                    title: None,
+                },
+            });
+            let anchors = PortAnchors::new(b, &cfg);
+            for (kind, p) in anchors.control_positions(r) {
+                let placement = anchors.frame.placement_of(&kind);
+                if let Some(points) = control_port_symbol(&kind, p, placement, 10.0) {
+                    list.push(Primitive::Polyline {
+                        points: points.into_iter().map(|p| (p.x, p.y)).collect(),
+                        stroke: Stroke::new(1.0, Rgba::rgb(90, 90, 90)),
+                        arrow: false,
+                    });
+                }
+            }
+            if let Some(label) = crate::builtin_libraries::compute_block_instance_label(b) {
+                list.push(Primitive::Text {
+                    pos: (r.center().x, r.center().y),
+                    offset: (0.0, 0.0),
+                    text: label,
+                    size: 12.0,
+                    color: color32_to_rgba(block_foreground_color(b, fill)),
+                    anchor: [Align::Center, Align::Center],
+                });
+            }
+            if let Some(color) = self.highlights.get(&i) {
+                let r = r.expand(3.0);
+                list.push(Primitive::Rect {
+                    rect: [r.left(), r.top(), r.right(), r.bottom()],
+                    corner_radius: 4.0,
+                    fill: None,
+                    stroke: Some(Stroke::new(2.5, color32_to_rgba(*color))),
+                    title: None,
+                });
+            }
+            if self.show_block_names && b.show_name.unwrap_or(true) {
+                list.push(Primitive::Text {
+                    pos: (r.center().x, r.bottom()),
+                    offset: (0.0, 2.0),
+                    text: b.name.clone(),
+                    size: 11.0,
+                    color: text_color,
+                    anchor: [Align::Center, Align::Min],
+                });
+            }
+        }
+    }
+
+    /// Icons of the blocks without an instance label.
+    fn paint_icons(
+        &self,
+        painter: &egui::Painter,
+        transform: &ViewTransform,
+        blocks: &[(usize, Rect)],
+    ) {
+        for (i, r) in blocks {
+            let b = &self.system.blocks[*i];
+            if crate::builtin_libraries::compute_block_instance_label(b).is_none() {
+                let r = Rect::from_min_max(transform.to_screen(r.min), transform.to_screen(r.max));
+                render_block_icon(painter, b, &r, transform.font_scale(), None);
+            }
+        }
+    }
+
+    fn layout_lines(&self, list: &mut DisplayList, bg_luminance: f32) {
+        let system = self.system;
+        let (port_counts, _) = compute_port_info(&system.lines, &system.blocks);
+        let colors = assign_line_colors(&compute_line_adjacency(&system.lines), bg_luminance);
+        let ports = Ports {
+            rects: system
+                .blocks
+                .iter()
+                .filter_map(|b| Some((b.sid.clone()?, (parse_block_rect(b)?, PortAnchors::of(b)))))
+                .collect(),
+            counts: port_counts,
+        };
+        for (li, line) in system.lines.iter().enumerate() {
+            let color = color32_to_rgba(colors.get(li).copied().unwrap_or(Color32::LIGHT_GREEN));
+            let Some(mut cur) = line.src.as_ref().and_then(|src| ports.pos(src)) else {
+                continue;
+            };
+            let mut pts = vec![cur];
+            for p in &line.points {
+                cur += Vec2::new(p.x as f32, p.y as f32);
+                pts.push(cur);
+            }
+            if let Some(dst) = line.dst.as_ref().and_then(|dst| ports.pos(dst)) {
+                pts.push(dst);
+            }
+            push_signal(list, &pts, line.dst.is_some(), color);
+            for branch in &line.branches {
+                layout_branch(list, &ports, branch, cur, color);
+            }
+        }
+    }
+}
+
+/// Model area covered by the blocks and annotations of `system`.
+pub fn system_bounds(system: &System) -> Option<Rect> {
+    let blocks = system.blocks.iter().filter_map(parse_block_rect);
+    let annotations = system
+        .annotations
+        .iter()
+        .filter_map(|a| a.position.as_deref().and_then(parse_rect_str));
+    blocks.chain(annotations).reduce(|a, b| a.union(b))
+}
+
+/// Block rectangles and port counts, for line endpoints.
+struct Ports {
+    rects: HashMap<String, (Rect, PortAnchors)>,
+    counts: HashMap<(String, u8), u32>,
+}
+
+impl Ports {
+    fn pos(&self, ep: &EndpointRef) -> Option<Pos2> {
+        let (rect, anchors) = self.rects.get(&ep.sid)?;
+        let kind = if ep.port_type == "out" { 1 } else { 0 };
+        let count = self.counts.get(&(ep.sid.clone(), kind)).copied();
+        Some(anchors.endpoint_pos(*rect, ep, count))
+    }
+}
+
+fn layout_branch(list: &mut DisplayList, ports: &Ports, branch: &Branch, start: Pos2, color: Rgba) {
+    let mut cur = start;
+    let mut pts = vec![cur];
+    for p in &branch.points {
+        cur += Vec2::new(p.x as f32, p.y as f32);
+        pts.push(cur);
+    }
+    let dst = branch.dst.as_ref().and_then(|dst| ports.pos(dst));
+    pts.extend(dst);
+    push_signal(list, &pts, dst.is_some(), color);
+    for sub in &branch.branches {
+        layout_branch(list, ports, sub, cur, color);
+    }
+}
+
+/// A signal through `pts`, routed orthogonally, with an arrowhead if it
+/// ends at a port.
+fn push_signal(list: &mut DisplayList, pts: &[Pos2], arrow: bool, color: Rgba) {
+    let points: Vec<(f32, f32)> = orthogonalize_polyline(pts)
+        .iter()
+        .map(|p| (p.x, p.y))
+        .collect();
+    if points.len() < 2 {
+        return;
+    }
+    list.push(Primitive::Polyline {
+        points,
+        stroke: Stroke::new(1.5, color),
+        arrow,
+    });
+}
+
+fn layout_annotations(list: &mut DisplayList, system: &System, text_color: Rgba) {
+    for a in &system.annotations {
+        let Some(r) = a.position.as_deref().and_then(parse_rect_str) else {
+            continue;
+        };
+        let text = super::text::annotation_to_plain_text(
+            a.text.as_deref().unwrap_or_default(),
+            a.interpreter.as_deref(),
+        );
+        list.push(Primitive::Text {
+            pos: (r.left(), r.top()),
+            offset: (0.0, 0.0),
+            text,
+            size: 12.0,
+            color: text_color,
+            anchor: [Align::Min, Align::Min],
+        });
+    }
+}
+
+/// The topmost block under the pointer.
+fn hit_block(
+    blocks: &[(usize, Rect)],
+    transform: &ViewTransform,
+    response: &egui::Response,
+) -> Option<usize> {
+    let pos = response.hover_pos().or(response.interact_pointer_pos())?;
+    blocks
+        .iter()
+        .rev()
+        .find(|(_, r)| {
+            Rect::from_min_max(transform.to_screen(r.min), transform.to_screen(r.max)).contains(pos)
+        })
+        .map(|(i, _)| *i)
+}
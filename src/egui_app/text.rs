@@ -5,6 +5,7 @@ use egui::text::LayoutJob;
 use quick_xml::Reader;
 use quick_xml::escape::unescape;
 use quick_xml::events::{BytesStart, Event};
+use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 
 const DEFAULT_FONT_SIZE_PX: f32 = 12.0;
@@ -34,6 +35,10 @@ pub struct ResolvedStyle {
     pub bold: bool,
     pub italic: bool,
     pub underline: bool,
+    pub strikethrough: bool,
+    pub monospace: bool,
+    /// Target of the enclosing `<a href>`, if any.
+    pub link: Option<String>,
 }
 
 impl Default for ResolvedStyle {
@@ -45,6 +50,9 @@ impl Default for ResolvedStyle {
             bold: false,
             italic: false,
             underline: false,
+            strikethrough: false,
+            monospace: false,
+            link: None,
         }
     }
 }
@@ -84,6 +92,39 @@ impl AnnotationRichText {
         out
     }
 
+    /// Links of the text with their char ranges in the text of
+    /// [`Self::to_layout_job`], in reading order.
+    pub fn links(&self) -> Vec<(std::ops::Range<usize>, &str)> {
+        let mut links = Vec::new();
+        let mut pos = 0;
+        for (idx, line) in self.lines.iter().enumerate() {
+            if line.spans.is_empty() {
+                pos += 1;
+                continue;
+            }
+            for span in &line.spans {
+                let len = span.text.chars().count();
+                if let Some(link) = &span.style.link {
+                    links.push((pos..pos + len, link.as_str()));
+                }
+                pos += len;
+            }
+            if idx + 1 < self.lines.len() {
+                pos += 1;
+            }
+        }
+        links
+    }
+
+    /// The link at char `index` of the text of [`Self::to_layout_job`],
+    /// e.g. from `Galley::cursor_from_pos`.
+    pub fn link_at(&self, index: usize) -> Option<&str> {
+        self.links()
+            .into_iter()
+            .find(|(range, _)| range.contains(&index))
+            .map(|(_, link)| link)
+    }
+
     pub fn to_layout_job(
         &self,
         style: &EguiStyle,
@@ -108,15 +149,17 @@ impl AnnotationRichText {
             for span in &line.spans {
                 let mut fmt = egui::text::TextFormat::default();
                 let target_size = span.style.font_size_px.max(1.0) * font_scale;
-                fmt.font_id = FontId::proportional(target_size);
-                let mut color = span
-                    .style
-                    .color
-                    .or(line.resolved_style.color)
-                    .unwrap_or(base_color);
+                fmt.font_id = if span.style.monospace {
+                    FontId::monospace(target_size)
+                } else {
+                    FontId::proportional(target_size)
+                };
+                let explicit_color = span.style.color.or(line.resolved_style.color);
+                let mut color = explicit_color.unwrap_or(base_color);
                 let bold_source = span.style.bold || line.resolved_style.bold;
-                if bold_source && span.style.color.is_none() && line.resolved_style.color.is_none()
-                {
+                if span.style.link.is_some() && explicit_color.is_none() {
+                    color = style.visuals.hyperlink_color;
+                } else if bold_source && explicit_color.is_none() {
                     color = style.visuals.strong_text_color();
                 }
                 fmt.color = color;
@@ -129,6 +172,9 @@ impl AnnotationRichText {
                 if span.style.underline || line.resolved_style.underline {
                     fmt.underline = Stroke::new(1.0, color);
                 }
+                if span.style.strikethrough {
+                    fmt.strikethrough = Stroke::new(1.0, color);
+                }
                 job.append(&span.text, 0.0, fmt);
             }
             if idx + 1 < self.lines.len() {
@@ -222,6 +268,9 @@ fn parse_annotation_html(html: &str) -> Result<AnnotationRichText, ()> {
     let mut lines: Vec<AnnotationLine> = Vec::new();
     let mut current_line: Option<AnnotationLine> = None;
     let mut skip_depth: usize = 0;
+    // Open `<ul>` (None) and `<ol>` (number of the next item) elements.
+    let mut lists: Vec<Option<u32>> = Vec::new();
+    let mut pre_depth: usize = 0;
 
     loop {
         match reader.read_event_into(&mut buf) {
@@ -242,9 +291,28 @@ fn parse_annotation_html(html: &str) -> Result<AnnotationRichText, ()> {
                 let attrs = collect_attributes(&e)?;
                 let frame = style_from_element(&name, &attrs);
                 style_stack.push(frame);
-                if name == "p" {
-                    finalize_line(&mut current_line, &mut lines);
-                    current_line = Some(AnnotationLine::new(resolve_style(&style_stack)));
+                match name.as_str() {
+                    "ul" | "ol" => {
+                        finalize_line(&mut current_line, &mut lines);
+                        let start = find_attr(&attrs, "start").and_then(|s| s.trim().parse().ok());
+                        lists.push((name == "ol").then(|| start.unwrap_or(1)));
+                    }
+                    "li" => {
+                        finalize_line(&mut current_line, &mut lines);
+                        let mut line = AnnotationLine::new(resolve_style(&style_stack));
+                        let mut marker_style = resolve_style(&style_stack);
+                        marker_style.link = None;
+                        line.push_span(list_marker(&mut lists), marker_style);
+                        current_line = Some(line);
+                    }
+                    _ if is_block_element(&name) => {
+                        if name == "pre" {
+                            pre_depth += 1;
+                        }
+                        finalize_line(&mut current_line, &mut lines);
+                        current_line = Some(AnnotationLine::new(resolve_style(&style_stack)));
+                    }
+                    _ => {}
                 }
             }
             Ok(Event::Empty(e)) => {
@@ -262,7 +330,7 @@ fn parse_annotation_html(html: &str) -> Result<AnnotationRichText, ()> {
                 let attrs = collect_attributes(&e)?;
                 let frame = style_from_element(&name, &attrs);
                 style_stack.push(frame.clone());
-                if name == "p" {
+                if name == "li" || is_block_element(&name) {
                     finalize_line(&mut current_line, &mut lines);
                     lines.push(AnnotationLine::new(resolve_style(&style_stack)));
                 }
@@ -282,7 +350,13 @@ fn parse_annotation_html(html: &str) -> Result<AnnotationRichText, ()> {
                 if name == "br" {
                     continue;
                 }
-                if name == "p" {
+                if name == "ul" || name == "ol" {
+                    lists.pop();
+                }
+                if name == "pre" {
+                    pre_depth = pre_depth.saturating_sub(1);
+                }
+                if matches!(name.as_str(), "ul" | "ol" | "li") || is_block_element(&name) {
                     finalize_line(&mut current_line, &mut lines);
                 }
                 if style_stack.len() > 1 {
@@ -296,7 +370,11 @@ fn parse_annotation_html(html: &str) -> Result<AnnotationRichText, ()> {
                 // Decode text as UTF-8 and unescape XML entities
                 let raw = std::str::from_utf8(e.as_ref()).map_err(|_| ())?;
                 let unesc = unescape(raw).map_err(|_| ())?;
-                push_text_segment(unesc, &mut current_line, &style_stack);
+                if pre_depth > 0 {
+                    push_preformatted(&unesc, &mut current_line, &mut lines, &style_stack);
+                } else {
+                    push_text_segment(unesc, &mut current_line, &style_stack);
+                }
             }
             Ok(Event::CData(e)) => {
                 if skip_depth > 0 {
@@ -336,6 +414,10 @@ fn push_text_segment(
     if owned.is_empty() {
         return;
     }
+    // Indentation between block elements
+    if current_line.is_none() && owned.trim().is_empty() {
+        return;
+    }
     if current_line.is_none() {
         current_line.replace(AnnotationLine::new(resolve_style(style_stack)));
     }
@@ -345,6 +427,43 @@ fn push_text_segment(
     }
 }
 
+/// Text inside `<pre>`: newlines start new lines.
+fn push_preformatted(
+    text: &str,
+    current_line: &mut Option<AnnotationLine>,
+    lines: &mut Vec<AnnotationLine>,
+    style_stack: &[StyleFrame],
+) {
+    let text = text.replace('\r', "");
+    for (idx, chunk) in text.split('\n').enumerate() {
+        if idx > 0 {
+            handle_break(current_line, lines, style_stack);
+        }
+        push_text_segment(Cow::Borrowed(chunk), current_line, style_stack);
+    }
+}
+
+/// Elements rendered on lines of their own, besides list items.
+fn is_block_element(name: &str) -> bool {
+    matches!(
+        name,
+        "p" | "div" | "pre" | "blockquote" | "h1" | "h2" | "h3" | "h4" | "h5" | "h6"
+    )
+}
+
+/// Indented bullet or number of a new `<li>` in the innermost list.
+fn list_marker(lists: &mut [Option<u32>]) -> String {
+    let indent = "    ".repeat(lists.len().saturating_sub(1));
+    match lists.last_mut() {
+        Some(Some(number)) => {
+            let marker = format!("{indent}{number}. ");
+            *number += 1;
+            marker
+        }
+        _ => format!("{indent}• "),
+    }
+}
+
 fn finalize_line(current_line: &mut Option<AnnotationLine>, lines: &mut Vec<AnnotationLine>) {
     if let Some(line) = current_line.take() {
         lines.push(line);
@@ -375,6 +494,9 @@ fn looks_like_html(raw: &str, interpreter: Option<&str>) -> bool {
                 || raw.contains("<body")
                 || raw.contains("<p")
                 || raw.contains("<span")
+                || raw.contains("<ul")
+                || raw.contains("<ol")
+                || raw.contains("<a href")
         })
 }
 
@@ -405,6 +527,9 @@ struct StyleFrame {
     bold: Option<bool>,
     italic: Option<bool>,
     underline: Option<bool>,
+    strikethrough: Option<bool>,
+    monospace: Option<bool>,
+    link: Option<String>,
 }
 
 impl StyleFrame {
@@ -416,6 +541,9 @@ impl StyleFrame {
             bold: Some(false),
             italic: Some(false),
             underline: Some(false),
+            strikethrough: Some(false),
+            monospace: Some(false),
+            link: None,
         }
     }
 }
@@ -450,12 +578,53 @@ fn style_from_element(name: &str, attrs: &[(String, String)]) -> StyleFrame {
         "b" | "strong" => frame.bold = Some(true),
         "i" | "em" | "cite" => frame.italic = Some(true),
         "u" | "ins" => frame.underline = Some(true),
+        "s" | "strike" | "del" => frame.strikethrough = Some(true),
+        "code" | "tt" | "pre" | "kbd" | "samp" => frame.monospace = Some(true),
+        "a" => {
+            if let Some(href) = find_attr(attrs, "href") {
+                frame.link = Some(href.trim().to_string());
+                frame.underline.get_or_insert(true);
+            }
+        }
+        "font" => {
+            if let Some(size) = find_attr(attrs, "size").and_then(html_font_size) {
+                frame.font_size_px = Some(size);
+            }
+            if let Some(face) = find_attr(attrs, "face") {
+                frame.monospace =
+                    Some(font_id_for_name(Some(face), 1.0).family == egui::FontFamily::Monospace);
+            }
+        }
         _ => {}
     }
+    if let Some(level) = name
+        .strip_prefix('h')
+        .and_then(|l| l.parse::<usize>().ok())
+        .filter(|l| (1..=6).contains(l))
+    {
+        // Browser default heading sizes relative to 16 px body text.
+        const SCALE: [f32; 6] = [2.0, 1.5, 1.17, 1.0, 0.83, 0.67];
+        frame.bold.get_or_insert(true);
+        frame
+            .font_size_px
+            .get_or_insert(DEFAULT_FONT_SIZE_PX * SCALE[level - 1]);
+    }
 
     frame
 }
 
+/// Pixel size of `<font size>`: 1-7, or relative to 3 as in `+1`.
+fn html_font_size(value: &str) -> Option<f32> {
+    const SIZES: [f32; 7] = [10.0, 13.0, 16.0, 18.0, 24.0, 32.0, 48.0];
+    let value = value.trim();
+    let size = if value.starts_with(['+', '-']) {
+        3 + value.trim_start_matches('+').parse::<i32>().ok()?
+    } else {
+        value.parse::<i32>().ok()?
+    };
+    Some(SIZES[size.clamp(1, 7) as usize - 1] * DEFAULT_FONT_SIZE_PX / 16.0)
+}
+
 fn find_attr<'a>(attrs: &'a [(String, String)], name: &str) -> Option<&'a str> {
     attrs
         .iter()
@@ -488,10 +657,20 @@ fn apply_css_declarations(frame: &mut StyleFrame, decls: &str) {
                         frame.italic = Some(italic);
                     }
                 }
-                "text-decoration" => {
+                "text-decoration" | "text-decoration-line" => {
                     if let Some(underline) = parse_text_decoration(value) {
                         frame.underline = Some(underline);
                     }
+                    if value.contains("line-through") {
+                        frame.strikethrough = Some(true);
+                    } else if value.contains("none") {
+                        frame.strikethrough = Some(false);
+                    }
+                }
+                "font-family" => {
+                    frame.monospace = Some(
+                        font_id_for_name(Some(value), 1.0).family == egui::FontFamily::Monospace,
+                    );
                 }
                 "color" => {
                     if let Some(color) = parse_color(value) {
@@ -519,6 +698,64 @@ fn clean_css_value<'a>(value: &'a str) -> &'a str {
     }
 }
 
+/// Map a Simulink `FontName` onto one of egui's font families.
+///
+/// egui only ships a proportional and a monospace family, so fixed-width
+/// fonts (Courier, Consolas, ...) map to monospace and everything else to
+/// proportional.
+pub fn font_id_for_name(font_name: Option<&str>, size: f32) -> FontId {
+    let lower = font_name.unwrap_or_default().to_ascii_lowercase();
+    let mono = [
+        "courier",
+        "consol",
+        "mono",
+        "fixed",
+        "menlo",
+        "lucida console",
+    ];
+    if mono.iter().any(|m| lower.contains(m)) {
+        FontId::monospace(size)
+    } else {
+        FontId::proportional(size)
+    }
+}
+
+/// Returns `true` for Simulink/CSS font weights that should render bold.
+pub fn is_bold_weight(weight: Option<&str>) -> bool {
+    weight.and_then(parse_font_weight).unwrap_or(false)
+}
+
+/// Apply an annotation's block-level `ForegroundColor`, `FontName`,
+/// `FontSize` and `FontWeight` to a layout job built by
+/// [`AnnotationRichText::to_layout_job`].
+///
+/// Inline HTML styling wins: only sections still using `base_color` are
+/// recolored, and sizes are scaled relative to the default 12 px.
+pub fn apply_annotation_style(
+    job: &mut LayoutJob,
+    annotation: &crate::model::Annotation,
+    base_color: Color32,
+    strong_color: Color32,
+) {
+    let fg = annotation
+        .foreground_color
+        .map(|c| Color32::from_rgba_unmultiplied(c.r, c.g, c.b, c.a));
+    let bold = is_bold_weight(annotation.font_weight.as_deref());
+    let scale = annotation
+        .font_size
+        .map_or(1.0, |s| s as f32 / DEFAULT_FONT_SIZE_PX);
+    for section in &mut job.sections {
+        let fmt = &mut section.format;
+        fmt.font_id.size *= scale;
+        if annotation.font_name.is_some() && fmt.font_id.family == egui::FontFamily::Proportional {
+            fmt.font_id = font_id_for_name(annotation.font_name.as_deref(), fmt.font_id.size);
+        }
+        if fmt.color == base_color {
+            fmt.color = fg.unwrap_or(if bold { strong_color } else { base_color });
+        }
+    }
+}
+
 fn parse_font_size(value: &str) -> Option<f32> {
     let lower = value.to_ascii_lowercase();
     if lower.ends_with('%') {
@@ -686,6 +923,17 @@ fn resolve_style(stack: &[StyleFrame]) -> ResolvedStyle {
         .rev()
         .find_map(|s| s.underline)
         .unwrap_or(false);
+    let strikethrough = stack
+        .iter()
+        .rev()
+        .find_map(|s| s.strikethrough)
+        .unwrap_or(false);
+    let monospace = stack
+        .iter()
+        .rev()
+        .find_map(|s| s.monospace)
+        .unwrap_or(false);
+    let link = stack.iter().rev().find_map(|s| s.link.clone());
     ResolvedStyle {
         font_size_px,
         color,
@@ -693,6 +941,9 @@ fn resolve_style(stack: &[StyleFrame]) -> ResolvedStyle {
         bold,
         italic,
         underline,
+        strikethrough,
+        monospace,
+        link,
     }
 }
 
@@ -725,51 +976,97 @@ pub fn highlight_query_job(text: &str, query: &str) -> LayoutJob {
     job
 }
 
-/// MATLAB syntax highlighter using syntect. Lazily loads the syntax set and theme.
-pub fn matlab_syntax_job(script: &str) -> LayoutJob {
-    use egui::FontId;
-    use egui::text::TextFormat;
+/// Syntect theme used for code in light mode.
+pub const LIGHT_CODE_THEME: &str = "InspiredGitHub";
+/// Syntect theme used for code in dark mode.
+pub const DARK_CODE_THEME: &str = "base16-ocean.dark";
+
+/// Theme and font size of highlighted code in the chart and code windows.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodeStyle {
+    /// Syntect theme name (see [`code_theme_names`]), or `None` to use
+    /// [`LIGHT_CODE_THEME`] or [`DARK_CODE_THEME`] following the egui dark
+    /// mode.
+    pub theme: Option<String>,
+    /// Monospace font size in points.
+    pub font_size: f32,
+}
+
+impl Default for CodeStyle {
+    fn default() -> Self {
+        Self {
+            theme: None,
+            font_size: 14.0,
+        }
+    }
+}
+
+impl CodeStyle {
+    /// The theme to use with the given egui dark mode setting.
+    pub fn theme_name(&self, dark_mode: bool) -> &str {
+        match &self.theme {
+            Some(theme) => theme,
+            None if dark_mode => DARK_CODE_THEME,
+            None => LIGHT_CODE_THEME,
+        }
+    }
+}
+
+/// Names of the available code themes, sorted.
+pub fn code_theme_names() -> Vec<&'static str> {
+    syntect_sets().1.themes.keys().map(String::as_str).collect()
+}
+
+/// Syntect syntax definitions and themes, loaded on first use.
+fn syntect_sets() -> &'static (syntect::parsing::SyntaxSet, syntect::highlighting::ThemeSet) {
     use once_cell::sync::OnceCell;
+    static SETS: OnceCell<(syntect::parsing::SyntaxSet, syntect::highlighting::ThemeSet)> =
+        OnceCell::new();
+    SETS.get_or_init(|| {
+        (
+            syntect::parsing::SyntaxSet::load_defaults_newlines(),
+            syntect::highlighting::ThemeSet::load_defaults(),
+        )
+    })
+}
+
+/// Highlight `code` with the first of `scopes`, then of `names`, that the
+/// syntax set knows; plain text otherwise. Unknown themes fall back to the
+/// default for `dark_mode`.
+fn syntax_job(
+    code: &str,
+    scopes: &[&str],
+    names: &[&str],
+    style: &CodeStyle,
+    dark_mode: bool,
+) -> LayoutJob {
+    use egui::text::TextFormat;
     use syntect::easy::HighlightLines;
-    use syntect::highlighting::{Style, ThemeSet};
-    use syntect::parsing::SyntaxSet;
+    use syntect::highlighting::Style;
+    use syntect::parsing::Scope;
     use syntect::util::LinesWithEndings;
 
-    static SYNTAX_SET: OnceCell<SyntaxSet> = OnceCell::new();
-    static THEME_SET: OnceCell<ThemeSet> = OnceCell::new();
-
-    let ss = SYNTAX_SET.get_or_init(|| SyntaxSet::load_defaults_newlines());
-    let ts = THEME_SET.get_or_init(|| ThemeSet::load_defaults());
-    // Important: Don't select by ".m" file extension as syntect often resolves that to Objective‑C.
-    // Prefer the explicit MATLAB scope or well-known names and only then fall back to plain text.
-    let syntax = {
-        use syntect::parsing::Scope;
-        // Try by scope first (most reliable)
-        let by_scope = Scope::new("source.matlab")
-            .ok()
-            .and_then(|s| ss.find_syntax_by_scope(s));
-        if let Some(s) = by_scope {
-            s
-        } else {
-            // Try a few common names that appear across sublime grammars
-            ss.find_syntax_by_name("Matlab")
-                .or_else(|| ss.find_syntax_by_name("MATLAB"))
-                .or_else(|| ss.find_syntax_by_name("Matlab (Octave)"))
-                .or_else(|| ss.find_syntax_by_name("MATLAB (Octave)"))
-                .unwrap_or_else(|| ss.find_syntax_plain_text())
-        }
-    };
+    let (ss, ts) = syntect_sets();
+    let syntax = scopes
+        .iter()
+        .filter_map(|s| Scope::new(s).ok())
+        .find_map(|s| ss.find_syntax_by_scope(s))
+        .or_else(|| names.iter().find_map(|n| ss.find_syntax_by_name(n)))
+        .unwrap_or_else(|| ss.find_syntax_plain_text());
+    let fallback = CodeStyle::default();
     let theme = ts
         .themes
-        .get("InspiredGitHub")
+        .get(style.theme_name(dark_mode))
+        .or_else(|| ts.themes.get(fallback.theme_name(dark_mode)))
         .or_else(|| ts.themes.values().next())
         .unwrap();
 
     let mut h = HighlightLines::new(syntax, theme);
     let mut job = LayoutJob::default();
-    let mono = FontId::monospace(14.0);
+    let mono = FontId::monospace(style.font_size);
 
-    for line in LinesWithEndings::from(script) {
+    for line in LinesWithEndings::from(code) {
         let regions: Vec<(Style, &str)> = h.highlight(line, ss);
         for (style, text) in regions {
             let color = Color32::from_rgba_premultiplied(
@@ -789,4 +1086,38 @@ pub fn matlab_syntax_job(script: &str) -> LayoutJob {
     job
 }
 
+/// MATLAB syntax highlighter using syntect. Lazily loads the syntax set and theme.
+pub fn matlab_syntax_job(script: &str) -> LayoutJob {
+    matlab_syntax_job_styled(script, &CodeStyle::default(), false)
+}
+
+/// [`matlab_syntax_job`] with the given theme and font size.
+pub fn matlab_syntax_job_styled(script: &str, style: &CodeStyle, dark_mode: bool) -> LayoutJob {
+    // Important: Don't select by ".m" file extension as syntect often resolves that to Objective‑C.
+    // Prefer the explicit MATLAB scope or well-known names and only then fall back to plain text.
+    syntax_job(
+        script,
+        &["source.matlab"],
+        &["Matlab", "MATLAB", "Matlab (Octave)", "MATLAB (Octave)"],
+        style,
+        dark_mode,
+    )
+}
+
+/// C/C++ syntax highlighter for CFunction block code, see [`matlab_syntax_job`].
+pub fn c_syntax_job(code: &str) -> LayoutJob {
+    c_syntax_job_styled(code, &CodeStyle::default(), false)
+}
+
+/// [`c_syntax_job`] with the given theme and font size.
+pub fn c_syntax_job_styled(code: &str, style: &CodeStyle, dark_mode: bool) -> LayoutJob {
+    syntax_job(
+        code,
+        &["source.c++", "source.c"],
+        &["C++", "C"],
+        style,
+        dark_mode,
+    )
+}
+
 // tests moved to tests/ module
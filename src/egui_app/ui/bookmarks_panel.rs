@@ -0,0 +1,82 @@
+//! Bookmarks panel of the viewer.
+//!
+//! Lists the bookmarks and the recently opened signals; clicking an entry
+//! opens its subsystem and selects the block or signal. Entries whose target
+//! is gone from the model are greyed out.
+
+use crate::block_path::BlockPath;
+use crate::egui_app::bookmarks::{Bookmark, BookmarkTarget};
+use crate::egui_app::navigation::resolve_subsystem_by_vec;
+use crate::egui_app::state::SubsystemApp;
+use eframe::egui::{self, RichText};
+
+/// A click in the bookmarks panel.
+pub(crate) enum BookmarkAction {
+    GoTo(Bookmark),
+    Remove(usize),
+    AddCurrentSubsystem,
+}
+
+/// Draw the panel; returns the clicked action, if any.
+pub(crate) fn show_bookmarks(app: &SubsystemApp, ui: &mut egui::Ui) -> Option<BookmarkAction> {
+    let mut action = None;
+    ui.horizontal(|ui| {
+        ui.label(RichText::new("Bookmarks").strong());
+        if ui
+            .small_button("☆ Subsystem")
+            .on_hover_text("Bookmark the shown subsystem")
+            .clicked()
+        {
+            action = Some(BookmarkAction::AddCurrentSubsystem);
+        }
+    });
+    egui::ScrollArea::vertical()
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            if app.bookmarks.is_empty() {
+                ui.weak("Right-click a block or signal to bookmark it.");
+            }
+            for (i, bookmark) in app.bookmarks.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                        action = Some(BookmarkAction::Remove(i));
+                    }
+                    if entry(app, ui, bookmark) {
+                        action = Some(BookmarkAction::GoTo(bookmark.clone()));
+                    }
+                });
+            }
+            if !app.recent_signals.is_empty() {
+                ui.separator();
+                ui.label(RichText::new("Recent signals").strong());
+                for signal in &app.recent_signals {
+                    if entry(app, ui, signal) {
+                        action = Some(BookmarkAction::GoTo(signal.clone()));
+                    }
+                }
+            }
+        });
+    action
+}
+
+/// One clickable entry; returns whether it was clicked.
+fn entry(app: &SubsystemApp, ui: &mut egui::Ui, bookmark: &Bookmark) -> bool {
+    let icon = match bookmark.target {
+        BookmarkTarget::Subsystem => "📁",
+        BookmarkTarget::Block { .. } => "▪",
+        BookmarkTarget::Signal { .. } => "〰",
+    };
+    let exists = resolve_subsystem_by_vec(&app.root, &bookmark.path).is_some();
+    let location = if bookmark.path.is_empty() {
+        "Root".to_string()
+    } else {
+        format!("/{}", BlockPath::from(&bookmark.path[..]))
+    };
+    ui.add_enabled(
+        exists,
+        egui::Button::new(format!("{icon} {}", bookmark.label)).frame(false),
+    )
+    .on_hover_text(location)
+    .on_disabled_hover_text("No longer in the model")
+    .clicked()
+}
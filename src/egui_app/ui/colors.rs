@@ -53,32 +53,38 @@ pub fn hash_color(input: &str, s: f32, v: f32) -> Color32 {
     hsv_to_color32(h, s, v)
 }
 
+pub fn rgba_to_color32(c: crate::color::Rgba) -> Color32 {
+    Color32::from_rgba_unmultiplied(c.r, c.g, c.b, c.a)
+}
+
+pub fn color32_to_rgba(c: Color32) -> crate::color::Rgba {
+    let [r, g, b, a] = c.to_srgba_unmultiplied();
+    crate::color::Rgba::new(r, g, b, a)
+}
+
+/// Text color for a block: its `ForegroundColor` if set, otherwise a color
+/// contrasting with the block fill.
+pub fn block_foreground_color(block: &crate::model::Block, bg: Color32) -> Color32 {
+    block
+        .foreground_color
+        .map(rgba_to_color32)
+        .unwrap_or_else(|| contrast_color(bg))
+}
+
+/// Blend `tint` over `base`, weighted by the tint's alpha.
+pub fn blend_tint(base: Color32, tint: Color32) -> Color32 {
+    let [tr, tg, tb, ta] = tint.to_srgba_unmultiplied();
+    let t = ta as f32 / 255.0;
+    let mix = |b: u8, o: u8| (b as f32 + (o as f32 - b as f32) * t).round() as u8;
+    Color32::from_rgb(mix(base.r(), tr), mix(base.g(), tg), mix(base.b(), tb))
+}
+
 pub fn block_base_color(
     block: &crate::model::Block,
     cfg: &crate::block_types::BlockTypeConfig,
 ) -> Color32 {
-    if let Some(ref color_str) = block.background_color {
-        let lower = color_str.to_lowercase();
-        match lower.as_str() {
-            "yellow" => return Color32::from_rgb(255, 230, 120),
-            "red" => return Color32::from_rgb(230, 90, 90),
-            "green" => return Color32::from_rgb(120, 210, 140),
-            "blue" => return Color32::from_rgb(100, 160, 230),
-            "black" => return Color32::from_rgb(40, 40, 40),
-            "white" => return Color32::from_rgb(235, 235, 235),
-            "gray" | "grey" => return Color32::from_rgb(180, 180, 180),
-            _ => {
-                if lower.starts_with('#') && lower.len() == 7 {
-                    if let (Ok(r), Ok(g), Ok(b)) = (
-                        u8::from_str_radix(&lower[1..3], 16),
-                        u8::from_str_radix(&lower[3..5], 16),
-                        u8::from_str_radix(&lower[5..7], 16),
-                    ) {
-                        return Color32::from_rgb(r, g, b);
-                    }
-                }
-            }
-        }
+    if let Some(c) = block.background_color {
+        return rgba_to_color32(c);
     }
     if let Some(bg) = cfg.background {
         return Color32::from_rgb(bg.0, bg.1, bg.2);
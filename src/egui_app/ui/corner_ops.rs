@@ -163,7 +163,7 @@ pub fn enforce_orthogonal(points: &mut Vec<Point>) {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{Branch, EndpointRef, Line, Point};
+    use crate::model::{Branch, EndpointRef, Line, Point, PortKind};
     use indexmap::IndexMap;
 
     fn test_line(points: Vec<Point>) -> Line {
@@ -332,7 +332,7 @@ mod tests {
         let mut branches = vec![Branch {
             dst: Some(EndpointRef {
                 sid: "42".to_string(),
-                port_type: "in".to_string(),
+                port_type: PortKind::In,
                 port_index: 1,
             }),
             name: None,
@@ -351,7 +351,7 @@ mod tests {
         let mut branches = vec![Branch {
             dst: Some(EndpointRef {
                 sid: "99".to_string(),
-                port_type: "in".to_string(),
+                port_type: PortKind::In,
                 port_index: 1,
             }),
             name: None,
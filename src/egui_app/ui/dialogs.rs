@@ -1,40 +1,27 @@
 use super::helpers::{block_dialog_title, is_block_subsystem};
 use super::types::UpdateResponse;
 use crate::egui_app::state::{BlockDialog, ChartView, SignalDialog, SubsystemApp};
-use crate::egui_app::text::matlab_syntax_job;
-use crate::model::EndpointRef;
+use crate::egui_app::text::{CodeStyle, c_syntax_job_styled, matlab_syntax_job_styled};
+use crate::model::{CFunctionCode, EndpointRef};
 use eframe::egui::{self, Color32, RichText};
 
 fn build_chart_view_for_block(
     app: &SubsystemApp,
     block: &crate::model::Block,
 ) -> Option<ChartView> {
-    let is_chart_block = block.block_type == "MATLAB Function"
-        || (block.block_type == "SubSystem" && block.is_matlab_function);
-    if !is_chart_block {
-        return None;
-    }
-    let by_sid = block
-        .sid
-        .as_ref()
-        .and_then(|sid| app.chart_map.get(sid))
-        .cloned();
-    let mut instance_name = if app.path.is_empty() {
-        block.name.clone()
-    } else {
-        format!("{}/{}", app.path.join("/"), block.name)
-    };
-    instance_name = instance_name.trim_matches('/').to_string();
-    let cid_opt = by_sid.or_else(|| app.chart_map.get(&instance_name).cloned());
-    let chart = cid_opt.and_then(|cid| app.charts.get(&cid));
-    chart.map(|chart| ChartView {
+    let chart_id = app.chart_id_for_block(block)?;
+    let chart = app.charts.get(&chart_id)?;
+    let script = chart.script.clone().unwrap_or_default();
+    Some(ChartView {
         title: chart
             .name
             .clone()
             .or(chart.eml_name.clone())
             .unwrap_or_else(|| block.name.clone()),
-        script: chart.script.clone().unwrap_or_default(),
+        original_script: script.clone(),
+        script,
         open: true,
+        chart_id: Some(chart_id),
     })
 }
 
@@ -56,6 +43,7 @@ pub fn apply_update_response(app: &mut SubsystemApp, response: &UpdateResponse)
                 line_idx: *line_idx,
                 open: true,
             });
+            app.record_recent_signal(*line_idx);
         }
         UpdateResponse::Block { block, handled, .. } => {
             if *handled {
@@ -86,26 +74,65 @@ pub fn apply_update_response(app: &mut SubsystemApp, response: &UpdateResponse)
 }
 
 fn show_chart_window(app: &mut SubsystemApp, ui: &mut egui::Ui) {
-    if let Some(cv) = &mut app.chart_view {
-        let mut open_flag = cv.open;
-        egui::Window::new(format!("Chart: {}", cv.title))
-            .open(&mut open_flag)
-            .resizable(true)
-            .vscroll(true)
-            .min_width(400.0)
-            .min_height(200.0)
-            .show(ui.ctx(), |ui| {
-                egui::ScrollArea::vertical()
-                    .auto_shrink([false; 2])
-                    .show(ui, |ui| {
-                        let job = matlab_syntax_job(&cv.script);
-                        ui.add(egui::Label::new(job).wrap());
-                    });
-            });
-        cv.open = open_flag;
-        if !cv.open {
-            app.chart_view = None;
-        }
+    let style = app.code_style.clone();
+    let dark_mode = ui.visuals().dark_mode;
+    let Some(cv) = &mut app.chart_view else {
+        return;
+    };
+    let mut open_flag = cv.open;
+    let mut apply = None;
+    let modified = if cv.is_modified() { " *" } else { "" };
+    egui::Window::new(format!("Chart: {}{}", cv.title, modified))
+        .id(egui::Id::new(("rustylink_chart_window", cv.chart_id)))
+        .open(&mut open_flag)
+        .resizable(true)
+        .min_width(400.0)
+        .min_height(200.0)
+        .show(ui.ctx(), |ui| {
+            if cv.chart_id.is_some() {
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(cv.is_modified(), egui::Button::new("Apply"))
+                        .clicked()
+                    {
+                        apply = cv.chart_id.map(|id| (id, cv.script.clone()));
+                        cv.original_script = cv.script.clone();
+                    }
+                    if ui
+                        .add_enabled(cv.is_modified(), egui::Button::new("Revert"))
+                        .clicked()
+                    {
+                        cv.script = cv.original_script.clone();
+                    }
+                });
+                ui.separator();
+            }
+            egui::ScrollArea::vertical()
+                .auto_shrink([false; 2])
+                .show(ui, |ui| {
+                    let mut layouter = |ui: &egui::Ui,
+                                        text: &dyn egui::TextBuffer,
+                                        wrap_width: f32| {
+                        let mut job = matlab_syntax_job_styled(text.as_str(), &style, dark_mode);
+                        job.wrap.max_width = wrap_width;
+                        ui.painter().layout_job(job)
+                    };
+                    ui.add(
+                        egui::TextEdit::multiline(&mut cv.script)
+                            .code_editor()
+                            .interactive(cv.chart_id.is_some())
+                            .desired_width(f32::INFINITY)
+                            .layouter(&mut layouter),
+                    );
+                });
+        });
+    cv.open = open_flag;
+    if !cv.open {
+        app.chart_view = None;
+    }
+    if let Some((chart_id, script)) = apply {
+        app.set_chart_script(chart_id, &script);
+        app.show_notification("Chart script applied", 1500);
     }
 }
 
@@ -281,6 +308,8 @@ fn show_block_window(app: &mut SubsystemApp, ui: &mut egui::Ui) {
         // the title was cleaned when the dialog was created; normalize again just
         // in case the string was mutated by a custom button handler.
         let win_title = crate::parser::helpers::clean_whitespace(&bd.title);
+        let mut c_apply = None;
+        let code_style = app.code_style.clone();
         egui::Window::new(format!("Block: {}", win_title))
             .open(&mut open_flag)
             .resizable(true)
@@ -304,8 +333,10 @@ fn show_block_window(app: &mut SubsystemApp, ui: &mut egui::Ui) {
                     if let Some(z) = &block.zorder {
                         ui.label(format!("Z: {}", z));
                     }
-                    if block.commented {
-                        ui.label("commented");
+                    if block.comment_through {
+                        ui.label("commented through");
+                    } else if block.commented {
+                        ui.label("commented out");
                     }
                 });
                 ui.separator();
@@ -351,51 +382,36 @@ fn show_block_window(app: &mut SubsystemApp, ui: &mut egui::Ui) {
                         egui::CollapsingHeader::new("C/C++ Code")
                             .default_open(true)
                             .show(ui, |ui| {
-                                if let Some(s) = &cfg.start_code {
-                                    ui.label(RichText::new("StartCode").strong());
-                                    ui.add(
-                                        egui::TextEdit::multiline(&mut s.clone())
-                                            .desired_width(f32::INFINITY),
-                                    );
-                                }
-                                if let Some(s) = &cfg.output_code {
-                                    ui.label(RichText::new("OutputCode").strong());
-                                    ui.add(
-                                        egui::TextEdit::multiline(&mut s.clone())
-                                            .desired_width(f32::INFINITY),
-                                    );
-                                }
-                                if let Some(s) = &cfg.terminate_code {
-                                    ui.label(RichText::new("TerminateCode").strong());
-                                    ui.add(
-                                        egui::TextEdit::multiline(&mut s.clone())
-                                            .desired_width(f32::INFINITY),
-                                    );
-                                }
-                                if let Some(s) = &cfg.codegen_start_code {
-                                    ui.label(RichText::new("CodegenStartCode").strong());
-                                    ui.add(
-                                        egui::TextEdit::multiline(&mut s.clone())
-                                            .desired_width(f32::INFINITY),
-                                    );
-                                }
-                                if let Some(s) = &cfg.codegen_output_code {
-                                    ui.label(RichText::new("CodegenOutputCode").strong());
-                                    ui.add(
-                                        egui::TextEdit::multiline(&mut s.clone())
-                                            .desired_width(f32::INFINITY),
-                                    );
-                                }
-                                if let Some(s) = &cfg.codegen_terminate_code {
-                                    ui.label(RichText::new("CodegenTerminateCode").strong());
-                                    ui.add(
-                                        egui::TextEdit::multiline(&mut s.clone())
-                                            .desired_width(f32::INFINITY),
-                                    );
-                                }
+                                c_apply = show_c_function_code(ui, &block, cfg, &code_style);
                             });
                     }
                 }
+                if let Some(conditions) = block.condition_labels() {
+                    ui.separator();
+                    egui::CollapsingHeader::new("Conditions")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for (i, label) in conditions.iter().enumerate() {
+                                ui.label(format!("Out{}: {}", i + 1, label));
+                            }
+                        });
+                }
+                let callbacks = block.all_callbacks();
+                if !callbacks.is_empty() {
+                    ui.separator();
+                    egui::CollapsingHeader::new("Callbacks")
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            for (name, code) in callbacks.iter() {
+                                ui.label(RichText::new(name).strong());
+                                ui.add(
+                                    egui::TextEdit::multiline(&mut code.to_string())
+                                        .code_editor()
+                                        .desired_width(f32::INFINITY),
+                                );
+                            }
+                        });
+                }
                 egui::CollapsingHeader::new("Ports")
                     .default_open(true)
                     .show(ui, |ui| {
@@ -454,6 +470,11 @@ fn show_block_window(app: &mut SubsystemApp, ui: &mut egui::Ui) {
                     });
                 }
             });
+        if let Some(code) = c_apply
+            && app.set_c_function_code(&block, code)
+        {
+            app.show_notification("Code applied", 1500);
+        }
         if let Some(bd_mut) = &mut app.block_view {
             bd_mut.open = open_flag;
             if !bd_mut.open {
@@ -463,6 +484,77 @@ fn show_block_window(app: &mut SubsystemApp, ui: &mut egui::Ui) {
     }
 }
 
+/// Code slots of a CFunction block, each in its own collapsible section.
+/// Read-only with highlighting until Edit is clicked; the edit buffer lives
+/// in egui memory until applied or cancelled. Returns the code to apply.
+fn show_c_function_code(
+    ui: &mut egui::Ui,
+    block: &crate::model::Block,
+    cfg: &CFunctionCode,
+    style: &CodeStyle,
+) -> Option<CFunctionCode> {
+    let dark_mode = ui.visuals().dark_mode;
+    let id = egui::Id::new((
+        "rustylink_c_code",
+        block.sid.as_deref(),
+        block.name.as_str(),
+    ));
+    let mut buffer: Option<CFunctionCode> = ui.data(|d| d.get_temp(id));
+    let mut apply = None;
+    ui.horizontal(|ui| match &buffer {
+        None => {
+            if ui.button("✏ Edit").clicked() {
+                buffer = Some(cfg.clone());
+            }
+        }
+        Some(edited) => {
+            if ui.button("Apply").clicked() {
+                apply = Some(edited.clone());
+                buffer = None;
+            }
+            if ui.button("Cancel").clicked() {
+                buffer = None;
+            }
+        }
+    });
+    let mut layouter = |ui: &egui::Ui, text: &dyn egui::TextBuffer, wrap_width: f32| {
+        let mut job = c_syntax_job_styled(text.as_str(), style, dark_mode);
+        job.wrap.max_width = wrap_width;
+        ui.painter().layout_job(job)
+    };
+    for name in CFunctionCode::SLOT_NAMES {
+        let Some(Some(code)) = cfg.slot(name) else {
+            continue;
+        };
+        egui::CollapsingHeader::new(RichText::new(name).strong())
+            .id_salt((id, name))
+            .default_open(!code.trim().is_empty())
+            .show(ui, |ui| {
+                match buffer.as_mut().and_then(|b| b.slot_mut(name)) {
+                    Some(slot) => {
+                        ui.add(
+                            egui::TextEdit::multiline(slot.get_or_insert_default())
+                                .code_editor()
+                                .desired_width(f32::INFINITY)
+                                .layouter(&mut layouter),
+                        );
+                    }
+                    None => {
+                        ui.add(
+                            egui::Label::new(c_syntax_job_styled(code, style, dark_mode))
+                                .selectable(true),
+                        );
+                    }
+                }
+            });
+    }
+    ui.data_mut(|d| match buffer {
+        Some(b) => d.insert_temp(id, b),
+        None => d.remove::<CFunctionCode>(id),
+    });
+    apply
+}
+
 /// Show a scope popout window with an interactive liveplot.
 #[cfg(feature = "dashboard")]
 fn show_scope_popout_window(app: &mut SubsystemApp, ui: &mut egui::Ui) {
@@ -1,4 +1,4 @@
-use super::types::{ClickAction, UpdateResponse};
+use super::types::{ClickAction, UpdateResponse, ViewerEvent};
 
 /// Normalize a user-facing string by collapsing all whitespace to a single
 /// space and trimming leading/trailing whitespace.  This is used by various
@@ -30,10 +30,28 @@ pub(crate) fn is_block_subsystem(b: &crate::model::Block) -> bool {
             .map_or(false, |sub| sub.chart.is_none())
 }
 
-pub(crate) fn record_interaction(current: &mut UpdateResponse, new: UpdateResponse) {
+/// Clicks of one frame: the one returned from `update` and all of them as
+/// events.
+pub(crate) struct FrameInteractions {
+    pub response: UpdateResponse,
+    pub events: Vec<ViewerEvent>,
+}
+
+impl FrameInteractions {
+    pub fn new() -> Self {
+        Self {
+            response: UpdateResponse::None,
+            events: Vec::new(),
+        }
+    }
+}
+
+pub(crate) fn record_interaction(frame: &mut FrameInteractions, new: UpdateResponse) {
     if matches!(new, UpdateResponse::None) {
         return;
     }
+    frame.events.extend(ViewerEvent::from_response(&new));
+    let current = &mut frame.response;
     fn is_double(resp: &UpdateResponse) -> bool {
         match resp {
             UpdateResponse::Block { action, .. } | UpdateResponse::Signal { action, .. } => {
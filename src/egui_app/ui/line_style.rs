@@ -0,0 +1,76 @@
+//! Stroke styles for signal lines, following Simulink's conventions.
+//!
+//! Scalar lines are plain strokes, nonscalar (vector) lines are wider, bus
+//! lines are drawn as a double line and control (function-call/action) lines
+//! are dashed. The kind of each line comes from
+//! [`crate::signal_kind::infer_line_kinds`].
+
+use crate::signal_kind::SignalKind;
+use eframe::egui::{self, Color32, Pos2, Stroke};
+
+/// Width of scalar, bus and control lines.
+const BASE_WIDTH: f32 = 2.0;
+/// Extra width added to selected lines.
+const SELECTED_EXTRA: f32 = 1.5;
+
+/// How to paint one signal line and its branches.
+#[derive(Debug, Clone, Copy)]
+pub struct LineStyle {
+    pub stroke: Stroke,
+    pub kind: SignalKind,
+    /// Canvas color, used for the gap of bus double lines.
+    pub background: Color32,
+}
+
+impl LineStyle {
+    pub fn new(
+        kind: SignalKind,
+        color: Color32,
+        selected: bool,
+        vector_width: f32,
+        background: Color32,
+    ) -> Self {
+        let base = match kind {
+            SignalKind::Vector(_) => vector_width.max(BASE_WIDTH),
+            _ => BASE_WIDTH,
+        };
+        let width = if selected {
+            base + SELECTED_EXTRA
+        } else {
+            base
+        };
+        Self {
+            stroke: Stroke::new(width, color),
+            kind,
+            background,
+        }
+    }
+
+    /// Paint a connected polyline in this style.
+    pub fn paint_polyline(&self, painter: &egui::Painter, pts: &[Pos2]) {
+        if pts.len() < 2 {
+            return;
+        }
+        match self.kind {
+            SignalKind::Bus => {
+                // Outer strokes first so the gap stays continuous at corners.
+                let outer = Stroke::new(self.stroke.width * 2.5, self.stroke.color);
+                let inner = Stroke::new(self.stroke.width * 0.9, self.background);
+                for seg in pts.windows(2) {
+                    painter.line_segment([seg[0], seg[1]], outer);
+                }
+                for seg in pts.windows(2) {
+                    painter.line_segment([seg[0], seg[1]], inner);
+                }
+            }
+            SignalKind::Control => {
+                painter.extend(egui::Shape::dashed_line(pts, self.stroke, 6.0, 4.0));
+            }
+            SignalKind::Scalar | SignalKind::Vector(_) => {
+                for seg in pts.windows(2) {
+                    painter.line_segment([seg[0], seg[1]], self.stroke);
+                }
+            }
+        }
+    }
+}
@@ -1,15 +1,18 @@
+pub mod bookmarks_panel;
 pub mod colors;
 pub mod corner_ops;
 pub mod dialogs;
 pub mod helpers;
 pub mod line_coloring;
+pub mod line_style;
 pub mod signal_routing;
+pub mod subsystem_tree;
 pub mod types;
 pub mod update;
 pub mod view_transform;
 
 pub use dialogs::{apply_update_response, show_info_windows};
-pub use types::{ClickAction, UpdateResponse};
+pub use types::{ClickAction, UpdateResponse, ViewerEvent};
 
 use crate::egui_app::state::SubsystemApp;
 use eframe::egui;
@@ -0,0 +1,112 @@
+//! Subsystem tree panel of the viewer.
+//!
+//! Lists the subsystem hierarchy with the number of blocks in each
+//! subsystem; charts are marked and open the level containing them with the
+//! chart block selected. Navigating on the canvas expands the tree down to
+//! the shown subsystem and scrolls it into view; collapsed branches are not
+//! walked.
+
+use crate::egui_app::navigation::child_subsystems;
+use crate::egui_app::state::SubsystemApp;
+use crate::model::System;
+use eframe::egui;
+
+/// Where a click in the tree leads: the subsystem to show and the SID of a
+/// block to select in it.
+pub(crate) type TreeTarget = (Vec<String>, Option<String>);
+
+/// Draw the tree; returns the clicked target, if any.
+pub(crate) fn show_subsystem_tree(app: &mut SubsystemApp, ui: &mut egui::Ui) -> Option<TreeTarget> {
+    let reveal = app.tree_revealed_path.as_ref() != Some(&app.path);
+    let mut target = None;
+    let tree = Tree {
+        current: &app.path,
+        reveal,
+        id: app.egui_id("subsystem_tree"),
+    };
+    egui::ScrollArea::both()
+        .auto_shrink([false, false])
+        .show(ui, |ui| {
+            let is_root = app.path.is_empty();
+            let label = format!("Root ({})", app.root.blocks.len());
+            let resp = ui.selectable_label(is_root, label);
+            if is_root && reveal {
+                resp.scroll_to_me(None);
+            }
+            if resp.clicked() {
+                target = Some((Vec::new(), None));
+            }
+            tree.children(ui, &app.root, &mut Vec::new(), &mut target);
+        });
+    app.tree_revealed_path = Some(app.path.clone());
+    target
+}
+
+struct Tree<'a> {
+    /// Path of the shown subsystem.
+    current: &'a [String],
+    /// Expand the ancestors of `current` and scroll to it this frame.
+    reveal: bool,
+    id: egui::Id,
+}
+
+impl Tree<'_> {
+    fn children(
+        &self,
+        ui: &mut egui::Ui,
+        system: &System,
+        path: &mut Vec<String>,
+        target: &mut Option<TreeTarget>,
+    ) {
+        for (block, sub) in child_subsystems(system) {
+            if sub.chart.is_some() {
+                let resp = ui
+                    .selectable_label(false, format!("📊 {}", block.name))
+                    .on_hover_text("Chart");
+                if resp.clicked() {
+                    *target = Some((path.clone(), block.sid.clone()));
+                }
+                continue;
+            }
+            path.push(block.name.clone());
+            let is_current = path.as_slice() == self.current;
+            let label = format!("{} ({})", block.name, sub.blocks.len());
+            if child_subsystems(sub).next().is_none() {
+                self.node_label(ui, label, is_current, path, target);
+            } else {
+                let id = self.id.with(&*path);
+                let mut state = egui::collapsing_header::CollapsingState::load_with_default_open(
+                    ui.ctx(),
+                    id,
+                    false,
+                );
+                if self.reveal && !is_current && self.current.starts_with(path) {
+                    state.set_open(true);
+                }
+                state
+                    .show_header(ui, |ui| {
+                        self.node_label(ui, label, is_current, path, target);
+                    })
+                    .body(|ui| self.children(ui, sub, path, target));
+            }
+            path.pop();
+        }
+    }
+
+    fn node_label(
+        &self,
+        ui: &mut egui::Ui,
+        label: String,
+        is_current: bool,
+        path: &[String],
+        target: &mut Option<TreeTarget>,
+    ) {
+        let resp = ui.selectable_label(is_current, label);
+        if is_current && self.reveal {
+            resp.scroll_to_me(None);
+        }
+        if resp.clicked() {
+            *target = Some((path.to_vec(), None));
+        }
+    }
+}
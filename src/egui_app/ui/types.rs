@@ -1,4 +1,5 @@
 use crate::model::{Block, Line};
+use eframe::egui::Vec2;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ClickAction {
@@ -23,3 +24,57 @@ pub enum UpdateResponse {
         handled: bool,
     },
 }
+
+/// Viewer interaction, collected per frame for
+/// [`SubsystemApp::take_events`](crate::egui_app::SubsystemApp::take_events).
+/// Unlike the [`UpdateResponse`] returned from `update`, which keeps one
+/// click per frame, every interaction is reported.
+#[derive(Clone, Debug)]
+pub enum ViewerEvent {
+    BlockClicked {
+        action: ClickAction,
+        block: Box<Block>,
+        handled: bool,
+    },
+    SignalClicked {
+        action: ClickAction,
+        line_idx: usize,
+        line: Box<Line>,
+        handled: bool,
+    },
+    /// The pointer moved onto a signal.
+    SignalHovered { line_idx: usize, line: Box<Line> },
+    /// Another subsystem is shown; the path is empty for the root.
+    SubsystemEntered { path: Vec<String> },
+    /// Zoom or pan of the canvas changed.
+    ViewChanged { zoom: f32, pan: Vec2 },
+}
+
+impl ViewerEvent {
+    /// The click of `response`, if any.
+    pub fn from_response(response: &UpdateResponse) -> Option<Self> {
+        match response.clone() {
+            UpdateResponse::None => None,
+            UpdateResponse::Block {
+                action,
+                block,
+                handled,
+            } => Some(Self::BlockClicked {
+                action,
+                block: Box::new(block),
+                handled,
+            }),
+            UpdateResponse::Signal {
+                action,
+                line_idx,
+                line,
+                handled,
+            } => Some(Self::SignalClicked {
+                action,
+                line_idx,
+                line: Box::new(line),
+                handled,
+            }),
+        }
+    }
+}
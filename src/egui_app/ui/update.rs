@@ -1,15 +1,24 @@
-use super::colors::{block_base_color, contrast_color};
+use super::bookmarks_panel::{self, BookmarkAction};
+use super::colors::{
+    blend_tint, block_base_color, block_foreground_color, contrast_color, rgba_to_color32,
+};
 use super::corner_ops;
-use super::helpers::{is_block_subsystem, record_interaction};
+use super::helpers::{FrameInteractions, is_block_subsystem, record_interaction};
 use super::line_coloring;
+use super::line_style::LineStyle;
 use super::signal_routing;
-use super::types::{ClickAction, UpdateResponse};
+use super::subsystem_tree;
+use super::types::{ClickAction, UpdateResponse, ViewerEvent};
 use super::view_transform;
+use crate::block_path::BlockPath;
 use crate::block_types::BlockShape;
+use crate::builtin_libraries::virtual_library::PortPlacement;
 use crate::editor::operations;
 #[cfg(feature = "dashboard")]
 use crate::egui_app::DashboardControlValue;
-use crate::egui_app::geometry::endpoint_pos_maybe_mirrored;
+use crate::egui_app::bookmarks::Bookmark;
+use crate::egui_app::context_menu::show_context_menu_items;
+use crate::egui_app::geometry::{PortAnchors, PortFrame, block_outline, control_port_symbol};
 use crate::egui_app::geometry::{parse_block_rect, parse_rect_str};
 use crate::egui_app::navigation::resolve_subsystem_by_vec;
 #[cfg(not(feature = "dashboard"))]
@@ -18,22 +27,28 @@ use crate::egui_app::render::{
     ComputedPortYCoordinates, PortLabelMaxWidths, port_label_display_name,
 };
 use crate::egui_app::render::{
-    get_block_type_cfg, get_interior_renderer, render_block_icon, render_manual_switch,
-    wrap_text_to_max_width,
+    get_block_type_cfg, get_interior_renderer, paint_pass_through_arrow, render_block_icon,
+    render_manual_switch, wrap_text_to_max_width,
 };
 use crate::egui_app::state::ViewerDragState;
 use crate::egui_app::state::{SubsystemApp, resolve_subsystem_by_vec_mut};
 use crate::egui_app::text::highlight_query_job;
+use crate::focus_nav::FocusDirection;
+use crate::overlay::MetricOverlay;
 use eframe::egui::{self, Align2, Color32, Pos2, Rect, RichText, Sense, Stroke, Vec2};
 use std::collections::HashMap;
 
+/// Outline around the block with keyboard focus.
+const FOCUS_RING_COLOR: Color32 = Color32::from_rgb(255, 140, 0);
+
 pub(crate) fn update_internal(
     app: &mut SubsystemApp,
     ui: &mut egui::Ui,
     enable_context_menus: bool,
 ) -> UpdateResponse {
-    let mut interaction = UpdateResponse::None;
+    let mut interaction = FrameInteractions::new();
     let mut navigate_to: Option<Vec<String>> = None;
+    let mut new_bookmark: Option<Bookmark> = None;
     let mut clear_search = false;
     let path_snapshot = app.path.clone();
 
@@ -47,6 +62,9 @@ pub(crate) fn update_internal(
                 navigate_to = Some(p);
             }
             ui.separator();
+            ui.toggle_value(&mut app.show_subsystem_tree, "🌲 Tree");
+            ui.toggle_value(&mut app.show_bookmarks, "★ Bookmarks");
+            ui.separator();
             ui.label(RichText::new("Path:").strong());
             if ui.link("Root").clicked() {
                 navigate_to = Some(Vec::new());
@@ -66,9 +84,22 @@ pub(crate) fn update_internal(
             if resp.changed() {
                 app.update_search_matches();
             }
+            if !app.search_history.is_empty() {
+                ui.menu_button("Recent", |ui| {
+                    for query in app.search_history.clone() {
+                        if ui.button(&query).clicked() {
+                            app.search_query = query;
+                            app.update_search_matches();
+                            ui.close();
+                        }
+                    }
+                });
+            }
 
             ui.separator();
             ui.checkbox(&mut app.show_block_names_default, "Block names");
+            ui.checkbox(&mut app.joint_label_placement, "Joint labels")
+                .on_hover_text("Place all signal labels together to reduce overlaps");
             ui.label("Name size");
             ui.add(
                 egui::DragValue::new(&mut app.block_name_font_factor)
@@ -87,6 +118,27 @@ pub(crate) fn update_internal(
                     .speed(0.01)
                     .range(0.05..=0.5),
             );
+            ui.label("Vector width");
+            ui.add(
+                egui::DragValue::new(&mut app.vector_line_width)
+                    .speed(0.1)
+                    .range(1.0..=8.0),
+            );
+            if app.overlay_names().next().is_some() {
+                ui.separator();
+                let names: Vec<String> = app.overlay_names().map(str::to_string).collect();
+                let mut selected = app.active_overlay().map(|o| o.name.clone());
+                egui::ComboBox::from_id_salt(app.egui_id("overlay_select"))
+                    .selected_text(selected.as_deref().unwrap_or("No overlay"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut selected, None, "No overlay");
+                        for name in names {
+                            let label = name.clone();
+                            ui.selectable_value(&mut selected, Some(name), label);
+                        }
+                    });
+                app.set_active_overlay(selected.as_deref());
+            }
             ui.separator();
             let move_label = if app.move_mode_enabled {
                 "Edit: On"
@@ -179,7 +231,7 @@ pub(crate) fn update_internal(
                     .max_height(200.0)
                     .show(ui, |ui| {
                         for p in app.search_matches.clone() {
-                            let label = format!("/{}", p.join("/"));
+                            let label = format!("/{}", BlockPath::from(&p[..]));
                             let job = highlight_query_job(&label, &app.search_query);
                             let resp = ui.add(egui::Label::new(job).sense(Sense::click()));
                             if resp.clicked() {
@@ -192,6 +244,25 @@ pub(crate) fn update_internal(
         }
     });
 
+    let mut tree_target = None;
+    if app.show_subsystem_tree {
+        egui::SidePanel::left(app.egui_id("subsystem_tree_panel"))
+            .resizable(true)
+            .default_width(220.0)
+            .show_inside(ui, |ui| {
+                tree_target = subsystem_tree::show_subsystem_tree(app, ui);
+            });
+    }
+    let mut bookmark_action = None;
+    if app.show_bookmarks {
+        egui::SidePanel::right(app.egui_id("bookmarks_panel"))
+            .resizable(true)
+            .default_width(220.0)
+            .show_inside(ui, |ui| {
+                bookmark_action = bookmarks_panel::show_bookmarks(app, ui);
+            });
+    }
+
     // Owned snapshot for use inside the UI closure to avoid immutable borrows of `app`
     let entities_opt = app.current_entities();
     let system_valid = entities_opt.is_some();
@@ -209,8 +280,13 @@ pub(crate) fn update_internal(
 
     // Temporary variable to store block to open as subsystem
     let mut block_to_open_subsystem: Option<crate::model::Block> = None;
+    // Set by Escape; applied after the frame like other navigation.
+    let mut keyboard_go_up = false;
+    // Signal under the pointer, reported when it changes.
+    let mut hovered_line: Option<usize> = None;
+    // Clicked annotation link or click callback, followed after the frame.
+    let mut clicked_link: Option<String> = None;
     // Snapshots for use inside closure (avoid borrowing `app` immutably inside UI rendering)
-    let block_click_handler_snapshot = app.block_click_handler.clone();
     let block_menu_items_snapshot = app.block_menu_items.clone();
     let signal_menu_items_snapshot = app.signal_menu_items.clone();
 
@@ -220,7 +296,7 @@ pub(crate) fn update_internal(
             let requested = if app.path.is_empty() {
                 String::from("/")
             } else {
-                format!("/{}", app.path.join("/"))
+                format!("/{}", BlockPath::from(&app.path[..]))
             };
             ui.colored_label(Color32::RED, "Invalid path — nothing to render");
             ui.label(format!("Requested path: {}", requested));
@@ -238,7 +314,10 @@ pub(crate) fn update_internal(
                 }
             }
             if !existing_parent.is_empty() {
-                ui.label(format!("Nearest existing parent: /{}", existing_parent.join("/")));
+                ui.label(format!(
+                    "Nearest existing parent: /{}",
+                    BlockPath::from(existing_parent.as_slice())
+                ));
                 if let Some(parent_sys) = resolve_subsystem_by_vec(&app.root, &existing_parent) {
                     let names: Vec<String> = parent_sys
                         .blocks
@@ -292,6 +371,40 @@ pub(crate) fn update_internal(
         // Use entities snapshot for this frame
         let entities = entities_opt.as_ref().unwrap();
 
+        // ── Keyboard navigation: arrows move the focus, Enter opens the focused
+        // block like a double-click, Escape goes up one level ──
+        let mut focus_moved = false;
+        let mut keyboard_open_sid: Option<String> = None;
+        if !ui.ctx().wants_keyboard_input() {
+            let (direction, enter, escape) = ui.input(|i| {
+                let direction = if !i.modifiers.is_none() {
+                    None
+                } else if i.key_pressed(egui::Key::ArrowLeft) {
+                    Some(FocusDirection::Left)
+                } else if i.key_pressed(egui::Key::ArrowRight) {
+                    Some(FocusDirection::Right)
+                } else if i.key_pressed(egui::Key::ArrowUp) {
+                    Some(FocusDirection::Up)
+                } else if i.key_pressed(egui::Key::ArrowDown) {
+                    Some(FocusDirection::Down)
+                } else {
+                    None
+                };
+                (
+                    direction,
+                    i.key_pressed(egui::Key::Enter),
+                    i.key_pressed(egui::Key::Escape),
+                )
+            });
+            if let Some(direction) = direction {
+                focus_moved = app.move_focus(direction);
+            }
+            if enter {
+                keyboard_open_sid = app.focused_block().and_then(|b| b.sid.clone());
+            }
+            keyboard_go_up = escape;
+        }
+
         // ── Keyboard shortcuts for undo/redo ──
         if app.move_mode_enabled {
             let undo_requested = ui.input(|i| {
@@ -327,8 +440,7 @@ pub(crate) fn update_internal(
             let mut bc = b.clone();
             // Do not overwrite if already present
             bc.properties
-                .entry("SystemName".to_string())
-                .or_insert(system_name.clone());
+                .get_or_insert_with("SystemName", || system_name.clone());
             enriched_blocks.push(bc);
         }
         let blocks: Vec<(&crate::model::Block, Rect)> = enriched_blocks
@@ -399,26 +511,25 @@ pub(crate) fn update_internal(
             Sense::click_and_drag()
         };
         let canvas_resp = ui.interact(avail, ui.id().with("canvas"), canvas_sense);
-        if !app.move_mode_enabled && canvas_resp.dragged() {
+        let nav = ui.input(view_transform::canvas_navigation);
+        if !app.move_mode_enabled && canvas_resp.dragged() && !nav.multi_touch {
             let d = canvas_resp.drag_delta();
             staged_pan += d;
         }
-        let scroll_y = ui.input(|i| i.raw_scroll_delta.y);
-        if scroll_y.abs() > 0.0 && canvas_resp.hovered() {
-            let factor = (1.0_f32 + scroll_y as f32 * 0.001_f32).max(0.1_f32);
-            let old_zoom = staged_zoom;
-            let new_zoom = (old_zoom * factor).clamp(0.2, 10.0);
-            if (new_zoom - old_zoom).abs() > f32::EPSILON {
-                let origin = Pos2::new(avail.left() + margin, avail.top() + margin);
-                let s_old = base_scale * old_zoom;
-                let s_new = base_scale * new_zoom;
-                let cursor = canvas_resp.hover_pos().unwrap_or(avail.center());
-                let world_x = (cursor.x - origin.x - staged_pan.x) / s_old + bb.left();
-                let world_y = (cursor.y - origin.y - staged_pan.y) / s_old + bb.top();
-                staged_zoom = new_zoom;
-                staged_pan.x = cursor.x - ((world_x - bb.left()) * s_new + origin.x);
-                staged_pan.y = cursor.y - ((world_y - bb.top()) * s_new + origin.y);
-            }
+        if !nav.is_none() && (canvas_resp.hovered() || nav.multi_touch) {
+            let vt = view_transform::ViewTransform {
+                bb,
+                avail,
+                margin,
+                base_scale,
+                zoom: staged_zoom,
+                pan: staged_pan + nav.pan,
+            };
+            let center = nav
+                .zoom_center
+                .or(canvas_resp.hover_pos())
+                .unwrap_or(avail.center());
+            (staged_zoom, staged_pan) = vt.zoom_at(center, nav.zoom_factor);
         }
 
         egui::Area::new("zoom_controls".into())
@@ -490,6 +601,12 @@ pub(crate) fn update_internal(
             }
         }
 
+        fn paint_drop_shadow(painter: &egui::Painter, r: Rect, rounding: f32, font_scale: f32) {
+            // Simulink draws a solid shadow offset down and to the right.
+            let offset = egui::vec2(4.0, 4.0) * font_scale.max(0.2);
+            painter.rect_filled(r.translate(offset), rounding, Color32::from_black_alpha(90));
+        }
+
         // Collect scope blocks for deferred liveplot rendering (after painter borrow ends).
         #[cfg(feature = "dashboard")]
         let mut deferred_scope_rects: Vec<(String, Rect)> = Vec::new();
@@ -520,7 +637,14 @@ pub(crate) fn update_internal(
             };
             let resp = ui.allocate_rect(r_screen, block_sense);
             let cfg = get_block_type_cfg(b);
-            let bg = block_base_color(b, &cfg);
+            let bg = match app.overlay_color(b) {
+                Some(c) => rgba_to_color32(c),
+                None => block_base_color(b, &cfg),
+            };
+            let bg = match app.block_style(b).and_then(|s| s.tint) {
+                Some(tint) => blend_tint(bg, tint),
+                None => bg,
+            };
             let mut effective_bg = bg;
 
             if app.move_mode_enabled && resp.drag_started() {
@@ -592,6 +716,9 @@ pub(crate) fn update_internal(
                 }
                 block_action = Some(ClickAction::Primary);
             }
+            if block_action.is_none() && b.sid.is_some() && b.sid == keyboard_open_sid {
+                block_action = Some(ClickAction::DoublePrimary);
+            }
 
             // Selection: single-click selects, Shift-click toggles (multi-select).
             // This is independent from block dialogs (which remain available on double-click).
@@ -610,6 +737,7 @@ pub(crate) fn update_internal(
                         app.selected_block_sids.insert(sid.clone());
                     }
                     app.selected_line_indices.clear();
+                    app.focused_block_sid = Some(sid.clone());
                 }
             }
 
@@ -650,18 +778,20 @@ pub(crate) fn update_internal(
                 }
             }
 
+            if b.drop_shadow && !b.commented {
+                let rounding = match cfg.shape {
+                    BlockShape::Rectangle => 6.0,
+                    BlockShape::Rounded => r_screen.size().min_elem() / 4.0,
+                    _ => 0.0,
+                };
+                paint_drop_shadow(ui.painter(), r_screen, rounding, font_scale);
+            }
             match cfg.shape {
-                BlockShape::Triangle => {
-                    // Gain-style: right-pointing triangle fill.
-                    // Vertices: left-top, right-center, left-bottom.
-                    let pts = vec![
-                        egui::pos2(r_screen.left(), r_screen.top()),
-                        egui::pos2(r_screen.right(), r_screen.center().y),
-                        egui::pos2(r_screen.left(), r_screen.bottom()),
-                    ];
-                    let mut tri = egui::epaint::PathShape::closed_line(pts, Stroke::NONE);
-                    tri.fill = bg;
-                    ui.painter().add(egui::Shape::Path(tri));
+                BlockShape::Triangle | BlockShape::Trapezoid => {
+                    let pts = block_outline(b, &cfg, r_screen).unwrap_or_default();
+                    let mut outline = egui::epaint::PathShape::closed_line(pts, Stroke::NONE);
+                    outline.fill = bg;
+                    ui.painter().add(egui::Shape::Path(outline));
                 }
                 BlockShape::Circle => {
                     let center = r_screen.center();
@@ -680,6 +810,10 @@ pub(crate) fn update_internal(
                         ui.painter().rect_filled(r_screen, 6.0, bg);
                     }
                 }
+                BlockShape::Rounded => {
+                    let rounding = r_screen.size().min_elem() / 4.0;
+                    ui.painter().rect_filled(r_screen, rounding, bg);
+                }
             }
             if enable_context_menus {
                 resp.context_menu(|ui| {
@@ -694,14 +828,11 @@ pub(crate) fn update_internal(
                         );
                         ui.close();
                     }
-                    for item in &block_menu_items_snapshot {
-                        if (item.filter)(b) {
-                            if ui.button(&item.label).clicked() {
-                                (item.on_click)(b);
-                                ui.close();
-                            }
-                        }
+                    if ui.button("☆ Bookmark").clicked() {
+                        new_bookmark = Some(Bookmark::block(path_snapshot.clone(), b));
+                        ui.close();
                     }
+                    show_context_menu_items(ui, &block_menu_items_snapshot, b, true);
                 });
             }
             if let Some(action) = block_action {
@@ -710,9 +841,7 @@ pub(crate) fn update_internal(
                     // Primary click is used for selection; keep it from opening dialogs/subsystems.
                     handled = true;
                 } else if matches!(action, ClickAction::DoublePrimary) {
-                    if let Some(handler) = block_click_handler_snapshot.as_ref() {
-                        handled = handler(app, b);
-                    }
+                    handled = app.run_block_double_click_handlers(b);
                     // Double-click on Constant block opens inline editor.
                     #[cfg(feature = "dashboard")]
                     if !handled && b.block_type == "Constant" {
@@ -822,31 +951,64 @@ pub(crate) fn update_internal(
             block_views.push((b, r_screen, resp.clicked(), effective_bg));
         }
 
-        // Draw annotations (convert HTML-rich content to plain text) without background
+        // Draw annotations (convert HTML-rich content to plain text); background only if set
         for (a, r_model) in &annotations {
             let r_screen = Rect::from_min_max(to_screen(r_model.min), to_screen(r_model.max));
-            let _resp = ui.allocate_rect(r_screen, Sense::hover());
             let raw = a.text.clone().unwrap_or_default();
             let parsed =
                 crate::egui_app::text::annotation_to_rich_text(&raw, a.interpreter.as_deref());
+            let has_links = !parsed.links().is_empty();
+            let callback = a.click_callback();
+            let sense = if has_links || callback.is_some() {
+                Sense::click()
+            } else {
+                Sense::hover()
+            };
+            let resp = ui.allocate_rect(r_screen, sense);
             let base_font = 12.0;
             let mut job = parsed.to_layout_job(ui.style(), font_scale, base_font);
+            crate::egui_app::text::apply_annotation_style(
+                &mut job,
+                a,
+                ui.visuals().text_color(),
+                ui.visuals().strong_text_color(),
+            );
+            if a.drop_shadow {
+                paint_drop_shadow(ui.painter(), r_screen, 0.0, font_scale);
+            }
+            if let Some(bg) = a.background_color {
+                ui.painter().rect_filled(r_screen, 0.0, rgba_to_color32(bg));
+            }
             job.wrap.max_width = f32::INFINITY;
-            let galley = ui.painter().layout_job(job.clone());
+            let mut galley = ui.painter().layout_job(job.clone());
             let paint_pos = r_screen.left_top();
             if galley.size().x <= r_screen.width() {
-                ui.painter().galley(paint_pos, galley, Color32::WHITE);
+                ui.painter().galley(paint_pos, galley.clone(), Color32::WHITE);
             } else {
                 job.wrap.max_width = r_screen.width();
-                let job_for_wrap = job.clone();
-                ui.scope_builder(egui::UiBuilder::new().max_rect(r_screen), |child_ui| {
-                    let wrapped = child_ui.painter().layout_job(job_for_wrap);
-                    child_ui
-                        .painter()
-                        .galley(paint_pos, wrapped, Color32::WHITE);
-                });
+                galley = ui.painter().layout_job(job);
+                ui.painter()
+                    .with_clip_rect(r_screen)
+                    .galley(paint_pos, galley.clone(), Color32::WHITE);
+            }
+            // Links under the pointer win over the annotation's click callback
+            let mut link = None;
+            if has_links
+                && let Some(pointer) = resp.hover_pos()
+                && galley.rect.translate(paint_pos.to_vec2()).contains(pointer)
+            {
+                let index = galley.cursor_from_pos(pointer - paint_pos).index;
+                link = parsed
+                    .link_at(index)
+                    .or_else(|| index.checked_sub(1).and_then(|i| parsed.link_at(i)));
+            }
+            if let Some(link) = link.or(callback.filter(|_| resp.hovered())) {
+                ui.ctx().set_cursor_icon(egui::CursorIcon::PointingHand);
+                if resp.clicked() {
+                    clicked_link = Some(link.to_string());
+                }
+                resp.on_hover_text(link);
             }
-            // no special tooltip; text is directly visible inside the rectangle
         }
 
         // Precompute lookup maps
@@ -863,6 +1025,8 @@ pub(crate) fn update_internal(
             let line_adjacency = line_coloring::compute_line_adjacency(&entities.lines);
             let bg_lum = line_coloring::rel_luminance(Color32::from_gray(245));
             app.view_cache.line_colors = line_coloring::assign_line_colors(&line_adjacency, bg_lum);
+            app.view_cache.line_kinds =
+                crate::signal_kind::infer_line_kinds(&entities.blocks, &entities.lines);
 
             let block_refs: Vec<&crate::model::Block> = blocks.iter().map(|(b, _)| *b).collect();
             let (pc, cp) = signal_routing::compute_port_info(
@@ -874,6 +1038,7 @@ pub(crate) fn update_internal(
             app.view_cache.mark_valid(&app.path, cache_gen);
         }
         let line_colors = app.view_cache.line_colors.clone();
+        let line_kinds = app.view_cache.line_kinds.clone();
         let port_counts = app.view_cache.port_counts.clone();
         let connected_ports = app.view_cache.connected_ports.clone();
 
@@ -890,11 +1055,11 @@ pub(crate) fn update_internal(
         )> = Vec::new();
         let mut port_label_requests: Vec<(String, u32, bool, f32)> = Vec::new();
         let mut port_y_screen: HashMap<(String, u32, bool), f32> = HashMap::new();
-        // Precompute mirroring for each block SID in this view
-        let mut sid_mirrored: HashMap<String, bool> = HashMap::new();
+        // Precompute mirroring and rotation for each block SID in this view
+        let mut sid_anchors: HashMap<String, PortAnchors> = HashMap::new();
         for (b, _r) in &blocks {
             if let Some(sid) = &b.sid {
-                sid_mirrored.insert(sid.clone(), b.block_mirror.unwrap_or(false));
+                sid_anchors.insert(sid.clone(), PortAnchors::of(b));
             }
         }
         for (li, line) in entities.lines.iter().enumerate() {
@@ -908,8 +1073,8 @@ pub(crate) fn update_internal(
             let num_src = port_counts
                 .get(&(src.sid.clone(), if src.port_type == "out" { 1 } else { 0 }))
                 .copied();
-            let mirrored_src = sid_mirrored.get(&src.sid).copied().unwrap_or(false);
-            let mut cur = endpoint_pos_maybe_mirrored(*sr, src, num_src, mirrored_src);
+            let anchors_src = sid_anchors.get(&src.sid).cloned().unwrap_or_default();
+            let mut cur = anchors_src.endpoint_pos(*sr, src, num_src);
             offsets_pts.push(cur);
             for off in &line.points {
                 cur = Pos2::new(cur.x + off.x as f32, cur.y + off.y as f32);
@@ -931,18 +1096,8 @@ pub(crate) fn update_internal(
                     let num_dst = port_counts
                         .get(&(dst.sid.clone(), if dst.port_type == "out" { 1 } else { 0 }))
                         .copied();
-                    let mirrored_dst = entities
-                        .blocks
-                        .iter()
-                        .find(|b| b.sid.as_ref() == Some(&dst.sid))
-                        .and_then(|b| b.block_mirror)
-                        .unwrap_or(false);
-                    let dst_pt = endpoint_pos_maybe_mirrored(
-                        *dr,
-                        dst,
-                        num_dst,
-                        mirrored_dst,
-                    );
+                    let anchors_dst = sid_anchors.get(&dst.sid).cloned().unwrap_or_default();
+                    let dst_pt = anchors_dst.endpoint_pos(*dr, dst, num_dst);
                     let dst_screen = to_screen(dst_pt);
                     screen_pts.push(dst_screen);
                     if dst.port_type == "in" {
@@ -971,7 +1126,7 @@ pub(crate) fn update_internal(
                     br,
                     &mut segments_all,
                     &mut port_y_screen,
-                    &sid_mirrored,
+                    &sid_anchors,
                 );
             }
             let pad = 8.0;
@@ -1020,7 +1175,7 @@ pub(crate) fn update_internal(
             br: &crate::model::Branch,
             out: &mut Vec<(Pos2, Pos2)>,
             port_y_screen: &mut HashMap<(String, u32, bool), f32>,
-            sid_mirrored: &HashMap<String, bool>,
+            sid_anchors: &HashMap<String, PortAnchors>,
         ) {
             let mut pts: Vec<Pos2> = vec![start];
             let mut cur = start;
@@ -1037,13 +1192,8 @@ pub(crate) fn update_internal(
                         if dstb.port_type == "out" { 1 } else { 0 },
                     );
                     let num_dst = port_counts.get(&key).copied();
-                    let mirrored_dst = sid_mirrored.get(&dstb.sid).copied().unwrap_or(false);
-                    let end_pt = crate::egui_app::geometry::endpoint_pos_maybe_mirrored(
-                        *dr,
-                        dstb,
-                        num_dst,
-                        mirrored_dst,
-                    );
+                    let anchors_dst = sid_anchors.get(&dstb.sid).cloned().unwrap_or_default();
+                    let end_pt = anchors_dst.endpoint_pos(*dr, dstb, num_dst);
                     let a = to_screen(*pts.last().unwrap_or(&cur));
                     let b = to_screen(end_pt);
                     signal_routing::push_orthogonal_segments(&[a, b], out);
@@ -1061,7 +1211,7 @@ pub(crate) fn update_internal(
                     sub,
                     out,
                     port_y_screen,
-                    sid_mirrored,
+                    sid_anchors,
                 );
             }
         }
@@ -1073,7 +1223,7 @@ pub(crate) fn update_internal(
             tail: Pos2,
             tip: Pos2,
             color: Color32,
-            stroke: Stroke,
+            style: &LineStyle,
         ) {
             let size = 8.0_f32;
             let dir = Vec2::new(tip.x - tail.x, tip.y - tail.y);
@@ -1082,7 +1232,7 @@ pub(crate) fn update_internal(
             let uy = dir.y / len;
             let inset = size * 0.6;
             let tip_adj = Pos2::new(tip.x - ux * inset, tip.y - uy * inset);
-            painter.line_segment([tail, tip_adj], stroke);
+            style.paint_polyline(painter, &[tail, tip_adj]);
 
             let px = -uy;
             let py = ux;
@@ -1103,10 +1253,10 @@ pub(crate) fn update_internal(
             port_counts: &HashMap<(String, u8), u32>,
             start: Pos2,
             br: &crate::model::Branch,
-            stroke: Stroke,
+            style: LineStyle,
             color: Color32,
             port_label_requests: &mut Vec<(String, u32, bool, f32)>,
-            sid_mirrored: &HashMap<String, bool>,
+            sid_anchors: &HashMap<String, PortAnchors>,
         ) {
             let mut pts: Vec<Pos2> = vec![start];
             let mut cur = start;
@@ -1115,9 +1265,7 @@ pub(crate) fn update_internal(
                 pts.push(cur);
             }
             let screen_pts: Vec<Pos2> = pts.iter().map(|p| to_screen(*p)).collect();
-            for seg in signal_routing::orthogonalize_polyline(&screen_pts).windows(2) {
-                painter.line_segment([seg[0], seg[1]], stroke);
-            }
+            style.paint_polyline(painter, &signal_routing::orthogonalize_polyline(&screen_pts));
             if let Some(dstb) = &br.dst {
                 if let Some(dr) = sid_map.get(&dstb.sid) {
                     let key = (
@@ -1125,30 +1273,21 @@ pub(crate) fn update_internal(
                         if dstb.port_type == "out" { 1 } else { 0 },
                     );
                     let num_dst = port_counts.get(&key).copied();
-                    let mirrored_dst = sid_mirrored.get(&dstb.sid).copied().unwrap_or(false);
-                    let end_pt = endpoint_pos_maybe_mirrored(
-                        *dr,
-                        dstb,
-                        num_dst,
-                        mirrored_dst,
-                    );
+                    let anchors_dst = sid_anchors.get(&dstb.sid).cloned().unwrap_or_default();
+                    let end_pt = anchors_dst.endpoint_pos(*dr, dstb, num_dst);
                     let last = *pts.last().unwrap_or(&cur);
                     let a = to_screen(last);
                     let b = to_screen(end_pt);
                     let ortho = signal_routing::orthogonalize_polyline(&[a, b]);
                     if dstb.port_type == "in" {
-                        for seg in ortho.windows(2).take(ortho.len().saturating_sub(2)) {
-                            painter.line_segment([seg[0], seg[1]], stroke);
-                        }
                         if ortho.len() >= 2 {
                             let n = ortho.len();
-                            draw_arrow_with_trim(painter, ortho[n - 2], ortho[n - 1], color, stroke);
+                            style.paint_polyline(painter, &ortho[..n - 1]);
+                            draw_arrow_with_trim(painter, ortho[n - 2], ortho[n - 1], color, &style);
                         }
                         port_label_requests.push((dstb.sid.clone(), dstb.port_index, true, b.y));
                     } else {
-                        for seg in ortho.windows(2) {
-                            painter.line_segment([seg[0], seg[1]], stroke);
-                        }
+                        style.paint_polyline(painter, &ortho);
                     }
                 }
             }
@@ -1160,10 +1299,10 @@ pub(crate) fn update_internal(
                     port_counts,
                     *pts.last().unwrap_or(&cur),
                     sub,
-                    stroke,
+                    style,
                     color,
                     port_label_requests,
-                    sid_mirrored,
+                    sid_anchors,
                 );
             }
         }
@@ -1186,9 +1325,12 @@ pub(crate) fn update_internal(
                 .get(*li)
                 .copied()
                 .unwrap_or(line_stroke_default.color);
-            let stroke = Stroke::new(
-                if app.selected_line_indices.contains(li) { 3.5 } else { 2.0 },
+            let style = LineStyle::new(
+                line_kinds.get(*li).copied().unwrap_or_default(),
                 color,
+                app.selected_line_indices.contains(li),
+                app.vector_line_width,
+                ui.visuals().panel_fill,
             );
             let has_in_dst = line.dst.as_ref().map_or(false, |dst| dst.port_type == "in");
             let mut draw_pts = screen_pts.clone();
@@ -1204,14 +1346,12 @@ pub(crate) fn update_internal(
                     draw_pts[0].y += uy * inset;
                 }
             }
-            let last_idx = draw_pts.len().saturating_sub(1);
-            for (seg_idx, seg) in draw_pts.windows(2).enumerate() {
-                let is_last = has_in_dst && seg_idx == last_idx.saturating_sub(1);
-                if is_last {
-                    draw_arrow_with_trim(&painter, seg[0], seg[1], color, stroke);
-                } else {
-                    painter.line_segment([seg[0], seg[1]], stroke);
-                }
+            let n = draw_pts.len();
+            if has_in_dst && n >= 2 {
+                style.paint_polyline(&painter, &draw_pts[..n - 1]);
+                draw_arrow_with_trim(&painter, draw_pts[n - 2], draw_pts[n - 1], color, &style);
+            } else {
+                style.paint_polyline(&painter, &draw_pts);
             }
             for br in &line.branches {
                 draw_branch_rec(
@@ -1221,10 +1361,10 @@ pub(crate) fn update_internal(
                     &port_counts,
                     *main_anchor,
                     br,
-                    stroke,
+                    style,
                     color,
                     &mut port_label_requests,
-                    &sid_mirrored,
+                    &sid_anchors,
                 );
             }
             // Precise per-segment distance check.  We detect clicks via
@@ -1265,6 +1405,9 @@ pub(crate) fn update_internal(
                         }
                     }
                     let near_segment = min_dist <= 8.0;
+                    if near_segment && ui.input(|i| i.pointer.hover_pos()).is_some() {
+                        hovered_line = Some(*li);
+                    }
                     if near_segment {
                         // Determine click type from pointer state.
                         let primary_clicked = ui.input(|i| i.pointer.button_clicked(egui::PointerButton::Primary));
@@ -1326,14 +1469,15 @@ pub(crate) fn update_internal(
                                 );
                                 ui.close();
                             }
-                            for item in &signal_menu_items_snapshot {
-                                if (item.filter)(line) {
-                                    if ui.button(&item.label).clicked() {
-                                        (item.on_click)(line);
-                                        ui.close();
-                                    }
-                                }
+                            if ui.button("☆ Bookmark").clicked() {
+                                new_bookmark = Bookmark::signal(
+                                    path_snapshot.clone(),
+                                    line,
+                                    &entities.blocks,
+                                );
+                                ui.close();
                             }
+                            show_context_menu_items(ui, &signal_menu_items_snapshot, line, true);
                         });
                     }
                 }
@@ -1598,14 +1742,11 @@ pub(crate) fn update_internal(
             step_fraction: 0.25,
             perp_offset: 2.0,
         };
-        let mut placed_label_rects: Vec<Rect> = Vec::new();
-        let mut draw_line_labels = |line: &crate::model::Line,
-                                    screen_pts: &Vec<Pos2>,
-                                    main_anchor: Pos2,
-                                    color: Color32,
-                                    line_idx: usize| {
+        // Gather the segments of every named line; the longest one carries the label.
+        let mut label_inputs = Vec::new();
+        for (line, screen_pts, main_anchor, _resp, li, _segments_all) in &line_views {
             if screen_pts.len() < 2 {
-                return;
+                continue;
             }
             let Some(label_text) = line
                 .name
@@ -1614,7 +1755,7 @@ pub(crate) fn update_internal(
                 .filter(|s| !s.is_empty())
                 .map(|s| s.to_string())
             else {
-                return;
+                continue;
             };
             let mut segments: Vec<(Pos2, Pos2)> = Vec::new();
             for seg in screen_pts.windows(2) {
@@ -1625,168 +1766,304 @@ pub(crate) fn update_internal(
                     &to_screen,
                     &sid_map,
                     &port_counts,
-                    main_anchor,
+                    *main_anchor,
                     br,
                     &mut segments,
                     &mut port_y_screen,
-                    &sid_mirrored,
+                    &sid_anchors,
                 );
             }
-            let mut best_len2 = -1.0f32;
-            let mut best_seg: Option<(Pos2, Pos2)> = None;
-            for (a, b) in &segments {
-                let dx = b.x - a.x;
-                let dy = b.y - a.y;
-                let l2 = dx * dx + dy * dy;
-                if l2 > best_len2 {
-                    best_len2 = l2;
-                    best_seg = Some((*a, *b));
+            label_inputs.push((*li, label_text, segments));
+        }
+
+        // Placements are cached in model coordinates (so panning reuses them)
+        // and recomputed only when the zoom bucket or the geometry changes.
+        let view_scale = base_scale * staged_zoom;
+        let view_origin = to_screen(Pos2::ZERO);
+        let to_model = |q: Pos2| -> Pos2 {
+            Pos2::new(
+                (q.x - view_origin.x) / view_scale,
+                (q.y - view_origin.y) / view_scale,
+            )
+        };
+        let zoom_bucket = crate::label_place::zoom_bucket(staged_zoom);
+        let geometry_key = {
+            use std::hash::{Hash, Hasher};
+            let mut h = std::collections::hash_map::DefaultHasher::new();
+            let mut hash_pos = |p: Pos2| {
+                ((p.x * 10.0).round() as i64).hash(&mut h);
+                ((p.y * 10.0).round() as i64).hash(&mut h);
+            };
+            hash_pos(Pos2::new(base_scale, 0.0));
+            for (_li, _text, segments) in &label_inputs {
+                for (a, b) in segments {
+                    hash_pos(to_model(*a));
+                    hash_pos(to_model(*b));
                 }
             }
-            let Some((sa, sb)) = best_seg else {
-                return;
-            };
-            let poly: Vec<crate::label_place::Vec2f> = vec![
-                crate::label_place::Vec2f { x: sa.x, y: sa.y },
-                crate::label_place::Vec2f { x: sb.x, y: sb.y },
-            ];
-            let mut avoid_rects: Vec<crate::label_place::RectF> = placed_label_rects
-                .iter()
-                .map(|r| {
-                    crate::label_place::RectF::from_min_max(
-                        crate::label_place::Vec2f {
-                            x: r.left(),
-                            y: r.top(),
-                        },
-                        crate::label_place::Vec2f {
-                            x: r.right(),
-                            y: r.bottom(),
-                        },
-                    )
-                })
-                .collect();
             for (_b, br, _clicked, _bg) in &block_views {
-                avoid_rects.push(crate::label_place::RectF::from_min_max(
-                    crate::label_place::Vec2f {
-                        x: br.left(),
-                        y: br.top(),
-                    },
-                    crate::label_place::Vec2f {
-                        x: br.right(),
-                        y: br.bottom(),
-                    },
-                ));
+                hash_pos(to_model(br.min));
+                hash_pos(to_model(br.max));
             }
-            let line_thickness = 0.8f32;
-            for (a, b) in &segments {
-                let min_x = a.x.min(b.x) - line_thickness;
-                let max_x = a.x.max(b.x) + line_thickness;
-                let min_y = a.y.min(b.y) - line_thickness;
-                let max_y = a.y.max(b.y) + line_thickness;
-                avoid_rects.push(crate::label_place::RectF::from_min_max(
-                    crate::label_place::Vec2f { x: min_x, y: min_y },
-                    crate::label_place::Vec2f { x: max_x, y: max_y },
-                ));
+            for (li, text, _segments) in &label_inputs {
+                li.hash(&mut h);
+                text.hash(&mut h);
             }
-            let mut final_drawn = false;
-            let mut font_size = signal_font;
-            let mut tried_wrap = false;
-            let mut wrap_text = label_text.clone();
-            while !final_drawn {
-                let font_id = egui::FontId::proportional(font_size);
+            app.joint_label_placement.hash(&mut h);
+            h.finish()
+        };
+
+        if app
+            .view_cache
+            .label_placements
+            .get(zoom_bucket, geometry_key)
+            .is_none()
+        {
+            let mut placed: Vec<crate::label_place::CachedLabel> = Vec::new();
+            if app.joint_label_placement {
+                // Every label on its line's longest segment, placed in one pass.
+                let polylines: Vec<Vec<crate::label_place::Vec2f>> = label_inputs
+                    .iter()
+                    .map(|(_li, _text, segments)| {
+                        segments
+                            .iter()
+                            .min_by(|(a, b), (c, d)| {
+                                (*d - *c).length_sq().total_cmp(&(*b - *a).length_sq())
+                            })
+                            .map(|(a, b)| {
+                                vec![
+                                    crate::label_place::Vec2f::new(a.x, a.y),
+                                    crate::label_place::Vec2f::new(b.x, b.y),
+                                ]
+                            })
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                let requests: Vec<crate::label_place::LabelRequest> = label_inputs
+                    .iter()
+                    .zip(&polylines)
+                    .map(|((_li, text, _segments), poly)| crate::label_place::LabelRequest {
+                        polyline: poly,
+                        text,
+                    })
+                    .collect();
+                let obstacles: Vec<crate::label_place::RectF> = block_views
+                    .iter()
+                    .map(|(_b, br, _clicked, _bg)| {
+                        crate::label_place::RectF::from_min_max(
+                            crate::label_place::Vec2f::new(br.left(), br.top()),
+                            crate::label_place::Vec2f::new(br.right(), br.bottom()),
+                        )
+                    })
+                    .collect();
                 let meas = EguiMeasurer {
                     painter: ui.painter(),
-                    font: font_id.clone(),
-                    color,
+                    font: egui::FontId::proportional(signal_font),
+                    color: line_stroke_default.color,
                 };
-                let candidate_texts: Vec<String> = if !tried_wrap && label_text.contains(' ') {
-                    let bytes: Vec<(usize, char)> = label_text.char_indices().collect();
-                    let mut best_split = None;
-                    let mut best_dist = usize::MAX;
-                    for (i, ch) in bytes.iter() {
-                        if *ch == ' ' {
-                            let dist =
-                                (*i as isize - (label_text.len() as isize) / 2).abs() as usize;
-                            if dist < best_dist {
-                                best_dist = dist;
-                                best_split = Some(*i);
-                            }
-                        }
-                    }
-                    if let Some(split) = best_split {
-                        wrap_text = format!(
-                            "{}\n{}",
-                            &label_text[..split].trim_end(),
-                            &label_text[split + 1..].trim_start()
-                        );
-                        vec![label_text.clone(), wrap_text.clone()]
-                    } else {
-                        vec![label_text.clone()]
+                let results = crate::label_place::place_all(&requests, &meas, cfg, &obstacles);
+                for ((line_idx, label_text, _segments), result) in label_inputs.iter().zip(results) {
+                    let Some(result) = result else {
+                        continue;
+                    };
+                    let min = to_model(Pos2::new(result.rect.min.x, result.rect.min.y));
+                    let max = to_model(Pos2::new(result.rect.max.x, result.rect.max.y));
+                    placed.push(crate::label_place::CachedLabel {
+                        line_index: *line_idx,
+                        text: label_text.clone(),
+                        rect: crate::label_place::RectF::from_min_max(
+                            crate::label_place::Vec2f::new(min.x, min.y),
+                            crate::label_place::Vec2f::new(max.x, max.y),
+                        ),
+                        horizontal: result.horizontal,
+                        font_size: signal_font / font_scale,
+                    });
+                }
+            } else {
+            let mut placed_label_rects: Vec<Rect> = Vec::new();
+            for (line_idx, label_text, segments) in &label_inputs {
+                let color = line_colors
+                    .get(*line_idx)
+                    .copied()
+                    .unwrap_or(line_stroke_default.color);
+                let mut best_len2 = -1.0f32;
+                let mut best_seg: Option<(Pos2, Pos2)> = None;
+                for (a, b) in segments {
+                    let dx = b.x - a.x;
+                    let dy = b.y - a.y;
+                    let l2 = dx * dx + dy * dy;
+                    if l2 > best_len2 {
+                        best_len2 = l2;
+                        best_seg = Some((*a, *b));
                     }
-                } else {
-                    vec![label_text.clone(), wrap_text.clone()]
+                }
+                let Some((sa, sb)) = best_seg else {
+                    continue;
                 };
-                for candidate in candidate_texts.into_iter().filter(|s| !s.is_empty()) {
-                    if let Some(result) =
-                        crate::label_place::place_label(&poly, &candidate, &meas, cfg, &avoid_rects)
+                let poly: Vec<crate::label_place::Vec2f> = vec![
+                    crate::label_place::Vec2f { x: sa.x, y: sa.y },
+                    crate::label_place::Vec2f { x: sb.x, y: sb.y },
+                ];
+                let mut avoid_rects: Vec<crate::label_place::RectF> = placed_label_rects
+                    .iter()
+                    .map(|r| {
+                        crate::label_place::RectF::from_min_max(
+                            crate::label_place::Vec2f {
+                                x: r.left(),
+                                y: r.top(),
+                            },
+                            crate::label_place::Vec2f {
+                                x: r.right(),
+                                y: r.bottom(),
+                            },
+                        )
+                    })
+                    .collect();
+                for (_b, br, _clicked, _bg) in &block_views {
+                    avoid_rects.push(crate::label_place::RectF::from_min_max(
+                        crate::label_place::Vec2f {
+                            x: br.left(),
+                            y: br.top(),
+                        },
+                        crate::label_place::Vec2f {
+                            x: br.right(),
+                            y: br.bottom(),
+                        },
+                    ));
+                }
+                let line_thickness = 0.8f32;
+                for (a, b) in segments {
+                    let min_x = a.x.min(b.x) - line_thickness;
+                    let max_x = a.x.max(b.x) + line_thickness;
+                    let min_y = a.y.min(b.y) - line_thickness;
+                    let max_y = a.y.max(b.y) + line_thickness;
+                    avoid_rects.push(crate::label_place::RectF::from_min_max(
+                        crate::label_place::Vec2f { x: min_x, y: min_y },
+                        crate::label_place::Vec2f { x: max_x, y: max_y },
+                    ));
+                }
+                let mut final_drawn = false;
+                let mut font_size = signal_font;
+                let mut tried_wrap = false;
+                let mut wrap_text = label_text.clone();
+                while !final_drawn {
+                    let font_id = egui::FontId::proportional(font_size);
+                    let meas = EguiMeasurer {
+                        painter: ui.painter(),
+                        font: font_id.clone(),
+                        color,
+                    };
+                    let candidate_texts: Vec<String> = if !tried_wrap && label_text.contains(' ')
                     {
-                        if result.horizontal {
-                            let galley = ui.painter().layout_no_wrap(
-                                candidate.clone(),
-                                font_id.clone(),
-                                color,
+                        let bytes: Vec<(usize, char)> = label_text.char_indices().collect();
+                        let mut best_split = None;
+                        let mut best_dist = usize::MAX;
+                        for (i, ch) in bytes.iter() {
+                            if *ch == ' ' {
+                                let dist =
+                                    (*i as isize - (label_text.len() as isize) / 2).unsigned_abs();
+                                if dist < best_dist {
+                                    best_dist = dist;
+                                    best_split = Some(*i);
+                                }
+                            }
+                        }
+                        if let Some(split) = best_split {
+                            wrap_text = format!(
+                                "{}\n{}",
+                                &label_text[..split].trim_end(),
+                                &label_text[split + 1..].trim_start()
                             );
-                            let draw_pos = Pos2::new(result.rect.min.x, result.rect.min.y);
-                            painter.galley(draw_pos, galley, color);
+                            vec![label_text.clone(), wrap_text.clone()]
                         } else {
-                            let galley = ui.painter().layout_no_wrap(
-                                candidate.clone(),
-                                font_id.clone(),
-                                color,
+                            vec![label_text.clone()]
+                        }
+                    } else {
+                        vec![label_text.clone(), wrap_text.clone()]
+                    };
+                    for candidate in candidate_texts.into_iter().filter(|s| !s.is_empty()) {
+                        if let Some(result) = crate::label_place::place_label(
+                            &poly,
+                            &candidate,
+                            &meas,
+                            cfg,
+                            &avoid_rects,
+                        ) {
+                            let rect = Rect::from_min_max(
+                                Pos2::new(result.rect.min.x, result.rect.min.y),
+                                Pos2::new(result.rect.max.x, result.rect.max.y),
                             );
-                            let draw_pos = Pos2::new(result.rect.min.x, result.rect.min.y);
-                            // Draw rotated text using TextShape with angle around the draw_pos (top-left)
-                            let text_shape = egui::epaint::TextShape {
-                                pos: draw_pos,
-                                galley,
-                                fallback_color: Color32::TRANSPARENT,
-                                opacity_factor: 1.0,
-                                underline: Stroke::NONE,
-                                override_text_color: Some(color),
-                                angle: std::f32::consts::FRAC_PI_2,
-                            };
-                            painter.add(egui::Shape::Text(text_shape));
+                            placed_label_rects.push(rect);
+                            let (min, max) = (to_model(rect.min), to_model(rect.max));
+                            placed.push(crate::label_place::CachedLabel {
+                                line_index: *line_idx,
+                                text: candidate,
+                                rect: crate::label_place::RectF::from_min_max(
+                                    crate::label_place::Vec2f::new(min.x, min.y),
+                                    crate::label_place::Vec2f::new(max.x, max.y),
+                                ),
+                                horizontal: result.horizontal,
+                                font_size: font_size / font_scale,
+                            });
+                            final_drawn = true;
+                            break;
                         }
-                        let rect = Rect::from_min_max(
-                            Pos2::new(result.rect.min.x, result.rect.min.y),
-                            Pos2::new(result.rect.max.x, result.rect.max.y),
-                        );
-                        placed_label_rects.push(rect);
-                        signal_label_rects.push((rect, line_idx));
-                        final_drawn = true;
-                        break;
                     }
-                }
-                if final_drawn {
-                    break;
-                }
-                if !tried_wrap && label_text.contains(' ') {
-                    tried_wrap = true;
-                } else {
-                    font_size *= 0.9;
-                    if font_size < 9.0 * font_scale {
+                    if final_drawn {
                         break;
                     }
+                    if !tried_wrap && label_text.contains(' ') {
+                        tried_wrap = true;
+                    } else {
+                        font_size *= 0.9;
+                        if font_size < 9.0 * font_scale {
+                            break;
+                        }
+                    }
                 }
             }
-        };
+            }
+            app.view_cache
+                .label_placements
+                .store(zoom_bucket, geometry_key, placed);
+        }
 
-        for (line, screen_pts, main_anchor, _resp, li, _segments_all) in &line_views {
-            let color = line_colors
-                .get(*li)
-                .copied()
-                .unwrap_or(line_stroke_default.color);
-            draw_line_labels(line, screen_pts, *main_anchor, color, *li);
+        // Draw the cached labels at the current zoom and pan.
+        if let Some(labels) = app
+            .view_cache
+            .label_placements
+            .get(zoom_bucket, geometry_key)
+        {
+            for label in labels {
+                let color = line_colors
+                    .get(label.line_index)
+                    .copied()
+                    .unwrap_or(line_stroke_default.color);
+                let font_id = egui::FontId::proportional(label.font_size * font_scale);
+                let galley = ui
+                    .painter()
+                    .layout_no_wrap(label.text.clone(), font_id, color);
+                let rect = Rect::from_min_max(
+                    view_origin + Vec2::new(label.rect.min.x, label.rect.min.y) * view_scale,
+                    view_origin + Vec2::new(label.rect.max.x, label.rect.max.y) * view_scale,
+                );
+                if label.horizontal {
+                    painter.galley(rect.min, galley, color);
+                } else {
+                    // Draw rotated text using TextShape with angle around the draw_pos (top-left)
+                    let text_shape = egui::epaint::TextShape {
+                        pos: rect.min,
+                        galley,
+                        fallback_color: Color32::TRANSPARENT,
+                        opacity_factor: 1.0,
+                        underline: Stroke::NONE,
+                        override_text_color: Some(color),
+                        angle: std::f32::consts::FRAC_PI_2,
+                    };
+                    painter.add(egui::Shape::Text(text_shape));
+                }
+                signal_label_rects.push((rect, label.line_index));
+            }
         }
 
         // Clickable labels
@@ -1903,14 +2180,12 @@ pub(crate) fn update_internal(
                         ui.close();
                     }
                     let line_ref = &entities.lines[*li];
-                    for item in &signal_menu_items_snapshot {
-                        if (item.filter)(line_ref) {
-                            if ui.button(&item.label).clicked() {
-                                (item.on_click)(line_ref);
-                                ui.close();
-                            }
-                        }
+                    if ui.button("☆ Bookmark").clicked() {
+                        new_bookmark =
+                            Bookmark::signal(path_snapshot.clone(), line_ref, &entities.blocks);
+                        ui.close();
                     }
+                    show_context_menu_items(ui, &signal_menu_items_snapshot, line_ref, true);
                 });
             }
         }
@@ -1961,8 +2236,11 @@ pub(crate) fn update_internal(
                 }
 
                 // Same side selection as the label drawing code.
-                let mirrored = block.block_mirror.unwrap_or(false);
-                let is_left = *is_input ^ mirrored;
+                let frame = PortFrame::of(block);
+                if frame.is_vertical() {
+                    continue;
+                }
+                let is_left = *is_input ^ frame.flips_sides();
                 let entry = port_label_max_widths.entry(sid.clone()).or_default();
                 if is_left {
                     entry.left = entry.left.max(size.x);
@@ -1989,21 +2267,33 @@ pub(crate) fn update_internal(
             collidable_obstacle_rects.push(*r);
         }
 
+        // Port hit areas for hover information and click-to-trace:
+        // (screen rect, block, port index, is_input).
+        let mut port_hits: Vec<(Rect, &crate::model::Block, u32, bool)> = Vec::new();
+
+        // Badges from host style overrides, drawn after all blocks.
+        let mut style_badges: Vec<(Rect, String, Color32)> = Vec::new();
+
         // Finish blocks (border, icon/value, labels) and click handling
         for (b, r_screen, _clicked, bg) in &block_views {
             let cfg = get_block_type_cfg(b);
+            let style = app.block_style(b).cloned().unwrap_or_default();
+            if let Some(badge) = style.badge.filter(|t| !t.is_empty()) {
+                let fill = style
+                    .badge_color
+                    .unwrap_or(Color32::from_rgb(60, 60, 60));
+                style_badges.push((*r_screen, badge, fill));
+            }
             let border_rgb = cfg.border.unwrap_or(crate::block_types::Rgb(180, 180, 200));
-            let stroke = Stroke::new(
-                2.0,
-                Color32::from_rgb(border_rgb.0, border_rgb.1, border_rgb.2),
+            let border_color = style.border.unwrap_or(
+                b.foreground_color.map(rgba_to_color32).unwrap_or(
+                    Color32::from_rgb(border_rgb.0, border_rgb.1, border_rgb.2),
+                ),
             );
+            let stroke = Stroke::new(2.0, border_color);
             match cfg.shape {
-                BlockShape::Triangle => {
-                    let pts = vec![
-                        egui::pos2(r_screen.left(), r_screen.top()),
-                        egui::pos2(r_screen.right(), r_screen.center().y),
-                        egui::pos2(r_screen.left(), r_screen.bottom()),
-                    ];
+                BlockShape::Triangle | BlockShape::Trapezoid => {
+                    let pts = block_outline(b, &cfg, *r_screen).unwrap_or_default();
                     painter.add(egui::Shape::Path(egui::epaint::PathShape::closed_line(
                         pts, stroke,
                     )));
@@ -2019,6 +2309,10 @@ pub(crate) fn update_internal(
                 BlockShape::Rectangle => {
                     painter.rect_stroke(*r_screen, 4.0, stroke, egui::StrokeKind::Inside);
                 }
+                BlockShape::Rounded => {
+                    let rounding = r_screen.size().min_elem() / 4.0;
+                    painter.rect_stroke(*r_screen, rounding, stroke, egui::StrokeKind::Inside);
+                }
             }
 
             fn paint_port_chevron_placed(
@@ -2097,20 +2391,31 @@ pub(crate) fn update_internal(
                 .and_then(|p| p.outs)
                 .unwrap_or(cfg.default_outs);
             if in_count > 0 || out_count > 0 {
-                let mirrored = b.block_mirror.unwrap_or(false);
+                let frame = PortFrame::of(b);
+                let mirrored = frame.mirrored;
                 let overrides = &cfg.port_position_overrides;
-                let (ins, outs) = crate::egui_app::geometry::port_indicator_positions_with_overrides(
-                    *r_screen,
-                    in_count,
-                    out_count,
-                    mirrored,
-                    overrides,
-                );
-                let ins_left_side = !mirrored;
-                let outs_left_side = mirrored;
+                let (ins, outs) =
+                    PortAnchors::new(b, &cfg).indicator_positions(*r_screen, in_count, out_count);
+                // Left/right edges use the horizontal chevron; top/bottom edges
+                // (rotated blocks) are drawn like placement overrides.
+                let edge_chevron = |side: PortPlacement| {
+                    (
+                        side == PortPlacement::Left,
+                        frame.is_vertical().then_some(side),
+                    )
+                };
+                let (ins_left_side, ins_placement) = edge_chevron(frame.placement(true));
+                let (outs_left_side, outs_placement) = edge_chevron(frame.placement(false));
                 let block_sid = b.sid.as_deref().unwrap_or("");
+                let hit_size = (8.0 * font_scale.max(0.2) * 4.0).max(12.0);
                 for (i, p) in ins.iter().enumerate() {
                     let port_idx = (i as u32) + 1;
+                    port_hits.push((
+                        Rect::from_center_size(*p, Vec2::splat(hit_size)),
+                        *b,
+                        port_idx,
+                        true,
+                    ));
                     // Skip chevron if this input port is connected
                     if connected_ports.contains(&(block_sid.to_string(), port_idx, true)) {
                         continue;
@@ -2118,7 +2423,9 @@ pub(crate) fn update_internal(
                     let ovr_placement = overrides
                         .iter()
                         .find(|o| o.is_input && o.port_index == port_idx)
-                        .map(|o| o.placement);
+                        .map(|o| {
+                            crate::egui_app::geometry::rotate_placement(o.placement, frame.orientation)
+                        });
                     let left_side = ovr_placement
                         .map(|pl| crate::egui_app::geometry::port_override_is_left_side(pl, mirrored))
                         .unwrap_or(ins_left_side);
@@ -2126,13 +2433,19 @@ pub(crate) fn update_internal(
                         &painter,
                         *p,
                         left_side,
-                        ovr_placement,
+                        ovr_placement.or(ins_placement),
                         font_scale,
                         Color32::from_rgb(60, 60, 200),
                     );
                 }
                 for (i, p) in outs.iter().enumerate() {
                     let port_idx = (i as u32) + 1;
+                    port_hits.push((
+                        Rect::from_center_size(*p, Vec2::splat(hit_size)),
+                        *b,
+                        port_idx,
+                        false,
+                    ));
                     // Skip chevron if this output port is connected
                     if connected_ports.contains(&(block_sid.to_string(), port_idx, false)) {
                         continue;
@@ -2140,7 +2453,9 @@ pub(crate) fn update_internal(
                     let ovr_placement = overrides
                         .iter()
                         .find(|o| !o.is_input && o.port_index == port_idx)
-                        .map(|o| o.placement);
+                        .map(|o| {
+                            crate::egui_app::geometry::rotate_placement(o.placement, frame.orientation)
+                        });
                     let left_side = ovr_placement
                         .map(|pl| crate::egui_app::geometry::port_override_is_left_side(pl, mirrored))
                         .unwrap_or(outs_left_side);
@@ -2148,13 +2463,26 @@ pub(crate) fn update_internal(
                         &painter,
                         *p,
                         left_side,
-                        ovr_placement,
+                        ovr_placement.or(outs_placement),
                         font_scale,
                         Color32::from_rgb(200, 60, 60),
                     );
                 }
             }
-            let fg = contrast_color(*bg);
+            // Enable and trigger symbols of conditionally executed subsystems,
+            // just inside the block below their control ports.
+            let anchors = PortAnchors::new(b, &cfg);
+            for (kind, p) in anchors.control_positions(*r_screen) {
+                let placement = anchors.frame.placement_of(&kind);
+                let size = (40.0 * font_scale).max(10.0);
+                if let Some(points) = control_port_symbol(&kind, p, placement, size) {
+                    painter.add(egui::Shape::line(
+                        points,
+                        Stroke::new((4.0 * font_scale).max(1.0), border_color),
+                    ));
+                }
+            }
+            let fg = block_foreground_color(b, *bg);
             let display_signal_label = if b.block_type == "Display" {
                 let sid = b.sid.as_deref();
                 sid.and_then(|sid| {
@@ -2217,7 +2545,8 @@ pub(crate) fn update_internal(
             } else if b.mask.is_some() {
                 if let Some(text) = b.mask_display_text.as_ref() {
                     let font_size = (b.font_size.unwrap_or(14) as f32) * font_scale;
-                    let font_id = egui::FontId::proportional(font_size);
+                    let font_id =
+                        crate::egui_app::text::font_id_for_name(b.font_name.as_deref(), font_size);
                     let color = fg;
                     let galley = painter.layout_no_wrap(text.clone(), font_id.clone(), color);
                     let pos = r_screen.center() - galley.size() * 0.5;
@@ -2310,6 +2639,9 @@ pub(crate) fn update_internal(
                     icon_port_label_widths,
                 );
             }
+            if b.comment_through {
+                paint_pass_through_arrow(&painter, b, r_screen, font_scale);
+            }
             #[cfg(feature = "dashboard")]
             let _ = render_dashboard_live_overlay(app, ui, b, *r_screen, fg);
 
@@ -2333,12 +2665,13 @@ pub(crate) fn update_internal(
                     .as_ref()
                     .and_then(|p| p.outs)
                     .unwrap_or(cfg.default_outs);
-                let mirrored = b.block_mirror.unwrap_or(false);
-                let ins_left_side = !mirrored;
-                let outs_left_side = mirrored;
-                let has_left = (in_count > 0 && ins_left_side) || (out_count > 0 && outs_left_side);
-                let has_right = (in_count > 0 && !ins_left_side)
-                    || (out_count > 0 && !outs_left_side);
+                let frame = PortFrame::of(b);
+                let has_side = |side: PortPlacement| {
+                    (in_count > 0 && frame.placement(true) == side)
+                        || (out_count > 0 && frame.placement(false) == side)
+                };
+                let has_left = has_side(PortPlacement::Left);
+                let has_right = has_side(PortPlacement::Right);
                 let left_extra = if has_left { chevron_w } else { 0.0 };
                 let right_extra = if has_right { chevron_w } else { 0.0 };
                 let overall_w = r_screen.width() + left_extra + right_extra;
@@ -2366,7 +2699,10 @@ pub(crate) fn update_internal(
                 let center_x = (left + right) * 0.5;
 
                 loop {
-                    let font = egui::FontId::proportional(current_font_px);
+                    let font = crate::egui_app::text::font_id_for_name(
+                        b.font_name.as_deref(),
+                        current_font_px,
+                    );
                     let line_height = (current_font_px * 1.2).max(1.0);
                     let lines = wrap_text_to_max_width(&painter, &b.name, font.clone(), max_label_w);
                     if lines.is_empty() {
@@ -2436,7 +2772,10 @@ pub(crate) fn update_internal(
 
                 if !best_lines.is_empty() {
                     collidable_obstacle_rects.extend(best_rects);
-                    let font = egui::FontId::proportional(best_font_px);
+                    let font = crate::egui_app::text::font_id_for_name(
+                        b.font_name.as_deref(),
+                        best_font_px,
+                    );
                     let line_height = best_line_height;
 
                     match b.name_location {
@@ -2486,6 +2825,42 @@ pub(crate) fn update_internal(
             }
         }
 
+        for (r, badge, fill) in &style_badges {
+            let radius = (7.0 * font_scale.max(0.2) * 2.0).clamp(6.0, 16.0);
+            let center = r.right_top();
+            painter.circle_filled(center, radius, *fill);
+            painter.circle_stroke(center, radius, Stroke::new(1.0, Color32::WHITE));
+            painter.text(
+                center,
+                Align2::CENTER_CENTER,
+                badge,
+                egui::FontId::proportional(radius * 1.3),
+                contrast_color(*fill),
+            );
+        }
+
+        if let Some(r) = app
+            .focused_block_sid
+            .as_ref()
+            .and_then(|sid| sid_screen_map.get(sid))
+        {
+            // Bring a block focused from the keyboard into view.
+            if focus_moved && !avail.contains_rect(*r) {
+                staged_pan += avail.center() - r.center();
+                ui.ctx().request_repaint();
+            }
+            painter.rect_stroke(
+                r.expand(4.0),
+                6.0,
+                Stroke::new(2.0, FOCUS_RING_COLOR),
+                egui::StrokeKind::Outside,
+            );
+        }
+
+        if let Some(overlay) = app.active_overlay() {
+            draw_overlay_legend(&painter, avail, overlay);
+        }
+
         // Draw port labels
         let mut seen_port_labels: std::collections::HashSet<(String, u32, bool, i32)> =
             Default::default();
@@ -2517,7 +2892,12 @@ pub(crate) fn update_internal(
             {
                 continue;
             }
-            let mirrored = block.block_mirror.unwrap_or(false);
+            // Labels are laid out by port y; rotated blocks with ports on the
+            // top/bottom edges don't get inside-block labels.
+            let frame = PortFrame::of(block);
+            if frame.is_vertical() {
+                continue;
+            }
             let pname = port_label_display_name(block, index, is_input, &cfg);
             let galley = ui.painter().layout_no_wrap(
                 pname,
@@ -2531,7 +2911,7 @@ pub(crate) fn update_internal(
                 let y_min = brect.top();
                 let y_max = (brect.bottom() - size.y).max(y_min);
                 let y_top = (y - half_h).max(y_min).min(y_max);
-                let pos = if is_input ^ mirrored {
+                let pos = if is_input ^ frame.flips_sides() {
                     Pos2::new(brect.left() + 4.0 * font_scale, y_top)
                 } else {
                     Pos2::new(brect.right() - 4.0 * font_scale - size.x, y_top)
@@ -2540,6 +2920,43 @@ pub(crate) fn update_internal(
             }
         }
 
+        // Ports: hovering shows name, data type and signal; clicking selects
+        // the attached line.
+        for (rect, block, port_idx, is_input) in &port_hits {
+            let Some(sid) = block.sid.as_deref() else {
+                continue;
+            };
+            let resp = ui.interact(
+                *rect,
+                ui.id().with(("port", sid, *port_idx, *is_input)),
+                Sense::click(),
+            );
+            if resp.hovered() {
+                painter.circle_stroke(
+                    rect.center(),
+                    rect.width() * 0.3,
+                    Stroke::new(2.0, Color32::from_rgb(255, 180, 0)),
+                );
+            }
+            let resp = resp.on_hover_ui(|ui| {
+                ui.label(crate::port_info::hover_text(
+                    &entities.blocks,
+                    &entities.lines,
+                    block,
+                    *port_idx,
+                    *is_input,
+                ));
+            });
+            if resp.clicked()
+                && let Some(li) =
+                    crate::port_info::attached_line(&entities.lines, sid, *port_idx, *is_input)
+            {
+                app.selected_line_indices.clear();
+                app.selected_line_indices.insert(li);
+                app.selected_block_sids.clear();
+            }
+        }
+
         // Deferred liveplot rendering for Scope/DashboardScope blocks.
         // This runs after the painter borrow is no longer needed so we can
         // use `ui` mutably via `scope_builder`.
@@ -2583,6 +3000,33 @@ pub(crate) fn update_internal(
         }
     });
 
+    // Commit this frame's view before navigating, so that navigation can
+    // remember it and set up the view of the next subsystem.
+    let view_changed = app.zoom != staged_zoom || app.pan != staged_pan;
+    app.zoom = staged_zoom;
+    app.pan = staged_pan;
+    app.reset_view = staged_reset;
+    app.view_bounds = staged_view_bounds;
+
+    for event in std::mem::take(&mut interaction.events) {
+        app.push_event(event);
+    }
+    if hovered_line != app.hovered_line {
+        if let Some(line_idx) = hovered_line
+            && let Some(line) = app.current_system().and_then(|s| s.lines.get(line_idx))
+        {
+            let line = Box::new(line.clone());
+            app.push_event(ViewerEvent::SignalHovered { line_idx, line });
+        }
+        app.hovered_line = hovered_line;
+    }
+    if view_changed {
+        app.push_event(ViewerEvent::ViewChanged {
+            zoom: app.zoom,
+            pan: app.pan,
+        });
+    }
+
     // After the UI closure, call open_block_if_subsystem if needed
     if let Some(block) = block_to_open_subsystem {
         app.open_block_if_subsystem(&block);
@@ -2591,16 +3035,109 @@ pub(crate) fn update_internal(
     if let Some(p) = navigate_to {
         app.navigate_to_path(p);
     }
-    app.zoom = staged_zoom;
-    app.pan = staged_pan;
-    app.reset_view = staged_reset;
-    app.view_bounds = staged_view_bounds;
+    if let Some((p, sid)) = tree_target {
+        app.navigate_to_path(p);
+        app.selected_block_sids.extend(sid);
+    }
+    match bookmark_action {
+        Some(BookmarkAction::GoTo(bookmark)) => {
+            app.go_to_bookmark(&bookmark);
+        }
+        Some(BookmarkAction::Remove(index)) => {
+            app.remove_bookmark(index);
+        }
+        Some(BookmarkAction::AddCurrentSubsystem) => {
+            app.bookmark_current_subsystem();
+        }
+        None => {}
+    }
+    if let Some(bookmark) = new_bookmark {
+        app.add_bookmark(bookmark);
+    }
+    if let Some(link) = clicked_link
+        && !app.open_link(&link)
+        && is_web_link(&link)
+    {
+        ui.ctx().open_url(egui::OpenUrl::new_tab(link));
+    }
+    if keyboard_go_up {
+        app.go_up();
+    }
     if clear_search {
-        app.search_query.clear();
+        let query = std::mem::take(&mut app.search_query);
+        app.record_search(&query);
         app.search_matches.clear();
     }
 
-    interaction
+    interaction.response
+}
+
+/// Links opened in the browser when neither the model nor the host handles
+/// them.
+fn is_web_link(link: &str) -> bool {
+    let lower = link.trim().to_ascii_lowercase();
+    ["http://", "https://", "mailto:", "file://"]
+        .iter()
+        .any(|scheme| lower.starts_with(scheme))
+}
+
+/// Gradient bar with the overlay name and value range in the bottom-left
+/// corner of the canvas.
+fn draw_overlay_legend(painter: &egui::Painter, canvas: Rect, overlay: &MetricOverlay) {
+    const BAR: Vec2 = Vec2::new(160.0, 12.0);
+    const STEPS: usize = 32;
+    let frame = Rect::from_min_size(
+        canvas.left_bottom() + Vec2::new(10.0, -62.0),
+        Vec2::new(BAR.x + 16.0, 52.0),
+    );
+    painter.rect_filled(frame, 4.0, Color32::from_white_alpha(230));
+    painter.rect_stroke(
+        frame,
+        4.0,
+        Stroke::new(1.0, Color32::from_gray(160)),
+        egui::StrokeKind::Inside,
+    );
+    let font = egui::FontId::proportional(11.0);
+    painter.text(
+        frame.min + Vec2::new(8.0, 6.0),
+        Align2::LEFT_TOP,
+        &overlay.name,
+        font.clone(),
+        Color32::BLACK,
+    );
+    let bar = Rect::from_min_size(frame.min + Vec2::new(8.0, 22.0), BAR);
+    let step_w = BAR.x / STEPS as f32;
+    for i in 0..STEPS {
+        let t = (i as f32 + 0.5) / STEPS as f32;
+        let x = bar.left() + i as f32 * step_w;
+        let r = Rect::from_min_max(
+            Pos2::new(x, bar.top()),
+            Pos2::new(x + step_w + 0.5, bar.bottom()),
+        );
+        painter.rect_filled(r, 0.0, rgba_to_color32(overlay.gradient.color_at(t)));
+    }
+    painter.rect_stroke(
+        bar,
+        0.0,
+        Stroke::new(1.0, Color32::from_gray(90)),
+        egui::StrokeKind::Outside,
+    );
+    if let Some((lo, hi)) = overlay.value_range() {
+        painter.text(
+            bar.left_bottom() + Vec2::new(0.0, 2.0),
+            Align2::LEFT_TOP,
+            format!("{lo}"),
+            font.clone(),
+            Color32::BLACK,
+        );
+        painter.text(
+            bar.right_bottom() + Vec2::new(0.0, 2.0),
+            Align2::RIGHT_TOP,
+            format!("{hi}"),
+            font,
+            Color32::BLACK,
+        );
+    }
 }
 
 fn draw_viewer_resize_handles(
@@ -2,12 +2,13 @@
 //!
 //! `ViewTransform` encapsulates the mapping between model (world) coordinates
 //! and screen (pixel) coordinates so that the transform logic is defined once
-//! and can be tested independently.
+//! and can be tested independently. [`canvas_navigation`] turns wheel,
+//! trackpad and touch input into zoom and pan changes for the viewer and the
+//! editor canvas.
 
-use eframe::egui::{Pos2, Rect, Vec2};
+use eframe::egui::{self, Event, MouseWheelUnit, Pos2, Rect, Vec2};
 
 /// Immutable snapshot of the viewer's coordinate transform for a single frame.
-#[cfg(test)]
 #[derive(Clone, Copy, Debug)]
 pub struct ViewTransform {
     /// Bounding-box in model space that is being fitted into the viewport.
@@ -24,7 +25,6 @@ pub struct ViewTransform {
     pub pan: Vec2,
 }
 
-#[cfg(test)]
 impl ViewTransform {
     /// Compute a new `ViewTransform` from the given content bounds and viewport.
     pub fn new(bb: Rect, avail: Rect, margin: f32, zoom: f32, pan: Vec2) -> Self {
@@ -95,6 +95,88 @@ impl ViewTransform {
     }
 }
 
+/// Zoom and pan requested by one frame of wheel, trackpad and touch input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CanvasNavigation {
+    /// Multiplicative zoom change; `1.0` leaves the zoom unchanged.
+    pub zoom_factor: f32,
+    /// Zoom around this point instead of the pointer (touch gestures).
+    pub zoom_center: Option<Pos2>,
+    /// Pan offset in screen points.
+    pub pan: Vec2,
+    /// A multi-touch gesture is in progress; single-pointer drags should not
+    /// pan as well.
+    pub multi_touch: bool,
+}
+
+impl Default for CanvasNavigation {
+    fn default() -> Self {
+        Self {
+            zoom_factor: 1.0,
+            zoom_center: None,
+            pan: Vec2::ZERO,
+            multi_touch: false,
+        }
+    }
+}
+
+impl CanvasNavigation {
+    pub fn is_none(&self) -> bool {
+        self.zoom_factor == 1.0 && self.pan == Vec2::ZERO
+    }
+}
+
+/// Interpret the canvas input of one frame.
+///
+/// - Mouse wheels (line/page units) zoom, as they always have.
+/// - Precise scrolling (point units, i.e. two-finger trackpad scrolling)
+///   pans.
+/// - Trackpad pinches and Ctrl/Cmd+scroll zoom through egui's `zoom_delta`.
+/// - On touch screens, pinching zooms around the fingers and dragging with
+///   two fingers pans.
+pub fn canvas_navigation(input: &egui::InputState) -> CanvasNavigation {
+    let mut nav = wheel_navigation(&input.events, input.raw_scroll_delta);
+    nav.zoom_factor *= input.zoom_delta();
+    if let Some(touch) = input.multi_touch() {
+        nav.pan += touch.translation_delta;
+        nav.zoom_center = Some(touch.center_pos);
+        nav.multi_touch = true;
+    }
+    nav
+}
+
+/// Wheel part of [`canvas_navigation`]: precise scroll events pan, the
+/// remaining (mouse wheel) scrolling zooms. Scroll events with the zoom
+/// modifier are left to egui, which reports them in `zoom_delta`.
+pub fn wheel_navigation(events: &[Event], raw_scroll_delta: Vec2) -> CanvasNavigation {
+    let mut pan = Vec2::ZERO;
+    let mut zoom_modified = false;
+    for event in events {
+        if let Event::MouseWheel {
+            unit,
+            delta,
+            modifiers,
+        } = event
+        {
+            if modifiers.command || modifiers.ctrl {
+                zoom_modified = true;
+            } else if *unit == MouseWheelUnit::Point {
+                pan += *delta;
+            }
+        }
+    }
+    let wheel_y = if zoom_modified {
+        0.0
+    } else {
+        raw_scroll_delta.y - pan.y
+    };
+    CanvasNavigation {
+        zoom_factor: (1.0 + wheel_y * 0.001).max(0.1),
+        pan,
+        ..Default::default()
+    }
+}
+
 /// Compute a `preview_block_rect` during drag — offsets the block's model
 /// rect by the current drag delta if the block is selected.
 pub fn preview_block_rect(
@@ -293,6 +375,49 @@ mod tests {
         assert!((world_before.y - world_after.y).abs() < 0.5);
     }
 
+    fn wheel(unit: MouseWheelUnit, delta: Vec2, modifiers: egui::Modifiers) -> Event {
+        Event::MouseWheel {
+            unit,
+            delta,
+            modifiers,
+        }
+    }
+
+    #[test]
+    fn mouse_wheel_zooms() {
+        let events = [wheel(
+            MouseWheelUnit::Line,
+            Vec2::new(0.0, 1.0),
+            egui::Modifiers::NONE,
+        )];
+        let nav = wheel_navigation(&events, Vec2::new(0.0, 50.0));
+        assert!((nav.zoom_factor - 1.05).abs() < 1e-6);
+        assert_eq!(nav.pan, Vec2::ZERO);
+    }
+
+    #[test]
+    fn trackpad_scroll_pans() {
+        let events = [wheel(
+            MouseWheelUnit::Point,
+            Vec2::new(3.0, -4.0),
+            egui::Modifiers::NONE,
+        )];
+        let nav = wheel_navigation(&events, Vec2::new(3.0, -4.0));
+        assert_eq!(nav.zoom_factor, 1.0);
+        assert_eq!(nav.pan, Vec2::new(3.0, -4.0));
+    }
+
+    #[test]
+    fn zoom_modifier_scroll_is_left_to_zoom_delta() {
+        let events = [wheel(
+            MouseWheelUnit::Point,
+            Vec2::new(0.0, 6.0),
+            egui::Modifiers::COMMAND,
+        )];
+        let nav = wheel_navigation(&events, Vec2::new(0.0, 6.0));
+        assert!(nav.is_none());
+    }
+
     #[test]
     fn font_scale_positive_at_min_zoom() {
         let vt = ViewTransform::new(
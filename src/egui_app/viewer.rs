@@ -0,0 +1,589 @@
+//! Standalone viewer window around [`SubsystemApp`].
+//!
+//! [`ViewerApp`] adds what a desktop application needs on top of the
+//! embeddable subsystem view: a File menu with an open dialog and a list of
+//! recently opened models, a welcome screen when no model is open, and
+//! opening `.slx` files dropped onto the window. Models opened from the UI
+//! are parsed on a background thread while a progress bar is shown, so
+//! large models don't freeze the window. The recent files list is kept in
+//! `eframe::Storage` next to the viewer sessions, as are the
+//! [`ViewerSettings`] of the View menu. "Compare With…" shows the open model
+//! side by side with another version of it in a [`DiffView`].
+
+use super::SubsystemApp;
+use super::diff_view::DiffView;
+use super::loader::{LoadedModel, library_search_paths, load_model, load_model_with_progress};
+use super::text::{CodeStyle, code_theme_names};
+use crate::cancel::{CancellationToken, DropGuard};
+use crate::parser::ParseProgress;
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, mpsc};
+
+/// `eframe::Storage` key of the recent files list.
+pub const RECENT_FILES_KEY: &str = "rustylink.recent_files";
+
+/// `eframe::Storage` key of the [`ViewerSettings`].
+pub const SETTINGS_KEY: &str = "rustylink.viewer_settings";
+
+/// Number of recently opened models remembered.
+pub const RECENT_FILES_LEN: usize = 10;
+
+/// File extensions the viewer opens.
+pub const MODEL_EXTENSIONS: [&str; 2] = ["slx", "xml"];
+
+/// Add `path` to the front of `recent`, dropping an older entry for the
+/// same file and trimming the list to [`RECENT_FILES_LEN`] entries.
+pub fn push_recent_file(recent: &mut Vec<String>, path: &str) {
+    recent.retain(|p| p != path);
+    recent.insert(0, path.to_string());
+    recent.truncate(RECENT_FILES_LEN);
+}
+
+/// Whether `path` has one of the [`MODEL_EXTENSIONS`].
+pub fn is_model_file(path: &std::path::Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| MODEL_EXTENSIONS.iter().any(|m| e.eq_ignore_ascii_case(m)))
+}
+
+/// Appearance settings of the desktop viewer, changed in the View menu.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewerSettings {
+    pub dark_mode: bool,
+    /// Theme and font size of code in the chart and block windows.
+    pub code_style: CodeStyle,
+}
+
+/// Callback applied to every viewer created for an opened model.
+pub type ConfigureViewerFn = Arc<dyn Fn(&mut SubsystemApp) + Send + Sync>;
+
+/// A model that could not be opened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpenError {
+    /// The model file, if the error is about one (offered for retry).
+    pub path: Option<Utf8PathBuf>,
+    pub message: String,
+}
+
+/// A model being parsed on a background thread.
+struct PendingLoad {
+    path: Utf8PathBuf,
+    initial_path: Vec<String>,
+    restore_session: bool,
+    receiver: mpsc::Receiver<Result<LoadedModel>>,
+    progress: mpsc::Receiver<ParseProgress>,
+    /// Latest progress update received.
+    latest: Option<ParseProgress>,
+    /// Stops the parse when the load is abandoned.
+    _cancel: DropGuard,
+}
+
+/// Desktop viewer: an optional open model plus menus and recent files.
+pub struct ViewerApp {
+    pub viewer: Option<SubsystemApp>,
+    /// Most recently opened first.
+    pub recent_files: Vec<String>,
+    /// Library directories searched in addition to the model's own.
+    pub library_paths: Vec<Utf8PathBuf>,
+    /// The last failed open, shown until dismissed.
+    pub error: Option<OpenError>,
+    /// Comparison of the open model with another version, shown instead of
+    /// the model while set.
+    pub diff: Option<DiffView>,
+    pub settings: ViewerSettings,
+    configure: Option<ConfigureViewerFn>,
+    loading: Option<PendingLoad>,
+}
+
+impl ViewerApp {
+    pub fn new(library_paths: Vec<Utf8PathBuf>) -> Self {
+        Self {
+            viewer: None,
+            recent_files: Vec::new(),
+            library_paths,
+            error: None,
+            diff: None,
+            settings: ViewerSettings::default(),
+            configure: None,
+            loading: None,
+        }
+    }
+
+    /// Call `configure` on the viewer of every model opened from now on,
+    /// e.g. to add context menu items.
+    pub fn on_open(&mut self, configure: impl Fn(&mut SubsystemApp) + Send + Sync + 'static) {
+        self.configure = Some(Arc::new(configure));
+    }
+
+    /// Load the recent files list and the settings from `storage`.
+    pub fn restore(&mut self, storage: &dyn eframe::Storage) {
+        if let Some(settings) = storage
+            .get_string(SETTINGS_KEY)
+            .and_then(|text| serde_json::from_str(&text).ok())
+        {
+            self.settings = settings;
+        }
+        if let Some(recent) = storage
+            .get_string(RECENT_FILES_KEY)
+            .and_then(|text| serde_json::from_str(&text).ok())
+        {
+            self.recent_files = recent;
+        }
+    }
+
+    /// Open the model at `path`, showing the subsystem at `initial_path`.
+    /// With `storage`, the viewer session saved for the model is restored
+    /// instead, and its saved bookmarks are added. Failures are kept in [`ViewerApp::error`].
+    pub fn open(
+        &mut self,
+        path: &Utf8Path,
+        initial_path: Vec<String>,
+        storage: Option<&dyn eframe::Storage>,
+    ) -> bool {
+        let lib_paths = library_search_paths(path, &self.library_paths);
+        let loaded = load_model(path, &lib_paths);
+        self.finish_open(path, initial_path, storage, loaded)
+    }
+
+    /// Like [`ViewerApp::open`], but parse the model on a background thread.
+    /// The current model stays shown until [`ViewerApp::poll_load`] picks up
+    /// the result; `ctx` is repainted when it is ready. Starting another load
+    /// or [`ViewerApp::cancel_load`] abandons a pending one and stops its
+    /// parse.
+    pub fn open_in_background(
+        &mut self,
+        path: &Utf8Path,
+        initial_path: Vec<String>,
+        restore_session: bool,
+        ctx: &egui::Context,
+    ) {
+        let lib_paths = library_search_paths(path, &self.library_paths);
+        let (sender, receiver) = mpsc::channel();
+        let (progress_sender, progress) = mpsc::channel();
+        let thread_path = path.to_path_buf();
+        let ctx = ctx.clone();
+        let cancel = CancellationToken::new();
+        let thread_cancel = cancel.clone();
+        let spawned = std::thread::Builder::new()
+            .name("rustylink-load".to_string())
+            .spawn(move || {
+                let loaded = load_model_with_progress(
+                    &thread_path,
+                    &lib_paths,
+                    Some(progress_sender),
+                    &thread_cancel,
+                );
+                // The receiver is gone if the load was abandoned.
+                let _ = sender.send(loaded);
+                ctx.request_repaint();
+            });
+        match spawned {
+            Ok(_) => {
+                self.loading = Some(PendingLoad {
+                    path: path.to_path_buf(),
+                    initial_path,
+                    restore_session,
+                    receiver,
+                    progress,
+                    latest: None,
+                    _cancel: cancel.drop_guard(),
+                });
+            }
+            Err(e) => self.set_error(Some(path), format!("Failed to start loading: {e}")),
+        }
+    }
+
+    /// Compare the model at `path`, as the old version, with the open model.
+    /// Failures are kept in [`ViewerApp::error`].
+    pub fn compare_with(&mut self, path: &Utf8Path) -> bool {
+        let Some(viewer) = &self.viewer else {
+            return false;
+        };
+        let lib_paths = library_search_paths(path, &self.library_paths);
+        match load_model(path, &lib_paths) {
+            Ok(old) => {
+                self.diff = Some(DiffView::new(crate::diff::diff_systems(
+                    &old.root,
+                    &viewer.root,
+                )));
+                true
+            }
+            Err(e) => {
+                self.set_error(Some(path), format!("{e:#}"));
+                false
+            }
+        }
+    }
+
+    /// Abandon the pending background load, if any, and stop its parse.
+    pub fn cancel_load(&mut self) {
+        self.loading = None;
+    }
+
+    /// Whether a background load is in progress.
+    pub fn is_loading(&self) -> bool {
+        self.loading.is_some()
+    }
+
+    /// Latest parse progress of the background load, if any was reported.
+    pub fn load_progress(&self) -> Option<&ParseProgress> {
+        self.loading.as_ref().and_then(|p| p.latest.as_ref())
+    }
+
+    /// Swap in the model of a finished background load. Returns whether a
+    /// load finished, successfully or not.
+    pub fn poll_load(&mut self, storage: Option<&dyn eframe::Storage>) -> bool {
+        let Some(pending) = &mut self.loading else {
+            return false;
+        };
+        if let Some(latest) = pending.progress.try_iter().last() {
+            pending.latest = Some(latest);
+        }
+        let loaded = match pending.receiver.try_recv() {
+            Ok(loaded) => loaded,
+            Err(mpsc::TryRecvError::Empty) => return false,
+            Err(mpsc::TryRecvError::Disconnected) => {
+                Err(anyhow::anyhow!("Loading thread stopped unexpectedly"))
+            }
+        };
+        let pending = self.loading.take().expect("pending load");
+        let storage = storage.filter(|_| pending.restore_session);
+        self.finish_open(&pending.path, pending.initial_path, storage, loaded);
+        true
+    }
+
+    fn finish_open(
+        &mut self,
+        path: &Utf8Path,
+        initial_path: Vec<String>,
+        storage: Option<&dyn eframe::Storage>,
+        loaded: Result<LoadedModel>,
+    ) -> bool {
+        match loaded {
+            Ok(model) => {
+                model.report_unresolved();
+                let mut viewer = model.into_app(initial_path);
+                if let Some(configure) = &self.configure {
+                    configure(&mut viewer);
+                }
+                if let Some(storage) = storage {
+                    viewer.restore_session(storage);
+                    viewer.restore_bookmarks(storage);
+                }
+                self.viewer = Some(viewer);
+                self.diff = None;
+                self.error = None;
+                push_recent_file(&mut self.recent_files, path.as_str());
+                true
+            }
+            Err(e) => {
+                eprintln!("[rustylink] Failed to open {path}: {e:#}");
+                self.set_error(Some(path), format!("{e:#}"));
+                false
+            }
+        }
+    }
+
+    fn set_error(&mut self, path: Option<&Utf8Path>, message: String) {
+        self.error = Some(OpenError {
+            path: path.map(Utf8Path::to_path_buf),
+            message,
+        });
+    }
+
+    /// Show a file dialog and open the chosen model.
+    fn open_dialog(&mut self, ctx: &egui::Context) {
+        let picked = rfd::FileDialog::new()
+            .add_filter("Simulink models", &MODEL_EXTENSIONS)
+            .pick_file();
+        if let Some(path) = picked.and_then(|p| Utf8PathBuf::from_path_buf(p).ok()) {
+            self.open_in_background(&path, Vec::new(), true, ctx);
+        }
+    }
+
+    fn menu_bar(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::top("rustylink_viewer_menu").show(ctx, |ui| {
+            egui::MenuBar::new().ui(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    if ui.button("Open…").clicked() {
+                        ui.close();
+                        self.open_dialog(ctx);
+                    }
+                    let mut chosen = None;
+                    ui.add_enabled_ui(!self.recent_files.is_empty(), |ui| {
+                        ui.menu_button("Open Recent", |ui| {
+                            for file in &self.recent_files {
+                                if ui.button(file).clicked() {
+                                    chosen = Some(file.clone());
+                                }
+                            }
+                        });
+                    });
+                    if let Some(file) = chosen {
+                        ui.close();
+                        self.open_in_background(Utf8Path::new(&file), Vec::new(), true, ctx);
+                    }
+                    if ui
+                        .add_enabled(self.viewer.is_some(), egui::Button::new("Compare With…"))
+                        .clicked()
+                    {
+                        ui.close();
+                        let picked = rfd::FileDialog::new()
+                            .add_filter("Simulink models", &MODEL_EXTENSIONS)
+                            .pick_file();
+                        if let Some(path) = picked.and_then(|p| Utf8PathBuf::from_path_buf(p).ok())
+                        {
+                            self.compare_with(&path);
+                        }
+                    }
+                    if self.diff.is_some() && ui.button("Close Comparison").clicked() {
+                        ui.close();
+                        self.diff = None;
+                    }
+                    if ui
+                        .add_enabled(self.viewer.is_some(), egui::Button::new("Close"))
+                        .clicked()
+                    {
+                        ui.close();
+                        self.viewer = None;
+                        self.diff = None;
+                    }
+                    ui.separator();
+                    if ui.button("Quit").clicked() {
+                        ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+                    }
+                });
+                ui.menu_button("View", |ui| self.view_menu(ui));
+            });
+        });
+    }
+
+    fn view_menu(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.settings.dark_mode, "Dark mode");
+        ui.separator();
+        let code = &mut self.settings.code_style;
+        ui.menu_button("Code Theme", |ui| {
+            ui.radio_value(&mut code.theme, None, "Follow dark mode");
+            ui.separator();
+            for name in code_theme_names() {
+                ui.radio_value(&mut code.theme, Some(name.to_string()), name);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label("Code font size");
+            ui.add(
+                egui::DragValue::new(&mut code.font_size)
+                    .range(8.0..=32.0)
+                    .speed(0.5),
+            );
+        });
+    }
+
+    /// Apply the settings to the egui context and the open viewer.
+    fn apply_settings(&mut self, ctx: &egui::Context) {
+        if ctx.style().visuals.dark_mode != self.settings.dark_mode {
+            ctx.set_visuals(if self.settings.dark_mode {
+                egui::Visuals::dark()
+            } else {
+                egui::Visuals::light()
+            });
+        }
+        if let Some(viewer) = &mut self.viewer
+            && viewer.code_style != self.settings.code_style
+        {
+            viewer.code_style = self.settings.code_style.clone();
+        }
+    }
+
+    fn welcome(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 4.0);
+                ui.heading("rustylink viewer");
+                ui.label("Open a Simulink model or drop an .slx file here.");
+                ui.add_space(12.0);
+                if ui.button("Open…").clicked() {
+                    self.open_dialog(ctx);
+                }
+                if !self.recent_files.is_empty() {
+                    ui.add_space(16.0);
+                    ui.label("Recent files");
+                    let mut chosen = None;
+                    for file in &self.recent_files {
+                        if ui.link(file).clicked() {
+                            chosen = Some(file.clone());
+                        }
+                    }
+                    if let Some(file) = chosen {
+                        self.open_in_background(Utf8Path::new(&file), Vec::new(), true, ctx);
+                    }
+                }
+            });
+        });
+    }
+
+    /// Load the first model file dropped onto the window.
+    fn handle_dropped_files(&mut self, ctx: &egui::Context) {
+        let dropped = ctx.input(|i| i.raw.dropped_files.clone());
+        let Some(path) = dropped
+            .iter()
+            .filter_map(|f| f.path.as_deref())
+            .find(|p| is_model_file(p))
+        else {
+            if !dropped.is_empty() {
+                self.set_error(
+                    None,
+                    "Only .slx and system .xml files can be opened".to_string(),
+                );
+            }
+            return;
+        };
+        match Utf8Path::from_path(path) {
+            Some(path) => self.open_in_background(path, Vec::new(), true, ctx),
+            None => self.set_error(None, format!("Path is not valid UTF-8: {}", path.display())),
+        }
+    }
+
+    fn drop_hint(&self, ctx: &egui::Context) {
+        let hovering = ctx.input(|i| {
+            i.raw
+                .hovered_files
+                .iter()
+                .any(|f| f.path.as_deref().is_none_or(is_model_file))
+        });
+        if !hovering {
+            return;
+        }
+        let screen = ctx.content_rect();
+        let painter = ctx.layer_painter(egui::LayerId::new(
+            egui::Order::Foreground,
+            egui::Id::new("rustylink_viewer_drop"),
+        ));
+        painter.rect_filled(screen, 0.0, egui::Color32::from_black_alpha(120));
+        painter.text(
+            screen.center(),
+            egui::Align2::CENTER_CENTER,
+            "Drop to open",
+            egui::FontId::proportional(28.0),
+            egui::Color32::WHITE,
+        );
+    }
+
+    /// Progress shown over the window while a model is parsed.
+    fn loading_indicator(&mut self, ctx: &egui::Context) {
+        let Some(pending) = &self.loading else {
+            return;
+        };
+        let mut cancel = false;
+        egui::Window::new("Loading")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.add(egui::Spinner::new());
+                    ui.label(format!(
+                        "Loading {}…",
+                        pending.path.file_name().unwrap_or(pending.path.as_str())
+                    ));
+                });
+                if let Some(progress) = &pending.latest {
+                    ui.add(
+                        egui::ProgressBar::new(progress.fraction())
+                            .desired_width(320.0)
+                            .text(progress.to_string()),
+                    );
+                }
+                cancel = ui.button("Cancel").clicked();
+            });
+        if cancel {
+            self.cancel_load();
+        }
+    }
+
+    fn error_window(&mut self, ctx: &egui::Context) {
+        let Some(error) = &self.error else {
+            return;
+        };
+        let title = match &error.path {
+            Some(path) => format!(
+                "Could not open {}",
+                path.file_name().unwrap_or(path.as_str())
+            ),
+            None => "Could not open file".to_string(),
+        };
+        let mut dismissed = false;
+        let mut retry = None;
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                if let Some(path) = &error.path {
+                    ui.label(path.as_str());
+                    ui.add_space(4.0);
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        ui.label(egui::RichText::new(&error.message).monospace());
+                    });
+                ui.add_space(8.0);
+                ui.horizontal(|ui| {
+                    if ui.button("OK").clicked() {
+                        dismissed = true;
+                    }
+                    if let Some(path) = &error.path
+                        && ui.button("Retry").clicked()
+                    {
+                        retry = Some(path.clone());
+                    }
+                    if ui.button("Copy message").clicked() {
+                        ui.ctx().copy_text(error.message.clone());
+                    }
+                });
+            });
+        if let Some(path) = retry {
+            self.error = None;
+            self.open_in_background(&path, Vec::new(), true, ctx);
+        } else if dismissed {
+            self.error = None;
+        }
+    }
+}
+
+impl eframe::App for ViewerApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        self.poll_load(frame.storage());
+        self.handle_dropped_files(ctx);
+        self.menu_bar(ctx);
+        self.apply_settings(ctx);
+        match (&mut self.diff, &mut self.viewer) {
+            (Some(diff), _) => {
+                egui::CentralPanel::default().show(ctx, |ui| diff.show(ui));
+            }
+            (None, Some(viewer)) => viewer.update(ctx, frame),
+            (None, None) => self.welcome(ctx),
+        }
+        self.drop_hint(ctx);
+        self.loading_indicator(ctx);
+        self.error_window(ctx);
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if let Ok(text) = serde_json::to_string(&self.recent_files) {
+            storage.set_string(RECENT_FILES_KEY, text);
+        }
+        if let Ok(text) = serde_json::to_string(&self.settings) {
+            storage.set_string(SETTINGS_KEY, text);
+        }
+        if let Some(viewer) = &mut self.viewer {
+            viewer.save(storage);
+        }
+    }
+}
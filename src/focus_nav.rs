@@ -0,0 +1,96 @@
+//! Keyboard focus movement between blocks.
+//!
+//! Arrow keys move the focus to the block in that direction, preferring
+//! blocks connected to the focused one by a signal line so that following a
+//! signal path takes one key press per block. If no connected block lies in
+//! that direction, the nearest block in that direction is used instead, so
+//! every block stays reachable.
+
+use crate::generator::thumbnail::block_rect;
+use crate::model::{Block, Branch, Line, System};
+use std::collections::BTreeSet;
+
+/// Direction of a focus move, in screen terms.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FocusDirection {
+    Left,
+    Right,
+    Up,
+    Down,
+}
+
+/// Block to focus first: the top-left-most block with a SID and position.
+pub fn first_block(system: &System) -> Option<&Block> {
+    system
+        .blocks
+        .iter()
+        .filter(|b| b.sid.is_some())
+        .filter_map(|b| block_center(b).map(|c| (b, c)))
+        .min_by(|(_, a), (_, b)| (a.0 + a.1).total_cmp(&(b.0 + b.1)))
+        .map(|(b, _)| b)
+}
+
+/// SID of the block to focus when moving from `from_sid` in `direction`.
+pub fn neighbor<'a>(
+    system: &'a System,
+    from_sid: &str,
+    direction: FocusDirection,
+) -> Option<&'a str> {
+    let from = system
+        .blocks
+        .iter()
+        .find(|b| b.sid.as_deref() == Some(from_sid))?;
+    let origin = block_center(from)?;
+    let connected = connected_sids(&system.lines, from_sid);
+
+    let best = |only_connected: bool| {
+        system
+            .blocks
+            .iter()
+            .filter_map(|b| {
+                let sid = b.sid.as_deref()?;
+                if sid == from_sid || (only_connected && !connected.contains(sid)) {
+                    return None;
+                }
+                let c = block_center(b)?;
+                let (dx, dy) = (c.0 - origin.0, c.1 - origin.1);
+                let (along, across) = match direction {
+                    FocusDirection::Right => (dx, dy),
+                    FocusDirection::Left => (-dx, dy),
+                    FocusDirection::Down => (dy, dx),
+                    FocusDirection::Up => (-dy, dx),
+                };
+                // Blocks must lie in the direction of travel; sideways offset
+                // counts double so the focus stays on the same row/column.
+                (along > 0.0).then_some((sid, along + 2.0 * across.abs()))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(sid, _)| sid)
+    };
+    best(true).or_else(|| best(false))
+}
+
+/// SIDs of all blocks sharing a signal line with `sid`, in either direction.
+pub fn connected_sids<'a>(lines: &'a [Line], sid: &str) -> BTreeSet<&'a str> {
+    fn collect<'a>(branches: &'a [Branch], out: &mut Vec<&'a str>) {
+        for br in branches {
+            out.extend(br.dst.as_ref().map(|d| d.sid.as_str()));
+            collect(&br.branches, out);
+        }
+    }
+    let mut result = BTreeSet::new();
+    for line in lines {
+        let mut ends: Vec<&str> = line.src.iter().map(|s| s.sid.as_str()).collect();
+        ends.extend(line.dst.as_ref().map(|d| d.sid.as_str()));
+        collect(&line.branches, &mut ends);
+        if ends.contains(&sid) {
+            result.extend(ends.into_iter().filter(|s| *s != sid));
+        }
+    }
+    result
+}
+
+fn block_center(b: &Block) -> Option<(f32, f32)> {
+    let r = block_rect(b)?;
+    Some(((r[0] + r[2]) / 2.0, (r[1] + r[3]) / 2.0))
+}
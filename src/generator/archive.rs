@@ -7,12 +7,134 @@
 
 use crate::block;
 use crate::generator::system_xml;
+use crate::generator::thumbnail::{self, ThumbnailOptions};
 use crate::model::*;
 use anyhow::{Context, Result, anyhow};
 use roxmltree::Document;
 use std::collections::BTreeMap;
 use std::io::{Read, Seek, Write};
 
+const CONTENT_TYPES_PATH: &str = "[Content_Types].xml";
+const PACKAGE_RELS_PATH: &str = "_rels/.rels";
+/// Relationship type of system XML parts.
+pub const SYSTEM_REL_TYPE: &str = "http://schemas.mathworks.com/simulink/2010/relationships/system";
+/// Opening of an OPC `.rels` part, up to the first relationship.
+pub(crate) const RELS_HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+"#;
+const THUMBNAIL_REL_TYPE: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/metadata/thumbnail";
+
+/// Options for [`SlxArchive::write_to_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct WriteOptions {
+    /// When set, render the root system into `metadata/thumbnail.png` so file
+    /// browsers and MATLAB show an up-to-date preview. An existing thumbnail
+    /// is replaced; otherwise the entry and its package metadata are added.
+    pub thumbnail: Option<ThumbnailOptions>,
+    /// Drop archive members that are not part of the Simulink model package
+    /// (see [`is_essential_entry`]), e.g. `slprj/` leftovers, code caches, or
+    /// custom parts. Package metadata referring to the dropped members is
+    /// cleaned up as well. By default every member is copied through verbatim.
+    pub strip_nonessential: bool,
+}
+
+/// Kind of block diagram created by [`SlxArchive::new_empty`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockDiagramKind {
+    Model,
+    Library,
+}
+
+impl BlockDiagramKind {
+    /// Element name below `<ModelInformation>` in `blockdiagram.xml`.
+    fn tag(self) -> &'static str {
+        match self {
+            BlockDiagramKind::Model => "Model",
+            BlockDiagramKind::Library => "Library",
+        }
+    }
+}
+
+/// Returns `true` if `path` is one of the members MATLAB needs to open the
+/// model: the OPC package metadata (`[Content_Types].xml`, `_rels/`), the
+/// `metadata/` parts, and everything below `simulink/`.
+///
+/// Anything else (code caches, `slprj/` leftovers, tool-specific custom parts)
+/// is considered non-essential and is only dropped when
+/// [`WriteOptions::strip_nonessential`] is set.
+pub fn is_essential_entry(path: &str) -> bool {
+    let p = path.trim_start_matches("./").trim_start_matches('/');
+    p == CONTENT_TYPES_PATH
+        || p.starts_with("_rels/")
+        || p.starts_with("metadata/")
+        || p.starts_with("simulink/")
+}
+
+/// Remove every self-closing `<tag …/>` element whose `attr` value satisfies
+/// `remove`. Used to drop `[Content_Types].xml` overrides and package
+/// relationships that point to stripped members.
+pub(crate) fn remove_xml_elements(
+    xml: &str,
+    tag: &str,
+    attr: &str,
+    remove: impl Fn(&str) -> bool,
+) -> String {
+    let open = format!("<{} ", tag);
+    let needle = format!("{}=\"", attr);
+    let mut out = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let Some(len) = rest[start..].find("/>").map(|i| i + 2) else {
+            break;
+        };
+        let element = &rest[start..start + len];
+        let value = element.find(&needle).and_then(|i| {
+            let v = &element[i + needle.len()..];
+            v.find('"').map(|end| &v[..end])
+        });
+        out.push_str(&rest[..start]);
+        if !value.is_some_and(&remove) {
+            out.push_str(element);
+        }
+        rest = &rest[start + len..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Add a `png` default content type to `[Content_Types].xml` if missing.
+fn ensure_png_content_type(xml: &str) -> String {
+    if xml.contains("Extension=\"png\"") {
+        return xml.to_string();
+    }
+    match xml.rfind("</Types>") {
+        Some(idx) => format!(
+            "{}<Default Extension=\"png\" ContentType=\"image/png\"/>{}",
+            &xml[..idx],
+            &xml[idx..]
+        ),
+        None => xml.to_string(),
+    }
+}
+
+/// Add a thumbnail relationship to the package `_rels/.rels` if missing.
+fn ensure_thumbnail_relationship(xml: &str) -> String {
+    if xml.contains(THUMBNAIL_REL_TYPE) {
+        return xml.to_string();
+    }
+    match xml.rfind("</Relationships>") {
+        Some(idx) => format!(
+            "{}<Relationship Id=\"rIdThumbnail\" Target=\"{}\" Type=\"{}\"/>{}",
+            &xml[..idx],
+            THUMBNAIL_PATH,
+            THUMBNAIL_REL_TYPE,
+            &xml[idx..]
+        ),
+        None => xml.to_string(),
+    }
+}
+
 /// Returns `true` if the given path is a system XML file that should be parsed.
 fn is_system_xml(path: &str) -> bool {
     // Match paths like "simulink/systems/system_root.xml" or "simulink/systems/system_18.xml"
@@ -24,11 +146,86 @@ fn is_system_xml(path: &str) -> bool {
     }
 }
 
+/// Returns `true` if the given path is a Stateflow chart XML file.
+fn is_chart_xml(path: &str) -> bool {
+    let normalized = path.trim_start_matches("./").trim_start_matches('/');
+    if let Some(rest) = normalized.strip_prefix("simulink/stateflow/") {
+        rest.starts_with("chart_") && rest.ends_with(".xml") && !rest.contains('/')
+    } else {
+        false
+    }
+}
+
+/// The `<P Name="script">` element of a chart's MATLAB function: the one in
+/// the `<eml>` of its first state that has one.
+fn chart_script_node<'a, 'input>(
+    chart: roxmltree::Node<'a, 'input>,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    chart
+        .descendants()
+        .filter(|c| c.is_element() && c.has_tag_name("state"))
+        .filter_map(|st| {
+            st.children()
+                .find(|c| c.is_element() && c.has_tag_name("eml"))
+        })
+        .find_map(|eml| {
+            eml.children().find(|c| {
+                c.is_element() && c.has_tag_name("P") && c.attribute("Name") == Some("script")
+            })
+        })
+}
+
+/// Replace the script in the chart XML `text` if the chart has id `chart_id`.
+/// Only the content of the script element changes.
+fn splice_chart_script(text: &str, chart_id: u32, script: &str) -> Result<Option<String>> {
+    let doc = Document::parse(text)?;
+    let Some(chart) = doc
+        .descendants()
+        .find(|n| n.is_element() && n.has_tag_name("chart"))
+        .filter(|c| c.attribute("id").and_then(|id| id.parse().ok()) == Some(chart_id))
+    else {
+        return Ok(None);
+    };
+    let node = chart_script_node(chart)
+        .ok_or_else(|| anyhow!("Chart {} has no MATLAB script", chart_id))?;
+    let range = node.range();
+    let element = &text[range.clone()];
+    let start_tag_end = element
+        .find('>')
+        .ok_or_else(|| anyhow!("Malformed script element in chart {}", chart_id))?;
+    let escaped = system_xml::xml_escape(script);
+    let replaced = if element[..start_tag_end].ends_with('/') {
+        format!(
+            "{}>{}</P>",
+            element[..start_tag_end - 1].trim_end(),
+            escaped
+        )
+    } else {
+        let end_tag_start = element
+            .rfind("</")
+            .ok_or_else(|| anyhow!("Malformed script element in chart {}", chart_id))?;
+        format!(
+            "{}{}{}",
+            &element[..=start_tag_end],
+            escaped,
+            &element[end_tag_start..]
+        )
+    };
+    Ok(Some(format!(
+        "{}{}{}",
+        &text[..range.start],
+        replaced,
+        &text[range.end..]
+    )))
+}
+
 impl SlxArchive {
     /// Read an SLX file from a reader (ZIP format).
     ///
     /// System XML files are parsed into [`System`] models; all other files are
     /// stored as raw bytes. The entry order and compression settings are preserved.
+    /// Block parameters referring to variables of embedded MAT files are
+    /// resolved into [`Block::workspace_values`].
     pub fn from_reader<R: Read + Seek>(reader: R) -> Result<Self> {
         let mut zip = zip::ZipArchive::new(reader).context("Failed to open SLX ZIP")?;
         let mut entries = Vec::with_capacity(zip.len());
@@ -82,10 +279,76 @@ impl SlxArchive {
 
         // Resolve BindingPersistence refs for dashboard/HMI blocks.
         archive.resolve_dashboard_bindings();
+        archive.resolve_workspace_values();
 
         Ok(archive)
     }
 
+    /// Create an archive holding an empty block diagram named `name`.
+    ///
+    /// `name` must be a valid MATLAB identifier, since Simulink uses it as the
+    /// first segment of every block path (and `SourceBlock`) in the diagram.
+    pub fn new_empty(kind: BlockDiagramKind, name: &str) -> Result<Self> {
+        let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(anyhow!("Invalid block diagram name {:?}", name));
+        }
+        let tag = kind.tag();
+        let blockdiagram = format!(
+            r#"<?xml version="1.0" encoding="utf-8"?>
+<ModelInformation Version="1.0">
+  <{tag}>
+    <P Name="Name">{name}</P>
+    <System Ref="system_root"/>
+  </{tag}>
+</ModelInformation>
+"#
+        );
+        let files = [
+            (
+                CONTENT_TYPES_PATH,
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types">
+  <Default ContentType="application/vnd.openxmlformats-package.relationships+xml" Extension="rels"/>
+  <Default ContentType="application/vnd.mathworks.simulink.mdl+xml" Extension="xml"/>
+</Types>
+"#
+                .to_string(),
+            ),
+            (
+                PACKAGE_RELS_PATH,
+                format!(
+                    "{RELS_HEADER}  <Relationship Id=\"blockDiagram\" Target=\"simulink/blockdiagram.xml\" Type=\"http://schemas.mathworks.com/simulink/2010/relationships/blockDiagram\"/>\n</Relationships>\n"
+                ),
+            ),
+            ("simulink/blockdiagram.xml", blockdiagram),
+            (
+                "simulink/_rels/blockdiagram.xml.rels",
+                format!(
+                    "{RELS_HEADER}  <Relationship Id=\"system_root\" Target=\"systems/system_root.xml\" Type=\"{SYSTEM_REL_TYPE}\"/>\n</Relationships>\n"
+                ),
+            ),
+            (
+                "simulink/systems/system_root.xml",
+                "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<System>\n</System>\n".to_string(),
+            ),
+        ];
+        let mut buf = std::io::Cursor::new(Vec::new());
+        {
+            let mut zip = zip::ZipWriter::new(&mut buf);
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            for (path, content) in files {
+                zip.start_file(path, options)?;
+                zip.write_all(content.as_bytes())?;
+            }
+            zip.finish()?;
+        }
+        buf.set_position(0);
+        Self::from_reader(buf)
+    }
+
     /// Read an SLX file from disk.
     pub fn from_file(path: impl AsRef<std::path::Path>) -> Result<Self> {
         let file = std::fs::File::open(path.as_ref())
@@ -99,9 +362,47 @@ impl SlxArchive {
     /// System XML entries are regenerated from their [`System`] model;
     /// all other entries are written from their raw bytes.
     pub fn write_to<W: Write + Seek>(&self, writer: W) -> Result<()> {
+        self.write_to_with_options(writer, &WriteOptions::default())
+    }
+
+    /// Write the archive to a writer, applying the given [`WriteOptions`].
+    pub fn write_to_with_options<W: Write + Seek>(
+        &self,
+        writer: W,
+        options: &WriteOptions,
+    ) -> Result<()> {
         let mut zip = zip::ZipWriter::new(writer);
 
+        let thumbnail = match options.thumbnail {
+            Some(thumb_opts) => {
+                let root = self
+                    .root_system()
+                    .ok_or_else(|| anyhow!("Cannot render thumbnail: no root system in archive"))?;
+                Some(
+                    thumbnail::render_thumbnail_png(root, thumb_opts)
+                        .context("Cannot render thumbnail")?,
+                )
+            }
+            None => None,
+        };
+        let add_thumbnail =
+            thumbnail.is_some() && !self.entries.iter().any(|e| e.path == THUMBNAIL_PATH);
+
+        let stripped: std::collections::BTreeSet<&str> = if options.strip_nonessential {
+            self.entries
+                .iter()
+                .map(|e| e.path.trim_start_matches("./").trim_start_matches('/'))
+                .filter(|p| !is_essential_entry(p))
+                .collect()
+        } else {
+            Default::default()
+        };
+        let is_stripped = |target: &str| stripped.contains(target.trim_start_matches('/'));
+
         for entry in &self.entries {
+            if is_stripped(entry.path.trim_start_matches("./")) {
+                continue;
+            }
             let options = if entry.compressed {
                 zip::write::FileOptions::default()
                     .compression_method(zip::CompressionMethod::Deflated)
@@ -110,17 +411,57 @@ impl SlxArchive {
                     .compression_method(zip::CompressionMethod::Stored)
             };
 
-            zip.start_file(&entry.path, options)?;
-
-            match &entry.content {
-                SlxContent::Raw(data) => {
-                    zip.write_all(data)?;
+            let data = match &entry.content {
+                SlxContent::Raw(data) if entry.path.ends_with('/') && data.is_empty() => {
+                    // Preserve explicit directory members as directories.
+                    zip.add_directory(entry.path.trim_end_matches('/'), options)?;
+                    continue;
+                }
+                SlxContent::Raw(_) if entry.path == THUMBNAIL_PATH && thumbnail.is_some() => {
+                    std::borrow::Cow::Borrowed(thumbnail.as_deref().unwrap_or_default())
+                }
+                SlxContent::Raw(data) if entry.path == CONTENT_TYPES_PATH => {
+                    let mut xml = String::from_utf8_lossy(data).into_owned();
+                    if add_thumbnail {
+                        xml = ensure_png_content_type(&xml);
+                    }
+                    if !stripped.is_empty() {
+                        xml = remove_xml_elements(&xml, "Override", "PartName", is_stripped);
+                    }
+                    if xml.as_bytes() == data.as_slice() {
+                        std::borrow::Cow::Borrowed(data.as_slice())
+                    } else {
+                        std::borrow::Cow::Owned(xml.into_bytes())
+                    }
                 }
+                SlxContent::Raw(data) if entry.path == PACKAGE_RELS_PATH => {
+                    let mut xml = String::from_utf8_lossy(data).into_owned();
+                    if add_thumbnail {
+                        xml = ensure_thumbnail_relationship(&xml);
+                    }
+                    if !stripped.is_empty() {
+                        xml = remove_xml_elements(&xml, "Relationship", "Target", is_stripped);
+                    }
+                    if xml.as_bytes() == data.as_slice() {
+                        std::borrow::Cow::Borrowed(data.as_slice())
+                    } else {
+                        std::borrow::Cow::Owned(xml.into_bytes())
+                    }
+                }
+                SlxContent::Raw(data) => std::borrow::Cow::Borrowed(data.as_slice()),
                 SlxContent::SystemXml(system) => {
-                    let xml = system_xml::generate_system_xml(system);
-                    zip.write_all(xml.as_bytes())?;
+                    std::borrow::Cow::Owned(system_xml::generate_system_xml(system).into_bytes())
                 }
-            }
+            };
+            zip.start_file(&entry.path, options)?;
+            zip.write_all(&data)?;
+        }
+
+        if let (true, Some(png)) = (add_thumbnail, &thumbnail) {
+            let options = zip::write::FileOptions::default()
+                .compression_method(zip::CompressionMethod::Deflated);
+            zip.start_file(THUMBNAIL_PATH, options)?;
+            zip.write_all(png)?;
         }
 
         zip.finish()?;
@@ -129,10 +470,19 @@ impl SlxArchive {
 
     /// Write the archive to a file on disk.
     pub fn write_to_file(&self, path: impl AsRef<std::path::Path>) -> Result<()> {
+        self.write_to_file_with_options(path, &WriteOptions::default())
+    }
+
+    /// Write the archive to a file on disk, applying the given [`WriteOptions`].
+    pub fn write_to_file_with_options(
+        &self,
+        path: impl AsRef<std::path::Path>,
+        options: &WriteOptions,
+    ) -> Result<()> {
         let file = std::fs::File::create(path.as_ref())
             .with_context(|| format!("Failed to create {}", path.as_ref().display()))?;
         let writer = std::io::BufWriter::new(file);
-        self.write_to(writer)
+        self.write_to_with_options(writer, options)
     }
 
     /// Get the System model for a given entry path.
@@ -175,6 +525,17 @@ impl SlxArchive {
         self.entries.iter().map(|e| e.path.as_str()).collect()
     }
 
+    /// List the entry paths that are not part of the Simulink model package
+    /// (see [`is_essential_entry`]). These are copied through verbatim unless
+    /// [`WriteOptions::strip_nonessential`] is set.
+    pub fn nonessential_entry_paths(&self) -> Vec<&str> {
+        self.entries
+            .iter()
+            .map(|e| e.path.as_str())
+            .filter(|p| !is_essential_entry(p))
+            .collect()
+    }
+
     /// Resolve a `Ref="bdmxdata:…"` style reference to a file path within the
     /// archive, using the parsed relationships from `blockdiagram.xml.rels`.
     ///
@@ -207,6 +568,15 @@ impl SlxArchive {
         })
     }
 
+    /// Collect all embedded images (mask images, annotation images, the model
+    /// thumbnail, …) from the raw entries into an [`AssetRegistry`].
+    pub fn assets(&self) -> AssetRegistry {
+        collect_assets(self.entries.iter().filter_map(|e| match &e.content {
+            SlxContent::Raw(data) => Some((e.path.as_str(), data.as_slice())),
+            SlxContent::SystemXml(_) => None,
+        }))
+    }
+
     /// Look up a BindingPersistence ref and return the raw `.mxarray` bytes.
     ///
     /// The `ref_value` should be of the form `"bdmxdata:BindingPersistence_NNN"`.
@@ -266,6 +636,33 @@ impl SlxArchive {
         }
     }
 
+    /// Resolve parameters referring to model workspace variables stored in
+    /// MAT files of the archive into [`crate::model::Block::workspace_values`].
+    ///
+    /// MAT files that cannot be read leave the parameters unresolved rather
+    /// than failing to open the archive.
+    fn resolve_workspace_values(&mut self) {
+        let Ok(workspace) = crate::workspace::ModelWorkspace::from_archive(self) else {
+            return;
+        };
+        if workspace.is_empty() {
+            return;
+        }
+        fn apply(system: &mut System, workspace: &crate::workspace::ModelWorkspace) {
+            for block in &mut system.blocks {
+                crate::mask_eval::resolve_workspace_values(block, workspace);
+                if let Some(sub) = &mut block.subsystem {
+                    apply(sub, workspace);
+                }
+            }
+        }
+        for entry in &mut self.entries {
+            if let SlxContent::SystemXml(ref mut system) = entry.content {
+                apply(system, &workspace);
+            }
+        }
+    }
+
     /// Recursively collect all `BindingPersistence` ref values from a system.
     fn collect_binding_refs<F: FnMut(&str)>(system: &System, cb: &mut F) {
         for block in &system.blocks {
@@ -367,14 +764,7 @@ impl SlxArchive {
         let chart_texts: Vec<(&str, String)> = self
             .entries
             .iter()
-            .filter(|e| {
-                let norm = e.path.trim_start_matches("./").trim_start_matches('/');
-                if let Some(rest) = norm.strip_prefix("simulink/stateflow/") {
-                    rest.starts_with("chart_") && rest.ends_with(".xml") && !rest.contains('/')
-                } else {
-                    false
-                }
-            })
+            .filter(|e| is_chart_xml(&e.path))
             .filter_map(|e| {
                 if let SlxContent::Raw(ref data) = e.content {
                     std::str::from_utf8(data)
@@ -408,6 +798,50 @@ impl SlxArchive {
         (charts_by_id, chart_map)
     }
 
+    /// Replace the MATLAB script of the Stateflow chart with id `chart_id`,
+    /// e.g. the code of a MATLAB Function block, in its
+    /// `simulink/stateflow/chart_*.xml` entry. The rest of the chart XML is
+    /// kept as it is. Returns whether the script changed.
+    pub fn set_chart_script(&mut self, chart_id: u32, script: &str) -> Result<bool> {
+        for entry in &mut self.entries {
+            let SlxContent::Raw(data) = &mut entry.content else {
+                continue;
+            };
+            if !is_chart_xml(&entry.path) {
+                continue;
+            }
+            let text = std::str::from_utf8(data)
+                .with_context(|| format!("Non-UTF8 content in {}", entry.path))?;
+            let Some(updated) = splice_chart_script(text, chart_id, script)
+                .with_context(|| format!("Failed to update {}", entry.path))?
+            else {
+                continue;
+            };
+            let changed = updated != text;
+            *data = updated.into_bytes();
+            return Ok(changed);
+        }
+        Err(anyhow!("No chart with id {} in archive", chart_id))
+    }
+
+    /// Write the scripts of `charts`, as returned by
+    /// [`SlxArchive::parse_charts`] and edited since, back into the archive.
+    /// Charts without a script or not stored in this archive are skipped.
+    /// Returns the number of charts whose script changed.
+    pub fn update_chart_scripts(&mut self, charts: &BTreeMap<u32, Chart>) -> Result<usize> {
+        let (stored, _) = self.parse_charts();
+        let mut changed = 0;
+        for (id, chart) in charts {
+            let (Some(script), Some(old)) = (&chart.script, stored.get(id)) else {
+                continue;
+            };
+            if old.script.as_ref() != Some(script) && self.set_chart_script(*id, script)? {
+                changed += 1;
+            }
+        }
+        Ok(changed)
+    }
+
     /// Return library names from `simulink/graphicalInterface.json`.
     ///
     /// Reads the raw entry, deserializes the JSON, and extracts library names
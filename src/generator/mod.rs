@@ -3,6 +3,8 @@
 //! This module provides:
 //! - [`system_xml`] – Generate system XML text from a [`System`] model.
 //! - [`archive`] – Read and write complete SLX ZIP archives with round-trip fidelity.
+//! - [`thumbnail`] – Render a PNG preview of a system for `metadata/thumbnail.png`.
 
 pub mod archive;
 pub mod system_xml;
+pub mod thumbnail;
@@ -24,7 +24,7 @@ fn indent(out: &mut String, level: usize) {
 
 /// Escape text content for XML. Matches Simulink's escaping which encodes
 /// `&`, `<`, `>`, `"`, and `'` even in text content.
-fn xml_escape(s: &str) -> String {
+pub(crate) fn xml_escape(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for ch in s.chars() {
         match ch {
@@ -41,7 +41,7 @@ fn xml_escape(s: &str) -> String {
 
 /// Escape an attribute value for XML. Like [`xml_escape`] but also encodes
 /// newlines as `&#xA;` and carriage returns as `&#xD;`.
-fn xml_escape_attr(s: &str) -> String {
+pub(crate) fn xml_escape_attr(s: &str) -> String {
     let mut out = String::with_capacity(s.len());
     for ch in s.chars() {
         match ch {
@@ -197,6 +197,9 @@ fn write_port_counts(out: &mut String, pc: &PortCounts, level: usize) {
     if let Some(outs) = pc.outs {
         out.push_str(&format!(" out=\"{}\"", outs));
     }
+    for (kind, count) in &pc.special {
+        out.push_str(&format!(" {}=\"{}\"", xml_escape(kind.as_str()), count));
+    }
     out.push_str("/>\n");
 }
 
@@ -0,0 +1,385 @@
+//! Model thumbnail rendering for `metadata/thumbnail.png`.
+//!
+//! Produces a small raster preview of a [`System`]: blocks are
+//! drawn as filled rectangles (using their `BackgroundColor`, `ForegroundColor`
+//! and `DropShadow` where available) and signal lines as polylines, thicker for
+//! nonscalar and bus signals and dashed for control signals. The image is
+//! encoded as an RGBA PNG compressed with `flate2`.
+
+use crate::model::{Block, BlockOrientation, Branch, EndpointRef, PortKind, System};
+use crate::signal_kind::{SignalKind, infer_line_kinds};
+use anyhow::{Result, anyhow};
+use flate2::write::ZlibEncoder;
+use flate2::{Compression, Crc};
+use std::collections::HashMap;
+use std::io::Write;
+
+/// Size of the generated thumbnail in pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ThumbnailOptions {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Default for ThumbnailOptions {
+    fn default() -> Self {
+        Self {
+            width: 256,
+            height: 256,
+        }
+    }
+}
+
+type Rgba = [u8; 4];
+
+const WHITE: Rgba = [255, 255, 255, 255];
+const BLOCK_OUTLINE: Rgba = [40, 40, 40, 255];
+const LINE_COLOR: Rgba = [0, 0, 0, 255];
+const SHADOW: Rgba = [150, 150, 150, 255];
+/// Largest canvas rendered, in pixels (64 Mi, i.e. 256 MiB of RGBA).
+const MAX_PIXELS: usize = 1 << 26;
+
+/// Render `system` into PNG bytes suitable for `metadata/thumbnail.png`.
+///
+/// Fails if the requested size doesn't fit in memory or the image can't be
+/// compressed.
+pub fn render_thumbnail_png(system: &System, options: ThumbnailOptions) -> Result<Vec<u8>> {
+    let width = options.width.max(1);
+    let height = options.height.max(1);
+    let mut canvas = Canvas::new(width, height)?;
+
+    let (rects, polylines) = diagram_geometry(system);
+
+    // Fit the diagram's bounding box into the canvas with a small margin.
+    let mut bounds = [
+        f32::INFINITY,
+        f32::INFINITY,
+        f32::NEG_INFINITY,
+        f32::NEG_INFINITY,
+    ];
+    let mut grow = |x: f32, y: f32| {
+        bounds[0] = bounds[0].min(x);
+        bounds[1] = bounds[1].min(y);
+        bounds[2] = bounds[2].max(x);
+        bounds[3] = bounds[3].max(y);
+    };
+    for (_, r) in &rects {
+        grow(r[0], r[1]);
+        grow(r[2], r[3]);
+    }
+    for p in polylines.iter().flat_map(|(_, pts)| pts) {
+        grow(p.0, p.1);
+    }
+    if !bounds[0].is_finite() {
+        return encode_png(width, height, &canvas.pixels);
+    }
+    let margin = 0.05 * width.min(height) as f32;
+    let span_x = (bounds[2] - bounds[0]).max(1.0);
+    let span_y = (bounds[3] - bounds[1]).max(1.0);
+    let scale =
+        ((width as f32 - 2.0 * margin) / span_x).min((height as f32 - 2.0 * margin) / span_y);
+    let off_x = (width as f32 - span_x * scale) / 2.0;
+    let off_y = (height as f32 - span_y * scale) / 2.0;
+    let to_px = |x: f32, y: f32| {
+        (
+            (x - bounds[0]) * scale + off_x,
+            (y - bounds[1]) * scale + off_y,
+        )
+    };
+
+    for (kind, pts) in &polylines {
+        let (thickness, dash) = match kind {
+            SignalKind::Scalar => (1, None),
+            SignalKind::Vector(_) => (2, None),
+            SignalKind::Bus => (3, None),
+            SignalKind::Control => (1, Some((4, 3))),
+        };
+        for w in pts.windows(2) {
+            let a = to_px(w[0].0, w[0].1);
+            let b = to_px(w[1].0, w[1].1);
+            canvas.styled_line(a, b, LINE_COLOR, thickness, dash);
+        }
+    }
+    for (b, r) in &rects {
+        let (l, t) = to_px(r[0], r[1]);
+        let (rr, bb) = to_px(r[2], r[3]);
+        if b.drop_shadow {
+            canvas.fill_rect(l + 2.0, t + 2.0, rr + 2.0, bb + 2.0, SHADOW);
+        }
+        let fill = b.background_color.map_or(WHITE, |c| c.to_array());
+        let outline = b.foreground_color.map_or(BLOCK_OUTLINE, |c| c.to_array());
+        canvas.fill_rect(l, t, rr, bb, fill);
+        canvas.stroke_rect(l, t, rr, bb, outline);
+    }
+
+    encode_png(width, height, &canvas.pixels)
+}
+
+/// A block with its `Position` rectangle `[left, top, right, bottom]`.
+pub(crate) type PlacedBlock<'a> = (&'a Block, [f32; 4]);
+/// A signal line or branch as a polyline in model coordinates.
+pub(crate) type Polyline = (SignalKind, Vec<(f32, f32)>);
+
+/// Block rectangles and signal polylines of `system` in model coordinates.
+///
+/// Blocks without a parseable `Position` are skipped, as are lines whose
+/// source block is missing.
+pub(crate) fn diagram_geometry(system: &System) -> (Vec<PlacedBlock<'_>>, Vec<Polyline>) {
+    let rects: Vec<(&Block, [f32; 4])> = system
+        .blocks
+        .iter()
+        .filter_map(|b| block_rect(b).map(|r| (b, r)))
+        .collect();
+    let polylines = line_polylines(system).into_iter().flatten().collect();
+    (rects, polylines)
+}
+
+/// The polylines of each line of `system`, in line order: the branches,
+/// then the trunk. Empty for lines whose source block is missing.
+pub(crate) fn line_polylines(system: &System) -> Vec<Vec<Polyline>> {
+    let by_sid: HashMap<&str, (&Block, [f32; 4])> = system
+        .blocks
+        .iter()
+        .filter_map(|b| Some((b.sid.as_deref()?, (b, block_rect(b)?))))
+        .collect();
+
+    let kinds = infer_line_kinds(&system.blocks, &system.lines);
+    let mut lines = Vec::new();
+    for (line, &kind) in system.lines.iter().zip(&kinds) {
+        let mut polylines: Vec<Polyline> = Vec::new();
+        if let Some(start) = line.src.as_ref().and_then(|s| anchor(&by_sid, s)) {
+            let mut pts = vec![start];
+            let mut cur = start;
+            for off in &line.points {
+                cur = (cur.0 + off.x as f32, cur.1 + off.y as f32);
+                pts.push(cur);
+            }
+            if let Some(end) = line.dst.as_ref().and_then(|d| anchor(&by_sid, d)) {
+                pts.push(end);
+            }
+            collect_branches(&line.branches, cur, &by_sid, kind, &mut polylines);
+            polylines.push((kind, pts));
+        }
+        lines.push(polylines);
+    }
+    lines
+}
+
+/// `Position` of a block as `[left, top, right, bottom]`.
+pub(crate) fn block_rect(b: &Block) -> Option<[f32; 4]> {
+    let pos = b.position.as_deref()?;
+    let inner = pos.trim().trim_start_matches('[').trim_end_matches(']');
+    let nums: Vec<f32> = inner
+        .split(',')
+        .filter_map(|s| s.trim().parse::<f32>().ok())
+        .collect();
+    (nums.len() == 4).then(|| [nums[0], nums[1], nums[2], nums[3]])
+}
+
+/// Port anchor on the block edge; ports are spread evenly along the side.
+/// Control ports are spread over the top edge and the state port sits in
+/// the middle of the bottom edge, turned with the block.
+fn anchor(by_sid: &HashMap<&str, (&Block, [f32; 4])>, ep: &EndpointRef) -> Option<(f32, f32)> {
+    let (block, r) = by_sid.get(ep.sid.as_str())?;
+    if ep.port_type.is_control() || ep.port_type == PortKind::State {
+        let top = ep.port_type.is_control();
+        let f = if top {
+            let controls = block.control_ports();
+            let i = controls
+                .iter()
+                .position(|k| *k == ep.port_type)
+                .unwrap_or(0);
+            (i as f32 + 0.5) / controls.len().max(1) as f32
+        } else {
+            0.5
+        };
+        let x = r[0] + (r[2] - r[0]) * f;
+        let y = r[1] + (r[3] - r[1]) * f;
+        return Some(match (block.orientation, top) {
+            (BlockOrientation::Right, true) | (BlockOrientation::Left, false) => (x, r[1]),
+            (BlockOrientation::Right, false) | (BlockOrientation::Left, true) => (x, r[3]),
+            (BlockOrientation::Down, true) | (BlockOrientation::Up, false) => (r[2], y),
+            (BlockOrientation::Down, false) | (BlockOrientation::Up, true) => (r[0], y),
+        });
+    }
+    let count = block
+        .port_counts
+        .as_ref()
+        .and_then(|pc| {
+            if ep.port_type == "out" {
+                pc.outs
+            } else {
+                pc.ins
+            }
+        })
+        .unwrap_or(1)
+        .max(ep.port_index)
+        .max(1);
+    let idx = ep.port_index.max(1);
+    let f = (idx as f32 - 0.5) / count as f32;
+    let x = r[0] + (r[2] - r[0]) * f;
+    let y = r[1] + (r[3] - r[1]) * f;
+    let mirrored = block.block_mirror.unwrap_or(false);
+    let upstream = ep.port_type.is_input() ^ mirrored;
+    Some(match (block.orientation, upstream) {
+        (BlockOrientation::Right, true) | (BlockOrientation::Left, false) => (r[0], y),
+        (BlockOrientation::Right, false) | (BlockOrientation::Left, true) => (r[2], y),
+        (BlockOrientation::Down, true) | (BlockOrientation::Up, false) => (x, r[1]),
+        (BlockOrientation::Down, false) | (BlockOrientation::Up, true) => (x, r[3]),
+    })
+}
+
+fn collect_branches(
+    branches: &[Branch],
+    start: (f32, f32),
+    by_sid: &HashMap<&str, (&Block, [f32; 4])>,
+    kind: SignalKind,
+    out: &mut Vec<(SignalKind, Vec<(f32, f32)>)>,
+) {
+    for br in branches {
+        let mut pts = vec![start];
+        let mut cur = start;
+        for off in &br.points {
+            cur = (cur.0 + off.x as f32, cur.1 + off.y as f32);
+            pts.push(cur);
+        }
+        if let Some(end) = br.dst.as_ref().and_then(|d| anchor(by_sid, d)) {
+            pts.push(end);
+        }
+        collect_branches(&br.branches, cur, by_sid, kind, out);
+        out.push((kind, pts));
+    }
+}
+
+/// RGBA pixel buffer the thumbnail is drawn into, row by row.
+struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32) -> Result<Self> {
+        let pixels = (width as usize)
+            .checked_mul(height as usize)
+            .filter(|n| *n <= MAX_PIXELS)
+            .ok_or_else(|| anyhow!("Thumbnail size {width}x{height} is too large"))?;
+        Ok(Self {
+            width,
+            height,
+            pixels: WHITE.repeat(pixels),
+        })
+    }
+
+    fn put(&mut self, x: i64, y: i64, c: Rgba) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let i = (y as usize * self.width as usize + x as usize) * 4;
+        self.pixels[i..i + 4].copy_from_slice(&c);
+    }
+
+    fn fill_rect(&mut self, l: f32, t: f32, r: f32, b: f32, c: Rgba) {
+        let (x0, x1) = (l.min(r).round() as i64, l.max(r).round() as i64);
+        let (y0, y1) = (t.min(b).round() as i64, t.max(b).round() as i64);
+        for y in y0..=y1 {
+            for x in x0..=x1 {
+                self.put(x, y, c);
+            }
+        }
+    }
+
+    fn stroke_rect(&mut self, l: f32, t: f32, r: f32, b: f32, c: Rgba) {
+        self.line((l, t), (r, t), c);
+        self.line((r, t), (r, b), c);
+        self.line((r, b), (l, b), c);
+        self.line((l, b), (l, t), c);
+    }
+
+    fn line(&mut self, a: (f32, f32), b: (f32, f32), c: Rgba) {
+        self.styled_line(a, b, c, 1, None);
+    }
+
+    /// Bresenham line between two pixel positions, `thickness` pixels wide and
+    /// optionally dashed as `(on, off)` pixel runs.
+    fn styled_line(
+        &mut self,
+        a: (f32, f32),
+        b: (f32, f32),
+        c: Rgba,
+        thickness: i64,
+        dash: Option<(u32, u32)>,
+    ) {
+        let (mut x0, mut y0) = (a.0.round() as i64, a.1.round() as i64);
+        let (x1, y1) = (b.0.round() as i64, b.1.round() as i64);
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut err = dx + dy;
+        let lo = -(thickness - 1) / 2;
+        let hi = thickness / 2;
+        let mut step = 0u32;
+        loop {
+            let on = dash.is_none_or(|(on, off)| step % (on + off) < on);
+            if on {
+                for oy in lo..=hi {
+                    for ox in lo..=hi {
+                        self.put(x0 + ox, y0 + oy, c);
+                    }
+                }
+            }
+            step += 1;
+            if x0 == x1 && y0 == y1 {
+                break;
+            }
+            let e2 = 2 * err;
+            if e2 >= dy {
+                err += dy;
+                x0 += sx;
+            }
+            if e2 <= dx {
+                err += dx;
+                y0 += sy;
+            }
+        }
+    }
+}
+
+// ── Minimal PNG encoder (RGBA8, zlib via flate2) ───────────────────────────
+
+fn encode_png(width: u32, height: u32, rgba: &[u8]) -> Result<Vec<u8>> {
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&width.to_be_bytes());
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    // bit depth 8, color type 6 (RGBA), compression 0, filter 0, interlace 0
+    ihdr.extend_from_slice(&[8, 6, 0, 0, 0]);
+    write_chunk(&mut out, b"IHDR", &ihdr)?;
+
+    let row_len = width as usize * 4;
+    let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+    for row in rgba.chunks(row_len) {
+        // Filter type none.
+        zlib.write_all(&[0])?;
+        zlib.write_all(row)?;
+    }
+    let idat = zlib.finish()?;
+    write_chunk(&mut out, b"IDAT", &idat)?;
+    write_chunk(&mut out, b"IEND", &[])?;
+    Ok(out)
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) -> Result<()> {
+    let len = u32::try_from(data.len())
+        .map_err(|_| anyhow!("PNG {} chunk is too large", String::from_utf8_lossy(kind)))?;
+    out.extend_from_slice(&len.to_be_bytes());
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let mut crc = Crc::new();
+    crc.update(&out[start..]);
+    out.extend_from_slice(&crc.sum().to_be_bytes());
+    Ok(())
+}
@@ -0,0 +1,43 @@
+//! Content hashes of a model, to tell cheaply whether it changed.
+//!
+//! [`System::full_hash`] covers everything the parser keeps, in document
+//! order. [`System::structural_hash`] covers what the model computes and
+//! leaves out how it looks: the content the default [`CosmeticFilter`]
+//! treats as cosmetic (block and line geometry, z-order, fonts, colors,
+//! annotations, editor window state) and the order of blocks and lines in
+//! the files. Moving a block or re-saving the model keeps the structural
+//! hash; changing a parameter, a connection or a chart script changes it.
+//! [`System::structural_hash_with`] takes another filter.
+//!
+//! Both are 64-bit FNV-1a hashes of the model's JSON form, so they are the
+//! same on every platform and between runs, and only change with the JSON
+//! format of the model types.
+
+use crate::cosmetic::CosmeticFilter;
+use crate::model::System;
+
+impl System {
+    /// Hash of the model without its cosmetic content; see the
+    /// [module docs](crate::hash).
+    pub fn structural_hash(&self) -> u64 {
+        self.structural_hash_with(&CosmeticFilter::default())
+    }
+
+    /// [`System::structural_hash`] leaving out what `filter` treats as
+    /// cosmetic.
+    pub fn structural_hash_with(&self, filter: &CosmeticFilter) -> u64 {
+        let value = filter.to_value(self).unwrap_or_default();
+        fnv1a(&serde_json::to_vec(&value).unwrap_or_default())
+    }
+
+    /// Hash of the whole model, including layout and document order.
+    pub fn full_hash(&self) -> u64 {
+        fnv1a(&serde_json::to_vec(self).unwrap_or_default())
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
@@ -0,0 +1,205 @@
+//! Model interfaces and compatibility checks between model versions.
+//!
+//! [`ModelInterface`] holds the root-level Inports and Outports of a model
+//! with their declared data types and dimensions. [`check_compat`] compares
+//! two versions and classifies every change as breaking or not, so a release
+//! gate can reject changes that break models referencing this one.
+//!
+//! Ports are matched by name within their direction; a renamed port shows up
+//! as a removal and an addition.
+
+use crate::model::System;
+use crate::stimulus::parse_dimensions;
+use serde::Serialize;
+
+/// Direction of a root-level port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PortDirection {
+    Input,
+    Output,
+}
+
+impl PortDirection {
+    fn block_type(self) -> &'static str {
+        match self {
+            PortDirection::Input => "Inport",
+            PortDirection::Output => "Outport",
+        }
+    }
+}
+
+/// A root-level Inport or Outport.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InterfacePort {
+    pub name: String,
+    /// 1-based port number.
+    pub port: u32,
+    /// Declared `OutDataTypeStr`; `None` when inherited.
+    pub data_type: Option<String>,
+    /// Declared `PortDimensions`; `None` when inherited (`-1`).
+    pub dimensions: Option<Vec<u32>>,
+}
+
+/// The root-level ports of a model, ordered by port number.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ModelInterface {
+    pub inputs: Vec<InterfacePort>,
+    pub outputs: Vec<InterfacePort>,
+}
+
+impl ModelInterface {
+    /// Read the Inport and Outport blocks directly inside `system`.
+    pub fn from_system(system: &System) -> Self {
+        ModelInterface {
+            inputs: ports(system, PortDirection::Input),
+            outputs: ports(system, PortDirection::Output),
+        }
+    }
+
+    pub fn ports(&self, direction: PortDirection) -> &[InterfacePort] {
+        match direction {
+            PortDirection::Input => &self.inputs,
+            PortDirection::Output => &self.outputs,
+        }
+    }
+}
+
+fn ports(system: &System, direction: PortDirection) -> Vec<InterfacePort> {
+    let mut ports: Vec<InterfacePort> = system
+        .blocks
+        .iter()
+        .filter(|b| b.block_type == direction.block_type())
+        .map(|b| {
+            let prop = |name: &str| b.properties.get(name).map(|v| v.trim());
+            InterfacePort {
+                name: b.name.clone(),
+                port: prop("Port").and_then(|p| p.parse().ok()).unwrap_or(1),
+                data_type: prop("OutDataTypeStr")
+                    .filter(|t| !t.is_empty() && !t.starts_with("Inherit"))
+                    .map(str::to_string),
+                dimensions: prop("PortDimensions").and_then(parse_dimensions),
+            }
+        })
+        .collect();
+    ports.sort_by_key(|p| p.port);
+    ports
+}
+
+/// How a port changed between two interface versions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum ChangeKind {
+    /// A new port. Outputs may be left unconnected by callers and are
+    /// optional; new inputs must be connected.
+    Added {
+        port: u32,
+        optional: bool,
+    },
+    Removed {
+        port: u32,
+    },
+    /// The port number changed, so positional connections move.
+    Renumbered {
+        old: u32,
+        new: u32,
+    },
+    TypeChange {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    WidthChange {
+        old: Option<Vec<u32>>,
+        new: Option<Vec<u32>>,
+    },
+}
+
+/// One classified interface change.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct InterfaceChange {
+    pub direction: PortDirection,
+    /// Port name.
+    pub name: String,
+    #[serde(flatten)]
+    pub change: ChangeKind,
+    pub breaking: bool,
+}
+
+/// Result of [`check_compat`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Compatibility {
+    pub changes: Vec<InterfaceChange>,
+}
+
+impl Compatibility {
+    pub fn is_breaking(&self) -> bool {
+        self.changes.iter().any(|c| c.breaking)
+    }
+
+    pub fn breaking(&self) -> impl Iterator<Item = &InterfaceChange> {
+        self.changes.iter().filter(|c| c.breaking)
+    }
+}
+
+/// Classify the changes from `old` to `new`, inputs before outputs.
+///
+/// Removed ports, required new ports and renumbering are breaking. Type and
+/// dimension changes are breaking too, except when an input goes from a
+/// declared value to inherited, which accepts everything it accepted before.
+pub fn check_compat(old: &ModelInterface, new: &ModelInterface) -> Compatibility {
+    let mut changes = Vec::new();
+    for direction in [PortDirection::Input, PortDirection::Output] {
+        let (old_ports, new_ports) = (old.ports(direction), new.ports(direction));
+        let mut push = |name: &str, change: ChangeKind, breaking: bool| {
+            changes.push(InterfaceChange {
+                direction,
+                name: name.to_string(),
+                change,
+                breaking,
+            });
+        };
+        for o in old_ports {
+            let Some(n) = new_ports.iter().find(|n| n.name == o.name) else {
+                push(&o.name, ChangeKind::Removed { port: o.port }, true);
+                continue;
+            };
+            if o.port != n.port {
+                let change = ChangeKind::Renumbered {
+                    old: o.port,
+                    new: n.port,
+                };
+                push(&o.name, change, true);
+            }
+            // Inheriting on an input relaxes the constraint.
+            let relaxes = |new_inherited: bool| direction == PortDirection::Input && new_inherited;
+            if o.data_type != n.data_type {
+                let breaking = !relaxes(n.data_type.is_none());
+                let change = ChangeKind::TypeChange {
+                    old: o.data_type.clone(),
+                    new: n.data_type.clone(),
+                };
+                push(&o.name, change, breaking);
+            }
+            if o.dimensions != n.dimensions {
+                let breaking = !relaxes(n.dimensions.is_none());
+                let change = ChangeKind::WidthChange {
+                    old: o.dimensions.clone(),
+                    new: n.dimensions.clone(),
+                };
+                push(&o.name, change, breaking);
+            }
+        }
+        for n in new_ports {
+            if !old_ports.iter().any(|o| o.name == n.name) {
+                let optional = direction == PortDirection::Output;
+                let change = ChangeKind::Added {
+                    port: n.port,
+                    optional,
+                };
+                push(&n.name, change, !optional);
+            }
+        }
+    }
+    Compatibility { changes }
+}
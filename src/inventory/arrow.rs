@@ -0,0 +1,122 @@
+//! Arrow record batches and Parquet files of an [`Inventory`].
+//!
+//! Each table becomes one batch with one column per row field, named as the
+//! field. [`write_parquet`] writes them as `blocks.parquet`,
+//! `parameters.parquet` and `connections.parquet`, which SQL engines such as
+//! DuckDB or Spark can read directly, e.g. as a glob over many exports.
+
+use super::Inventory;
+use anyhow::{Context, Result};
+use arrow_array::{ArrayRef, RecordBatch, StringArray, UInt32Array};
+use camino::{Utf8Path, Utf8PathBuf};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use std::sync::Arc;
+
+fn strings<'a>(values: impl Iterator<Item = &'a str>) -> ArrayRef {
+    Arc::new(StringArray::from_iter_values(values))
+}
+
+fn optional_strings<'a>(values: impl Iterator<Item = Option<&'a str>>) -> ArrayRef {
+    Arc::new(values.collect::<StringArray>())
+}
+
+fn numbers(values: impl Iterator<Item = u32>) -> ArrayRef {
+    Arc::new(UInt32Array::from_iter_values(values))
+}
+
+/// One row per block.
+pub fn blocks_batch(inventory: &Inventory) -> Result<RecordBatch> {
+    let rows = &inventory.blocks;
+    Ok(RecordBatch::try_from_iter([
+        ("model", strings(rows.iter().map(|r| r.model.as_str()))),
+        ("path", strings(rows.iter().map(|r| r.path.as_str()))),
+        ("system", strings(rows.iter().map(|r| r.system.as_str()))),
+        ("name", strings(rows.iter().map(|r| r.name.as_str()))),
+        (
+            "sid",
+            optional_strings(rows.iter().map(|r| r.sid.as_deref())),
+        ),
+        (
+            "block_type",
+            strings(rows.iter().map(|r| r.block_type.as_str())),
+        ),
+        (
+            "library_source",
+            optional_strings(rows.iter().map(|r| r.library_source.as_deref())),
+        ),
+        (
+            "library_block",
+            optional_strings(rows.iter().map(|r| r.library_block.as_deref())),
+        ),
+        ("depth", numbers(rows.iter().map(|r| r.depth))),
+    ])?)
+}
+
+/// One row per block parameter.
+pub fn parameters_batch(inventory: &Inventory) -> Result<RecordBatch> {
+    let rows = &inventory.parameters;
+    Ok(RecordBatch::try_from_iter([
+        ("model", strings(rows.iter().map(|r| r.model.as_str()))),
+        (
+            "block_path",
+            strings(rows.iter().map(|r| r.block_path.as_str())),
+        ),
+        ("name", strings(rows.iter().map(|r| r.name.as_str()))),
+        ("value", strings(rows.iter().map(|r| r.value.as_str()))),
+    ])?)
+}
+
+/// One row per line source/destination pair.
+pub fn connections_batch(inventory: &Inventory) -> Result<RecordBatch> {
+    let rows = &inventory.connections;
+    Ok(RecordBatch::try_from_iter([
+        ("model", strings(rows.iter().map(|r| r.model.as_str()))),
+        ("system", strings(rows.iter().map(|r| r.system.as_str()))),
+        (
+            "line_name",
+            optional_strings(rows.iter().map(|r| r.line_name.as_deref())),
+        ),
+        ("src_sid", strings(rows.iter().map(|r| r.src_sid.as_str()))),
+        (
+            "src_port_type",
+            strings(rows.iter().map(|r| r.src_port_type.as_str())),
+        ),
+        ("src_port", numbers(rows.iter().map(|r| r.src_port))),
+        ("dst_sid", strings(rows.iter().map(|r| r.dst_sid.as_str()))),
+        (
+            "dst_port_type",
+            strings(rows.iter().map(|r| r.dst_port_type.as_str())),
+        ),
+        ("dst_port", numbers(rows.iter().map(|r| r.dst_port))),
+    ])?)
+}
+
+/// The three tables by name: `blocks`, `parameters` and `connections`.
+pub fn record_batches(inventory: &Inventory) -> Result<Vec<(&'static str, RecordBatch)>> {
+    Ok(vec![
+        ("blocks", blocks_batch(inventory)?),
+        ("parameters", parameters_batch(inventory)?),
+        ("connections", connections_batch(inventory)?),
+    ])
+}
+
+/// Write `<table>.parquet` for each of the [`record_batches`] into `dir`,
+/// creating it if needed. Returns the written files.
+pub fn write_parquet(inventory: &Inventory, dir: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Create {}", dir))?;
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+    let mut written = Vec::new();
+    for (table, batch) in record_batches(inventory)? {
+        let path = dir.join(format!("{table}.parquet"));
+        let file = std::fs::File::create(&path).with_context(|| format!("Create {}", path))?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(props.clone()))?;
+        writer.write(&batch)?;
+        writer.close()?;
+        written.push(path);
+    }
+    Ok(written)
+}
@@ -0,0 +1,191 @@
+//! Flat tables of a model's blocks, parameters and connections.
+//!
+//! [`Inventory::from_system`] flattens the block tree into rows that carry
+//! the model name and block paths, so inventories of many models can be
+//! concatenated and queried together. With the `arrow` feature, [`arrow`]
+//! turns them into Arrow record batches and Parquet files.
+//!
+//! - [`BlockRow`]: one row per block, including blocks inside subsystems.
+//! - [`ParameterRow`]: one row per `<P>` parameter of a block.
+//! - [`ConnectionRow`]: one row per source/destination pair of a line; a
+//!   branched line gives one row for every destination.
+//! - [`PortRow`]: one row per `<Port>` element of a block.
+//! - [`ChartRow`]: one row per Stateflow chart or MATLAB Function.
+//!
+//! With the `sqlite` feature, [`sqlite`] stores them in normalized tables.
+
+#[cfg(feature = "arrow")]
+pub mod arrow;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+
+use crate::block_path::BlockPath;
+use crate::model::{Branch, EndpointRef, System};
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Inventory {
+    pub blocks: Vec<BlockRow>,
+    pub parameters: Vec<ParameterRow>,
+    pub connections: Vec<ConnectionRow>,
+    pub ports: Vec<PortRow>,
+    pub charts: Vec<ChartRow>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockRow {
+    pub model: String,
+    /// [`BlockPath`] string of the block.
+    pub path: String,
+    /// Path of the system containing the block, empty for the root.
+    pub system: String,
+    pub name: String,
+    pub sid: Option<String>,
+    pub block_type: String,
+    /// Library of a linked block.
+    pub library_source: Option<String>,
+    /// Library block a linked block refers to, e.g. `simulink/Math
+    /// Operations/Gain`, also before libraries are resolved.
+    pub library_block: Option<String>,
+    /// Subsystem nesting, 0 for blocks of the root system.
+    pub depth: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterRow {
+    pub model: String,
+    pub block_path: String,
+    pub name: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionRow {
+    pub model: String,
+    /// Path of the system containing the line, empty for the root.
+    pub system: String,
+    pub line_name: Option<String>,
+    pub src_sid: String,
+    pub src_port_type: String,
+    pub src_port: u32,
+    pub dst_sid: String,
+    pub dst_port_type: String,
+    pub dst_port: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortRow {
+    pub model: String,
+    pub block_path: String,
+    pub port_type: String,
+    pub index: Option<u32>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartRow {
+    pub model: String,
+    /// Path of the subsystem block holding the chart, empty for the root.
+    pub block_path: String,
+    pub chart_id: Option<u32>,
+    pub name: Option<String>,
+    pub script: Option<String>,
+    pub inputs: u32,
+    pub outputs: u32,
+}
+
+impl Inventory {
+    /// Rows of `system` and its loaded subsystems, labelled with `model`.
+    pub fn from_system(model: &str, system: &System) -> Self {
+        let mut inventory = Inventory::default();
+        inventory.add_system(model, system, &mut BlockPath::root());
+        inventory
+    }
+
+    /// Append the rows of `other`, e.g. to collect several models.
+    pub fn extend(&mut self, other: Inventory) {
+        self.blocks.extend(other.blocks);
+        self.parameters.extend(other.parameters);
+        self.connections.extend(other.connections);
+        self.ports.extend(other.ports);
+        self.charts.extend(other.charts);
+    }
+
+    fn add_system(&mut self, model: &str, system: &System, path: &mut BlockPath) {
+        let system_path = path.to_string();
+        if let Some(chart) = &system.chart {
+            self.charts.push(ChartRow {
+                model: model.to_string(),
+                block_path: system_path.clone(),
+                chart_id: chart.id,
+                name: chart.name.clone(),
+                script: chart.script.clone(),
+                inputs: chart.inputs.len() as u32,
+                outputs: chart.outputs.len() as u32,
+            });
+        }
+        for block in &system.blocks {
+            let block_path = path.child(&block.name).to_string();
+            self.blocks.push(BlockRow {
+                model: model.to_string(),
+                path: block_path.clone(),
+                system: system_path.clone(),
+                name: block.name.clone(),
+                sid: block.sid.clone(),
+                block_type: block.block_type.clone(),
+                library_source: block.library_source.clone(),
+                library_block: block
+                    .library_block_path
+                    .clone()
+                    .or_else(|| block.properties.get("SourceBlock").cloned()),
+                depth: path.segments().len() as u32,
+            });
+            self.parameters
+                .extend(block.properties.iter().map(|(name, value)| ParameterRow {
+                    model: model.to_string(),
+                    block_path: block_path.clone(),
+                    name: name.clone(),
+                    value: value.clone(),
+                }));
+            self.ports.extend(block.ports.iter().map(|port| PortRow {
+                model: model.to_string(),
+                block_path: block_path.clone(),
+                port_type: port.port_type.clone(),
+                index: port.index,
+            }));
+        }
+        for line in &system.lines {
+            let Some(src) = &line.src else { continue };
+            let mut dsts = Vec::new();
+            collect_destinations(line.dst.as_ref(), &line.branches, &mut dsts);
+            self.connections
+                .extend(dsts.into_iter().map(|dst| ConnectionRow {
+                    model: model.to_string(),
+                    system: system_path.clone(),
+                    line_name: line.name.clone(),
+                    src_sid: src.sid.clone(),
+                    src_port_type: src.port_type.to_string(),
+                    src_port: src.port_index,
+                    dst_sid: dst.sid.clone(),
+                    dst_port_type: dst.port_type.to_string(),
+                    dst_port: dst.port_index,
+                }));
+        }
+        for block in &system.blocks {
+            if let Some(sub) = block.subsystem.as_deref() {
+                path.push(&block.name);
+                self.add_system(model, sub, path);
+                path.pop();
+            }
+        }
+    }
+}
+
+fn collect_destinations<'a>(
+    dst: Option<&'a EndpointRef>,
+    branches: &'a [Branch],
+    out: &mut Vec<&'a EndpointRef>,
+) {
+    out.extend(dst);
+    for branch in branches {
+        collect_destinations(branch.dst.as_ref(), &branch.branches, out);
+    }
+}
@@ -0,0 +1,232 @@
+//! SQLite database of an [`Inventory`].
+//!
+//! [`write`] stores the rows in normalized tables, indexed for lookups by
+//! block type, SID, parameter and library block:
+//!
+//! | table       | columns |
+//! |-------------|---------|
+//! | `blocks`    | `id`, `model`, `path`, `system`, `name`, `sid`, `block_type`, `library_source`, `library_block`, `depth` |
+//! | `params`    | `block_id`, `name`, `value` |
+//! | `ports`     | `block_id`, `port_type`, `port_index` |
+//! | `lines`     | `id`, `model`, `system`, `name`, `src_sid`, `src_port_type`, `src_port`, `dst_sid`, `dst_port_type`, `dst_port` |
+//! | `charts`    | `id`, `model`, `block_id`, `chart_id`, `name`, `script`, `inputs`, `outputs` |
+//! | `hierarchy` | `ancestor_id`, `descendant_id`, `distance` |
+//!
+//! `hierarchy` holds every subsystem/descendant pair, so the content of a
+//! subsystem at any depth is one indexed lookup. A database can hold many
+//! models; writing a model again replaces its rows.
+
+use super::Inventory;
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use rusqlite::{Connection, params};
+use std::collections::{BTreeSet, HashMap};
+
+pub use rusqlite;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS blocks (
+    id INTEGER PRIMARY KEY,
+    model TEXT NOT NULL,
+    path TEXT NOT NULL,
+    system TEXT NOT NULL,
+    name TEXT NOT NULL,
+    sid TEXT,
+    block_type TEXT NOT NULL,
+    library_source TEXT,
+    library_block TEXT,
+    depth INTEGER NOT NULL,
+    UNIQUE (model, path)
+);
+CREATE TABLE IF NOT EXISTS params (
+    block_id INTEGER NOT NULL REFERENCES blocks(id),
+    name TEXT NOT NULL,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS ports (
+    block_id INTEGER NOT NULL REFERENCES blocks(id),
+    port_type TEXT NOT NULL,
+    port_index INTEGER
+);
+CREATE TABLE IF NOT EXISTS lines (
+    id INTEGER PRIMARY KEY,
+    model TEXT NOT NULL,
+    system TEXT NOT NULL,
+    name TEXT,
+    src_sid TEXT NOT NULL,
+    src_port_type TEXT NOT NULL,
+    src_port INTEGER NOT NULL,
+    dst_sid TEXT NOT NULL,
+    dst_port_type TEXT NOT NULL,
+    dst_port INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS charts (
+    id INTEGER PRIMARY KEY,
+    model TEXT NOT NULL,
+    block_id INTEGER REFERENCES blocks(id),
+    chart_id INTEGER,
+    name TEXT,
+    script TEXT,
+    inputs INTEGER NOT NULL,
+    outputs INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS hierarchy (
+    ancestor_id INTEGER NOT NULL REFERENCES blocks(id),
+    descendant_id INTEGER NOT NULL REFERENCES blocks(id),
+    distance INTEGER NOT NULL,
+    PRIMARY KEY (ancestor_id, descendant_id)
+);
+CREATE INDEX IF NOT EXISTS blocks_type ON blocks (block_type);
+CREATE INDEX IF NOT EXISTS blocks_sid ON blocks (model, sid);
+CREATE INDEX IF NOT EXISTS blocks_library_block ON blocks (library_block);
+CREATE INDEX IF NOT EXISTS params_block ON params (block_id);
+CREATE INDEX IF NOT EXISTS params_name ON params (name, value);
+CREATE INDEX IF NOT EXISTS ports_block ON ports (block_id);
+CREATE INDEX IF NOT EXISTS lines_src ON lines (model, src_sid);
+CREATE INDEX IF NOT EXISTS lines_dst ON lines (model, dst_sid);
+CREATE INDEX IF NOT EXISTS charts_block ON charts (block_id);
+CREATE INDEX IF NOT EXISTS hierarchy_descendant ON hierarchy (descendant_id);
+";
+
+/// Statements removing the rows of model `?1` before it is written again.
+const DELETE_MODEL: &str = "
+DELETE FROM hierarchy WHERE descendant_id IN (SELECT id FROM blocks WHERE model = ?1);
+DELETE FROM params WHERE block_id IN (SELECT id FROM blocks WHERE model = ?1);
+DELETE FROM ports WHERE block_id IN (SELECT id FROM blocks WHERE model = ?1);
+DELETE FROM charts WHERE model = ?1;
+DELETE FROM lines WHERE model = ?1;
+DELETE FROM blocks WHERE model = ?1;
+";
+
+/// Create the tables in `conn` if needed and store `inventory`, replacing
+/// earlier rows of the same models. Runs in one transaction.
+pub fn write(conn: &mut Connection, inventory: &Inventory) -> Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute_batch(SCHEMA)?;
+    let models: BTreeSet<&str> = inventory
+        .blocks
+        .iter()
+        .map(|r| r.model.as_str())
+        .chain(inventory.connections.iter().map(|r| r.model.as_str()))
+        .chain(inventory.charts.iter().map(|r| r.model.as_str()))
+        .collect();
+    for model in models {
+        for statement in DELETE_MODEL.split(';').filter(|s| !s.trim().is_empty()) {
+            tx.execute(statement, [model])?;
+        }
+    }
+
+    let mut ids: HashMap<(&str, &str), i64> = HashMap::new();
+    {
+        let mut insert = tx.prepare(
+            "INSERT INTO blocks (model, path, system, name, sid, block_type, library_source, library_block, depth)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        for row in &inventory.blocks {
+            insert.execute(params![
+                row.model,
+                row.path,
+                row.system,
+                row.name,
+                row.sid,
+                row.block_type,
+                row.library_source,
+                row.library_block,
+                row.depth,
+            ])?;
+            ids.insert(
+                (row.model.as_str(), row.path.as_str()),
+                tx.last_insert_rowid(),
+            );
+        }
+
+        // Walk up from each block through the subsystems containing it.
+        let systems: HashMap<(&str, &str), &str> = inventory
+            .blocks
+            .iter()
+            .map(|b| ((b.model.as_str(), b.path.as_str()), b.system.as_str()))
+            .collect();
+        let mut insert = tx.prepare(
+            "INSERT INTO hierarchy (ancestor_id, descendant_id, distance) VALUES (?1, ?2, ?3)",
+        )?;
+        for row in &inventory.blocks {
+            let model = row.model.as_str();
+            let id = ids[&(model, row.path.as_str())];
+            let mut system = row.system.as_str();
+            let mut distance = 1;
+            while let Some(&ancestor) = ids.get(&(model, system)) {
+                insert.execute(params![ancestor, id, distance])?;
+                system = systems[&(model, system)];
+                distance += 1;
+            }
+        }
+
+        let mut insert =
+            tx.prepare("INSERT INTO params (block_id, name, value) VALUES (?1, ?2, ?3)")?;
+        for row in &inventory.parameters {
+            if let Some(id) = ids.get(&(row.model.as_str(), row.block_path.as_str())) {
+                insert.execute(params![id, row.name, row.value])?;
+            }
+        }
+
+        let mut insert =
+            tx.prepare("INSERT INTO ports (block_id, port_type, port_index) VALUES (?1, ?2, ?3)")?;
+        for row in &inventory.ports {
+            if let Some(id) = ids.get(&(row.model.as_str(), row.block_path.as_str())) {
+                insert.execute(params![id, row.port_type, row.index])?;
+            }
+        }
+
+        let mut insert = tx.prepare(
+            "INSERT INTO lines (model, system, name, src_sid, src_port_type, src_port, dst_sid, dst_port_type, dst_port)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        )?;
+        for row in &inventory.connections {
+            insert.execute(params![
+                row.model,
+                row.system,
+                row.line_name,
+                row.src_sid,
+                row.src_port_type,
+                row.src_port,
+                row.dst_sid,
+                row.dst_port_type,
+                row.dst_port,
+            ])?;
+        }
+
+        let mut insert = tx.prepare(
+            "INSERT INTO charts (model, block_id, chart_id, name, script, inputs, outputs)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        )?;
+        for row in &inventory.charts {
+            let block_id = ids.get(&(row.model.as_str(), row.block_path.as_str()));
+            insert.execute(params![
+                row.model,
+                block_id,
+                row.chart_id,
+                row.name,
+                row.script,
+                row.inputs,
+                row.outputs,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// [`write`] to the database file at `path`, creating it if needed.
+pub fn write_file(inventory: &Inventory, path: &Utf8Path) -> Result<()> {
+    let mut conn = Connection::open(path).with_context(|| format!("Open {}", path))?;
+    write(&mut conn, inventory)
+}
+
+/// `(model, path)` of every block linked to `library_block`, e.g.
+/// `CtrlLib/PI`, ordered by model and path.
+pub fn where_used(conn: &Connection, library_block: &str) -> Result<Vec<(String, String)>> {
+    let mut query = conn
+        .prepare("SELECT model, path FROM blocks WHERE library_block = ?1 ORDER BY model, path")?;
+    let rows = query.query_map([library_block], |row| Ok((row.get(0)?, row.get(1)?)))?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
@@ -25,6 +25,20 @@
 //! 2. Provide a measurer that returns the text size for a string, font, and color (the color usually
 //!    doesn't affect size but passes through if you need caching by key).
 //! 3. Call `place_label` with the polyline and text. Use the returned rectangle to render.
+//!
+//! `place_label` is greedy: each label only avoids the ones placed before it.
+//! For dense diagrams, `place_all` places every label of a subsystem at once.
+//! It enumerates candidate positions per label, starts from the greedy choice
+//! and then repeatedly moves each label to its cheapest candidate given all
+//! others (iterated conditional modes) until no label moves. The cost counts
+//! label overlaps, overlaps with obstacles, crossings with any line and the
+//! segment spill. Like `place_label`, it is deterministic.
+//! The viewer uses it instead of `place_label` when its "Joint labels"
+//! option (`SubsystemApp::joint_label_placement`) is on.
+//!
+//! [`PlacementCache`] lets the viewer keep placements across frames: results
+//! are stored in model coordinates and keyed by a [`zoom_bucket`] and a
+//! fingerprint of the geometry.
 
 use std::cmp::Ordering;
 
@@ -138,15 +152,7 @@ pub fn place_label(
     for (_i, a, b, _len) in segs.into_iter() {
         let horizontal = (a.y - b.y).abs() <= (a.x - b.x).abs();
         // Measure oriented text
-        let oriented_text = if horizontal {
-            text.to_string()
-        } else {
-            text.chars()
-                .map(|c| c.to_string())
-                .collect::<Vec<String>>()
-                .join("\n")
-        };
-        let (w, h) = measurer.measure(&oriented_text);
+        let (w, h) = measurer.measure(&oriented_text(text, horizontal));
         let seg_min_x = a.x.min(b.x);
         let seg_max_x = a.x.max(b.x);
         let seg_min_y = a.y.min(b.y);
@@ -286,6 +292,313 @@ pub fn place_label(
     })
 }
 
+/// One label for [`place_all`].
+#[derive(Debug, Clone, Copy)]
+pub struct LabelRequest<'a> {
+    pub polyline: &'a [Vec2f],
+    pub text: &'a str,
+}
+
+/// Cost of a label crossing one line segment.
+const CROSSING_PENALTY: f32 = 200.0;
+/// Upper bound on improvement passes in [`place_all`].
+const MAX_PASSES: usize = 16;
+/// Slide positions per side of the segment center.
+const MAX_SLIDES: i32 = 4;
+/// Perpendicular offset multiples tried on each side of the segment.
+const MAX_PERP_STEPS: i32 = 2;
+
+#[derive(Debug, Clone, Copy)]
+struct Candidate {
+    rect: RectF,
+    horizontal: bool,
+    /// Cost that doesn't depend on the other labels (spill, crossings,
+    /// obstacles and the preference for central, close positions).
+    base_cost: f32,
+}
+
+/// Place all labels of a subsystem jointly, minimizing overlaps between
+/// labels, overlaps with `obstacles` (e.g. blocks) and crossings with any of
+/// the requested polylines.
+///
+/// Returns one result per request, in order; `None` for polylines with fewer
+/// than two points. `intersected` reports whether the final expanded rectangle
+/// still overlaps another label or an obstacle.
+pub fn place_all(
+    requests: &[LabelRequest],
+    measurer: &dyn Measurer,
+    cfg: Config,
+    obstacles: &[RectF],
+) -> Vec<Option<PlacementResult>> {
+    let all_segments: Vec<(Vec2f, Vec2f)> = requests
+        .iter()
+        .flat_map(|r| r.polyline.windows(2).map(|w| (w[0], w[1])))
+        .collect();
+    let candidates: Vec<Vec<Candidate>> = requests
+        .iter()
+        .map(|r| label_candidates(r, measurer, cfg, obstacles, &all_segments))
+        .collect();
+
+    // Greedy start: cheapest candidate given the labels before it.
+    let mut chosen: Vec<Option<usize>> = vec![None; requests.len()];
+    for i in 0..requests.len() {
+        chosen[i] = best_candidate(i, &candidates, &chosen, cfg);
+    }
+
+    for _ in 0..MAX_PASSES {
+        let mut moved = false;
+        for i in 0..requests.len() {
+            let best = best_candidate(i, &candidates, &chosen, cfg);
+            if best != chosen[i] {
+                chosen[i] = best;
+                moved = true;
+            }
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    chosen
+        .iter()
+        .enumerate()
+        .map(|(i, c)| {
+            let cand = candidates[i][(*c)?];
+            let er = expanded_rect(cand.rect, cfg.expand_factor);
+            let hits_label = chosen.iter().enumerate().any(|(j, cj)| {
+                j != i
+                    && cj.is_some_and(|cj| {
+                        er.intersects(expanded_rect(candidates[j][cj].rect, cfg.expand_factor))
+                    })
+            });
+            let hits_obstacle = obstacles
+                .iter()
+                .any(|o| er.intersects(expanded_rect(*o, cfg.expand_factor)));
+            Some(PlacementResult {
+                rect: cand.rect,
+                horizontal: cand.horizontal,
+                intersected: hits_label || hits_obstacle,
+            })
+        })
+        .collect()
+}
+
+/// Index of the cheapest candidate of label `i` given the current choice of
+/// every other label. Ties keep the earlier (more preferred) candidate.
+fn best_candidate(
+    i: usize,
+    candidates: &[Vec<Candidate>],
+    chosen: &[Option<usize>],
+    cfg: Config,
+) -> Option<usize> {
+    let others: Vec<RectF> = chosen
+        .iter()
+        .enumerate()
+        .filter(|(j, _)| *j != i)
+        .filter_map(|(j, c)| c.map(|c| expanded_rect(candidates[j][c].rect, cfg.expand_factor)))
+        .collect();
+    let mut best: Option<(usize, f32)> = None;
+    for (k, cand) in candidates[i].iter().enumerate() {
+        let er = expanded_rect(cand.rect, cfg.expand_factor);
+        let cost = cand.base_cost + others.iter().map(|o| overlap_area(er, *o)).sum::<f32>();
+        if best.is_none_or(|(_, b)| cost < b) {
+            best = Some((k, cost));
+        }
+    }
+    best.map(|(k, _)| k)
+}
+
+/// Enumerate candidate rectangles for one label along every segment of its
+/// polyline, both sides and a few perpendicular distances.
+fn label_candidates(
+    req: &LabelRequest,
+    measurer: &dyn Measurer,
+    cfg: Config,
+    obstacles: &[RectF],
+    all_segments: &[(Vec2f, Vec2f)],
+) -> Vec<Candidate> {
+    let mut out = Vec::new();
+    if req.polyline.len() < 2 {
+        return out;
+    }
+    let longest = req
+        .polyline
+        .windows(2)
+        .map(|w| seg_len(w[0], w[1]))
+        .fold(0.0f32, f32::max);
+    for w in req.polyline.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let len = seg_len(a, b);
+        let horizontal = (a.y - b.y).abs() <= (a.x - b.x).abs();
+        let (tw, th) = measurer.measure(&oriented_text(req.text, horizontal));
+        // Extent along the segment and the label's size in that direction.
+        let (lo, hi, along) = if horizontal {
+            (a.x.min(b.x), a.x.max(b.x), tw)
+        } else {
+            (a.y.min(b.y), a.y.max(b.y), th)
+        };
+        let spill = (along - (hi - lo)).max(0.0);
+        let (min_t, max_t) = if hi - lo >= along {
+            (lo + along * 0.5, hi - along * 0.5)
+        } else {
+            let mid = (lo + hi) * 0.5;
+            (mid, mid)
+        };
+        let center_t = ((lo + hi) * 0.5).clamp(min_t, max_t);
+        let step = (along.max(if horizontal { 40.0 } else { 20.0 }) * cfg.step_fraction).max(1.0);
+        let (cx, cy) = ((a.x + b.x) * 0.5, (a.y + b.y) * 0.5);
+
+        for slide in 0..=MAX_SLIDES {
+            let deltas: &[f32] = if slide == 0 { &[0.0] } else { &[1.0, -1.0] };
+            for sign in deltas {
+                let t = (center_t + sign * slide as f32 * step).clamp(min_t, max_t);
+                if slide > 0 && (t - center_t).abs() < 1e-3 {
+                    continue;
+                }
+                for k in 0..=MAX_PERP_STEPS {
+                    // Preferred side first: above horizontal, right of vertical lines.
+                    for side in [1.0f32, -1.0] {
+                        let off = cfg.perp_offset * (1 + k) as f32;
+                        let (ccx, ccy) = if horizontal {
+                            (t, cy - side * (off + th * 0.5))
+                        } else {
+                            (cx + side * (off + tw * 0.5), t)
+                        };
+                        let rect = RectF::from_min_max(
+                            Vec2f::new(ccx - tw * 0.5, ccy - th * 0.5),
+                            Vec2f::new(ccx + tw * 0.5, ccy + th * 0.5),
+                        );
+                        let er = expanded_rect(rect, cfg.expand_factor);
+                        let obstacle_cost: f32 = obstacles
+                            .iter()
+                            .map(|o| overlap_area(er, expanded_rect(*o, cfg.expand_factor)))
+                            .sum();
+                        let crossings = all_segments
+                            .iter()
+                            .filter(|(p, q)| segment_intersects_rect(*p, *q, rect))
+                            .count();
+                        let preference = 0.5 * (t - center_t).abs()
+                            + 2.0 * k as f32
+                            + if side < 0.0 { 1.0 } else { 0.0 }
+                            + 0.05 * (longest - len);
+                        out.push(Candidate {
+                            rect,
+                            horizontal,
+                            base_cost: spill * 100.0
+                                + obstacle_cost
+                                + crossings as f32 * CROSSING_PENALTY
+                                + preference,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+fn oriented_text(text: &str, horizontal: bool) -> String {
+    if horizontal {
+        text.to_string()
+    } else {
+        text.chars()
+            .map(|c| c.to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+}
+
+fn seg_len(a: Vec2f, b: Vec2f) -> f32 {
+    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
+}
+
+fn overlap_area(a: RectF, b: RectF) -> f32 {
+    let ix = (a.max.x.min(b.max.x) - a.min.x.max(b.min.x)).max(0.0);
+    let iy = (a.max.y.min(b.max.y) - a.min.y.max(b.min.y)).max(0.0);
+    ix * iy
+}
+
+/// Liang–Barsky test: does segment `a`–`b` pass through the interior of `r`?
+fn segment_intersects_rect(a: Vec2f, b: Vec2f, r: RectF) -> bool {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let mut t0 = 0.0f32;
+    let mut t1 = 1.0f32;
+    for (p, q) in [
+        (-dx, a.x - r.min.x),
+        (dx, r.max.x - a.x),
+        (-dy, a.y - r.min.y),
+        (dy, r.max.y - a.y),
+    ] {
+        if p == 0.0 {
+            if q <= 0.0 {
+                return false;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+    t0 < t1
+}
+
+/// Zoom buckets per doubling of the zoom factor.
+const ZOOM_BUCKETS_PER_DOUBLING: f32 = 4.0;
+
+/// Bucket of a zoom factor for [`PlacementCache`]. Zoom levels within about
+/// 19% of each other share a bucket, so smooth zooming doesn't re-run the
+/// placement on every frame.
+pub fn zoom_bucket(zoom: f32) -> i32 {
+    (zoom.max(1e-6).log2() * ZOOM_BUCKETS_PER_DOUBLING).floor() as i32
+}
+
+/// A placed label in layout (model) coordinates, so it survives panning.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedLabel {
+    pub line_index: usize,
+    /// Final text, possibly wrapped onto two lines.
+    pub text: String,
+    pub rect: RectF,
+    pub horizontal: bool,
+    /// Font size relative to the viewer's font scale.
+    pub font_size: f32,
+}
+
+/// Label placements of one view, reused while the zoom bucket and the
+/// geometry fingerprint stay the same.
+#[derive(Debug, Clone, Default)]
+pub struct PlacementCache {
+    key: Option<(i32, u64)>,
+    labels: Vec<CachedLabel>,
+}
+
+impl PlacementCache {
+    /// Cached labels for the given zoom bucket and geometry, if any.
+    pub fn get(&self, zoom_bucket: i32, geometry: u64) -> Option<&[CachedLabel]> {
+        (self.key == Some((zoom_bucket, geometry))).then_some(self.labels.as_slice())
+    }
+
+    /// Labels of the last stored placement, whatever its key.
+    pub fn labels(&self) -> &[CachedLabel] {
+        &self.labels
+    }
+
+    /// Replace the cached labels.
+    pub fn store(&mut self, zoom_bucket: i32, geometry: u64, labels: Vec<CachedLabel>) {
+        self.key = Some((zoom_bucket, geometry));
+        self.labels = labels;
+    }
+
+    /// Drop all cached labels.
+    pub fn clear(&mut self) {
+        self.key = None;
+        self.labels.clear();
+    }
+}
+
 // Unit tests were moved to integration tests in `tests/label_place.rs` to
 // exercise the public API from the outside and keep implementation details
 // private. See `tests/label_place.rs` for the test coverage and examples.
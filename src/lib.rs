@@ -1,4 +1,9 @@
+pub mod analysis;
+pub mod annotation_link;
 pub mod block;
+pub mod block_path;
+pub mod bus;
+pub mod cancel;
 /// Simulink System XML parser.
 ///
 /// This crate provides a `SimulinkParser` to load and parse Simulink XML system
@@ -6,9 +11,35 @@ pub mod block;
 ///
 /// The binary `rustylink` demonstrates usage and prints the parsed JSON.
 pub mod color;
+pub mod connection;
+pub mod cosmetic;
+pub mod diff;
+pub mod display_list;
+pub mod doctor;
+pub mod focus_nav;
+pub mod hash;
+pub mod interface;
+pub mod inventory;
 pub mod label_place;
+pub mod lint;
+pub mod logging_spec;
 pub mod model;
+pub mod ndjson;
+pub mod overlay;
 pub mod parser;
+pub mod patterns;
+pub mod port_info;
+pub mod project;
+pub mod property_bag;
+pub mod report;
+pub mod sample_time;
+pub mod selection;
+pub mod service;
+pub mod signal_kind;
+pub mod stable_json;
+pub mod stimulus;
+pub mod transform;
+pub mod watch;
 
 /// Definitions for built-in virtual libraries used by the parser and UI.
 pub mod builtin_libraries;
@@ -19,16 +50,32 @@ pub mod generator;
 // Optional mask evaluation feature
 pub mod mask_eval;
 
+/// Minimal MAT-file reader and model workspace variables.
+pub mod mat_file;
+pub mod workspace;
+
 // Optional GUI/egui functionality lives behind the `egui` feature flag.
 // This module provides an interactive viewer for Simulink subsystems and
 // is used by the example in examples/egui_viewer.rs.
 #[cfg(feature = "egui")]
 pub mod egui_app;
 
+// Visual regression test support (off-screen rendering, baseline images).
+#[cfg(feature = "testing")]
+pub mod testing;
+
 // Block type registry and configuration (egui feature)
 #[cfg(feature = "egui")]
 pub mod block_types;
 
+// REST server (server feature)
+#[cfg(feature = "server")]
+pub mod server;
+
+// Terminal model browser (tui feature)
+#[cfg(feature = "tui")]
+pub mod tui;
+
 // Comprehensive model editor (egui feature)
 #[cfg(feature = "egui")]
 pub mod editor;
@@ -36,8 +83,8 @@ pub mod editor;
 // Re-export core API so downstream users can easily access/modify the registry
 #[cfg(feature = "egui")]
 pub use block_types::{
-    BlockTypeConfig, IconSpec, Rgb, get_block_type_config_map, register_user_library_block_types,
-    set_block_type_config, update_block_type_config,
+    BlockTypeConfig, IconSpec, Rgb, get_block_type_config_map, load_config, register_icon_dir,
+    register_user_library_block_types, set_block_type_config, update_block_type_config,
 };
 
 // Re-export user virtual library API for downstream registration
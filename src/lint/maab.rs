@@ -0,0 +1,208 @@
+//! A first batch of the MathWorks Advisory Board (MAAB/JMAAB) modeling
+//! guidelines, enabled with [`Ruleset::Maab`](super::Ruleset::Maab).
+//!
+//! | Rule      | Checks                                                     |
+//! |-----------|------------------------------------------------------------|
+//! | `jc_0201` | subsystem names use only `a-z A-Z 0-9 _`, no leading digit, no leading, trailing or double underscore |
+//! | `jc_0211` | the same for Inport and Outport names                      |
+//! | `db_0032` | signal lines crossing each other                           |
+//! | `db_0042` | port blocks ordered top to bottom by port number, Inports left of Outports |
+//! | `jm_0001` | continuous-time blocks in controller models                |
+//! | `hd_0001` | To File, To Workspace and Stop Simulation blocks           |
+//!
+//! Crossings are information, since the check cannot tell whether a
+//! different layout would avoid them; the other rules report warnings.
+
+use super::{Finding, Severity};
+use crate::block_path::BlockPath;
+use crate::generator::thumbnail::{block_rect, line_polylines};
+use crate::model::{Block, System};
+
+/// The rules of the guideline pack, by [`Finding::source`].
+pub const RULES: &[&str] = &[
+    "jc_0201", "jc_0211", "db_0032", "db_0042", "jm_0001", "hd_0001",
+];
+
+/// Continuous-time block types and the discrete blocks replacing them.
+const CONTINUOUS: &[(&str, &str)] = &[
+    ("Derivative", "Discrete Derivative"),
+    ("Integrator", "Discrete-Time Integrator"),
+    ("SecondOrderIntegrator", "two Discrete-Time Integrators"),
+    ("StateSpace", "Discrete State-Space"),
+    ("TransferFcn", "Discrete Transfer Fcn"),
+    ("ZeroPole", "Discrete Zero-Pole"),
+    ("TransportDelay", "Delay"),
+    ("VariableTransportDelay", "Variable Integer Delay"),
+];
+
+/// Sink block types that only make sense in simulation.
+const SIMULATION_SINKS: &[&str] = &["ToFile", "ToWorkspace", "Stop"];
+
+/// Run the guideline checks on `system` and its subsystems.
+pub fn check(system: &System) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    check_level(system, &mut BlockPath::root(), &mut findings);
+    findings
+}
+
+fn check_level(system: &System, path: &mut BlockPath, out: &mut Vec<Finding>) {
+    let mut finding = |block: &Block, source: &str, severity, message: String| {
+        out.push(Finding {
+            block: path.child(&block.name).to_string(),
+            severity,
+            source: source.to_string(),
+            message,
+        });
+    };
+    for block in &system.blocks {
+        let (rule, what) = match block.block_type.as_str() {
+            "SubSystem" => ("jc_0201", "Subsystem"),
+            "Inport" | "Outport" => ("jc_0211", block.block_type.as_str()),
+            _ => ("", ""),
+        };
+        if let (false, Some(problem)) = (rule.is_empty(), name_problem(&block.name)) {
+            let message = format!("{} name \"{}\" {}", what, block.name, problem);
+            finding(block, rule, Severity::Warning, message);
+        }
+        if let Some((_, replacement)) = CONTINUOUS.iter().find(|(t, _)| *t == block.block_type) {
+            let message = format!(
+                "{} is a continuous-time block; use {} in controller models",
+                block.block_type, replacement
+            );
+            finding(block, "jm_0001", Severity::Warning, message);
+        }
+        if SIMULATION_SINKS.contains(&block.block_type.as_str()) {
+            let message = format!(
+                "{} blocks are not allowed in controller models; log the signal instead",
+                block.block_type
+            );
+            finding(block, "hd_0001", Severity::Warning, message);
+        }
+    }
+    for (block, message) in port_order(system) {
+        finding(block, "db_0042", Severity::Warning, message);
+    }
+    for (block, message) in crossings(system) {
+        finding(block, "db_0032", Severity::Information, message);
+    }
+
+    for block in &system.blocks {
+        if let Some(sub) = block.subsystem.as_deref() {
+            path.push(&block.name);
+            check_level(sub, path, out);
+            path.pop();
+        }
+    }
+}
+
+/// What is wrong with a subsystem or port name under jc_0201 and jc_0211.
+fn name_problem(name: &str) -> Option<&'static str> {
+    if name.contains(['\n', '\r']) {
+        Some("contains a line break")
+    } else if name.contains(' ') {
+        Some("contains a space")
+    } else if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        Some("uses characters other than a-z, A-Z, 0-9 and _")
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        Some("starts with a digit")
+    } else if name.starts_with('_') || name.ends_with('_') {
+        Some("starts or ends with an underscore")
+    } else if name.contains("__") {
+        Some("contains consecutive underscores")
+    } else {
+        None
+    }
+}
+
+/// Port blocks placed above a port with a lower number, and Inports right
+/// of an Outport.
+fn port_order(system: &System) -> Vec<(&Block, String)> {
+    let ports = |block_type: &str| {
+        let mut ports: Vec<(u32, &Block, [f32; 4])> = system
+            .blocks
+            .iter()
+            .filter(|b| b.block_type == block_type)
+            .filter_map(|b| {
+                let port = b
+                    .properties
+                    .get("Port")
+                    .and_then(|p| p.trim().parse().ok())
+                    .unwrap_or(1);
+                Some((port, b, block_rect(b)?))
+            })
+            .collect();
+        ports.sort_by_key(|(port, _, _)| *port);
+        ports
+    };
+    let inports = ports("Inport");
+    let outports = ports("Outport");
+    let mut out = Vec::new();
+    for ports in [&inports, &outports] {
+        for pair in ports.windows(2) {
+            let ((above, _, upper), (port, block, rect)) = (pair[0], pair[1]);
+            if rect[1] < upper[1] {
+                let message = format!(
+                    "{} {} sits above {} {}; order port blocks top to bottom by port number",
+                    block.block_type, port, block.block_type, above
+                );
+                out.push((block, message));
+            }
+        }
+    }
+    let leftmost = outports.iter().min_by(|a, b| a.2[0].total_cmp(&b.2[0]));
+    if let Some((_, outport, outport_rect)) = leftmost {
+        for (_, block, rect) in &inports {
+            if rect[0] > outport_rect[0] {
+                let message = format!(
+                    "Inport sits right of Outport \"{}\"; place Inports on the left",
+                    outport.name
+                );
+                out.push((*block, message));
+            }
+        }
+    }
+    out
+}
+
+/// Lines crossing an earlier line, reported at their source block.
+fn crossings(system: &System) -> Vec<(&Block, String)> {
+    let source = |i: usize| {
+        let sid = system.lines[i].src.as_ref()?.sid.as_str();
+        system.blocks.iter().find(|b| b.sid.as_deref() == Some(sid))
+    };
+    let segments: Vec<Vec<[(f32, f32); 2]>> = line_polylines(system)
+        .iter()
+        .map(|polylines| {
+            polylines
+                .iter()
+                .flat_map(|(_, pts)| pts.windows(2).map(|w| [w[0], w[1]]))
+                .collect()
+        })
+        .collect();
+    let mut out = Vec::new();
+    for (j, later) in segments.iter().enumerate() {
+        for (i, earlier) in segments[..j].iter().enumerate() {
+            let cross = later
+                .iter()
+                .any(|a| earlier.iter().any(|b| segments_cross(*a, *b)));
+            if let (true, Some(block), Some(other)) = (cross, source(j), source(i)) {
+                let message = format!(
+                    "the signal from \"{}\" crosses the signal from \"{}\"",
+                    block.name, other.name
+                );
+                out.push((block, message));
+            }
+        }
+    }
+    out
+}
+
+/// Whether two segments cross at a point inside both; touching ends and
+/// overlapping collinear segments do not count.
+fn segments_cross(a: [(f32, f32); 2], b: [(f32, f32); 2]) -> bool {
+    fn side(p: (f32, f32), q: (f32, f32), r: (f32, f32)) -> f32 {
+        (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+    }
+    side(a[0], a[1], b[0]) * side(a[0], a[1], b[1]) < 0.0
+        && side(b[0], b[1], a[0]) * side(b[0], b[1], a[1]) < 0.0
+}
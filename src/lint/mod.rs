@@ -0,0 +1,524 @@
+//! Model checks collected into one list of findings.
+//!
+//! [`lint`] runs the rate-transition check and the magic number search of
+//! [`crate::analysis`] and reports each result as a [`Finding`] at a block
+//! path. The JSON-RPC service publishes them as diagnostics and the editor
+//! shows them next to the blocks. A [`Gate`] decides whether a list of
+//! findings fails a CI run, and [`to_junit`] reports them as JUnit XML for
+//! the test views of CI servers.
+//!
+//! [`lint_with`] adds the rules of a [`LintConfig`], read from a JSON file
+//! such as
+//!
+//! ```json
+//! {
+//!   "naming": [
+//!     { "name": "inport-prefix", "applies_to": "inports", "pattern": "^u_",
+//!       "severity": "error", "exclude": ["Legacy"] },
+//!     { "name": "signal-prefix", "applies_to": "signals", "pattern": "^sig_",
+//!       "message": "Signal names start with sig_ (guideline 4.2)" }
+//!   ]
+//! }
+//! ```
+//!
+//! A naming rule checks the names of `blocks`, `subsystems`, `inports`,
+//! `outports` or named `signals` against a regular expression and reports
+//! mismatches under its own name, as warnings unless it sets a `severity`.
+//! Blocks at or below an `exclude` path are skipped; a signal counts as
+//! part of the block driving it.
+//!
+//! `"rulesets": ["maab"]` adds the guideline checks of [`maab`], and a
+//! `"blocks"` section the block type policy of [`policy`].
+//!
+//! A pattern rule reports every match of a [`PatternGraph`] in its text
+//! form at the first block of the match:
+//!
+//! ```json
+//! {
+//!   "patterns": [
+//!     { "name": "saturated-output", "pattern": "Gain -> Saturate -> Outport",
+//!       "message": "use a Saturation Dynamic block", "severity": "information" }
+//!   ]
+//! }
+//! ```
+
+use crate::analysis::{MagicNumberOptions, find_magic_numbers, rate_transition_issues};
+use crate::block_path::BlockPath;
+use crate::generator::system_xml::{xml_escape, xml_escape_attr};
+use crate::model::{Block, System};
+use crate::patterns::{self, PatternGraph};
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+pub mod maab;
+pub mod policy;
+
+use policy::BlockPolicy;
+
+/// The checks [`lint`] runs, by [`Finding::source`].
+pub const RULES: &[&str] = &["rate", "magic-number"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Information => "information",
+        }
+    }
+}
+
+/// One problem at one block.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Finding {
+    /// Block path from the root, e.g. `"Controller/Kp"`.
+    pub block: String,
+    pub severity: Severity,
+    /// The check that found it, e.g. `rate`, `magic-number` or the name of
+    /// a configured rule.
+    pub source: String,
+    pub message: String,
+}
+
+/// Rules added to the built-in checks, as described in the
+/// [module docs](self).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LintConfig {
+    pub rulesets: Vec<Ruleset>,
+    pub naming: Vec<NamingRule>,
+    pub blocks: BlockPolicy,
+    pub patterns: Vec<PatternRule>,
+}
+
+impl LintConfig {
+    pub fn from_file(path: &Utf8Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path).with_context(|| format!("Read {}", path))?;
+        serde_json::from_str(&text).with_context(|| format!("Parse {}", path))
+    }
+
+    /// The names of all checks: [`RULES`], those of the rulesets and the
+    /// block policy, then the naming and pattern rules.
+    pub fn rules(&self) -> Vec<String> {
+        let rulesets = self.rulesets.iter().flat_map(|r| r.rules());
+        let policy = match self.blocks.is_empty() {
+            true => &[][..],
+            false => policy::RULES,
+        };
+        let configured = self
+            .naming
+            .iter()
+            .map(|r| r.name.clone())
+            .chain(self.patterns.iter().map(|r| r.name.clone()));
+        RULES
+            .iter()
+            .chain(rulesets)
+            .chain(policy)
+            .map(|r| r.to_string())
+            .chain(configured)
+            .collect()
+    }
+}
+
+/// A built-in set of guideline checks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Ruleset {
+    /// MAAB/JMAAB modeling guidelines, see [`maab`].
+    Maab,
+}
+
+impl Ruleset {
+    /// The names of its checks, by [`Finding::source`].
+    pub fn rules(self) -> &'static [&'static str] {
+        match self {
+            Ruleset::Maab => maab::RULES,
+        }
+    }
+
+    fn check(self, system: &System) -> Vec<Finding> {
+        match self {
+            Ruleset::Maab => maab::check(system),
+        }
+    }
+}
+
+impl FromStr for Ruleset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "maab" => Ok(Ruleset::Maab),
+            other => anyhow::bail!("Unknown ruleset '{}' (expected maab)", other),
+        }
+    }
+}
+
+/// What a [`NamingRule`] checks the names of.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NamingTarget {
+    Blocks,
+    Subsystems,
+    Inports,
+    Outports,
+    /// Named lines.
+    Signals,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct NamingRule {
+    /// Reported as [`Finding::source`].
+    pub name: String,
+    pub applies_to: NamingTarget,
+    /// Names must match it somewhere; anchor it with `^` and `$` to match
+    /// the whole name.
+    #[serde(deserialize_with = "deserialize_regex")]
+    pub pattern: Regex,
+    #[serde(default = "warning")]
+    pub severity: Severity,
+    /// Block paths whose blocks, subsystem contents and signals are skipped.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Explanation reported instead of the generic one.
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl NamingRule {
+    fn check(&self, path: &str, what: &str, name: &str, out: &mut Vec<Finding>) {
+        if excluded(&self.exclude, path) || self.pattern.is_match(name) {
+            return;
+        }
+        out.push(Finding {
+            block: path.to_string(),
+            severity: self.severity,
+            source: self.name.clone(),
+            message: match &self.message {
+                Some(message) => format!("{} \"{}\": {}", what, name, message),
+                None => format!("{} \"{}\" does not match {}", what, name, self.pattern),
+            },
+        });
+    }
+}
+
+/// A [`PatternGraph`] reported wherever it matches.
+///
+/// A search stopped by [`patterns::MAX_STEPS`] is reported at the root
+/// system, so that a pathological pattern cannot stall a lint run.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PatternRule {
+    /// Reported as [`Finding::source`].
+    pub name: String,
+    /// In the text form of [`crate::patterns`].
+    #[serde(deserialize_with = "deserialize_pattern")]
+    pub pattern: PatternGraph,
+    #[serde(default = "warning")]
+    pub severity: Severity,
+    /// Block paths whose matches are skipped.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Explanation, e.g. the refactoring to apply, appended to the message.
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl PatternRule {
+    fn check(&self, system: &System, out: &mut Vec<Finding>) {
+        let search = patterns::search(system, &self.pattern, patterns::MAX_STEPS);
+        for m in &search.matches {
+            let Some(first) = m.blocks.first() else {
+                continue;
+            };
+            if m.blocks.iter().any(|b| excluded(&self.exclude, b)) {
+                continue;
+            }
+            let name = |b: &String| {
+                let path = b.parse::<BlockPath>().ok();
+                let name = path.as_ref().and_then(BlockPath::name).unwrap_or(b);
+                format!("\"{}\"", name)
+            };
+            let names: Vec<String> = m.blocks.iter().map(name).collect();
+            let found = format!("Blocks {} match pattern {}", names.join(", "), self.name);
+            out.push(Finding {
+                block: first.clone(),
+                severity: self.severity,
+                source: self.name.clone(),
+                message: match &self.message {
+                    Some(message) => format!("{}: {}", found, message),
+                    None => found,
+                },
+            });
+        }
+        if !search.complete {
+            out.push(Finding {
+                block: String::new(),
+                severity: self.severity,
+                source: self.name.clone(),
+                message: format!(
+                    "Search for pattern {} stopped after {} steps, further matches may be missing",
+                    self.name,
+                    patterns::MAX_STEPS
+                ),
+            });
+        }
+    }
+}
+
+fn warning() -> Severity {
+    Severity::Warning
+}
+
+/// Whether `path` is at or below one of the `exclude` paths.
+fn excluded(exclude: &[String], path: &str) -> bool {
+    exclude.iter().any(|e| {
+        let e = e.trim_matches('/');
+        path == e || path.strip_prefix(e).is_some_and(|r| r.starts_with('/'))
+    })
+}
+
+fn deserialize_pattern<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<PatternGraph, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    pattern.parse().map_err(serde::de::Error::custom)
+}
+
+fn deserialize_regex<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let pattern = String::deserialize(deserializer)?;
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+/// [`lint`] plus the rulesets and rules of `config`.
+pub fn lint_with(system: &System, config: &LintConfig) -> Vec<Finding> {
+    let mut findings = lint(system);
+    for ruleset in &config.rulesets {
+        findings.extend(ruleset.check(system));
+    }
+    findings.extend(config.blocks.check(system));
+    for rule in &config.naming {
+        check_names(system, rule, &mut BlockPath::root(), &mut findings);
+    }
+    for rule in &config.patterns {
+        rule.check(system, &mut findings);
+    }
+    findings
+}
+
+fn check_names(system: &System, rule: &NamingRule, path: &mut BlockPath, out: &mut Vec<Finding>) {
+    let block_path = |block: &Block| path.child(&block.name).to_string();
+    for block in &system.blocks {
+        let what = match (rule.applies_to, block.block_type.as_str()) {
+            (NamingTarget::Blocks, _) => "block",
+            (NamingTarget::Subsystems, "SubSystem") => "subsystem",
+            (NamingTarget::Inports, "Inport") => "Inport",
+            (NamingTarget::Outports, "Outport") => "Outport",
+            _ => continue,
+        };
+        rule.check(&block_path(block), what, &block.name, out);
+    }
+    if rule.applies_to == NamingTarget::Signals {
+        for line in &system.lines {
+            let (Some(name), Some(src)) = (&line.name, &line.src) else {
+                continue;
+            };
+            let source = system
+                .blocks
+                .iter()
+                .find(|b| b.sid.as_deref() == Some(src.sid.as_str()));
+            if let (false, Some(block)) = (name.is_empty(), source) {
+                rule.check(&block_path(block), "signal", name, out);
+            }
+        }
+    }
+    for block in &system.blocks {
+        if let Some(sub) = block.subsystem.as_deref() {
+            path.push(&block.name);
+            check_names(sub, rule, path, out);
+            path.pop();
+        }
+    }
+}
+
+/// Run all checks on `system` and its subsystems: rate-transition issues are
+/// warnings, magic numbers information.
+pub fn lint(system: &System) -> Vec<Finding> {
+    let rates = rate_transition_issues(system)
+        .into_iter()
+        .map(|issue| Finding {
+            block: issue.path,
+            severity: Severity::Warning,
+            source: "rate".to_string(),
+            message: issue.message,
+        });
+    let magic = find_magic_numbers(system, &MagicNumberOptions::default())
+        .into_iter()
+        .map(|m| Finding {
+            block: m.path,
+            severity: Severity::Information,
+            source: "magic-number".to_string(),
+            message: format!(
+                "{} = {} uses literal(s) {}",
+                m.parameter,
+                m.expression,
+                m.literals.join(", ")
+            ),
+        });
+    rates.chain(magic).collect()
+}
+
+/// The findings at `block` or, for a subsystem, inside it.
+pub fn findings_at<'a>(
+    findings: &'a [Finding],
+    block: &'a str,
+) -> impl Iterator<Item = &'a Finding> {
+    findings.iter().filter(move |f| {
+        f.block == block
+            || f.block
+                .strip_prefix(block)
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+/// CI thresholds on a list of findings. Errors always fail.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Gate {
+    /// Fail when more than this many warnings are found; `None` allows any.
+    pub max_warnings: Option<usize>,
+    /// Fail on any finding of these checks ([`Finding::source`]), whatever
+    /// its severity.
+    pub fail_on: Vec<String>,
+}
+
+impl Gate {
+    /// Why `findings` fail the gate, `None` if they pass.
+    pub fn check(&self, findings: &[Finding]) -> Option<String> {
+        let count = |severity| findings.iter().filter(|f| f.severity == severity).count();
+        let mut reasons = Vec::new();
+        let errors = count(Severity::Error);
+        if errors > 0 {
+            reasons.push(format!("{errors} error(s)"));
+        }
+        let warnings = count(Severity::Warning);
+        if let Some(max) = self.max_warnings
+            && warnings > max
+        {
+            reasons.push(format!("{warnings} warning(s), at most {max} allowed"));
+        }
+        for rule in &self.fail_on {
+            let hits = findings.iter().filter(|f| f.source == *rule).count();
+            if hits > 0 {
+                reasons.push(format!("{hits} {rule} finding(s)"));
+            }
+        }
+        (!reasons.is_empty()).then(|| reasons.join(", "))
+    }
+}
+
+/// JUnit XML report of `findings`: one test suite per rule in `rules`
+/// ([`LintConfig::rules`]) and one test case per system level of `system`, failed by the findings at
+/// blocks directly in that level. `model` names the root level and prefixes
+/// the others, e.g. `plant/Ctrl`.
+pub fn to_junit(system: &System, findings: &[Finding], rules: &[String], model: &str) -> String {
+    fn levels(system: &System, path: &mut BlockPath, out: &mut Vec<BlockPath>) {
+        out.push(path.clone());
+        for block in &system.blocks {
+            if let Some(sub) = block.subsystem.as_deref() {
+                path.push(&block.name);
+                levels(sub, path, out);
+                path.pop();
+            }
+        }
+    }
+    let parent = |f: &Finding| {
+        f.block
+            .parse::<BlockPath>()
+            .ok()
+            .and_then(|p| p.parent())
+            .unwrap_or_default()
+    };
+    let mut systems = Vec::new();
+    levels(system, &mut BlockPath::root(), &mut systems);
+    // Findings in levels the model does not expand, e.g. unresolved links
+    for f in findings {
+        let level = parent(f);
+        if !systems.contains(&level) {
+            systems.push(level);
+        }
+    }
+    let name = |level: &BlockPath| match level.is_root() {
+        true => model.to_string(),
+        false => format!("{model}/{level}"),
+    };
+
+    let mut suites = String::new();
+    let mut total_failures = 0;
+    for rule in rules {
+        let mut cases = String::new();
+        let mut failures = 0;
+        for level in &systems {
+            let found: Vec<&Finding> = findings
+                .iter()
+                .filter(|f| f.source == *rule && parent(f) == *level)
+                .collect();
+            let _ = write!(
+                cases,
+                "    <testcase classname=\"{}\" name=\"{}\"",
+                xml_escape_attr(rule),
+                xml_escape_attr(&name(level))
+            );
+            let Some(worst) = found.iter().map(|f| f.severity).min() else {
+                cases.push_str("/>\n");
+                continue;
+            };
+            failures += 1;
+            let _ = write!(
+                cases,
+                ">\n      <failure type=\"{}\" message=\"{} finding(s)\">",
+                worst.as_str(),
+                found.len()
+            );
+            for f in &found {
+                let _ = writeln!(
+                    cases,
+                    "{}: {}: {}",
+                    xml_escape(&f.block),
+                    f.severity.as_str(),
+                    xml_escape(&f.message)
+                );
+            }
+            cases.push_str("</failure>\n    </testcase>\n");
+        }
+        total_failures += failures;
+        let _ = write!(
+            suites,
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n{}  </testsuite>\n",
+            xml_escape_attr(rule),
+            systems.len(),
+            failures,
+            cases
+        );
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n{}</testsuites>\n",
+        xml_escape_attr(model),
+        systems.len() * rules.len(),
+        total_failures,
+        suites
+    )
+}
@@ -0,0 +1,194 @@
+//! Which blocks a model may use, set in the `blocks` section of a
+//! [`LintConfig`](super::LintConfig):
+//!
+//! ```json
+//! {
+//!   "blocks": {
+//!     "forbidden": [
+//!       { "type": "MATLABFcn", "severity": "error",
+//!         "remedy": "use a MATLAB Function block, which generates code" },
+//!       { "type": "Scope", "exclude": ["Test"] }
+//!     ],
+//!     "allowed": { "types": ["Inport", "Outport", "Gain", "Sum", "SubSystem"] },
+//!     "required": [
+//!       { "type": "Gain", "property": "SaturateOnIntegerOverflow", "value": "on" }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! `forbidden` block types are reported as `forbidden-block`. With an
+//! `allowed` list, every other block type is reported as `unlisted-block`.
+//! A `required` property must be set on every block of the type, to `value`
+//! if given, or is reported as `required-property`. Each entry may set a
+//! `severity` (warning by default), `exclude` paths like naming rules, and a
+//! `remedy` appended to the message. [`counts_per_subsystem`] sums the
+//! findings up by system level.
+
+use super::{Finding, Severity, excluded, warning};
+use crate::block_path::BlockPath;
+use crate::model::{Block, System};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// The rules of a [`BlockPolicy`], by [`Finding::source`].
+pub const RULES: &[&str] = &["forbidden-block", "unlisted-block", "required-property"];
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlockPolicy {
+    pub forbidden: Vec<ForbiddenBlock>,
+    /// The only block types allowed; `None` allows any not forbidden.
+    pub allowed: Option<AllowedBlocks>,
+    pub required: Vec<RequiredProperty>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ForbiddenBlock {
+    /// `BlockType`, e.g. `Scope`.
+    #[serde(rename = "type")]
+    pub block_type: String,
+    #[serde(default = "warning")]
+    pub severity: Severity,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// What to do instead, appended to the message.
+    #[serde(default)]
+    pub remedy: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AllowedBlocks {
+    pub types: Vec<String>,
+    /// Severity, exclusions and remedy for blocks not on the list.
+    #[serde(default = "warning")]
+    pub severity: Severity,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub remedy: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RequiredProperty {
+    /// `BlockType` the property is required on.
+    #[serde(rename = "type")]
+    pub block_type: String,
+    pub property: String,
+    /// The value it must have; `None` only requires it to be set.
+    #[serde(default)]
+    pub value: Option<String>,
+    #[serde(default = "warning")]
+    pub severity: Severity,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub remedy: Option<String>,
+}
+
+impl BlockPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.forbidden.is_empty() && self.allowed.is_none() && self.required.is_empty()
+    }
+
+    /// Check every block of `system` and its subsystems.
+    pub fn check(&self, system: &System) -> Vec<Finding> {
+        let mut out = Vec::new();
+        system.walk_blocks(&mut Vec::new(), &mut |path, block| {
+            let path = BlockPath::from(path).child(&block.name).to_string();
+            let block_type = block.block_type.as_str();
+            let mut report = |source: &str, severity, exclude: &[String], remedy, message| {
+                if excluded(exclude, &path) {
+                    return;
+                }
+                out.push(Finding {
+                    block: path.clone(),
+                    severity,
+                    source: source.to_string(),
+                    message: match remedy {
+                        Some(remedy) => format!("{}; {}", message, remedy),
+                        None => message,
+                    },
+                });
+            };
+            for rule in self.forbidden.iter().filter(|r| r.block_type == block_type) {
+                let message = format!("{} blocks are not allowed", block_type);
+                let remedy = rule.remedy.as_deref();
+                report(
+                    "forbidden-block",
+                    rule.severity,
+                    &rule.exclude,
+                    remedy,
+                    message,
+                );
+            }
+            if let Some(allowed) = &self.allowed
+                && !allowed.types.iter().any(|t| t == block_type)
+            {
+                let message = format!("{} is not on the list of allowed blocks", block_type);
+                let remedy = allowed.remedy.as_deref();
+                report(
+                    "unlisted-block",
+                    allowed.severity,
+                    &allowed.exclude,
+                    remedy,
+                    message,
+                );
+            }
+            for rule in self.required.iter().filter(|r| r.block_type == block_type) {
+                if let Some(message) = rule.violation(block) {
+                    let remedy = rule.remedy.as_deref();
+                    report(
+                        "required-property",
+                        rule.severity,
+                        &rule.exclude,
+                        remedy,
+                        message,
+                    );
+                }
+            }
+        });
+        out
+    }
+}
+
+impl RequiredProperty {
+    fn violation(&self, block: &Block) -> Option<String> {
+        let actual = block.properties.get(&self.property).map(|v| v.trim());
+        match (self.value.as_deref().map(str::trim), actual) {
+            (None, Some(_)) => None,
+            (Some(value), Some(actual)) if actual == value => None,
+            (None, None) => Some(format!("{} is not set", self.property)),
+            (Some(value), None) => Some(format!(
+                "{} is not set, it must be {}",
+                self.property, value
+            )),
+            (Some(value), Some(actual)) => Some(format!(
+                "{} is {}, it must be {}",
+                self.property, actual, value
+            )),
+        }
+    }
+}
+
+/// The number of policy findings directly in each system level, by the
+/// [`BlockPath`] string of its subsystem (`""` for the root).
+pub fn counts_per_subsystem(findings: &[Finding]) -> BTreeMap<String, usize> {
+    let mut counts = BTreeMap::new();
+    for f in findings
+        .iter()
+        .filter(|f| RULES.contains(&f.source.as_str()))
+    {
+        let level = f
+            .block
+            .parse::<BlockPath>()
+            .ok()
+            .and_then(|p| p.parent())
+            .unwrap_or_default();
+        *counts.entry(level.to_string()).or_default() += 1;
+    }
+    counts
+}
@@ -0,0 +1,193 @@
+//! Signal logging specifications for data recorders.
+//!
+//! Simulink marks signals for logging on the output port that drives them:
+//! `DataLogging` logs the signal to the simulation output, `TestPoint`
+//! guarantees it stays observable in generated code. [`logging_spec`] collects
+//! every marked port with its logging name, decimation and buffer limit, and
+//! [`LoggingSpec`] writes the result as JSON or as a MAT file holding one
+//! `loggingSpec` struct with the same fields.
+
+use crate::block_path::BlockPath;
+use crate::mat_file::{MatValue, write_mat};
+use crate::model::{Block, System};
+use crate::port_info::attached_line;
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::Serialize;
+
+/// Version of the specification layout, bumped on incompatible changes.
+pub const LOGGING_SPEC_VERSION: u32 = 1;
+
+/// A signal marked for logging or as a test point.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggedSignal {
+    /// Path of the block driving the signal, as a [`BlockPath`] string.
+    pub block: String,
+    pub sid: Option<String>,
+    /// 1-based output port index.
+    pub port: u32,
+    /// Signal name from the port or its line; empty for unnamed signals.
+    pub signal: String,
+    /// Name the signal is logged under: `DataLoggingName` when the name mode
+    /// is `Custom`, otherwise the signal name, or `<block>:<port>` for
+    /// unnamed signals.
+    pub logging_name: String,
+    /// `DataLogging` is on.
+    pub logged: bool,
+    /// `TestPoint` is on.
+    pub test_point: bool,
+    /// Record every n-th sample (`DataLoggingDecimation`); 1 unless
+    /// decimation is enabled.
+    pub decimation: u32,
+    /// Keep only the last n samples (`DataLoggingMaxPoints`), if limited.
+    pub max_points: Option<u64>,
+    /// `DataLoggingSampleTime`; `-1` means inherited.
+    pub sample_time: String,
+}
+
+/// All logged signals of a model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LoggingSpec {
+    pub version: u32,
+    pub model: String,
+    pub signals: Vec<LoggedSignal>,
+}
+
+/// Collect the logged signals and test points of `system` and its
+/// subsystems, in model order.
+pub fn logging_spec(system: &System, model: &str) -> LoggingSpec {
+    let mut signals = Vec::new();
+    collect(system, &BlockPath::root(), &mut signals);
+    LoggingSpec {
+        version: LOGGING_SPEC_VERSION,
+        model: model.to_string(),
+        signals,
+    }
+}
+
+fn collect(system: &System, prefix: &BlockPath, signals: &mut Vec<LoggedSignal>) {
+    for block in &system.blocks {
+        let path = prefix.child(&block.name);
+        for port in block.ports.iter().filter(|p| p.port_type == "out") {
+            let param = |name: &str| port.properties.get(name).map(|v| v.trim());
+            let is_on = |name: &str| param(name).is_some_and(|v| v.eq_ignore_ascii_case("on"));
+            let (logged, test_point) = (is_on("DataLogging"), is_on("TestPoint"));
+            if !logged && !test_point {
+                continue;
+            }
+            let index = port.index.unwrap_or(1);
+            let signal = param("Name")
+                .filter(|n| !n.is_empty())
+                .map(str::to_string)
+                .or_else(|| line_name(system, block, index))
+                .unwrap_or_default();
+            let logging_name = match param("DataLoggingName") {
+                Some(name)
+                    if param("DataLoggingNameMode") == Some("Custom") && !name.is_empty() =>
+                {
+                    name.to_string()
+                }
+                _ if !signal.is_empty() => signal.clone(),
+                _ => format!("{}:{}", path, index),
+            };
+            let decimation = if is_on("DataLoggingDecimateData") {
+                param("DataLoggingDecimation").and_then(|v| v.parse().ok())
+            } else {
+                None
+            };
+            let max_points = if is_on("DataLoggingLimitDataPoints") {
+                param("DataLoggingMaxPoints").and_then(|v| v.parse().ok())
+            } else {
+                None
+            };
+            signals.push(LoggedSignal {
+                block: path.to_string(),
+                sid: block.sid.clone(),
+                port: index,
+                signal,
+                logging_name,
+                logged,
+                test_point,
+                decimation: decimation.unwrap_or(1).max(1),
+                max_points,
+                sample_time: param("DataLoggingSampleTime").unwrap_or("-1").to_string(),
+            });
+        }
+        if let Some(sub) = &block.subsystem {
+            collect(sub, &path, signals);
+        }
+    }
+}
+
+/// Name of the line leaving output `index` of `block`.
+fn line_name(system: &System, block: &Block, index: u32) -> Option<String> {
+    let line = &system.lines[attached_line(&system.lines, block.sid.as_deref()?, index, false)?];
+    line.name.clone().filter(|n| !n.is_empty())
+}
+
+impl LoggingSpec {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// The specification as a MAT file with one `loggingSpec` variable: a
+    /// struct with `version`, `model` and a 1×N `signals` struct array. An
+    /// unlimited `maxPoints` is stored as `Inf`, a missing SID as `''`.
+    pub fn to_mat(&self) -> Vec<u8> {
+        let scalar = |v: f64| MatValue::Numeric {
+            dims: vec![1, 1],
+            real: vec![v],
+            imag: None,
+            logical: false,
+        };
+        let flag = |v: bool| MatValue::Numeric {
+            dims: vec![1, 1],
+            real: vec![f64::from(u8::from(v))],
+            imag: None,
+            logical: true,
+        };
+        let text = |s: &str| MatValue::Char(s.to_string());
+        let signals = self
+            .signals
+            .iter()
+            .map(|s| {
+                IndexMap::from([
+                    ("block".to_string(), text(&s.block)),
+                    ("sid".to_string(), text(s.sid.as_deref().unwrap_or(""))),
+                    ("port".to_string(), scalar(f64::from(s.port))),
+                    ("signal".to_string(), text(&s.signal)),
+                    ("loggingName".to_string(), text(&s.logging_name)),
+                    ("logged".to_string(), flag(s.logged)),
+                    ("testPoint".to_string(), flag(s.test_point)),
+                    ("decimation".to_string(), scalar(f64::from(s.decimation))),
+                    (
+                        "maxPoints".to_string(),
+                        scalar(s.max_points.map_or(f64::INFINITY, |n| n as f64)),
+                    ),
+                    ("sampleTime".to_string(), text(&s.sample_time)),
+                ])
+            })
+            .collect::<Vec<_>>();
+        let spec = IndexMap::from([
+            ("version".to_string(), scalar(f64::from(self.version))),
+            ("model".to_string(), text(&self.model)),
+            (
+                "signals".to_string(),
+                MatValue::Struct {
+                    dims: vec![1, signals.len()],
+                    elements: signals,
+                },
+            ),
+        ]);
+        let vars = IndexMap::from([(
+            "loggingSpec".to_string(),
+            MatValue::Struct {
+                dims: vec![1, 1],
+                elements: vec![spec],
+            },
+        )]);
+        write_mat(&vars)
+    }
+}
@@ -1,43 +1,1694 @@
 // Use the library crate's modules instead of redefining them here.
 
 use anyhow::{Context, Result};
-use camino::Utf8PathBuf;
-use clap::Parser;
-use rustylink::parser::{FsSource, SimulinkParser, ZipSource};
+use camino::{Utf8Path, Utf8PathBuf};
+use clap::{CommandFactory, Parser, Subcommand};
+use rustylink::analysis::{
+    MagicNumberOptions, find_clones, find_magic_numbers, rate_transition_issues,
+};
+use rustylink::cancel::CancellationToken;
+use rustylink::cosmetic::CosmeticFilter;
+use rustylink::diff::{SystemDiff, diff_systems_filtered, to_html};
+use rustylink::doctor::{Status as DoctorStatus, diagnose};
+use rustylink::generator::archive::WriteOptions;
+use rustylink::generator::thumbnail::ThumbnailOptions;
+use rustylink::interface::{ChangeKind, ModelInterface, PortDirection, check_compat};
+use rustylink::lint::policy::counts_per_subsystem;
+use rustylink::lint::{Finding, Gate, LintConfig, Ruleset, Severity, lint_with, to_junit};
+use rustylink::model::{SlxArchive, System, THUMBNAIL_PATH};
+use rustylink::parser::{
+    ContentSource, FsSource, ModelProtectedError, ParseProfile, ParseProgress, ParserOptions,
+    ProfilePhase, ProtectionKind, SimulinkParser, ZipSource,
+};
+use rustylink::report::{
+    BlockParameterDefaults, MexPlatform, OverrideOptions, SFunctionDependency, model_callbacks,
+    overrides_to_csv, sfunction_dependencies,
+};
+use rustylink::stimulus::{
+    harness_model, root_inports, root_outports, stimulus_csv, stimulus_json,
+};
+use rustylink::transform::{
+    AnonymizeOptions, PortOrder, anonymize_archive, extract_to_library, name_signals_in,
+    new_library, order_ports_in,
+};
+use rustylink::watch::parse_operations;
+use std::process::ExitCode;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about = "Parse Simulink .slx or XML system files to JSON", long_about = None)]
+#[command(subcommand_negates_reqs = true)]
+#[command(
+    after_help = "Exit status: 0 ok, 1 findings above the --max-warnings/--fail-on \
+threshold, 2 the model could not be read or parsed, 3 usage error"
+)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     /// Simulink .slx file or system XML file
-    #[arg(value_name = "SIMULINK_FILE")]
-    simulink_file: String,
+    #[arg(value_name = "SIMULINK_FILE", required = true)]
+    simulink_file: Option<String>,
 
     /// Print output as JSON (full tree)
     #[arg(short = 'j', long = "json")]
     json: bool,
+
+    /// Print the JSON tree with blocks sorted by SID and keys sorted by name,
+    /// for byte-level comparisons (see `rustylink::stable_json`); implies --json
+    #[arg(long = "stable-json")]
+    stable_json: bool,
+
+    /// Report files discovered/parsed and the current phase on stderr while parsing
+    #[arg(long = "progress")]
+    progress: bool,
+
+    /// Print the subcommands and their options as JSON, for wrapper tooling
+    #[arg(long = "help-json", exclusive = true)]
+    help_json: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Dump embedded images (mask images, annotation images, thumbnail) to a folder
+    ExtractAssets {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Output directory; archive paths are recreated below it
+        #[arg(short = 'o', long = "output", default_value = "assets")]
+        output: String,
+    },
+    /// Read an .slx file and write it back out through the generator
+    Rewrite {
+        /// Input .slx file
+        #[arg(value_name = "INPUT")]
+        input: String,
+
+        /// Output .slx file
+        #[arg(value_name = "OUTPUT")]
+        output: String,
+
+        /// Drop archive members that are not part of the model package
+        /// (slprj leftovers, code caches, custom parts)
+        #[arg(long = "strip-nonessential")]
+        strip_nonessential: bool,
+    },
+    /// List all MATLAB callback code of a model: model callbacks, block
+    /// callbacks, mask initialization and mask parameter callbacks
+    Callbacks {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Print JSON instead of a listing
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// List the S-function MEX and MATLAB files a model needs, with the file
+    /// name expected on each platform
+    Sfunctions {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Directories to look for the files for this platform; fails if any
+        /// is missing. Can be repeated.
+        #[arg(short = 'L', long = "path", value_name = "DIR")]
+        path: Vec<String>,
+
+        /// Print JSON instead of a listing
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// Export configuration collected from a model
+    #[command(group(
+        clap::ArgGroup::new("outputs")
+            .required(true)
+            .multiple(true)
+            .args(["logging_spec", "parquet", "sqlite"])
+    ))]
+    Export {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Write the signals marked for logging or as test points to FILE:
+        /// a MAT file if it ends in `.mat`, JSON otherwise (`-` for stdout)
+        #[arg(long = "logging-spec", value_name = "FILE")]
+        logging_spec: Option<String>,
+
+        /// Write the blocks, parameters and connections as Parquet files
+        /// into DIR (needs the `arrow` feature)
+        #[arg(long = "parquet", value_name = "DIR")]
+        parquet: Option<String>,
+
+        /// Store blocks, parameters, ports, lines, charts and the subsystem
+        /// hierarchy in the SQLite database FILE, replacing earlier rows of
+        /// the same model (needs the `sqlite` feature)
+        #[arg(long = "sqlite", value_name = "FILE")]
+        sqlite: Option<String>,
+    },
+    /// List block parameters that differ from the model's BlockParameterDefaults
+    ParameterOverrides {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Print CSV instead of a grouped listing
+        #[arg(long = "csv")]
+        csv: bool,
+
+        #[command(flatten)]
+        cosmetic: CosmeticArgs,
+    },
+    /// Triage a model that does not open: archive integrity, release,
+    /// protection, and missing libraries, referenced models and S-functions.
+    /// Exits with status 2 if it cannot open and 1 if something is missing
+    Doctor {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Directories to look for libraries, referenced models and
+        /// S-functions besides the model's own. Can be repeated.
+        #[arg(short = 'L', long = "path", value_name = "DIR")]
+        path: Vec<String>,
+
+        /// Print JSON instead of a summary
+        #[arg(long = "json")]
+        json: bool,
+    },
+    /// Run all model checks (rate transitions, magic numbers); exits with
+    /// status 1 on errors or when a threshold is exceeded
+    Lint {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Output format; `junit` is for the test reports of Jenkins or GitLab
+        #[arg(long = "format", value_name = "FORMAT", default_value = "text")]
+        format: ReportFormat,
+
+        /// Read additional rules, such as naming conventions and forbidden
+        /// blocks, from a JSON file (see `rustylink::lint`)
+        #[arg(long = "config", value_name = "FILE")]
+        config: Option<String>,
+
+        /// Add a built-in set of guideline checks: `maab` for the MAAB/JMAAB
+        /// modeling guidelines. Repeatable
+        #[arg(long = "ruleset", value_name = "RULESET")]
+        ruleset: Vec<Ruleset>,
+
+        #[command(flatten)]
+        gate: GateArgs,
+    },
+    /// List numeric literals in block parameters; exits with status 1 if any
+    /// are found, unless --max-warnings allows more
+    MagicNumbers {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Additional literal values to accept besides 0, 1 and -1
+        #[arg(long = "allow", value_name = "VALUE")]
+        allow: Vec<f64>,
+
+        #[command(flatten)]
+        gate: GateArgs,
+    },
+    /// Check multirate wiring and Rate Transition settings; exits with status 1
+    /// if any issues are found, unless --max-warnings allows more
+    RateCheck {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        #[command(flatten)]
+        gate: GateArgs,
+    },
+    /// List groups of structurally identical or similar subsystems
+    Clones {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Ignore subsystems with fewer blocks than this
+        #[arg(long = "min-blocks", value_name = "N", default_value_t = 3)]
+        min_blocks: usize,
+    },
+    /// Move a subsystem into a library and replace its copies with linked blocks
+    ExtractToLibrary {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Library .slx file; created if it does not exist
+        #[arg(long = "library", value_name = "LIBRARY_SLX")]
+        library: String,
+
+        /// Subsystem paths; the first one becomes the library block
+        #[arg(value_name = "PATH", required = true)]
+        paths: Vec<String>,
+
+        /// Output .slx file for the model (defaults to rewriting SLX_FILE)
+        #[arg(short = 'o', long = "output")]
+        output: Option<String>,
+    },
+    /// Renumber subsystem Inports and Outports top to bottom and close gaps
+    /// in their numbering, or move them into the order of their numbers
+    OrderPorts {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// `position` renumbers the ports as placed, `number` moves them
+        #[arg(long = "by", value_name = "ORDER", default_value = "position")]
+        by: PortOrderArg,
+
+        /// Output .slx file (defaults to rewriting SLX_FILE)
+        #[arg(short = 'o', long = "output")]
+        output: Option<String>,
+    },
+    /// Name the unnamed signals feeding Outports and To Workspace blocks, and
+    /// logged signals, after the ports driving them
+    NameSignals {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Output .slx file (defaults to rewriting SLX_FILE)
+        #[arg(short = 'o', long = "output")]
+        output: Option<String>,
+    },
+    /// Rename blocks and signals and strip annotations, callbacks and code so
+    /// that a model can be shared in a bug report
+    Anonymize {
+        /// Input .slx file
+        #[arg(value_name = "INPUT")]
+        input: String,
+
+        /// Output .slx file
+        #[arg(value_name = "OUTPUT")]
+        output: String,
+
+        /// Write the old-to-new name mapping as JSON to FILE (keep it private)
+        #[arg(long = "map", value_name = "FILE")]
+        map: Option<String>,
+    },
+    /// Generate stimulus templates and a test harness for the root Inports;
+    /// prints the CSV template if no output is given
+    Stimulus {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Write a CSV template (one column per Inport element)
+        #[arg(long = "csv", value_name = "FILE")]
+        csv: Option<String>,
+
+        /// Write a JSON template with the interface and From Workspace data
+        #[arg(long = "json", value_name = "FILE")]
+        json: Option<String>,
+
+        /// Write a harness model feeding the model from the workspace
+        #[arg(long = "harness", value_name = "SLX_FILE")]
+        harness: Option<String>,
+    },
+    /// Parse a model, resolve its libraries and print it as JSON
+    Parse {
+        /// Simulink .slx file or system XML file
+        #[arg(value_name = "SIMULINK_FILE")]
+        file: String,
+
+        /// Additional directories to search for library `.slx` files. Can be repeated.
+        #[arg(short = 'L', long = "lib")]
+        lib: Vec<String>,
+
+        /// Print a table of per-phase timings to stderr
+        #[arg(long = "profile")]
+        profile: bool,
+
+        /// Write the per-phase timings as JSON to FILE
+        #[arg(long = "profile-json", value_name = "FILE")]
+        profile_json: Option<String>,
+
+        /// Don't print the model JSON
+        #[arg(short = 'q', long = "quiet")]
+        quiet: bool,
+
+        /// Sort blocks by SID and keys by name (see `rustylink::stable_json`)
+        #[arg(long = "stable-json")]
+        stable_json: bool,
+
+        /// Stream one JSON object per system, block, line and annotation
+        /// instead of the tree (see `rustylink::ndjson`)
+        #[arg(long = "ndjson", conflicts_with = "stable_json")]
+        ndjson: bool,
+    },
+    /// Count blocks, lines and library links of many .slx files without
+    /// building the full models; prints per-file and total counts as JSON
+    Stats {
+        /// Simulink .slx files
+        #[arg(value_name = "SLX_FILE", required = true)]
+        files: Vec<String>,
+    },
+    /// Print the structural hash of each model, which ignores layout and
+    /// other cosmetic changes (see `rustylink::hash`), one `HASH  FILE` line
+    /// per file
+    Hash {
+        /// Simulink .slx files
+        #[arg(value_name = "SLX_FILE", required = true)]
+        files: Vec<String>,
+
+        /// Print the full hash instead, which changes with every edit
+        #[arg(long = "full")]
+        full: bool,
+
+        /// Read what the structural hash leaves out from a JSON file (see
+        /// `rustylink::cosmetic`)
+        #[arg(long = "cosmetic-config", value_name = "FILE", conflicts_with = "full")]
+        cosmetic_config: Option<String>,
+    },
+    /// Print a model as sorted JSON for `git diff`; configure with
+    /// `git config diff.slx.textconv "rustylink textconv"` and `*.slx diff=slx`
+    /// in .gitattributes
+    Textconv {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        #[command(flatten)]
+        cosmetic: CosmeticArgs,
+    },
+    /// Compare two models; prints the differences as JSON
+    Diff {
+        /// Original .slx file
+        #[arg(value_name = "OLD_SLX")]
+        old: String,
+
+        /// Changed .slx file
+        #[arg(value_name = "NEW_SLX")]
+        new: String,
+
+        /// Write an HTML report with side-by-side diagrams instead
+        #[arg(long = "html", value_name = "FILE")]
+        html: Option<String>,
+
+        #[command(flatten)]
+        cosmetic: CosmeticArgs,
+
+        #[command(flatten)]
+        gate: GateArgs,
+    },
+    /// Compare the root Inports and Outports of two model versions and
+    /// classify the changes as breaking or not
+    InterfaceDiff {
+        /// Original .slx file
+        #[arg(value_name = "OLD_SLX")]
+        old: String,
+
+        /// Changed .slx file
+        #[arg(value_name = "NEW_SLX")]
+        new: String,
+
+        /// Exit with status 1 if any change is breaking; same as
+        /// `--fail-on breaking-change`
+        #[arg(long = "fail-on-breaking")]
+        fail_on_breaking: bool,
+
+        /// Print JSON instead of a listing
+        #[arg(long = "json")]
+        json: bool,
+
+        #[command(flatten)]
+        gate: GateArgs,
+    },
+    /// Re-run checks whenever the model or its libraries are saved
+    Watch {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+
+        /// Comma-separated operations: lint, render, clones
+        #[arg(long = "on-change", value_name = "OPS", default_value = "lint")]
+        on_change: String,
+    },
+    /// Run as a service keeping parsed models in memory
+    Serve {
+        /// Serve the REST API on this address, e.g. 127.0.0.1:8080
+        #[arg(long = "http", value_name = "ADDR", required_unless_present = "stdio")]
+        http: Option<String>,
+
+        /// Speak JSON-RPC on stdin/stdout, for editor integrations
+        #[arg(long = "stdio", conflicts_with = "http")]
+        stdio: bool,
+    },
+    /// Browse a model's subsystems, blocks and parameters in the terminal
+    /// (needs the `tui` feature)
+    Tui {
+        /// Simulink .slx file
+        #[arg(value_name = "SLX_FILE")]
+        slx_file: String,
+    },
+    /// Print a shell completion script, e.g.
+    /// `rustylink completions bash > /etc/bash_completion.d/rustylink`
+    Completions {
+        /// Shell to complete in
+        #[arg(value_name = "SHELL")]
+        shell: clap_complete::Shell,
+    },
+    /// Work with the editor's block catalog
+    Catalog {
+        #[command(subcommand)]
+        command: CatalogCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CatalogCommand {
+    /// Write the built-in block catalog as TOML, to extend and load with the
+    /// viewer's --catalog (needs the `egui` feature)
+    Export {
+        /// Output file; stdout if omitted
+        #[arg(value_name = "FILE")]
+        output: Option<String>,
+
+        /// Write JSON instead of TOML; the default for a .json FILE
+        #[arg(long = "json")]
+        json: bool,
+    },
+}
+
+/// What `order-ports` keeps: the placement or the numbers.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum PortOrderArg {
+    Position,
+    Number,
+}
+
+/// How the checking commands print their findings.
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum ReportFormat {
+    Text,
+    Json,
+    Junit,
+}
+
+/// When findings fail the run, shared by the commands reporting findings.
+#[derive(clap::Args, Debug)]
+struct GateArgs {
+    /// Exit with status 1 if more than N warnings are found
+    #[arg(long = "max-warnings", value_name = "N")]
+    max_warnings: Option<usize>,
+
+    /// Exit with status 1 on any finding of this rule, e.g. `rate`,
+    /// `magic-number`, `block-removed` or `breaking-change`; repeatable
+    #[arg(long = "fail-on", value_name = "RULE")]
+    fail_on: Vec<String>,
+}
+
+impl GateArgs {
+    /// The selected gate; `default_max` applies without --max-warnings.
+    fn gate(&self, default_max: Option<usize>) -> Gate {
+        Gate {
+            max_warnings: self.max_warnings.or(default_max),
+            fail_on: self.fail_on.clone(),
+        }
+    }
+}
+
+/// Findings failed a [`Gate`]; exits with status 1.
+#[derive(Debug)]
+struct GateFailed(String);
+
+impl std::fmt::Display for GateFailed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for GateFailed {}
+
+/// The command line asks for something this build cannot do; exits with
+/// status 3 like clap's own usage errors.
+#[derive(Debug)]
+struct UsageError(String);
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for UsageError {}
+
+/// Exit statuses, kept stable so pipelines can gate on them.
+const EXIT_FINDINGS: u8 = 1;
+const EXIT_PARSE_ERROR: u8 = 2;
+const EXIT_USAGE: u8 = 3;
+
+/// Fail with [`GateFailed`] if `findings` do not pass `gate`.
+fn enforce(gate: &Gate, findings: &[Finding]) -> Result<()> {
+    match gate.check(findings) {
+        Some(reason) => Err(GateFailed(reason).into()),
+        None => Ok(()),
+    }
+}
+
+/// What counts as a cosmetic change, shared by the commands comparing models.
+#[derive(clap::Args, Debug)]
+struct CosmeticArgs {
+    /// Ignore parameters that only affect appearance (Position, ZOrder,
+    /// fonts, colors, window settings)
+    #[arg(long = "ignore-cosmetic")]
+    ignore_cosmetic: bool,
+
+    /// Read what counts as cosmetic from a JSON file (see
+    /// `rustylink::cosmetic`); implies --ignore-cosmetic
+    #[arg(long = "cosmetic-config", value_name = "FILE")]
+    cosmetic_config: Option<String>,
+}
+
+impl CosmeticArgs {
+    /// The selected filter, `None` if every change counts.
+    fn filter(&self) -> Result<Option<CosmeticFilter>> {
+        match &self.cosmetic_config {
+            Some(path) => CosmeticFilter::from_file(Utf8Path::new(path)).map(Some),
+            None => Ok(self.ignore_cosmetic.then(CosmeticFilter::default)),
+        }
+    }
+}
+
+fn doctor(slx_file: &str, path: &[String], json: bool) -> Result<()> {
+    let data = std::fs::read(slx_file).with_context(|| format!("Read {}", slx_file))?;
+    let model_dir = match Utf8Path::new(slx_file).parent() {
+        Some(dir) if !dir.as_str().is_empty() => dir.to_path_buf(),
+        _ => Utf8PathBuf::from("."),
+    };
+    let mut dirs = vec![model_dir];
+    dirs.extend(path.iter().map(Utf8PathBuf::from));
+    let diagnosis = diagnose(&data, &dirs);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diagnosis)?);
+    } else {
+        for check in &diagnosis.checks {
+            let tag = match check.status {
+                DoctorStatus::Ok => "ok",
+                DoctorStatus::Warning => "WARNING",
+                DoctorStatus::Error => "ERROR",
+            };
+            println!("{:8} {}: {}", tag, check.name, check.summary);
+            for detail in &check.details {
+                println!("         {}", detail);
+            }
+            if let Some(hint) = &check.hint {
+                println!("         -> {}", hint);
+            }
+        }
+    }
+    let count = |status| {
+        diagnosis
+            .checks
+            .iter()
+            .filter(|c| c.status == status)
+            .count()
+    };
+    match diagnosis.status() {
+        DoctorStatus::Ok => Ok(()),
+        DoctorStatus::Warning => Err(GateFailed(format!(
+            "{} check(s) found missing dependencies",
+            count(DoctorStatus::Warning)
+        ))
+        .into()),
+        DoctorStatus::Error => anyhow::bail!(
+            "{} will not open: {} check(s) failed",
+            slx_file,
+            count(DoctorStatus::Error)
+        ),
+    }
+}
+
+fn lint_model(
+    slx_file: &str,
+    format: ReportFormat,
+    config: Option<&str>,
+    rulesets: &[Ruleset],
+    gate: &Gate,
+) -> Result<()> {
+    let mut config = match config {
+        Some(path) => LintConfig::from_file(Utf8Path::new(path))?,
+        None => LintConfig::default(),
+    };
+    for ruleset in rulesets {
+        if !config.rulesets.contains(ruleset) {
+            config.rulesets.push(*ruleset);
+        }
+    }
+    let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+    let source = ZipSource::new(std::io::BufReader::new(file))?;
+    let mut parser = SimulinkParser::new("", source);
+    let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+    let findings = lint_with(&system, &config);
+    match format {
+        ReportFormat::Text => {
+            for f in &findings {
+                let severity = f.severity.as_str();
+                println!("{:11} {} [{}]: {}", severity, f.block, f.source, f.message);
+            }
+            let model = Utf8Path::new(slx_file).file_stem().unwrap_or(slx_file);
+            for (level, count) in counts_per_subsystem(&findings) {
+                match level.is_empty() {
+                    true => eprintln!("{} block policy finding(s) in {}", count, model),
+                    false => eprintln!("{} block policy finding(s) in {}/{}", count, model, level),
+                }
+            }
+        }
+        ReportFormat::Json => println!("{}", serde_json::to_string_pretty(&findings)?),
+        ReportFormat::Junit => {
+            let model = Utf8Path::new(slx_file).file_stem().unwrap_or(slx_file);
+            print!("{}", to_junit(&system, &findings, &config.rules(), model));
+        }
+    }
+    enforce(gate, &findings)
+}
+
+fn magic_numbers(slx_file: &str, allow: &[f64], gate: &Gate) -> Result<()> {
+    let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+    let source = ZipSource::new(std::io::BufReader::new(file))?;
+    let mut parser = SimulinkParser::new("", source);
+    let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+    let mut options = MagicNumberOptions::default();
+    options.allowed.extend_from_slice(allow);
+    let found = find_magic_numbers(&system, &options);
+    for m in &found {
+        println!(
+            "{} ({}): {} = {} [{}]",
+            m.path,
+            m.block_type,
+            m.parameter,
+            m.expression,
+            m.literals.join(", ")
+        );
+    }
+    if !found.is_empty() {
+        eprintln!("{} parameter(s) with magic numbers", found.len());
+    }
+    let findings: Vec<Finding> = found
+        .into_iter()
+        .map(|m| Finding {
+            block: m.path,
+            severity: Severity::Warning,
+            source: "magic-number".to_string(),
+            message: m.expression,
+        })
+        .collect();
+    enforce(gate, &findings)
+}
+
+fn rate_check(slx_file: &str, gate: &Gate) -> Result<()> {
+    let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+    let source = ZipSource::new(std::io::BufReader::new(file))?;
+    let mut parser = SimulinkParser::new("", source);
+    let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+    let issues = rate_transition_issues(&system);
+    for issue in &issues {
+        println!("{} [{:?}]: {}", issue.path, issue.kind, issue.message);
+    }
+    if !issues.is_empty() {
+        eprintln!("{} rate issue(s)", issues.len());
+    }
+    let findings: Vec<Finding> = issues
+        .into_iter()
+        .map(|issue| Finding {
+            block: issue.path,
+            severity: Severity::Warning,
+            source: "rate".to_string(),
+            message: issue.message,
+        })
+        .collect();
+    enforce(gate, &findings)
+}
+
+fn clones(slx_file: &str, min_blocks: usize) -> Result<()> {
+    let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+    let source = ZipSource::new(std::io::BufReader::new(file))?;
+    let mut parser = SimulinkParser::new("", source);
+    let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+    for group in find_clones(&system, min_blocks) {
+        println!(
+            "{} blocks, similarity {:.2}:",
+            group.block_count, group.similarity
+        );
+        for path in &group.paths {
+            println!("  {}", path);
+        }
+    }
+    Ok(())
+}
+
+fn extract_library(
+    slx_file: &str,
+    library: &str,
+    paths: &[String],
+    output: Option<&str>,
+) -> Result<()> {
+    let lib_path = Utf8PathBuf::from(library);
+    let lib_name = lib_path
+        .file_stem()
+        .with_context(|| format!("No library name in {}", library))?;
+    let mut lib = if lib_path.exists() {
+        SlxArchive::from_file(&lib_path)?
+    } else {
+        new_library(lib_name)?
+    };
+    let mut model = SlxArchive::from_file(slx_file)?;
+    let paths: Vec<&str> = paths.iter().map(String::as_str).collect();
+    let extraction = extract_to_library(&mut model, &paths, &mut lib, lib_name)?;
+    lib.write_to_file(&lib_path)?;
+    model.write_to_file(output.unwrap_or(slx_file))?;
+    eprintln!(
+        "Linked {} subsystem(s) to {}",
+        extraction.replaced.len(),
+        extraction.source_block
+    );
+    Ok(())
+}
+
+fn order_ports(slx_file: &str, by: PortOrderArg, output: Option<&str>) -> Result<()> {
+    let by = match by {
+        PortOrderArg::Position => PortOrder::ByPosition,
+        PortOrderArg::Number => PortOrder::ByNumber,
+    };
+    let mut archive = SlxArchive::from_file(slx_file)?;
+    let changes = order_ports_in(&mut archive, by)?;
+    for c in &changes {
+        let moved = if c.moved { " (moved)" } else { "" };
+        println!(
+            "{}: port {} -> {}{}",
+            c.block, c.old_port, c.new_port, moved
+        );
+    }
+    if changes.is_empty() {
+        eprintln!("All ports are in order");
+        if output.is_none() {
+            return Ok(());
+        }
+    } else {
+        eprintln!("Changed {} port block(s)", changes.len());
+    }
+    archive.write_to_file(output.unwrap_or(slx_file))
+}
+
+fn name_signals(slx_file: &str, output: Option<&str>) -> Result<()> {
+    let mut archive = SlxArchive::from_file(slx_file)?;
+    let named = name_signals_in(&mut archive)?;
+    for n in &named {
+        println!("{}:{} -> {}", n.block, n.port, n.name);
+    }
+    if named.is_empty() {
+        eprintln!("No unnamed signals to name");
+        if output.is_none() {
+            return Ok(());
+        }
+    } else {
+        eprintln!("Named {} signal(s)", named.len());
+    }
+    archive.write_to_file(output.unwrap_or(slx_file))
+}
+
+fn anonymize(input: &str, output: &str, map: Option<&str>) -> Result<()> {
+    let mut archive = SlxArchive::from_file(input)?;
+    let result = anonymize_archive(&mut archive, &AnonymizeOptions::default())?;
+    // The old preview image shows the original names.
+    let thumbnail = archive
+        .entries
+        .iter()
+        .any(|e| e.path == THUMBNAIL_PATH)
+        .then(ThumbnailOptions::default);
+    let options = WriteOptions {
+        thumbnail,
+        strip_nonessential: true,
+    };
+    archive.write_to_file_with_options(output, &options)?;
+    if let Some(map) = map {
+        std::fs::write(map, serde_json::to_string_pretty(&result)?)
+            .with_context(|| format!("Write {}", map))?;
+    }
+    eprintln!(
+        "Renamed {} block(s) and {} signal(s); removed {} annotation(s) and {} callback(s); stripped {} code item(s)",
+        result.renamed_blocks.len(),
+        result.renamed_signals.len(),
+        result.removed_annotations,
+        result.removed_callbacks,
+        result.stripped_code
+    );
+    Ok(())
+}
+
+fn parse(
+    file: &str,
+    lib: &[String],
+    profile: bool,
+    profile_json: Option<&str>,
+    quiet: bool,
+    stable_json: bool,
+    ndjson: bool,
+) -> Result<()> {
+    let path = Utf8PathBuf::from(file);
+    let options = ParserOptions {
+        profile: profile || profile_json.is_some(),
+        ..Default::default()
+    };
+    let mut lib_paths: Vec<Utf8PathBuf> = path
+        .parent()
+        .filter(|p| !p.as_str().is_empty())
+        .map(Utf8Path::to_path_buf)
+        .into_iter()
+        .collect();
+    lib_paths.extend(lib.iter().map(Utf8PathBuf::from));
+
+    let (system, timings) = if matches!(path.extension(), Some("slx") | Some("slxp")) {
+        let start = std::time::Instant::now();
+        let file = std::fs::File::open(&path).with_context(|| format!("Open {}", path))?;
+        let source = ZipSource::new(std::io::BufReader::new(file))?;
+        let opened = start.elapsed();
+        let mut parser = SimulinkParser::with_options("", source, options);
+        if let Some(timings) = parser.profile_mut() {
+            timings.record(ProfilePhase::ZipRead, opened);
+        }
+        parse_and_resolve(parser, "simulink/systems/system_root.xml", &lib_paths)?
+    } else {
+        let parser = SimulinkParser::with_options(".", FsSource, options);
+        parse_and_resolve(parser, path.as_str(), &lib_paths)?
+    };
+
+    if ndjson && !quiet {
+        let out = std::io::BufWriter::new(std::io::stdout().lock());
+        rustylink::ndjson::write_records(&system, out)?;
+    } else if !quiet {
+        println!("{}", model_json(&system, stable_json)?);
+    }
+    if let Some(timings) = timings {
+        if profile {
+            eprint!("{}", timings.summary_table());
+        }
+        if let Some(out) = profile_json {
+            std::fs::write(out, serde_json::to_string_pretty(&timings)?)
+                .with_context(|| format!("Write {}", out))?;
+        }
+    }
+    Ok(())
+}
+
+/// Pretty JSON of a parsed model, optionally in the stable ordering.
+fn model_json(system: &System, stable: bool) -> Result<String> {
+    Ok(if stable {
+        rustylink::stable_json::to_string_pretty(system)?
+    } else {
+        serde_json::to_string_pretty(system)?
+    })
+}
+
+fn stats(files: &[String]) -> Result<()> {
+    use rayon::prelude::*;
+    use rustylink::parser::view::{ModelStats, scan_slx};
+
+    let scanned = files
+        .par_iter()
+        .map(|file| scan_slx(Utf8Path::new(file)).with_context(|| format!("Scan {}", file)))
+        .collect::<Result<Vec<ModelStats>>>()?;
+    let mut total = ModelStats::default();
+    let mut per_file = serde_json::Map::new();
+    for (file, stats) in files.iter().zip(&scanned) {
+        total.merge(stats);
+        per_file.insert(file.clone(), serde_json::to_value(stats)?);
+    }
+    let report = serde_json::json!({ "files": per_file, "total": total });
+    println!("{}", serde_json::to_string_pretty(&report)?);
+    Ok(())
+}
+
+fn hash(files: &[String], full: bool, cosmetic_config: Option<&str>) -> Result<()> {
+    let filter = match cosmetic_config {
+        Some(path) => CosmeticFilter::from_file(Utf8Path::new(path))?,
+        None => CosmeticFilter::default(),
+    };
+    for slx_file in files {
+        let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+        let source = ZipSource::new(std::io::BufReader::new(file))?;
+        let mut parser = SimulinkParser::new("", source);
+        let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+        let hash = if full {
+            system.full_hash()
+        } else {
+            system.structural_hash_with(&filter)
+        };
+        println!("{hash:016x}  {slx_file}");
+    }
+    Ok(())
+}
+
+fn textconv(slx_file: &str, cosmetic: Option<CosmeticFilter>) -> Result<()> {
+    let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+    let source = ZipSource::new(std::io::BufReader::new(file))?;
+    let mut parser = SimulinkParser::new("", source);
+    let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+    let value = cosmetic
+        .unwrap_or_else(CosmeticFilter::none)
+        .to_value(&system)?;
+    println!("{}", serde_json::to_string_pretty(&value)?);
+    Ok(())
+}
+
+fn parse_and_resolve<S: ContentSource>(
+    mut parser: SimulinkParser<S>,
+    root: &str,
+    lib_paths: &[Utf8PathBuf],
+) -> Result<(System, Option<ParseProfile>)> {
+    let mut system = parser
+        .parse_system_file(root)
+        .with_context(|| format!("Failed to parse {}", root))?;
+    parser.resolve_libraries(&mut system, lib_paths)?;
+    Ok((system, parser.profile().cloned()))
+}
+
+fn diff(
+    old: &str,
+    new: &str,
+    html: Option<&str>,
+    cosmetic: Option<CosmeticFilter>,
+    gate: &Gate,
+) -> Result<()> {
+    let load = |slx_file: &str| -> Result<_> {
+        let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+        let source = ZipSource::new(std::io::BufReader::new(file))?;
+        let mut parser = SimulinkParser::new("", source);
+        parser.parse_system_file("simulink/systems/system_root.xml")
+    };
+    let filter = cosmetic.unwrap_or_else(CosmeticFilter::none);
+    let diff = diff_systems_filtered(&load(old)?, &load(new)?, &filter, &CancellationToken::new())?;
+    match html {
+        Some(path) => {
+            std::fs::write(path, to_html(&diff)).with_context(|| format!("Write {}", path))?
+        }
+        None => println!("{}", serde_json::to_string_pretty(&diff)?),
+    }
+    enforce(gate, &diff_findings(&diff))
+}
+
+/// Every change as a warning, with rules `block-added`, `block-removed`,
+/// `block-modified`, `connection-added` and `connection-removed`.
+fn diff_findings(diff: &SystemDiff) -> Vec<Finding> {
+    use rustylink::diff::ChangeKind as Change;
+    let rule = |prefix: &str, kind: Change| -> &'static str {
+        match (prefix, kind) {
+            ("block", Change::Added) => "block-added",
+            ("block", Change::Removed) => "block-removed",
+            ("block", Change::Modified) => "block-modified",
+            (_, Change::Added) => "connection-added",
+            (_, Change::Removed | Change::Modified) => "connection-removed",
+        }
+    };
+    let blocks = diff.blocks.iter().map(|b| Finding {
+        block: b.path.clone(),
+        severity: Severity::Warning,
+        source: rule("block", b.kind).to_string(),
+        message: match b.parameters.len() {
+            0 => b.block_type.clone(),
+            n => format!("{}, {} parameter(s) changed", b.block_type, n),
+        },
+    });
+    let connections = diff.connections.iter().map(|c| Finding {
+        block: c.system.clone(),
+        severity: Severity::Warning,
+        source: rule("connection", c.kind).to_string(),
+        message: format!("{} -> {}", c.src, c.dst),
+    });
+    blocks.chain(connections).collect()
+}
+
+/// Breaking changes are `breaking-change` warnings, the others
+/// `interface-change` information.
+fn interface_diff(old: &str, new: &str, json: bool, gate: &Gate) -> Result<()> {
+    let load = |slx_file: &str| -> Result<_> {
+        let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+        let source = ZipSource::new(std::io::BufReader::new(file))?;
+        let mut parser = SimulinkParser::new("", source);
+        let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+        Ok(ModelInterface::from_system(&system))
+    };
+    let compat = check_compat(&load(old)?, &load(new)?);
+    let mut findings = Vec::new();
+    for c in &compat.changes {
+        let direction = match c.direction {
+            PortDirection::Input => "input",
+            PortDirection::Output => "output",
+        };
+        let change = match &c.change {
+            ChangeKind::Added { port, optional } => {
+                let kind = if *optional { "optional" } else { "required" };
+                format!("added as {} port {}", kind, port)
+            }
+            ChangeKind::Removed { port } => format!("removed (was port {})", port),
+            ChangeKind::Renumbered { old, new } => {
+                format!("moved from port {} to {}", old, new)
+            }
+            ChangeKind::TypeChange { old, new } => format!(
+                "type {} -> {}",
+                old.as_deref().unwrap_or("inherited"),
+                new.as_deref().unwrap_or("inherited")
+            ),
+            ChangeKind::WidthChange { old, new } => {
+                let dims = |d: &Option<Vec<u32>>| match d {
+                    Some(d) => format!("{:?}", d),
+                    None => "inherited".to_string(),
+                };
+                format!("dimensions {} -> {}", dims(old), dims(new))
+            }
+        };
+        if !json {
+            let tag = if c.breaking { "BREAKING" } else { "ok" };
+            println!("{:8} {} {}: {}", tag, direction, c.name, change);
+        }
+        findings.push(Finding {
+            block: format!("{} {}", direction, c.name),
+            severity: if c.breaking {
+                Severity::Warning
+            } else {
+                Severity::Information
+            },
+            source: if c.breaking {
+                "breaking-change"
+            } else {
+                "interface-change"
+            }
+            .to_string(),
+            message: change,
+        });
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&compat)?);
+    }
+    enforce(gate, &findings)
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
-    let path = Utf8PathBuf::from(&cli.simulink_file);
+fn watch(slx_file: &str, on_change: &str) -> Result<()> {
+    let ops = parse_operations(on_change)?;
+    eprintln!("Watching {} (Ctrl+C to stop)", slx_file);
+    rustylink::watch::watch(Utf8Path::new(slx_file), &ops, |line| println!("{line}"))
+}
+
+#[cfg(feature = "server")]
+fn serve(http: &str) -> Result<()> {
+    eprintln!("Listening on http://{}", http);
+    rustylink::server::run(http)
+}
+
+#[cfg(not(feature = "server"))]
+fn serve(_http: &str) -> Result<()> {
+    Err(UsageError(
+        "HTTP server support is not built in; rebuild with --features server".to_string(),
+    )
+    .into())
+}
+
+#[cfg(feature = "tui")]
+fn tui(slx_file: &str) -> Result<()> {
+    let archive = SlxArchive::from_file(slx_file)?;
+    let root = archive
+        .root_system()
+        .with_context(|| format!("No root system in {}", slx_file))?;
+    let name = Utf8Path::new(slx_file).file_stem().unwrap_or(slx_file);
+    rustylink::tui::run(root, name)
+}
+
+#[cfg(not(feature = "tui"))]
+fn tui(_slx_file: &str) -> Result<()> {
+    Err(UsageError(
+        "Terminal browser support is not built in; rebuild with --features tui".to_string(),
+    )
+    .into())
+}
+
+fn stimulus(
+    slx_file: &str,
+    csv: Option<&str>,
+    json: Option<&str>,
+    harness: Option<&str>,
+) -> Result<()> {
+    let model_name = Utf8PathBuf::from(slx_file)
+        .file_stem()
+        .map(str::to_string)
+        .with_context(|| format!("No model name in {}", slx_file))?;
+    let archive = SlxArchive::from_file(slx_file)?;
+    let root = archive
+        .root_system()
+        .with_context(|| format!("No root system in {}", slx_file))?;
+    let inports = root_inports(root);
+    if let Some(path) = csv {
+        std::fs::write(path, stimulus_csv(&inports)).with_context(|| format!("Write {}", path))?;
+    }
+    if let Some(path) = json {
+        let text = serde_json::to_string_pretty(&stimulus_json(&inports))?;
+        std::fs::write(path, text).with_context(|| format!("Write {}", path))?;
+    }
+    if let Some(path) = harness {
+        harness_model(&model_name, &inports, &root_outports(root))?.write_to_file(path)?;
+    }
+    if csv.is_none() && json.is_none() && harness.is_none() {
+        print!("{}", stimulus_csv(&inports));
+    }
+    Ok(())
+}
+
+fn parameter_overrides(slx_file: &str, csv: bool, cosmetic: Option<CosmeticFilter>) -> Result<()> {
+    let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+    let mut source = ZipSource::new(std::io::BufReader::new(file))?;
+    let defaults = BlockParameterDefaults::from_source(&mut source)?;
+    let mut parser = SimulinkParser::new("", source);
+    let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+    let options = OverrideOptions { cosmetic };
+    let report = rustylink::report::parameter_overrides(&system, &defaults, options);
+    if csv {
+        print!("{}", overrides_to_csv(&report));
+        return Ok(());
+    }
+    for group in &report {
+        let path = if group.path.is_empty() {
+            "<root>"
+        } else {
+            group.path.as_str()
+        };
+        println!("{}", path);
+        for o in &group.overrides {
+            let default = o.default.as_deref().unwrap_or("<none>");
+            println!(
+                "  {} ({}): {} = {} (default {})",
+                o.block, o.block_type, o.parameter, o.value, default
+            );
+        }
+    }
+    Ok(())
+}
+
+fn callbacks(slx_file: &str, json: bool) -> Result<()> {
+    let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+    let mut source = ZipSource::new(std::io::BufReader::new(file))?;
+    let model = model_callbacks(&mut source)?;
+    let mut parser = SimulinkParser::new("", source);
+    let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+    let blocks = system.all_callbacks();
+    if json {
+        let report = serde_json::json!({ "model": model.entries, "blocks": blocks });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    let sites = model
+        .iter()
+        .map(|(name, code)| ("<model>", name, code))
+        .chain(
+            blocks
+                .iter()
+                .map(|s| (s.path.as_str(), s.name.as_str(), s.code.as_str())),
+        );
+    for (path, name, code) in sites {
+        println!("{} {}:", path, name);
+        for line in code.lines() {
+            println!("    {}", line);
+        }
+    }
+    Ok(())
+}
+
+fn sfunctions(slx_file: &str, path: &[String], json: bool) -> Result<()> {
+    let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+    let source = ZipSource::new(std::io::BufReader::new(file))?;
+    let mut parser = SimulinkParser::new("", source);
+    let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+    let deps = sfunction_dependencies(&system);
+    let dirs: Vec<Utf8PathBuf> = path.iter().map(Utf8PathBuf::from).collect();
+    let platform = MexPlatform::current();
+    let found = |dep: &SFunctionDependency| platform.and_then(|p| dep.find(&dirs, p));
+    if json {
+        let mut report = Vec::new();
+        for dep in &deps {
+            let files: std::collections::BTreeMap<&str, String> = MexPlatform::ALL
+                .iter()
+                .map(|p| (p.name(), dep.expected_file(*p)))
+                .collect();
+            let mut value = serde_json::to_value(dep)?;
+            value["expectedFiles"] = serde_json::json!(files);
+            if !dirs.is_empty() {
+                value["foundAt"] = serde_json::json!(found(dep).map(|p| p.to_string()));
+            }
+            report.push(value);
+        }
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        for dep in &deps {
+            let files: Vec<String> = if dep.matlab {
+                vec![dep.expected_file(MexPlatform::Win64)]
+            } else {
+                MexPlatform::ALL
+                    .iter()
+                    .map(|p| dep.expected_file(*p))
+                    .collect()
+            };
+            println!("{}: {}", dep.function_name, files.join(", "));
+            if !dirs.is_empty() {
+                match found(dep) {
+                    Some(at) => println!("  found at {}", at),
+                    None => println!("  MISSING"),
+                }
+            }
+            if !dep.modules.is_empty() {
+                println!("  modules: {}", dep.modules.join(", "));
+            }
+            for block in &dep.blocks {
+                println!("  used by {}", block);
+            }
+        }
+    }
+    if !dirs.is_empty() {
+        let platform = platform.context("MATLAB does not support this platform")?;
+        let missing = deps.iter().filter(|d| found(d).is_none()).count();
+        if missing > 0 {
+            anyhow::bail!(
+                "{} S-function file(s) missing for {}",
+                missing,
+                platform.name()
+            );
+        }
+    }
+    Ok(())
+}
+
+fn export(
+    slx_file: &str,
+    logging_spec: Option<&str>,
+    parquet: Option<&str>,
+    sqlite: Option<&str>,
+) -> Result<()> {
+    let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+    let source = ZipSource::new(std::io::BufReader::new(file))?;
+    let mut parser = SimulinkParser::new("", source);
+    let system = parser.parse_system_file("simulink/systems/system_root.xml")?;
+    let model = Utf8Path::new(slx_file).file_stem().unwrap_or(slx_file);
+    if let Some(out) = logging_spec {
+        let spec = rustylink::logging_spec::logging_spec(&system, model);
+        if out == "-" {
+            println!("{}", spec.to_json()?);
+        } else {
+            let data = if out.to_ascii_lowercase().ends_with(".mat") {
+                spec.to_mat()
+            } else {
+                spec.to_json()?.into_bytes()
+            };
+            std::fs::write(out, data).with_context(|| format!("Write {}", out))?;
+            eprintln!("Wrote {} logged signal(s) to {}", spec.signals.len(), out);
+        }
+    }
+    if parquet.is_some() || sqlite.is_some() {
+        let inventory = rustylink::inventory::Inventory::from_system(model, &system);
+        if let Some(dir) = parquet {
+            write_parquet(&inventory, Utf8Path::new(dir))?;
+        }
+        if let Some(db) = sqlite {
+            write_sqlite(&inventory, Utf8Path::new(db))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "arrow")]
+fn write_parquet(inventory: &rustylink::inventory::Inventory, dir: &Utf8Path) -> Result<()> {
+    for path in rustylink::inventory::arrow::write_parquet(inventory, dir)? {
+        eprintln!("Wrote {}", path);
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "arrow"))]
+fn write_parquet(_inventory: &rustylink::inventory::Inventory, _dir: &Utf8Path) -> Result<()> {
+    Err(
+        UsageError("Parquet export is not built in; rebuild with --features arrow".to_string())
+            .into(),
+    )
+}
+
+#[cfg(feature = "sqlite")]
+fn write_sqlite(inventory: &rustylink::inventory::Inventory, db: &Utf8Path) -> Result<()> {
+    rustylink::inventory::sqlite::write_file(inventory, db)?;
+    eprintln!("Wrote {} block(s) to {}", inventory.blocks.len(), db);
+    Ok(())
+}
+
+#[cfg(not(feature = "sqlite"))]
+fn write_sqlite(_inventory: &rustylink::inventory::Inventory, _db: &Utf8Path) -> Result<()> {
+    Err(
+        UsageError("SQLite export is not built in; rebuild with --features sqlite".to_string())
+            .into(),
+    )
+}
+
+#[cfg(feature = "egui")]
+fn catalog_export(output: Option<&str>, json: bool) -> Result<()> {
+    use rustylink::editor::block_catalog::{CatalogFile, builtin_catalog};
+
+    let file = CatalogFile {
+        blocks: builtin_catalog(),
+    };
+    let text = if json || output.is_some_and(|o| o.ends_with(".json")) {
+        serde_json::to_string_pretty(&file)? + "\n"
+    } else {
+        toml::to_string(&file)?
+    };
+    match output {
+        Some(path) => std::fs::write(path, text).with_context(|| format!("Write {}", path))?,
+        None => print!("{text}"),
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "egui"))]
+fn catalog_export(_output: Option<&str>, _json: bool) -> Result<()> {
+    Err(
+        UsageError("The block catalog is not built in; rebuild with --features egui".to_string())
+            .into(),
+    )
+}
+
+fn rewrite(input: &str, output: &str, strip_nonessential: bool) -> Result<()> {
+    let archive = SlxArchive::from_file(input)?;
+    let nonessential = archive.nonessential_entry_paths();
+    if strip_nonessential {
+        for path in &nonessential {
+            eprintln!("Stripping {}", path);
+        }
+    } else if !nonessential.is_empty() {
+        eprintln!(
+            "Preserving {} non-essential member(s); use --strip-nonessential to drop them",
+            nonessential.len()
+        );
+    }
+    let options = WriteOptions {
+        strip_nonessential,
+        ..Default::default()
+    };
+    archive.write_to_file_with_options(output, &options)
+}
+
+fn extract_assets(slx_file: &str, output: &str) -> Result<()> {
+    let file = std::fs::File::open(slx_file).with_context(|| format!("Open {}", slx_file))?;
+    let mut source = ZipSource::new(std::io::BufReader::new(file))?;
+    let assets = source.extract_assets()?;
+    let out_dir = Utf8PathBuf::from(output);
+    for asset in assets.iter() {
+        // Only keep normal path components so archive entries cannot escape `out_dir`.
+        let rel: Utf8PathBuf = camino::Utf8Path::new(&asset.path)
+            .components()
+            .filter(|c| matches!(c, camino::Utf8Component::Normal(_)))
+            .collect();
+        let dest = out_dir.join(rel);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent).with_context(|| format!("Create {}", parent))?;
+        }
+        std::fs::write(&dest, &asset.data).with_context(|| format!("Write {}", dest))?;
+        println!("{} ({}, {} bytes)", dest, asset.mime_type, asset.data.len());
+    }
+    eprintln!("Extracted {} asset(s) to {}", assets.len(), out_dir);
+    Ok(())
+}
+
+/// Print what is still readable from a protected model to stderr.
+fn report_protected(path: &str, protected: &ModelProtectedError) {
+    eprintln!("{}: {}", path, protected);
+    for part in &protected.encrypted_parts {
+        eprintln!("  encrypted: {}", part);
+    }
+    if let Some(gi) = &protected.interface {
+        for lib in gi.library_names() {
+            eprintln!("  references library: {}", lib);
+        }
+        if let Some(solver) = &gi.solver_name {
+            eprintln!("  solver: {:?}", solver);
+        }
+    }
+}
+
+/// Print parse progress on one stderr line until the parser is dropped.
+fn print_progress(
+    updates: std::sync::mpsc::Receiver<ParseProgress>,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut printed = false;
+        for update in updates {
+            eprint!("\r\x1b[K{update}");
+            printed = true;
+        }
+        if printed {
+            eprintln!();
+        }
+    })
+}
+
+fn main() -> ExitCode {
+    let cli = match Cli::try_parse() {
+        Ok(cli) => cli,
+        Err(e) => {
+            let _ = e.print();
+            // --help and --version end up here too
+            return if e.use_stderr() {
+                ExitCode::from(EXIT_USAGE)
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
+    let file = cli.simulink_file.clone().unwrap_or_default();
+    let Err(e) = run(cli) else {
+        return ExitCode::SUCCESS;
+    };
+    if let Some(protected) = e.downcast_ref::<ModelProtectedError>() {
+        report_protected(&file, protected);
+        return ExitCode::from(EXIT_PARSE_ERROR);
+    }
+    if let Some(failed) = e.downcast_ref::<GateFailed>() {
+        eprintln!("Failed: {}", failed);
+        return ExitCode::from(EXIT_FINDINGS);
+    }
+    eprintln!("Error: {:?}", e);
+    if e.is::<UsageError>() {
+        ExitCode::from(EXIT_USAGE)
+    } else {
+        ExitCode::from(EXIT_PARSE_ERROR)
+    }
+}
+
+fn run(cli: Cli) -> Result<()> {
+    if cli.help_json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&command_json(&Cli::command()))?
+        );
+        return Ok(());
+    }
+    if let Some(command) = &cli.command {
+        return match command {
+            Command::ExtractAssets { slx_file, output } => extract_assets(slx_file, output),
+            Command::Rewrite {
+                input,
+                output,
+                strip_nonessential,
+            } => rewrite(input, output, *strip_nonessential),
+            Command::Callbacks { slx_file, json } => callbacks(slx_file, *json),
+            Command::Sfunctions {
+                slx_file,
+                path,
+                json,
+            } => sfunctions(slx_file, path, *json),
+            Command::Export {
+                slx_file,
+                logging_spec,
+                parquet,
+                sqlite,
+            } => export(
+                slx_file,
+                logging_spec.as_deref(),
+                parquet.as_deref(),
+                sqlite.as_deref(),
+            ),
+            Command::ParameterOverrides {
+                slx_file,
+                csv,
+                cosmetic,
+            } => parameter_overrides(slx_file, *csv, cosmetic.filter()?),
+            Command::Doctor {
+                slx_file,
+                path,
+                json,
+            } => doctor(slx_file, path, *json),
+            Command::Lint {
+                slx_file,
+                format,
+                config,
+                ruleset,
+                gate,
+            } => lint_model(
+                slx_file,
+                *format,
+                config.as_deref(),
+                ruleset,
+                &gate.gate(None),
+            ),
+            Command::MagicNumbers {
+                slx_file,
+                allow,
+                gate,
+            } => magic_numbers(slx_file, allow, &gate.gate(Some(0))),
+            Command::RateCheck { slx_file, gate } => rate_check(slx_file, &gate.gate(Some(0))),
+            Command::Clones {
+                slx_file,
+                min_blocks,
+            } => clones(slx_file, *min_blocks),
+            Command::ExtractToLibrary {
+                slx_file,
+                library,
+                paths,
+                output,
+            } => extract_library(slx_file, library, paths, output.as_deref()),
+            Command::OrderPorts {
+                slx_file,
+                by,
+                output,
+            } => order_ports(slx_file, *by, output.as_deref()),
+            Command::NameSignals { slx_file, output } => name_signals(slx_file, output.as_deref()),
+            Command::Anonymize { input, output, map } => anonymize(input, output, map.as_deref()),
+            Command::Stimulus {
+                slx_file,
+                csv,
+                json,
+                harness,
+            } => stimulus(
+                slx_file,
+                csv.as_deref(),
+                json.as_deref(),
+                harness.as_deref(),
+            ),
+            Command::Parse {
+                file,
+                lib,
+                profile,
+                profile_json,
+                quiet,
+                stable_json,
+                ndjson,
+            } => parse(
+                file,
+                lib,
+                *profile,
+                profile_json.as_deref(),
+                *quiet,
+                *stable_json,
+                *ndjson,
+            ),
+            Command::Stats { files } => stats(files),
+            Command::Hash {
+                files,
+                full,
+                cosmetic_config,
+            } => hash(files, *full, cosmetic_config.as_deref()),
+            Command::Textconv { slx_file, cosmetic } => textconv(slx_file, cosmetic.filter()?),
+            Command::Diff {
+                old,
+                new,
+                html,
+                cosmetic,
+                gate,
+            } => diff(
+                old,
+                new,
+                html.as_deref(),
+                cosmetic.filter()?,
+                &gate.gate(None),
+            ),
+            Command::InterfaceDiff {
+                old,
+                new,
+                fail_on_breaking,
+                json,
+                gate,
+            } => {
+                let mut gate = gate.gate(None);
+                if *fail_on_breaking {
+                    gate.fail_on.push("breaking-change".to_string());
+                }
+                interface_diff(old, new, *json, &gate)
+            }
+            Command::Watch {
+                slx_file,
+                on_change,
+            } => watch(slx_file, on_change),
+            Command::Serve { http, stdio } => match http {
+                Some(http) if !stdio => serve(http),
+                _ => rustylink::service::run(std::io::stdin().lock(), std::io::stdout().lock()),
+            },
+            Command::Tui { slx_file } => tui(slx_file),
+            Command::Completions { shell } => {
+                clap_complete::generate(
+                    *shell,
+                    &mut Cli::command(),
+                    "rustylink",
+                    &mut std::io::stdout(),
+                );
+                Ok(())
+            }
+            Command::Catalog {
+                command: CatalogCommand::Export { output, json },
+            } => catalog_export(output.as_deref(), *json),
+        };
+    }
+    let simulink_file = cli.simulink_file.as_deref().unwrap_or_default();
+    let path = Utf8PathBuf::from(simulink_file);
     let root_dir = Utf8PathBuf::from(".");
 
-    if cli.json {
+    if cli.json || cli.stable_json {
         // Print the complete JSON tree
-        let system = if path.extension() == Some("slx") {
+        let mut progress = None;
+        let system = if matches!(path.extension(), Some("slx") | Some("slxp")) {
             let file = std::fs::File::open(&path).with_context(|| format!("Open {}", path))?;
             let reader = std::io::BufReader::new(file);
-            let mut parser = SimulinkParser::new("", ZipSource::new(reader)?);
+            let mut source = ZipSource::new(reader)?;
+            if let Some(protected) = source.protection() {
+                if protected.kind == ProtectionKind::ProtectedModel {
+                    // Only the interface is readable; print it instead of the
+                    // tree and fail as main does for protected models.
+                    println!("{}", serde_json::to_string_pretty(&protected)?);
+                    return Err(protected.into());
+                }
+                eprintln!("Warning: {}: {}", path, protected);
+            }
+            let mut parser = SimulinkParser::new("", source);
+            if cli.progress {
+                progress = Some(print_progress(parser.progress_channel()));
+            }
             let root = Utf8PathBuf::from("simulink/systems/system_root.xml");
             parser.parse_system_file(&root)?
         } else {
             let mut parser = SimulinkParser::new(&root_dir, FsSource);
+            if cli.progress {
+                progress = Some(print_progress(parser.progress_channel()));
+            }
             parser
                 .parse_system_file(&path)
                 .with_context(|| format!("Failed to parse {}", path))?
         };
-        let json = serde_json::to_string_pretty(&system)?;
-        println!("{}", json);
+        // The channel closed with the parser, so the printer is finishing.
+        if let Some(progress) = progress {
+            let _ = progress.join();
+        }
+        println!("{}", model_json(&system, cli.stable_json)?);
     } else {
         // Report unknown tags and block types
         let mut unknown_tags = std::collections::BTreeSet::new();
@@ -223,3 +1874,51 @@ fn main() -> Result<()> {
 }
 
 // No longer needed: resolve_default_root_xml and path_exists
+
+/// `--help-json` description of `cmd` and its subcommands. Keys are only
+/// ever added, so wrappers can rely on them across versions.
+fn command_json(cmd: &clap::Command) -> serde_json::Value {
+    let args: Vec<serde_json::Value> = cmd
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set())
+        .map(|arg| {
+            // Flags report no value names or the implicit `true`/`false`
+            let takes_value = arg.get_action().takes_values();
+            let value_names: Vec<&str> = match arg.get_value_names() {
+                Some(names) if takes_value => names.iter().map(|v| v.as_str()).collect(),
+                _ => Vec::new(),
+            };
+            let possible_values: Vec<String> = if takes_value {
+                arg.get_possible_values()
+                    .iter()
+                    .map(|v| v.get_name().to_string())
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            serde_json::json!({
+                "id": arg.get_id().as_str(),
+                "long": arg.get_long(),
+                "short": arg.get_short().map(String::from),
+                "value_names": value_names,
+                "help": arg.get_help().map(|h| h.to_string()),
+                "positional": arg.is_positional(),
+                "required": arg.is_required_set(),
+                "takes_value": takes_value,
+                "multiple": matches!(arg.get_action(), clap::ArgAction::Append),
+                "default_values": arg.get_default_values()
+                    .iter()
+                    .map(|v| v.to_string_lossy())
+                    .collect::<Vec<_>>(),
+                "possible_values": possible_values,
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "name": cmd.get_name(),
+        "version": cmd.get_version(),
+        "about": cmd.get_about().map(|a| a.to_string()),
+        "args": args,
+        "subcommands": cmd.get_subcommands().map(command_json).collect::<Vec<_>>(),
+    })
+}
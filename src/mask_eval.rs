@@ -7,6 +7,47 @@
 //! We map popup parameter to an index (1-based). We then select the corresponding element from var cell array and return it.
 //! If anything fails, we return None.
 use crate::model::{Block, MaskParamType};
+use crate::workspace::ModelWorkspace;
+
+/// Resolve a numeric block parameter (mask parameter or block property named
+/// `name`), looking up workspace variables the value refers to.
+///
+/// Returns `None` if the parameter is missing or cannot be reduced to a number.
+pub fn resolve_numeric_parameter(
+    block: &Block,
+    name: &str,
+    workspace: &ModelWorkspace,
+) -> Option<f64> {
+    workspace.resolve_numeric(parameter_value(block, name)?)
+}
+
+/// Fill [`Block::workspace_values`] with the mask parameters and block
+/// properties of `block` that refer to variables of `workspace`.
+pub fn resolve_workspace_values(block: &mut Block, workspace: &ModelWorkspace) {
+    let mask_params = block.mask.iter().flat_map(|m| &m.parameters);
+    let resolved: Vec<(String, f64)> = (mask_params.map(|p| p.name.as_str()))
+        .chain(block.properties.keys().map(String::as_str))
+        .filter_map(|name| {
+            let value = parameter_value(block, name)?;
+            // Plain numbers need no workspace.
+            if value.trim().parse::<f64>().is_ok() {
+                return None;
+            }
+            Some((name.to_string(), workspace.resolve_numeric(value)?))
+        })
+        .collect();
+    block.workspace_values.extend(resolved);
+}
+
+/// The value of mask parameter `name`, or else of block property `name`.
+fn parameter_value<'a>(block: &'a Block, name: &str) -> Option<&'a str> {
+    block
+        .mask
+        .as_ref()
+        .and_then(|m| m.parameters.iter().find(|p| p.name == name))
+        .and_then(|p| p.value.as_deref())
+        .or_else(|| block.properties.get(name).map(|s| s.as_str()))
+}
 
 pub fn evaluate_mask_display(block: &mut Block) {
     let Some(mask) = block.mask.as_ref() else {
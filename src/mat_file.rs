@@ -0,0 +1,539 @@
+//! Minimal MAT-file (Level 5 / v7) reader and writer.
+//!
+//! Model workspace data and signal initial values are sometimes stored as
+//! embedded MAT files inside SLX archives. This reader understands the subset
+//! needed for numeric parameter resolution:
+//!
+//! - numeric and logical arrays of any element type (converted to `f64`)
+//! - character arrays
+//! - structs (including struct arrays) and cell arrays
+//! - zlib-compressed variables (`miCOMPRESSED`, the v7 default)
+//!
+//! Sparse matrices, objects, and function handles are reported as
+//! [`MatValue::Unsupported`]. HDF5-based v7.3 files are rejected.
+//!
+//! MAT files come from untrusted archives, so [`read_mat_with_limits`]
+//! bounds the nesting of cells and structs by [`ParseLimits::max_depth`]
+//! and each inflated variable by [`ParseLimits::max_file_size`], and
+//! rejects element counts the data could not hold.
+
+use crate::parser::{LimitExceeded, LimitKind, ParseLimits};
+use anyhow::{Context, Result, anyhow, bail};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+// Data element types (`mi*`).
+const MI_INT8: u32 = 1;
+const MI_UINT8: u32 = 2;
+const MI_INT16: u32 = 3;
+const MI_UINT16: u32 = 4;
+const MI_INT32: u32 = 5;
+const MI_UINT32: u32 = 6;
+const MI_SINGLE: u32 = 7;
+const MI_DOUBLE: u32 = 9;
+const MI_INT64: u32 = 12;
+const MI_UINT64: u32 = 13;
+const MI_MATRIX: u32 = 14;
+const MI_COMPRESSED: u32 = 15;
+const MI_UTF8: u32 = 16;
+const MI_UTF16: u32 = 17;
+const MI_UTF32: u32 = 18;
+
+// Array classes (`mx*_CLASS`).
+const MX_CELL: u32 = 1;
+const MX_STRUCT: u32 = 2;
+const MX_CHAR: u32 = 4;
+const MX_DOUBLE: u32 = 6;
+const MX_UINT8: u32 = 9;
+
+const FLAG_COMPLEX: u32 = 0x0800;
+const FLAG_LOGICAL: u32 = 0x0200;
+
+/// A value read from a MAT file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MatValue {
+    /// Numeric or logical array. `real` (and `imag` for complex data) hold the
+    /// elements in MATLAB's column-major order.
+    Numeric {
+        dims: Vec<usize>,
+        real: Vec<f64>,
+        imag: Option<Vec<f64>>,
+        logical: bool,
+    },
+    /// Character array; multi-row arrays are joined with `\n`.
+    Char(String),
+    /// Struct or struct array; one field map per element.
+    Struct {
+        dims: Vec<usize>,
+        elements: Vec<IndexMap<String, MatValue>>,
+    },
+    /// Cell array in column-major order.
+    Cell {
+        dims: Vec<usize>,
+        items: Vec<MatValue>,
+    },
+    /// An array class this reader does not decode (value is the class id).
+    Unsupported(u32),
+}
+
+impl MatValue {
+    /// The value as a real scalar, if it is a 1-element numeric array.
+    pub fn as_scalar(&self) -> Option<f64> {
+        match self {
+            MatValue::Numeric { real, .. } if real.len() == 1 => Some(real[0]),
+            _ => None,
+        }
+    }
+
+    /// The value as a string, if it is a character array.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            MatValue::Char(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// Look up a field of a scalar struct.
+    pub fn field(&self, name: &str) -> Option<&MatValue> {
+        match self {
+            MatValue::Struct { elements, .. } if elements.len() == 1 => elements[0].get(name),
+            _ => None,
+        }
+    }
+
+    /// Format the value as a MATLAB expression (e.g. `[1 2;3 4]` or `'abc'`),
+    /// suitable for display in parameter dialogs or mask evaluation.
+    pub fn to_matlab_literal(&self) -> Option<String> {
+        match self {
+            MatValue::Numeric { dims, real, .. } => {
+                if real.len() == 1 {
+                    return Some(format_number(real[0]));
+                }
+                let rows = dims.first().copied().unwrap_or(1).max(1);
+                let cols = real.len() / rows;
+                let body: Vec<String> = (0..rows)
+                    .map(|r| {
+                        (0..cols)
+                            .map(|c| format_number(real[c * rows + r]))
+                            .collect::<Vec<_>>()
+                            .join(" ")
+                    })
+                    .collect();
+                Some(format!("[{}]", body.join(";")))
+            }
+            MatValue::Char(s) => Some(format!("'{}'", s.replace('\'', "''"))),
+            _ => None,
+        }
+    }
+}
+
+fn format_number(v: f64) -> String {
+    if v.fract() == 0.0 && v.abs() < 1e15 {
+        format!("{}", v as i64)
+    } else {
+        format!("{}", v)
+    }
+}
+
+/// Read all variables from a Level 5 MAT file, in file order, within the
+/// default [`ParseLimits`].
+pub fn read_mat(data: &[u8]) -> Result<IndexMap<String, MatValue>> {
+    read_mat_with_limits(data, &ParseLimits::default())
+}
+
+/// [`read_mat`] within `limits`.
+pub fn read_mat_with_limits(
+    data: &[u8],
+    limits: &ParseLimits,
+) -> Result<IndexMap<String, MatValue>> {
+    if data.len() < 128 {
+        bail!("MAT file too short ({} bytes)", data.len());
+    }
+    let big_endian = match &data[126..128] {
+        b"IM" => false,
+        b"MI" => true,
+        _ => bail!("Not a Level 5 MAT file (missing endian indicator)"),
+    };
+    if data.starts_with(b"MATLAB 7.3") {
+        bail!("HDF5-based MAT v7.3 files are not supported");
+    }
+    let mut vars = IndexMap::new();
+    let mut r = Reader {
+        data: &data[128..],
+        pos: 0,
+        big_endian,
+        depth: 0,
+        limits,
+    };
+    while r.remaining() >= 8 {
+        let (name, value) = r.read_variable()?;
+        if let Some(name) = name {
+            vars.insert(name, value);
+        }
+    }
+    Ok(vars)
+}
+
+/// Returns `true` if `data` starts with a Level 5 MAT file header.
+pub fn is_mat_file(data: &[u8]) -> bool {
+    data.len() >= 128 && data.starts_with(b"MATLAB") && matches!(&data[126..128], b"IM" | b"MI")
+}
+
+/// Write `vars` as an uncompressed Level 5 MAT file. Logical arrays are
+/// stored as `uint8`, all other numeric arrays as `double`;
+/// [`MatValue::Unsupported`] values become empty arrays.
+pub(crate) fn write_mat(vars: &IndexMap<String, MatValue>) -> Vec<u8> {
+    let mut out = b"MATLAB 5.0 MAT-file, written by rustylink".to_vec();
+    out.resize(116, b' ');
+    out.extend_from_slice(&[0; 8]);
+    out.extend_from_slice(&0x0100u16.to_le_bytes());
+    out.extend_from_slice(b"IM");
+    for (name, value) in vars {
+        write_matrix(&mut out, name, value);
+    }
+    out
+}
+
+fn write_element(out: &mut Vec<u8>, ty: u32, data: &[u8]) {
+    out.extend_from_slice(&ty.to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    out.resize(out.len() + (8 - data.len() % 8) % 8, 0);
+}
+
+fn write_matrix(out: &mut Vec<u8>, name: &str, value: &MatValue) {
+    let mut body = Vec::new();
+    let header = |body: &mut Vec<u8>, flags: u32, dims: &[usize]| {
+        let flags: Vec<u8> = [flags, 0].iter().flat_map(|v| v.to_le_bytes()).collect();
+        write_element(body, MI_UINT32, &flags);
+        let dims: Vec<u8> = dims
+            .iter()
+            .flat_map(|&d| (d as i32).to_le_bytes())
+            .collect();
+        write_element(body, MI_INT32, &dims);
+        write_element(body, MI_INT8, name.as_bytes());
+    };
+    let doubles =
+        |values: &[f64]| -> Vec<u8> { values.iter().flat_map(|v| v.to_le_bytes()).collect() };
+    match value {
+        MatValue::Numeric {
+            dims,
+            real,
+            imag,
+            logical: true,
+        } if imag.is_none() => {
+            header(&mut body, MX_UINT8 | FLAG_LOGICAL, dims);
+            let bytes: Vec<u8> = real.iter().map(|&v| u8::from(v != 0.0)).collect();
+            write_element(&mut body, MI_UINT8, &bytes);
+        }
+        MatValue::Numeric {
+            dims, real, imag, ..
+        } => {
+            let complex = if imag.is_some() { FLAG_COMPLEX } else { 0 };
+            header(&mut body, MX_DOUBLE | complex, dims);
+            write_element(&mut body, MI_DOUBLE, &doubles(real));
+            if let Some(imag) = imag {
+                write_element(&mut body, MI_DOUBLE, &doubles(imag));
+            }
+        }
+        MatValue::Char(text) => {
+            let units: Vec<u16> = text.encode_utf16().collect();
+            let dims = if units.is_empty() {
+                [0, 0]
+            } else {
+                [1, units.len()]
+            };
+            header(&mut body, MX_CHAR, &dims);
+            let bytes: Vec<u8> = units.iter().flat_map(|u| u.to_le_bytes()).collect();
+            write_element(&mut body, MI_UINT16, &bytes);
+        }
+        MatValue::Struct { dims, elements } => {
+            header(&mut body, MX_STRUCT, dims);
+            let fields: Vec<&String> = elements.first().map_or(Vec::new(), |e| e.keys().collect());
+            let field_len = fields.iter().map(|f| f.len() + 1).max().unwrap_or(1);
+            write_element(&mut body, MI_INT32, &(field_len as i32).to_le_bytes());
+            let mut names = vec![0u8; field_len * fields.len()];
+            for (i, field) in fields.iter().enumerate() {
+                names[i * field_len..i * field_len + field.len()].copy_from_slice(field.as_bytes());
+            }
+            write_element(&mut body, MI_INT8, &names);
+            let empty = MatValue::Unsupported(0);
+            for element in elements {
+                for field in &fields {
+                    write_matrix(&mut body, "", element.get(*field).unwrap_or(&empty));
+                }
+            }
+        }
+        MatValue::Cell { dims, items } => {
+            header(&mut body, MX_CELL, dims);
+            for item in items {
+                write_matrix(&mut body, "", item);
+            }
+        }
+        MatValue::Unsupported(_) => {
+            header(&mut body, MX_DOUBLE, &[0, 0]);
+            write_element(&mut body, MI_DOUBLE, &[]);
+        }
+    }
+    write_element(out, MI_MATRIX, &body);
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+    /// Variables this one is nested in.
+    depth: usize,
+    limits: &'a ParseLimits,
+}
+
+impl<'a> Reader<'a> {
+    fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    fn u32_at(&self, at: usize) -> Result<u32> {
+        let b: [u8; 4] = self
+            .data
+            .get(at..at + 4)
+            .ok_or_else(|| anyhow!("Truncated MAT data element"))?
+            .try_into()?;
+        Ok(if self.big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        })
+    }
+
+    /// Read the next data element, returning its type and payload.
+    fn read_element(&mut self) -> Result<(u32, &'a [u8])> {
+        let first = self.u32_at(self.pos)?;
+        if first >> 16 != 0 {
+            // Small data element: type and size packed into one word.
+            let ty = first & 0xffff;
+            let n = (first >> 16) as usize;
+            let start = self.pos + 4;
+            let payload = self
+                .data
+                .get(start..start + n)
+                .ok_or_else(|| anyhow!("Truncated MAT small data element"))?;
+            self.pos += 8;
+            return Ok((ty, payload));
+        }
+        let n = self.u32_at(self.pos + 4)? as usize;
+        let start = self.pos + 8;
+        let payload = self
+            .data
+            .get(start..start + n)
+            .ok_or_else(|| anyhow!("Truncated MAT data element (type {})", first))?;
+        // Compressed elements are not padded to an 8-byte boundary.
+        let padded = if first == MI_COMPRESSED {
+            n
+        } else {
+            n.div_ceil(8) * 8
+        };
+        self.pos = (start + padded).min(self.data.len());
+        Ok((first, payload))
+    }
+
+    fn read_variable(&mut self) -> Result<(Option<String>, MatValue)> {
+        let limits = self.limits;
+        if self.depth >= limits.max_depth {
+            return Err(LimitExceeded::new(LimitKind::Depth, limits.max_depth, "MAT file").into());
+        }
+        let (ty, payload) = self.read_element()?;
+        let mut inner = Reader {
+            data: payload,
+            pos: 0,
+            big_endian: self.big_endian,
+            depth: self.depth + 1,
+            limits,
+        };
+        match ty {
+            MI_COMPRESSED => {
+                let mut inflated = Vec::new();
+                flate2::read::ZlibDecoder::new(payload)
+                    .take(limits.max_file_size.saturating_add(1))
+                    .read_to_end(&mut inflated)
+                    .context("Failed to inflate compressed MAT variable")?;
+                if inflated.len() as u64 > limits.max_file_size {
+                    let limit = limits.max_file_size;
+                    return Err(LimitExceeded::new(LimitKind::FileSize, limit, "MAT file").into());
+                }
+                // The compressed element wraps the variable, it does not nest it.
+                inner.data = &inflated;
+                inner.depth = self.depth;
+                inner.read_variable()
+            }
+            MI_MATRIX => inner.parse_matrix(),
+            other => Ok((None, MatValue::Unsupported(other))),
+        }
+    }
+
+    /// Parse the payload of an `miMATRIX` element this reader holds.
+    fn parse_matrix(&mut self) -> Result<(Option<String>, MatValue)> {
+        let big_endian = self.big_endian;
+        if self.data.is_empty() {
+            return Ok((
+                None,
+                MatValue::Numeric {
+                    dims: vec![0, 0],
+                    real: Vec::new(),
+                    imag: None,
+                    logical: false,
+                },
+            ));
+        }
+        let (_, flags_raw) = self.read_element()?;
+        let flags = decode_numbers(MI_UINT32, flags_raw, big_endian)?
+            .first()
+            .copied()
+            .unwrap_or(0.0) as u32;
+        let class = flags & 0xff;
+        let (dims_ty, dims_raw) = self.read_element()?;
+        let dims: Vec<usize> = decode_numbers(dims_ty, dims_raw, big_endian)?
+            .into_iter()
+            .map(|d| d.max(0.0) as usize)
+            .collect();
+        let count = dims
+            .iter()
+            .try_fold(1usize, |n, &d| n.checked_mul(d))
+            .ok_or_else(|| anyhow!("MAT array dimensions {:?} overflow", dims))?;
+        let (_, name_raw) = self.read_element()?;
+        let name = String::from_utf8_lossy(name_raw).into_owned();
+        let name = (!name.is_empty()).then_some(name);
+
+        let value = match class {
+            MX_CELL => {
+                // Every item is at least one 8-byte element tag.
+                if count > self.remaining() / 8 {
+                    bail!("MAT cell array of {} items exceeds its data", count);
+                }
+                let mut items = Vec::with_capacity(count);
+                for _ in 0..count {
+                    items.push(self.read_variable()?.1);
+                }
+                MatValue::Cell { dims, items }
+            }
+            MX_STRUCT => {
+                let (len_ty, len_raw) = self.read_element()?;
+                let field_len = decode_numbers(len_ty, len_raw, big_endian)?
+                    .first()
+                    .copied()
+                    .unwrap_or(0.0) as usize;
+                let (_, names_raw) = self.read_element()?;
+                let fields: Vec<String> = if field_len == 0 {
+                    Vec::new()
+                } else {
+                    names_raw
+                        .chunks(field_len)
+                        .map(|c| {
+                            let end = c.iter().position(|&b| b == 0).unwrap_or(c.len());
+                            String::from_utf8_lossy(&c[..end]).into_owned()
+                        })
+                        .collect()
+                };
+                let fits = match fields.len() {
+                    0 => count <= self.data.len(),
+                    n => count
+                        .checked_mul(n)
+                        .is_some_and(|values| values <= self.remaining() / 8),
+                };
+                if !fits {
+                    bail!("MAT struct array of {} elements exceeds its data", count);
+                }
+                let mut elements = Vec::with_capacity(count);
+                for _ in 0..count {
+                    let mut map = IndexMap::new();
+                    for f in &fields {
+                        map.insert(f.clone(), self.read_variable()?.1);
+                    }
+                    elements.push(map);
+                }
+                MatValue::Struct { dims, elements }
+            }
+            MX_CHAR => {
+                let (ty, raw) = if self.remaining() >= 8 {
+                    self.read_element()?
+                } else {
+                    (MI_UTF8, &[][..])
+                };
+                let chars: Vec<char> = match ty {
+                    MI_UINT8 | MI_INT8 | MI_UTF8 => String::from_utf8_lossy(raw).chars().collect(),
+                    MI_UINT16 | MI_UTF16 => {
+                        let units: Vec<u16> = decode_numbers(MI_UINT16, raw, big_endian)?
+                            .into_iter()
+                            .map(|v| v as u16)
+                            .collect();
+                        char::decode_utf16(units)
+                            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                            .collect()
+                    }
+                    MI_UTF32 | MI_UINT32 => decode_numbers(MI_UINT32, raw, big_endian)?
+                        .into_iter()
+                        .map(|v| char::from_u32(v as u32).unwrap_or(char::REPLACEMENT_CHARACTER))
+                        .collect(),
+                    other => bail!("Unsupported character data type {}", other),
+                };
+                // Character arrays are stored column-major; rebuild the rows.
+                let rows = dims.first().copied().unwrap_or(1).max(1);
+                let cols = chars.len() / rows;
+                let lines: Vec<String> = (0..rows)
+                    .map(|row| (0..cols).map(|c| chars[c * rows + row]).collect())
+                    .collect();
+                MatValue::Char(lines.join("\n"))
+            }
+            6..=15 => {
+                let (ty, raw) = self.read_element()?;
+                let real = decode_numbers(ty, raw, big_endian)?;
+                let imag = if flags & FLAG_COMPLEX != 0 {
+                    let (ty, raw) = self.read_element()?;
+                    Some(decode_numbers(ty, raw, big_endian)?)
+                } else {
+                    None
+                };
+                MatValue::Numeric {
+                    dims,
+                    real,
+                    imag,
+                    logical: flags & FLAG_LOGICAL != 0,
+                }
+            }
+            other => MatValue::Unsupported(other),
+        };
+        Ok((name, value))
+    }
+}
+
+/// Decode a numeric data element payload into `f64` values.
+fn decode_numbers(ty: u32, raw: &[u8], big_endian: bool) -> Result<Vec<f64>> {
+    macro_rules! decode {
+        ($t:ty) => {{
+            const N: usize = std::mem::size_of::<$t>();
+            raw.chunks_exact(N)
+                .map(|c| {
+                    let b: [u8; N] = c.try_into().unwrap_or([0; N]);
+                    (if big_endian {
+                        <$t>::from_be_bytes(b)
+                    } else {
+                        <$t>::from_le_bytes(b)
+                    }) as f64
+                })
+                .collect()
+        }};
+    }
+    Ok(match ty {
+        MI_INT8 => decode!(i8),
+        MI_UINT8 | MI_UTF8 => decode!(u8),
+        MI_INT16 => decode!(i16),
+        MI_UINT16 | MI_UTF16 => decode!(u16),
+        MI_INT32 => decode!(i32),
+        MI_UINT32 | MI_UTF32 => decode!(u32),
+        MI_SINGLE => decode!(f32),
+        MI_DOUBLE => decode!(f64),
+        MI_INT64 => decode!(i64),
+        MI_UINT64 => decode!(u64),
+        other => bail!("Unsupported numeric data type {}", other),
+    })
+}
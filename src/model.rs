@@ -1,3 +1,5 @@
+use crate::block_path::BlockPath;
+pub use crate::property_bag::PropertyBag;
 use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
@@ -109,7 +111,12 @@ pub struct Block {
     pub position: Option<String>,
     /// Convenience: parsed ZOrder string (also stored in `properties`).
     pub zorder: Option<String>,
+    /// `Commented` is `on` or `through`.
     pub commented: bool,
+    /// `Commented` is `through`: the block is bypassed and its inputs pass
+    /// straight to its outputs. Implies `commented`.
+    #[serde(default)]
+    pub comment_through: bool,
     /// Location of the block name label (defaults to Bottom if not specified).
     #[serde(default)]
     pub name_location: NameLocation,
@@ -129,7 +136,7 @@ pub struct Block {
 
     /// Ordered map of all `<P>` element key-value pairs, including Position
     /// and ZOrder in their original order.
-    pub properties: IndexMap<String, String>,
+    pub properties: PropertyBag,
 
     /// Names of properties whose XML value is stored in a `Ref` attribute
     /// rather than as text content (e.g., `LibrarySourceProduct`).
@@ -167,7 +174,7 @@ pub struct Block {
     pub annotations: Vec<Annotation>,
     /// Convenience: parsed background color.
     #[serde(default)]
-    pub background_color: Option<String>,
+    pub background_color: Option<crate::color::Rgba>,
     /// Convenience: parsed show-name flag.
     #[serde(default)]
     pub show_name: Option<bool>,
@@ -177,6 +184,15 @@ pub struct Block {
     /// Convenience: parsed font weight.
     #[serde(default)]
     pub font_weight: Option<String>,
+    /// Convenience: parsed foreground (text and border) color.
+    #[serde(default)]
+    pub foreground_color: Option<crate::color::Rgba>,
+    /// Convenience: parsed `DropShadow` flag.
+    #[serde(default)]
+    pub drop_shadow: bool,
+    /// Convenience: parsed font name (`None` for Simulink's `"auto"`).
+    #[serde(default)]
+    pub font_name: Option<String>,
     /// Evaluated display text from mask's Display script.
     #[serde(default)]
     pub mask_display_text: Option<String>,
@@ -186,18 +202,30 @@ pub struct Block {
     /// Whether the block is mirrored.
     #[serde(default)]
     pub block_mirror: Option<bool>,
+    /// Signal-flow direction from `BlockRotation` / legacy `Orientation`.
+    #[serde(default)]
+    pub orientation: BlockOrientation,
     /// Library source name this block was copied from.
     #[serde(default)]
     pub library_source: Option<String>,
     /// Full library block path.
     #[serde(default)]
     pub library_block_path: Option<String>,
+    /// Parameter values this library link instance sets over its library
+    /// block; `None` for unlinked blocks and links without overrides.
+    #[serde(default)]
+    pub library_overrides: Option<LibraryOverrides>,
     /// Parsed dashboard binding from a `BindingPersistence` `.mxarray` file.
     ///
     /// Present only for Dashboard / HMI blocks that carry a `BindingPersistence`
     /// property in the SLX archive.
     #[serde(default)]
     pub dashboard_binding: Option<DashboardBinding>,
+    /// Numeric values of mask and block parameters that refer to model
+    /// workspace variables, resolved from the MAT files of the SLX archive
+    /// by [`SlxArchive::from_reader`].
+    #[serde(default)]
+    pub workspace_values: BTreeMap<String, f64>,
 
     /// Order of child XML elements inside this block, used for round-trip
     /// XML generation. When empty, a default order is used.
@@ -210,28 +238,348 @@ fn default_block_tag() -> String {
 }
 
 impl Block {
-    /// Returns the full path to this block as `<subsystem>/<block name>`.
+    /// Replace the code of a CFunction block, keeping the matching
+    /// parameters in [`Self::properties`] in sync so the code is written on
+    /// save.
+    pub fn set_c_function_code(&mut self, code: CFunctionCode) {
+        for name in CFunctionCode::SLOT_NAMES {
+            if let Some(Some(text)) = code.slot(name) {
+                let added = self
+                    .properties
+                    .insert(name.to_string(), text.clone())
+                    .is_none();
+                if added && !self.child_order.is_empty() {
+                    self.child_order.push(BlockChildKind::P(name.to_string()));
+                }
+            }
+        }
+        self.c_function = Some(code);
+    }
+
+    /// Returns the full path to this block as `<subsystem>/<block name>`, in
+    /// [`BlockPath`] text form.
     pub fn get_full_path(&self, root: &System) -> Option<String> {
         let mut result: Option<String> = None;
         let mut path = Vec::new();
         root.walk_blocks(&mut path, &mut |p, b| {
             if std::ptr::eq(b, self) {
-                let mut full = p.join("/");
-                if !full.is_empty() {
-                    full.push('/');
-                }
-                full.push_str(&self.name);
-                result = Some(full);
+                result = Some(BlockPath::from(p).child(&self.name).to_string());
             }
         });
         result
     }
+
+    /// Take over the content of the library block `template` this block
+    /// links to: its subsystem, ports and mask (unless the instance has its
+    /// own), with [`Self::library_overrides`] applied on top. Parameter
+    /// overrides set mask parameter values; overrides of inner blocks are
+    /// written to their properties.
+    ///
+    /// Returns the relative paths of overridden inner blocks that the
+    /// template does not contain.
+    pub fn instantiate_library_block(&mut self, template: &Block) -> Vec<String> {
+        if let Some(sub) = &template.subsystem {
+            self.subsystem = Some(sub.clone());
+        }
+        self.port_counts = template.port_counts.clone();
+        self.ports = template.ports.clone();
+        if self.mask.is_none() {
+            self.mask = template.mask.clone();
+        }
+        let Some(overrides) = &self.library_overrides else {
+            return Vec::new();
+        };
+        if let Some(mask) = &mut self.mask {
+            for param in &mut mask.parameters {
+                if let Some(value) = overrides.parameters.get(&param.name) {
+                    param.value = Some(value.clone());
+                }
+            }
+        }
+        let mut missing = Vec::new();
+        for (path, params) in &overrides.blocks {
+            let target = path.parse::<BlockPath>().ok().and_then(|p| {
+                let (name, parents) = p.split_last()?;
+                let mut system = self.subsystem.as_deref_mut()?;
+                for parent in parents {
+                    let block = system.blocks.iter_mut().find(|b| &b.name == parent)?;
+                    system = block.subsystem.as_deref_mut()?;
+                }
+                system.blocks.iter_mut().find(|b| &b.name == name)
+            });
+            match target {
+                Some(block) => {
+                    for (name, value) in params {
+                        block.properties.insert(name.clone(), value.clone());
+                    }
+                }
+                None => missing.push(path.clone()),
+            }
+        }
+        missing
+    }
+
+    /// The block's comment state.
+    pub fn comment_mode(&self) -> CommentMode {
+        match (self.commented, self.comment_through) {
+            (false, _) => CommentMode::Off,
+            (true, false) => CommentMode::Out,
+            (true, true) => CommentMode::Through,
+        }
+    }
+
+    /// The block's non-empty callback parameters (`InitFcn`, `OpenFcn`, …).
+    pub fn callbacks(&self) -> Callbacks {
+        Callbacks::from_properties(
+            self.properties
+                .iter()
+                .map(|(k, v)| (k.as_str(), v.as_str())),
+        )
+    }
+
+    /// [`Block::callbacks`] plus the mask initialization code
+    /// (`MaskInitialization`) and mask parameter callbacks
+    /// (`<parameter>:Callback`): all MATLAB code the block runs.
+    pub fn all_callbacks(&self) -> Callbacks {
+        let mut callbacks = self.callbacks();
+        if let Some(mask) = &self.mask {
+            let init = mask
+                .initialization
+                .clone()
+                .map(|code| ("MaskInitialization".to_string(), code));
+            let params = mask
+                .parameters
+                .iter()
+                .filter_map(|p| Some((format!("{}:Callback", p.name), p.callback.clone()?)));
+            callbacks.entries.extend(
+                init.into_iter()
+                    .chain(params)
+                    .filter(|(_, code)| !code.trim().is_empty()),
+            );
+        }
+        callbacks
+    }
+
+    /// The S-function parameters of an `S-Function` or `M-S-Function`
+    /// (Level-2 MATLAB S-Function) block; `None` for other blocks.
+    pub fn s_function(&self) -> Option<SFunction> {
+        let matlab = match self.block_type.as_str() {
+            "S-Function" => false,
+            "M-S-Function" => true,
+            _ => return None,
+        };
+        let param = |name: &str| self.properties.get(name).map_or("", |v| v.trim());
+        Some(SFunction {
+            function_name: param("FunctionName").to_string(),
+            parameters: split_matlab_list(param("Parameters")),
+            modules: param("SFunctionModules")
+                .split(|c: char| c.is_whitespace() || matches!(c, ',' | '\'' | '"' | '{' | '}'))
+                .filter(|m| !m.is_empty())
+                .map(str::to_string)
+                .collect(),
+            matlab,
+        })
+    }
+
+    /// Name of the model a `ModelReference` block refers to, without the
+    /// file extension; `None` for other blocks or an empty reference.
+    pub fn referenced_model(&self) -> Option<&str> {
+        if self.block_type != "ModelReference" {
+            return None;
+        }
+        let name = ["ModelNameDialog", "ModelFile", "ModelName"]
+            .iter()
+            .find_map(|p| self.properties.get(p).filter(|v| !v.trim().is_empty()))?
+            .trim();
+        Some(
+            [".slxp", ".slx", ".mdl"]
+                .iter()
+                .find_map(|ext| name.strip_suffix(ext))
+                .unwrap_or(name),
+        )
+    }
+
+    /// The sign list and icon shape of a `Sum` block; `None` for other
+    /// blocks.
+    pub fn sum_params(&self) -> Option<SumParams> {
+        (self.block_type == "Sum").then(|| SumParams::from_block(self))
+    }
+
+    /// Labels of the action outputs of an `If` or `SwitchCase` block, one per
+    /// output port as Simulink draws them: `if(u1 > 0)`, `elseif(u2)`,
+    /// `else`, or `case 1:`, `case {2,3}:`, `default:`. `None` for other
+    /// blocks. Missing parameters take the Simulink defaults.
+    pub fn condition_labels(&self) -> Option<Vec<String>> {
+        let param = |name: &str| self.properties.get(name).map(|v| v.trim());
+        let shown = |name: &str| param(name).is_none_or(|v| !v.eq_ignore_ascii_case("off"));
+        let (mut labels, otherwise) = match self.block_type.as_str() {
+            "If" => {
+                let mut labels = vec![format!("if({})", param("IfExpression").unwrap_or("u1 > 0"))];
+                labels.extend(
+                    split_matlab_list(param("ElseIfExpressions").unwrap_or(""))
+                        .into_iter()
+                        .map(|e| format!("elseif({e})")),
+                );
+                (labels, shown("ShowElse").then_some("else"))
+            }
+            "SwitchCase" => {
+                let conditions = param("CaseConditions").unwrap_or("{1}");
+                let inner = conditions
+                    .strip_prefix('{')
+                    .and_then(|c| c.strip_suffix('}'))
+                    .unwrap_or(conditions);
+                let labels = split_matlab_list(inner)
+                    .into_iter()
+                    .map(|c| {
+                        // A vector of values matches any of them
+                        let c = match c.strip_prefix('[').and_then(|c| c.strip_suffix(']')) {
+                            Some(values) => format!("{{{}}}", values.trim()),
+                            None => c,
+                        };
+                        format!("case {c}:")
+                    })
+                    .collect();
+                (labels, shown("ShowDefaultCase").then_some("default:"))
+            }
+            _ => return None,
+        };
+        labels.extend(otherwise.map(str::to_string));
+        Some(labels)
+    }
+
+    /// Control ports of a conditionally executed subsystem, in Simulink's
+    /// left-to-right order on the top edge: from `<PortCounts>` and from the
+    /// `EnablePort`, `TriggerPort`, `ActionPort` and `ResetPort` blocks
+    /// inside.
+    pub fn control_ports(&self) -> Vec<PortKind> {
+        let children = self.subsystem.as_ref().map_or(&[][..], |s| &s.blocks);
+        [
+            ("EnablePort", PortKind::Enable),
+            ("TriggerPort", PortKind::Trigger),
+            ("ActionPort", PortKind::IfAction),
+            ("ResetPort", PortKind::Reset),
+        ]
+        .into_iter()
+        .filter(|(block_type, kind)| {
+            self.port_counts
+                .as_ref()
+                .and_then(|pc| pc.count(kind))
+                .is_some_and(|n| n > 0)
+                || children.iter().any(|b| b.block_type == *block_type)
+        })
+        .map(|(_, kind)| kind)
+        .collect()
+    }
+
+    /// Set the comment state, keeping the `Commented` parameter in sync.
+    pub fn set_comment_mode(&mut self, mode: CommentMode) {
+        self.commented = mode != CommentMode::Off;
+        self.comment_through = mode == CommentMode::Through;
+        match mode.as_param() {
+            Some(value) => {
+                self.properties
+                    .insert("Commented".to_string(), value.to_string());
+            }
+            None => {
+                self.properties.swap_remove("Commented");
+            }
+        }
+    }
 }
 
 // ────────────────────────────────────────────────────────────────────────────
 // Supporting types
 // ────────────────────────────────────────────────────────────────────────────
 
+/// Whether the block or model parameter `name` holds callback code.
+///
+/// Simulink names all callbacks `…Fcn`; `MATLABFcn` is the expression of the
+/// Interpreted MATLAB Function block rather than a callback.
+pub fn is_callback_param(name: &str) -> bool {
+    name.ends_with("Fcn") && name != "MATLABFcn"
+}
+
+/// MATLAB callback code of a block or model, by parameter name in document
+/// order. Empty callbacks are left out.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Callbacks {
+    pub entries: IndexMap<String, String>,
+}
+
+impl Callbacks {
+    /// Collect the callbacks among `properties`.
+    pub fn from_properties<'a>(properties: impl IntoIterator<Item = (&'a str, &'a str)>) -> Self {
+        let entries = properties
+            .into_iter()
+            .filter(|(name, code)| is_callback_param(name) && !code.trim().is_empty())
+            .map(|(name, code)| (name.to_string(), code.to_string()))
+            .collect();
+        Callbacks { entries }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries.get(name).map(String::as_str)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+/// A piece of MATLAB code found by [`System::all_callbacks`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallbackSite {
+    /// Block path from the system the search started in, joined by `/`.
+    pub path: String,
+    /// Callback parameter (`InitFcn`, …), `MaskInitialization`, or
+    /// `<parameter>:Callback` for a mask parameter callback.
+    pub name: String,
+    pub code: String,
+}
+
+/// Simulink's `Commented` block parameter.
+///
+/// A commented-out block is removed from the model together with its signals;
+/// a commented-through block is removed but its inputs are wired to its
+/// outputs (input *n* to output *n*).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum CommentMode {
+    #[default]
+    Off,
+    Out,
+    Through,
+}
+
+impl CommentMode {
+    /// Parse a `Commented` value (`off`, `on`, `through`).
+    pub fn from_param(value: &str) -> Self {
+        if value.eq_ignore_ascii_case("through") {
+            Self::Through
+        } else if value.eq_ignore_ascii_case("on") {
+            Self::Out
+        } else {
+            Self::Off
+        }
+    }
+
+    /// The `Commented` value; `None` for `Off`, which Simulink omits.
+    pub fn as_param(self) -> Option<&'static str> {
+        match self {
+            Self::Off => None,
+            Self::Out => Some("on"),
+            Self::Through => Some("through"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum NameLocation {
     Top,
@@ -246,11 +594,104 @@ impl Default for NameLocation {
     }
 }
 
+/// Signal-flow direction of a block.
+///
+/// Derived from `BlockRotation` (0/90/180/270 degrees clockwise) or the legacy
+/// MDL `Orientation` parameter. `Right` is Simulink's default: inputs on the
+/// left edge, outputs on the right. `BlockMirror` is tracked separately.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum BlockOrientation {
+    #[default]
+    Right,
+    Down,
+    Left,
+    Up,
+}
+
+impl BlockOrientation {
+    /// Parse a `BlockRotation` value in degrees (`"0"`, `"90"`, `"180"`, `"270"`).
+    pub fn from_rotation_degrees(value: &str) -> Option<Self> {
+        let deg = value.trim().parse::<f64>().ok()?.round() as i64;
+        match deg.rem_euclid(360) {
+            0 => Some(Self::Right),
+            90 => Some(Self::Down),
+            180 => Some(Self::Left),
+            270 => Some(Self::Up),
+            _ => None,
+        }
+    }
+
+    /// Parse a legacy `Orientation` value (`"right"`, `"down"`, `"left"`, `"up"`).
+    pub fn from_orientation_str(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "right" => Some(Self::Right),
+            "down" => Some(Self::Down),
+            "left" => Some(Self::Left),
+            "up" => Some(Self::Up),
+            _ => None,
+        }
+    }
+
+    /// Clockwise rotation in degrees, as written to `BlockRotation`.
+    pub fn rotation_degrees(self) -> u32 {
+        match self {
+            Self::Right => 0,
+            Self::Down => 90,
+            Self::Left => 180,
+            Self::Up => 270,
+        }
+    }
+
+    /// Legacy `Orientation` value.
+    pub fn as_orientation_str(self) -> &'static str {
+        match self {
+            Self::Right => "right",
+            Self::Down => "down",
+            Self::Left => "left",
+            Self::Up => "up",
+        }
+    }
+
+    /// The orientation after one further 90° clockwise rotation.
+    pub fn rotated_clockwise(self) -> Self {
+        match self {
+            Self::Right => Self::Down,
+            Self::Down => Self::Left,
+            Self::Left => Self::Up,
+            Self::Up => Self::Right,
+        }
+    }
+
+    /// `true` when ports sit on the top and bottom edges.
+    pub fn is_vertical(self) -> bool {
+        matches!(self, Self::Down | Self::Up)
+    }
+}
+
 /// Represents the `<PortCounts in="…" out="…"/>` XML element.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PortCounts {
     pub ins: Option<u32>,
     pub outs: Option<u32>,
+    /// Counts of the other port kinds (`enable="1"`, `trigger="1"`, …) in
+    /// file order.
+    #[serde(default)]
+    pub special: Vec<(PortKind, u32)>,
+}
+
+impl PortCounts {
+    /// Number of ports of `kind`, if the element gives one.
+    pub fn count(&self, kind: &PortKind) -> Option<u32> {
+        match kind {
+            PortKind::In => self.ins,
+            PortKind::Out => self.outs,
+            _ => self
+                .special
+                .iter()
+                .find(|(k, _)| k == kind)
+                .map(|(_, n)| *n),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -279,6 +720,51 @@ pub struct Line {
     pub properties: IndexMap<String, String>,
 }
 
+impl Line {
+    /// Stable identifier of the line; `None` for lines connected to nothing.
+    pub fn id(&self) -> Option<LineId> {
+        match (&self.src, &self.dst) {
+            (Some(src), _) => Some(LineId::from_source(src)),
+            (None, Some(dst)) => Some(LineId(format!(">{dst}"))),
+            (None, None) => None,
+        }
+    }
+}
+
+/// Stable identifier of a signal line within its system.
+///
+/// Line indices shift whenever lines are added or removed. A `LineId` is
+/// derived from the line's source port instead, which carries at most one
+/// line, and written like the endpoint in the XML, e.g. `7#out:1`. Lines
+/// without a source are named by their destination after a `>`, e.g.
+/// `>9#in:2`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct LineId(String);
+
+impl LineId {
+    /// Id of the line leaving `src`.
+    pub fn from_source(src: &EndpointRef) -> Self {
+        Self(src.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for LineId {
+    fn from(id: &str) -> Self {
+        Self(id.to_string())
+    }
+}
+
+impl std::fmt::Display for LineId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// A branch of a signal line.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Branch {
@@ -299,13 +785,134 @@ pub struct Point {
     pub y: i32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct EndpointRef {
     pub sid: String,
-    pub port_type: String,
+    pub port_type: PortKind,
     pub port_index: u32,
 }
 
+/// The XML form, e.g. `7#out:1`.
+impl std::fmt::Display for EndpointRef {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}#{}:{}", self.sid, self.port_type, self.port_index)
+    }
+}
+
+/// Kind of block port, as written in line endpoints (`7#trigger:1`) and
+/// `<PortCounts>` attributes.
+///
+/// Besides data inputs and outputs, Simulink blocks have control ports on
+/// their top edge (enable, trigger, action and reset) and a state output on
+/// the bottom edge. Physical connection ports sit on the left (`lconn`) and
+/// right (`rconn`). Unknown kinds are kept verbatim.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum PortKind {
+    In,
+    Out,
+    Enable,
+    Trigger,
+    /// Action port of If/SwitchCase Action subsystems.
+    IfAction,
+    Reset,
+    /// State output of an Integrator.
+    State,
+    LConn,
+    RConn,
+    Other(String),
+}
+
+impl PortKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::In => "in",
+            Self::Out => "out",
+            Self::Enable => "enable",
+            Self::Trigger => "trigger",
+            Self::IfAction => "ifaction",
+            Self::Reset => "reset",
+            Self::State => "state",
+            Self::LConn => "lconn",
+            Self::RConn => "rconn",
+            Self::Other(s) => s,
+        }
+    }
+
+    /// `true` for ports that receive signals or sit on the input side:
+    /// everything but `out`, `state` and `rconn`.
+    pub fn is_input(&self) -> bool {
+        !matches!(self, Self::Out | Self::State | Self::RConn)
+    }
+
+    /// Enable, trigger, action and reset ports, drawn on the top edge.
+    pub fn is_control(&self) -> bool {
+        matches!(
+            self,
+            Self::Enable | Self::Trigger | Self::IfAction | Self::Reset
+        )
+    }
+}
+
+impl From<&str> for PortKind {
+    fn from(s: &str) -> Self {
+        match s {
+            "in" => Self::In,
+            "out" => Self::Out,
+            "enable" => Self::Enable,
+            "trigger" => Self::Trigger,
+            "ifaction" => Self::IfAction,
+            "reset" => Self::Reset,
+            "state" => Self::State,
+            "lconn" => Self::LConn,
+            "rconn" => Self::RConn,
+            other => Self::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<String> for PortKind {
+    fn from(s: String) -> Self {
+        Self::from(s.as_str())
+    }
+}
+
+impl From<PortKind> for String {
+    fn from(kind: PortKind) -> Self {
+        kind.as_str().to_string()
+    }
+}
+
+impl std::fmt::Display for PortKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl PartialEq<str> for PortKind {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for PortKind {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for PortKind {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<PortKind> for String {
+    fn eq(&self, other: &PortKind) -> bool {
+        self == other.as_str()
+    }
+}
+
 // ────────────────────────────────────────────────────────────────────────────
 // Stateflow Chart
 // ────────────────────────────────────────────────────────────────────────────
@@ -350,6 +957,166 @@ pub struct CFunctionCode {
     pub codegen_terminate_code: Option<String>,
 }
 
+impl CFunctionCode {
+    /// Block parameter names of the code slots, in the order Simulink's
+    /// dialog shows them.
+    pub const SLOT_NAMES: [&'static str; 6] = [
+        "StartCode",
+        "OutputCode",
+        "TerminateCode",
+        "CodegenStartCode",
+        "CodegenOutputCode",
+        "CodegenTerminateCode",
+    ];
+
+    /// The code slot stored under the block parameter `name`, e.g.
+    /// `OutputCode`.
+    pub fn slot(&self, name: &str) -> Option<&Option<String>> {
+        Some(match name {
+            "StartCode" => &self.start_code,
+            "OutputCode" => &self.output_code,
+            "TerminateCode" => &self.terminate_code,
+            "CodegenStartCode" => &self.codegen_start_code,
+            "CodegenOutputCode" => &self.codegen_output_code,
+            "CodegenTerminateCode" => &self.codegen_terminate_code,
+            _ => return None,
+        })
+    }
+
+    pub fn slot_mut(&mut self, name: &str) -> Option<&mut Option<String>> {
+        Some(match name {
+            "StartCode" => &mut self.start_code,
+            "OutputCode" => &mut self.output_code,
+            "TerminateCode" => &mut self.terminate_code,
+            "CodegenStartCode" => &mut self.codegen_start_code,
+            "CodegenOutputCode" => &mut self.codegen_output_code,
+            "CodegenTerminateCode" => &mut self.codegen_terminate_code,
+            _ => return None,
+        })
+    }
+}
+
+/// Parameters of an S-function block, see [`Block::s_function`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SFunction {
+    /// `FunctionName`: the MEX or MATLAB file name without extension.
+    pub function_name: String,
+    /// `Parameters`, split into the individual MATLAB expressions.
+    pub parameters: Vec<String>,
+    /// `SFunctionModules`: extra source modules the MEX file is built from.
+    pub modules: Vec<String>,
+    /// A Level-2 MATLAB S-Function (`.m` file) rather than a MEX file.
+    pub matlab: bool,
+}
+
+/// Parameters of a Sum block, see [`Block::sum_params`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SumParams {
+    /// `Inputs` as one entry per character, e.g. `|+-`. On round blocks each
+    /// entry takes one slot around the left half of the circle.
+    pub signs: Vec<SumSign>,
+    /// `IconShape` is `rectangular` rather than `round`.
+    pub rectangular: bool,
+}
+
+impl SumParams {
+    /// Read `Inputs` and `IconShape` from `block` whatever its type, e.g.
+    /// for custom blocks drawn like a Sum.
+    pub fn from_block(block: &Block) -> Self {
+        let rectangular = block
+            .properties
+            .get("IconShape")
+            .is_some_and(|s| s.trim().eq_ignore_ascii_case("rectangular"));
+        let inputs = block.properties.get("Inputs").map(|s| s.trim());
+        let signs = match inputs {
+            Some(s) if !s.is_empty() && s.chars().all(|c| "+-|".contains(c)) => {
+                s.chars().map(SumSign::from_char).collect()
+            }
+            // A port count, or nothing: all inputs added, after a spacer on
+            // round blocks (`|++`)
+            _ => {
+                let count = inputs
+                    .and_then(|s| s.parse().ok())
+                    .or_else(|| block.port_counts.as_ref().and_then(|pc| pc.ins))
+                    .unwrap_or(2);
+                let spacer = (!rectangular).then_some(SumSign::Spacer);
+                spacer
+                    .into_iter()
+                    .chain(std::iter::repeat_n(SumSign::Plus, count as usize))
+                    .collect()
+            }
+        };
+        Self { signs, rectangular }
+    }
+
+    /// The signs of the input ports in port order, without spacers.
+    pub fn port_signs(&self) -> Vec<SumSign> {
+        self.signs
+            .iter()
+            .copied()
+            .filter(|s| *s != SumSign::Spacer)
+            .collect()
+    }
+}
+
+/// One character of a Sum block's `Inputs` sign list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SumSign {
+    Plus,
+    Minus,
+    /// `|`: an empty slot.
+    Spacer,
+}
+
+impl SumSign {
+    fn from_char(c: char) -> Self {
+        match c {
+            '-' => Self::Minus,
+            '|' => Self::Spacer,
+            _ => Self::Plus,
+        }
+    }
+
+    pub fn as_char(self) -> char {
+        match self {
+            Self::Plus => '+',
+            Self::Minus => '-',
+            Self::Spacer => '|',
+        }
+    }
+}
+
+/// Split a comma-separated list of MATLAB expressions, ignoring commas inside
+/// brackets and quoted strings.
+fn split_matlab_list(list: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut start = 0;
+    let mut prev = ' ';
+    for (i, c) in list.char_indices() {
+        // A quote right after a value is MATLAB's transpose operator.
+        let transpose =
+            c == '\'' && (prev.is_alphanumeric() || matches!(prev, ')' | ']' | '}' | '.' | '_'));
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') if !transpose => quote = Some(c),
+            (None, '(' | '[' | '{') => depth += 1,
+            (None, ')' | ']' | '}') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                items.push(list[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    items.push(list[start..].trim().to_string());
+    items.retain(|item| !item.is_empty());
+    items
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Mask {
     pub display: Option<String>,
@@ -435,6 +1202,39 @@ pub struct InstanceData {
     pub properties: IndexMap<String, String>,
 }
 
+/// Parameters a library link instance overrides, collected from its
+/// `<InstanceData>` (the linked block's own, usually mask, parameters) and
+/// `<LinkData>` (dialog parameters of blocks inside a parameterized link).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LibraryOverrides {
+    /// Parameters of the linked block itself.
+    pub parameters: IndexMap<String, String>,
+    /// Parameters of blocks inside the library block, keyed by their
+    /// [`BlockPath`] relative to it.
+    pub blocks: IndexMap<String, IndexMap<String, String>>,
+}
+
+impl LibraryOverrides {
+    pub fn new(instance: Option<&InstanceData>, link: Option<&LinkData>) -> Self {
+        let mut overrides = LibraryOverrides::default();
+        if let Some(instance) = instance {
+            overrides.parameters = instance.properties.clone();
+        }
+        for entry in link.iter().flat_map(|l| &l.dialog_parameters) {
+            overrides
+                .blocks
+                .entry(entry.block_name.clone())
+                .or_default()
+                .extend(entry.properties.clone());
+        }
+        overrides
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parameters.is_empty() && self.blocks.is_empty()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub enum ValueKind {
     Unknown,
@@ -458,6 +1258,41 @@ pub struct Annotation {
     pub zorder: Option<String>,
     pub interpreter: Option<String>,
     pub properties: IndexMap<String, String>,
+    /// Convenience: parsed foreground (text) color.
+    #[serde(default)]
+    pub foreground_color: Option<crate::color::Rgba>,
+    /// Convenience: parsed background color.
+    #[serde(default)]
+    pub background_color: Option<crate::color::Rgba>,
+    /// Convenience: parsed `DropShadow` flag.
+    #[serde(default)]
+    pub drop_shadow: bool,
+    /// Convenience: parsed font name (`None` for Simulink's `"auto"`).
+    #[serde(default)]
+    pub font_name: Option<String>,
+    /// Convenience: parsed font size.
+    #[serde(default)]
+    pub font_size: Option<u32>,
+    /// Convenience: parsed font weight.
+    #[serde(default)]
+    pub font_weight: Option<String>,
+}
+
+impl Annotation {
+    /// MATLAB code run when the annotation is clicked: its `ClickFcn`, or
+    /// the text itself when `UseDisplayTextAsClickCallback` is on.
+    pub fn click_callback(&self) -> Option<&str> {
+        let use_text = self
+            .properties
+            .get("UseDisplayTextAsClickCallback")
+            .is_some_and(|v| v.eq_ignore_ascii_case("on"));
+        let callback = if use_text {
+            self.text.as_deref()
+        } else {
+            self.properties.get("ClickFcn").map(String::as_str)
+        };
+        callback.filter(|c| !c.trim().is_empty())
+    }
 }
 
 // ────────────────────────────────────────────────────────────────────────────
@@ -686,6 +1521,137 @@ pub fn parse_rels_xml(xml: &str) -> Vec<Relationship> {
     rels
 }
 
+// ────────────────────────────────────────────────────────────────────────────
+// Embedded assets (images stored inside the SLX archive)
+// ────────────────────────────────────────────────────────────────────────────
+
+/// A binary asset embedded in an SLX archive, such as a mask image, an
+/// annotation image, or the model thumbnail (`metadata/thumbnail.png`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ModelAsset {
+    /// Path of the asset within the archive (e.g. `"metadata/thumbnail.png"`).
+    pub path: String,
+    /// MIME type derived from the file extension or content (e.g. `"image/png"`).
+    pub mime_type: String,
+    /// Raw file content.
+    pub data: Vec<u8>,
+}
+
+impl ModelAsset {
+    /// File name component of the asset path.
+    pub fn file_name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// Registry of binary assets embedded in a model, keyed by archive path.
+///
+/// Viewers and exporters use this to render mask/annotation images and
+/// thumbnails without having to re-open the SLX archive.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AssetRegistry {
+    pub assets: BTreeMap<String, ModelAsset>,
+}
+
+impl AssetRegistry {
+    /// Add an asset, replacing any previous asset with the same path.
+    pub fn insert(&mut self, asset: ModelAsset) {
+        self.assets.insert(asset.path.clone(), asset);
+    }
+
+    /// Look up an asset by archive path. Leading `./` and `/` are ignored.
+    pub fn get(&self, path: &str) -> Option<&ModelAsset> {
+        let p = path.trim_start_matches("./").trim_start_matches('/');
+        self.assets.get(p)
+    }
+
+    /// The model thumbnail (`metadata/thumbnail.png`), if present.
+    pub fn thumbnail(&self) -> Option<&ModelAsset> {
+        self.get(THUMBNAIL_PATH)
+    }
+
+    /// Iterate over all assets in path order.
+    pub fn iter(&self) -> impl Iterator<Item = &ModelAsset> {
+        self.assets.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.assets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+}
+
+/// Archive path of the standard model thumbnail.
+pub const THUMBNAIL_PATH: &str = "metadata/thumbnail.png";
+
+/// Determine the MIME type of an embedded image from its path, falling back
+/// to sniffing the leading magic bytes of `data`.
+///
+/// Returns `None` for anything that is not a recognised image format.
+pub fn asset_mime_type(path: &str, data: &[u8]) -> Option<&'static str> {
+    asset_mime_type_from_path(path).or_else(|| {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some("image/png")
+        } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+            Some("image/jpeg")
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some("image/gif")
+        } else if data.starts_with(b"BM") && data.len() > 14 {
+            Some("image/bmp")
+        } else {
+            None
+        }
+    })
+}
+
+/// MIME type of an embedded image judged by its file extension alone.
+pub(crate) fn asset_mime_type_from_path(path: &str) -> Option<&'static str> {
+    let ext = path
+        .rsplit('/')
+        .next()
+        .and_then(|f| f.rsplit_once('.'))
+        .map(|(_, e)| e.to_ascii_lowercase());
+    match ext.as_deref() {
+        Some("png") => Some("image/png"),
+        Some("jpg") | Some("jpeg") => Some("image/jpeg"),
+        Some("gif") => Some("image/gif"),
+        Some("bmp") => Some("image/bmp"),
+        Some("svg") => Some("image/svg+xml"),
+        Some("ico") => Some("image/x-icon"),
+        Some("tif") | Some("tiff") => Some("image/tiff"),
+        Some("webp") => Some("image/webp"),
+        _ => None,
+    }
+}
+
+/// Build an [`AssetRegistry`] from `(path, bytes)` pairs, keeping only entries
+/// recognised as images by [`asset_mime_type`].
+pub fn collect_assets<'a, I>(entries: I) -> AssetRegistry
+where
+    I: IntoIterator<Item = (&'a str, &'a [u8])>,
+{
+    let mut registry = AssetRegistry::default();
+    for (path, data) in entries {
+        if path.ends_with('/') {
+            continue;
+        }
+        if let Some(mime) = asset_mime_type(path, data) {
+            registry.insert(ModelAsset {
+                path: path
+                    .trim_start_matches("./")
+                    .trim_start_matches('/')
+                    .to_string(),
+                mime_type: mime.to_string(),
+                data: data.to_vec(),
+            });
+        }
+    }
+    registry
+}
+
 // ────────────────────────────────────────────────────────────────────────────
 // System walk helpers
 // ────────────────────────────────────────────────────────────────────────────
@@ -706,6 +1672,26 @@ impl System {
         }
     }
 
+    /// All MATLAB code that runs on model events, for auditing:
+    /// [`Block::all_callbacks`] of every block, recursively in model order.
+    ///
+    /// Model-level callbacks live in `blockdiagram.xml`; see
+    /// [`crate::report::model_callbacks`].
+    pub fn all_callbacks(&self) -> Vec<CallbackSite> {
+        let mut sites = Vec::new();
+        let mut path = Vec::new();
+        self.walk_blocks(&mut path, &mut |p, block| {
+            for (name, code) in block.all_callbacks().entries {
+                sites.push(CallbackSite {
+                    path: BlockPath::from(p).child(&block.name).to_string(),
+                    name,
+                    code,
+                });
+            }
+        });
+        sites
+    }
+
     /// Find all blocks of a given type, returning `(path, Block)` pairs.
     pub fn find_blocks_by_type(&self, block_type: &str) -> Vec<(Vec<String>, Block)> {
         let mut result = Vec::new();
@@ -717,6 +1703,38 @@ impl System {
         });
         result
     }
+
+    /// The block at `path` (a [`BlockPath`] string relative to this system),
+    /// descending through loaded subsystems.
+    pub fn block_at(&self, path: &str) -> Option<&Block> {
+        self.block_at_path(&path.parse().ok()?)
+    }
+
+    /// The block at `path`, descending through loaded subsystems.
+    pub fn block_at_path(&self, path: &BlockPath) -> Option<&Block> {
+        let (name, parents) = path.split_last()?;
+        let system = self.system_at(parents)?;
+        system.blocks.iter().find(|b| &b.name == name)
+    }
+
+    /// Index in `lines` of the line with `id`.
+    pub fn line_index(&self, id: &LineId) -> Option<usize> {
+        self.lines.iter().position(|l| l.id().as_ref() == Some(id))
+    }
+
+    /// The line with `id`.
+    pub fn line(&self, id: &LineId) -> Option<&Line> {
+        self.line_index(id).map(|i| &self.lines[i])
+    }
+
+    /// The system level at `path` (block names from this system), if every
+    /// block on the way is a loaded subsystem.
+    pub fn system_at(&self, path: &[String]) -> Option<&System> {
+        path.iter().try_fold(self, |system, name| {
+            let block = system.blocks.iter().find(|b| &b.name == name)?;
+            block.subsystem.as_deref()
+        })
+    }
 }
 
 // ────────────────────────────────────────────────────────────────────────────
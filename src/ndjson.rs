@@ -0,0 +1,252 @@
+//! Newline-delimited JSON export for huge models.
+//!
+//! Instead of one JSON tree, [`write_records`] writes one JSON object per
+//! line, so tools like `jq` or Spark can process a model record by record.
+//! Each record has a `kind` and the `path` of the system it belongs to (a
+//! [`BlockPath`] string, empty for the root system) next to the fields of
+//! the item:
+//!
+//! - `system`: the `properties` and Stateflow `chart` of a system level,
+//! - `block`: a [`Block`](crate::model::Block) without its `subsystem`,
+//!   whose content follows as records with the block's path,
+//! - `line`: a [`Line`](crate::model::Line),
+//! - `annotation`: an [`Annotation`](crate::model::Annotation).
+//!
+//! A system's records come in that order, followed by the records of its
+//! subsystems in block order.
+
+use crate::block_path::BlockPath;
+use crate::model::{Chart, System};
+use anyhow::Result;
+use indexmap::IndexMap;
+use serde::Serialize;
+use serde::ser::{Impossible, SerializeMap, SerializeStruct, Serializer};
+use std::io::Write;
+
+/// Write `system` and its subsystems to `out`, one record per line.
+pub fn write_records<W: Write>(system: &System, mut out: W) -> Result<()> {
+    write_system(system, &mut BlockPath::root(), &mut out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Fields of a `system` record.
+#[derive(Serialize)]
+struct SystemHeader<'a> {
+    properties: &'a IndexMap<String, String>,
+    chart: &'a Option<Chart>,
+}
+
+fn write_system<W: Write>(system: &System, path: &mut BlockPath, out: &mut W) -> Result<()> {
+    let text = path.to_string();
+    let header = SystemHeader {
+        properties: &system.properties,
+        chart: &system.chart,
+    };
+    write_record(out, "system", &text, &header)?;
+    for block in &system.blocks {
+        write_record(out, "block", &text, block)?;
+    }
+    for line in &system.lines {
+        write_record(out, "line", &text, line)?;
+    }
+    for annotation in &system.annotations {
+        write_record(out, "annotation", &text, annotation)?;
+    }
+    for block in &system.blocks {
+        if let Some(sub) = block.subsystem.as_deref() {
+            path.push(&block.name);
+            write_system(sub, path, out)?;
+            path.pop();
+        }
+    }
+    Ok(())
+}
+
+fn write_record<W: Write, T: Serialize>(
+    out: &mut W,
+    kind: &str,
+    path: &str,
+    item: &T,
+) -> Result<()> {
+    let record = Record { kind, path, item };
+    serde_json::to_writer(&mut *out, &record)?;
+    out.write_all(b"\n")?;
+    Ok(())
+}
+
+/// `kind` and `path` followed by the fields of `item`, written straight to
+/// the output.
+struct Record<'a, T> {
+    kind: &'a str,
+    path: &'a str,
+    item: &'a T,
+}
+
+impl<T: Serialize> Serialize for Record<'_, T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("kind", self.kind)?;
+        map.serialize_entry("path", self.path)?;
+        self.item.serialize(Fields(&mut map))?;
+        map.end()
+    }
+}
+
+/// Serializer adding the fields of a struct to a record, except a block's
+/// `subsystem`, which is never serialized since its content follows as
+/// records of its own.
+struct Fields<'a, M>(&'a mut M);
+
+fn not_a_struct<T, E: serde::ser::Error>() -> Result<T, E> {
+    Err(E::custom("NDJSON records are built from structs"))
+}
+
+impl<M: SerializeMap> SerializeStruct for Fields<'_, M> {
+    type Ok = ();
+    type Error = M::Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), M::Error> {
+        if key == "subsystem" {
+            return Ok(());
+        }
+        self.0.serialize_entry(key, value)
+    }
+
+    fn end(self) -> Result<(), M::Error> {
+        Ok(())
+    }
+}
+
+impl<M: SerializeMap> Serializer for Fields<'_, M> {
+    type Ok = ();
+    type Error = M::Error;
+    type SerializeSeq = Impossible<(), M::Error>;
+    type SerializeTuple = Impossible<(), M::Error>;
+    type SerializeTupleStruct = Impossible<(), M::Error>;
+    type SerializeTupleVariant = Impossible<(), M::Error>;
+    type SerializeMap = Impossible<(), M::Error>;
+    type SerializeStruct = Self;
+    type SerializeStructVariant = Impossible<(), M::Error>;
+
+    fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self, M::Error> {
+        Ok(self)
+    }
+
+    fn serialize_bool(self, _: bool) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_i8(self, _: i8) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_i16(self, _: i16) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_i32(self, _: i32) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_i64(self, _: i64) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_u8(self, _: u8) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_u16(self, _: u16) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_u32(self, _: u32) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_u64(self, _: u64) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_f32(self, _: f32) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_f64(self, _: f64) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_char(self, _: char) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_str(self, _: &str) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_bytes(self, _: &[u8]) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_none(self) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_some<T: Serialize + ?Sized>(self, _: &T) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_unit(self) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_unit_struct(self, _: &'static str) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_unit_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+    ) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: &T,
+    ) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<(), M::Error> {
+        not_a_struct()
+    }
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, M::Error> {
+        not_a_struct()
+    }
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, M::Error> {
+        not_a_struct()
+    }
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, M::Error> {
+        not_a_struct()
+    }
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, M::Error> {
+        not_a_struct()
+    }
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, M::Error> {
+        not_a_struct()
+    }
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, M::Error> {
+        not_a_struct()
+    }
+}
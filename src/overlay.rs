@@ -0,0 +1,299 @@
+//! Metric overlays: color blocks by host-provided values.
+//!
+//! A [`MetricOverlay`] maps block paths (in [`BlockPath`] text form, e.g.
+//! `"Controller/Gain"`) to values such as execution coverage, CPU
+//! load or error counts. Values are normalized over the overlay's range and
+//! mapped onto a [`Gradient`]. The viewer paints blocks with these colors and
+//! shows a legend; [`render_svg`] exports the same view as an SVG document
+//! and [`display_list`] hands it to other [`DisplayList`] backends.
+
+use crate::block_path::BlockPath;
+use crate::color::Rgba;
+use crate::display_list::{Align, DisplayList, Primitive, Stroke};
+use crate::generator::thumbnail::diagram_geometry;
+use crate::model::System;
+use crate::signal_kind::SignalKind;
+use std::collections::HashMap;
+
+/// Piecewise-linear color gradient over `[0, 1]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f32, Rgba)>,
+}
+
+impl Gradient {
+    /// Gradient through `stops` (position, color). Positions are clamped to
+    /// `[0, 1]` and sorted.
+    pub fn new(stops: impl IntoIterator<Item = (f32, Rgba)>) -> Self {
+        let mut stops: Vec<(f32, Rgba)> = stops
+            .into_iter()
+            .map(|(t, c)| (if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) }, c))
+            .collect();
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    /// Blue (low) through yellow to red (high), for load and error counts.
+    pub fn heat() -> Self {
+        Self::new([
+            (0.0, Rgba::rgb(49, 99, 206)),
+            (0.5, Rgba::rgb(250, 215, 70)),
+            (1.0, Rgba::rgb(214, 47, 39)),
+        ])
+    }
+
+    /// Red (low) through yellow to green (high), for coverage.
+    pub fn coverage() -> Self {
+        Self::new([
+            (0.0, Rgba::rgb(214, 47, 39)),
+            (0.5, Rgba::rgb(250, 215, 70)),
+            (1.0, Rgba::rgb(58, 166, 85)),
+        ])
+    }
+
+    pub fn stops(&self) -> &[(f32, Rgba)] {
+        &self.stops
+    }
+
+    /// Color at position `t`, clamped to `[0, 1]`.
+    pub fn color_at(&self, t: f32) -> Rgba {
+        let t = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) };
+        let (Some(first), Some(last)) = (self.stops.first(), self.stops.last()) else {
+            return Rgba::rgb(128, 128, 128);
+        };
+        if t <= first.0 {
+            return first.1;
+        }
+        if t >= last.0 {
+            return last.1;
+        }
+        for w in self.stops.windows(2) {
+            let ((t0, c0), (t1, c1)) = (w[0], w[1]);
+            if t <= t1 {
+                let f = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1.0 };
+                let mix = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * f).round() as u8;
+                return Rgba::new(
+                    mix(c0.r, c1.r),
+                    mix(c0.g, c1.g),
+                    mix(c0.b, c1.b),
+                    mix(c0.a, c1.a),
+                );
+            }
+        }
+        last.1
+    }
+}
+
+impl Default for Gradient {
+    fn default() -> Self {
+        Self::heat()
+    }
+}
+
+/// Named set of per-block metric values.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricOverlay {
+    /// Shown in the legend and used to select the overlay.
+    pub name: String,
+    /// Values keyed by block path from the root, e.g. `"Controller/Gain"`.
+    pub values: HashMap<String, f32>,
+    /// Value range mapped onto the gradient; `None` uses the data's range.
+    pub range: Option<(f32, f32)>,
+    pub gradient: Gradient,
+}
+
+impl MetricOverlay {
+    /// Overlay with the [`Gradient::heat`] gradient and automatic range.
+    /// A leading `/` marking an absolute path is ignored.
+    pub fn new(name: impl Into<String>, values: HashMap<String, f32>) -> Self {
+        let values = values
+            .into_iter()
+            .map(|(k, v)| (normalize_path(&k), v))
+            .collect();
+        Self {
+            name: name.into(),
+            values,
+            range: None,
+            gradient: Gradient::default(),
+        }
+    }
+
+    pub fn with_range(mut self, min: f32, max: f32) -> Self {
+        self.range = Some((min, max));
+        self
+    }
+
+    pub fn with_gradient(mut self, gradient: Gradient) -> Self {
+        self.gradient = gradient;
+        self
+    }
+
+    /// The explicit range, or the minimum and maximum of the finite values.
+    pub fn value_range(&self) -> Option<(f32, f32)> {
+        if self.range.is_some() {
+            return self.range;
+        }
+        self.values
+            .values()
+            .copied()
+            .filter(|v| v.is_finite())
+            .fold(None, |acc, v| match acc {
+                None => Some((v, v)),
+                Some((lo, hi)) => Some((lo.min(v), hi.max(v))),
+            })
+    }
+
+    /// Value for the block at `path`, if the host provided one.
+    pub fn value(&self, path: &str) -> Option<f32> {
+        self.values.get(&normalize_path(path)).copied()
+    }
+
+    /// Gradient color for `value` within [`Self::value_range`]. A range of
+    /// zero width maps everything to the top of the gradient.
+    pub fn color_for_value(&self, value: f32) -> Rgba {
+        let t = match self.value_range() {
+            Some((lo, hi)) if hi > lo => (value - lo) / (hi - lo),
+            _ => 1.0,
+        };
+        self.gradient.color_at(t)
+    }
+
+    /// Color for the block at `path`, if it has a value.
+    pub fn color_for(&self, path: &str) -> Option<Rgba> {
+        self.value(path).map(|v| self.color_for_value(v))
+    }
+}
+
+/// `path` without the absolute marker; unparsable paths are kept as given.
+fn normalize_path(path: &str) -> String {
+    path.parse::<BlockPath>()
+        .map_or_else(|_| path.to_string(), |p| p.to_string())
+}
+
+/// Path of a block named `block_name` inside the system at `system_path`,
+/// in [`BlockPath`] text form.
+pub fn block_path(system_path: &[String], block_name: &str) -> String {
+    BlockPath::from(system_path).child(block_name).to_string()
+}
+
+const SVG_MARGIN: f32 = 20.0;
+const LEGEND_WIDTH: f32 = 160.0;
+
+/// Render `system` (located at `system_path`) as an SVG document, coloring
+/// blocks from `overlay` and adding its legend below the diagram.
+///
+/// Coordinates are model coordinates; blocks without a value keep their
+/// own background color.
+pub fn render_svg(
+    system: &System,
+    system_path: &[String],
+    overlay: Option<&MetricOverlay>,
+) -> String {
+    display_list(system, system_path, overlay).to_svg(SVG_MARGIN)
+}
+
+/// Render `system` like [`render_svg`], filling the blocks listed in
+/// `highlights` (keyed by block path) with the given colors and no legend.
+pub fn render_svg_highlighted(
+    system: &System,
+    system_path: &[String],
+    highlights: &HashMap<String, Rgba>,
+) -> String {
+    let fill = |path: &str| highlights.get(path).map(|c| (*c, path.to_string()));
+    display_list_with(system, system_path, &fill, None).to_svg(SVG_MARGIN)
+}
+
+/// The drawing behind [`render_svg`], for other backends such as
+/// [`DisplayList::to_png`].
+pub fn display_list(
+    system: &System,
+    system_path: &[String],
+    overlay: Option<&MetricOverlay>,
+) -> DisplayList {
+    let fill = |path: &str| {
+        let (o, v) = (overlay?, overlay?.value(path)?);
+        Some((o.color_for_value(v), format!("{path}: {v}")))
+    };
+    display_list_with(system, system_path, &fill, overlay)
+}
+
+/// Shared layout. `fill` gives the fill color and tooltip of a block by
+/// path; blocks without one keep their own background color.
+fn display_list_with(
+    system: &System,
+    system_path: &[String],
+    fill: &dyn Fn(&str) -> Option<(Rgba, String)>,
+    overlay: Option<&MetricOverlay>,
+) -> DisplayList {
+    let (rects, polylines) = diagram_geometry(system);
+    let black = Rgba::rgb(0, 0, 0);
+    let mut list = DisplayList::new().with_background(Rgba::rgb(255, 255, 255));
+
+    for (kind, points) in polylines {
+        let stroke = match kind {
+            SignalKind::Scalar => Stroke::new(1.0, black),
+            SignalKind::Vector(_) => Stroke::new(2.0, black),
+            SignalKind::Bus => Stroke::new(3.0, black),
+            SignalKind::Control => Stroke::new(1.0, black).dashed(4.0, 3.0),
+        };
+        list.push(Primitive::Polyline {
+            points,
+            stroke,
+            arrow: false,
+        });
+    }
+
+    for (block, r) in &rects {
+        let path = block_path(system_path, &block.name);
+        let (fill, title) = fill(&path).unwrap_or_else(|| {
+            let background = block.background_color.unwrap_or(Rgba::rgb(255, 255, 255));
+            (background, path.clone())
+        });
+        let stroke = block.foreground_color.unwrap_or(Rgba::rgb(40, 40, 40));
+        list.push(Primitive::Rect {
+            rect: *r,
+            corner_radius: 0.0,
+            fill: Some(fill),
+            stroke: Some(Stroke::new(1.0, stroke)),
+            title: Some(title),
+        });
+        list.push(Primitive::Text {
+            pos: ((r[0] + r[2]) / 2.0, r[1].max(r[3]) + 12.0),
+            offset: (0.0, 0.0),
+            text: block.name.clone(),
+            size: 10.0,
+            color: black,
+            anchor: [Align::Center, Align::Max],
+        });
+    }
+
+    if let Some(overlay) = overlay {
+        let bounds = list.bounds().unwrap_or([0.0; 4]);
+        let x = bounds[0];
+        let y = bounds[3] + SVG_MARGIN + 8.0;
+        let label = |pos, text: String, size, h| Primitive::Text {
+            pos,
+            offset: (0.0, 0.0),
+            text,
+            size,
+            color: black,
+            anchor: [h, Align::Max],
+        };
+        list.push(label((x, y), overlay.name.clone(), 11.0, Align::Min));
+        list.push(Primitive::GradientRect {
+            rect: [x, y + 5.0, x + LEGEND_WIDTH, y + 15.0],
+            stops: overlay.gradient.stops().to_vec(),
+            stroke: Some(Stroke::new(0.5, black)),
+        });
+        if let Some((lo, hi)) = overlay.value_range() {
+            list.push(label((x, y + 27.0), lo.to_string(), 10.0, Align::Min));
+            list.push(label(
+                (x + LEGEND_WIDTH, y + 27.0),
+                hi.to_string(),
+                10.0,
+                Align::Max,
+            ));
+        }
+    }
+    list
+}
@@ -1,14 +1,22 @@
 //! Stateflow chart XML parsing.
 
+use super::limits::ParseLimits;
 use crate::model::*;
-use anyhow::{Context, Result, anyhow};
-use roxmltree::Document;
+use anyhow::{Result, anyhow};
 use std::collections::BTreeMap;
 
 /// Parse a Stateflow chart from its XML text.
 pub fn parse_chart_from_text(text: &str, path_hint: Option<&str>) -> Result<Chart> {
-    let doc = Document::parse(text)
-        .with_context(|| format!("Failed to parse XML {}", path_hint.unwrap_or("<chart>")))?;
+    parse_chart_with_limits(text, path_hint, &ParseLimits::default())
+}
+
+/// [`parse_chart_from_text`] within the given [`ParseLimits`].
+pub fn parse_chart_with_limits(
+    text: &str,
+    path_hint: Option<&str>,
+    limits: &ParseLimits,
+) -> Result<Chart> {
+    let doc = limits.parse_xml(text, path_hint.unwrap_or("<chart>"))?;
     let chart_node = doc
         .descendants()
         .find(|n| n.is_element() && n.has_tag_name("chart"))
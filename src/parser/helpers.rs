@@ -36,7 +36,7 @@ pub fn parse_endpoint(s: &str) -> Result<EndpointRef> {
     let (ptype, pidx_str) = rest
         .split_once(':')
         .ok_or_else(|| anyhow!("Invalid endpoint port format: {}", s))?;
-    let port_type = ptype.trim().to_string();
+    let port_type = PortKind::from(ptype.trim());
     let port_index: u32 = pidx_str.trim().parse()?;
     Ok(EndpointRef {
         sid,
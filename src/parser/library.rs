@@ -1,5 +1,6 @@
 //! Library resolution – locate `.slx` library files on disk.
 
+use crate::block_path::BlockPath;
 use camino::{Utf8Path, Utf8PathBuf};
 
 /// Result for library resolution: which libraries were found (with path)
@@ -52,17 +53,21 @@ fn normalize_segment(seg: &str) -> String {
     seg.to_ascii_lowercase()
 }
 
-fn split_path_segments(path: &str) -> Vec<String> {
-    let path = path.replace('\\', "/");
-    path.split('/')
-        .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-        .collect()
+/// Names of the [`BlockPath`] `path`, with runs of whitespace in each name
+/// collapsed to one space. `None` if `path` has an empty name.
+fn split_path_segments(path: &str) -> Option<Vec<String>> {
+    let path: BlockPath = path.parse().ok()?;
+    Some(
+        path.iter()
+            .map(|s| s.split_whitespace().collect::<Vec<_>>().join(" "))
+            .filter(|s| !s.is_empty())
+            .collect(),
+    )
 }
 
 /// Split a `SourceBlock`-style reference (`Library/Block/...`) into
-/// `(library_name, block_path)`.
+/// `(library_name, block_path)`, where `block_path` is a [`BlockPath`]
+/// string below the library, so `lib/In//Out` is block `In/Out` of `lib`.
 ///
 /// Unlike a simple `split_once('/')`, this function supports "virtual libraries"
 /// whose logical names include sub-paths (e.g. `simulink/Discrete`). It performs
@@ -70,11 +75,7 @@ fn split_path_segments(path: &str) -> Vec<String> {
 ///
 /// Returns `None` if the input does not contain at least one `/` separator.
 pub fn split_source_block_reference(source_block: &str) -> Option<(String, String)> {
-    let source_block = source_block.trim();
-    if source_block.is_empty() {
-        return None;
-    }
-    let segs = split_path_segments(source_block);
+    let segs = split_path_segments(source_block.trim())?;
     if segs.len() < 2 {
         return None;
     }
@@ -83,7 +84,7 @@ pub fn split_source_block_reference(source_block: &str) -> Option<(String, Strin
     let mut best_prefix: Option<&'static str> = None;
     let mut best_len: usize = 0;
     for prefix in SPECIAL_VIRTUAL_LIBRARIES {
-        let p_segs = split_path_segments(prefix);
+        let p_segs = split_path_segments(prefix).unwrap_or_default();
         if p_segs.is_empty() || segs.len() <= p_segs.len() {
             continue;
         }
@@ -101,7 +102,7 @@ pub fn split_source_block_reference(source_block: &str) -> Option<(String, Strin
     }
 
     if let Some(prefix) = best_prefix {
-        let rest = segs[best_len..].join("/");
+        let rest = BlockPath::from(&segs[best_len..]).to_string();
         return Some((prefix.to_string(), rest));
     }
 
@@ -111,7 +112,7 @@ pub fn split_source_block_reference(source_block: &str) -> Option<(String, Strin
         .strip_suffix(".slx")
         .or_else(|| lib.strip_suffix(".SLX"))
         .unwrap_or(lib);
-    let rest = segs[1..].join("/");
+    let rest = BlockPath::from(&segs[1..]).to_string();
     Some((lib.to_string(), rest))
 }
 
@@ -123,13 +124,12 @@ pub fn is_virtual_library(name: &str) -> bool {
     if name.is_empty() {
         return false;
     }
-    let segs = split_path_segments(name);
-    if segs.is_empty() {
+    let Some(segs) = split_path_segments(name).filter(|s| !s.is_empty()) else {
         return false;
-    }
+    };
 
     SPECIAL_VIRTUAL_LIBRARIES.iter().any(|prefix| {
-        let p_segs = split_path_segments(prefix);
+        let p_segs = split_path_segments(prefix).unwrap_or_default();
         if p_segs.is_empty() {
             return false;
         }
@@ -0,0 +1,152 @@
+//! Resource limits for parsing untrusted models.
+//!
+//! Services that parse uploaded `.slx` files must not run out of memory or
+//! stack on a crafted archive. [`ParseLimits`] bounds the uncompressed size
+//! read from a ZIP archive (against zip bombs and lying size headers), the
+//! number of XML nodes per file, the XML element nesting depth, the bytes and
+//! blocks read for one model and the subsystem tree built by linking
+//! `<System Ref>` references. DTDs, and with them custom entities, are always
+//! rejected. Exceeding a limit fails with a [`LimitExceeded`] error.
+
+use anyhow::{Context, Result};
+use roxmltree::{Document, ParsingOptions};
+use serde::Serialize;
+
+/// Upper bounds enforced while parsing.
+///
+/// The defaults are far above what real models need; set a field to its
+/// type's `MAX` to disable that check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseLimits {
+    /// Maximum XML element nesting depth of one file.
+    pub max_depth: usize,
+    /// Maximum subsystem nesting after linking referenced systems.
+    pub max_subsystem_depth: usize,
+    /// Maximum number of XML nodes (elements, text, comments, …) in one file.
+    pub max_xml_nodes: u32,
+    /// Maximum uncompressed size of one archive member, in bytes.
+    pub max_file_size: u64,
+    /// Maximum uncompressed bytes read from one archive in total.
+    pub max_total_size: u64,
+    /// Maximum bytes of model files (system and chart XML) the parser reads
+    /// for one [`parse_system_file`](super::SimulinkParser::parse_system_file)
+    /// call, whatever the content source.
+    pub max_total_xml_bytes: u64,
+    /// Maximum number of blocks in the system files read for one parse,
+    /// before referenced systems are copied in by linking.
+    pub max_blocks: usize,
+    /// Maximum number of blocks in the linked system tree. References are
+    /// copied into every block that uses them, so a few small files can
+    /// otherwise expand into an exponentially large tree.
+    pub max_linked_blocks: usize,
+}
+
+impl Default for ParseLimits {
+    fn default() -> Self {
+        ParseLimits {
+            max_depth: 256,
+            max_subsystem_depth: 256,
+            max_xml_nodes: 8_000_000,
+            max_file_size: 512 * 1024 * 1024,
+            max_total_size: 2 * 1024 * 1024 * 1024,
+            max_total_xml_bytes: 2 * 1024 * 1024 * 1024,
+            max_blocks: 1_000_000,
+            max_linked_blocks: 2_000_000,
+        }
+    }
+}
+
+/// Which of the [`ParseLimits`] was exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitKind {
+    Depth,
+    SubsystemDepth,
+    XmlNodes,
+    FileSize,
+    TotalSize,
+    TotalXmlBytes,
+    Blocks,
+    LinkedBlocks,
+}
+
+impl LimitKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            LimitKind::Depth => "nesting depth",
+            LimitKind::SubsystemDepth => "subsystem nesting depth",
+            LimitKind::XmlNodes => "XML node count",
+            LimitKind::FileSize => "uncompressed file size",
+            LimitKind::TotalSize => "total uncompressed size",
+            LimitKind::TotalXmlBytes => "total model file size",
+            LimitKind::Blocks => "block count",
+            LimitKind::LinkedBlocks => "linked block count",
+        }
+    }
+}
+
+/// Error returned when a model exceeds one of the [`ParseLimits`].
+///
+/// Recover it with `err.downcast_ref::<LimitExceeded>()`, e.g. to reject an
+/// upload with a client error instead of an internal one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LimitExceeded {
+    pub kind: LimitKind,
+    pub limit: u64,
+    /// File or system the limit was exceeded in.
+    pub path: String,
+}
+
+impl LimitExceeded {
+    pub(crate) fn new(kind: LimitKind, limit: impl TryInto<u64>, path: impl Into<String>) -> Self {
+        LimitExceeded {
+            kind,
+            limit: limit.try_into().unwrap_or(u64::MAX),
+            path: path.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for LimitExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} exceeds the {} limit of {}",
+            self.path,
+            self.kind.name(),
+            self.limit
+        )
+    }
+}
+
+impl std::error::Error for LimitExceeded {}
+
+impl ParseLimits {
+    /// Parse `text` as XML without DTDs, within the node and depth limits.
+    pub fn parse_xml<'input>(&self, text: &'input str, path: &str) -> Result<Document<'input>> {
+        let options = ParsingOptions {
+            allow_dtd: false,
+            nodes_limit: self.max_xml_nodes,
+        };
+        let doc = match Document::parse_with_options(text, options) {
+            Err(roxmltree::Error::NodesLimitReached) => {
+                return Err(
+                    LimitExceeded::new(LimitKind::XmlNodes, self.max_xml_nodes, path).into(),
+                );
+            }
+            doc => doc.with_context(|| format!("Failed to parse XML {}", path))?,
+        };
+        // roxmltree itself parses iteratively, but the system parser recurses
+        // into nested elements. Parents come before their children in
+        // document order, so one pass computes every depth.
+        let mut depths = vec![0usize; doc.descendants().count()];
+        for node in doc.descendants().filter(|n| n.is_element()) {
+            let depth = node.parent().map_or(0, |p| depths[p.id().get_usize()]) + 1;
+            if depth > self.max_depth {
+                return Err(LimitExceeded::new(LimitKind::Depth, self.max_depth, path).into());
+            }
+            depths[node.id().get_usize()] = depth;
+        }
+        Ok(doc)
+    }
+}
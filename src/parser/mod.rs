@@ -9,26 +9,46 @@
 //! - [`chart`] – Stateflow chart parsing
 //! - [`graphical_interface`] – `graphicalInterface.json` types
 //! - [`library`] – Library `.slx` file resolution
+//! - [`limits`] – Resource limits for untrusted models
+//! - [`pool`] – Parsers shared across threads by long-running services
+//! - [`profile`] – Per-phase timings
+//! - [`progress`] – Progress updates for background parsing
+//! - [`protected`] – Protected model / encrypted part detection
+//! - [`view`] – Zero-copy views for analysis-only scans
 
 pub mod chart;
 pub mod graphical_interface;
 pub mod helpers;
 pub mod library;
+pub mod limits;
+pub mod pool;
+pub mod profile;
+pub mod progress;
+pub mod protected;
 pub mod source;
+pub mod view;
 
 // Re-export key types at the parser module level for backward compatibility.
 pub use graphical_interface::*;
 pub use helpers::{parse_endpoint, parse_points, resolve_system_reference};
 pub use library::*;
+pub use limits::{LimitExceeded, LimitKind, ParseLimits};
+pub use pool::{ParserPool, SharedParser};
+pub use profile::{ParseProfile, ParserOptions, ProfilePhase};
+pub use progress::{ParsePhase, ParseProgress};
+pub use protected::{ModelProtectedError, ProtectionKind};
 pub use source::*;
 
 use crate::builtin_libraries::matrix_library;
+use crate::cancel::CancellationToken;
 use crate::model::*;
 use anyhow::{Context, Result, anyhow};
 use camino::{Utf8Path, Utf8PathBuf};
+use progress::ProgressReporter;
 use rayon::prelude::*;
-use roxmltree::Document;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::mpsc;
+use std::time::Instant;
 
 /// Core Simulink parser. Generic over [`ContentSource`] so it can read from
 /// the filesystem ([`FsSource`]) or from a ZIP archive ([`ZipSource`]).
@@ -39,10 +59,25 @@ pub struct SimulinkParser<S: ContentSource> {
     system_to_chart_map: BTreeMap<String, u32>,
     sid_to_chart_id: BTreeMap<String, u32>,
     systems_shallow_by_path: BTreeMap<String, System>,
+    /// `stateflow` directories whose charts are already in `charts_by_id`.
+    stateflow_dirs_scanned: BTreeSet<Utf8PathBuf>,
+    progress: Option<ProgressReporter>,
+    profile: Option<ParseProfile>,
+    limits: ParseLimits,
+    cancel: CancellationToken,
+    /// Bytes and blocks read by the current `parse_system_file` call, checked
+    /// against [`ParseLimits::max_total_xml_bytes`] and
+    /// [`ParseLimits::max_blocks`].
+    bytes_read: u64,
+    blocks_read: usize,
 }
 
 impl<S: ContentSource> SimulinkParser<S> {
     pub fn new(root_dir: impl AsRef<Utf8Path>, source: S) -> Self {
+        Self::with_options(root_dir, source, ParserOptions::default())
+    }
+
+    pub fn with_options(root_dir: impl AsRef<Utf8Path>, source: S, options: ParserOptions) -> Self {
         Self {
             root_dir: root_dir.as_ref().to_path_buf(),
             source,
@@ -50,17 +85,112 @@ impl<S: ContentSource> SimulinkParser<S> {
             system_to_chart_map: BTreeMap::new(),
             sid_to_chart_id: BTreeMap::new(),
             systems_shallow_by_path: BTreeMap::new(),
+            stateflow_dirs_scanned: BTreeSet::new(),
+            progress: None,
+            profile: options.profile.then(ParseProfile::default),
+            limits: options.limits,
+            cancel: options.cancel,
+            bytes_read: 0,
+            blocks_read: 0,
+        }
+    }
+
+    /// Timings collected so far, if [`ParserOptions::profile`] is set.
+    pub fn profile(&self) -> Option<&ParseProfile> {
+        self.profile.as_ref()
+    }
+
+    /// Mutable access to the timings, e.g. to record time spent opening the
+    /// archive before the parser was created.
+    pub fn profile_mut(&mut self) -> Option<&mut ParseProfile> {
+        self.profile.as_mut()
+    }
+
+    fn record(&mut self, phase: ProfilePhase, start: Instant) {
+        if let Some(profile) = &mut self.profile {
+            profile.record(phase, start.elapsed());
+        }
+    }
+
+    fn read_to_string(&mut self, path: &Utf8Path) -> Result<String> {
+        let start = Instant::now();
+        let text = self.source.read_to_string(path);
+        self.record(ProfilePhase::ZipRead, start);
+        let text = text?;
+        self.bytes_read += text.len() as u64;
+        if self.bytes_read > self.limits.max_total_xml_bytes {
+            return Err(LimitExceeded::new(
+                LimitKind::TotalXmlBytes,
+                self.limits.max_total_xml_bytes,
+                path.as_str(),
+            )
+            .into());
         }
+        Ok(text)
+    }
+
+    /// Fail if the files read so far exceed the budget of one parse. Reads
+    /// during preloading skip files that fail, so exceeding the budget there
+    /// is reported here.
+    fn check_budget(&self, path: &Utf8Path) -> Result<()> {
+        let limits = &self.limits;
+        if self.bytes_read > limits.max_total_xml_bytes {
+            return Err(LimitExceeded::new(
+                LimitKind::TotalXmlBytes,
+                limits.max_total_xml_bytes,
+                path.as_str(),
+            )
+            .into());
+        }
+        if self.blocks_read > limits.max_blocks {
+            return Err(
+                LimitExceeded::new(LimitKind::Blocks, limits.max_blocks, path.as_str()).into(),
+            );
+        }
+        Ok(())
+    }
+
+    fn list_dir(&mut self, path: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+        let start = Instant::now();
+        let paths = self.source.list_dir(path);
+        self.record(ProfilePhase::ZipRead, start);
+        paths
+    }
+
+    /// Send [`ParseProgress`] updates to `sender` while parsing.
+    pub fn set_progress_sender(&mut self, sender: mpsc::Sender<ParseProgress>) {
+        self.progress = Some(ProgressReporter::new(sender));
+    }
+
+    /// Receive [`ParseProgress`] updates while parsing. The channel closes
+    /// when the parser is dropped.
+    pub fn progress_channel(&mut self) -> mpsc::Receiver<ParseProgress> {
+        let (sender, receiver) = mpsc::channel();
+        self.set_progress_sender(sender);
+        receiver
     }
 
     /// Parse a system XML file into a [`System`], resolving subsystem references.
     pub fn parse_system_file(&mut self, path: impl AsRef<Utf8Path>) -> Result<System> {
         let path = path.as_ref();
+        if let Some(progress) = &self.progress {
+            progress.reset();
+        }
+        self.cancel.check()?;
+        self.bytes_read = 0;
+        self.blocks_read = 0;
         self.try_parse_stateflow_for(path);
         self.try_preload_systems_for(path);
-        let text = self.source.read_to_string(path)?;
-        let doc =
-            Document::parse(&text).with_context(|| format!("Failed to parse XML {}", path))?;
+        // Both skip their files once cancelled.
+        self.cancel.check()?;
+        self.check_budget(path)?;
+        if let Some(progress) = &self.progress {
+            progress.discovered(1);
+        }
+        let text = self.read_to_string(path)?;
+        let start = Instant::now();
+        let doc = self.limits.parse_xml(&text, path.as_str())?;
+        self.record(ProfilePhase::XmlParse, start);
         let system_node = doc
             .descendants()
             .find(|n| n.has_tag_name("System"))
@@ -69,16 +199,33 @@ impl<S: ContentSource> SimulinkParser<S> {
             .parent()
             .map(|p| p.to_owned())
             .unwrap_or_else(|| self.root_dir.clone());
+        let start = Instant::now();
         let mut sys = crate::block::parse_system_shallow(system_node, base_dir_owned.as_path())?;
-        self.link_system_refs(&mut sys, base_dir_owned.as_path());
+        self.record(ProfilePhase::ShallowParse, start);
+        // Preloaded files are counted already.
+        if !self.systems_shallow_by_path.contains_key(path.as_str()) {
+            self.blocks_read += count_blocks(&sys);
+            self.check_budget(path)?;
+        }
+        if let Some(progress) = &self.progress {
+            progress.parsed(ParsePhase::Systems, path);
+            progress.phase(ParsePhase::Linking);
+        }
+        let start = Instant::now();
+        let linked = self.link_system_refs(&mut sys, path, base_dir_owned.as_path());
+        self.record(ProfilePhase::Linking, start);
+        linked?;
+        if let Some(progress) = &self.progress {
+            progress.phase(ParsePhase::Done);
+        }
         Ok(sys)
     }
 
     /// Parse a Stateflow chart XML file.
     pub fn parse_chart_file(&mut self, path: impl AsRef<Utf8Path>) -> Result<Chart> {
         let path = path.as_ref();
-        let text = self.source.read_to_string(path)?;
-        chart::parse_chart_from_text(&text, Some(path.as_str()))
+        let text = self.read_to_string(path)?;
+        chart::parse_chart_with_limits(&text, Some(path.as_str()), &self.limits)
     }
 
     /// Parse `simulink/graphicalInterface.json`.
@@ -87,7 +234,7 @@ impl<S: ContentSource> SimulinkParser<S> {
         path: impl AsRef<Utf8Path>,
     ) -> Result<GraphicalInterface> {
         let path = path.as_ref();
-        let text = self.source.read_to_string(path)?;
+        let text = self.read_to_string(path)?;
         let v: serde_json::Value = serde_json::from_str(&text)
             .with_context(|| format!("Failed to parse JSON {}", path))?;
         let gi_value = v
@@ -116,10 +263,34 @@ impl<S: ContentSource> SimulinkParser<S> {
         Ok(gi.library_block_references_by_library())
     }
 
+    /// [`Self::resolve_library_references`], timed as
+    /// [`ProfilePhase::LibraryResolution`] when profiling.
+    pub fn resolve_libraries(
+        &mut self,
+        system: &mut System,
+        lib_paths: &[Utf8PathBuf],
+    ) -> Result<()> {
+        let start = Instant::now();
+        let result = Self::resolve_library_references_with_cancel(system, lib_paths, &self.cancel);
+        self.record(ProfilePhase::LibraryResolution, start);
+        result
+    }
+
     /// Resolve library references in a parsed system.
     pub fn resolve_library_references(
         system: &mut System,
         lib_paths: &[Utf8PathBuf],
+    ) -> Result<()> {
+        Self::resolve_library_references_with_cancel(system, lib_paths, &CancellationToken::new())
+    }
+
+    /// [`Self::resolve_library_references`], stopping with a
+    /// [`Cancelled`](crate::cancel::Cancelled) error once `cancel` is
+    /// cancelled. Libraries are parsed with the same token.
+    pub fn resolve_library_references_with_cancel(
+        system: &mut System,
+        lib_paths: &[Utf8PathBuf],
+        cancel: &CancellationToken,
     ) -> Result<()> {
         use std::collections::HashMap;
         let mut library_cache: HashMap<String, System> = HashMap::new();
@@ -131,6 +302,7 @@ impl<S: ContentSource> SimulinkParser<S> {
             &resolver,
             &mut library_cache,
             suppress_missing_external_warnings,
+            cancel,
         )?;
         Ok(())
     }
@@ -141,6 +313,7 @@ impl<S: ContentSource> SimulinkParser<S> {
         resolver: &LibraryResolver,
         cache: &mut std::collections::HashMap<String, System>,
         suppress_missing_external_warnings: bool,
+        cancel: &CancellationToken,
     ) -> Result<()> {
         fn warn_yellow(msg: impl AsRef<str>) {
             // ANSI yellow; printed to stderr.
@@ -158,6 +331,7 @@ impl<S: ContentSource> SimulinkParser<S> {
         }
 
         for block in &mut system.blocks {
+            cancel.check()?;
             let block_host_path = if system_path.is_empty() {
                 format!("/{}", block.name)
             } else {
@@ -190,11 +364,12 @@ impl<S: ContentSource> SimulinkParser<S> {
                         } else {
                             let lookup = resolver.locate(std::iter::once(lib_name));
                             if let Some((_, lib_file)) = lookup.found.first() {
-                                match Self::parse_library_file(lib_file) {
+                                match Self::parse_library_file(lib_file, cancel) {
                                     Ok(lib_system) => {
                                         cache.insert(lib_name.to_string(), lib_system);
                                     }
                                     Err(e) => {
+                                        cancel.check()?;
                                         // sanitize each piece so stray whitespace doesn't
                                         // create confusing log lines
                                         let lib_name_clean =
@@ -265,14 +440,18 @@ impl<S: ContentSource> SimulinkParser<S> {
                         }
                     }
                     if let Some(lib_system) = cache.get(lib_name) {
-                        if let Some(lib_block) = Self::find_block_by_name(lib_system, block_path) {
-                            if let Some(ref lib_subsystem) = lib_block.subsystem {
-                                block.subsystem = Some(lib_subsystem.clone());
+                        if let Some(lib_block) = Self::find_library_block(lib_system, block_path) {
+                            // take over the library block's content and ports so that
+                            // the host block renders properly, with the instance's
+                            // parameter overrides applied on top.
+                            for missing in block.instantiate_library_block(&lib_block) {
+                                let host_clean =
+                                    crate::parser::helpers::clean_whitespace(&block_host_path);
+                                warn_yellow(format!(
+                                    "overridden block '{}' not found in library block '{}' (requested by '{}')",
+                                    missing, source_block, host_clean
+                                ));
                             }
-                            // copy relevant metadata from the library stub so that the
-                            // host block can be rendered with proper ports, etc.
-                            block.port_counts = lib_block.port_counts.clone();
-                            block.ports = lib_block.ports.clone();
 
                             block.library_source = Some(lib_name.to_string());
                             block.library_block_path = Some(source_block.clone());
@@ -301,23 +480,32 @@ impl<S: ContentSource> SimulinkParser<S> {
                     resolver,
                     cache,
                     suppress_missing_external_warnings,
+                    cancel,
                 )?;
             }
         }
         Ok(())
     }
 
-    fn parse_library_file(lib_path: &Utf8Path) -> Result<System> {
+    fn parse_library_file(lib_path: &Utf8Path, cancel: &CancellationToken) -> Result<System> {
         let file = std::fs::File::open(lib_path.as_std_path())
             .with_context(|| format!("Open library {}", lib_path))?;
         let reader = std::io::BufReader::new(file);
-        let mut parser = SimulinkParser::new("", ZipSource::new(reader)?);
+        let options = ParserOptions {
+            cancel: cancel.clone(),
+            ..ParserOptions::default()
+        };
+        let mut parser = SimulinkParser::with_options("", ZipSource::new(reader)?, options);
         let root = Utf8PathBuf::from("simulink/systems/system_root.xml");
         parser.parse_system_file(&root)
     }
 
-    fn find_block_by_name(system: &System, name: &str) -> Option<Block> {
-        system.blocks.iter().find(|b| b.name == name).cloned()
+    /// The block at `path`, a [`BlockPath`](crate::block_path::BlockPath)
+    /// string below the library root.
+    /// Stubs of virtual libraries are named by the whole path instead.
+    fn find_library_block(system: &System, path: &str) -> Option<Block> {
+        let stub = system.blocks.iter().find(|b| b.name == path);
+        stub.or_else(|| system.block_at(path)).cloned()
     }
 }
 
@@ -340,7 +528,10 @@ impl<S: ContentSource> SimulinkParser<S> {
         }
         let sim_root: Utf8PathBuf = found_root.unwrap_or_else(|| self.root_dir.clone());
         let stateflow_dir = sim_root.join("stateflow");
-        if let Ok(paths) = self.source.list_dir(&stateflow_dir) {
+        if self.stateflow_dirs_scanned.contains(&stateflow_dir) {
+            return;
+        }
+        if let Ok(paths) = self.list_dir(&stateflow_dir) {
             let chart_paths: Vec<Utf8PathBuf> = paths
                 .into_iter()
                 .filter(|p| {
@@ -348,17 +539,39 @@ impl<S: ContentSource> SimulinkParser<S> {
                         .is_some_and(|f| f.starts_with("chart_") && f.ends_with(".xml"))
                 })
                 .collect();
+            if let Some(progress) = &self.progress {
+                progress.discovered(chart_paths.len());
+                progress.phase(ParsePhase::Charts);
+            }
             let mut texts: Vec<(String, String)> = Vec::new();
             for p in &chart_paths {
-                if let Ok(t) = self.source.read_to_string(p) {
+                if let Ok(t) = self.read_to_string(p) {
                     texts.push((p.as_str().to_string(), t));
                 }
             }
-            let parsed: Vec<Chart> = texts
+            let progress = self.progress.as_ref();
+            let limits = &self.limits;
+            let cancel = &self.cancel;
+            let parsed: Vec<(Option<Chart>, std::time::Duration)> = texts
                 .par_iter()
-                .filter_map(|(p, t)| chart::parse_chart_from_text(t, Some(p)).ok())
+                .map(|(p, t)| {
+                    let start = Instant::now();
+                    let chart = (!cancel.is_cancelled())
+                        .then(|| chart::parse_chart_with_limits(t, Some(p), limits).ok())
+                        .flatten();
+                    if let Some(progress) = progress {
+                        progress.parsed(ParsePhase::Charts, Utf8Path::new(p));
+                    }
+                    (chart, start.elapsed())
+                })
                 .collect();
-            for chart in parsed {
+            for (chart, duration) in parsed {
+                if let Some(profile) = &mut self.profile {
+                    profile.record(ProfilePhase::ChartParse, duration);
+                }
+                let Some(chart) = chart else {
+                    continue;
+                };
                 if let Some(id) = chart.id {
                     let ch = self.charts_by_id.entry(id).or_insert(chart);
                     if let Some(nm) = ch.name.clone() {
@@ -366,6 +579,10 @@ impl<S: ContentSource> SimulinkParser<S> {
                     }
                 }
             }
+            // Charts skipped after cancelling must not count as loaded.
+            if !self.cancel.is_cancelled() {
+                self.stateflow_dirs_scanned.insert(stateflow_dir);
+            }
         }
     }
 
@@ -396,7 +613,7 @@ impl<S: ContentSource> SimulinkParser<S> {
         }
         let sim_root: Utf8PathBuf = found_root.unwrap_or_else(|| self.root_dir.clone());
         let systems_dir = sim_root.join("systems");
-        if let Ok(paths) = self.source.list_dir(&systems_dir) {
+        if let Ok(paths) = self.list_dir(&systems_dir) {
             let sys_paths: Vec<Utf8PathBuf> = paths
                 .into_iter()
                 .filter(|p| {
@@ -411,33 +628,55 @@ impl<S: ContentSource> SimulinkParser<S> {
             if to_read.is_empty() {
                 return;
             }
+            if let Some(progress) = &self.progress {
+                progress.discovered(to_read.len());
+                progress.phase(ParsePhase::Systems);
+            }
             let mut pairs: Vec<(Utf8PathBuf, String)> = Vec::new();
             for p in &to_read {
-                if let Ok(t) = self.source.read_to_string(p) {
+                if let Ok(t) = self.read_to_string(p) {
                     pairs.push((p.clone(), t));
                 }
             }
-            let parsed: Vec<(Utf8PathBuf, Result<System>)> = pairs
+            let progress = self.progress.as_ref();
+            let limits = &self.limits;
+            let cancel = &self.cancel;
+            // Timings of the XML and shallow parse of each file.
+            type Timed = (Utf8PathBuf, Result<System>, [std::time::Duration; 2]);
+            let parsed: Vec<Timed> = pairs
                 .par_iter()
                 .map(|(p, t)| {
-                    let res = Document::parse(t)
-                        .with_context(|| format!("Failed to parse XML {}", p))
-                        .and_then(|doc| {
-                            let sysnode = doc
-                                .descendants()
-                                .find(|n| n.is_element() && n.has_tag_name("System"))
-                                .ok_or_else(|| anyhow!("No <System> root in {}", p))?;
-                            let base_dir_owned: Utf8PathBuf = p
-                                .parent()
-                                .map(|pp| pp.to_owned())
-                                .unwrap_or_else(|| systems_dir.clone());
-                            crate::block::parse_system_shallow(sysnode, base_dir_owned.as_path())
-                        });
-                    (p.clone(), res)
+                    let start = Instant::now();
+                    let doc = cancel
+                        .check()
+                        .map_err(anyhow::Error::from)
+                        .and_then(|()| limits.parse_xml(t, p.as_str()));
+                    let xml_time = start.elapsed();
+                    let start = Instant::now();
+                    let res = doc.and_then(|doc| {
+                        let sysnode = doc
+                            .descendants()
+                            .find(|n| n.is_element() && n.has_tag_name("System"))
+                            .ok_or_else(|| anyhow!("No <System> root in {}", p))?;
+                        let base_dir_owned: Utf8PathBuf = p
+                            .parent()
+                            .map(|pp| pp.to_owned())
+                            .unwrap_or_else(|| systems_dir.clone());
+                        crate::block::parse_system_shallow(sysnode, base_dir_owned.as_path())
+                    });
+                    if let Some(progress) = progress {
+                        progress.parsed(ParsePhase::Systems, p);
+                    }
+                    (p.clone(), res, [xml_time, start.elapsed()])
                 })
                 .collect();
-            for (p, res) in parsed {
+            for (p, res, [xml_time, shallow_time]) in parsed {
+                if let Some(profile) = &mut self.profile {
+                    profile.record(ProfilePhase::XmlParse, xml_time);
+                    profile.record(ProfilePhase::ShallowParse, shallow_time);
+                }
                 if let Ok(sys) = res {
+                    self.blocks_read += count_blocks(&sys);
                     self.systems_shallow_by_path
                         .insert(p.as_str().to_string(), sys);
                 }
@@ -445,21 +684,86 @@ impl<S: ContentSource> SimulinkParser<S> {
         }
     }
 
-    fn link_system_refs(&self, system: &mut System, current_base: &Utf8Path) {
+    /// Replace `<System Ref>` references below the system parsed from `path`
+    /// with copies of the referenced systems.
+    fn link_system_refs(
+        &self,
+        system: &mut System,
+        path: &Utf8Path,
+        current_base: &Utf8Path,
+    ) -> Result<()> {
+        let mut linker = Linker {
+            parser: self,
+            stack: vec![path.to_path_buf()],
+            blocks: 0,
+        };
+        linker.link(system, current_base, 0)
+    }
+}
+
+/// Blocks of `system` including those of inline subsystems.
+fn count_blocks(system: &System) -> usize {
+    system
+        .blocks
+        .iter()
+        .map(|b| 1 + b.subsystem.as_deref().map_or(0, count_blocks))
+        .sum()
+}
+
+/// State of [`SimulinkParser::link_system_refs`].
+struct Linker<'p, S: ContentSource> {
+    parser: &'p SimulinkParser<S>,
+    /// Files of the systems being linked. A reference back to one of them is
+    /// a cycle and stays unlinked instead of recursing forever.
+    stack: Vec<Utf8PathBuf>,
+    /// Blocks in the linked tree so far.
+    blocks: usize,
+}
+
+impl<S: ContentSource> Linker<'_, S> {
+    fn link(&mut self, system: &mut System, current_base: &Utf8Path, depth: usize) -> Result<()> {
+        let limits = &self.parser.limits;
+        let current = || self.stack.last().map(|p| p.to_string()).unwrap_or_default();
+        if depth > limits.max_subsystem_depth {
+            return Err(LimitExceeded::new(
+                LimitKind::SubsystemDepth,
+                limits.max_subsystem_depth,
+                current(),
+            )
+            .into());
+        }
+        self.parser.cancel.check()?;
+        self.blocks += system.blocks.len();
+        if self.blocks > limits.max_linked_blocks {
+            return Err(LimitExceeded::new(
+                LimitKind::LinkedBlocks,
+                limits.max_linked_blocks,
+                current(),
+            )
+            .into());
+        }
         for blk in &mut system.blocks {
             // Check for system_ref (external reference stored by the parser)
             if let Some(ref ref_name) = blk.system_ref {
                 let ref_path = helpers::resolve_system_reference(ref_name, current_base);
-                if let Some(sub) = self.systems_shallow_by_path.get(ref_path.as_str()) {
+                if self.stack.contains(&ref_path) {
+                    continue;
+                }
+                if let Some(sub) = self.parser.systems_shallow_by_path.get(ref_path.as_str()) {
                     let mut sub_cloned = sub.clone();
-                    let sub_base_dir = ref_path.parent().unwrap_or(current_base);
-                    self.link_system_refs(&mut sub_cloned, sub_base_dir);
+                    let sub_base_dir = ref_path.parent().unwrap_or(current_base).to_path_buf();
+                    self.stack.push(ref_path);
+                    let linked = self.link(&mut sub_cloned, &sub_base_dir, depth + 1);
+                    self.stack.pop();
+                    linked?;
                     blk.subsystem = Some(Box::new(sub_cloned));
+                    continue;
                 }
             }
             if let Some(ref mut sub) = blk.subsystem {
-                self.link_system_refs(sub, current_base);
+                self.link(sub, current_base, depth + 1)?;
             }
         }
+        Ok(())
     }
 }
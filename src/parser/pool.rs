@@ -0,0 +1,141 @@
+//! Parsers shared across threads by long-running services.
+//!
+//! A [`SimulinkParser`] caches the Stateflow charts and shallow-parsed system
+//! files of its model, so parsing another system of the same model, or the
+//! same one again, skips that work. [`ParserPool`] keeps one parser per model
+//! ID together with the systems parsed from it, so that request handlers on
+//! different threads reuse them instead of starting from the archive again.
+//!
+//! Each parser sits behind its own `Arc<RwLock<..>>`: parsing a system takes
+//! the write lock of that model only, while chart lookups and cached systems
+//! are served under read locks.
+
+use super::{ContentSource, ParserOptions, SimulinkParser};
+use crate::model::System;
+use anyhow::{Result, anyhow};
+use camino::{Utf8Path, Utf8PathBuf};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// A parser shared between threads.
+pub type SharedParser<S> = Arc<RwLock<SimulinkParser<S>>>;
+
+/// Reusable parsers and their parsed systems, by model ID.
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let pool = ParserPool::new();
+/// pool.open(&id, || ZipSource::new(std::io::Cursor::new(bytes)))?;
+/// let root = pool.system(&id, "simulink/systems/system_root.xml")?;
+/// let charts = pool.parser(&id).unwrap().read().unwrap().get_charts().len();
+/// ```
+pub struct ParserPool<S: ContentSource> {
+    options: ParserOptions,
+    parsers: RwLock<HashMap<String, SharedParser<S>>>,
+    /// Linked systems by model ID and system file.
+    systems: RwLock<HashMap<String, HashMap<Utf8PathBuf, Arc<System>>>>,
+}
+
+impl<S: ContentSource> Default for ParserPool<S> {
+    fn default() -> Self {
+        Self::with_options(ParserOptions::default())
+    }
+}
+
+impl<S: ContentSource> ParserPool<S> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A pool whose parsers are created with `options`.
+    pub fn with_options(options: ParserOptions) -> Self {
+        ParserPool {
+            options,
+            parsers: RwLock::default(),
+            systems: RwLock::default(),
+        }
+    }
+
+    /// The parser for model `id`, creating it from the source returned by
+    /// `source` if the pool has none yet. `source` is not called for a model
+    /// that is already open. It runs without holding the pool lock, so two
+    /// threads opening the same model may both call it; the parser of the
+    /// first one is kept.
+    pub fn open(&self, id: &str, source: impl FnOnce() -> Result<S>) -> Result<SharedParser<S>> {
+        if let Some(parser) = self.parser(id) {
+            return Ok(parser);
+        }
+        let parser = SimulinkParser::with_options("", source()?, self.options.clone());
+        let parser = Arc::new(RwLock::new(parser));
+        // Another thread may have opened the model since the check above.
+        let parser = write(&self.parsers)
+            .entry(id.to_string())
+            .or_insert(parser)
+            .clone();
+        Ok(parser)
+    }
+
+    /// The parser for model `id`, if it is open.
+    pub fn parser(&self, id: &str) -> Option<SharedParser<S>> {
+        read(&self.parsers).get(id).cloned()
+    }
+
+    /// The system at `path` in model `id`, parsed and linked on first use and
+    /// cached for later calls.
+    pub fn system(&self, id: &str, path: impl AsRef<Utf8Path>) -> Result<Arc<System>> {
+        let path = path.as_ref();
+        if let Some(system) = read(&self.systems).get(id).and_then(|s| s.get(path)) {
+            return Ok(system.clone());
+        }
+        let parser = self
+            .parser(id)
+            .ok_or_else(|| anyhow!("No open model with ID {}", id))?;
+        let mut parser = write(&parser);
+        // Parsed by another thread while this one waited for the parser.
+        if let Some(system) = read(&self.systems).get(id).and_then(|s| s.get(path)) {
+            return Ok(system.clone());
+        }
+        let system = Arc::new(parser.parse_system_file(path)?);
+        // Skip the cache if the model was removed while it was parsed.
+        if read(&self.parsers).contains_key(id) {
+            write(&self.systems)
+                .entry(id.to_string())
+                .or_default()
+                .insert(path.to_path_buf(), system.clone());
+        }
+        Ok(system)
+    }
+
+    /// Drop the parser and cached systems of model `id`. Returns whether the
+    /// model was open. Threads still holding its parser keep it alive until
+    /// they are done.
+    pub fn remove(&self, id: &str) -> bool {
+        let removed = write(&self.parsers).remove(id).is_some();
+        write(&self.systems).remove(id);
+        removed
+    }
+
+    /// IDs of the open models, in no particular order.
+    pub fn ids(&self) -> Vec<String> {
+        read(&self.parsers).keys().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        read(&self.parsers).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        read(&self.parsers).is_empty()
+    }
+}
+
+// A panic in another thread leaves the maps consistent, so poisoned locks
+// are used as they are.
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
+}
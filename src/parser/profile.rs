@@ -0,0 +1,146 @@
+//! Per-phase timings of a parse, for finding hotspots on large models.
+//!
+//! Enable with [`ParserOptions::profile`]; after parsing,
+//! [`SimulinkParser::profile`](super::SimulinkParser::profile) returns the
+//! collected [`ParseProfile`]. Phases that run on several threads (XML,
+//! shallow and chart parsing) add up the time of every worker, so they can
+//! exceed the wall-clock time.
+
+use super::limits::ParseLimits;
+use crate::cancel::CancellationToken;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::time::{Duration, Instant};
+
+/// Options for [`SimulinkParser`](super::SimulinkParser).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParserOptions {
+    /// Collect per-phase timings into a [`ParseProfile`].
+    pub profile: bool,
+    /// Bounds on file sizes, XML nesting and the linked system tree.
+    pub limits: ParseLimits,
+    /// Stops parsing and library resolution with a
+    /// [`Cancelled`](crate::cancel::Cancelled) error once cancelled.
+    pub cancel: CancellationToken,
+}
+
+/// A timed part of parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfilePhase {
+    /// Reading files from the content source (ZIP archive or filesystem).
+    ZipRead,
+    /// Building XML documents.
+    XmlParse,
+    /// Turning `<System>` elements into [`System`](crate::model::System)s.
+    ShallowParse,
+    /// Parsing Stateflow charts.
+    ChartParse,
+    /// Linking referenced systems into the tree.
+    Linking,
+    /// Resolving library links.
+    LibraryResolution,
+}
+
+impl ProfilePhase {
+    pub const ALL: [ProfilePhase; 6] = [
+        ProfilePhase::ZipRead,
+        ProfilePhase::XmlParse,
+        ProfilePhase::ShallowParse,
+        ProfilePhase::ChartParse,
+        ProfilePhase::Linking,
+        ProfilePhase::LibraryResolution,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ProfilePhase::ZipRead => "zip read",
+            ProfilePhase::XmlParse => "XML parse",
+            ProfilePhase::ShallowParse => "shallow parse",
+            ProfilePhase::ChartParse => "chart parse",
+            ProfilePhase::Linking => "linking",
+            ProfilePhase::LibraryResolution => "library resolution",
+        }
+    }
+}
+
+/// Accumulated time of one phase.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct PhaseTiming {
+    #[serde(rename = "ms", serialize_with = "serialize_ms")]
+    pub duration: Duration,
+    /// Number of timed calls (files, for per-file phases).
+    pub calls: usize,
+}
+
+fn serialize_ms<S: serde::Serializer>(d: &Duration, s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_f64(d.as_secs_f64() * 1000.0)
+}
+
+/// Timings collected while parsing.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ParseProfile {
+    pub phases: BTreeMap<ProfilePhase, PhaseTiming>,
+}
+
+impl ParseProfile {
+    /// Add `duration` to `phase`.
+    pub fn record(&mut self, phase: ProfilePhase, duration: Duration) {
+        let timing = self.phases.entry(phase).or_default();
+        timing.duration += duration;
+        timing.calls += 1;
+    }
+
+    /// Run `f`, adding its run time to `phase`.
+    pub fn time<T>(&mut self, phase: ProfilePhase, f: impl FnOnce() -> T) -> T {
+        let start = Instant::now();
+        let result = f();
+        self.record(phase, start.elapsed());
+        result
+    }
+
+    pub fn get(&self, phase: ProfilePhase) -> PhaseTiming {
+        self.phases.get(&phase).copied().unwrap_or_default()
+    }
+
+    /// Sum over all phases.
+    pub fn total(&self) -> Duration {
+        self.phases.values().map(|t| t.duration).sum()
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).unwrap_or_default()
+    }
+
+    /// Plain-text table of all phases with time, share of the total and
+    /// call count.
+    pub fn summary_table(&self) -> String {
+        let total = self.total().as_secs_f64();
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "{:<20} {:>10} {:>7} {:>7}",
+            "phase", "ms", "%", "calls"
+        );
+        for phase in ProfilePhase::ALL {
+            let timing = self.get(phase);
+            let secs = timing.duration.as_secs_f64();
+            let share = if total > 0.0 {
+                secs / total * 100.0
+            } else {
+                0.0
+            };
+            let _ = writeln!(
+                out,
+                "{:<20} {:>10.2} {:>6.1}% {:>7}",
+                phase.name(),
+                secs * 1000.0,
+                share,
+                timing.calls
+            );
+        }
+        let _ = writeln!(out, "{:<20} {:>10.2}", "total", total * 1000.0);
+        out
+    }
+}
@@ -0,0 +1,115 @@
+//! Progress reporting for long-running parses.
+//!
+//! [`SimulinkParser::progress_channel`](super::SimulinkParser::progress_channel)
+//! returns a receiver of [`ParseProgress`] updates, sent while
+//! [`parse_system_file`](super::SimulinkParser::parse_system_file) runs, so a
+//! GUI can parse on a background thread and show a progress bar.
+
+use camino::{Utf8Path, Utf8PathBuf};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::Sender;
+
+/// Stage of a [`parse_system_file`](super::SimulinkParser::parse_system_file) call.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ParsePhase {
+    /// Parsing Stateflow chart XML files.
+    Charts,
+    /// Parsing system XML files.
+    Systems,
+    /// Linking referenced systems into the tree.
+    Linking,
+    /// Parsing finished.
+    Done,
+}
+
+impl fmt::Display for ParsePhase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ParsePhase::Charts => "Parsing charts",
+            ParsePhase::Systems => "Parsing systems",
+            ParsePhase::Linking => "Linking subsystems",
+            ParsePhase::Done => "Done",
+        })
+    }
+}
+
+/// One progress update. File counts cover the whole parse so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseProgress {
+    pub phase: ParsePhase,
+    /// XML files found that will be parsed.
+    pub files_discovered: usize,
+    /// XML files parsed so far.
+    pub files_parsed: usize,
+    /// The file just parsed, if the update is for one.
+    pub current_file: Option<Utf8PathBuf>,
+}
+
+impl ParseProgress {
+    /// Parsed share of the discovered files, `0.0..=1.0`.
+    pub fn fraction(&self) -> f32 {
+        match self.phase {
+            ParsePhase::Done => 1.0,
+            _ if self.files_discovered == 0 => 0.0,
+            _ => (self.files_parsed as f32 / self.files_discovered as f32).min(1.0),
+        }
+    }
+}
+
+impl fmt::Display for ParseProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}/{} files)",
+            self.phase, self.files_parsed, self.files_discovered
+        )
+    }
+}
+
+/// Counts files and sends updates. Shared by the parallel parse workers.
+#[derive(Debug)]
+pub(crate) struct ProgressReporter {
+    sender: Sender<ParseProgress>,
+    discovered: AtomicUsize,
+    parsed: AtomicUsize,
+}
+
+impl ProgressReporter {
+    pub(crate) fn new(sender: Sender<ParseProgress>) -> Self {
+        Self {
+            sender,
+            discovered: AtomicUsize::new(0),
+            parsed: AtomicUsize::new(0),
+        }
+    }
+
+    /// Start counting a new parse.
+    pub(crate) fn reset(&self) {
+        self.discovered.store(0, Ordering::Relaxed);
+        self.parsed.store(0, Ordering::Relaxed);
+    }
+
+    pub(crate) fn discovered(&self, count: usize) {
+        self.discovered.fetch_add(count, Ordering::Relaxed);
+    }
+
+    pub(crate) fn phase(&self, phase: ParsePhase) {
+        self.send(phase, None);
+    }
+
+    pub(crate) fn parsed(&self, phase: ParsePhase, file: &Utf8Path) {
+        self.parsed.fetch_add(1, Ordering::Relaxed);
+        self.send(phase, Some(file));
+    }
+
+    fn send(&self, phase: ParsePhase, file: Option<&Utf8Path>) {
+        // Nobody listening any more is fine; parsing goes on.
+        let _ = self.sender.send(ParseProgress {
+            phase,
+            files_discovered: self.discovered.load(Ordering::Relaxed),
+            files_parsed: self.parsed.load(Ordering::Relaxed),
+            current_file: file.map(Utf8Path::to_path_buf),
+        });
+    }
+}
@@ -0,0 +1,65 @@
+//! Detection of protected models (`.slxp`) and encrypted archive parts.
+//!
+//! Protected models ship their system XML encrypted (or not at all), which
+//! used to surface as confusing "file not found" or XML parse errors. The
+//! [`ZipSource`](super::ZipSource) checks for this up front and reports a
+//! [`ModelProtectedError`] carrying whatever is still readable.
+
+use super::graphical_interface::GraphicalInterface;
+use serde::Serialize;
+
+/// Why a model cannot be read in full.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ProtectionKind {
+    /// A protected model package (`.slxp`) that does not contain regular
+    /// system XML.
+    ProtectedModel,
+    /// The archive is a normal model, but some of its members are encrypted.
+    EncryptedParts,
+}
+
+/// Error returned when a model is protected or contains encrypted parts.
+///
+/// Callers can recover it from an [`anyhow::Error`] with
+/// `err.downcast_ref::<ModelProtectedError>()` to show the readable interface
+/// metadata instead of a generic failure.
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelProtectedError {
+    pub kind: ProtectionKind,
+    /// Archive members that could not be decrypted.
+    pub encrypted_parts: Vec<String>,
+    /// Archive members that are still readable.
+    pub readable_parts: Vec<String>,
+    /// Interface metadata from `simulink/graphicalInterface.json`, if readable.
+    pub interface: Option<GraphicalInterface>,
+}
+
+impl std::fmt::Display for ModelProtectedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.kind {
+            ProtectionKind::ProtectedModel => write!(f, "model is a protected model")?,
+            ProtectionKind::EncryptedParts => write!(
+                f,
+                "model contains {} encrypted part(s)",
+                self.encrypted_parts.len()
+            )?,
+        }
+        if self.interface.is_some() {
+            write!(f, "; interface metadata is available")
+        } else {
+            write!(f, "; no interface metadata is readable")
+        }
+    }
+}
+
+impl std::error::Error for ModelProtectedError {}
+
+/// Returns `true` if an archive member name marks a protected model package.
+pub(crate) fn is_protected_model_marker(name: &str) -> bool {
+    let lower = name.to_ascii_lowercase();
+    lower.starts_with("protectedmodel/")
+        || lower.contains("/protectedmodel/")
+        || lower.ends_with(".slxp")
+        || lower.ends_with(".enc")
+        || lower.ends_with(".encrypted")
+}
@@ -1,5 +1,8 @@
 //! Content source abstraction for reading files from the filesystem or ZIP archives.
 
+use super::limits::{LimitExceeded, LimitKind, ParseLimits};
+use super::protected::{ModelProtectedError, ProtectionKind, is_protected_model_marker};
+use crate::model::{AssetRegistry, asset_mime_type, asset_mime_type_from_path, collect_assets};
 use anyhow::{Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
 use std::io::Read;
@@ -42,18 +45,175 @@ impl ContentSource for FsSource {
     }
 }
 
+const ROOT_SYSTEM_PATH: &str = "simulink/systems/system_root.xml";
+const GRAPHICAL_INTERFACE_PATH: &str = "simulink/graphicalInterface.json";
+
 /// Reads files from a ZIP archive (used for `.slx` files).
+///
+/// Reads are bounded by [`ParseLimits::max_file_size`] and
+/// [`ParseLimits::max_total_size`], counting the bytes actually inflated
+/// rather than trusting the sizes in the archive headers.
 pub struct ZipSource<R: Read + std::io::Seek> {
     zip: zip::ZipArchive<R>,
+    limits: ParseLimits,
+    /// Uncompressed bytes read so far.
+    total_read: u64,
 }
 
 impl<R: Read + std::io::Seek> ZipSource<R> {
     pub fn new(reader: R) -> Result<Self> {
+        Self::with_limits(reader, ParseLimits::default())
+    }
+
+    pub fn with_limits(reader: R, limits: ParseLimits) -> Result<Self> {
         let zip = zip::ZipArchive::new(reader).context("Failed to open zip archive")?;
-        Ok(Self { zip })
+        Ok(Self {
+            zip,
+            limits,
+            total_read: 0,
+        })
+    }
+
+    /// Check whether the archive is a protected model (`.slxp`) or contains
+    /// encrypted members.
+    ///
+    /// Returns `None` for regular models. Otherwise the returned
+    /// [`ModelProtectedError`] lists encrypted and readable members and carries
+    /// the interface metadata from `simulink/graphicalInterface.json` when it is
+    /// readable.
+    pub fn protection(&mut self) -> Option<ModelProtectedError> {
+        let mut encrypted_parts = Vec::new();
+        let mut readable_parts = Vec::new();
+        let mut has_marker = false;
+        let mut has_root_system = false;
+        for i in 0..self.zip.len() {
+            let Ok(name) = self.zip.by_index_raw(i).map(|f| f.name().to_string()) else {
+                continue;
+            };
+            has_marker |= is_protected_model_marker(&name);
+            has_root_system |= name.trim_start_matches('/') == ROOT_SYSTEM_PATH;
+            match self.zip.by_index(i) {
+                Err(zip::result::ZipError::UnsupportedArchive(msg))
+                    if msg == zip::result::ZipError::PASSWORD_REQUIRED =>
+                {
+                    encrypted_parts.push(name)
+                }
+                _ => readable_parts.push(name),
+            }
+        }
+        let kind = if has_marker || (!has_root_system && !encrypted_parts.is_empty()) {
+            ProtectionKind::ProtectedModel
+        } else if !encrypted_parts.is_empty() {
+            ProtectionKind::EncryptedParts
+        } else {
+            return None;
+        };
+        let interface = self
+            .read_bytes(Utf8Path::new(GRAPHICAL_INTERFACE_PATH))
+            .ok()
+            .and_then(|data| serde_json::from_slice::<serde_json::Value>(&data).ok())
+            .and_then(|v| v.get("GraphicalInterface").cloned())
+            .and_then(|gi| serde_json::from_value(gi).ok());
+        Some(ModelProtectedError {
+            kind,
+            encrypted_parts,
+            readable_parts,
+            interface,
+        })
+    }
+
+    /// Read the raw bytes of a file in the archive.
+    pub fn read_bytes(&mut self, path: &Utf8Path) -> Result<Vec<u8>> {
+        let p = path
+            .as_str()
+            .trim_start_matches("./")
+            .trim_start_matches('/')
+            .to_string();
+        let f = self
+            .zip
+            .by_name(&p)
+            .with_context(|| format!("File {} not found in zip", p))?;
+        read_limited(f, &p, &self.limits, &mut self.total_read)
+    }
+
+    /// Collect all embedded images (mask images, annotation images, the model
+    /// thumbnail, …) into an [`AssetRegistry`] with their MIME types.
+    ///
+    /// Only members with an image extension, or whose first bytes look like
+    /// an image, are read in full.
+    pub fn extract_assets(&mut self) -> Result<AssetRegistry> {
+        let mut entries: Vec<(String, Vec<u8>)> = Vec::new();
+        for i in 0..self.zip.len() {
+            let mut f = match self.zip.by_index(i) {
+                Ok(f) => f,
+                // Encrypted members of protected models cannot be extracted.
+                Err(zip::result::ZipError::UnsupportedArchive(msg))
+                    if msg == zip::result::ZipError::PASSWORD_REQUIRED =>
+                {
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            };
+            if f.is_dir() {
+                continue;
+            }
+            let name = f.name().to_string();
+            if asset_mime_type_from_path(&name).is_none() {
+                let mut head = Vec::with_capacity(SNIFF_LEN);
+                (&mut f)
+                    .take(SNIFF_LEN as u64)
+                    .read_to_end(&mut head)
+                    .with_context(|| format!("Failed to read {} from zip", name))?;
+                if asset_mime_type(&name, &head).is_none() {
+                    continue;
+                }
+                drop(f);
+                f = self.zip.by_index(i)?;
+            }
+            let data = read_limited(f, &name, &self.limits, &mut self.total_read)?;
+            entries.push((name, data));
+        }
+        Ok(collect_assets(
+            entries.iter().map(|(p, d)| (p.as_str(), d.as_slice())),
+        ))
     }
 }
 
+/// Leading bytes read to recognise images without a known extension.
+const SNIFF_LEN: usize = 16;
+
+/// Read an archive member, failing once it inflates beyond the per-file or
+/// remaining total size limit.
+fn read_limited(
+    mut file: zip::read::ZipFile<'_>,
+    name: &str,
+    limits: &ParseLimits,
+    total_read: &mut u64,
+) -> Result<Vec<u8>> {
+    let remaining = limits.max_total_size.saturating_sub(*total_read);
+    let (allowed, exceeded) = if limits.max_file_size <= remaining {
+        let error = LimitExceeded::new(LimitKind::FileSize, limits.max_file_size, name);
+        (limits.max_file_size, error)
+    } else {
+        let error = LimitExceeded::new(LimitKind::TotalSize, limits.max_total_size, name);
+        (remaining, error)
+    };
+    // The declared size may be a lie, so it only serves as an early reject.
+    if file.size() > allowed {
+        return Err(exceeded.into());
+    }
+    let mut data = Vec::with_capacity(file.size() as usize);
+    (&mut file)
+        .take(allowed.saturating_add(1))
+        .read_to_end(&mut data)
+        .with_context(|| format!("Failed to read {} from zip", name))?;
+    *total_read += data.len() as u64;
+    if data.len() as u64 > allowed {
+        return Err(exceeded.into());
+    }
+    Ok(data)
+}
+
 impl<R: Read + std::io::Seek> ContentSource for ZipSource<R> {
     fn read_to_string(&mut self, path: &Utf8Path) -> Result<String> {
         let p = path
@@ -61,14 +221,19 @@ impl<R: Read + std::io::Seek> ContentSource for ZipSource<R> {
             .trim_start_matches("./")
             .trim_start_matches('/')
             .to_string();
-        let mut f = self
+        if self.zip.by_name(&p).is_err() {
+            // Missing or encrypted members of protected models get a
+            // dedicated error instead of a confusing "not found".
+            if let Some(protected) = self.protection() {
+                return Err(protected.into());
+            }
+        }
+        let f = self
             .zip
             .by_name(&p)
             .with_context(|| format!("File {} not found in zip", p))?;
-        let mut s = String::new();
-        f.read_to_string(&mut s)
-            .with_context(|| format!("Failed to read {} from zip", p))?;
-        Ok(s)
+        let data = read_limited(f, &p, &self.limits, &mut self.total_read)?;
+        String::from_utf8(data).with_context(|| format!("Failed to read {} from zip", p))
     }
 
     fn list_dir(&mut self, path: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
@@ -0,0 +1,251 @@
+//! Zero-copy views of system XML for analysis-only workloads.
+//!
+//! [`SystemView`] and [`BlockView`] borrow names, types and property values
+//! straight from a parsed [`roxmltree::Document`] instead of copying them into
+//! owned [`System`]s. Scanning thousands of models for statistics only needs
+//! a handful of fields per block, so this skips most allocations of the full
+//! parse. A view keeps its XML node, and [`SystemView::materialize`] runs the
+//! normal parse when the owned model turns out to be needed after all.
+//!
+//! [`scan_slx`] builds [`ModelStats`] for an `.slx` file this way.
+
+use super::{ContentSource, ZipSource};
+use crate::model::System;
+use anyhow::{Context, Result};
+use camino::Utf8Path;
+use roxmltree::{Document, Node};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Borrowed view of a `<System>` element.
+#[derive(Debug, Clone)]
+pub struct SystemView<'a, 'input> {
+    node: Node<'a, 'input>,
+    /// `<P>` properties in document order.
+    pub properties: Vec<(&'a str, &'a str)>,
+    pub blocks: Vec<BlockView<'a, 'input>>,
+    pub lines: Vec<LineView<'a>>,
+    pub annotation_count: usize,
+}
+
+/// Borrowed view of a `<Block>` or `<Reference>` element.
+#[derive(Debug, Clone)]
+pub struct BlockView<'a, 'input> {
+    pub block_type: &'a str,
+    pub name: &'a str,
+    pub sid: Option<&'a str>,
+    /// Direct `<P>` properties in document order.
+    pub properties: Vec<(&'a str, &'a str)>,
+    /// Inline `<System>` content.
+    pub subsystem: Option<SystemView<'a, 'input>>,
+    /// `Ref` of a `<System Ref="…"/>` stored in another file.
+    pub system_ref: Option<&'a str>,
+}
+
+/// Borrowed view of a `<Line>` element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineView<'a> {
+    pub name: Option<&'a str>,
+    pub src: Option<&'a str>,
+    /// Destinations of the line and all its branches.
+    pub dst_count: usize,
+}
+
+fn properties<'a>(node: Node<'a, '_>) -> Vec<(&'a str, &'a str)> {
+    node.children()
+        .filter(|c| c.has_tag_name("P"))
+        .filter_map(|p| Some((p.attribute("Name")?, p.text().unwrap_or(""))))
+        .collect()
+}
+
+fn property<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|c| c.has_tag_name("P") && c.attribute("Name") == Some(name))
+        .map(|p| p.text().unwrap_or(""))
+}
+
+impl<'a, 'input> SystemView<'a, 'input> {
+    /// View the `<System>` element `node`.
+    pub fn new(node: Node<'a, 'input>) -> Self {
+        let mut view = SystemView {
+            node,
+            properties: properties(node),
+            blocks: Vec::new(),
+            lines: Vec::new(),
+            annotation_count: 0,
+        };
+        for child in node.children().filter(Node::is_element) {
+            match child.tag_name().name() {
+                "Block" | "Reference" => view.blocks.push(BlockView::new(child)),
+                "Line" => view.lines.push(LineView::new(child)),
+                "Annotation" => view.annotation_count += 1,
+                _ => {}
+            }
+        }
+        view
+    }
+
+    /// View the first `<System>` element of `doc`.
+    pub fn from_document(doc: &'a Document<'input>) -> Option<Self> {
+        doc.descendants()
+            .find(|n| n.has_tag_name("System"))
+            .map(Self::new)
+    }
+
+    pub fn property(&self, name: &str) -> Option<&'a str> {
+        self.properties
+            .iter()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| *v)
+    }
+
+    /// Visit every block, including those of inline subsystems, with the
+    /// nesting depth (0 for this system's own blocks).
+    pub fn walk_blocks(&self, f: &mut impl FnMut(usize, &BlockView<'a, 'input>)) {
+        self.walk_blocks_at(0, f);
+    }
+
+    fn walk_blocks_at(&self, depth: usize, f: &mut impl FnMut(usize, &BlockView<'a, 'input>)) {
+        for block in &self.blocks {
+            f(depth, block);
+            if let Some(sub) = &block.subsystem {
+                sub.walk_blocks_at(depth + 1, f);
+            }
+        }
+    }
+
+    /// Parse the viewed element into an owned [`System`].
+    pub fn materialize(&self, base_dir: &Utf8Path) -> Result<System> {
+        crate::block::parse_system_shallow(self.node, base_dir)
+    }
+}
+
+impl<'a, 'input> BlockView<'a, 'input> {
+    pub fn new(node: Node<'a, 'input>) -> Self {
+        let block_type = match node.attribute("BlockType") {
+            Some(t) if !t.is_empty() => t,
+            _ if node.has_tag_name("Reference") => "Reference",
+            _ => "",
+        };
+        let system = node.children().find(|c| c.has_tag_name("System"));
+        let system_ref = system.and_then(|s| s.attribute("Ref"));
+        BlockView {
+            block_type,
+            name: node.attribute("Name").unwrap_or(""),
+            sid: node.attribute("SID"),
+            properties: properties(node),
+            subsystem: system.filter(|_| system_ref.is_none()).map(SystemView::new),
+            system_ref,
+        }
+    }
+
+    pub fn property(&self, name: &str) -> Option<&'a str> {
+        self.properties
+            .iter()
+            .find(|(k, _)| *k == name)
+            .map(|(_, v)| *v)
+    }
+}
+
+impl<'a> LineView<'a> {
+    pub fn new(node: Node<'a, '_>) -> Self {
+        LineView {
+            name: property(node, "Name"),
+            src: property(node, "Src"),
+            dst_count: node
+                .descendants()
+                .filter(|n| n.has_tag_name("P") && n.attribute("Name") == Some("Dst"))
+                .count(),
+        }
+    }
+}
+
+/// Statistics of one model, as gathered by [`scan_slx`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelStats {
+    /// System XML files in the archive.
+    pub systems: usize,
+    pub blocks: usize,
+    pub lines: usize,
+    pub annotations: usize,
+    /// Blocks per `BlockType`.
+    pub block_types: BTreeMap<String, usize>,
+    /// Blocks linked to a library, per `SourceBlock`.
+    pub library_links: BTreeMap<String, usize>,
+    /// Deepest inline subsystem nesting within one system file.
+    pub max_inline_depth: usize,
+}
+
+impl ModelStats {
+    /// Add the blocks, lines and annotations of `system` and its inline
+    /// subsystems.
+    pub fn add_system(&mut self, system: &SystemView) {
+        self.systems += 1;
+        add_counts(self, system);
+        system.walk_blocks(&mut |depth, block| {
+            self.blocks += 1;
+            self.max_inline_depth = self.max_inline_depth.max(depth);
+            increment(&mut self.block_types, block.block_type);
+            if let Some(source) = block.property("SourceBlock") {
+                increment(&mut self.library_links, source);
+            }
+        });
+    }
+
+    /// Add the counts of `other`, e.g. to total several models.
+    pub fn merge(&mut self, other: &ModelStats) {
+        self.systems += other.systems;
+        self.blocks += other.blocks;
+        self.lines += other.lines;
+        self.annotations += other.annotations;
+        for (key, count) in &other.block_types {
+            *self.block_types.entry(key.clone()).or_default() += count;
+        }
+        for (key, count) in &other.library_links {
+            *self.library_links.entry(key.clone()).or_default() += count;
+        }
+        self.max_inline_depth = self.max_inline_depth.max(other.max_inline_depth);
+    }
+}
+
+fn add_counts(stats: &mut ModelStats, system: &SystemView) {
+    stats.lines += system.lines.len();
+    stats.annotations += system.annotation_count;
+    for sub in system.blocks.iter().filter_map(|b| b.subsystem.as_ref()) {
+        add_counts(stats, sub);
+    }
+}
+
+/// Count `key`, allocating only the first time it is seen.
+fn increment(counts: &mut BTreeMap<String, usize>, key: &str) {
+    match counts.get_mut(key) {
+        Some(count) => *count += 1,
+        None => {
+            counts.insert(key.to_string(), 1);
+        }
+    }
+}
+
+/// Gather [`ModelStats`] for the `.slx` file at `path` without building the
+/// owned model.
+pub fn scan_slx(path: &Utf8Path) -> Result<ModelStats> {
+    let file = std::fs::File::open(path).with_context(|| format!("Open {}", path))?;
+    let mut source = ZipSource::new(std::io::BufReader::new(file))?;
+    let mut stats = ModelStats::default();
+    let mut files = source.list_dir(Utf8Path::new("simulink/systems"))?;
+    files.retain(|p| {
+        p.file_name()
+            .is_some_and(|f| f.starts_with("system_") && f.ends_with(".xml"))
+    });
+    files.sort();
+    for file in files {
+        let text = source.read_to_string(&file)?;
+        let doc =
+            Document::parse(&text).with_context(|| format!("Failed to parse XML {}", file))?;
+        if let Some(system) = SystemView::from_document(&doc) {
+            stats.add_system(&system);
+        }
+    }
+    Ok(stats)
+}
@@ -0,0 +1,556 @@
+//! Block connectivity patterns.
+//!
+//! A [`PatternGraph`] describes a few blocks and the signals between them,
+//! e.g. a Gain feeding a Saturation feeding an Outport. [`find`] reports
+//! every place in a model where blocks of one system level are wired that
+//! way, as candidates for refactoring suggestions or for the pattern rules
+//! of [`crate::lint::LintConfig`].
+//!
+//! Patterns are built in code
+//!
+//! ```
+//! use rustylink::patterns::PatternGraph;
+//!
+//! let pattern = PatternGraph::new()
+//!     .block("g", "Gain")
+//!     .block("s", "Saturate")
+//!     .block("y", "Outport")
+//!     .connect("g", "s")
+//!     .connect("s", "y");
+//! ```
+//!
+//! or parsed from a short text form:
+//!
+//! ```
+//! use rustylink::patterns::PatternGraph;
+//!
+//! let pattern: PatternGraph = "Gain -> Saturate -> Outport".parse().unwrap();
+//! let pid: PatternGraph = "u: * -> p: Gain -> sum: Sum
+//!     u -> i: Gain -> Integrator -> sum
+//!     u -> d: Gain -> Derivative -> sum"
+//!     .parse()
+//!     .unwrap();
+//! ```
+//!
+//! Statements are chains of blocks joined by `->`, separated by newlines or
+//! `;`. `name: Type` declares a named block, `*` stands for any block type,
+//! and a later `name` refers back to it; any other bare word is a new block
+//! of that type. Words may contain `-` between letters, as in `S-Function`. `[Param=value, ...]` after a block requires parameter
+//! values, and `-2:1->` connects output 2 to input 1 (`*` for any port)
+//! where a plain `->` accepts any ports.
+//!
+//! Blocks may have more connections than the pattern asks for. Commented
+//! blocks never match, and signals passing through commented-through blocks
+//! count as connected, as in [`crate::analysis::effective_connections`].
+
+use crate::analysis::effective_connections;
+use crate::block_path::BlockPath;
+use crate::model::{Block, CommentMode, System};
+use anyhow::{Result, bail};
+use std::collections::{BTreeSet, HashMap};
+use std::str::FromStr;
+
+/// Blocks and the connections between them to look for.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PatternGraph {
+    pub nodes: Vec<PatternNode>,
+    pub edges: Vec<PatternEdge>,
+}
+
+/// One block of a [`PatternGraph`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternNode {
+    /// `None` for blocks written as a bare type in the text form.
+    pub name: Option<String>,
+    /// `BlockType` to match; `None` matches any block.
+    pub block_type: Option<String>,
+    /// Parameters the block must have, with their values.
+    pub properties: Vec<(String, String)>,
+}
+
+/// A signal from one [`PatternNode`] to another, by index into
+/// [`PatternGraph::nodes`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PatternEdge {
+    pub from: usize,
+    /// Output port; `None` for any.
+    pub from_port: Option<u32>,
+    pub to: usize,
+    /// Input port; `None` for any.
+    pub to_port: Option<u32>,
+}
+
+/// Blocks matching a [`PatternGraph`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternMatch {
+    /// [`BlockPath`] string of the system level, `""` for the root.
+    pub system: String,
+    /// [`BlockPath`] strings of the matched blocks, in the order of
+    /// [`PatternGraph::nodes`].
+    pub blocks: Vec<String>,
+}
+
+impl PatternGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a block called `name` of `block_type`, `"*"` for any type.
+    pub fn block(mut self, name: &str, block_type: &str) -> Self {
+        self.nodes.push(PatternNode {
+            name: Some(name.to_string()),
+            block_type: (block_type != "*").then(|| block_type.to_string()),
+            properties: Vec::new(),
+        });
+        self
+    }
+
+    /// Require parameter `property` of block `name` to be `value`.
+    ///
+    /// Panics if no block is called `name`.
+    pub fn property(mut self, name: &str, property: &str, value: &str) -> Self {
+        let node = self.expect_node(name);
+        self.nodes[node]
+            .properties
+            .push((property.to_string(), value.to_string()));
+        self
+    }
+
+    /// Connect any output of block `from` to any input of block `to`.
+    ///
+    /// Panics if either block has not been added.
+    pub fn connect(self, from: &str, to: &str) -> Self {
+        self.connect_edge(from, None, to, None)
+    }
+
+    /// Connect output `from_port` of block `from` to input `to_port` of
+    /// block `to`.
+    ///
+    /// Panics if either block has not been added.
+    pub fn connect_ports(self, from: &str, from_port: u32, to: &str, to_port: u32) -> Self {
+        self.connect_edge(from, Some(from_port), to, Some(to_port))
+    }
+
+    fn connect_edge(
+        mut self,
+        from: &str,
+        from_port: Option<u32>,
+        to: &str,
+        to_port: Option<u32>,
+    ) -> Self {
+        let edge = PatternEdge {
+            from: self.expect_node(from),
+            from_port,
+            to: self.expect_node(to),
+            to_port,
+        };
+        self.edges.push(edge);
+        self
+    }
+
+    /// Index of the block called `name` in [`nodes`](Self::nodes).
+    pub fn node(&self, name: &str) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|n| n.name.as_deref() == Some(name))
+    }
+
+    fn expect_node(&self, name: &str) -> usize {
+        self.node(name)
+            .unwrap_or_else(|| panic!("pattern has no block called '{}'", name))
+    }
+}
+
+impl PatternMatch {
+    /// The path of the block matched by the pattern block called `name`.
+    pub fn block<'a>(&'a self, pattern: &PatternGraph, name: &str) -> Option<&'a str> {
+        self.blocks.get(pattern.node(name)?).map(String::as_str)
+    }
+}
+
+impl FromStr for PatternGraph {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut pattern = PatternGraph::new();
+        for statement in s.split(['\n', ';']) {
+            Parser {
+                rest: statement.trim(),
+                statement,
+            }
+            .statement(&mut pattern)?;
+        }
+        if pattern.nodes.is_empty() {
+            bail!("Empty pattern");
+        }
+        Ok(pattern)
+    }
+}
+
+/// Parser for one statement of the text form.
+struct Parser<'a> {
+    rest: &'a str,
+    statement: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn statement(&mut self, pattern: &mut PatternGraph) -> Result<()> {
+        if self.rest.is_empty() {
+            return Ok(());
+        }
+        let mut from = self.node(pattern)?;
+        while !self.rest.is_empty() {
+            let (from_port, to_port) = self.arrow()?;
+            let to = self.node(pattern)?;
+            pattern.edges.push(PatternEdge {
+                from,
+                from_port,
+                to,
+                to_port,
+            });
+            from = to;
+        }
+        Ok(())
+    }
+
+    fn node(&mut self, pattern: &mut PatternGraph) -> Result<usize> {
+        let word = match self.eat("*") {
+            true => "*",
+            false => self.word()?,
+        };
+        let index = if self.eat(":") {
+            let block_type = match self.eat("*") {
+                true => "*",
+                false => self.word()?,
+            };
+            if pattern.node(word).is_some() {
+                bail!("Block '{}' declared twice in '{}'", word, self.statement);
+            }
+            *pattern = std::mem::take(pattern).block(word, block_type);
+            pattern.nodes.len() - 1
+        } else if let Some(index) = pattern.node(word) {
+            index
+        } else {
+            pattern.nodes.push(PatternNode {
+                name: None,
+                block_type: (word != "*").then(|| word.to_string()),
+                properties: Vec::new(),
+            });
+            pattern.nodes.len() - 1
+        };
+        if self.eat("[") {
+            let Some((list, rest)) = self.rest.split_once(']') else {
+                bail!("Missing ']' in '{}'", self.statement);
+            };
+            for item in list.split(',').map(str::trim).filter(|i| !i.is_empty()) {
+                let Some((key, value)) = item.split_once('=') else {
+                    bail!("Expected Param=value, found '{}'", item);
+                };
+                pattern.nodes[index]
+                    .properties
+                    .push((key.trim().to_string(), value.trim().to_string()));
+            }
+            self.rest = rest.trim_start();
+        }
+        Ok(index)
+    }
+
+    /// `->` or `-out:in->`.
+    fn arrow(&mut self) -> Result<(Option<u32>, Option<u32>)> {
+        if self.eat("->") {
+            return Ok((None, None));
+        }
+        if self.eat("-")
+            && let Some((ports, rest)) = self.rest.split_once("->")
+            && let Some((from, to)) = ports.split_once(':')
+        {
+            self.rest = rest.trim_start();
+            return Ok((port(from, self.statement)?, port(to, self.statement)?));
+        }
+        bail!("Expected '->' in '{}'", self.statement)
+    }
+
+    /// Letters, digits and `_`, with inner `-` before a letter so block types
+    /// like `S-Function` stay one word while `-2:1->` starts an arrow.
+    fn word(&mut self) -> Result<&'a str> {
+        let is_word = |c: char| c.is_alphanumeric() || c == '_';
+        let mut end = 0;
+        let mut chars = self.rest.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let inner_dash = c == '-'
+                && end > 0
+                && chars
+                    .peek()
+                    .is_some_and(|&(_, n)| n.is_alphabetic() || n == '_');
+            if !(is_word(c) || inner_dash) {
+                break;
+            }
+            end = i + c.len_utf8();
+        }
+        if end == 0 {
+            bail!("Expected a block name or type in '{}'", self.statement);
+        }
+        let (word, rest) = self.rest.split_at(end);
+        self.rest = rest.trim_start();
+        Ok(word)
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest.trim_start();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+fn port(text: &str, statement: &str) -> Result<Option<u32>> {
+    match text.trim() {
+        "*" => Ok(None),
+        n => match n.parse() {
+            Ok(port) if port > 0 => Ok(Some(port)),
+            _ => bail!("Invalid port '{}' in '{}'", n, statement),
+        },
+    }
+}
+
+/// Candidate blocks [`find`] tries in all before giving up, plenty for
+/// realistic patterns while bounding the time a pathological one can take.
+pub const MAX_STEPS: usize = 1_000_000;
+
+/// Result of [`search`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternSearch {
+    pub matches: Vec<PatternMatch>,
+    /// `false` if the search stopped at its step limit, so that further
+    /// matches may exist.
+    pub complete: bool,
+}
+
+/// Every place in `system` and its subsystems where blocks of one level are
+/// connected as `pattern` describes, outermost level first.
+///
+/// Different blocks of the pattern match different blocks of the model.
+/// Matches of the same blocks in another role, as with symmetric patterns,
+/// are reported once. Stops after [`MAX_STEPS`]; use [`search`] to learn
+/// whether it did.
+pub fn find(system: &System, pattern: &PatternGraph) -> Vec<PatternMatch> {
+    search(system, pattern, MAX_STEPS).matches
+}
+
+/// [`find`], trying at most `max_steps` candidate blocks in all.
+pub fn search(system: &System, pattern: &PatternGraph, max_steps: usize) -> PatternSearch {
+    let mut steps = max_steps;
+    let mut matches = Vec::new();
+    let complete = find_below(
+        system,
+        pattern,
+        &mut BlockPath::root(),
+        &mut steps,
+        &mut matches,
+    );
+    PatternSearch { matches, complete }
+}
+
+/// `false` once the step limit is reached.
+fn find_below(
+    system: &System,
+    pattern: &PatternGraph,
+    path: &mut BlockPath,
+    steps: &mut usize,
+    out: &mut Vec<PatternMatch>,
+) -> bool {
+    let candidates: Vec<Vec<usize>> = pattern
+        .nodes
+        .iter()
+        .map(|node| {
+            (0..system.blocks.len())
+                .filter(|&i| matches_node(&system.blocks[i], node))
+                .collect()
+        })
+        .collect();
+    if candidates.iter().all(|c| !c.is_empty()) {
+        let index: HashMap<&str, usize> = (system.blocks.iter().enumerate())
+            .filter_map(|(i, b)| Some((b.sid.as_deref()?, i)))
+            .collect();
+        let mut search = Search {
+            pattern,
+            order: search_order(pattern, &candidates),
+            candidates,
+            wires: HashMap::new(),
+            successors: vec![Vec::new(); system.blocks.len()],
+            predecessors: vec![Vec::new(); system.blocks.len()],
+            assigned: vec![None; pattern.nodes.len()],
+            steps,
+            found: Vec::new(),
+        };
+        for c in effective_connections(system) {
+            let (Some(&from), Some(&to)) =
+                (index.get(c.src.sid.as_str()), index.get(c.dst.sid.as_str()))
+            else {
+                continue;
+            };
+            let ports = search.wires.entry((from, to)).or_default();
+            if ports.is_empty() {
+                search.successors[from].push(to);
+                search.predecessors[to].push(from);
+            }
+            ports.push((c.src.port_index, c.dst.port_type == "in", c.dst.port_index));
+        }
+        let complete = search.assign(0);
+        let mut found = search.found;
+        found.sort();
+        let mut seen = BTreeSet::new();
+        for blocks in found {
+            if seen.insert(blocks.iter().copied().collect::<BTreeSet<usize>>()) {
+                out.push(PatternMatch {
+                    system: path.to_string(),
+                    blocks: blocks
+                        .iter()
+                        .map(|&i| path.child(&system.blocks[i].name).to_string())
+                        .collect(),
+                });
+            }
+        }
+        if !complete {
+            return false;
+        }
+    }
+    for block in &system.blocks {
+        if let Some(sub) = block.subsystem.as_deref() {
+            path.push(&block.name);
+            let complete = find_below(sub, pattern, path, steps, out);
+            path.pop();
+            if !complete {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn matches_node(block: &Block, node: &PatternNode) -> bool {
+    block.comment_mode() == CommentMode::Off
+        && node
+            .block_type
+            .as_ref()
+            .is_none_or(|t| *t == block.block_type)
+        && node.properties.iter().all(|(key, value)| {
+            block
+                .properties
+                .get(key)
+                .is_some_and(|v| v.trim() == value.trim())
+        })
+}
+
+/// Pattern blocks in the order to assign them: the one with the fewest
+/// candidates first, then always one connected to an assigned block, so
+/// that its candidates can be taken from that block's wires.
+fn search_order(pattern: &PatternGraph, candidates: &[Vec<usize>]) -> Vec<usize> {
+    let mut placed = vec![false; pattern.nodes.len()];
+    let mut order = Vec::with_capacity(placed.len());
+    while order.len() < placed.len() {
+        let adjacent = |node: usize| {
+            pattern
+                .edges
+                .iter()
+                .any(|e| (e.from == node && placed[e.to]) || (e.to == node && placed[e.from]))
+        };
+        let next = (0..placed.len())
+            .filter(|&i| !placed[i])
+            .min_by_key(|&i| (!adjacent(i), candidates[i].len()))
+            .expect("an unplaced node is left");
+        placed[next] = true;
+        order.push(next);
+    }
+    order
+}
+
+/// Output, whether the destination port is an input, and input of a wire.
+type WirePorts = (u32, bool, u32);
+
+/// State of the backtracking search over one system level.
+struct Search<'a> {
+    pattern: &'a PatternGraph,
+    /// Matching blocks per pattern block, ascending.
+    candidates: Vec<Vec<usize>>,
+    order: Vec<usize>,
+    /// Wires by (source block, destination block).
+    wires: HashMap<(usize, usize), Vec<WirePorts>>,
+    successors: Vec<Vec<usize>>,
+    predecessors: Vec<Vec<usize>>,
+    /// Model block per pattern block, once assigned.
+    assigned: Vec<Option<usize>>,
+    steps: &'a mut usize,
+    /// Model blocks of every match, in the order of the pattern blocks.
+    found: Vec<Vec<usize>>,
+}
+
+impl Search<'_> {
+    /// Give the pattern blocks from `depth` of the search order on distinct
+    /// model blocks whose connections include every pattern edge among
+    /// them. `false` once the step limit is reached.
+    fn assign(&mut self, depth: usize) -> bool {
+        let Some(&node) = self.order.get(depth) else {
+            self.found
+                .push(self.assigned.iter().flatten().copied().collect());
+            return true;
+        };
+        let candidates = &self.candidates[node];
+        let wired = self.pattern.edges.iter().find_map(|e| {
+            if e.to == node
+                && let Some(from) = self.assigned[e.from]
+            {
+                Some(&self.successors[from])
+            } else if e.from == node
+                && let Some(to) = self.assigned[e.to]
+            {
+                Some(&self.predecessors[to])
+            } else {
+                None
+            }
+        });
+        let blocks: Vec<usize> = match wired {
+            Some(wired) => (wired.iter().copied())
+                .filter(|b| candidates.binary_search(b).is_ok())
+                .collect(),
+            None => candidates.clone(),
+        };
+        for block in blocks {
+            if self.assigned.contains(&Some(block)) {
+                continue;
+            }
+            if *self.steps == 0 {
+                return false;
+            }
+            *self.steps -= 1;
+            self.assigned[node] = Some(block);
+            let fits = self
+                .pattern
+                .edges
+                .iter()
+                .filter(|e| e.from == node || e.to == node)
+                .all(|e| match (self.assigned[e.from], self.assigned[e.to]) {
+                    (Some(from), Some(to)) => self.connected(e, from, to),
+                    _ => true,
+                });
+            let complete = !fits || self.assign(depth + 1);
+            self.assigned[node] = None;
+            if !complete {
+                return false;
+            }
+        }
+        true
+    }
+
+    fn connected(&self, edge: &PatternEdge, from: usize, to: usize) -> bool {
+        self.wires.get(&(from, to)).is_some_and(|ports| {
+            ports.iter().any(|&(output, to_input, input)| {
+                edge.from_port.is_none_or(|p| p == output)
+                    && edge.to_port.is_none_or(|p| to_input && p == input)
+            })
+        })
+    }
+}
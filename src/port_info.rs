@@ -0,0 +1,127 @@
+//! Port lookups for hover information and click-to-trace.
+//!
+//! Ports are identified the way lines reference them: block SID, 1-based
+//! port index and direction. Data types are taken from the model as written
+//! (`OutDataTypeStr`); compiled types are not available in model files.
+
+use crate::model::{Block, Branch, Line};
+
+/// Index of the line attached to a block port, if any.
+///
+/// For inputs the line may end at the port through one of its branches.
+pub fn attached_line(lines: &[Line], sid: &str, port_index: u32, is_input: bool) -> Option<usize> {
+    fn branch_hits(br: &Branch, sid: &str, port_index: u32) -> bool {
+        br.dst
+            .as_ref()
+            .is_some_and(|d| d.sid == sid && d.port_type == "in" && d.port_index == port_index)
+            || br.branches.iter().any(|b| branch_hits(b, sid, port_index))
+    }
+    lines.iter().position(|line| {
+        if is_input {
+            line.dst
+                .as_ref()
+                .is_some_and(|d| d.sid == sid && d.port_type == "in" && d.port_index == port_index)
+                || line
+                    .branches
+                    .iter()
+                    .any(|b| branch_hits(b, sid, port_index))
+        } else {
+            line.src
+                .as_ref()
+                .is_some_and(|s| s.sid == sid && s.port_type == "out" && s.port_index == port_index)
+        }
+    })
+}
+
+/// Name of a block port from its `<Port>` element or, for subsystems, from
+/// the matching `Inport`/`Outport` block inside.
+pub fn port_name(block: &Block, port_index: u32, is_input: bool) -> Option<String> {
+    let port_type = if is_input { "in" } else { "out" };
+    let from_port = block
+        .ports
+        .iter()
+        .filter(|p| p.port_type == port_type && p.index.unwrap_or(1) == port_index)
+        .find_map(|p| p.properties.get("Name").cloned())
+        .filter(|n| !n.is_empty());
+    from_port.or_else(|| inner_port_block(block, port_index, is_input).map(|b| b.name.clone()))
+}
+
+/// Data type of a block port, e.g. `double` or `Bus: MyBus`.
+///
+/// Outputs use the block's `OutDataTypeStr`; inputs without a declared type
+/// take the type of the output driving them. `Inherit: ...` settings count as
+/// undeclared. `blocks` and `lines` are the system containing `block`.
+pub fn port_data_type(
+    blocks: &[Block],
+    lines: &[Line],
+    block: &Block,
+    port_index: u32,
+    is_input: bool,
+) -> Option<String> {
+    let declared = |b: &Block| {
+        b.properties
+            .get("OutDataTypeStr")
+            .map(|t| t.trim().to_string())
+            .filter(|t| !t.is_empty() && !t.starts_with("Inherit"))
+    };
+    if block.subsystem.is_some() {
+        if let Some(t) = inner_port_block(block, port_index, is_input).and_then(declared) {
+            return Some(t);
+        }
+    } else if !is_input {
+        return declared(block);
+    }
+    if !is_input {
+        return None;
+    }
+    let line = &lines[attached_line(lines, block.sid.as_deref()?, port_index, true)?];
+    let src = line.src.as_ref()?;
+    let src_block = blocks
+        .iter()
+        .find(|b| b.sid.as_deref() == Some(src.sid.as_str()))?;
+    port_data_type(blocks, lines, src_block, src.port_index, false)
+}
+
+/// The `Inport` or `Outport` block inside a subsystem that implements the
+/// given port.
+fn inner_port_block(block: &Block, port_index: u32, is_input: bool) -> Option<&Block> {
+    let block_type = if is_input { "Inport" } else { "Outport" };
+    block.subsystem.as_ref()?.blocks.iter().find(|b| {
+        b.block_type == block_type
+            && b.properties
+                .get("Port")
+                .and_then(|p| p.trim().parse::<u32>().ok())
+                .unwrap_or(1)
+                == port_index
+    })
+}
+
+/// Hover text for a port: name, data type and the attached signal.
+pub fn hover_text(
+    blocks: &[Block],
+    lines: &[Line],
+    block: &Block,
+    port_index: u32,
+    is_input: bool,
+) -> String {
+    let dir = if is_input { "In" } else { "Out" };
+    let name =
+        port_name(block, port_index, is_input).unwrap_or_else(|| format!("{dir}{port_index}"));
+    let data_type = port_data_type(blocks, lines, block, port_index, is_input)
+        .unwrap_or_else(|| "inherited".to_string());
+    let signal = match block
+        .sid
+        .as_deref()
+        .and_then(|sid| attached_line(lines, sid, port_index, is_input))
+    {
+        Some(li) => lines[li]
+            .name
+            .as_deref()
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("line {}", li + 1)),
+        None => "unconnected".to_string(),
+    };
+    format!("{name} ({dir} {port_index})\nType: {data_type}\nSignal: {signal}")
+}
@@ -0,0 +1,312 @@
+//! A model loaded together with the libraries and models it depends on.
+//!
+//! [`Workspace::open`] parses a main `.slx` file, then every library its
+//! blocks link to and every model its Model blocks reference, looked up by
+//! name in the search directories, and so on for those files. Each file is
+//! parsed once and kept as saved, without copying library contents into
+//! linked blocks, so a library used by several models is held once.
+//! [`Workspace::where_used`] searches all loaded models at once, and
+//! [`Workspace::diff`] compares two workspaces model by model, e.g. two
+//! checkouts of the same project. [`Workspace::impacted_models`] follows a
+//! changed library block through links and Model blocks to every instance
+//! that needs retesting.
+
+use crate::block_path::BlockPath;
+use crate::diff::{SystemDiff, diff_systems};
+use crate::model::{Block, System};
+use crate::parser::library::{is_virtual_library, split_source_block_reference};
+use crate::parser::{SimulinkParser, ZipSource};
+use anyhow::{Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use indexmap::IndexMap;
+use std::collections::VecDeque;
+
+/// How a model came into the workspace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelRole {
+    /// The model the workspace was opened with.
+    Main,
+    /// Linked from a block's `SourceBlock`.
+    Library,
+    /// Referenced by a Model block.
+    Referenced,
+}
+
+/// One loaded file.
+#[derive(Debug, Clone)]
+pub struct WorkspaceModel {
+    /// File name without extension, as libraries and Model blocks refer to it.
+    pub name: String,
+    pub path: Utf8PathBuf,
+    pub role: ModelRole,
+    pub system: System,
+}
+
+/// A block found by [`Workspace::where_used`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+    pub model: String,
+    /// [`BlockPath`] string of the block within `model`.
+    pub path: String,
+}
+
+/// A block affected by a change to a library block, found by
+/// [`Workspace::impacted_models`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Impact {
+    pub model: String,
+    /// [`BlockPath`] string of the linked block or Model block in `model`.
+    pub path: String,
+    /// What the block uses that leads to the change: a library block like
+    /// `ctrllib/PI`, or the name of a referenced model.
+    pub through: String,
+}
+
+/// The differences between two workspaces, by model name.
+#[derive(Debug, Clone)]
+pub struct WorkspaceDiff {
+    /// Models only the new workspace loaded.
+    pub added: Vec<String>,
+    /// Models only the old workspace loaded.
+    pub removed: Vec<String>,
+    /// Models loaded by both that differ.
+    pub changed: Vec<(String, SystemDiff)>,
+}
+
+impl WorkspaceDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Workspace {
+    /// Loaded models by name, the main model first, then in the order they
+    /// were first needed.
+    models: IndexMap<String, WorkspaceModel>,
+    /// Libraries and referenced models not found in the search directories.
+    pub missing: Vec<String>,
+}
+
+impl Workspace {
+    /// Load the `.slx` file at `path` and everything it depends on. Files
+    /// are looked up as `<name>.slx` in the main model's directory, then in
+    /// `search_paths`, first match wins. Fails if any file found cannot be
+    /// parsed.
+    pub fn open(path: &Utf8Path, search_paths: &[Utf8PathBuf]) -> Result<Self> {
+        let mut dirs = vec![match path.parent() {
+            Some(dir) if !dir.as_str().is_empty() => dir.to_path_buf(),
+            _ => Utf8PathBuf::from("."),
+        }];
+        dirs.extend(search_paths.iter().cloned());
+        let mut workspace = Workspace {
+            models: IndexMap::new(),
+            missing: Vec::new(),
+        };
+        let mut queue = VecDeque::from([(path.to_path_buf(), ModelRole::Main)]);
+        while let Some((path, role)) = queue.pop_front() {
+            let model = load(&path, role)?;
+            for (name, role) in dependencies(&model.system) {
+                let known = workspace.models.contains_key(&name)
+                    || name == model.name
+                    || workspace.missing.contains(&name)
+                    || queue.iter().any(|(p, _)| p.file_stem() == Some(&name));
+                if known {
+                    continue;
+                }
+                match dirs
+                    .iter()
+                    .map(|dir| dir.join(format!("{}.slx", name)))
+                    .find(|p| p.is_file())
+                {
+                    Some(path) => queue.push_back((path, role)),
+                    None => workspace.missing.push(name),
+                }
+            }
+            workspace.models.insert(model.name.clone(), model);
+        }
+        Ok(workspace)
+    }
+
+    /// The model the workspace was opened with.
+    pub fn main(&self) -> &WorkspaceModel {
+        &self.models[0]
+    }
+
+    pub fn model(&self, name: &str) -> Option<&WorkspaceModel> {
+        self.models.get(name)
+    }
+
+    /// All loaded models, the main model first.
+    pub fn models(&self) -> impl Iterator<Item = &WorkspaceModel> {
+        self.models.values()
+    }
+
+    /// The block a `SourceBlock` reference like `ctrllib/PI` points to, from
+    /// the loaded library.
+    pub fn library_block(&self, source_block: &str) -> Option<&Block> {
+        let (library, path) = split_source_block_reference(source_block)?;
+        self.models.get(&library)?.system.block_at(&path)
+    }
+
+    /// Blocks in any loaded model that link to the library block `target`
+    /// (e.g. `ctrllib/PI`) or, for a model name, Model blocks referencing
+    /// it. Ordered by model, then depth first.
+    pub fn where_used(&self, target: &str) -> Vec<Usage> {
+        let target = target.trim();
+        let mut usages = Vec::new();
+        for model in self.models.values() {
+            model
+                .system
+                .walk_blocks(&mut Vec::new(), &mut |path, block| {
+                    let linked = block
+                        .properties
+                        .get("SourceBlock")
+                        .is_some_and(|s| s.trim() == target);
+                    if linked || block.referenced_model() == Some(target) {
+                        usages.push(Usage {
+                            model: model.name.clone(),
+                            path: BlockPath::from(path).child(&block.name).to_string(),
+                        });
+                    }
+                });
+        }
+        usages
+    }
+
+    /// Every block affected by a change to the library block `block`, whose
+    /// path starts with the library name, e.g. `ctrllib/PI/Kp` for a block
+    /// of a [`WorkspaceDiff::changed`] library. Links to the block or a library subsystem containing it are
+    /// affected; an affected block inside another library affects the links
+    /// to that library block in turn, and one inside a referenced model
+    /// affects the Model blocks referencing it. Listed in the order found,
+    /// nearest first; [`Impact::model`] gives the models to retest.
+    pub fn impacted_models(&self, block: &BlockPath) -> Vec<Impact> {
+        enum Target {
+            LibraryBlock(BlockPath),
+            Model(String),
+        }
+        let mut impacts: Vec<Impact> = Vec::new();
+        let mut queue = VecDeque::from([Target::LibraryBlock(block.clone())]);
+        while let Some(target) = queue.pop_front() {
+            let mut found = Vec::new();
+            for model in self.models.values() {
+                model
+                    .system
+                    .walk_blocks(&mut Vec::new(), &mut |path, block| {
+                        let through = match &target {
+                            Target::LibraryBlock(changed) => block
+                                .properties
+                                .get("SourceBlock")
+                                .map(|s| s.trim())
+                                .filter(|s| {
+                                    s.parse::<BlockPath>()
+                                        .is_ok_and(|source| changed.starts_with(&source))
+                                }),
+                            Target::Model(name) => block.referenced_model().filter(|m| m == name),
+                        };
+                        if let Some(through) = through {
+                            let path = BlockPath::from(path).child(&block.name);
+                            found.push((
+                                Impact {
+                                    model: model.name.clone(),
+                                    path: path.to_string(),
+                                    through: through.to_string(),
+                                },
+                                path,
+                            ));
+                        }
+                    });
+            }
+            for (impact, path) in found {
+                if impacts
+                    .iter()
+                    .any(|i| i.model == impact.model && i.path == impact.path)
+                {
+                    continue;
+                }
+                match self.models[&impact.model].role {
+                    ModelRole::Library => {
+                        let segments = [vec![impact.model.clone()], path.into_segments()];
+                        queue.push_back(Target::LibraryBlock(segments.concat().into()));
+                    }
+                    ModelRole::Main | ModelRole::Referenced => {
+                        if !impacts.iter().any(|i| i.model == impact.model) {
+                            queue.push_back(Target::Model(impact.model.clone()));
+                        }
+                    }
+                }
+                impacts.push(impact);
+            }
+        }
+        impacts
+    }
+
+    /// Compare with `new`, a later version of this workspace. Models are
+    /// matched by name.
+    pub fn diff(&self, new: &Workspace) -> WorkspaceDiff {
+        let added = new
+            .models
+            .keys()
+            .filter(|name| !self.models.contains_key(*name))
+            .cloned()
+            .collect();
+        let removed = self
+            .models
+            .keys()
+            .filter(|name| !new.models.contains_key(*name))
+            .cloned()
+            .collect();
+        let changed = self
+            .models
+            .values()
+            .filter_map(|old| {
+                let diff = diff_systems(&old.system, &new.models.get(&old.name)?.system);
+                (!diff.is_empty()).then(|| (old.name.clone(), diff))
+            })
+            .collect();
+        WorkspaceDiff {
+            added,
+            removed,
+            changed,
+        }
+    }
+}
+
+fn load(path: &Utf8Path, role: ModelRole) -> Result<WorkspaceModel> {
+    let file = std::fs::File::open(path).with_context(|| format!("Open {}", path))?;
+    let source = ZipSource::new(std::io::BufReader::new(file))?;
+    let mut parser = SimulinkParser::new("", source);
+    let system = parser
+        .parse_system_file("simulink/systems/system_root.xml")
+        .with_context(|| format!("Failed to parse {}", path))?;
+    Ok(WorkspaceModel {
+        name: path.file_stem().unwrap_or(path.as_str()).to_string(),
+        path: path.to_path_buf(),
+        role,
+        system,
+    })
+}
+
+/// Libraries and referenced models of `system`, in block order, without
+/// the built-in virtual libraries.
+fn dependencies(system: &System) -> Vec<(String, ModelRole)> {
+    let mut out: Vec<(String, ModelRole)> = Vec::new();
+    system.walk_blocks(&mut Vec::new(), &mut |_, block| {
+        let library = block
+            .properties
+            .get("SourceBlock")
+            .filter(|s| !is_virtual_library(s))
+            .and_then(|s| split_source_block_reference(s))
+            .map(|(library, _)| (library, ModelRole::Library));
+        let referenced = block
+            .referenced_model()
+            .map(|name| (name.to_string(), ModelRole::Referenced));
+        for dep in library.into_iter().chain(referenced) {
+            if !out.iter().any(|(name, _)| *name == dep.0) {
+                out.push(dep);
+            }
+        }
+    });
+    out
+}
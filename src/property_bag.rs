@@ -0,0 +1,229 @@
+//! Insertion-ordered property storage for blocks.
+//!
+//! Blocks rarely have more than about twenty `<P>` properties, so a plain
+//! vector of pairs with linear lookup is faster to build and smaller than a
+//! hash map, and it keeps the original XML order the generator writes back.
+//! [`PropertyBag`] offers the subset of the `IndexMap` API the crate uses and
+//! serializes as a JSON object in insertion order.
+
+use serde::de::{MapAccess, Visitor};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// Ordered `name → value` map backed by a vector.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct PropertyBag {
+    entries: Vec<(String, String)>,
+}
+
+impl PropertyBag {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn position(&self, key: &str) -> Option<usize> {
+        self.entries.iter().position(|(k, _)| k == key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.entries.iter().find(|(k, _)| k == key).map(|(_, v)| v)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut String> {
+        self.entries
+            .iter_mut()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    }
+
+    /// The value of `key`, inserting `default()` first if it is missing.
+    pub fn get_or_insert_with(
+        &mut self,
+        key: &str,
+        default: impl FnOnce() -> String,
+    ) -> &mut String {
+        let index = match self.position(key) {
+            Some(index) => index,
+            None => {
+                self.entries.push((key.to_string(), default()));
+                self.entries.len() - 1
+            }
+        };
+        &mut self.entries[index].1
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.position(key).is_some()
+    }
+
+    /// Set `key` to `value`. An existing key keeps its position and its old
+    /// value is returned; a new key is appended.
+    pub fn insert(&mut self, key: String, value: String) -> Option<String> {
+        match self.get_mut(&key) {
+            Some(old) => Some(std::mem::replace(old, value)),
+            None => {
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Remove `key`, moving the last property into its place.
+    pub fn swap_remove(&mut self, key: &str) -> Option<String> {
+        let index = self.position(key)?;
+        Some(self.entries.swap_remove(index).1)
+    }
+
+    /// Remove `key`, keeping the order of the remaining properties.
+    pub fn shift_remove(&mut self, key: &str) -> Option<String> {
+        let index = self.position(key)?;
+        Some(self.entries.remove(index).1)
+    }
+
+    /// Keep only the properties for which `keep` returns true.
+    pub fn retain(&mut self, mut keep: impl FnMut(&String, &mut String) -> bool) {
+        self.entries.retain_mut(|(k, v)| keep(k, v));
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.entries.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut String)> {
+        self.entries.iter_mut().map(|(k, v)| (&*k, v))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(k, _)| k)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(_, v)| v)
+    }
+}
+
+/// Iterator over `(name, value)` pairs in insertion order.
+#[derive(Clone)]
+pub struct Iter<'a>(std::slice::Iter<'a, (String, String)>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a String);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl<'a> IntoIterator for &'a PropertyBag {
+    type Item = (&'a String, &'a String);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl IntoIterator for PropertyBag {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl FromIterator<(String, String)> for PropertyBag {
+    fn from_iter<I: IntoIterator<Item = (String, String)>>(iter: I) -> Self {
+        let mut bag = PropertyBag::new();
+        bag.extend(iter);
+        bag
+    }
+}
+
+impl Extend<(String, String)> for PropertyBag {
+    fn extend<I: IntoIterator<Item = (String, String)>>(&mut self, iter: I) {
+        for (k, v) in iter {
+            self.insert(k, v);
+        }
+    }
+}
+
+impl std::ops::Index<&str> for PropertyBag {
+    type Output = String;
+
+    fn index(&self, key: &str) -> &String {
+        self.get(key)
+            .unwrap_or_else(|| panic!("no property named {key:?}"))
+    }
+}
+
+impl fmt::Debug for PropertyBag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Serialize for PropertyBag {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.len()))?;
+        for (k, v) in self {
+            map.serialize_entry(k, v)?;
+        }
+        map.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for PropertyBag {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct BagVisitor;
+
+        impl<'de> Visitor<'de> for BagVisitor {
+            type Value = PropertyBag;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a map of property names to values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut access: A) -> Result<PropertyBag, A::Error> {
+                let mut bag = PropertyBag::with_capacity(access.size_hint().unwrap_or(0));
+                while let Some((k, v)) = access.next_entry::<String, String>()? {
+                    bag.insert(k, v);
+                }
+                Ok(bag)
+            }
+        }
+
+        deserializer.deserialize_map(BagVisitor)
+    }
+}
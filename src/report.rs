@@ -0,0 +1,322 @@
+//! Configuration audit reports.
+//!
+//! [`parameter_overrides`] lists every block parameter whose value differs
+//! from the model's `BlockParameterDefaults`, grouped by subsystem. Newer SLX
+//! files keep the defaults in `simulink/bddefaults.xml`, older ones inline
+//! in `simulink/blockdiagram.xml`; [`BlockParameterDefaults::from_source`]
+//! reads either.
+//!
+//! [`model_callbacks`] reads the model-level callbacks from
+//! `blockdiagram.xml`; together with [`System::all_callbacks`] it lists all
+//! MATLAB code a model runs when it is loaded, simulated or edited.
+//!
+//! [`sfunction_dependencies`] lists the MEX and MATLAB files the model's
+//! S-function blocks need, with the file name expected on each platform.
+
+use crate::block_path::BlockPath;
+use crate::cosmetic::CosmeticFilter;
+use crate::model::{Callbacks, System};
+use crate::parser::ContentSource;
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+const BLOCK_DIAGRAM: &str = "simulink/blockdiagram.xml";
+
+/// Files that may hold `BlockParameterDefaults`, in lookup order.
+const DEFAULTS_FILES: [&str; 2] = ["simulink/bddefaults.xml", BLOCK_DIAGRAM];
+
+/// Parameters that identify a block rather than configure it.
+const IDENTITY_PARAMS: &[&str] = &["Name", "SID", "BlockType"];
+
+/// Default parameter values per block type.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BlockParameterDefaults {
+    pub by_type: BTreeMap<String, IndexMap<String, String>>,
+}
+
+impl BlockParameterDefaults {
+    /// Parse the `<BlockParameterDefaults>` element of a `bddefaults.xml` or
+    /// `blockdiagram.xml` document. Documents without one yield no defaults.
+    pub fn parse(xml: &str) -> Result<Self> {
+        let doc = roxmltree::Document::parse(xml)?;
+        let mut by_type: BTreeMap<String, IndexMap<String, String>> = BTreeMap::new();
+        let sections = doc
+            .descendants()
+            .filter(|n| n.has_tag_name("BlockParameterDefaults"));
+        for block in sections.flat_map(|s| s.children().filter(|n| n.has_tag_name("Block"))) {
+            let Some(block_type) = block.attribute("BlockType") else {
+                continue;
+            };
+            let params = by_type.entry(block_type.to_string()).or_default();
+            for p in block.children().filter(|n| n.has_tag_name("P")) {
+                if let Some(name) = p.attribute("Name") {
+                    params.insert(name.to_string(), p.text().unwrap_or("").to_string());
+                }
+            }
+        }
+        Ok(Self { by_type })
+    }
+
+    /// Read the defaults from a model source, trying `bddefaults.xml` before
+    /// `blockdiagram.xml`.
+    pub fn from_source<S: ContentSource>(source: &mut S) -> Result<Self> {
+        for path in DEFAULTS_FILES {
+            let Ok(xml) = source.read_to_string(Utf8Path::new(path)) else {
+                continue;
+            };
+            let defaults = Self::parse(&xml)?;
+            if !defaults.by_type.is_empty() {
+                return Ok(defaults);
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// Default value of `param` for blocks of `block_type`.
+    pub fn get(&self, block_type: &str, param: &str) -> Option<&str> {
+        self.by_type
+            .get(block_type)
+            .and_then(|p| p.get(param))
+            .map(String::as_str)
+    }
+}
+
+/// Read the callbacks of the model (`PreLoadFcn`, `InitFcn`, `StopFcn`, …)
+/// from its `blockdiagram.xml`. Sources without one yield no callbacks.
+pub fn model_callbacks<S: ContentSource>(source: &mut S) -> Result<Callbacks> {
+    let Ok(xml) = source.read_to_string(Utf8Path::new(BLOCK_DIAGRAM)) else {
+        return Ok(Callbacks::default());
+    };
+    parse_model_callbacks(&xml)
+}
+
+/// Parse the callbacks of the `<Model>` or `<Library>` element of a
+/// `blockdiagram.xml` document.
+pub fn parse_model_callbacks(xml: &str) -> Result<Callbacks> {
+    let doc = roxmltree::Document::parse(xml)?;
+    let Some(model) = doc
+        .root_element()
+        .children()
+        .find(|n| n.has_tag_name("Model") || n.has_tag_name("Library"))
+    else {
+        return Ok(Callbacks::default());
+    };
+    Ok(Callbacks::from_properties(
+        model
+            .children()
+            .filter(|n| n.has_tag_name("P"))
+            .filter_map(|p| Some((p.attribute("Name")?, p.text().unwrap_or("")))),
+    ))
+}
+
+/// Options for [`parameter_overrides`].
+#[derive(Debug, Clone, Default)]
+pub struct OverrideOptions {
+    /// Skip the parameters this filter treats as cosmetic.
+    pub cosmetic: Option<CosmeticFilter>,
+}
+
+/// A block parameter whose value differs from its default.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParameterOverride {
+    pub block: String,
+    pub block_type: String,
+    pub parameter: String,
+    pub value: String,
+    /// `None` if the defaults have no value for this parameter.
+    pub default: Option<String>,
+}
+
+/// Overrides of the blocks directly inside one subsystem.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SubsystemOverrides {
+    /// Subsystem path from the root joined by `/` (`""` for the root).
+    pub path: String,
+    pub overrides: Vec<ParameterOverride>,
+}
+
+/// List the parameters of every block in `system` (recursively) that differ
+/// from `defaults`, grouped by subsystem in model order. Subsystems without
+/// overrides are omitted.
+pub fn parameter_overrides(
+    system: &System,
+    defaults: &BlockParameterDefaults,
+    options: OverrideOptions,
+) -> Vec<SubsystemOverrides> {
+    let mut groups: IndexMap<String, Vec<ParameterOverride>> = IndexMap::new();
+    let mut path = Vec::new();
+    system.walk_blocks(&mut path, &mut |p, block| {
+        for (param, value) in &block.properties {
+            if IDENTITY_PARAMS.contains(&param.as_str())
+                || options
+                    .cosmetic
+                    .as_ref()
+                    .is_some_and(|f| f.is_cosmetic(param))
+            {
+                continue;
+            }
+            let default = defaults.get(&block.block_type, param);
+            if default == Some(value.as_str()) {
+                continue;
+            }
+            groups
+                .entry(BlockPath::from(p).to_string())
+                .or_default()
+                .push(ParameterOverride {
+                    block: block.name.clone(),
+                    block_type: block.block_type.clone(),
+                    parameter: param.clone(),
+                    value: value.clone(),
+                    default: default.map(str::to_string),
+                });
+        }
+    });
+    groups
+        .into_iter()
+        .map(|(path, overrides)| SubsystemOverrides { path, overrides })
+        .collect()
+}
+
+/// Render a report as CSV with a header row.
+pub fn overrides_to_csv(report: &[SubsystemOverrides]) -> String {
+    let mut csv = String::from("subsystem,block,block_type,parameter,value,default\n");
+    for group in report {
+        for o in &group.overrides {
+            let fields = [
+                group.path.as_str(),
+                &o.block,
+                &o.block_type,
+                &o.parameter,
+                &o.value,
+                o.default.as_deref().unwrap_or(""),
+            ];
+            let row: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+            let _ = writeln!(csv, "{}", row.join(","));
+        }
+    }
+    csv
+}
+
+/// Quote a CSV field if it contains a separator, quote or line break.
+pub(crate) fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// A platform MATLAB loads MEX files for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MexPlatform {
+    Win64,
+    Glnxa64,
+    Maci64,
+    Maca64,
+}
+
+impl MexPlatform {
+    pub const ALL: [MexPlatform; 4] = [
+        MexPlatform::Win64,
+        MexPlatform::Glnxa64,
+        MexPlatform::Maci64,
+        MexPlatform::Maca64,
+    ];
+
+    /// MATLAB's name for the platform (`computer('arch')`).
+    pub fn name(self) -> &'static str {
+        match self {
+            MexPlatform::Win64 => "win64",
+            MexPlatform::Glnxa64 => "glnxa64",
+            MexPlatform::Maci64 => "maci64",
+            MexPlatform::Maca64 => "maca64",
+        }
+    }
+
+    /// MEX file extension without the dot.
+    pub fn mex_extension(self) -> &'static str {
+        match self {
+            MexPlatform::Win64 => "mexw64",
+            MexPlatform::Glnxa64 => "mexa64",
+            MexPlatform::Maci64 => "mexmaci64",
+            MexPlatform::Maca64 => "mexmaca64",
+        }
+    }
+
+    /// The platform this program runs on, if MATLAB supports it.
+    pub fn current() -> Option<Self> {
+        match (std::env::consts::OS, std::env::consts::ARCH) {
+            ("windows", "x86_64") => Some(MexPlatform::Win64),
+            ("linux", "x86_64") => Some(MexPlatform::Glnxa64),
+            ("macos", "x86_64") => Some(MexPlatform::Maci64),
+            ("macos", "aarch64") => Some(MexPlatform::Maca64),
+            _ => None,
+        }
+    }
+}
+
+/// An S-function file a model needs, with the blocks that use it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SFunctionDependency {
+    pub function_name: String,
+    /// Level-2 MATLAB S-Function, loaded from `<name>.m` on every platform.
+    pub matlab: bool,
+    /// Paths of the blocks calling the S-function, joined by `/`.
+    pub blocks: Vec<String>,
+    /// Source modules listed by any of the blocks.
+    pub modules: Vec<String>,
+}
+
+impl SFunctionDependency {
+    /// File name MATLAB loads the S-function from on `platform`.
+    pub fn expected_file(&self, platform: MexPlatform) -> String {
+        if self.matlab {
+            format!("{}.m", self.function_name)
+        } else {
+            format!("{}.{}", self.function_name, platform.mex_extension())
+        }
+    }
+
+    /// The first of `dirs` containing the file for `platform`.
+    pub fn find(&self, dirs: &[Utf8PathBuf], platform: MexPlatform) -> Option<Utf8PathBuf> {
+        let file = self.expected_file(platform);
+        dirs.iter().map(|d| d.join(&file)).find(|p| p.is_file())
+    }
+}
+
+/// The S-functions used by blocks of `system` (recursively), sorted by
+/// function name. Blocks without a `FunctionName` are skipped.
+pub fn sfunction_dependencies(system: &System) -> Vec<SFunctionDependency> {
+    let mut deps: BTreeMap<(String, bool), SFunctionDependency> = BTreeMap::new();
+    let mut path = Vec::new();
+    system.walk_blocks(&mut path, &mut |p, block| {
+        let Some(sfun) = block.s_function() else {
+            return;
+        };
+        if sfun.function_name.is_empty() {
+            return;
+        }
+        let dep = deps
+            .entry((sfun.function_name.clone(), sfun.matlab))
+            .or_insert_with(|| SFunctionDependency {
+                function_name: sfun.function_name.clone(),
+                matlab: sfun.matlab,
+                blocks: Vec::new(),
+                modules: Vec::new(),
+            });
+        dep.blocks
+            .push(BlockPath::from(p).child(&block.name).to_string());
+        for module in sfun.modules {
+            if !dep.modules.contains(&module) {
+                dep.modules.push(module);
+            }
+        }
+    });
+    deps.into_values().collect()
+}
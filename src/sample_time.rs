@@ -0,0 +1,272 @@
+//! Sample-time propagation.
+//!
+//! Simulink only stores the sample times blocks declare; everything set to
+//! `-1` inherits its rate when the model is compiled. [`propagate`]
+//! approximates that: inherited blocks run at the fastest rate among their
+//! inputs, subsystems pass rates through their Inport and Outport blocks, and
+//! Rate Transition blocks with an inherited output rate take it from the
+//! blocks they drive. Values that are not numeric (workspace variables) are
+//! left unresolved.
+
+use crate::analysis::{Connection, effective_connections};
+use crate::block_path::BlockPath;
+use crate::model::{Block, System};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+
+/// A resolved sample time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum SampleTime {
+    /// `0`: continuous.
+    Continuous,
+    /// `Ts` or `[Ts, offset]`.
+    Discrete { period: f64, offset: f64 },
+    /// `inf`: constant, never updated after initialization.
+    Constant,
+}
+
+impl SampleTime {
+    /// Parse a `SampleTime` parameter value. Inherited (`-1`) and
+    /// non-numeric values yield `None`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+        let parts: Vec<&str> = value
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|s| !s.is_empty())
+            .collect();
+        match parts[..] {
+            ["inf" | "Inf"] => Some(SampleTime::Constant),
+            [period] => Self::from_parts(period.parse().ok()?, 0.0),
+            [period, offset] => Self::from_parts(period.parse().ok()?, offset.parse().ok()?),
+            _ => None,
+        }
+    }
+
+    fn from_parts(period: f64, offset: f64) -> Option<Self> {
+        if period == 0.0 {
+            Some(SampleTime::Continuous)
+        } else if period > 0.0 && period.is_finite() {
+            Some(SampleTime::Discrete { period, offset })
+        } else {
+            None
+        }
+    }
+
+    /// The period of a discrete rate.
+    pub fn period(self) -> Option<f64> {
+        match self {
+            SampleTime::Discrete { period, .. } => Some(period),
+            _ => None,
+        }
+    }
+
+    /// Whether this rate runs more often than `other`. Continuous is the
+    /// fastest rate and constant the slowest.
+    pub fn is_faster_than(self, other: SampleTime) -> bool {
+        self.rank() < other.rank()
+    }
+
+    fn rank(self) -> f64 {
+        match self {
+            SampleTime::Continuous => 0.0,
+            SampleTime::Discrete { period, .. } => period,
+            SampleTime::Constant => f64::INFINITY,
+        }
+    }
+}
+
+impl fmt::Display for SampleTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SampleTime::Continuous => write!(f, "0"),
+            SampleTime::Discrete { period, offset } if *offset == 0.0 => write!(f, "{}", period),
+            SampleTime::Discrete { period, offset } => write!(f, "[{}, {}]", period, offset),
+            SampleTime::Constant => write!(f, "inf"),
+        }
+    }
+}
+
+/// The sample time a block declares, if any.
+///
+/// Constant blocks default to `inf`; Rate Transition blocks declare their
+/// output rate in `OutPortSampleTime`.
+pub fn declared(block: &Block) -> Option<SampleTime> {
+    let param = match block.block_type.as_str() {
+        "RateTransition" => "OutPortSampleTime",
+        "SubSystem" => "SystemSampleTime",
+        _ => "SampleTime",
+    };
+    match block.properties.get(param) {
+        Some(value) => SampleTime::parse(value),
+        None if block.block_type == "Constant" => Some(SampleTime::Constant),
+        None => None,
+    }
+}
+
+/// Propagated output sample times of every block in a model.
+#[derive(Debug, Clone, Default)]
+pub struct SampleTimes {
+    /// `(block path, output port)` -> rate. Paths are [`BlockPath`] strings
+    /// from the root.
+    by_port: HashMap<(String, u32), SampleTime>,
+}
+
+impl SampleTimes {
+    /// Rate of output `port` (1-based) of the block at `path`.
+    pub fn output(&self, path: &str, port: u32) -> Option<SampleTime> {
+        self.by_port.get(&(path.to_string(), port)).copied()
+    }
+
+    /// Rate of the block at `path`: its first output, or for sinks the rate
+    /// it runs at as determined by its inputs.
+    pub fn block(&self, path: &str) -> Option<SampleTime> {
+        self.output(path, 1)
+    }
+}
+
+/// Propagate sample times through `system` and its subsystems.
+pub fn propagate(system: &System) -> SampleTimes {
+    let mut times = SampleTimes::default();
+    propagate_level(system, &BlockPath::root(), &[], &mut times);
+    times
+}
+
+/// Propagate within one system level, given the rates arriving at its
+/// Inport blocks. Returns the rates leaving its Outport blocks by port.
+fn propagate_level(
+    system: &System,
+    prefix: &BlockPath,
+    inputs: &[Option<SampleTime>],
+    times: &mut SampleTimes,
+) -> Vec<Option<SampleTime>> {
+    let conns = effective_connections(system);
+    let path_of = |b: &Block| prefix.child(&b.name);
+    // (sid, output port) -> rate at this level.
+    let mut rates: HashMap<(String, u32), SampleTime> = HashMap::new();
+    let mut subsystem_inputs: HashMap<&str, Vec<Option<SampleTime>>> = HashMap::new();
+    let mut subsystem_outputs: HashMap<&str, Vec<Option<SampleTime>>> = HashMap::new();
+
+    // Every pass resolves at least one more block or stops.
+    for _ in 0..=system.blocks.len() {
+        let mut changed = false;
+        for block in &system.blocks {
+            let Some(sid) = block.sid.as_deref() else {
+                continue;
+            };
+            let input_rates = input_rates(&conns, &rates, sid);
+            let outputs: Vec<Option<SampleTime>> = match block.block_type.as_str() {
+                "Inport" => {
+                    let port = port_number(block);
+                    let from_parent = inputs.get(port as usize - 1).copied().flatten();
+                    vec![declared(block).or(from_parent)]
+                }
+                "SubSystem" if block.subsystem.is_some() => {
+                    let mut ins = input_rates.clone();
+                    if let Some(rate) = declared(block) {
+                        ins.iter_mut().for_each(|r| *r = Some(rate));
+                    }
+                    if subsystem_inputs.get(sid) != Some(&ins) {
+                        let inner = block.subsystem.as_deref().unwrap_or(system);
+                        let outs = propagate_level(inner, &path_of(block), &ins, times);
+                        subsystem_outputs.insert(sid, outs);
+                        subsystem_inputs.insert(sid, ins);
+                    }
+                    subsystem_outputs[sid].clone()
+                }
+                "RateTransition" => {
+                    vec![declared(block).or_else(|| downstream_rate(system, &conns, sid))]
+                }
+                _ => vec![declared(block).or_else(|| fastest(&input_rates))],
+            };
+            for (i, rate) in outputs.into_iter().enumerate() {
+                if let Some(rate) = rate {
+                    let key = (sid.to_string(), i as u32 + 1);
+                    if rates.insert(key, rate) != Some(rate) {
+                        changed = true;
+                    }
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let mut outports: Vec<(u32, Option<SampleTime>)> = Vec::new();
+    for block in &system.blocks {
+        let sid = block.sid.as_deref().unwrap_or_default();
+        for ((s, port), rate) in &rates {
+            if s == sid {
+                times
+                    .by_port
+                    .insert((path_of(block).to_string(), *port), *rate);
+            }
+        }
+        if block.block_type == "Outport" {
+            let rate = rates.get(&(sid.to_string(), 1)).copied();
+            outports.push((port_number(block), rate));
+        }
+    }
+    let count = outports.iter().map(|&(p, _)| p).max().unwrap_or(0);
+    let mut result = vec![None; count as usize];
+    for (port, rate) in outports {
+        result[port as usize - 1] = rate;
+    }
+    result
+}
+
+/// Rates arriving at the data inputs of block `sid`, by input port.
+fn input_rates(
+    conns: &[Connection],
+    rates: &HashMap<(String, u32), SampleTime>,
+    sid: &str,
+) -> Vec<Option<SampleTime>> {
+    let mut result: Vec<Option<SampleTime>> = Vec::new();
+    for c in conns
+        .iter()
+        .filter(|c| c.dst.sid == sid && c.dst.port_type == "in")
+    {
+        let idx = c.dst.port_index.max(1) as usize - 1;
+        if result.len() <= idx {
+            result.resize(idx + 1, None);
+        }
+        result[idx] = rates.get(&(c.src.sid.clone(), c.src.port_index)).copied();
+    }
+    result
+}
+
+/// The fastest known non-constant rate, else constant if any input is.
+fn fastest(rates: &[Option<SampleTime>]) -> Option<SampleTime> {
+    let known = rates.iter().flatten().copied();
+    known
+        .clone()
+        .filter(|r| *r != SampleTime::Constant)
+        .reduce(|a, b| if b.is_faster_than(a) { b } else { a })
+        .or_else(|| known.into_iter().next())
+}
+
+/// The first rate declared by a block driven by block `sid`.
+fn downstream_rate(system: &System, conns: &[Connection], sid: &str) -> Option<SampleTime> {
+    conns
+        .iter()
+        .filter(|c| c.src.sid == sid)
+        .filter_map(|c| {
+            system
+                .blocks
+                .iter()
+                .find(|b| b.sid.as_deref() == Some(c.dst.sid.as_str()))
+        })
+        .find_map(declared)
+}
+
+fn port_number(block: &Block) -> u32 {
+    block
+        .properties
+        .get("Port")
+        .and_then(|p| p.trim().parse().ok())
+        .filter(|&p| p > 0)
+        .unwrap_or(1)
+}
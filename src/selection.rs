@@ -0,0 +1,321 @@
+//! Field selections over a model, in a small GraphQL-like syntax.
+//!
+//! A selection names the fields to return, so callers get only what they
+//! need instead of whole blocks with all their properties:
+//!
+//! ```text
+//! blocks(type: "Gain") { path sid properties(Gain) }
+//! ```
+//!
+//! returns `{"blocks": [{"path": "Ctrl/K", "sid": "7", "properties": {"Gain": "2"}}, ...]}`.
+//!
+//! A field is a name, optional arguments in parentheses and an optional
+//! sub-selection in braces; fields are separated by whitespace or commas.
+//! Arguments are `key: "value"` filters or bare names.
+//!
+//! At the top level, `blocks` lists all blocks of the model, including those
+//! in subsystems, and takes `key: "value"` filters on `type`, `name`, `sid`,
+//! `path` or any block parameter. `block(path: "A/B")` is the block at a
+//! [`BlockPath`](crate::block_path::BlockPath). Every other field is a field
+//! of the root [`System`] JSON.
+//!
+//! Below a block, `path` is its block path and `properties(A, B)` picks
+//! parameters; without names it returns them all. Other fields select from
+//! the block JSON as printed by `rustylink -j`. On JSON objects, bare-name
+//! arguments pick keys; on arrays of objects, `key: "value"` arguments keep
+//! the matching elements.
+
+use crate::model::{Block, System};
+use crate::overlay::block_path;
+use anyhow::{Result, bail};
+use serde_json::{Map, Value};
+
+/// One selected field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub args: Vec<Arg>,
+    /// Fields selected below this one; empty to return the whole value.
+    pub selection: Vec<Field>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Arg {
+    /// `key: "value"`
+    Filter(String, String),
+    /// A bare name.
+    Name(String),
+}
+
+impl Field {
+    fn names(&self) -> Vec<&str> {
+        self.args
+            .iter()
+            .filter_map(|a| match a {
+                Arg::Name(name) => Some(name.as_str()),
+                Arg::Filter(..) => None,
+            })
+            .collect()
+    }
+
+    fn filters(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.args.iter().filter_map(|a| match a {
+            Arg::Filter(key, value) => Some((key.as_str(), value.as_str())),
+            Arg::Name(_) => None,
+        })
+    }
+}
+
+/// Parse a selection into its top-level fields.
+pub fn parse(text: &str) -> Result<Vec<Field>> {
+    let mut parser = Parser { text, pos: 0 };
+    let fields = parser.fields()?;
+    parser.skip_space();
+    if parser.pos < text.len() {
+        bail!(
+            "Unexpected '{}' at {}",
+            parser.peek().unwrap_or(' '),
+            parser.pos
+        );
+    }
+    if fields.is_empty() {
+        bail!("Empty selection");
+    }
+    Ok(fields)
+}
+
+/// Evaluate the selection `text` on `system`.
+pub fn select(system: &System, text: &str) -> Result<Value> {
+    select_fields(system, &parse(text)?)
+}
+
+/// Evaluate parsed `fields` on `system`, one entry per field.
+pub fn select_fields(system: &System, fields: &[Field]) -> Result<Value> {
+    let mut root = None;
+    let mut out = Map::new();
+    for field in fields {
+        let value = match field.name.as_str() {
+            "blocks" => {
+                let mut blocks = Vec::new();
+                let mut path = Vec::new();
+                system.walk_blocks(&mut path, &mut |parent, block| {
+                    let path = block_path(parent, &block.name);
+                    if field
+                        .filters()
+                        .all(|(key, value)| block_field(block, &path, key) == Some(value))
+                    {
+                        blocks.push(project_block(block, &path, &field.selection));
+                    }
+                });
+                Value::Array(blocks.into_iter().collect::<Result<_>>()?)
+            }
+            "block" => {
+                let Some((_, path)) = field.filters().find(|(key, _)| *key == "path") else {
+                    bail!("block needs a path argument");
+                };
+                match system.block_at(path) {
+                    Some(block) => project_block(block, path, &field.selection)?,
+                    None => Value::Null,
+                }
+            }
+            _ => {
+                if root.is_none() {
+                    root = Some(serde_json::to_value(system)?);
+                }
+                let value = root.as_ref().and_then(|r| r.get(&field.name));
+                apply(value.cloned().unwrap_or(Value::Null), field)
+            }
+        };
+        out.insert(field.name.clone(), value);
+    }
+    Ok(Value::Object(out))
+}
+
+/// Fields usable in `blocks` filters.
+fn block_field<'a>(block: &'a Block, path: &'a str, key: &str) -> Option<&'a str> {
+    match key {
+        "type" => Some(&block.block_type),
+        "name" => Some(&block.name),
+        "sid" => block.sid.as_deref(),
+        "path" => Some(path),
+        _ => block.properties.get(key).map(String::as_str),
+    }
+}
+
+fn project_block(block: &Block, path: &str, selection: &[Field]) -> Result<Value> {
+    if selection.is_empty() {
+        let mut value = serde_json::to_value(block)?;
+        value["path"] = path.into();
+        return Ok(value);
+    }
+    let mut full = None;
+    let mut out = Map::new();
+    for field in selection {
+        let value = match field.name.as_str() {
+            "path" => path.into(),
+            "name" => block.name.as_str().into(),
+            "type" => block.block_type.as_str().into(),
+            "sid" => block.sid.as_deref().into(),
+            "properties" => {
+                let names = field.names();
+                block
+                    .properties
+                    .iter()
+                    .filter(|(key, _)| names.is_empty() || names.contains(&key.as_str()))
+                    .map(|(key, value)| (key.clone(), Value::from(value.as_str())))
+                    .collect::<Map<_, _>>()
+                    .into()
+            }
+            _ => {
+                if full.is_none() {
+                    full = Some(serde_json::to_value(block)?);
+                }
+                let value = full.as_ref().and_then(|f| f.get(&field.name));
+                apply(value.cloned().unwrap_or(Value::Null), field)
+            }
+        };
+        out.insert(field.name.clone(), value);
+    }
+    Ok(Value::Object(out))
+}
+
+/// Apply the arguments and sub-selection of `field` to a JSON value.
+fn apply(value: Value, field: &Field) -> Value {
+    match value {
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .filter(|item| {
+                    field
+                        .filters()
+                        .all(|(key, value)| item.get(key).and_then(Value::as_str) == Some(value))
+                })
+                .map(|item| select_value(item, field))
+                .collect(),
+        ),
+        value => select_value(value, field),
+    }
+}
+
+fn select_value(value: Value, field: &Field) -> Value {
+    let Value::Object(mut map) = value else {
+        return value;
+    };
+    let names = field.names();
+    if !names.is_empty() {
+        map.retain(|key, _| names.contains(&key.as_str()));
+    }
+    if field.selection.is_empty() {
+        return Value::Object(map);
+    }
+    field
+        .selection
+        .iter()
+        .map(|sub| {
+            let value = map.remove(&sub.name).unwrap_or(Value::Null);
+            (sub.name.clone(), apply(value, sub))
+        })
+        .collect::<Map<_, _>>()
+        .into()
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek() {
+            if !(c.is_whitespace() || c == ',') {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        self.skip_space();
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        if !self.eat(expected) {
+            bail!("Expected '{}' at {}", expected, self.pos);
+        }
+        Ok(())
+    }
+
+    fn name(&mut self) -> Option<String> {
+        self.skip_space();
+        let start = self.pos;
+        while let Some(c) = self.peek() {
+            if !(c.is_alphanumeric() || c == '_') {
+                break;
+            }
+            self.pos += c.len_utf8();
+        }
+        (self.pos > start).then(|| self.text[start..self.pos].to_string())
+    }
+
+    fn string(&mut self) -> Result<String> {
+        self.expect('"')?;
+        let mut value = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(value);
+                }
+                '\\' => match chars.next() {
+                    Some((_, c)) => value.push(c),
+                    None => break,
+                },
+                c => value.push(c),
+            }
+        }
+        bail!("Unterminated string at {}", self.pos)
+    }
+
+    fn fields(&mut self) -> Result<Vec<Field>> {
+        let mut fields = Vec::new();
+        while let Some(name) = self.name() {
+            let mut args = Vec::new();
+            if self.eat('(') {
+                while !self.eat(')') {
+                    let Some(key) = self.name() else {
+                        bail!("Expected an argument at {}", self.pos);
+                    };
+                    if self.eat(':') {
+                        args.push(Arg::Filter(key, self.string()?));
+                    } else {
+                        args.push(Arg::Name(key));
+                    }
+                }
+            }
+            let selection = if self.eat('{') {
+                let selection = self.fields()?;
+                self.expect('}')?;
+                selection
+            } else {
+                Vec::new()
+            };
+            fields.push(Field {
+                name,
+                args,
+                selection,
+            });
+        }
+        Ok(fields)
+    }
+}
@@ -0,0 +1,255 @@
+//! HTTP server exposing parsing, queries and diffs as REST endpoints.
+//!
+//! Parsed models are cached in memory under an ID derived from the file
+//! content, so uploading the same file twice parses it once. Their
+//! [structural and full hashes](crate::hash) are computed once when cached;
+//! `POST /diff` of two models with the same full hash answers without
+//! comparing them.
+//!
+//! | Endpoint | Body / parameters | Response |
+//! |---|---|---|
+//! | `POST /parse` | `.slx` file bytes | model JSON as printed by `rustylink -j`; the ID in the `X-Model-Id` header, the hashes in `X-Structural-Hash` and `X-Full-Hash` |
+//! | `GET /model/{id}/query` | `?path=A/B`, `?type=Gain` or `?select=...`, or nothing | the block at `path` (`/` inside a name doubled), `[{path, block}]` of a type, the fields picked by a [selection](crate::selection), or the root system |
+//! | `DELETE /model/{id}` | | evicts the model |
+//! | `POST /diff` | `{"old": id, "new": id}` | diff JSON as printed by `rustylink diff` |
+//!
+//! Errors are returned as `{"error": message}`.
+
+use crate::cancel::CancellationToken;
+use crate::diff::diff_systems;
+use crate::model::{Block, System};
+use crate::overlay::block_path;
+use crate::parser::{ParserOptions, ProtectionKind, SimulinkParser, ZipSource};
+use anyhow::{Context, Result};
+use axum::Json;
+use axum::Router;
+use axum::body::Bytes;
+use axum::extract::{Path, Query, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::sync::{Arc, RwLock};
+
+/// Header carrying the ID of a parsed model.
+pub const MODEL_ID_HEADER: &str = "x-model-id";
+/// Header carrying [`System::structural_hash`] of a parsed model, in hex.
+pub const STRUCTURAL_HASH_HEADER: &str = "x-structural-hash";
+/// Header carrying [`System::full_hash`] of a parsed model, in hex.
+pub const FULL_HASH_HEADER: &str = "x-full-hash";
+
+/// Models parsed so far, by ID.
+#[derive(Debug, Default)]
+pub struct ServerState {
+    models: RwLock<HashMap<String, CachedModel>>,
+}
+
+/// A parsed model and its hashes.
+#[derive(Debug, Clone)]
+struct CachedModel {
+    system: Arc<System>,
+    structural_hash: u64,
+    full_hash: u64,
+}
+
+impl ServerState {
+    pub fn new() -> Arc<Self> {
+        Arc::default()
+    }
+
+    fn get(&self, id: &str) -> Result<CachedModel, ApiError> {
+        let models = self.models.read().unwrap_or_else(|e| e.into_inner());
+        models
+            .get(id)
+            .cloned()
+            .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("No model with ID {id}")))
+    }
+}
+
+/// The REST routes, sharing `state`.
+pub fn router(state: Arc<ServerState>) -> Router {
+    Router::new()
+        .route("/parse", post(parse))
+        .route("/model/{id}/query", get(query))
+        .route("/model/{id}", delete(evict))
+        .route("/diff", post(diff))
+        .with_state(state)
+}
+
+/// Serve the routes on `addr` (e.g. `127.0.0.1:8080`) until the process ends.
+pub fn run(addr: &str) -> Result<()> {
+    let runtime = tokio::runtime::Runtime::new()?;
+    runtime.block_on(async {
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Bind {}", addr))?;
+        axum::serve(listener, router(ServerState::new())).await?;
+        Ok(())
+    })
+}
+
+/// An error response: status plus `{"error": message}`.
+#[derive(Debug)]
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+/// Parse the root system of an `.slx` file held in memory.
+fn parse_slx(bytes: &[u8], cancel: CancellationToken) -> Result<System> {
+    let mut source = ZipSource::new(std::io::Cursor::new(bytes))?;
+    if let Some(protected) = source.protection()
+        && protected.kind == ProtectionKind::ProtectedModel
+    {
+        return Err(protected.into());
+    }
+    let options = ParserOptions {
+        cancel,
+        ..ParserOptions::default()
+    };
+    let mut parser = SimulinkParser::with_options("", source, options);
+    parser.parse_system_file("simulink/systems/system_root.xml")
+}
+
+fn model_id(bytes: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+async fn parse(State(state): State<Arc<ServerState>>, body: Bytes) -> Result<Response, ApiError> {
+    let id = model_id(&body);
+    let model = match state.get(&id) {
+        Ok(model) => model,
+        Err(_) => {
+            // Axum drops this future when the client disconnects, which
+            // stops the parse through the guard.
+            let cancel = CancellationToken::new();
+            let _guard = cancel.drop_guard();
+            let system = tokio::task::spawn_blocking(move || parse_slx(&body, cancel))
+                .await
+                .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+                .map_err(|e| ApiError(StatusCode::UNPROCESSABLE_ENTITY, format!("{e:#}")))?;
+            let model = CachedModel {
+                structural_hash: system.structural_hash(),
+                full_hash: system.full_hash(),
+                system: Arc::new(system),
+            };
+            let mut models = state.models.write().unwrap_or_else(|e| e.into_inner());
+            models.insert(id.clone(), model.clone());
+            model
+        }
+    };
+    let mut response = Json(model.system.as_ref()).into_response();
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        headers.insert(MODEL_ID_HEADER, value.clone());
+    }
+    for (name, hash) in [
+        (STRUCTURAL_HASH_HEADER, model.structural_hash),
+        (FULL_HASH_HEADER, model.full_hash),
+    ] {
+        if let Ok(value) = HeaderValue::from_str(&format!("{hash:016x}")) {
+            headers.insert(name, value);
+        }
+    }
+    if let Ok(value) = HeaderValue::from_str(&format!("/model/{id}/query")) {
+        headers.insert(header::LOCATION, value);
+    }
+    Ok(response)
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryParams {
+    path: Option<String>,
+    #[serde(rename = "type")]
+    block_type: Option<String>,
+    select: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BlockMatch {
+    path: String,
+    block: Block,
+}
+
+async fn query(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+    Query(params): Query<QueryParams>,
+) -> Result<Response, ApiError> {
+    let system = state.get(&id)?.system;
+    if let Some(select) = params.select {
+        if params.path.is_some() || params.block_type.is_some() {
+            return Err(ApiError(
+                StatusCode::BAD_REQUEST,
+                "Give select without path or type".to_string(),
+            ));
+        }
+        let selected = crate::selection::select(&system, &select)
+            .map_err(|e| ApiError(StatusCode::BAD_REQUEST, format!("{e:#}")))?;
+        return Ok(Json(selected).into_response());
+    }
+    match (params.path, params.block_type) {
+        (Some(_), Some(_)) => Err(ApiError(
+            StatusCode::BAD_REQUEST,
+            "Give either path or type, not both".to_string(),
+        )),
+        (Some(path), None) => {
+            let block = system
+                .block_at(&path)
+                .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, format!("No block at {path}")))?;
+            Ok(Json(block).into_response())
+        }
+        (None, Some(block_type)) => {
+            let matches: Vec<BlockMatch> = system
+                .find_blocks_by_type(&block_type)
+                .into_iter()
+                .map(|(parent, block)| BlockMatch {
+                    path: block_path(&parent, &block.name),
+                    block,
+                })
+                .collect();
+            Ok(Json(matches).into_response())
+        }
+        (None, None) => Ok(Json(system.as_ref()).into_response()),
+    }
+}
+
+async fn evict(
+    State(state): State<Arc<ServerState>>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    let mut models = state.models.write().unwrap_or_else(|e| e.into_inner());
+    match models.remove(&id) {
+        Some(_) => Ok(StatusCode::NO_CONTENT),
+        None => Err(ApiError(
+            StatusCode::NOT_FOUND,
+            format!("No model with ID {id}"),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DiffRequest {
+    old: String,
+    new: String,
+}
+
+async fn diff(
+    State(state): State<Arc<ServerState>>,
+    Json(request): Json<DiffRequest>,
+) -> Result<Response, ApiError> {
+    let old = state.get(&request.old)?;
+    let new = state.get(&request.new)?;
+    if old.full_hash == new.full_hash {
+        return Ok(Json(json!({ "blocks": [], "connections": [] })).into_response());
+    }
+    Ok(Json(diff_systems(&old.system, &new.system)).into_response())
+}
@@ -0,0 +1,287 @@
+//! Long-running JSON-RPC service for editor integrations.
+//!
+//! [`run`] speaks JSON-RPC 2.0 with LSP-style `Content-Length` framing (plain
+//! one-message-per-line JSON is accepted too) and keeps the models it opened
+//! in memory. Models are identified by their file path.
+//!
+//! | Method | Params | Result |
+//! |---|---|---|
+//! | `initialize` | | `{"capabilities": [methods]}` |
+//! | `model/open`, `model/didSave` | `{file}` | `{"file", "blocks"}`; (re)loads and publishes diagnostics |
+//! | `model/close` | `{file}` | `null` |
+//! | `block/hover` | `{file, block}` | type, SID, parameters and library link of the block at path `block` |
+//! | `block/definition` | `{file, block}` | `{file, block}` of the library block it links to, or `null` |
+//! | `block/whereUsed` | `{source}` | `[{file, block}]` of blocks in open models linked to library block `source` |
+//! | `shutdown` / `exit` | | `null`; `exit` ends the service |
+//!
+//! After loading a model the service sends a `model/publishDiagnostics`
+//! notification with its rate-transition issues and magic numbers.
+
+use crate::model::System;
+use crate::overlay::block_path;
+use crate::parser::{LibraryResolver, split_source_block_reference};
+use crate::watch::load_model;
+use anyhow::{Context, Result, anyhow};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+
+/// JSON-RPC error codes.
+pub const PARSE_ERROR: i64 = -32700;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+/// A model could not be loaded or a block was not found.
+pub const REQUEST_FAILED: i64 = -32000;
+
+const METHODS: [&str; 9] = [
+    "initialize",
+    "model/open",
+    "model/didSave",
+    "model/close",
+    "block/hover",
+    "block/definition",
+    "block/whereUsed",
+    "shutdown",
+    "exit",
+];
+
+/// A failed request: JSON-RPC error code and message.
+#[derive(Debug)]
+struct RpcError(i64, String);
+
+impl From<anyhow::Error> for RpcError {
+    fn from(e: anyhow::Error) -> Self {
+        RpcError(REQUEST_FAILED, format!("{e:#}"))
+    }
+}
+
+/// The loaded models and the dispatch of requests to them.
+#[derive(Debug, Default)]
+pub struct Service {
+    models: BTreeMap<Utf8PathBuf, System>,
+    exit: bool,
+}
+
+impl Service {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Handle one message. Returns the response (none for notifications)
+    /// and any notifications to send before it.
+    pub fn handle(&mut self, message: &Value) -> (Option<Value>, Vec<Value>) {
+        let id = message.get("id").cloned();
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        let mut notifications = Vec::new();
+        let result = self.dispatch(method, &params, &mut notifications);
+        let response = id.map(|id| match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err(RpcError(code, message)) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": { "code": code, "message": message },
+            }),
+        });
+        (response, notifications)
+    }
+
+    /// Whether an `exit` message was received.
+    pub fn exited(&self) -> bool {
+        self.exit
+    }
+
+    fn dispatch(
+        &mut self,
+        method: &str,
+        params: &Value,
+        notifications: &mut Vec<Value>,
+    ) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(json!({ "capabilities": METHODS })),
+            "model/open" | "model/didSave" => {
+                let file = Utf8PathBuf::from(param(params, "file")?);
+                let system = load_model(&file)?;
+                let blocks = count_blocks(&system);
+                notifications.push(json!({
+                    "jsonrpc": "2.0",
+                    "method": "model/publishDiagnostics",
+                    "params": { "file": file.as_str(), "diagnostics": diagnostics(&system) },
+                }));
+                self.models.insert(file.clone(), system);
+                Ok(json!({ "file": file.as_str(), "blocks": blocks }))
+            }
+            "model/close" => {
+                let file = param(params, "file")?;
+                self.models.remove(Utf8Path::new(file));
+                Ok(Value::Null)
+            }
+            "block/hover" => {
+                let (file, block) = self.block(params)?;
+                let (_, system) = self.model(file)?;
+                let block = system
+                    .block_at(block)
+                    .ok_or_else(|| RpcError(REQUEST_FAILED, format!("No block at {block}")))?;
+                Ok(json!({
+                    "name": block.name,
+                    "type": block.block_type,
+                    "sid": block.sid,
+                    "parameters": block.properties,
+                    "sourceBlock": block.properties.get("SourceBlock"),
+                    "isSubsystem": block.subsystem.is_some(),
+                }))
+            }
+            "block/definition" => {
+                let (file, block) = self.block(params)?;
+                let (file, system) = self.model(file)?;
+                let block = system
+                    .block_at(block)
+                    .ok_or_else(|| RpcError(REQUEST_FAILED, format!("No block at {block}")))?;
+                let Some(source) = block.properties.get("SourceBlock") else {
+                    return Ok(Value::Null);
+                };
+                let Some((lib, lib_block)) = split_source_block_reference(source) else {
+                    return Ok(Value::Null);
+                };
+                let dir = match file.parent() {
+                    Some(dir) if !dir.as_str().is_empty() => dir,
+                    _ => Utf8Path::new("."),
+                };
+                let found = LibraryResolver::new([dir]).locate([lib.as_str()]).found;
+                Ok(match found.into_iter().next() {
+                    Some((_, lib_file)) => json!({ "file": lib_file.as_str(), "block": lib_block }),
+                    None => Value::Null,
+                })
+            }
+            "block/whereUsed" => {
+                let source = param(params, "source")?;
+                let mut uses = Vec::new();
+                for (file, system) in &self.models {
+                    system.walk_blocks(&mut Vec::new(), &mut |p, block| {
+                        if block.properties.get("SourceBlock").map(String::as_str) == Some(source) {
+                            uses.push(json!({ "file": file.as_str(), "block": block_path(p, &block.name) }));
+                        }
+                    });
+                }
+                Ok(Value::Array(uses))
+            }
+            "shutdown" => Ok(Value::Null),
+            "exit" => {
+                self.exit = true;
+                Ok(Value::Null)
+            }
+            other => Err(RpcError(
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{other}'"),
+            )),
+        }
+    }
+
+    fn model(&self, file: &str) -> Result<(&Utf8Path, &System), RpcError> {
+        self.models
+            .get_key_value(Utf8Path::new(file))
+            .map(|(f, s)| (f.as_path(), s))
+            .ok_or_else(|| RpcError(REQUEST_FAILED, format!("Model {file} is not open")))
+    }
+
+    fn block<'a>(&self, params: &'a Value) -> Result<(&'a str, &'a str), RpcError> {
+        Ok((param(params, "file")?, param(params, "block")?))
+    }
+}
+
+fn param<'a>(params: &'a Value, name: &str) -> Result<&'a str, RpcError> {
+    params
+        .get(name)
+        .and_then(Value::as_str)
+        .ok_or_else(|| RpcError(INVALID_PARAMS, format!("Missing string parameter '{name}'")))
+}
+
+fn count_blocks(system: &System) -> usize {
+    let mut count = 0;
+    system.walk_blocks(&mut Vec::new(), &mut |_, _| count += 1);
+    count
+}
+
+/// The [`lint`](crate::lint::lint) findings as diagnostics.
+fn diagnostics(system: &System) -> Vec<Value> {
+    crate::lint::lint(system)
+        .into_iter()
+        .map(|f| serde_json::to_value(f).unwrap_or_default())
+        .collect()
+}
+
+/// Read one message: `Content-Length` framed, or a single line of JSON.
+/// Returns `None` at end of input.
+pub fn read_message(reader: &mut impl BufRead) -> Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            match length {
+                Some(_) => break,
+                None => continue,
+            }
+        }
+        // Anything but a header starts a line-delimited message.
+        if length.is_none() && (line.trim_start().starts_with('{') || !line.contains(':')) {
+            return Ok(Some(line.to_string()));
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.eq_ignore_ascii_case("content-length")
+        {
+            length = Some(
+                value
+                    .trim()
+                    .parse::<usize>()
+                    .context("Bad Content-Length")?,
+            );
+        }
+    }
+    let length = length.ok_or_else(|| anyhow!("Missing Content-Length"))?;
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+    Ok(Some(String::from_utf8(body)?))
+}
+
+/// Write one `Content-Length` framed message.
+pub fn write_message(writer: &mut impl Write, message: &Value) -> Result<()> {
+    let body = serde_json::to_string(message)?;
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Serve requests from `reader` until `exit` or end of input.
+pub fn run(mut reader: impl BufRead, mut writer: impl Write) -> Result<()> {
+    let mut service = Service::new();
+    while let Some(text) = read_message(&mut reader)? {
+        let message: Value = match serde_json::from_str(&text) {
+            Ok(message) => message,
+            Err(e) => {
+                let error = json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": { "code": PARSE_ERROR, "message": e.to_string() },
+                });
+                write_message(&mut writer, &error)?;
+                continue;
+            }
+        };
+        let (response, notifications) = service.handle(&message);
+        for notification in &notifications {
+            write_message(&mut writer, notification)?;
+        }
+        if let Some(response) = response {
+            write_message(&mut writer, &response)?;
+        }
+        if service.exited() {
+            break;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,233 @@
+//! Signal kind inference for line styling.
+//!
+//! Simulink draws bus signals, nonscalar signals and control
+//! (function-call / action) signals differently from plain scalar lines. The
+//! model files don't record compiled signal dimensions, so this module infers
+//! them from the blocks that drive each line: sources with explicit
+//! dimensions (`Mux`, `Inport`, `Constant`, `BusCreator`, ...) seed the
+//! analysis, and elementwise blocks (`Gain`, `UnitDelay`, ...) forward the
+//! kind of their first input. Anything that can't be determined is `Scalar`.
+
+use crate::model::{Block, Branch, EndpointRef, Line};
+use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+
+/// Inferred kind of a signal line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SignalKind {
+    #[default]
+    Scalar,
+    /// Nonscalar signal with the given number of elements (> 1).
+    Vector(u32),
+    Bus,
+    /// Function-call or action signal.
+    Control,
+}
+
+/// Blocks whose output has the same dimensions as their first input.
+const ELEMENTWISE: &[&str] = &[
+    "Abs",
+    "Bias",
+    "DataTypeConversion",
+    "DiscreteIntegrator",
+    "Gain",
+    "Integrator",
+    "Math",
+    "Memory",
+    "Saturate",
+    "SignalConversion",
+    "SignalSpecification",
+    "Trigonometry",
+    "UnaryMinus",
+    "UnitDelay",
+    "ZeroOrderHold",
+];
+
+/// Blocks that only emit function-call or action signals.
+const CONTROL_SOURCES: &[&str] = &[
+    "If",
+    "SwitchCase",
+    "FunctionCallGenerator",
+    "FunctionCallSplit",
+];
+
+/// Destination port types that only accept control signals.
+const CONTROL_PORTS: &[&str] = &["ifaction"];
+
+/// Infer the kind of every line of one system, indexed like `lines`.
+///
+/// Subsystem boundaries are not crossed.
+pub fn infer_line_kinds(blocks: &[Block], lines: &[Line]) -> Vec<SignalKind> {
+    let by_sid: HashMap<&str, &Block> = blocks
+        .iter()
+        .filter_map(|b| b.sid.as_deref().map(|sid| (sid, b)))
+        .collect();
+
+    // Kind of each (block SID, output port index), seeded from sources.
+    let mut outputs: HashMap<(&str, u32), SignalKind> = HashMap::new();
+    for line in lines {
+        let Some(src) = line.src.as_ref() else {
+            continue;
+        };
+        if let Some(kind) = by_sid.get(src.sid.as_str()).and_then(|b| source_kind(b)) {
+            outputs.insert((src.sid.as_str(), src.port_index), kind);
+        }
+    }
+
+    // Forward kinds through elementwise blocks until nothing changes. Each
+    // pass can extend a chain by one block, so the line count bounds it.
+    for _ in 0..lines.len() {
+        let mut changed = false;
+        for line in lines {
+            let Some(src) = line.src.as_ref() else {
+                continue;
+            };
+            let Some(kind) = outputs.get(&(src.sid.as_str(), src.port_index)).copied() else {
+                continue;
+            };
+            if kind == SignalKind::Control {
+                continue;
+            }
+            for dst in line_destinations(line) {
+                if dst.port_type != "in" || dst.port_index != 1 {
+                    continue;
+                }
+                let Some(block) = by_sid.get(dst.sid.as_str()) else {
+                    continue;
+                };
+                if !ELEMENTWISE.contains(&block.block_type.as_str()) {
+                    continue;
+                }
+                if let Entry::Vacant(e) = outputs.entry((dst.sid.as_str(), 1)) {
+                    e.insert(kind);
+                    changed = true;
+                }
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    lines
+        .iter()
+        .map(|line| {
+            let from_src = line
+                .src
+                .as_ref()
+                .and_then(|src| outputs.get(&(src.sid.as_str(), src.port_index)).copied());
+            if let Some(kind) = from_src {
+                return kind;
+            }
+            if line_destinations(line).any(|d| CONTROL_PORTS.contains(&d.port_type.as_str())) {
+                return SignalKind::Control;
+            }
+            SignalKind::Scalar
+        })
+        .collect()
+}
+
+/// Kind of a block's outputs when it follows from the block alone.
+fn source_kind(block: &Block) -> Option<SignalKind> {
+    let prop = |name: &str| block.properties.get(name).map(String::as_str);
+    let bus_typed = prop("OutDataTypeStr").is_some_and(|t| t.trim().starts_with("Bus:"));
+    match block.block_type.as_str() {
+        _ if bus_typed => Some(SignalKind::Bus),
+        "BusCreator" => Some(SignalKind::Bus),
+        "BusSelector" if prop("OutputAsBus").is_some_and(|v| v.eq_ignore_ascii_case("on")) => {
+            Some(SignalKind::Bus)
+        }
+        t if CONTROL_SOURCES.contains(&t) => Some(SignalKind::Control),
+        "Mux" => {
+            let inputs = prop("Inputs")
+                .and_then(width_sum)
+                .or_else(|| block.port_counts.as_ref().and_then(|pc| pc.ins));
+            inputs.map(vector_kind)
+        }
+        "Inport" | "Constant" => {
+            let width = match block.block_type.as_str() {
+                "Constant" => prop("Value").and_then(literal_element_count),
+                _ => prop("PortDimensions").and_then(dimensions_product),
+            };
+            width.map(vector_kind)
+        }
+        _ => None,
+    }
+}
+
+fn vector_kind(width: u32) -> SignalKind {
+    if width > 1 {
+        SignalKind::Vector(width)
+    } else {
+        SignalKind::Scalar
+    }
+}
+
+/// Total width from a Mux `Inputs` parameter: a count (`"3"`) or a vector of
+/// per-input widths (`"[2 3]"`, where `-1` counts as one element).
+fn width_sum(value: &str) -> Option<u32> {
+    let value = value.trim();
+    if !value.starts_with('[') {
+        return value.parse::<u32>().ok();
+    }
+    let parts = integer_parts(value)?;
+    parts.iter().try_fold(0u32, |sum, &n| {
+        sum.checked_add(u32::try_from(n.max(1)).ok()?)
+    })
+}
+
+/// Number of elements from a `PortDimensions` value (`"3"`, `"[2 3]"`).
+/// `-1` (inherited) and counts beyond `u32` yield `None`.
+fn dimensions_product(value: &str) -> Option<u32> {
+    let parts = integer_parts(value)?;
+    if parts.is_empty() || parts.iter().any(|&n| n < 1) {
+        return None;
+    }
+    parts
+        .iter()
+        .try_fold(1u32, |count, &n| count.checked_mul(u32::try_from(n).ok()?))
+}
+
+/// Element count of a MATLAB numeric literal such as `[1 2 3]` or `[1;2]`.
+fn literal_element_count(value: &str) -> Option<u32> {
+    let value = value.trim();
+    if !value.starts_with('[') || !value.ends_with(']') {
+        return None;
+    }
+    let parts = numeric_parts(value)?;
+    u32::try_from(parts.len()).ok()
+}
+
+/// Parse a scalar or bracketed list of numbers separated by spaces, commas
+/// or semicolons. Returns `None` when any element is not a number.
+fn numeric_parts(value: &str) -> Option<Vec<f64>> {
+    value
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(|c: char| c.is_whitespace() || c == ',' || c == ';')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<f64>().ok())
+        .collect()
+}
+
+/// Like [`numeric_parts`], but every element must be an integer.
+fn integer_parts(value: &str) -> Option<Vec<i64>> {
+    numeric_parts(value)?
+        .into_iter()
+        .map(|f| (f.fract() == 0.0).then_some(f as i64))
+        .collect()
+}
+
+/// All destination endpoints of a line, including those on branches.
+fn line_destinations(line: &Line) -> impl Iterator<Item = &EndpointRef> {
+    fn collect<'a>(branches: &'a [Branch], out: &mut Vec<&'a EndpointRef>) {
+        for br in branches {
+            out.extend(br.dst.as_ref());
+            collect(&br.branches, out);
+        }
+    }
+    let mut out: Vec<&EndpointRef> = line.dst.iter().collect();
+    collect(&line.branches, &mut out);
+    out.into_iter()
+}
@@ -0,0 +1,109 @@
+//! JSON output with a fixed ordering, for snapshot tests and byte-level
+//! comparisons of exports.
+//!
+//! The plain `serde_json` output of a [`System`](crate::model::System)
+//! follows the document order of the model files, which changes when a model
+//! is re-saved or blocks are moved between files. [`to_value`] and
+//! [`to_string_pretty`] apply the following contract instead:
+//!
+//! - Object keys, including those of property maps, are sorted by their
+//!   UTF-8 bytes.
+//! - `blocks` and `annotations` arrays are sorted by SID. SIDs compare
+//!   segment by segment (`12:3` is split at `:`), numeric segments by value
+//!   and others as strings. Entries without SID come last.
+//! - `lines` arrays are sorted by source endpoint, then by destination
+//!   endpoint, each compared by SID, port type and port index.
+//! - All other arrays, e.g. line points, branches, mask parameters and
+//!   `child_order`, keep their order, which carries meaning.
+//!
+//! Sorting is stable, so entries that compare equal keep document order.
+//! Changes to this contract are breaking changes of the output format.
+
+use serde::Serialize;
+use serde_json::Value;
+use std::cmp::Ordering;
+
+/// `value` as JSON with the ordering described in the [module docs](self).
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<Value> {
+    // Without `preserve_order`, `serde_json` maps keep their keys sorted.
+    let mut value = serde_json::to_value(value)?;
+    sort(&mut value);
+    Ok(value)
+}
+
+/// Pretty-printed [`to_value`].
+pub fn to_string_pretty<T: Serialize + ?Sized>(value: &T) -> serde_json::Result<String> {
+    serde_json::to_string_pretty(&to_value(value)?)
+}
+
+fn sort(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, child) in map.iter_mut() {
+                sort(child);
+                if let Value::Array(items) = child {
+                    match key.as_str() {
+                        "blocks" | "annotations" => {
+                            items.sort_by(|a, b| compare_sids(a.get("sid"), b.get("sid")))
+                        }
+                        "lines" => items.sort_by(compare_lines),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(sort),
+        _ => {}
+    }
+}
+
+fn compare_lines(a: &Value, b: &Value) -> Ordering {
+    compare_endpoints(a.get("src"), b.get("src"))
+        .then_with(|| compare_endpoints(a.get("dst"), b.get("dst")))
+}
+
+fn compare_endpoints(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    let (a, b) = match (a.filter(|v| !v.is_null()), b.filter(|v| !v.is_null())) {
+        (Some(a), Some(b)) => (a, b),
+        (a, b) => return a.is_none().cmp(&b.is_none()),
+    };
+    compare_sids(a.get("sid"), b.get("sid"))
+        .then_with(|| {
+            let port_type = |v: &Value| {
+                v.get("port_type")
+                    .and_then(Value::as_str)
+                    .map(str::to_owned)
+            };
+            port_type(a).cmp(&port_type(b))
+        })
+        .then_with(|| {
+            let index = |v: &Value| v.get("port_index").and_then(Value::as_u64);
+            index(a).cmp(&index(b))
+        })
+}
+
+fn compare_sids(a: Option<&Value>, b: Option<&Value>) -> Ordering {
+    match (a.and_then(Value::as_str), b.and_then(Value::as_str)) {
+        (Some(a), Some(b)) => {
+            let mut a = a.split(':');
+            let mut b = b.split(':');
+            loop {
+                match (a.next(), b.next()) {
+                    (Some(x), Some(y)) => {
+                        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+                            (Ok(x), Ok(y)) => x.cmp(&y),
+                            (Ok(_), Err(_)) => Ordering::Less,
+                            (Err(_), Ok(_)) => Ordering::Greater,
+                            (Err(_), Err(_)) => x.cmp(y),
+                        };
+                        if ordering != Ordering::Equal {
+                            return ordering;
+                        }
+                    }
+                    (x, y) => return x.is_some().cmp(&y.is_some()),
+                }
+            }
+        }
+        (a, b) => a.is_none().cmp(&b.is_none()),
+    }
+}
@@ -0,0 +1,284 @@
+//! Test stimulus templates for a model's root inputs.
+//!
+//! [`root_inports`] reads the root-level Inport blocks with their declared
+//! data types and dimensions. [`stimulus_csv`] and [`stimulus_json`] turn them
+//! into data templates with one column (or array entry) per signal element,
+//! and [`harness_model`] builds a model that plays the data into the model
+//! under test through From Workspace blocks.
+
+use crate::generator::archive::BlockDiagramKind;
+use crate::generator::system_xml::{xml_escape, xml_escape_attr};
+use crate::model::{SlxArchive, System};
+use crate::report::csv_field;
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::fmt::Write as _;
+
+/// Data type assumed for Inports that inherit their type.
+pub const DEFAULT_DATA_TYPE: &str = "double";
+
+/// Workspace variable the harness reads stimulus data from; each Inport is
+/// one field of it (see [`InportSignal::field_name`]).
+pub const STIMULUS_VARIABLE: &str = "stimulus";
+
+/// A root-level input of a model.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InportSignal {
+    pub name: String,
+    /// 1-based port number.
+    pub port: u32,
+    /// Declared `OutDataTypeStr`, or [`DEFAULT_DATA_TYPE`] when inherited.
+    pub data_type: String,
+    /// Declared `PortDimensions`; `[1]` when inherited.
+    pub dimensions: Vec<u32>,
+    /// Declared `SampleTime`; `None` when inherited.
+    pub sample_time: Option<String>,
+}
+
+impl InportSignal {
+    /// Number of scalar elements per sample.
+    pub fn width(&self) -> u32 {
+        self.dimensions.iter().product()
+    }
+
+    /// CSV column headers: the name for scalars, `name(i)` per element
+    /// otherwise (column-major, as MATLAB indexes).
+    pub fn columns(&self) -> Vec<String> {
+        match self.width() {
+            1 => vec![self.name.clone()],
+            n => (1..=n).map(|i| format!("{}({})", self.name, i)).collect(),
+        }
+    }
+
+    /// The name as a valid MATLAB struct field: other characters become
+    /// `_`, and names not starting with a letter get an `x` prefix.
+    pub fn field_name(&self) -> String {
+        let mut field: String = self
+            .name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        if !field.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            field.insert(0, 'x');
+        }
+        field
+    }
+
+    /// Whether the From Workspace block may interpolate between samples.
+    fn interpolates(&self) -> bool {
+        matches!(self.data_type.as_str(), "double" | "single")
+    }
+}
+
+/// The root-level Inport blocks of `system`, ordered by port number.
+pub fn root_inports(system: &System) -> Vec<InportSignal> {
+    let mut inports: Vec<InportSignal> = system
+        .blocks
+        .iter()
+        .filter(|b| b.block_type == "Inport")
+        .map(|b| {
+            let prop = |name: &str| b.properties.get(name).map(|v| v.trim());
+            InportSignal {
+                name: b.name.clone(),
+                port: prop("Port").and_then(|p| p.parse().ok()).unwrap_or(1),
+                data_type: prop("OutDataTypeStr")
+                    .filter(|t| !t.is_empty() && !t.starts_with("Inherit"))
+                    .unwrap_or(DEFAULT_DATA_TYPE)
+                    .to_string(),
+                dimensions: prop("PortDimensions")
+                    .and_then(parse_dimensions)
+                    .unwrap_or_else(|| vec![1]),
+                sample_time: prop("SampleTime")
+                    .filter(|t| *t != "-1")
+                    .map(str::to_string),
+            }
+        })
+        .collect();
+    inports.sort_by_key(|i| i.port);
+    inports
+}
+
+/// Names of the root-level Outport blocks of `system`, by port number.
+pub fn root_outports(system: &System) -> Vec<String> {
+    let mut outports: Vec<(u32, &str)> = system
+        .blocks
+        .iter()
+        .filter(|b| b.block_type == "Outport")
+        .map(|b| {
+            let port = b.properties.get("Port").and_then(|p| p.trim().parse().ok());
+            (port.unwrap_or(1), b.name.as_str())
+        })
+        .collect();
+    outports.sort_by_key(|&(port, _)| port);
+    outports.into_iter().map(|(_, n)| n.to_string()).collect()
+}
+
+/// Parse `PortDimensions` (`"3"`, `"[2 3]"`). `-1` (inherited) yields `None`.
+pub(crate) fn parse_dimensions(value: &str) -> Option<Vec<u32>> {
+    let dims: Vec<u32> = value
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse::<u32>().ok().filter(|&n| n > 0))
+        .collect::<Option<_>>()?;
+    (!dims.is_empty()).then_some(dims)
+}
+
+/// A CSV template: a `time` column plus one column per signal element, with
+/// a single all-zero sample at `t = 0`.
+pub fn stimulus_csv(inports: &[InportSignal]) -> String {
+    let mut header = vec!["time".to_string()];
+    header.extend(inports.iter().flat_map(InportSignal::columns));
+    let zeros = vec!["0"; header.len()];
+    let header: Vec<String> = header.iter().map(|h| csv_field(h)).collect();
+    let mut csv = String::new();
+    let _ = writeln!(csv, "{}", header.join(","));
+    let _ = writeln!(csv, "{}", zeros.join(","));
+    csv
+}
+
+/// A JSON template with the interface description and, under
+/// [`STIMULUS_VARIABLE`], one From Workspace "structure with time" per
+/// Inport holding a single all-zero sample at `t = 0`.
+///
+/// In MATLAB, `s = jsondecode(fileread(file)); stimulus = s.stimulus;`
+/// provides the variable the [`harness_model`] reads.
+pub fn stimulus_json(inports: &[InportSignal]) -> Value {
+    let data: serde_json::Map<String, Value> = inports
+        .iter()
+        .map(|i| {
+            let dims: &[u32] = &i.dimensions;
+            let signal = json!({
+                "time": [0.0],
+                "signals": {
+                    "values": [vec![0; i.width() as usize]],
+                    "dimensions": if dims.len() == 1 { json!(dims[0]) } else { json!(dims) },
+                },
+            });
+            (i.field_name(), signal)
+        })
+        .collect();
+    json!({
+        "interface": inports,
+        STIMULUS_VARIABLE: data,
+    })
+}
+
+/// Build a test harness for `model_name`: one From Workspace block per Inport
+/// reading `stimulus.<field>`, wired into a Model block referencing the model
+/// under test, whose outputs drive Outports of the same names.
+pub fn harness_model(
+    model_name: &str,
+    inports: &[InportSignal],
+    outports: &[String],
+) -> Result<SlxArchive> {
+    let mut archive =
+        SlxArchive::new_empty(BlockDiagramKind::Model, &format!("{model_name}_harness"))?;
+    let xml = harness_xml(model_name, inports, outports);
+    let doc = roxmltree::Document::parse(&xml)?;
+    let system = crate::block::parse_system_shallow(doc.root_element(), "".into())?;
+    let root = archive
+        .get_system_mut("simulink/systems/system_root.xml")
+        .ok_or_else(|| anyhow!("No root system in harness"))?;
+    *root = system;
+    Ok(archive)
+}
+
+/// Vertical distance between harness rows.
+const ROW_HEIGHT: i32 = 50;
+
+fn harness_xml(model_name: &str, inports: &[InportSignal], outports: &[String]) -> String {
+    let rows = inports.len().max(outports.len()).max(1) as i32;
+    let model_sid = 1;
+    let mut xml = String::from("<System>\n");
+    let p = |xml: &mut String, name: &str, value: &str| {
+        let _ = writeln!(xml, "    <P Name=\"{}\">{}</P>", name, xml_escape(value));
+    };
+
+    let _ = writeln!(
+        xml,
+        "  <Block BlockType=\"ModelReference\" Name=\"{}\" SID=\"{}\">",
+        xml_escape_attr(model_name),
+        model_sid
+    );
+    let _ = writeln!(
+        xml,
+        "    <PortCounts in=\"{}\" out=\"{}\"/>",
+        inports.len(),
+        outports.len()
+    );
+    p(
+        &mut xml,
+        "Position",
+        &format!("[250, 20, 400, {}]", 20 + rows * ROW_HEIGHT - 20),
+    );
+    p(&mut xml, "ModelName", model_name);
+    xml.push_str("  </Block>\n");
+
+    let mut sid = model_sid + 1;
+    let mut lines = String::new();
+    for (row, inport) in inports.iter().enumerate() {
+        let y = 20 + row as i32 * ROW_HEIGHT;
+        let _ = writeln!(
+            xml,
+            "  <Block BlockType=\"FromWorkspace\" Name=\"{}\" SID=\"{}\">",
+            xml_escape_attr(&inport.name),
+            sid
+        );
+        p(
+            &mut xml,
+            "Position",
+            &format!("[30, {}, 150, {}]", y, y + 30),
+        );
+        let variable = format!("{STIMULUS_VARIABLE}.{}", inport.field_name());
+        p(&mut xml, "VariableName", &variable);
+        if let Some(ts) = &inport.sample_time {
+            p(&mut xml, "SampleTime", ts);
+        }
+        if !inport.interpolates() {
+            p(&mut xml, "Interpolate", "off");
+        }
+        p(&mut xml, "OutputAfterFinalValue", "Holding final value");
+        xml.push_str("  </Block>\n");
+        let _ = writeln!(
+            lines,
+            "  <Line>\n    <P Name=\"Src\">{}#out:1</P>\n    <P Name=\"Dst\">{}#in:{}</P>\n  </Line>",
+            sid,
+            model_sid,
+            row + 1
+        );
+        sid += 1;
+    }
+    for (row, name) in outports.iter().enumerate() {
+        let y = 28 + row as i32 * ROW_HEIGHT;
+        let _ = writeln!(
+            xml,
+            "  <Block BlockType=\"Outport\" Name=\"{}\" SID=\"{}\">",
+            xml_escape_attr(name),
+            sid
+        );
+        p(
+            &mut xml,
+            "Position",
+            &format!("[480, {}, 510, {}]", y, y + 14),
+        );
+        if row > 0 {
+            p(&mut xml, "Port", &(row + 1).to_string());
+        }
+        xml.push_str("  </Block>\n");
+        let _ = writeln!(
+            lines,
+            "  <Line>\n    <P Name=\"Src\">{}#out:{}</P>\n    <P Name=\"Dst\">{}#in:1</P>\n  </Line>",
+            model_sid,
+            row + 1,
+            sid
+        );
+        sid += 1;
+    }
+    xml.push_str(&lines);
+    xml.push_str("</System>\n");
+    xml
+}
@@ -0,0 +1,363 @@
+//! Visual regression testing of the diagram renderer (feature `testing`).
+//!
+//! [`render_system`] and [`render_app`] run egui off-screen and rasterize
+//! the tessellated output on the CPU, so tests need neither a window nor a
+//! GPU. [`check_baseline`] compares a rendering with a stored PNG, allowing
+//! small per-channel differences and a fraction of differing pixels; on a
+//! mismatch it writes `<baseline>.actual.png` and `<baseline>.diff.png`
+//! next to the baseline. Run the tests with `RUSTYLINK_BLESS=1` to write
+//! missing or changed baselines, then review the images before committing.
+//!
+//! ```rust,ignore
+//! let image = render_system(&system, DEFAULT_SIZE);
+//! check_baseline(&image, "tests/fixtures/visual/gain.png", Tolerance::default())?;
+//! ```
+
+use crate::egui_app::{SubsystemApp, SystemView};
+use crate::model::System;
+use anyhow::{Context, Result, bail};
+use eframe::egui::{self, Color32, ColorImage, Pos2, Rect, TextureId, Vec2};
+use egui::epaint::{ClippedPrimitive, Mesh, Primitive, Vertex, textures::TexturesDelta};
+use resvg::tiny_skia::Pixmap;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Environment variable that makes [`check_baseline`] write baselines.
+pub const BLESS_VAR: &str = "RUSTYLINK_BLESS";
+
+/// Size in points (and pixels) of renderings in the repository's tests.
+pub const DEFAULT_SIZE: Vec2 = Vec2::new(640.0, 480.0);
+
+/// Frames run before rasterizing, so fonts are loaded and views fitted.
+const FRAMES: usize = 3;
+
+/// How far a rendering may be from its baseline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Tolerance {
+    /// Largest difference of a color channel that still counts as equal.
+    pub channel: u8,
+    /// Fraction of pixels allowed to differ by more than `channel`.
+    pub max_differing_fraction: f32,
+}
+
+impl Default for Tolerance {
+    fn default() -> Self {
+        Self {
+            channel: 8,
+            max_differing_fraction: 0.001,
+        }
+    }
+}
+
+/// Result of [`compare_images`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageDiff {
+    pub differing_pixels: usize,
+    pub total_pixels: usize,
+    /// Largest channel difference over all pixels.
+    pub max_channel_difference: u8,
+}
+
+impl ImageDiff {
+    pub fn differing_fraction(&self) -> f32 {
+        self.differing_pixels as f32 / self.total_pixels.max(1) as f32
+    }
+
+    pub fn is_within(&self, tolerance: Tolerance) -> bool {
+        self.differing_fraction() <= tolerance.max_differing_fraction
+    }
+}
+
+/// Render `system` fitted into `size` points at one pixel per point, with
+/// egui's light theme.
+pub fn render_system(system: &System, size: Vec2) -> Pixmap {
+    render_ui(size, |ui| {
+        SystemView::new(system, "rustylink_testing").show(ui);
+    })
+}
+
+/// Render the viewer of `app`, as drawn by
+/// [`update`](crate::egui_app::update), into `size` points.
+pub fn render_app(app: &mut SubsystemApp, size: Vec2) -> Pixmap {
+    render_ui(size, |ui| {
+        crate::egui_app::update(app, ui);
+    })
+}
+
+/// Render what `add_contents` draws into a central panel of `size` points.
+pub fn render_ui(size: Vec2, mut add_contents: impl FnMut(&mut egui::Ui)) -> Pixmap {
+    let ctx = egui::Context::default();
+    ctx.set_theme(egui::Theme::Light);
+    let mut textures = HashMap::new();
+    let mut last = None;
+    for _ in 0..FRAMES {
+        let input = egui::RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, size)),
+            ..Default::default()
+        };
+        let output = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| add_contents(ui));
+        });
+        apply_textures(&mut textures, &output.textures_delta);
+        last = Some(output);
+    }
+    let output = last.expect("at least one frame");
+    let primitives = ctx.tessellate(output.shapes, output.pixels_per_point);
+    let width = (size.x * output.pixels_per_point).round().max(1.0) as u32;
+    let height = (size.y * output.pixels_per_point).round().max(1.0) as u32;
+    rasterize(
+        &primitives,
+        &textures,
+        output.pixels_per_point,
+        width,
+        height,
+    )
+}
+
+fn apply_textures(textures: &mut HashMap<TextureId, ColorImage>, delta: &TexturesDelta) {
+    for (id, image_delta) in &delta.set {
+        let egui::ImageData::Color(patch) = &image_delta.image;
+        match image_delta.pos {
+            None => {
+                textures.insert(*id, (**patch).clone());
+            }
+            Some([x0, y0]) => {
+                let Some(image) = textures.get_mut(id) else {
+                    continue;
+                };
+                for y in 0..patch.size[1] {
+                    for x in 0..patch.size[0] {
+                        let (tx, ty) = (x0 + x, y0 + y);
+                        if tx < image.size[0] && ty < image.size[1] {
+                            image.pixels[ty * image.size[0] + tx] =
+                                patch.pixels[y * patch.size[0] + x];
+                        }
+                    }
+                }
+            }
+        }
+    }
+    for id in &delta.free {
+        textures.remove(id);
+    }
+}
+
+/// Draw tessellated meshes into a white pixmap of `width` × `height`.
+/// Paint callbacks are skipped.
+fn rasterize(
+    primitives: &[ClippedPrimitive],
+    textures: &HashMap<TextureId, ColorImage>,
+    pixels_per_point: f32,
+    width: u32,
+    height: u32,
+) -> Pixmap {
+    let mut pixmap = Pixmap::new(width, height).expect("non-empty pixmap");
+    pixmap.fill(resvg::tiny_skia::Color::WHITE);
+    for primitive in primitives {
+        let Primitive::Mesh(mesh) = &primitive.primitive else {
+            continue;
+        };
+        let clip = Rect::from_min_max(
+            (primitive.clip_rect.min.to_vec2() * pixels_per_point).to_pos2(),
+            (primitive.clip_rect.max.to_vec2() * pixels_per_point).to_pos2(),
+        )
+        .intersect(Rect::from_min_size(
+            Pos2::ZERO,
+            Vec2::new(width as f32, height as f32),
+        ));
+        draw_mesh(
+            &mut pixmap,
+            mesh,
+            textures.get(&mesh.texture_id),
+            clip,
+            pixels_per_point,
+        );
+    }
+    pixmap
+}
+
+fn draw_mesh(
+    pixmap: &mut Pixmap,
+    mesh: &Mesh,
+    texture: Option<&ColorImage>,
+    clip: Rect,
+    pixels_per_point: f32,
+) {
+    if clip.is_negative() {
+        return;
+    }
+    let width = pixmap.width() as usize;
+    let data = pixmap.data_mut();
+    for triangle in mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+        let [pa, pb, pc] = [a, b, c].map(|v| (v.pos.to_vec2() * pixels_per_point).to_pos2());
+        let area = edge(pa, pb, pc);
+        if area.abs() < 1e-6 {
+            continue;
+        }
+        let bounds = Rect::from_points(&[pa, pb, pc]).intersect(clip);
+        if bounds.is_negative() {
+            continue;
+        }
+        let (x0, x1) = (bounds.min.x.floor() as usize, bounds.max.x.ceil() as usize);
+        let (y0, y1) = (bounds.min.y.floor() as usize, bounds.max.y.ceil() as usize);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let p = Pos2::new(x as f32 + 0.5, y as f32 + 0.5);
+                if !clip.contains(p) {
+                    continue;
+                }
+                let w = [edge(pb, pc, p), edge(pc, pa, p), edge(pa, pb, p)].map(|e| e / area);
+                if w.iter().any(|w| *w < 0.0) {
+                    continue;
+                }
+                let src = shade([a, b, c], w, texture);
+                let i = (y * width + x) * 4;
+                let inv_alpha = 1.0 - src[3] / 255.0;
+                for (channel, value) in src.iter().enumerate() {
+                    let dst = data[i + channel] as f32;
+                    data[i + channel] = (value + dst * inv_alpha).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+        }
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p`.
+fn edge(a: Pos2, b: Pos2, p: Pos2) -> f32 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Premultiplied color at barycentric coordinates `w`, times the texel.
+fn shade(vertices: [&Vertex; 3], w: [f32; 3], texture: Option<&ColorImage>) -> [f32; 4] {
+    let mut color = [0.0f32; 4];
+    let mut uv = Vec2::ZERO;
+    for (v, w) in vertices.iter().zip(w) {
+        for (channel, value) in color.iter_mut().zip(v.color.to_array()) {
+            *channel += value as f32 * w;
+        }
+        uv += v.uv.to_vec2() * w;
+    }
+    let texel = texture.map_or(Color32::WHITE, |image| {
+        let x = ((uv.x * image.size[0] as f32) as usize).min(image.size[0] - 1);
+        let y = ((uv.y * image.size[1] as f32) as usize).min(image.size[1] - 1);
+        image.pixels[y * image.size[0] + x]
+    });
+    for (channel, value) in color.iter_mut().zip(texel.to_array()) {
+        *channel *= value as f32 / 255.0;
+    }
+    color
+}
+
+/// Compare two images pixel by pixel; errors if their sizes differ.
+pub fn compare_images(
+    actual: &Pixmap,
+    expected: &Pixmap,
+    tolerance: Tolerance,
+) -> Result<ImageDiff> {
+    if (actual.width(), actual.height()) != (expected.width(), expected.height()) {
+        bail!(
+            "image is {}x{}, expected {}x{}",
+            actual.width(),
+            actual.height(),
+            expected.width(),
+            expected.height()
+        );
+    }
+    let mut diff = ImageDiff {
+        differing_pixels: 0,
+        total_pixels: (actual.width() * actual.height()) as usize,
+        max_channel_difference: 0,
+    };
+    for (a, e) in actual
+        .data()
+        .chunks_exact(4)
+        .zip(expected.data().chunks_exact(4))
+    {
+        let largest = a
+            .iter()
+            .zip(e)
+            .map(|(a, e)| a.abs_diff(*e))
+            .max()
+            .unwrap_or(0);
+        diff.max_channel_difference = diff.max_channel_difference.max(largest);
+        if largest > tolerance.channel {
+            diff.differing_pixels += 1;
+        }
+    }
+    Ok(diff)
+}
+
+/// `expected` faded, with the pixels differing by more than `channel` in
+/// red.
+pub fn diff_image(actual: &Pixmap, expected: &Pixmap, channel: u8) -> Pixmap {
+    let mut out = expected.clone();
+    for (o, a) in out
+        .data_mut()
+        .chunks_exact_mut(4)
+        .zip(actual.data().chunks_exact(4))
+    {
+        let differs = o.iter().zip(a).any(|(o, a)| o.abs_diff(*a) > channel);
+        let pixel = if differs {
+            [255, 0, 0, 255]
+        } else {
+            let gray = o[..3].iter().map(|v| *v as u16).sum::<u16>() / 3;
+            let faded = (255 - (255 - gray) / 4) as u8;
+            [faded, faded, faded, 255]
+        };
+        o.copy_from_slice(&pixel);
+    }
+    out
+}
+
+/// Compare `actual` with the PNG at `baseline`. With [`BLESS_VAR`] set, the
+/// baseline is written instead.
+pub fn check_baseline(
+    actual: &Pixmap,
+    baseline: impl AsRef<Path>,
+    tolerance: Tolerance,
+) -> Result<()> {
+    let baseline = baseline.as_ref();
+    if std::env::var_os(BLESS_VAR).is_some() {
+        if let Some(dir) = baseline.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        actual
+            .save_png(baseline)
+            .with_context(|| format!("write {}", baseline.display()))?;
+        return Ok(());
+    }
+    let expected = Pixmap::load_png(baseline).with_context(|| {
+        format!(
+            "Missing {}; run with {}=1 to create it",
+            baseline.display(),
+            BLESS_VAR
+        )
+    })?;
+    let outcome = compare_images(actual, &expected, tolerance);
+    if outcome.as_ref().is_ok_and(|diff| diff.is_within(tolerance)) {
+        return Ok(());
+    }
+    let actual_path = sibling(baseline, "actual");
+    actual.save_png(&actual_path)?;
+    let diff = outcome?;
+    let diff_path = sibling(baseline, "diff");
+    diff_image(actual, &expected, tolerance.channel).save_png(&diff_path)?;
+    bail!(
+        "{} differs from {}: {} of {} pixels ({:.3}%), up to {} per channel; see {} (run with {}=1 to update)",
+        actual_path.display(),
+        baseline.display(),
+        diff.differing_pixels,
+        diff.total_pixels,
+        diff.differing_fraction() * 100.0,
+        diff.max_channel_difference,
+        diff_path.display(),
+        BLESS_VAR
+    )
+}
+
+/// `dir/name.png` → `dir/name.<suffix>.png`.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}.{suffix}.png"))
+}
@@ -0,0 +1,1625 @@
+//! Model refactorings on SLX archives.
+//!
+//! [`extract_to_library`] complements [`crate::analysis::find_clones`]: it
+//! moves one copy of a duplicated subsystem into a library and replaces every
+//! copy with a block linked to it. Both archives are regenerated through the
+//! regular SLX writer.
+//!
+//! [`disable_link`] and [`restore_link`] switch a library link between a
+//! `Reference` to its library block and a disabled link carrying a local copy
+//! of the content, keeping the `SourceBlock`/`AncestorBlock` metadata the way
+//! Simulink writes it. [`disable_link_in`] and [`restore_link_in`] do the
+//! same on an archive, adding or removing the content's system files.
+//!
+//! [`order_ports`] and [`order_ports_in`] renumber subsystem ports to match
+//! how their Inport and Outport blocks are placed, or move the blocks to
+//! match their numbers, and close gaps in the numbering.
+//!
+//! [`name_signals`] and [`name_signals_in`] name the unnamed signals that
+//! reach Outports, To Workspace blocks or signal logging after the port
+//! driving them.
+//!
+//! [`anonymize`] and [`anonymize_archive`] scrub a model for sharing in bug
+//! reports: names, annotations, callbacks and code are replaced or removed
+//! while blocks, ports and connections stay as they are.
+
+use crate::block_path::BlockPath;
+use crate::cosmetic::CosmeticFilter;
+use crate::generator::archive::{
+    BlockDiagramKind, RELS_HEADER, SYSTEM_REL_TYPE, remove_xml_elements,
+};
+use crate::generator::system_xml::xml_escape;
+use crate::model::{
+    Block, BlockChildKind, Branch, CFunctionCode, EndpointRef, InstanceData, LibraryOverrides,
+    Line, Port, PortCounts, SlxArchive, SlxArchiveEntry, SlxContent, System, is_callback_param,
+};
+use crate::parser::helpers::resolve_system_reference;
+use crate::port_info::port_name;
+use anyhow::{Context, Result, anyhow, bail};
+use camino::Utf8Path;
+use indexmap::IndexMap;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+const ROOT_SYSTEM: &str = "simulink/systems/system_root.xml";
+const SYSTEMS_DIR: &str = "simulink/systems";
+
+/// Vertical gap between blocks stacked in a library root.
+const LIBRARY_BLOCK_SPACING: i32 = 40;
+
+/// Result of [`extract_to_library`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibraryExtraction {
+    /// `SourceBlock` of the new library block (`<library>/<block>`).
+    pub source_block: String,
+    /// Paths of the subsystems that were replaced by linked blocks.
+    pub replaced: Vec<String>,
+}
+
+/// Create an empty library archive named `name`.
+pub fn new_library(name: &str) -> Result<SlxArchive> {
+    SlxArchive::new_empty(BlockDiagramKind::Library, name)
+}
+
+/// Move the subsystem at `paths[0]` into `library` (named `lib_name`) and
+/// replace the subsystems at all `paths` in `model` with blocks linked to it.
+///
+/// Paths are [`BlockPath`] strings from the root, as reported by
+/// [`crate::analysis::find_clones`]; each must name a `SubSystem` stored in its
+/// own system file. The library block keeps the first subsystem's name,
+/// parameters and mask; the linked blocks keep only their placement and
+/// appearance. Block SIDs inside the moved system files are kept as they are.
+pub fn extract_to_library(
+    model: &mut SlxArchive,
+    paths: &[&str],
+    library: &mut SlxArchive,
+    lib_name: &str,
+) -> Result<LibraryExtraction> {
+    if paths.is_empty() {
+        bail!("No subsystem paths given");
+    }
+    let parsed = paths
+        .iter()
+        .map(|p| p.parse::<BlockPath>())
+        .collect::<Result<Vec<_>>>()?;
+    for (a, pa) in paths.iter().zip(&parsed) {
+        if let Some((b, _)) = paths
+            .iter()
+            .zip(&parsed)
+            .find(|(_, pb)| pb.len() > pa.len() && pb.starts_with(pa))
+        {
+            bail!("Subsystem {} is nested inside {}", b, a);
+        }
+    }
+    let locations = paths
+        .iter()
+        .zip(&parsed)
+        .map(|(p, parsed)| locate_subsystem(model, p, parsed))
+        .collect::<Result<Vec<_>>>()?;
+
+    let prototype = model
+        .get_system(&locations[0].parent_file)
+        .map(|s| s.blocks[locations[0].index].clone())
+        .ok_or_else(|| anyhow!("Missing {}", locations[0].parent_file))?;
+    let lib_root = library
+        .root_system()
+        .ok_or_else(|| anyhow!("No root system in library {}", lib_name))?;
+    if lib_root.blocks.iter().any(|b| b.name == prototype.name) {
+        bail!(
+            "Library {} already contains a block named {}",
+            lib_name,
+            prototype.name
+        );
+    }
+
+    // Copy the prototype's system files, renumbered to fit the library.
+    let files = subtree_files(model, &locations[0].system_file)?;
+    let mut next = next_system_number(library);
+    let renames: BTreeMap<String, String> = files
+        .iter()
+        .map(|f| {
+            let name = format!("system_{next}");
+            next += 1;
+            (f.clone(), name)
+        })
+        .collect();
+    for file in &files {
+        let mut system = model
+            .get_system(file)
+            .cloned()
+            .ok_or_else(|| anyhow!("Missing {}", file))?;
+        let mut children = Vec::new();
+        rename_system_refs(&mut system, &renames, &mut children);
+        let new_file = format!("{SYSTEMS_DIR}/{}.xml", renames[file]);
+        for child in children {
+            add_system_relationship(library, &new_file, &child);
+        }
+        library.entries.push(SlxArchiveEntry {
+            path: new_file,
+            content: SlxContent::SystemXml(system),
+            compressed: true,
+        });
+    }
+
+    let mut lib_block = prototype.clone();
+    lib_block.sid = Some(next_sid(library).to_string());
+    lib_block.system_ref = Some(renames[&files[0]].clone());
+    lib_block.subsystem = None;
+    if let Some(root) = library.get_system_mut(ROOT_SYSTEM) {
+        if let Some(pos) = stacked_position(root, &prototype) {
+            lib_block.properties.insert("Position".into(), pos.clone());
+            lib_block.position = Some(pos);
+        }
+        root.blocks.push(lib_block);
+    }
+    add_system_relationship(library, ROOT_SYSTEM, &renames[&files[0]]);
+
+    let source_block = format!("{}/{}", lib_name, prototype.name);
+    let ports = port_counts(model.get_system(&locations[0].system_file));
+    for loc in &locations {
+        let files = subtree_files(model, &loc.system_file)?;
+        if let Some(system) = model.get_system_mut(&loc.parent_file) {
+            let block = &mut system.blocks[loc.index];
+            *block = linked_block(block, lib_name, &source_block, &ports);
+        }
+        remove_system_relationship(model, &loc.parent_file, &loc.ref_name);
+        let rels: Vec<String> = files.iter().map(|f| rels_path(f)).collect();
+        model
+            .entries
+            .retain(|e| !files.contains(&e.path) && !rels.contains(&e.path));
+    }
+
+    Ok(LibraryExtraction {
+        source_block,
+        replaced: paths.iter().map(|p| p.to_string()).collect(),
+    })
+}
+
+/// Where a subsystem block sits in an archive.
+struct Location {
+    /// System file containing the block.
+    parent_file: String,
+    /// Index of the block in that file's top-level blocks.
+    index: usize,
+    /// `Ref` of the block's `<System>` element.
+    ref_name: String,
+    /// System file holding the subsystem's content.
+    system_file: String,
+}
+
+fn locate_subsystem(archive: &SlxArchive, path: &str, segments: &BlockPath) -> Result<Location> {
+    if segments.is_root() {
+        bail!("Empty subsystem path");
+    }
+    let (parent_file, index) = locate_block(archive, path, segments)?;
+    let block = archive
+        .get_system(&parent_file)
+        .map(|s| &s.blocks[index])
+        .ok_or_else(|| anyhow!("Missing {}", parent_file))?;
+    let Some(ref_name) = block.system_ref.clone() else {
+        bail!("{} is not a subsystem with its own system file", block.name);
+    };
+    if block.block_type != "SubSystem" {
+        bail!("{} is a {} block, not a SubSystem", path, block.block_type);
+    }
+    let base = Utf8Path::new(&parent_file)
+        .parent()
+        .unwrap_or(Utf8Path::new(""));
+    let system_file = resolve_system_reference(&ref_name, base).to_string();
+    Ok(Location {
+        parent_file,
+        index,
+        ref_name,
+        system_file,
+    })
+}
+
+/// System file containing the block at `segments` and its index there.
+fn locate_block(archive: &SlxArchive, path: &str, segments: &BlockPath) -> Result<(String, usize)> {
+    let Some((name, parents)) = segments.split_last() else {
+        bail!("Empty block path");
+    };
+    let mut file = ROOT_SYSTEM.to_string();
+    for seg in parents {
+        let system = archive
+            .get_system(&file)
+            .ok_or_else(|| anyhow!("Missing {}", file))?;
+        let block = system
+            .blocks
+            .iter()
+            .find(|b| &b.name == seg)
+            .ok_or_else(|| anyhow!("No block {} on path {}", seg, path))?;
+        let Some(ref_name) = &block.system_ref else {
+            bail!("{} is not a subsystem with its own system file", seg);
+        };
+        let base = Utf8Path::new(&file).parent().unwrap_or(Utf8Path::new(""));
+        file = resolve_system_reference(ref_name, base).to_string();
+    }
+    let index = archive
+        .get_system(&file)
+        .ok_or_else(|| anyhow!("Missing {}", file))?
+        .blocks
+        .iter()
+        .position(|b| &b.name == name)
+        .ok_or_else(|| anyhow!("No block {} on path {}", name, path))?;
+    Ok((file, index))
+}
+
+/// `file` and every system file referenced from it, transitively.
+fn subtree_files(archive: &SlxArchive, file: &str) -> Result<Vec<String>> {
+    let mut files = vec![file.to_string()];
+    let mut i = 0;
+    while i < files.len() {
+        let system = archive
+            .get_system(&files[i])
+            .ok_or_else(|| anyhow!("Missing {}", files[i]))?;
+        let base = Utf8Path::new(&files[i])
+            .parent()
+            .unwrap_or(Utf8Path::new(""))
+            .to_owned();
+        if let Some(name) = stateflow_block(system) {
+            bail!("Subsystems with Stateflow block {} are not supported", name);
+        }
+        let mut path = Vec::new();
+        system.walk_blocks(&mut path, &mut |_, b| {
+            if let Some(r) = &b.system_ref {
+                files.push(resolve_system_reference(r, &base).to_string());
+            }
+        });
+        i += 1;
+    }
+    Ok(files)
+}
+
+/// Name of the first Stateflow block in `system`, which the transforms cannot
+/// move because its chart lives outside the system files.
+fn stateflow_block(system: &System) -> Option<String> {
+    let mut path = Vec::new();
+    let mut stateflow = None;
+    system.walk_blocks(&mut path, &mut |_, b| {
+        if b.properties.contains_key("SFBlockType") {
+            stateflow.get_or_insert_with(|| b.name.clone());
+        }
+    });
+    stateflow
+}
+
+/// Point `<System Ref>`s at their renamed files, collecting the new names.
+fn rename_system_refs(
+    system: &mut System,
+    renames: &BTreeMap<String, String>,
+    children: &mut Vec<String>,
+) {
+    for block in &mut system.blocks {
+        if let Some(r) = &block.system_ref {
+            let file = resolve_system_reference(r, Utf8Path::new(SYSTEMS_DIR));
+            if let Some(new) = renames.get(file.as_str()) {
+                block.system_ref = Some(new.clone());
+                children.push(new.clone());
+            }
+        }
+        if let Some(sub) = &mut block.subsystem {
+            rename_system_refs(sub, renames, children);
+        }
+    }
+}
+
+fn next_system_number(archive: &SlxArchive) -> u32 {
+    archive
+        .entries
+        .iter()
+        .filter_map(|e| {
+            e.path
+                .strip_prefix("simulink/systems/system_")?
+                .strip_suffix(".xml")?
+                .parse::<u32>()
+                .ok()
+        })
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
+fn next_sid(archive: &SlxArchive) -> u32 {
+    let mut max = 0;
+    for entry in &archive.entries {
+        if let SlxContent::SystemXml(system) = &entry.content {
+            let mut path = Vec::new();
+            system.walk_blocks(&mut path, &mut |_, b| {
+                if let Some(sid) = b.sid.as_deref().and_then(|s| s.parse::<u32>().ok()) {
+                    max = max.max(sid);
+                }
+            });
+            let annotations = system.annotations.iter().filter_map(|a| a.sid.as_deref());
+            max = annotations
+                .filter_map(|s| s.parse::<u32>().ok())
+                .fold(max, u32::max);
+        }
+    }
+    max + 1
+}
+
+/// Position for `block` below the lowest block in `root`, keeping its size.
+fn stacked_position(root: &System, block: &Block) -> Option<String> {
+    let (l, t, r, b) = parse_position(block.position.as_deref()?)?;
+    let below = root
+        .blocks
+        .iter()
+        .filter_map(|b| parse_position(b.position.as_deref()?))
+        .map(|(l, _, _, b)| (l, b))
+        .max_by_key(|&(_, b)| b);
+    let (left, top) = match below {
+        Some((left, bottom)) => (left, bottom + LIBRARY_BLOCK_SPACING),
+        None => (l, t),
+    };
+    Some(format!(
+        "[{}, {}, {}, {}]",
+        left,
+        top,
+        left + r - l,
+        top + b - t
+    ))
+}
+
+fn parse_position(pos: &str) -> Option<(i32, i32, i32, i32)> {
+    let v: Vec<i32> = pos
+        .trim()
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .split(',')
+        .map(|s| s.trim().parse().ok())
+        .collect::<Option<_>>()?;
+    match v[..] {
+        [l, t, r, b] => Some((l, t, r, b)),
+        _ => None,
+    }
+}
+
+/// Port counts of a subsystem from its Inport and Outport blocks.
+fn port_counts(system: Option<&System>) -> PortCounts {
+    let count =
+        |ty: &str| system.map(|s| s.blocks.iter().filter(|b| b.block_type == ty).count() as u32);
+    PortCounts {
+        ins: count("Inport").filter(|&n| n > 0),
+        outs: count("Outport").filter(|&n| n > 0),
+        special: Vec::new(),
+    }
+}
+
+/// A `Reference` block standing in for `block`, linked to `source_block`.
+fn linked_block(block: &Block, lib_name: &str, source_block: &str, ports: &PortCounts) -> Block {
+    let mut linked = block.clone();
+    linked.block_type = "Reference".to_string();
+    let cosmetic = CosmeticFilter::default();
+    linked.properties.retain(|k, _| cosmetic.is_cosmetic(k));
+    linked
+        .properties
+        .insert("SourceBlock".into(), source_block.to_string());
+    linked
+        .properties
+        .insert("SourceType".into(), "SubSystem".into());
+    linked.ref_properties.clear();
+    linked.port_counts.get_or_insert_with(|| ports.clone());
+    linked.subsystem = None;
+    linked.system_ref = None;
+    linked.mask = None;
+    linked.instance_data = None;
+    linked.link_data = None;
+    linked.child_order.clear();
+    linked.library_source = Some(lib_name.to_string());
+    linked.library_block_path = Some(source_block.to_string());
+    linked
+}
+
+/// `_rels` part listing the system files referenced from `system_file`.
+fn rels_path(system_file: &str) -> String {
+    match system_file.rsplit_once('/') {
+        Some((dir, name)) => format!("{dir}/_rels/{name}.rels"),
+        None => format!("_rels/{system_file}.rels"),
+    }
+}
+
+fn add_system_relationship(archive: &mut SlxArchive, parent_file: &str, ref_name: &str) {
+    let path = rels_path(parent_file);
+    let rel = format!(
+        "  <Relationship Id=\"{ref_name}\" Target=\"{ref_name}.xml\" Type=\"{SYSTEM_REL_TYPE}\"/>\n"
+    );
+    let existing = archive
+        .entries
+        .iter_mut()
+        .find_map(|e| match &mut e.content {
+            SlxContent::Raw(data) if e.path == path => Some(data),
+            _ => None,
+        });
+    match existing {
+        Some(data) => {
+            let mut xml = String::from_utf8_lossy(data).into_owned();
+            if let Some(idx) = xml.rfind("</Relationships>") {
+                xml.insert_str(idx, &rel);
+                *data = xml.into_bytes();
+            }
+        }
+        None => archive.entries.push(SlxArchiveEntry {
+            path,
+            content: SlxContent::Raw(format!("{RELS_HEADER}{rel}</Relationships>\n").into_bytes()),
+            compressed: true,
+        }),
+    }
+}
+
+fn remove_system_relationship(archive: &mut SlxArchive, parent_file: &str, ref_name: &str) {
+    let path = rels_path(parent_file);
+    let target = format!("{ref_name}.xml");
+    for entry in &mut archive.entries {
+        if let SlxContent::Raw(data) = &mut entry.content
+            && entry.path == path
+        {
+            let xml = String::from_utf8_lossy(data);
+            let xml = remove_xml_elements(&xml, "Relationship", "Target", |t| t == target);
+            *data = xml.into_bytes();
+        }
+    }
+}
+
+/// Turn the library link `block` into a disabled link, as Simulink's
+/// "Disable Link" does: a `SubSystem` holding its own copy of the library
+/// content.
+///
+/// The content must already be resolved into `block.subsystem`, e.g. by
+/// [`crate::parser::SimulinkParser::resolve_library_references`] or
+/// [`Block::instantiate_library_block`], so that the link's overrides are in
+/// the copied blocks and mask. `SourceBlock` becomes `AncestorBlock`, which
+/// [`restore_link`] relinks to; `SourceType`, `<InstanceData>` and
+/// `<LinkData>` are dropped.
+pub fn disable_link(block: &mut Block) -> Result<()> {
+    let Some(source) = block.properties.get("SourceBlock").cloned() else {
+        bail!("{} is not a library link", block.name);
+    };
+    if block.subsystem.is_none() {
+        bail!(
+            "Library content of {} ({}) is not resolved",
+            block.name,
+            source
+        );
+    }
+    // Instance parameters that are not mask parameters belong to the block.
+    let mask_params: BTreeSet<String> = block
+        .mask
+        .iter()
+        .flat_map(|m| &m.parameters)
+        .map(|p| p.name.clone())
+        .collect();
+    let own: Vec<(String, String)> = block
+        .instance_data
+        .take()
+        .into_iter()
+        .flat_map(|d| d.properties)
+        .filter(|(k, _)| !mask_params.contains(k))
+        .collect();
+
+    block.block_type = "SubSystem".to_string();
+    block.tag_name = "Block".to_string();
+    block.properties = std::mem::take(&mut block.properties)
+        .into_iter()
+        .filter(|(k, _)| k != "SourceType")
+        .map(|(k, v)| match k.as_str() {
+            "SourceBlock" => ("AncestorBlock".to_string(), v),
+            _ => (k, v),
+        })
+        .chain(own.iter().cloned())
+        .collect();
+    block.ref_properties.remove("SourceBlock");
+    if !block.child_order.is_empty() {
+        block.child_order.retain(|k| match k {
+            BlockChildKind::P(name) => name != "SourceType",
+            BlockChildKind::InstanceData
+            | BlockChildKind::LinkData
+            | BlockChildKind::Mask
+            | BlockChildKind::System => false,
+            _ => true,
+        });
+        for kind in &mut block.child_order {
+            if *kind == BlockChildKind::P("SourceBlock".into()) {
+                *kind = BlockChildKind::P("AncestorBlock".into());
+            }
+        }
+        for (name, _) in own {
+            block.child_order.push(BlockChildKind::P(name));
+        }
+        block.child_order.push(BlockChildKind::Mask);
+        block.child_order.push(BlockChildKind::System);
+    }
+    block.link_data = None;
+    block.library_overrides = None;
+    block.library_source = None;
+    block.library_block_path = None;
+    Ok(())
+}
+
+/// Relink the disabled library link `block` to its `AncestorBlock`, as
+/// Simulink's "Restore Link" does when local changes are discarded.
+///
+/// The block becomes a `Reference` again and keeps its mask parameter values
+/// as `<InstanceData>`; edits to the blocks inside are lost. Resolve library
+/// references again to get the library content back.
+pub fn restore_link(block: &mut Block) -> Result<()> {
+    let Some(ancestor) = block.properties.get("AncestorBlock").cloned() else {
+        bail!("{} is not a disabled library link", block.name);
+    };
+    let source = ancestor.parse::<BlockPath>()?;
+    let lib_name = match &source[..] {
+        [lib, _, ..] => lib.clone(),
+        _ => bail!("Invalid AncestorBlock '{}' on {}", ancestor, block.name),
+    };
+    let ports = port_counts(block.subsystem.as_deref());
+    let instance: IndexMap<String, String> = block
+        .mask
+        .iter()
+        .flat_map(|m| &m.parameters)
+        .filter_map(|p| Some((p.name.clone(), p.value.clone()?)))
+        .collect();
+    let version = block.properties.get("LibraryVersion").cloned();
+
+    let mut linked = linked_block(block, &lib_name, &ancestor, &ports);
+    if let Some(version) = version {
+        linked.properties.insert("LibraryVersion".into(), version);
+    }
+    if !instance.is_empty() {
+        let instance = InstanceData {
+            properties: instance,
+        };
+        linked.library_overrides = Some(LibraryOverrides::new(Some(&instance), None));
+        linked.instance_data = Some(instance);
+    }
+    *block = linked;
+    Ok(())
+}
+
+/// [`disable_link`] on the block at `path` in `model`, copying its content
+/// out of `library` into new system files.
+///
+/// The copied blocks get fresh SIDs so that they stay unique in the model.
+/// Fails without changing `model` if an override of the link names a block
+/// the library no longer has.
+pub fn disable_link_in(model: &mut SlxArchive, path: &str, library: &SlxArchive) -> Result<()> {
+    let (parent_file, index) = locate_block(model, path, &path.parse()?)?;
+    let mut block = model
+        .get_system(&parent_file)
+        .map(|s| s.blocks[index].clone())
+        .ok_or_else(|| anyhow!("Missing {}", parent_file))?;
+    let Some(source) = block.properties.get("SourceBlock").cloned() else {
+        bail!("{} is not a library link", path);
+    };
+    let lib_root = library.assembled_root_system()?;
+    let template = source
+        .parse::<BlockPath>()?
+        .split_first()
+        .and_then(|(_, inner)| lib_root.block_at_path(&inner.into()))
+        .ok_or_else(|| anyhow!("Library has no block {}", source))?;
+    let Some(content) = &template.subsystem else {
+        bail!("Library block {} is not a subsystem", source);
+    };
+    if let Some(name) = stateflow_block(content) {
+        bail!("Subsystems with Stateflow block {} are not supported", name);
+    }
+    let missing = block.instantiate_library_block(template);
+    if !missing.is_empty() {
+        bail!(
+            "Overrides of {} name blocks missing from {}: {}",
+            path,
+            source,
+            missing.join(", ")
+        );
+    }
+    disable_link(&mut block)?;
+
+    let mut sid = next_sid(model);
+    if let Some(content) = &mut block.subsystem {
+        renumber_sids(content, &mut sid);
+    }
+    let mut next = next_system_number(model);
+    store_subsystems(model, &parent_file, &mut block, &mut next);
+    if let Some(system) = model.get_system_mut(&parent_file) {
+        system.blocks[index] = block;
+    }
+    Ok(())
+}
+
+/// [`restore_link`] on the block at `path` in `model`, removing the system
+/// files of its local content.
+pub fn restore_link_in(model: &mut SlxArchive, path: &str) -> Result<()> {
+    let loc = locate_subsystem(model, path, &path.parse()?)?;
+    let files = subtree_files(model, &loc.system_file)?;
+    let ports = port_counts(model.get_system(&loc.system_file));
+    if let Some(system) = model.get_system_mut(&loc.parent_file) {
+        let block = &mut system.blocks[loc.index];
+        block.port_counts.get_or_insert(ports);
+        restore_link(block)?;
+    }
+    remove_system_relationship(model, &loc.parent_file, &loc.ref_name);
+    let rels: Vec<String> = files.iter().map(|f| rels_path(f)).collect();
+    model
+        .entries
+        .retain(|e| !files.contains(&e.path) && !rels.contains(&e.path));
+    Ok(())
+}
+
+/// Give every block and annotation in `system` a fresh SID counting up from
+/// `next`, updating the line endpoints that refer to them.
+fn renumber_sids(system: &mut System, next: &mut u32) {
+    let mut renames = HashMap::new();
+    for block in &mut system.blocks {
+        if let Some(old) = block.sid.replace(next.to_string()) {
+            renames.insert(old, next.to_string());
+        }
+        *next += 1;
+        if let Some(sub) = &mut block.subsystem {
+            renumber_sids(sub, next);
+        }
+    }
+    for annotation in &mut system.annotations {
+        if annotation.sid.is_some() {
+            annotation.sid = Some(next.to_string());
+            *next += 1;
+        }
+    }
+    for line in &mut system.lines {
+        for key in ["Src", "Dst"] {
+            if let Some(endpoint) = line.properties.get_mut(key) {
+                rename_endpoint(endpoint, &renames);
+            }
+        }
+        for endpoint in line.src.iter_mut().chain(line.dst.iter_mut()) {
+            if let Some(new) = renames.get(&endpoint.sid) {
+                endpoint.sid = new.clone();
+            }
+        }
+        renumber_branches(&mut line.branches, &renames);
+    }
+}
+
+fn renumber_branches(branches: &mut [Branch], renames: &HashMap<String, String>) {
+    for branch in branches {
+        if let Some(endpoint) = branch.properties.get_mut("Dst") {
+            rename_endpoint(endpoint, renames);
+        }
+        if let Some(endpoint) = &mut branch.dst
+            && let Some(new) = renames.get(&endpoint.sid)
+        {
+            endpoint.sid = new.clone();
+        }
+        renumber_branches(&mut branch.branches, renames);
+    }
+}
+
+/// Rewrite the SID of a `Src`/`Dst` value such as `12#out:1`.
+fn rename_endpoint(endpoint: &mut String, renames: &HashMap<String, String>) {
+    if let Some((sid, port)) = endpoint.split_once('#')
+        && let Some(new) = renames.get(sid)
+    {
+        *endpoint = format!("{new}#{port}");
+    }
+}
+
+/// Move the inline content of `block`, and of the subsystems inside it,
+/// into new system files referenced from `parent_file`.
+fn store_subsystems(
+    archive: &mut SlxArchive,
+    parent_file: &str,
+    block: &mut Block,
+    next: &mut u32,
+) {
+    block.system_ref = None;
+    let Some(mut system) = block.subsystem.take() else {
+        return;
+    };
+    let name = format!("system_{next}");
+    *next += 1;
+    let file = format!("{SYSTEMS_DIR}/{name}.xml");
+    for inner in &mut system.blocks {
+        store_subsystems(archive, &file, inner, next);
+    }
+    add_system_relationship(archive, parent_file, &name);
+    archive.entries.push(SlxArchiveEntry {
+        path: file,
+        content: SlxContent::SystemXml(*system),
+        compressed: true,
+    });
+    block.system_ref = Some(name);
+}
+
+/// How [`order_ports`] makes port numbers and positions agree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PortOrder {
+    /// Renumber the port blocks top to bottom, as they are placed.
+    ByPosition,
+    /// Keep the order of the port numbers and move the port blocks into
+    /// it, onto the rows the ports of the same kind took before.
+    ByNumber,
+}
+
+/// A port block renumbered or moved by [`order_ports`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PortChange {
+    /// [`BlockPath`] string of the Inport or Outport block.
+    pub block: String,
+    pub old_port: u32,
+    pub new_port: u32,
+    /// Whether the block was moved, which only [`PortOrder::ByNumber`] does.
+    pub moved: bool,
+}
+
+/// Make the Inport and Outport numbers of every subsystem in `system` agree
+/// with their vertical order, as set by `by`, and number them from 1
+/// without gaps. Lines into and out of the subsystem blocks follow their
+/// ports.
+///
+/// The ports of `system` itself are left alone, since whatever uses it
+/// would have to be rewired. Library links and Stateflow charts are
+/// skipped, as are port kinds with a block without `Position` or with
+/// duplicated port numbers.
+pub fn order_ports(system: &mut System, by: PortOrder) -> Vec<PortChange> {
+    let mut changes = Vec::new();
+    order_ports_below(system, &BlockPath::root(), by, &mut changes);
+    changes
+}
+
+fn order_ports_below(
+    system: &mut System,
+    path: &BlockPath,
+    by: PortOrder,
+    out: &mut Vec<PortChange>,
+) {
+    for index in 0..system.blocks.len() {
+        let block = &mut system.blocks[index];
+        let inner = path.child(&block.name);
+        let Some(sub) = block.subsystem.as_deref_mut() else {
+            continue;
+        };
+        order_ports_below(sub, &inner, by, out);
+        if !has_own_ports(block) {
+            continue;
+        }
+        let Some(sub) = block.subsystem.as_deref_mut() else {
+            continue;
+        };
+        let changes = order_level(sub, by);
+        follow_ports(system, index, &changes);
+        out.extend(changes.into_iter().map(|c| c.at(&inner)));
+    }
+}
+
+/// Order the ports of the subsystem block at `index` in `system` like
+/// [`order_ports`] does, without the subsystems inside it. Block paths in
+/// the result are relative to `system`.
+pub fn order_subsystem_ports(
+    system: &mut System,
+    index: usize,
+    by: PortOrder,
+) -> Result<Vec<PortChange>> {
+    let block = system
+        .blocks
+        .get_mut(index)
+        .ok_or_else(|| anyhow!("No block at index {}", index))?;
+    if !has_own_ports(block) {
+        bail!("{} is not a subsystem with its own ports", block.name);
+    }
+    let inner = BlockPath::root().child(&block.name);
+    let Some(sub) = block.subsystem.as_deref_mut() else {
+        bail!("Content of {} is not loaded", block.name);
+    };
+    let changes = order_level(sub, by);
+    follow_ports(system, index, &changes);
+    Ok(changes.into_iter().map(|c| c.at(&inner)).collect())
+}
+
+/// [`order_ports`] on every system file of `archive`. Block paths in the
+/// result are full paths from the root system.
+pub fn order_ports_in(archive: &mut SlxArchive, by: PortOrder) -> Result<Vec<PortChange>> {
+    let mut changes = Vec::new();
+    let mut queue = vec![(ROOT_SYSTEM.to_string(), BlockPath::root())];
+    let mut visited = BTreeSet::new();
+    while let Some((file, path)) = queue.pop() {
+        if !visited.insert(file.clone()) {
+            continue;
+        }
+        let base = Utf8Path::new(&file)
+            .parent()
+            .unwrap_or(Utf8Path::new(""))
+            .to_owned();
+        let system = archive
+            .get_system_mut(&file)
+            .ok_or_else(|| anyhow!("Missing {}", file))?;
+        order_ports_below(system, &path, by, &mut changes);
+        let children: Vec<(usize, String, BlockPath, bool)> = system
+            .blocks
+            .iter()
+            .enumerate()
+            .filter_map(|(index, b)| {
+                let r = b.system_ref.as_deref()?;
+                let child = resolve_system_reference(r, &base).to_string();
+                Some((index, child, path.child(&b.name), has_own_ports(b)))
+            })
+            .collect();
+        for (index, child, inner, own) in children.into_iter().rev() {
+            if own && let Some(sub) = archive.get_system_mut(&child) {
+                let level = order_level(sub, by);
+                if let Some(system) = archive.get_system_mut(&file) {
+                    follow_ports(system, index, &level);
+                }
+                changes.extend(level.into_iter().map(|c| c.at(&inner)));
+            }
+            queue.push((child, inner));
+        }
+    }
+    Ok(changes)
+}
+
+/// Whether the ports of `block` are defined by the Inport and Outport
+/// blocks of its own content.
+fn has_own_ports(block: &Block) -> bool {
+    block.block_type == "SubSystem"
+        && !block.properties.contains_key("SourceBlock")
+        && !block.properties.contains_key("SFBlockType")
+}
+
+/// A port block changed by [`order_level`], named within its system.
+struct LevelChange {
+    name: String,
+    port_type: &'static str,
+    old: u32,
+    new: u32,
+    moved: bool,
+}
+
+impl LevelChange {
+    fn at(self, system: &BlockPath) -> PortChange {
+        PortChange {
+            block: system.child(&self.name).to_string(),
+            old_port: self.old,
+            new_port: self.new,
+            moved: self.moved,
+        }
+    }
+}
+
+/// Renumber or move the port blocks of one system level.
+fn order_level(system: &mut System, by: PortOrder) -> Vec<LevelChange> {
+    let mut changes = Vec::new();
+    for (block_type, port_type) in [("Inport", "in"), ("Outport", "out")] {
+        // Block index, port number and position of each port block
+        let ports: Option<Vec<_>> = system
+            .blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.block_type == block_type)
+            .map(|(index, b)| {
+                let port: u32 = b
+                    .properties
+                    .get("Port")
+                    .and_then(|p| p.trim().parse().ok())
+                    .unwrap_or(1);
+                Some((index, port, parse_position(b.position.as_deref()?)?))
+            })
+            .collect();
+        let Some(mut ports) = ports else {
+            continue;
+        };
+        let numbers: BTreeSet<u32> = ports.iter().map(|p| p.1).collect();
+        if numbers.len() < ports.len() {
+            continue;
+        }
+        let mut rows: Vec<i32> = ports.iter().map(|p| p.2.1).collect();
+        rows.sort_unstable();
+        match by {
+            PortOrder::ByPosition => ports.sort_by_key(|&(_, _, (l, t, _, _))| (t, l)),
+            PortOrder::ByNumber => ports.sort_by_key(|p| p.1),
+        }
+        for (k, &(index, old, (l, t, r, b))) in ports.iter().enumerate() {
+            let new = k as u32 + 1;
+            let block = &mut system.blocks[index];
+            let moved = by == PortOrder::ByNumber && rows[k] != t;
+            if moved {
+                let top = rows[k];
+                let position = format!("[{}, {}, {}, {}]", l, top, r, top + b - t);
+                block.position = Some(position.clone());
+                set_property(block, "Position", &position);
+            }
+            if new != old {
+                set_property(block, "Port", &new.to_string());
+            }
+            if moved || new != old {
+                changes.push(LevelChange {
+                    name: block.name.clone(),
+                    port_type,
+                    old,
+                    new,
+                    moved,
+                });
+            }
+        }
+    }
+    changes
+}
+
+/// Re-index the port properties of the subsystem block at `index` and the
+/// lines connected to it after its content was reordered.
+fn follow_ports(system: &mut System, index: usize, changes: &[LevelChange]) {
+    for port_type in ["in", "out"] {
+        let renumbered: HashMap<u32, u32> = changes
+            .iter()
+            .filter(|c| c.port_type == port_type && c.old != c.new)
+            .map(|c| (c.old, c.new))
+            .collect();
+        if renumbered.is_empty() {
+            continue;
+        }
+        let block = &mut system.blocks[index];
+        let slots: Vec<usize> = (0..block.ports.len())
+            .filter(|&i| block.ports[i].port_type == port_type)
+            .collect();
+        let mut ports: Vec<Port> = slots.iter().map(|&i| block.ports[i].clone()).collect();
+        for port in &mut ports {
+            if let Some(new) = port.index.and_then(|i| renumbered.get(&i)) {
+                port.index = Some(*new);
+            }
+        }
+        ports.sort_by_key(|p| p.index);
+        for (slot, port) in slots.into_iter().zip(ports) {
+            block.ports[slot] = port;
+        }
+
+        let Some(sid) = block.sid.clone() else {
+            continue;
+        };
+        let follow =
+            |endpoint: &mut EndpointRef, properties: &mut IndexMap<String, String>, key| {
+                if endpoint.sid == sid
+                    && endpoint.port_type == port_type
+                    && let Some(new) = renumbered.get(&endpoint.port_index)
+                {
+                    endpoint.port_index = *new;
+                    properties.insert(key, endpoint.to_string());
+                }
+            };
+        for line in &mut system.lines {
+            if let Some(src) = &mut line.src {
+                follow(src, &mut line.properties, "Src".to_string());
+            }
+            if let Some(dst) = &mut line.dst {
+                follow(dst, &mut line.properties, "Dst".to_string());
+            }
+            follow_branches(&mut line.branches, &follow);
+        }
+    }
+}
+
+fn follow_branches(
+    branches: &mut [Branch],
+    follow: &impl Fn(&mut EndpointRef, &mut IndexMap<String, String>, String),
+) {
+    for branch in branches {
+        if let Some(dst) = &mut branch.dst {
+            follow(dst, &mut branch.properties, "Dst".to_string());
+        }
+        follow_branches(&mut branch.branches, follow);
+    }
+}
+
+/// Set a `<P>` parameter, adding it to the saved element order if new.
+fn set_property(block: &mut Block, name: &str, value: &str) {
+    if block
+        .properties
+        .insert(name.to_string(), value.to_string())
+        .is_none()
+        && !block.child_order.is_empty()
+    {
+        let at = block
+            .child_order
+            .iter()
+            .position(|k| *k == BlockChildKind::PortProperties)
+            .unwrap_or(block.child_order.len());
+        block
+            .child_order
+            .insert(at, BlockChildKind::P(name.to_string()));
+    }
+}
+
+/// Sink block types whose input signals [`name_signals`] names.
+const NAMED_SINKS: &[&str] = &["Outport", "ToWorkspace"];
+
+/// A signal named by [`name_signals`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SignalName {
+    /// [`BlockPath`] string of the block driving the signal.
+    pub block: String,
+    /// 1-based output port of that block.
+    pub port: u32,
+    pub name: String,
+}
+
+/// Name the unnamed lines in `system` and its subsystems that feed an
+/// Outport or To Workspace block, or leave a port marked for logging or as
+/// a test point, so that logging specifications and generated code get
+/// stable identifiers.
+///
+/// Names come from the driving port: its port name, the Outport inside a
+/// subsystem, or else the block name with the port number for blocks with
+/// several outputs. They are made valid identifiers and unique within each
+/// system level by appending `_2`, `_3`, ...
+pub fn name_signals(system: &mut System) -> Vec<SignalName> {
+    let mut named = Vec::new();
+    name_signals_below(system, &BlockPath::root(), &mut named);
+    named
+}
+
+/// [`name_signals`] on every system file of `archive`. Block paths in the
+/// result are full paths from the root system.
+pub fn name_signals_in(archive: &mut SlxArchive) -> Result<Vec<SignalName>> {
+    let mut named = Vec::new();
+    for (file, path) in system_files(archive)? {
+        if let Some(system) = archive.get_system_mut(&file) {
+            name_signals_below(system, &path, &mut named);
+        }
+    }
+    Ok(named)
+}
+
+fn name_signals_below(system: &mut System, path: &BlockPath, out: &mut Vec<SignalName>) {
+    let mut taken: BTreeSet<String> = system.lines.iter().filter_map(|l| l.name.clone()).collect();
+    for i in 0..system.lines.len() {
+        let line = &system.lines[i];
+        if line.name.as_deref().is_some_and(|n| !n.trim().is_empty()) {
+            continue;
+        }
+        let Some(src) = line.src.clone() else {
+            continue;
+        };
+        let Some(block) = system
+            .blocks
+            .iter()
+            .find(|b| b.sid.as_deref() == Some(src.sid.as_str()))
+        else {
+            continue;
+        };
+        let logged = block.ports.iter().any(|p| {
+            p.port_type == "out"
+                && p.index.unwrap_or(1) == src.port_index
+                && ["DataLogging", "TestPoint"].iter().any(|k| {
+                    p.properties
+                        .get(*k)
+                        .is_some_and(|v| v.trim().eq_ignore_ascii_case("on"))
+                })
+        });
+        let feeds_sink = line.connections().iter().any(|c| {
+            system.blocks.iter().any(|b| {
+                b.sid.as_deref() == Some(c.dst.sid.as_str())
+                    && NAMED_SINKS.contains(&b.block_type.as_str())
+            })
+        });
+        if !logged && !feeds_sink {
+            continue;
+        }
+        let base = identifier(&port_name(block, src.port_index, false).unwrap_or_else(|| {
+            let outputs = block.port_counts.as_ref().and_then(|c| c.outs).unwrap_or(1);
+            match outputs > 1 || src.port_index > 1 {
+                true => format!("{}_{}", block.name, src.port_index),
+                false => block.name.clone(),
+            }
+        }));
+        let name = (1..)
+            .map(|n| match n {
+                1 => base.clone(),
+                n => format!("{}_{}", base, n),
+            })
+            .find(|n| !taken.contains(n))
+            .unwrap_or(base);
+        out.push(SignalName {
+            block: path.child(&block.name).to_string(),
+            port: src.port_index,
+            name: name.clone(),
+        });
+        taken.insert(name.clone());
+        let line = &mut system.lines[i];
+        line.name = Some(name.clone());
+        // Simulink writes the name first.
+        line.properties.shift_insert(0, "Name".to_string(), name);
+    }
+    for block in &mut system.blocks {
+        let inner = path.child(&block.name);
+        if let Some(sub) = block.subsystem.as_deref_mut() {
+            name_signals_below(sub, &inner, out);
+        }
+    }
+}
+
+/// `name` as a MATLAB identifier: letters, digits and single underscores,
+/// starting with a letter.
+fn identifier(name: &str) -> String {
+    let mut id = String::new();
+    for c in name.chars() {
+        match c.is_ascii_alphanumeric() {
+            true => id.push(c),
+            false if !id.is_empty() && !id.ends_with('_') => id.push('_'),
+            false => {}
+        }
+    }
+    let id = id.trim_end_matches('_');
+    match id.chars().next() {
+        None => "signal".to_string(),
+        Some(c) if !c.is_ascii_alphabetic() => format!("s_{}", id),
+        Some(_) => id.to_string(),
+    }
+}
+
+/// The system files reachable from the root system with the path of the
+/// subsystem each holds, root first.
+fn system_files(archive: &SlxArchive) -> Result<Vec<(String, BlockPath)>> {
+    let mut files = vec![(ROOT_SYSTEM.to_string(), BlockPath::root())];
+    let mut i = 0;
+    while i < files.len() {
+        let (file, path) = files[i].clone();
+        let base = Utf8Path::new(&file).parent().unwrap_or(Utf8Path::new(""));
+        let system = archive
+            .get_system(&file)
+            .ok_or_else(|| anyhow!("Missing {}", file))?;
+        system.walk_blocks(&mut Vec::new(), &mut |parents, b| {
+            if let Some(r) = &b.system_ref {
+                let child = resolve_system_reference(r, base).to_string();
+                if !files.iter().any(|(f, _)| *f == child) {
+                    let mut inner = path.clone();
+                    for parent in parents {
+                        inner.push(parent);
+                    }
+                    inner.push(&b.name);
+                    files.push((child, inner));
+                }
+            }
+        });
+        i += 1;
+    }
+    Ok(files)
+}
+
+/// Block parameters holding code, emptied by [`AnonymizeOptions::strip_code`].
+const CODE_PARAMS: &[&str] = &[
+    "OutputCode",
+    "StartCode",
+    "TerminateCode",
+    "CodegenOutputCode",
+    "CodegenStartCode",
+    "CodegenTerminateCode",
+    "MATLABFcn",
+    "Expr",
+];
+
+/// Free-text block parameters, removed by
+/// [`AnonymizeOptions::strip_annotations`].
+const DOC_PARAMS: &[&str] = &["Description", "Tag", "AttributesFormatString"];
+
+/// Block parameters naming signals, Goto tags and data stores, renamed by
+/// [`AnonymizeOptions::rename_signals`].
+const SIGNAL_PARAMS: &[&str] = &["GotoTag", "DataStoreName"];
+
+/// Bus block parameters listing signal names, either comma-separated dotted
+/// element paths (`a,bus1.x`) or a quoted cell array (`{'a',{'bus1',{'x'}}}`).
+const SIGNAL_LIST_PARAMS: &[&str] = &["InputSignals", "OutputSignals", "AssignedSignals"];
+
+/// `metadata/coreProperties.xml` elements naming authors or describing the
+/// model; always emptied by [`anonymize_archive`].
+const CORE_PROPERTIES: &[&str] = &[
+    "creator",
+    "lastModifiedBy",
+    "title",
+    "subject",
+    "description",
+    "keywords",
+];
+
+const BLOCK_DIAGRAM: &str = "simulink/blockdiagram.xml";
+const CORE_PROPERTIES_PATH: &str = "metadata/coreProperties.xml";
+const STATEFLOW_DIR: &str = "simulink/stateflow/";
+
+/// What [`anonymize`] renames and removes. Everything is enabled by default.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnonymizeOptions {
+    /// Rename blocks to their type plus a number per system level (`Gain1`,
+    /// `SubSystem2`); ports become `In<n>` and `Out<n>`.
+    pub rename_blocks: bool,
+    /// Rename signals, Goto tags, data stores and the signals listed by bus
+    /// blocks to `signal<n>`. The same old name always maps to the same new
+    /// one, so Goto/From pairs and bus selections still match.
+    pub rename_signals: bool,
+    /// Remove annotations and free-text documentation (`Description`, `Tag`,
+    /// mask help and description).
+    pub strip_annotations: bool,
+    /// Remove callback parameters (`InitFcn`, `OpenFcn`, …) and mask
+    /// parameter callbacks.
+    pub strip_callbacks: bool,
+    /// Empty C Function code, `Fcn` expressions, MATLAB Function scripts,
+    /// mask initialization and icon drawing commands.
+    pub strip_code: bool,
+}
+
+impl Default for AnonymizeOptions {
+    fn default() -> Self {
+        AnonymizeOptions {
+            rename_blocks: true,
+            rename_signals: true,
+            strip_annotations: true,
+            strip_callbacks: true,
+            strip_code: true,
+        }
+    }
+}
+
+/// What [`anonymize`] changed. The renaming maps let the model owner
+/// translate paths in a bug report back; they must not be shared along
+/// with the model.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Anonymization {
+    /// New path of every renamed block, by old path.
+    pub renamed_blocks: BTreeMap<String, String>,
+    /// New name of every renamed signal, Goto tag and data store, by old
+    /// name.
+    pub renamed_signals: BTreeMap<String, String>,
+    pub removed_annotations: usize,
+    pub removed_callbacks: usize,
+    /// Code parameters, scripts and mask commands that were emptied.
+    pub stripped_code: usize,
+}
+
+/// Scrub `system` and its subsystems for sharing, keeping blocks, ports and
+/// connections.
+///
+/// Library links (`SourceBlock`), SIDs, block types and parameter values
+/// other than those listed in [`AnonymizeOptions`] are kept.
+pub fn anonymize(system: &mut System, options: &AnonymizeOptions) -> Anonymization {
+    let mut anonymizer = Anonymizer::new(options);
+    anonymizer.system(system, &BlockPath::root(), &BlockPath::root());
+    anonymizer.result
+}
+
+/// [`anonymize`] every system file of `archive`, and scrub the rest of the
+/// package: Stateflow chart names follow their renamed blocks and lose their
+/// scripts, model callbacks and descriptions in `blockdiagram.xml` are
+/// emptied, and author and title metadata is always cleared.
+///
+/// Block paths in the result are full paths from the root system. Write the
+/// archive with [`WriteOptions::strip_nonessential`] and a fresh thumbnail so
+/// that caches and the old preview image do not leak either.
+///
+/// [`WriteOptions::strip_nonessential`]: crate::generator::archive::WriteOptions::strip_nonessential
+pub fn anonymize_archive(
+    archive: &mut SlxArchive,
+    options: &AnonymizeOptions,
+) -> Result<Anonymization> {
+    let mut anonymizer = Anonymizer::new(options);
+    // Visit files from the root so block paths are complete; files nothing
+    // refers to are scrubbed afterwards with paths relative to themselves.
+    let mut queue = vec![(
+        ROOT_SYSTEM.to_string(),
+        BlockPath::root(),
+        BlockPath::root(),
+    )];
+    let mut visited = BTreeSet::new();
+    while let Some((file, old_prefix, new_prefix)) = queue.pop() {
+        if !visited.insert(file.clone()) {
+            continue;
+        }
+        let Some(system) = archive.get_system_mut(&file) else {
+            continue;
+        };
+        anonymizer.system(system, &old_prefix, &new_prefix);
+        let base = Utf8Path::new(&file).parent().unwrap_or(Utf8Path::new(""));
+        for (ref_name, old_path, new_path) in anonymizer.refs.drain(..) {
+            let child = resolve_system_reference(&ref_name, base).to_string();
+            queue.push((child, old_path, new_path));
+        }
+    }
+    let files: Vec<String> = archive
+        .entries
+        .iter()
+        .filter(|e| matches!(e.content, SlxContent::SystemXml(_)))
+        .map(|e| e.path.clone())
+        .collect();
+    for file in files {
+        if !visited.contains(&file)
+            && let Some(system) = archive.get_system_mut(&file)
+        {
+            anonymizer.system(system, &BlockPath::root(), &BlockPath::root());
+        }
+    }
+    anonymizer.refs.clear();
+
+    let mut result = anonymizer.result;
+    for entry in &mut archive.entries {
+        let SlxContent::Raw(data) = &mut entry.content else {
+            continue;
+        };
+        let path = entry.path.trim_start_matches("./").trim_start_matches('/');
+        let rewritten = if path.starts_with(STATEFLOW_DIR) && path.ends_with(".xml") {
+            let (renamed, strip_code) = (&result.renamed_blocks, options.strip_code);
+            let mut stripped = 0;
+            let xml = rewrite_element_text(data, |node| match node.attribute("Name")? {
+                "name" if node.has_tag_name("P") => renamed.get(node.text()?).cloned(),
+                "script" if node.has_tag_name("P") && strip_code => {
+                    stripped += 1;
+                    Some(String::new())
+                }
+                _ => None,
+            });
+            result.stripped_code += stripped;
+            xml
+        } else if path == BLOCK_DIAGRAM {
+            let mut callbacks = 0;
+            let mut annotations = 0;
+            let xml = rewrite_element_text(data, |node| {
+                let name = node.attribute("Name").filter(|_| node.has_tag_name("P"))?;
+                if options.strip_callbacks && is_callback_param(name) {
+                    callbacks += 1;
+                } else if options.strip_annotations && name == "Description" {
+                    annotations += 1;
+                } else {
+                    return None;
+                }
+                Some(String::new())
+            });
+            result.removed_callbacks += callbacks;
+            result.removed_annotations += annotations;
+            xml
+        } else if path == CORE_PROPERTIES_PATH {
+            rewrite_element_text(data, |node| {
+                CORE_PROPERTIES
+                    .contains(&node.tag_name().name())
+                    .then(String::new)
+            })
+        } else {
+            continue;
+        };
+        *data = rewritten
+            .with_context(|| format!("Failed to anonymize {}", entry.path))?
+            .into_bytes();
+    }
+    Ok(result)
+}
+
+struct Anonymizer<'o> {
+    options: &'o AnonymizeOptions,
+    result: Anonymization,
+    /// `<System Ref>`s met since the last drain, with the old and new path
+    /// of the referring block.
+    refs: Vec<(String, BlockPath, BlockPath)>,
+}
+
+impl<'o> Anonymizer<'o> {
+    fn new(options: &'o AnonymizeOptions) -> Self {
+        Anonymizer {
+            options,
+            result: Anonymization::default(),
+            refs: Vec::new(),
+        }
+    }
+
+    fn system(&mut self, system: &mut System, old_prefix: &BlockPath, new_prefix: &BlockPath) {
+        if self.options.strip_annotations {
+            self.result.removed_annotations += system.annotations.len();
+            system.annotations.clear();
+        }
+        if let Some(chart) = &mut system.chart {
+            if self.options.strip_code && chart.script.take().is_some() {
+                self.result.stripped_code += 1;
+            }
+            if self.options.rename_blocks && chart.name.is_some() {
+                chart.name = Some(new_prefix.to_string());
+            }
+        }
+        let mut counters: HashMap<&'static str, usize> = HashMap::new();
+        for block in &mut system.blocks {
+            let old_path = old_prefix.child(&block.name);
+            if self.options.rename_blocks {
+                let base = generic_name(&block.block_type);
+                let n = counters.entry(base).or_default();
+                *n += 1;
+                block.name = format!("{base}{n}");
+            }
+            let new_path = new_prefix.child(&block.name);
+            self.block(block);
+            if let Some(r) = &block.system_ref {
+                self.refs
+                    .push((r.clone(), old_path.clone(), new_path.clone()));
+            }
+            if let Some(sub) = &mut block.subsystem {
+                self.system(sub, &old_path, &new_path);
+            }
+            if old_path != new_path {
+                let renamed = &mut self.result.renamed_blocks;
+                renamed.insert(old_path.to_string(), new_path.to_string());
+            }
+        }
+        if self.options.rename_signals {
+            for line in &mut system.lines {
+                self.line(line);
+            }
+        }
+    }
+
+    fn block(&mut self, block: &mut Block) {
+        let options = self.options;
+        if options.rename_signals {
+            for name in SIGNAL_PARAMS {
+                if let Some(value) = block.properties.get(name).cloned() {
+                    let new = self.signal_name(&value);
+                    block.properties.insert(name.to_string(), new);
+                }
+            }
+            for name in SIGNAL_LIST_PARAMS {
+                if let Some(value) = block.properties.get(name).cloned() {
+                    let new = self.signal_list(&value);
+                    block.properties.insert(name.to_string(), new);
+                }
+            }
+            for port in &mut block.ports {
+                if let Some(value) = port.properties.get("Name").cloned() {
+                    let new = self.signal_name(&value);
+                    port.properties.insert("Name".to_string(), new);
+                }
+            }
+        }
+        if options.strip_callbacks {
+            let before = block.properties.len();
+            block.properties.retain(|name, _| !is_callback_param(name));
+            self.result.removed_callbacks += before - block.properties.len();
+            for param in block.mask.iter_mut().flat_map(|m| &mut m.parameters) {
+                if param.callback.take().is_some() {
+                    self.result.removed_callbacks += 1;
+                }
+            }
+        }
+        if options.strip_annotations {
+            self.result.removed_annotations += block.annotations.len();
+            block.annotations.clear();
+            for name in DOC_PARAMS {
+                block.properties.shift_remove(name);
+            }
+            if let Some(mask) = &mut block.mask {
+                mask.help = None;
+                mask.description = None;
+            }
+        }
+        if options.strip_code {
+            for name in CODE_PARAMS {
+                if let Some(value) = block.properties.get_mut(name)
+                    && !value.is_empty()
+                {
+                    value.clear();
+                    self.result.stripped_code += 1;
+                }
+            }
+            if block.c_function.is_some() {
+                block.c_function = Some(CFunctionCode::default());
+            }
+            if let Some(mask) = &mut block.mask {
+                for code in [&mut mask.initialization, &mut mask.display] {
+                    if code.take().is_some() {
+                        self.result.stripped_code += 1;
+                    }
+                }
+                block.mask_display_text = None;
+            }
+        }
+    }
+
+    fn line(&mut self, line: &mut Line) {
+        if let Some(name) = line.name.as_deref().map(|n| self.signal_name(n)) {
+            line.properties.insert("Name".to_string(), name.clone());
+            line.name = Some(name);
+        }
+        let mut branches: Vec<_> = line.branches.iter_mut().collect();
+        while let Some(branch) = branches.pop() {
+            if let Some(name) = branch.name.as_deref().map(|n| self.signal_name(n)) {
+                branch.properties.insert("Name".to_string(), name.clone());
+                branch.name = Some(name);
+            }
+            branches.extend(branch.branches.iter_mut());
+        }
+    }
+
+    /// The generic name for signal, tag or store `old`; empty names stay empty.
+    /// `list` of [`SIGNAL_LIST_PARAMS`] with every signal name renamed.
+    fn signal_list(&mut self, list: &str) -> String {
+        if list.contains('\'') {
+            // Every second piece is quoted.
+            let mut pieces = Vec::new();
+            for (i, piece) in list.split('\'').enumerate() {
+                pieces.push(match i % 2 {
+                    1 => self.signal_path(piece),
+                    _ => piece.to_string(),
+                });
+            }
+            return pieces.join("'");
+        }
+        let mut paths = Vec::new();
+        for path in list.split(',') {
+            paths.push(self.signal_path(path.trim()));
+        }
+        paths.join(",")
+    }
+
+    /// Dotted bus element `path` with every segment renamed.
+    fn signal_path(&mut self, path: &str) -> String {
+        let mut segments = Vec::new();
+        for segment in path.split('.') {
+            segments.push(self.signal_name(segment.trim()));
+        }
+        segments.join(".")
+    }
+
+    fn signal_name(&mut self, old: &str) -> String {
+        if old.is_empty() {
+            return String::new();
+        }
+        let next = self.result.renamed_signals.len() + 1;
+        self.result
+            .renamed_signals
+            .entry(old.to_string())
+            .or_insert_with(|| format!("signal{next}"))
+            .clone()
+    }
+}
+
+/// Name stem for anonymized blocks of `block_type`.
+fn generic_name(block_type: &str) -> &'static str {
+    // Leaked strings would grow with every model, so unknown types share a
+    // stem; the block type itself stays visible on the block.
+    match block_type {
+        "Inport" => "In",
+        "Outport" => "Out",
+        "SubSystem" => "Subsystem",
+        "Reference" => "Linked",
+        "Gain" => "Gain",
+        "Sum" => "Sum",
+        "Product" => "Product",
+        "Constant" => "Constant",
+        "Scope" => "Scope",
+        "Goto" => "Goto",
+        "From" => "From",
+        "Mux" => "Mux",
+        "Demux" => "Demux",
+        "Terminator" => "Terminator",
+        "UnitDelay" => "Delay",
+        "Delay" => "Delay",
+        "Switch" => "Switch",
+        "S-Function" => "SFunction",
+        _ => "Block",
+    }
+}
+
+/// Replace the text of the elements of `xml` for which `replace` returns a
+/// new value. Elements without text, or with child elements, are skipped.
+fn rewrite_element_text(
+    xml: &[u8],
+    mut replace: impl FnMut(roxmltree::Node) -> Option<String>,
+) -> Result<String> {
+    let xml = std::str::from_utf8(xml)?;
+    let doc = roxmltree::Document::parse(xml)?;
+    let mut edits = Vec::new();
+    for node in doc.descendants().filter(|n| n.is_element()) {
+        let Some(text) = node
+            .first_child()
+            .filter(|c| c.is_text() && c.next_sibling().is_none())
+        else {
+            continue;
+        };
+        if let Some(new) = replace(node) {
+            edits.push((text.range(), xml_escape(&new)));
+        }
+    }
+    let mut out = xml.to_string();
+    for (range, new) in edits.into_iter().rev() {
+        out.replace_range(range, &new);
+    }
+    Ok(out)
+}
@@ -0,0 +1,389 @@
+//! Terminal model browser (`rustylink tui`), for looking through a model
+//! over SSH where no GUI is available.
+//!
+//! The screen has three panes: the subsystem tree, the blocks of the
+//! subsystem selected in the tree, and the parameters of the selected block.
+//! [`Browser`] holds the navigation state and draws into any ratatui
+//! backend; [`run`] drives it on the terminal.
+//!
+//! | Key | Action |
+//! |---|---|
+//! | `↑`/`↓`, `k`/`j` | move the selection |
+//! | `Tab` | switch between tree and block list |
+//! | `→`/`l`, `←`/`h` | expand / collapse a subsystem in the tree |
+//! | `Enter` | tree: show the blocks; block list: open a subsystem or jump to a search result |
+//! | `/` | search block names and types across the model |
+//! | `Esc` | leave the search results or the search prompt |
+//! | `q` | quit |
+
+use crate::block_path::BlockPath;
+use crate::model::{Block, System};
+use anyhow::Result;
+use ratatui::Frame;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block as Panel, List, ListItem, ListState, Paragraph, Row, Table};
+use std::collections::HashSet;
+
+/// Block indices from the root system down to a subsystem block; empty for
+/// the root.
+pub type SubsystemPath = Vec<usize>;
+
+/// The pane receiving the arrow keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Tree,
+    Blocks,
+}
+
+/// Navigation state of the browser.
+pub struct Browser<'a> {
+    root: &'a System,
+    name: String,
+    expanded: HashSet<SubsystemPath>,
+    tree: ListState,
+    blocks: ListState,
+    pub focus: Focus,
+    /// Text typed after `/`, while the prompt is open.
+    pub prompt: Option<String>,
+    /// Blocks matching the last search, as (subsystem, block index).
+    pub results: Option<Vec<(SubsystemPath, usize)>>,
+}
+
+impl<'a> Browser<'a> {
+    /// Browse `root`; `name` titles the tree, usually the model name.
+    pub fn new(root: &'a System, name: &str) -> Self {
+        let mut browser = Self {
+            root,
+            name: name.to_string(),
+            expanded: HashSet::from([Vec::new()]),
+            tree: ListState::default(),
+            blocks: ListState::default(),
+            focus: Focus::Tree,
+            prompt: None,
+            results: None,
+        };
+        browser.tree.select(Some(0));
+        browser.blocks.select(Some(0));
+        browser
+    }
+
+    /// Visible tree rows: depth, path and name of each subsystem, with the
+    /// children of expanded subsystems below them.
+    pub fn tree_rows(&self) -> Vec<(usize, SubsystemPath, String)> {
+        fn walk(
+            system: &System,
+            path: &mut SubsystemPath,
+            expanded: &HashSet<SubsystemPath>,
+            rows: &mut Vec<(usize, SubsystemPath, String)>,
+        ) {
+            if !expanded.contains(path) {
+                return;
+            }
+            for (i, block) in system.blocks.iter().enumerate() {
+                let Some(sub) = &block.subsystem else {
+                    continue;
+                };
+                path.push(i);
+                rows.push((path.len(), path.clone(), block.name.clone()));
+                walk(sub, path, expanded, rows);
+                path.pop();
+            }
+        }
+        let mut rows = vec![(0, Vec::new(), self.name.clone())];
+        walk(self.root, &mut Vec::new(), &self.expanded, &mut rows);
+        rows
+    }
+
+    /// The subsystem selected in the tree.
+    pub fn current_path(&self) -> SubsystemPath {
+        let rows = self.tree_rows();
+        let i = self.tree.selected().unwrap_or(0).min(rows.len() - 1);
+        rows[i].1.clone()
+    }
+
+    /// The system at `path`.
+    pub fn system_at(&self, path: &[usize]) -> Option<&'a System> {
+        let mut system = self.root;
+        for &i in path {
+            system = system.blocks.get(i)?.subsystem.as_deref()?;
+        }
+        Some(system)
+    }
+
+    /// The block selected in the block list or the search results.
+    pub fn selected_block(&self) -> Option<&'a Block> {
+        let i = self.blocks.selected()?;
+        match &self.results {
+            Some(results) => {
+                let (path, index) = results.get(i)?;
+                self.system_at(path)?.blocks.get(*index)
+            }
+            None => self.system_at(&self.current_path())?.blocks.get(i),
+        }
+    }
+
+    /// Blocks whose name or type contains `query`, ignoring case, depth
+    /// first over the whole model.
+    pub fn search(&self, query: &str) -> Vec<(SubsystemPath, usize)> {
+        fn walk(
+            system: &System,
+            query: &str,
+            path: &mut SubsystemPath,
+            out: &mut Vec<(SubsystemPath, usize)>,
+        ) {
+            for (i, block) in system.blocks.iter().enumerate() {
+                if block.name.to_lowercase().contains(query)
+                    || block.block_type.to_lowercase().contains(query)
+                {
+                    out.push((path.clone(), i));
+                }
+                if let Some(sub) = &block.subsystem {
+                    path.push(i);
+                    walk(sub, query, path, out);
+                    path.pop();
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(self.root, &query.to_lowercase(), &mut Vec::new(), &mut out);
+        out
+    }
+
+    /// Select subsystem `path` in the tree, expanding its ancestors.
+    pub fn open(&mut self, path: &[usize]) {
+        for len in 0..path.len() {
+            self.expanded.insert(path[..len].to_vec());
+        }
+        let row = self.tree_rows().iter().position(|(_, p, _)| p == path);
+        self.tree.select(Some(row.unwrap_or(0)));
+        self.blocks.select(Some(0));
+    }
+
+    /// Handle a key press; returns `false` to quit.
+    pub fn handle_key(&mut self, key: KeyCode) -> bool {
+        if let Some(prompt) = &mut self.prompt {
+            match key {
+                KeyCode::Char(c) => prompt.push(c),
+                KeyCode::Backspace => {
+                    prompt.pop();
+                }
+                KeyCode::Enter => {
+                    let query = self.prompt.take().unwrap_or_default();
+                    self.results = Some(self.search(&query));
+                    self.blocks.select(Some(0));
+                    self.focus = Focus::Blocks;
+                }
+                KeyCode::Esc => self.prompt = None,
+                _ => {}
+            }
+            return true;
+        }
+        match key {
+            KeyCode::Char('q') => return false,
+            KeyCode::Char('/') => self.prompt = Some(String::new()),
+            KeyCode::Esc => self.results = None,
+            KeyCode::Tab => {
+                self.focus = match self.focus {
+                    Focus::Tree => Focus::Blocks,
+                    Focus::Blocks => Focus::Tree,
+                }
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.step(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.step(1),
+            KeyCode::Right | KeyCode::Char('l') if self.focus == Focus::Tree => {
+                self.expanded.insert(self.current_path());
+            }
+            KeyCode::Left | KeyCode::Char('h') if self.focus == Focus::Tree => {
+                let path = self.current_path();
+                if !self.expanded.remove(&path) && !path.is_empty() {
+                    // Already collapsed: go to the parent
+                    self.open(&path[..path.len() - 1]);
+                }
+            }
+            KeyCode::Enter => match self.focus {
+                Focus::Tree => {
+                    self.results = None;
+                    self.blocks.select(Some(0));
+                    self.focus = Focus::Blocks;
+                }
+                Focus::Blocks => self.enter_block(),
+            },
+            _ => {}
+        }
+        true
+    }
+
+    fn step(&mut self, delta: isize) {
+        let len = match (self.focus, &self.results) {
+            (Focus::Tree, _) => self.tree_rows().len(),
+            (Focus::Blocks, Some(results)) => results.len(),
+            (Focus::Blocks, None) => self
+                .system_at(&self.current_path())
+                .map_or(0, |s| s.blocks.len()),
+        };
+        let state = match self.focus {
+            Focus::Tree => &mut self.tree,
+            Focus::Blocks => &mut self.blocks,
+        };
+        let i = state.selected().unwrap_or(0) as isize + delta;
+        state.select(Some(i.clamp(0, len.saturating_sub(1) as isize) as usize));
+        if self.focus == Focus::Tree {
+            self.blocks.select(Some(0));
+        }
+    }
+
+    /// Jump to a search result, or into the selected subsystem block.
+    fn enter_block(&mut self) {
+        let Some(i) = self.blocks.selected() else {
+            return;
+        };
+        if let Some(results) = self.results.take() {
+            if let Some((path, index)) = results.get(i).cloned() {
+                self.open(&path);
+                self.blocks.select(Some(index));
+            }
+            return;
+        }
+        let mut path = self.current_path();
+        let is_subsystem = self
+            .system_at(&path)
+            .and_then(|s| s.blocks.get(i))
+            .is_some_and(|b| b.subsystem.is_some());
+        if is_subsystem {
+            path.push(i);
+            self.open(&path);
+        }
+    }
+
+    /// Draw the panes and the search prompt into `frame`.
+    pub fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(frame.area());
+        let [tree_area, blocks_area, params_area] = Layout::horizontal([
+            Constraint::Percentage(30),
+            Constraint::Percentage(30),
+            Constraint::Percentage(40),
+        ])
+        .areas(main);
+        let highlight = |focused: bool| {
+            if focused {
+                Style::new().reversed()
+            } else {
+                Style::new().bold()
+            }
+        };
+
+        let tree: Vec<ListItem> = self
+            .tree_rows()
+            .into_iter()
+            .map(|(depth, path, name)| {
+                let has_children = self
+                    .system_at(&path)
+                    .is_some_and(|s| s.blocks.iter().any(|b| b.subsystem.is_some()));
+                let marker = match (has_children, self.expanded.contains(&path)) {
+                    (false, _) => "  ",
+                    (true, true) => "▾ ",
+                    (true, false) => "▸ ",
+                };
+                ListItem::new(format!("{}{marker}{name}", "  ".repeat(depth)))
+            })
+            .collect();
+        frame.render_stateful_widget(
+            List::new(tree)
+                .block(Panel::bordered().title("Subsystems"))
+                .highlight_style(highlight(self.focus == Focus::Tree)),
+            tree_area,
+            &mut self.tree,
+        );
+
+        let (title, items): (String, Vec<ListItem>) = match &self.results {
+            Some(results) => (
+                format!("Search results ({})", results.len()),
+                results
+                    .iter()
+                    .filter_map(|(path, i)| {
+                        let block = self.system_at(path)?.blocks.get(*i)?;
+                        let mut block_path = BlockPath::root();
+                        let mut system = self.root;
+                        for &j in path {
+                            block_path.push(&system.blocks[j].name);
+                            system = system.blocks[j].subsystem.as_deref()?;
+                        }
+                        Some(ListItem::new(block_path.child(&block.name).to_string()))
+                    })
+                    .collect(),
+            ),
+            None => (
+                "Blocks".to_string(),
+                self.system_at(&self.current_path())
+                    .map(|s| s.blocks.as_slice())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|b| {
+                        let marker = if b.subsystem.is_some() { "▸ " } else { "  " };
+                        ListItem::new(format!("{marker}{} ({})", b.name, b.block_type))
+                    })
+                    .collect(),
+            ),
+        };
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(Panel::bordered().title(title))
+                .highlight_style(highlight(self.focus == Focus::Blocks)),
+            blocks_area,
+            &mut self.blocks,
+        );
+
+        let params = Panel::bordered().title("Parameters");
+        match self.selected_block() {
+            Some(block) => {
+                let mut rows = vec![
+                    Row::new(["Name".to_string(), block.name.clone()]),
+                    Row::new(["BlockType".to_string(), block.block_type.clone()]),
+                ];
+                if let Some(sid) = &block.sid {
+                    rows.push(Row::new(["SID".to_string(), sid.clone()]));
+                }
+                let instance = block.instance_data.iter().flat_map(|d| &d.properties);
+                rows.extend(
+                    block
+                        .properties
+                        .iter()
+                        .chain(instance)
+                        .map(|(k, v)| Row::new([k.clone(), v.clone()])),
+                );
+                let widths = [Constraint::Percentage(40), Constraint::Fill(1)];
+                frame.render_widget(Table::new(rows, widths).block(params), params_area);
+            }
+            None => frame.render_widget(Paragraph::new("").block(params), params_area),
+        }
+
+        let status_line = match &self.prompt {
+            Some(prompt) => Line::from(format!("/{prompt}")),
+            None => Line::from("q quit · Tab switch pane · ←/→ collapse/expand · / search").dim(),
+        };
+        frame.render_widget(Paragraph::new(status_line), status);
+    }
+}
+
+/// Browse `root` on the terminal until `q` is pressed.
+pub fn run(root: &System, name: &str) -> Result<()> {
+    let mut terminal = ratatui::init();
+    let mut browser = Browser::new(root, name);
+    let result = (|| -> Result<()> {
+        loop {
+            terminal.draw(|frame| browser.draw(frame))?;
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && !browser.handle_key(key.code)
+            {
+                return Ok(());
+            }
+        }
+    })();
+    ratatui::restore();
+    result
+}
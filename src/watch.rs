@@ -0,0 +1,269 @@
+//! Watch mode: re-run checks whenever a model or its libraries change.
+//!
+//! [`watch`] observes the directories holding the model and the libraries it
+//! references, and after every save re-parses the model and runs the
+//! configured [`WatchOperation`]s. Saves that leave the model content
+//! untouched (MATLAB rewrites the file and its metadata on every save, even
+//! without edits) are recognized by the CRCs stored in the zip directory and
+//! skipped without parsing, and saves that change the files but parse to the
+//! same model (same [`System::full_hash`]) with unchanged libraries are
+//! skipped after parsing.
+
+use crate::analysis::{
+    MagicNumberOptions, find_clones, find_magic_numbers, rate_transition_issues,
+};
+use crate::model::System;
+use crate::overlay::render_svg;
+use crate::parser::{LibraryResolver, SimulinkParser, ZipSource, split_source_block_reference};
+use anyhow::{Context, Result, anyhow, bail};
+use camino::{Utf8Path, Utf8PathBuf};
+use notify::{RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Quiet period after a file event before the operations run, so the
+/// several writes of one save trigger a single run.
+pub const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Minimum subsystem size reported by [`WatchOperation::Clones`].
+const CLONE_MIN_BLOCKS: usize = 3;
+
+/// An operation re-run on every change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchOperation {
+    /// Rate-transition issues and magic numbers; fails if any are found.
+    Lint,
+    /// Write an SVG of the root system next to the model.
+    Render,
+    /// Count groups of cloned subsystems; informational, never fails.
+    Clones,
+}
+
+impl WatchOperation {
+    pub fn name(self) -> &'static str {
+        match self {
+            WatchOperation::Lint => "lint",
+            WatchOperation::Render => "render",
+            WatchOperation::Clones => "clones",
+        }
+    }
+}
+
+impl FromStr for WatchOperation {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim() {
+            "lint" => Ok(WatchOperation::Lint),
+            "render" => Ok(WatchOperation::Render),
+            "clones" => Ok(WatchOperation::Clones),
+            other => bail!(
+                "Unknown operation '{}' (expected lint, render or clones)",
+                other
+            ),
+        }
+    }
+}
+
+/// Parse a comma-separated operation list such as `lint,render`.
+pub fn parse_operations(list: &str) -> Result<Vec<WatchOperation>> {
+    let ops: Vec<WatchOperation> = list
+        .split(',')
+        .filter(|s| !s.trim().is_empty())
+        .map(str::parse)
+        .collect::<Result<_>>()?;
+    if ops.is_empty() {
+        bail!("No operations given");
+    }
+    Ok(ops)
+}
+
+/// Outcome of one operation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationResult {
+    pub operation: WatchOperation,
+    pub passed: bool,
+    /// Short description, e.g. `2 rate issue(s)`.
+    pub summary: String,
+}
+
+/// Run `ops` on `system`, the root system of the model at `model_path`.
+pub fn run_operations(
+    model_path: &Utf8Path,
+    system: &System,
+    ops: &[WatchOperation],
+) -> Vec<OperationResult> {
+    ops.iter()
+        .map(|&operation| {
+            let (passed, summary) = match operation {
+                WatchOperation::Lint => {
+                    let rates = rate_transition_issues(system).len();
+                    let magic = find_magic_numbers(system, &MagicNumberOptions::default()).len();
+                    match (rates, magic) {
+                        (0, 0) => (true, "ok".to_string()),
+                        _ => (
+                            false,
+                            format!("{rates} rate issue(s), {magic} magic number(s)"),
+                        ),
+                    }
+                }
+                WatchOperation::Render => {
+                    let svg_path = model_path.with_extension("svg");
+                    match std::fs::write(&svg_path, render_svg(system, &[], None)) {
+                        Ok(()) => (true, format!("wrote {svg_path}")),
+                        Err(e) => (false, format!("{svg_path}: {e}")),
+                    }
+                }
+                WatchOperation::Clones => {
+                    let groups = find_clones(system, CLONE_MIN_BLOCKS).len();
+                    (true, format!("{groups} clone group(s)"))
+                }
+            };
+            OperationResult {
+                operation,
+                passed,
+                summary,
+            }
+        })
+        .collect()
+}
+
+/// One line summarizing `results`, e.g. `FAIL  lint: 2 rate issue(s) | render: wrote m.svg`.
+pub fn format_summary(results: &[OperationResult]) -> String {
+    let status = if results.iter().all(|r| r.passed) {
+        "PASS"
+    } else {
+        "FAIL"
+    };
+    let parts: Vec<String> = results
+        .iter()
+        .map(|r| format!("{}: {}", r.operation.name(), r.summary))
+        .collect();
+    format!("{status}  {}", parts.join(" | "))
+}
+
+/// Parse the root system of the `.slx` file at `path`.
+pub fn load_model(path: &Utf8Path) -> Result<System> {
+    let file = std::fs::File::open(path).with_context(|| format!("Open {}", path))?;
+    let source = ZipSource::new(std::io::BufReader::new(file))?;
+    let mut parser = SimulinkParser::new("", source);
+    parser.parse_system_file("simulink/systems/system_root.xml")
+}
+
+/// Library files referenced by blocks of `system`, looked up next to the
+/// model. Built-in libraries and libraries not found on disk are skipped.
+pub fn referenced_libraries(model_path: &Utf8Path, system: &System) -> Vec<Utf8PathBuf> {
+    let mut names = BTreeSet::new();
+    system.walk_blocks(&mut Vec::new(), &mut |_, block| {
+        let source = block.properties.get("SourceBlock");
+        if let Some((lib, _)) = source.and_then(|s| split_source_block_reference(s)) {
+            names.insert(lib);
+        }
+    });
+    let dir = model_dir(model_path);
+    let resolver = LibraryResolver::new([dir]);
+    let found = resolver.locate(names.iter().map(String::as_str)).found;
+    found.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Hash of the names and CRCs of the archive's `simulink/` members.
+/// Unchanged model content yields the same value however often the file is
+/// rewritten; package metadata such as the modification date is ignored.
+pub fn content_fingerprint(path: &Utf8Path) -> Result<u64> {
+    let file = std::fs::File::open(path).with_context(|| format!("Open {}", path))?;
+    let mut zip = zip::ZipArchive::new(std::io::BufReader::new(file))
+        .with_context(|| format!("Read {}", path))?;
+    let mut hasher = DefaultHasher::new();
+    for i in 0..zip.len() {
+        let entry = zip.by_index_raw(i)?;
+        if !entry.name().starts_with("simulink/") {
+            continue;
+        }
+        entry.name().hash(&mut hasher);
+        entry.crc32().hash(&mut hasher);
+    }
+    Ok(hasher.finish())
+}
+
+fn model_dir(model_path: &Utf8Path) -> Utf8PathBuf {
+    match model_path.parent() {
+        Some(dir) if !dir.as_str().is_empty() => dir.to_path_buf(),
+        _ => Utf8PathBuf::from("."),
+    }
+}
+
+/// Watch `model_path` and its libraries until the watcher fails, calling
+/// `report` with a [`format_summary`] line (or an error message) after the
+/// initial run and after every change to their content.
+pub fn watch(
+    model_path: &Utf8Path,
+    ops: &[WatchOperation],
+    mut report: impl FnMut(&str),
+) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    let mut watched_dirs: BTreeSet<Utf8PathBuf> = BTreeSet::new();
+    let mut files: Vec<Utf8PathBuf> = vec![model_path.to_path_buf()];
+    let mut fingerprints: Vec<Option<u64>> = Vec::new();
+    // Model hash and library fingerprints of the last run.
+    let mut last_run = None;
+
+    loop {
+        let current: Vec<Option<u64>> = files.iter().map(|f| content_fingerprint(f).ok()).collect();
+        if current != fingerprints {
+            let result = load_model(model_path).map(|system| {
+                files.truncate(1);
+                files.extend(referenced_libraries(model_path, &system));
+                let libraries: Vec<Option<u64>> = files[1..]
+                    .iter()
+                    .map(|f| content_fingerprint(f).ok())
+                    .collect();
+                let run = Some((system.full_hash(), libraries));
+                (last_run != run).then(|| {
+                    last_run = run;
+                    run_operations(model_path, &system, ops)
+                })
+            });
+            // The model keeps the fingerprint it was loaded with, so a save
+            // during the run triggers another one.
+            fingerprints = current[..1].to_vec();
+            fingerprints.extend(files[1..].iter().map(|f| content_fingerprint(f).ok()));
+            // Editors save by writing a new file and renaming it over the
+            // old one, which drops watches on the file itself.
+            for file in &files {
+                let dir = model_dir(file);
+                if watched_dirs.insert(dir.clone()) {
+                    watcher
+                        .watch(dir.as_std_path(), RecursiveMode::NonRecursive)
+                        .with_context(|| format!("Watch {}", dir))?;
+                }
+            }
+            match result {
+                Ok(Some(results)) => report(&format_summary(&results)),
+                Ok(None) => {}
+                Err(e) => {
+                    last_run = None;
+                    report(&format!("ERROR  {e:#}"))
+                }
+            }
+        }
+
+        // Block until a relevant event, then wait for the save to settle.
+        loop {
+            let event = rx.recv().map_err(|_| anyhow!("File watcher stopped"))??;
+            if event.paths.iter().any(|p| is_watched(&files, p)) {
+                break;
+            }
+        }
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+    }
+}
+
+fn is_watched(files: &[Utf8PathBuf], path: &std::path::Path) -> bool {
+    files
+        .iter()
+        .any(|f| path.file_name() == f.as_std_path().file_name())
+}
@@ -0,0 +1,102 @@
+//! Model workspace variables loaded from MAT files embedded in an SLX archive.
+//!
+//! Used to resolve block and mask parameters that reference workspace
+//! variables (e.g. a Gain of `K` or `params.gain`) to numeric values.
+
+use crate::mat_file::{self, MatValue};
+use crate::model::{SlxArchive, SlxContent};
+use crate::parser::ParseLimits;
+use anyhow::{Context, Result};
+use indexmap::IndexMap;
+
+/// Variables of a model workspace, keyed by name.
+#[derive(Debug, Clone, Default)]
+pub struct ModelWorkspace {
+    pub variables: IndexMap<String, MatValue>,
+}
+
+impl ModelWorkspace {
+    /// Load all variables from a MAT file's bytes.
+    pub fn from_mat_bytes(data: &[u8]) -> Result<Self> {
+        Ok(Self {
+            variables: mat_file::read_mat_with_limits(data, &ParseLimits::default())?,
+        })
+    }
+
+    /// Collect variables from every MAT file stored in the archive.
+    ///
+    /// Entries are visited in archive order; later files override variables
+    /// of the same name from earlier ones. Entries without a MAT header are
+    /// ignored.
+    pub fn from_archive(archive: &SlxArchive) -> Result<Self> {
+        Self::from_archive_with_limits(archive, &ParseLimits::default())
+    }
+
+    /// [`Self::from_archive`], reading each MAT file within `limits`.
+    pub fn from_archive_with_limits(archive: &SlxArchive, limits: &ParseLimits) -> Result<Self> {
+        let mut ws = Self::default();
+        for entry in &archive.entries {
+            let SlxContent::Raw(data) = &entry.content else {
+                continue;
+            };
+            if !mat_file::is_mat_file(data) {
+                continue;
+            }
+            let vars = mat_file::read_mat_with_limits(data, limits)
+                .with_context(|| format!("Failed to read MAT file {}", entry.path))?;
+            ws.variables.extend(vars);
+        }
+        Ok(ws)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    /// Look up a variable, following dotted struct field access
+    /// (`params.gain.k`).
+    pub fn get(&self, path: &str) -> Option<&MatValue> {
+        let mut parts = path.trim().split('.');
+        let mut value = self.variables.get(parts.next()?.trim())?;
+        for field in parts {
+            value = value.field(field.trim())?;
+        }
+        Some(value)
+    }
+
+    /// Resolve a parameter expression to a number.
+    ///
+    /// Supports numeric literals, (optionally negated) variable references
+    /// with struct field access, and 1-based indexing into vectors such as
+    /// `table(3)`.
+    pub fn resolve_numeric(&self, expr: &str) -> Option<f64> {
+        let mut expr = expr.trim();
+        let mut sign = 1.0;
+        // Peel off negations and enclosing parentheses in a loop, so that
+        // long runs of them cannot overflow the stack.
+        loop {
+            if let Ok(v) = expr.parse::<f64>() {
+                return Some(sign * v);
+            }
+            if let Some(rest) = expr.strip_prefix('-') {
+                sign = -sign;
+                expr = rest.trim();
+            } else if let Some(inner) = expr.strip_prefix('(').and_then(|e| e.strip_suffix(')')) {
+                expr = inner.trim();
+            } else {
+                break;
+            }
+        }
+        let value = match expr.strip_suffix(')').and_then(|e| e.split_once('(')) {
+            Some((name, idx)) => {
+                let idx: usize = idx.trim().parse().ok()?;
+                match self.get(name)? {
+                    MatValue::Numeric { real, .. } => *real.get(idx.checked_sub(1)?)?,
+                    _ => return None,
+                }
+            }
+            None => self.get(expr)?.as_scalar()?,
+        };
+        Some(sign * value)
+    }
+}
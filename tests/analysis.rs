@@ -0,0 +1,430 @@
+mod common;
+
+use common::parse;
+use rustylink::analysis::{connections, effective_connections};
+use rustylink::model::CommentMode;
+
+/// In(1) -> Gain(2) -> Sat(3) -> Out(4), with a branch from Gain to Scope(5).
+fn chain(gain_commented: &str, sat_commented: &str) -> rustylink::model::System {
+    parse(&format!(
+        r#"<System>
+  <Block BlockType="Inport" Name="In" SID="1"/>
+  <Block BlockType="Gain" Name="Gain" SID="2"><P Name="Commented">{gain_commented}</P></Block>
+  <Block BlockType="Saturate" Name="Sat" SID="3"><P Name="Commented">{sat_commented}</P></Block>
+  <Block BlockType="Outport" Name="Out" SID="4"/>
+  <Block BlockType="Scope" Name="Scope" SID="5"/>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+  <Line><P Name="Src">2#out:1</P>
+    <Branch><P Name="Dst">3#in:1</P></Branch>
+    <Branch><P Name="Dst">5#in:1</P></Branch>
+  </Line>
+  <Line><P Name="Src">3#out:1</P><P Name="Dst">4#in:1</P></Line>
+</System>"#
+    ))
+}
+
+fn pairs(conns: &[rustylink::analysis::Connection]) -> Vec<(String, String)> {
+    conns
+        .iter()
+        .map(|c| (c.src.sid.clone(), c.dst.sid.clone()))
+        .collect()
+}
+
+fn p(a: &str, b: &str) -> (String, String) {
+    (a.to_string(), b.to_string())
+}
+
+#[test]
+fn comment_modes_are_parsed() {
+    let sys = chain("through", "on");
+    assert_eq!(sys.blocks[1].comment_mode(), CommentMode::Through);
+    assert!(sys.blocks[1].commented);
+    assert_eq!(sys.blocks[2].comment_mode(), CommentMode::Out);
+    assert!(!sys.blocks[2].comment_through);
+    assert_eq!(sys.blocks[0].comment_mode(), CommentMode::Off);
+}
+
+#[test]
+fn connections_include_branches() {
+    let sys = chain("off", "off");
+    assert_eq!(
+        pairs(&connections(&sys)),
+        [p("1", "2"), p("2", "3"), p("2", "5"), p("3", "4")]
+    );
+    assert_eq!(connections(&sys), effective_connections(&sys));
+}
+
+#[test]
+fn commented_out_blocks_cut_signals() {
+    let sys = chain("on", "off");
+    assert_eq!(pairs(&effective_connections(&sys)), [p("3", "4")]);
+}
+
+#[test]
+fn commented_through_blocks_pass_signals() {
+    let sys = chain("through", "off");
+    assert_eq!(
+        pairs(&effective_connections(&sys)),
+        [p("1", "3"), p("1", "5"), p("3", "4")]
+    );
+}
+
+#[test]
+fn chained_through_blocks_resolve_to_the_first_source() {
+    let sys = chain("through", "through");
+    let conns = effective_connections(&sys);
+    assert_eq!(pairs(&conns), [p("1", "5"), p("1", "4")]);
+    assert_eq!(conns[1].src.port_index, 1);
+    assert_eq!(conns[1].src.port_type, "out");
+}
+
+#[test]
+fn through_block_fed_by_commented_out_block_drops_signal() {
+    let sys = chain("on", "through");
+    assert!(effective_connections(&sys).is_empty());
+}
+
+#[test]
+fn through_block_maps_matching_ports() {
+    let sys = parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="A" SID="1"/>
+  <Block BlockType="Inport" Name="B" SID="2"/>
+  <Block BlockType="Mux" Name="M" SID="3"><P Name="Commented">through</P></Block>
+  <Block BlockType="Outport" Name="X" SID="4"/>
+  <Block BlockType="Outport" Name="Y" SID="5"/>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">3#in:1</P></Line>
+  <Line><P Name="Src">2#out:1</P><P Name="Dst">3#in:2</P></Line>
+  <Line><P Name="Src">3#out:1</P><P Name="Dst">4#in:1</P></Line>
+  <Line><P Name="Src">3#out:2</P><P Name="Dst">5#in:1</P></Line>
+</System>"#,
+    );
+    assert_eq!(
+        pairs(&effective_connections(&sys)),
+        [p("1", "4"), p("2", "5")]
+    );
+}
+
+#[test]
+fn numeric_literals_skip_identifiers_and_strings() {
+    use rustylink::analysis::numeric_literals;
+    assert_eq!(numeric_literals("Kp"), Vec::<String>::new());
+    assert_eq!(numeric_literals("k2*x"), Vec::<String>::new());
+    assert_eq!(numeric_literals("2.5*Kp"), ["2.5"]);
+    assert_eq!(numeric_literals("[1 -2 .5e-3]"), ["1", "-2", ".5e-3"]);
+    assert_eq!(numeric_literals("a-3"), ["3"]);
+    assert_eq!(numeric_literals("-1"), ["-1"]);
+    assert_eq!(numeric_literals("single(3)"), ["3"]);
+    assert_eq!(numeric_literals("A' * 4"), ["4"]);
+    assert_eq!(numeric_literals("foo('v2', 7)"), ["7"]);
+}
+
+#[test]
+fn magic_numbers_are_reported_per_parameter() {
+    use rustylink::analysis::{MagicNumberOptions, find_magic_numbers};
+    let mut sys = parse(
+        r#"<System>
+  <Block BlockType="Gain" Name="K" SID="1"><P Name="Gain">Kp</P></Block>
+  <Block BlockType="Gain" Name="K2" SID="2"><P Name="Gain">2*Kp</P></Block>
+  <Block BlockType="Constant" Name="C" SID="3"><P Name="Value">1</P></Block>
+  <Block BlockType="Saturate" Name="Sat" SID="4">
+    <P Name="UpperLimit">10</P>
+    <P Name="LowerLimit">-10</P>
+  </Block>
+  <Block BlockType="Gain" Name="Off" SID="5">
+    <P Name="Gain">42</P>
+    <P Name="Commented">on</P>
+  </Block>
+  <Block BlockType="SubSystem" Name="Sub" SID="6"/>
+</System>"#,
+    );
+    let inner = parse(
+        r#"<System>
+  <Block BlockType="Reference" Name="Cmp" SID="7">
+    <P Name="SourceBlock">simulink/Logic and Bit&#xA;Operations/Compare&#xA;To Constant</P>
+    <InstanceData>
+      <P Name="relop">&lt;=</P>
+      <P Name="const">3.0</P>
+    </InstanceData>
+  </Block>
+</System>"#,
+    );
+    sys.blocks[5].subsystem = Some(Box::new(inner));
+
+    let found = find_magic_numbers(&sys, &MagicNumberOptions::default());
+    let summary: Vec<(&str, &str, Vec<&str>)> = found
+        .iter()
+        .map(|m| {
+            (
+                m.path.as_str(),
+                m.parameter.as_str(),
+                m.literals.iter().map(String::as_str).collect(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("K2", "Gain", vec!["2"]),
+            ("Sat", "UpperLimit", vec!["10"]),
+            ("Sat", "LowerLimit", vec!["-10"]),
+            ("Sub/Cmp", "const", vec!["3.0"]),
+        ]
+    );
+    assert_eq!(found[0].expression, "2*Kp");
+
+    let options = MagicNumberOptions {
+        allowed: vec![1.0, 2.0, 10.0, -10.0, 3.0],
+    };
+    assert!(find_magic_numbers(&sys, &options).is_empty());
+}
+
+/// In -> Gain -> Saturate -> Out, plus `extra` blocks/lines.
+fn filter_chain(base_sid: u32, gain: &str, extra: &str) -> rustylink::model::System {
+    let s = |n: u32| base_sid + n;
+    parse(&format!(
+        r#"<System>
+  <Block BlockType="Inport" Name="u{b}" SID="{s1}"><P Name="Position">[{b}, 0, 30, 20]</P></Block>
+  <Block BlockType="Gain" Name="G{b}" SID="{s2}"><P Name="Gain">{gain}</P></Block>
+  <Block BlockType="Saturate" Name="S{b}" SID="{s3}"/>
+  <Block BlockType="Outport" Name="y{b}" SID="{s4}"/>
+  <Line><P Name="Src">{s1}#out:1</P><P Name="Dst">{s2}#in:1</P></Line>
+  <Line><P Name="Src">{s2}#out:1</P><P Name="Dst">{s3}#in:1</P></Line>
+  <Line><P Name="Src">{s3}#out:1</P><P Name="Dst">{s4}#in:1</P></Line>
+  {extra}
+</System>"#,
+        b = base_sid,
+        s1 = s(1),
+        s2 = s(2),
+        s3 = s(3),
+        s4 = s(4),
+    ))
+}
+
+fn clone_model() -> rustylink::model::System {
+    let mut root = parse(
+        r#"<System>
+  <Block BlockType="SubSystem" Name="Left" SID="1"/>
+  <Block BlockType="SubSystem" Name="Right" SID="2"/>
+  <Block BlockType="SubSystem" Name="Other" SID="3"/>
+  <Block BlockType="SubSystem" Name="Tiny" SID="4"/>
+  <Block BlockType="SubSystem" Name="Outer" SID="5"/>
+</System>"#,
+    );
+    root.blocks[0].subsystem = Some(Box::new(filter_chain(10, "Kp", "")));
+    root.blocks[1].subsystem = Some(Box::new(filter_chain(20, "2.5", "")));
+    root.blocks[2].subsystem = Some(Box::new(parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="a" SID="31"/>
+  <Block BlockType="Inport" Name="b" SID="32"><P Name="Port">2</P></Block>
+  <Block BlockType="Sum" Name="Add" SID="33"/>
+  <Block BlockType="Outport" Name="y" SID="34"/>
+  <Line><P Name="Src">31#out:1</P><P Name="Dst">33#in:1</P></Line>
+  <Line><P Name="Src">32#out:1</P><P Name="Dst">33#in:2</P></Line>
+  <Line><P Name="Src">33#out:1</P><P Name="Dst">34#in:1</P></Line>
+</System>"#,
+    )));
+    root.blocks[3].subsystem = Some(Box::new(parse(
+        r#"<System><Block BlockType="Inport" Name="a" SID="41"/></System>"#,
+    )));
+    // A third copy nested one level deeper.
+    let mut outer = parse(
+        r#"<System>
+  <Block BlockType="SubSystem" Name="Inner" SID="51"/>
+</System>"#,
+    );
+    outer.blocks[0].subsystem = Some(Box::new(filter_chain(60, "K", "")));
+    root.blocks[4].subsystem = Some(Box::new(outer));
+    root
+}
+
+#[test]
+fn exact_clones_ignore_names_positions_and_parameters() {
+    use rustylink::analysis::find_clones;
+    let groups = find_clones(&clone_model(), 3);
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].paths, ["Left", "Right", "Outer/Inner"]);
+    assert_eq!(groups[0].similarity, 1.0);
+    assert_eq!(groups[0].block_count, 4);
+
+    // `Tiny` and `Outer` are below the size limit either way.
+    assert_eq!(find_clones(&clone_model(), 5), []);
+}
+
+#[test]
+fn rewired_subsystems_are_not_exact_clones() {
+    use rustylink::analysis::find_clones;
+    let mut root = clone_model();
+    // Same blocks as `Left`, but Saturate before Gain.
+    let swapped = parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="u" SID="71"/>
+  <Block BlockType="Gain" Name="G" SID="72"/>
+  <Block BlockType="Saturate" Name="S" SID="73"/>
+  <Block BlockType="Outport" Name="y" SID="74"/>
+  <Line><P Name="Src">71#out:1</P><P Name="Dst">73#in:1</P></Line>
+  <Line><P Name="Src">73#out:1</P><P Name="Dst">72#in:1</P></Line>
+  <Line><P Name="Src">72#out:1</P><P Name="Dst">74#in:1</P></Line>
+</System>"#,
+    );
+    root.blocks[1].subsystem = Some(Box::new(swapped));
+    let groups = find_clones(&root, 3);
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].paths, ["Left", "Outer/Inner"]);
+}
+
+/// In -> `types`... -> Out as one chain, plus `extra` XML.
+fn long_chain(base_sid: u32, types: &[&str], extra: &str) -> rustylink::model::System {
+    let mut xml = String::from("<System>\n");
+    let all: Vec<&str> = ["Inport"]
+        .iter()
+        .chain(types)
+        .chain(&["Outport"])
+        .copied()
+        .collect();
+    for (i, t) in all.iter().enumerate() {
+        let sid = base_sid + i as u32;
+        xml += &format!(r#"<Block BlockType="{t}" Name="B{sid}" SID="{sid}"/>"#);
+        if i > 0 {
+            xml += &format!(
+                r#"<Line><P Name="Src">{}#out:1</P><P Name="Dst">{sid}#in:1</P></Line>"#,
+                sid - 1
+            );
+        }
+    }
+    xml += extra;
+    xml += "</System>";
+    parse(&xml)
+}
+
+#[test]
+fn near_clones_are_grouped_with_a_lower_score() {
+    use rustylink::analysis::{CLONE_SIMILARITY_THRESHOLD, find_clones};
+    let types = ["Gain", "Saturate", "Abs", "Sqrt", "Gain", "UnitDelay"];
+    let mut root = parse(
+        r#"<System>
+  <Block BlockType="SubSystem" Name="A" SID="1"/>
+  <Block BlockType="SubSystem" Name="B" SID="2"/>
+  <Block BlockType="SubSystem" Name="C" SID="3"/>
+</System>"#,
+    );
+    root.blocks[0].subsystem = Some(Box::new(long_chain(10, &types, "")));
+    // `B` additionally probes the Abs output with a Scope.
+    let probe = r#"<Block BlockType="Scope" Name="Probe" SID="99"/>
+  <Line><P Name="Src">23#out:1</P><P Name="Dst">99#in:1</P></Line>"#;
+    root.blocks[1].subsystem = Some(Box::new(long_chain(20, &types, probe)));
+    // `C` has the same blocks in a different order.
+    let shuffled = ["UnitDelay", "Gain", "Sqrt", "Abs", "Saturate", "Gain"];
+    root.blocks[2].subsystem = Some(Box::new(long_chain(30, &shuffled, "")));
+
+    let groups = find_clones(&root, 3);
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].paths, ["A", "B"]);
+    assert!(groups[0].similarity < 1.0);
+    assert!(groups[0].similarity >= CLONE_SIMILARITY_THRESHOLD);
+    assert_eq!(groups[0].block_count, 9);
+}
+
+fn multirate_model() -> rustylink::model::System {
+    let mut root = parse(
+        r#"<System>
+  <Block BlockType="UnitDelay" Name="Fast" SID="1"><P Name="SampleTime">0.01</P></Block>
+  <Block BlockType="UnitDelay" Name="Slow" SID="2"><P Name="SampleTime">0.1</P></Block>
+  <Block BlockType="RateTransition" Name="RT" SID="3"/>
+  <Block BlockType="UnitDelay" Name="Held" SID="4"><P Name="SampleTime">0.1</P></Block>
+  <Block BlockType="RateTransition" Name="RTOdd" SID="5">
+    <P Name="Integrity">off</P>
+    <P Name="OutPortSampleTime">0.015</P>
+  </Block>
+  <Block BlockType="RateTransition" Name="RTLoose" SID="6">
+    <P Name="Deterministic">off</P>
+    <P Name="OutPortSampleTime">[0.05, 0]</P>
+  </Block>
+  <Block BlockType="RateTransition" Name="RTSame" SID="7">
+    <P Name="OutPortSampleTime">0.01</P>
+  </Block>
+  <Block BlockType="Sum" Name="Sum" SID="8"/>
+  <Block BlockType="SubSystem" Name="Sub" SID="9"/>
+  <Block BlockType="Gain" Name="Muted" SID="10"><P Name="Commented">on</P><P Name="SampleTime">0.5</P></Block>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P>
+    <Branch><P Name="Dst">3#in:1</P></Branch>
+    <Branch><P Name="Dst">5#in:1</P></Branch>
+    <Branch><P Name="Dst">6#in:1</P></Branch>
+    <Branch><P Name="Dst">7#in:1</P></Branch>
+    <Branch><P Name="Dst">8#in:1</P></Branch>
+    <Branch><P Name="Dst">9#in:1</P></Branch>
+    <Branch><P Name="Dst">10#in:1</P></Branch>
+  </Line>
+  <Line><P Name="Src">3#out:1</P><P Name="Dst">4#in:1</P></Line>
+  <Line><P Name="Src">4#out:1</P><P Name="Dst">8#in:2</P></Line>
+</System>"#,
+    );
+    let inner = parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="In1" SID="1"/>
+  <Block BlockType="UnitDelay" Name="D" SID="2"><P Name="SampleTime">0.02</P></Block>
+  <Block BlockType="Outport" Name="Out1" SID="3"/>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+  <Line><P Name="Src">2#out:1</P><P Name="Dst">3#in:1</P></Line>
+</System>"#,
+    );
+    root.blocks[8].subsystem = Some(Box::new(inner));
+    root
+}
+
+#[test]
+fn sample_times_parse() {
+    use rustylink::sample_time::SampleTime;
+    assert_eq!(SampleTime::parse("-1"), None);
+    assert_eq!(SampleTime::parse("Ts"), None);
+    assert_eq!(SampleTime::parse("0"), Some(SampleTime::Continuous));
+    assert_eq!(SampleTime::parse("inf"), Some(SampleTime::Constant));
+    assert_eq!(
+        SampleTime::parse("[0.1 0.05]"),
+        Some(SampleTime::Discrete {
+            period: 0.1,
+            offset: 0.05
+        })
+    );
+    assert_eq!(SampleTime::parse("0.1").unwrap().to_string(), "0.1");
+}
+
+#[test]
+fn sample_times_propagate_through_inherited_blocks() {
+    use rustylink::sample_time::{SampleTime, propagate};
+    let times = propagate(&multirate_model());
+    let period = |path: &str| times.block(path).and_then(SampleTime::period);
+    // Inherited blocks run at their fastest input.
+    assert_eq!(period("Sum"), Some(0.01));
+    // An inherited Rate Transition output takes the rate it drives.
+    assert_eq!(period("RT"), Some(0.1));
+    assert_eq!(period("Sub/In1"), Some(0.01));
+    assert_eq!(
+        times.output("Sub", 1).and_then(SampleTime::period),
+        Some(0.02)
+    );
+}
+
+#[test]
+fn rate_transition_issues_are_reported() {
+    use rustylink::analysis::{RateIssueKind, rate_transition_issues};
+    let issues = rate_transition_issues(&multirate_model());
+    let found: Vec<(&str, RateIssueKind)> =
+        issues.iter().map(|i| (i.path.as_str(), i.kind)).collect();
+    assert_eq!(
+        found,
+        [
+            ("Slow", RateIssueKind::MissingRateTransition),
+            ("RTOdd", RateIssueKind::IntegrityDisabled),
+            ("RTOdd", RateIssueKind::NonIntegerRateRatio),
+            ("RTLoose", RateIssueKind::NondeterministicTransfer),
+            ("RTSame", RateIssueKind::RedundantRateTransition),
+            ("Sum", RateIssueKind::MixedRateInputs),
+            ("Sub/D", RateIssueKind::MissingRateTransition),
+        ]
+    );
+    assert_eq!(
+        issues[0].message,
+        "input 1 from Fast runs at 0.01 but the block runs at 0.1"
+    );
+}
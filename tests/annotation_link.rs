@@ -0,0 +1,57 @@
+use rustylink::annotation_link::{LinkTarget, parse_link_target};
+use rustylink::model::Annotation;
+
+fn block(path: &str) -> LinkTarget {
+    LinkTarget::Block(path.parse().unwrap())
+}
+
+#[test]
+fn open_system_paths_are_recognized() {
+    assert_eq!(
+        parse_link_target("matlab:open_system('model/Controller/PID')"),
+        block("Controller/PID")
+    );
+    assert_eq!(
+        parse_link_target("open_system(\"model/a//b\");"),
+        block("a//b")
+    );
+    assert_eq!(
+        parse_link_target("matlab: hilite_system('model/It''s')"),
+        block("It's")
+    );
+    assert_eq!(
+        parse_link_target("disp('hi'); open_system([bdroot '/Plant'])"),
+        block("Plant")
+    );
+    assert_eq!(parse_link_target("open_system('model')"), block(""));
+}
+
+#[test]
+fn other_links_are_left_to_the_host() {
+    for link in [
+        "https://example.com",
+        "matlab:doc gain",
+        "open_system(gcs)",
+        "myapp://run/3",
+    ] {
+        assert_eq!(parse_link_target(link), LinkTarget::Other(link.to_string()));
+    }
+}
+
+#[test]
+fn click_callback_uses_click_fcn_or_text() {
+    let mut annotation = Annotation {
+        text: Some("open_system('m/Sub')".to_string()),
+        ..Default::default()
+    };
+    assert_eq!(annotation.click_callback(), None);
+    annotation
+        .properties
+        .insert("ClickFcn".to_string(), "open_system('m/Other')".to_string());
+    assert_eq!(annotation.click_callback(), Some("open_system('m/Other')"));
+    annotation.properties.insert(
+        "UseDisplayTextAsClickCallback".to_string(),
+        "on".to_string(),
+    );
+    assert_eq!(annotation.click_callback(), Some("open_system('m/Sub')"));
+}
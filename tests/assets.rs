@@ -0,0 +1,87 @@
+use rustylink::model::{SlxArchive, asset_mime_type};
+use rustylink::parser::{ParseLimits, ZipSource};
+use std::io::{Cursor, Write};
+
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+fn build_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        for (name, data) in files {
+            zip.start_file(*name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    buf.into_inner()
+}
+
+#[test]
+fn mime_type_from_extension_and_magic() {
+    assert_eq!(asset_mime_type("a/b.PNG", &[]), Some("image/png"));
+    assert_eq!(asset_mime_type("x.jpeg", &[]), Some("image/jpeg"));
+    assert_eq!(asset_mime_type("icon.svg", &[]), Some("image/svg+xml"));
+    assert_eq!(asset_mime_type("blob", PNG_MAGIC), Some("image/png"));
+    assert_eq!(
+        asset_mime_type("blob", &[0xff, 0xd8, 0xff, 0xe0]),
+        Some("image/jpeg")
+    );
+    assert_eq!(asset_mime_type("simulink/blockdiagram.xml", b"<x/>"), None);
+}
+
+#[test]
+fn zip_source_extracts_only_images() {
+    let bytes = build_zip(&[
+        ("metadata/thumbnail.png", PNG_MAGIC),
+        ("simulink/maskimages/img_1", PNG_MAGIC),
+        ("simulink/blockdiagram.xml", b"<ModelInformation/>"),
+    ]);
+    let mut src = ZipSource::new(Cursor::new(bytes)).unwrap();
+    let assets = src.extract_assets().unwrap();
+    assert_eq!(assets.len(), 2);
+    let thumb = assets.thumbnail().expect("thumbnail");
+    assert_eq!(thumb.mime_type, "image/png");
+    assert_eq!(thumb.file_name(), "thumbnail.png");
+    assert!(assets.get("/simulink/maskimages/img_1").is_some());
+    assert!(assets.get("simulink/blockdiagram.xml").is_none());
+}
+
+#[test]
+fn non_image_members_are_not_read_for_assets() {
+    // Over the per-file limit, so reading it in full would fail.
+    let data = vec![b' '; 64 * 1024];
+    let bytes = build_zip(&[
+        ("simulink/blockdiagram.xml", &data),
+        ("simulink/maskimages/img_1", PNG_MAGIC),
+    ]);
+    let limits = ParseLimits {
+        max_file_size: 1024,
+        ..Default::default()
+    };
+    let mut src = ZipSource::with_limits(Cursor::new(bytes), limits).unwrap();
+    let assets = src.extract_assets().unwrap();
+    let paths: Vec<&str> = assets.iter().map(|a| a.path.as_str()).collect();
+    assert_eq!(paths, ["simulink/maskimages/img_1"]);
+    assert_eq!(assets.iter().next().unwrap().data, PNG_MAGIC);
+}
+
+#[test]
+fn slx_archive_exposes_assets() {
+    let bytes = build_zip(&[
+        ("metadata/thumbnail.png", PNG_MAGIC),
+        ("simulink/annotation.gif", b"GIF89a...."),
+    ]);
+    let archive = SlxArchive::from_reader(Cursor::new(bytes)).unwrap();
+    let assets = archive.assets();
+    let paths: Vec<&str> = assets.iter().map(|a| a.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        vec!["metadata/thumbnail.png", "simulink/annotation.gif"]
+    );
+    assert_eq!(
+        assets.get("simulink/annotation.gif").unwrap().mime_type,
+        "image/gif"
+    );
+}
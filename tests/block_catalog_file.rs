@@ -0,0 +1,78 @@
+#![cfg(feature = "egui")]
+
+use rustylink::editor::block_catalog::{
+    CatalogFile, builtin_catalog, get_block_catalog, get_block_catalog_by_category, load_catalog,
+};
+
+const CATALOG: &str = r#"
+[[blocks]]
+block_type = "SubSystem"
+display_name = "PI Controller"
+category = "ACME Control"
+default_inputs = 1
+default_outputs = 1
+description = "PI controller with anti-windup"
+icon = "pi.svg"
+
+[blocks.parameters]
+SourceBlock = "acme_lib/PI Controller"
+Kp = "1"
+
+[[blocks]]
+block_type = "Gain"
+display_name = "Gain"
+category = "Math Operations"
+default_inputs = 1
+default_outputs = 1
+description = "Gain with the ACME default"
+
+[blocks.parameters]
+Gain = "0.5"
+"#;
+
+#[test]
+fn loaded_entries_extend_and_override_the_catalog() {
+    let builtin = get_block_catalog().len();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("acme.toml");
+    std::fs::write(&path, CATALOG).unwrap();
+    assert_eq!(load_catalog(&path).unwrap(), 2);
+
+    let catalog = get_block_catalog();
+    assert_eq!(catalog.len(), builtin + 1);
+    let pi = catalog
+        .iter()
+        .find(|e| e.display_name == "PI Controller")
+        .unwrap();
+    assert_eq!(pi.icon.as_deref(), Some("pi.svg"));
+    assert_eq!(pi.parameters["SourceBlock"], "acme_lib/PI Controller");
+    let gains: Vec<_> = catalog
+        .iter()
+        .filter(|e| e.block_type == "Gain" && e.display_name == "Gain")
+        .collect();
+    assert_eq!(gains.len(), 1);
+    assert_eq!(gains[0].parameters["Gain"], "0.5");
+
+    let acme = get_block_catalog_by_category()
+        .iter()
+        .find(|c| c.name == "ACME Control")
+        .unwrap();
+    assert_eq!(acme.entries.len(), 1);
+
+    std::fs::write(dir.path().join("bad.yaml"), "").unwrap();
+    assert!(load_catalog(dir.path().join("bad.yaml")).is_err());
+}
+
+#[test]
+fn exported_catalog_loads_back() {
+    let file = CatalogFile {
+        blocks: builtin_catalog(),
+    };
+    let text = serde_json::to_string(&file).unwrap();
+    let back: CatalogFile = serde_json::from_str(&text).unwrap();
+    assert_eq!(back, file);
+
+    let text = toml::to_string(&file).unwrap();
+    let back: CatalogFile = toml::from_str(&text).unwrap();
+    assert_eq!(back, file);
+}
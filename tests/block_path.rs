@@ -0,0 +1,84 @@
+use camino::Utf8Path;
+use rustylink::block_path::{BlockPath, escape_name};
+use rustylink::model::System;
+use rustylink::overlay::block_path;
+
+fn path(names: &[&str]) -> BlockPath {
+    BlockPath::from(names.iter().map(|n| n.to_string()).collect::<Vec<_>>())
+}
+
+#[test]
+fn display_doubles_slashes_in_names() {
+    assert_eq!(BlockPath::root().to_string(), "");
+    assert_eq!(path(&["Ctrl", "Gain"]).to_string(), "Ctrl/Gain");
+    assert_eq!(path(&["Ctrl", "a/b"]).to_string(), "Ctrl/a//b");
+    assert_eq!(escape_name("x/y/z"), "x//y//z");
+    assert_eq!(block_path(&["In/Out".to_string()], "K"), "In//Out/K");
+}
+
+#[test]
+fn parses_escaped_and_absolute_paths() {
+    let parse = |s: &str| s.parse::<BlockPath>().unwrap();
+    assert_eq!(parse(""), BlockPath::root());
+    assert_eq!(parse("/"), BlockPath::root());
+    assert_eq!(parse("Ctrl/Gain"), path(&["Ctrl", "Gain"]));
+    assert_eq!(parse("/Ctrl/Gain"), path(&["Ctrl", "Gain"]));
+    assert_eq!(parse("Ctrl/a//b"), path(&["Ctrl", "a/b"]));
+    // An even run of leading slashes escapes the start of the first name.
+    assert_eq!(parse("//x"), path(&["/x"]));
+    assert_eq!(parse("///x"), path(&["/x"]));
+    assert!("Ctrl/".parse::<BlockPath>().is_err());
+    assert!("/Ctrl/".parse::<BlockPath>().is_err());
+}
+
+#[test]
+fn round_trips_through_text() {
+    for names in [
+        &["a"][..],
+        &["a/b", "c"],
+        &["/lead", "trail/x", "end/"],
+        &["Ctrl", "with space", "ü/ö"],
+    ] {
+        let p = path(names);
+        assert_eq!(p.to_string().parse::<BlockPath>().unwrap(), p, "{}", p);
+    }
+}
+
+#[test]
+fn navigation_helpers() {
+    let p = path(&["Ctrl", "Inner", "K"]);
+    assert_eq!(p.name(), Some("K"));
+    assert_eq!(p.parent(), Some(path(&["Ctrl", "Inner"])));
+    assert_eq!(BlockPath::root().parent(), None);
+    assert_eq!(path(&["Ctrl"]).child("Inner").child("K"), p);
+    assert!(p.starts_with(&path(&["Ctrl"])));
+    assert!(!path(&["Ctrl2"]).starts_with(&path(&["Ctrl"])));
+    assert_eq!(p.len(), 3);
+}
+
+#[test]
+fn block_lookup_uses_escaped_paths() {
+    let parse = |xml: &str| -> System {
+        let doc = roxmltree::Document::parse(xml).unwrap();
+        rustylink::block::parse_system_shallow(doc.root_element(), Utf8Path::new("")).unwrap()
+    };
+    let mut root = parse(
+        r#"<System>
+  <Block BlockType="SubSystem" Name="In/Out" SID="1"/>
+  <Block BlockType="SubSystem" Name="In" SID="2"/>
+</System>"#,
+    );
+    let inner = parse(r#"<System><Block BlockType="Gain" Name="K" SID="3"/></System>"#);
+    root.blocks[0].subsystem = Some(Box::new(inner));
+
+    assert_eq!(root.block_at("In//Out").unwrap().sid.as_deref(), Some("1"));
+    assert_eq!(
+        root.block_at("In//Out/K").unwrap().sid.as_deref(),
+        Some("3")
+    );
+    assert!(root.block_at("In/Out/K").is_none());
+    assert!(root.system_at(&path(&["In/Out"])).is_some());
+
+    let gain = root.block_at("In//Out/K").unwrap();
+    assert_eq!(gain.get_full_path(&root).as_deref(), Some("In//Out/K"));
+}
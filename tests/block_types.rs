@@ -0,0 +1,105 @@
+use rustylink::block_types::{
+    BlockShape, IconSpec, Rgb, get_block_type_config_map, load_config, set_block_type_config,
+};
+
+fn config(key: &str) -> rustylink::BlockTypeConfig {
+    get_block_type_config_map()
+        .read()
+        .unwrap()
+        .get(key)
+        .cloned()
+        .unwrap()
+}
+
+#[test]
+fn toml_config_merges_into_existing_entries() {
+    set_block_type_config(
+        "CfgTomlBlock",
+        rustylink::BlockTypeConfig {
+            border: Some(Rgb(1, 2, 3)),
+            default_ins: 2,
+            ..Default::default()
+        },
+    );
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("icons")).unwrap();
+    let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4"/>"#;
+    std::fs::write(dir.path().join("icons").join("valve.svg"), svg).unwrap();
+    let path = dir.path().join("blocks.toml");
+    std::fs::write(
+        &path,
+        r##"
+[block_types.CfgTomlBlock]
+background = "#ffe0a0"
+icon = { glyph = "K" }
+show_input_port_labels = false
+input_port_names = ["u", "k"]
+
+[block_types."cfg_lib/Valve"]
+icon = { svg = "icons/valve.svg" }
+shape = "circle"
+border = [10, 20, 30]
+"##,
+    )
+    .unwrap();
+
+    assert_eq!(load_config(&path).unwrap(), 2);
+
+    let cfg = config("CfgTomlBlock");
+    assert_eq!(cfg.background, Some(Rgb(0xff, 0xe0, 0xa0)));
+    assert_eq!(cfg.border, Some(Rgb(1, 2, 3)));
+    assert_eq!(cfg.default_ins, 2);
+    assert_eq!(cfg.icon, Some(IconSpec::Utf8("K")));
+    assert!(!cfg.show_input_port_labels);
+    assert!(cfg.show_output_port_labels);
+    assert_eq!(cfg.input_port_names, vec!["u", "k"]);
+
+    let cfg = config("cfg_lib/Valve");
+    assert_eq!(cfg.icon, Some(IconSpec::SvgBytes(svg.as_slice().into())));
+    assert_eq!(cfg.shape, BlockShape::Circle);
+    assert_eq!(cfg.border, Some(Rgb(10, 20, 30)));
+    assert!(cfg.known);
+}
+
+#[test]
+fn json_config_is_accepted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("blocks.json");
+    std::fs::write(
+        &path,
+        r#"{"block_types": {"CfgJsonBlock": {"show_output_port_labels": false}}}"#,
+    )
+    .unwrap();
+    assert_eq!(load_config(&path).unwrap(), 1);
+    assert!(!config("CfgJsonBlock").show_output_port_labels);
+}
+
+#[test]
+fn invalid_configs_are_rejected_without_changes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("bad.toml");
+    std::fs::write(
+        &path,
+        r#"
+[block_types.CfgBadBlock]
+background = "orange"
+"#,
+    )
+    .unwrap();
+    let err = load_config(&path).unwrap_err();
+    assert!(format!("{err:#}").contains("CfgBadBlock"), "{err:#}");
+    assert!(
+        get_block_type_config_map()
+            .read()
+            .unwrap()
+            .get("CfgBadBlock")
+            .is_none()
+    );
+
+    std::fs::write(&path, "[block_types.CfgBadBlock]\ncolour = \"#000000\"\n").unwrap();
+    assert!(load_config(&path).is_err());
+
+    let yaml = dir.path().join("blocks.yaml");
+    std::fs::write(&yaml, "").unwrap();
+    assert!(load_config(&yaml).is_err());
+}
@@ -0,0 +1,153 @@
+mod common;
+
+use common::parse;
+use rustylink::bus::{BusElement, element_paths, input_bus_elements, input_signals_value};
+use rustylink::model::System;
+
+/// `inner` bundles `x` and an unnamed signal; `outer` bundles `speed` and
+/// `inner`, passed through a delay into a selector.
+fn model(output_signals: &str) -> System {
+    parse(&format!(
+        r#"<System>
+  <Block BlockType="Inport" Name="A" SID="1"/>
+  <Block BlockType="Inport" Name="B" SID="2"/>
+  <Block BlockType="Inport" Name="C" SID="3"/>
+  <Block BlockType="BusCreator" Name="inner" SID="4">
+    <PortCounts in="2" out="1"/>
+  </Block>
+  <Block BlockType="BusCreator" Name="outer" SID="5">
+    <PortCounts in="2" out="1"/>
+  </Block>
+  <Block BlockType="UnitDelay" Name="Delay" SID="6"/>
+  <Block BlockType="BusSelector" Name="Select" SID="7">
+    <PortCounts in="1" out="2"/>
+    <P Name="OutputSignals">{output_signals}</P>
+  </Block>
+  <Block BlockType="Outport" Name="O1" SID="8"/>
+  <Block BlockType="Outport" Name="O2" SID="9"/>
+  <Line>
+    <P Name="Name">x</P>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">4#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">2#out:1</P>
+    <P Name="Dst">4#in:2</P>
+  </Line>
+  <Line>
+    <P Name="Name">speed</P>
+    <P Name="Src">3#out:1</P>
+    <P Name="Dst">5#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Name">inner</P>
+    <P Name="Src">4#out:1</P>
+    <P Name="Dst">5#in:2</P>
+  </Line>
+  <Line>
+    <P Name="Src">5#out:1</P>
+    <P Name="Dst">6#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">6#out:1</P>
+    <P Name="Dst">7#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">7#out:1</P>
+    <P Name="Dst">8#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">7#out:2</P>
+    <P Name="Dst">9#in:1</P>
+  </Line>
+</System>"#
+    ))
+}
+
+fn leaf(name: &str) -> BusElement {
+    BusElement {
+        name: name.to_string(),
+        children: Vec::new(),
+    }
+}
+
+#[test]
+fn bus_hierarchy_is_traced_through_creators_and_delays() {
+    let system = model("speed,inner.x");
+    let elements = input_bus_elements(&system, "7", 1).unwrap();
+    assert_eq!(
+        elements,
+        [
+            leaf("speed"),
+            BusElement {
+                name: "inner".to_string(),
+                children: vec![leaf("x"), leaf("signal2")],
+            },
+        ]
+    );
+    assert_eq!(
+        element_paths(&elements),
+        ["speed", "inner", "inner.x", "inner.signal2"]
+    );
+    assert_eq!(
+        input_signals_value(&elements),
+        "{'speed',{'inner',{'x','signal2'}}}"
+    );
+    // A plain signal is not a bus.
+    assert!(input_bus_elements(&system, "4", 1).is_none());
+}
+
+#[cfg(feature = "egui")]
+#[test]
+fn configuring_a_selector_keeps_lines_of_kept_signals() {
+    use rustylink::editor::operations::{EditorHistory, configure_bus_selector};
+
+    let mut system = model("speed,inner.x");
+    let elements = input_bus_elements(&system, "7", 1).unwrap();
+    let mut history = EditorHistory::new(10);
+    let selected = ["inner.signal2".to_string(), "inner.x".to_string()];
+    history.push(configure_bus_selector(&mut system, 6, &elements, &selected).unwrap());
+
+    let selector = &system.blocks[6];
+    assert_eq!(
+        selector.properties.get("OutputSignals").map(String::as_str),
+        Some("inner.x,inner.signal2")
+    );
+    assert_eq!(
+        selector.properties.get("InputSignals").map(String::as_str),
+        Some("{'speed',{'inner',{'x','signal2'}}}")
+    );
+    assert_eq!(selector.port_counts.as_ref().unwrap().outs, Some(2));
+    // The line of `speed` is gone; the one of `inner.x` moved to output 1.
+    let from_selector: Vec<_> = system
+        .lines
+        .iter()
+        .filter_map(|l| l.src.as_ref())
+        .filter(|s| s.sid == "7")
+        .map(|s| s.port_index)
+        .collect();
+    assert_eq!(from_selector, [1]);
+    assert_eq!(
+        system
+            .lines
+            .last()
+            .unwrap()
+            .properties
+            .get("Src")
+            .map(String::as_str),
+        Some("7#out:1")
+    );
+
+    assert!(history.undo(&mut system));
+    assert_eq!(system.lines.len(), 8);
+    assert_eq!(
+        system.blocks[6]
+            .properties
+            .get("OutputSignals")
+            .map(String::as_str),
+        Some("speed,inner.x")
+    );
+
+    assert!(configure_bus_selector(&mut system, 6, &elements, &["nope".to_string()]).is_err());
+    assert!(configure_bus_selector(&mut system, 3, &elements, &["speed".to_string()]).is_err());
+}
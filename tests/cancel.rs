@@ -0,0 +1,93 @@
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use rustylink::analysis::find_clones_with_cancel;
+use rustylink::cancel::{CancellationToken, Cancelled};
+use rustylink::diff::diff_systems_with_cancel;
+use rustylink::model::System;
+use rustylink::parser::{ContentSource, FsSource, ParserOptions, SimulinkParser};
+
+/// Two system files; reading `cancel_on` cancels `token`, as a frontend
+/// would while the parse is running.
+struct CancellingSource {
+    token: CancellationToken,
+    cancel_on: &'static str,
+}
+
+impl ContentSource for CancellingSource {
+    fn read_to_string(&mut self, path: &Utf8Path) -> Result<String> {
+        if path.as_str() == self.cancel_on {
+            self.token.cancel();
+        }
+        Ok(match path.file_name() {
+            Some("system_root.xml") => {
+                r#"<System><Block BlockType="SubSystem" Name="Sub" SID="1"><System Ref="system_1"/></Block></System>"#
+            }
+            _ => r#"<System><Block BlockType="Gain" Name="K" SID="2"/></System>"#,
+        }
+        .to_string())
+    }
+
+    fn list_dir(&mut self, path: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+        Ok(match path.as_str() {
+            "/simulink/systems" => vec![
+                "/simulink/systems/system_root.xml".into(),
+                "/simulink/systems/system_1.xml".into(),
+            ],
+            _ => Vec::new(),
+        })
+    }
+}
+
+fn parse(cancel_on: &'static str) -> Result<System> {
+    let token = CancellationToken::new();
+    let source = CancellingSource {
+        token: token.clone(),
+        cancel_on,
+    };
+    let options = ParserOptions {
+        cancel: token,
+        ..ParserOptions::default()
+    };
+    SimulinkParser::with_options("/", source, options)
+        .parse_system_file("/simulink/systems/system_root.xml")
+}
+
+fn is_cancelled(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<Cancelled>().is_some()
+}
+
+#[test]
+fn tokens_share_their_flag() {
+    let token = CancellationToken::new();
+    let clone = token.clone();
+    assert_eq!(token, clone);
+    assert_ne!(token, CancellationToken::new());
+    assert!(token.check().is_ok());
+    drop(clone.drop_guard());
+    assert!(token.is_cancelled());
+    assert_eq!(token.check(), Err(Cancelled));
+}
+
+#[test]
+fn cancelling_stops_a_running_parse() {
+    assert!(parse("never").is_ok());
+    let err = parse("/simulink/systems/system_1.xml").unwrap_err();
+    assert!(is_cancelled(&err), "{err:#}");
+}
+
+#[test]
+fn cancelled_library_resolution_and_analyses_stop() {
+    let mut system = parse("never").unwrap();
+    let token = CancellationToken::new();
+    let resolve = |system: &mut System, token| {
+        SimulinkParser::<FsSource>::resolve_library_references_with_cancel(system, &[], token)
+    };
+    assert!(resolve(&mut system, &token).is_ok());
+    assert!(find_clones_with_cancel(&system, 1, &token).is_ok());
+    assert!(diff_systems_with_cancel(&system, &system, &token).is_ok());
+
+    token.cancel();
+    assert!(is_cancelled(&resolve(&mut system, &token).unwrap_err()));
+    assert_eq!(find_clones_with_cancel(&system, 1, &token), Err(Cancelled));
+    assert!(diff_systems_with_cancel(&system, &system, &token).is_err());
+}
@@ -0,0 +1,103 @@
+use rustylink::color::{Rgba, parse_color, parse_simulink_color};
+
+#[test]
+fn parses_simulink_named_colors() {
+    assert_eq!(parse_simulink_color("yellow"), Some(Rgba::rgb(255, 255, 0)));
+    assert_eq!(
+        parse_simulink_color("lightBlue"),
+        Some(Rgba::rgb(173, 216, 230))
+    );
+    assert_eq!(
+        parse_simulink_color("darkGreen"),
+        Some(Rgba::rgb(0, 100, 0))
+    );
+    assert_eq!(parse_simulink_color("Orange"), Some(Rgba::rgb(255, 165, 0)));
+    assert_eq!(parse_simulink_color("automatic"), None);
+    assert_eq!(parse_simulink_color("custom"), None);
+    assert_eq!(parse_simulink_color("notacolor"), None);
+}
+
+#[test]
+fn parses_triples_hex_and_custom() {
+    assert_eq!(
+        parse_simulink_color("[1.000000, 0.411765, 0.380392]"),
+        Some(Rgba::rgb(255, 105, 97))
+    );
+    assert_eq!(
+        parse_simulink_color("[0 0.5 1]"),
+        Some(Rgba::rgb(0, 128, 255))
+    );
+    assert_eq!(
+        parse_simulink_color("[255, 128, 0]"),
+        Some(Rgba::rgb(255, 128, 0))
+    );
+    assert_eq!(
+        parse_simulink_color("[1, 0, 0, 0.5]"),
+        Some(Rgba::new(255, 0, 0, 128))
+    );
+    assert_eq!(
+        parse_simulink_color("custom([0, 1, 0])"),
+        Some(Rgba::rgb(0, 255, 0))
+    );
+    assert_eq!(parse_simulink_color("#0f0"), Some(Rgba::rgb(0, 255, 0)));
+    assert_eq!(
+        parse_simulink_color("#11223344"),
+        Some(Rgba::new(0x11, 0x22, 0x33, 0x44))
+    );
+    assert_eq!(parse_simulink_color("[1, 2]"), None);
+}
+
+#[test]
+fn block_background_color_is_parsed() {
+    let xml = r#"<System><Block BlockType="Gain" Name="G" SID="1"><P Name="BackgroundColor">[0, 0, 1]</P></Block></System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let system =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    assert_eq!(
+        system.blocks[0].background_color,
+        Some(Rgba::rgb(0, 0, 255))
+    );
+    assert_eq!(
+        system.blocks[0].background_color.unwrap().to_hex(),
+        "#0000ff"
+    );
+    assert_eq!(parse_color("red").as_deref(), Some("#ff0000"));
+}
+
+#[test]
+fn block_and_annotation_style_properties_are_parsed() {
+    let xml = r#"<System>
+  <Block BlockType="Gain" Name="G" SID="1">
+    <P Name="ForegroundColor">red</P>
+    <P Name="DropShadow">on</P>
+    <P Name="FontName">Courier New</P>
+    <P Name="FontSize">-1</P>
+  </Block>
+  <Annotation SID="2">
+    <P Name="Name">Note</P>
+    <P Name="ForegroundColor">[0, 0, 1]</P>
+    <P Name="BackgroundColor">yellow</P>
+    <P Name="FontName">auto</P>
+    <P Name="FontSize">16</P>
+    <P Name="FontWeight">bold</P>
+  </Annotation>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let system =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    let b = &system.blocks[0];
+    assert_eq!(b.foreground_color, Some(Rgba::rgb(255, 0, 0)));
+    assert!(b.drop_shadow);
+    assert_eq!(b.font_name.as_deref(), Some("Courier New"));
+    assert_eq!(b.font_size, None);
+
+    let a = &system.annotations[0];
+    assert_eq!(a.foreground_color, Some(Rgba::rgb(0, 0, 255)));
+    assert_eq!(a.background_color, Some(Rgba::rgb(255, 255, 0)));
+    assert!(!a.drop_shadow);
+    assert_eq!(a.font_name, None);
+    assert_eq!(a.font_size, Some(16));
+    assert_eq!(a.font_weight.as_deref(), Some("bold"));
+}
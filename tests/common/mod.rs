@@ -0,0 +1,10 @@
+//! Helpers shared by the integration tests.
+
+use camino::Utf8Path;
+use rustylink::model::System;
+
+/// The system of `xml`, without resolving subsystem references.
+pub fn parse(xml: &str) -> System {
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    rustylink::block::parse_system_shallow(doc.root_element(), Utf8Path::new("")).unwrap()
+}
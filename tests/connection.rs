@@ -0,0 +1,139 @@
+mod common;
+
+use common::parse;
+use rustylink::generator::system_xml::generate_system_xml;
+use rustylink::model::{EndpointRef, Point, PortKind, System};
+
+fn input(sid: &str) -> EndpointRef {
+    EndpointRef {
+        sid: sid.to_string(),
+        port_type: PortKind::In,
+        port_index: 1,
+    }
+}
+
+fn dst_sids(system: &System) -> Vec<String> {
+    system.lines[0]
+        .connections()
+        .into_iter()
+        .map(|c| c.dst.sid)
+        .collect()
+}
+
+/// A line from SID 1 to SID 2, and from a nested split to SIDs 3 and 4.
+const TREE: &str = r#"<System>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Points">[20, 0]</P>
+    <Branch>
+      <P Name="Points">[0, 40]</P>
+      <P Name="Dst">2#in:1</P>
+    </Branch>
+    <Branch>
+      <P Name="Points">[30, 0]</P>
+      <Branch>
+        <P Name="Dst">3#in:1</P>
+      </Branch>
+      <Branch>
+        <P Name="Points">[0, -20]</P>
+        <P Name="Dst">4#in:1</P>
+      </Branch>
+    </Branch>
+  </Line>
+</System>"#;
+
+#[test]
+fn connections_flatten_the_branch_tree() {
+    let system = parse(TREE);
+    let conns = system.lines[0].connections();
+    assert_eq!(dst_sids(&system), ["2", "3", "4"]);
+    assert!(conns.iter().all(|c| c.src.as_ref().unwrap().sid == "1"));
+    assert_eq!(conns[2].branch_path, [1, 1]);
+    assert_eq!(
+        conns[2].points,
+        [
+            Point { x: 20, y: 0 },
+            Point { x: 30, y: 0 },
+            Point { x: 0, y: -20 }
+        ]
+    );
+    let to_two = system.lines[0].connection_to(&input("2")).unwrap();
+    assert_eq!(to_two.branch_path, [0]);
+    assert!(system.lines[0].connection_to(&input("9")).is_none());
+}
+
+#[test]
+fn removing_a_connection_prunes_empty_branches() {
+    let mut system = parse(TREE);
+    let line = &mut system.lines[0];
+    assert!(line.remove_connection(&input("3")));
+    assert!(!line.remove_connection(&input("3")));
+    assert_eq!(line.branches[1].branches.len(), 1);
+    assert!(line.remove_connection(&input("4")));
+    // The split towards 3 and 4 led nowhere any more
+    assert_eq!(line.branches.len(), 1);
+    assert_eq!(dst_sids(&system), ["2"]);
+
+    assert!(system.lines[0].remove_connection(&input("2")));
+    assert!(system.lines[0].connections().is_empty());
+    let xml = generate_system_xml(&system);
+    assert!(!xml.contains("<Branch>"));
+    assert!(!xml.contains(r#"<P Name="Dst">"#));
+}
+
+#[test]
+fn adding_a_connection_splits_a_direct_line() {
+    let mut system = parse(
+        r#"<System>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Points">[20, 0]</P>
+    <P Name="Dst">2#in:1</P>
+  </Line>
+</System>"#,
+    );
+    let line = &mut system.lines[0];
+    line.add_connection(input("3"), vec![Point { x: 0, y: 30 }]);
+    assert_eq!(line.dst, None);
+    assert!(!line.properties.contains_key("Dst"));
+    assert_eq!(line.branches.len(), 2);
+    assert_eq!(
+        line.branches[0].properties.get("Dst").map(String::as_str),
+        Some("2#in:1")
+    );
+    assert_eq!(
+        line.branches[1]
+            .properties
+            .get("Points")
+            .map(String::as_str),
+        Some("[0, 30]")
+    );
+    assert_eq!(dst_sids(&system), ["2", "3"]);
+
+    // An unconnected line gets the destination on its trunk
+    let line = &mut system.lines[0];
+    line.remove_connection(&input("2"));
+    line.remove_connection(&input("3"));
+    line.add_connection(input("4"), vec![Point { x: 0, y: 10 }]);
+    assert!(line.branches.is_empty());
+    assert_eq!(line.dst, Some(input("4")));
+    assert_eq!(
+        line.properties.get("Points").map(String::as_str),
+        Some("[20, 0; 0, 10]")
+    );
+    assert_eq!(
+        line.properties.keys().last().map(String::as_str),
+        Some("Dst")
+    );
+}
+
+#[test]
+fn retargeting_keeps_the_route() {
+    let mut system = parse(TREE);
+    let line = &mut system.lines[0];
+    let route = line.connection_to(&input("3")).unwrap().points;
+    assert!(line.set_connection_dst(&input("3"), input("7")));
+    assert!(!line.set_connection_dst(&input("3"), input("8")));
+    assert_eq!(line.connection_to(&input("7")).unwrap().points, route);
+    assert!(generate_system_xml(&system).contains(r#"<P Name="Dst">7#in:1</P>"#));
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use camino::Utf8PathBuf;
+use common::parse;
+use rustylink::cancel::CancellationToken;
+use rustylink::cosmetic::CosmeticFilter;
+use rustylink::diff::diff_systems_filtered;
+use rustylink::model::System;
+
+fn model(gain: &str, position: &str, font_size: &str) -> System {
+    parse(&format!(
+        r#"<System>
+  <Block BlockType="Gain" Name="K" SID="10">
+    <P Name="Gain">{gain}</P>
+    <P Name="Position">{position}</P>
+    <P Name="FontSize">{font_size}</P>
+  </Block>
+</System>"#
+    ))
+}
+
+fn changed_parameters(old: &System, new: &System, filter: &CosmeticFilter) -> Vec<String> {
+    let diff = diff_systems_filtered(old, new, filter, &CancellationToken::new()).unwrap();
+    diff.blocks
+        .iter()
+        .flat_map(|b| b.parameters.iter().map(|p| p.name.clone()))
+        .collect()
+}
+
+#[test]
+fn groups_decide_what_diff_and_hash_ignore() {
+    let old = model("2", "[100, 10, 130, 40]", "10");
+    let moved = model("2", "[200, 10, 230, 40]", "12");
+    let retuned = model("3", "[100, 10, 130, 40]", "10");
+
+    assert_eq!(
+        changed_parameters(&old, &moved, &CosmeticFilter::none()),
+        ["Position", "FontSize"]
+    );
+    assert!(changed_parameters(&old, &moved, &CosmeticFilter::default()).is_empty());
+    assert_eq!(
+        changed_parameters(&old, &retuned, &CosmeticFilter::default()),
+        ["Gain"]
+    );
+    let layout_counts = CosmeticFilter {
+        layout: false,
+        ..CosmeticFilter::default()
+    };
+    assert_eq!(
+        changed_parameters(&old, &moved, &layout_counts),
+        ["Position"]
+    );
+
+    assert_eq!(old.structural_hash(), moved.structural_hash());
+    assert_ne!(
+        old.structural_hash_with(&layout_counts),
+        moved.structural_hash_with(&layout_counts)
+    );
+}
+
+#[test]
+fn filter_is_read_from_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = Utf8PathBuf::from_path_buf(dir.path().join("cosmetic.json")).unwrap();
+    std::fs::write(
+        &path,
+        r#"{ "fonts": false, "parameters": ["Description"], "keep": ["ZOrder"] }"#,
+    )
+    .unwrap();
+    let filter = CosmeticFilter::from_file(&path).unwrap();
+
+    assert!(filter.layout && !filter.fonts);
+    assert!(filter.is_cosmetic("Position"));
+    assert!(filter.is_cosmetic("Description"));
+    assert!(!filter.is_cosmetic("ZOrder"));
+    assert!(!filter.is_cosmetic("FontSize"));
+    assert!(!filter.is_cosmetic("Gain"));
+
+    std::fs::write(&path, r#"{ "layuot": false }"#).unwrap();
+    assert!(CosmeticFilter::from_file(&path).is_err());
+}
+
+#[test]
+fn textconv_value_drops_cosmetic_content() {
+    let value = CosmeticFilter::default()
+        .to_value(&model("2", "[100, 10, 130, 40]", "10"))
+        .unwrap();
+    let block = &value["blocks"][0];
+    assert_eq!(block["properties"], serde_json::json!({ "Gain": "2" }));
+    assert!(block.get("position").is_none());
+    assert!(block.get("font_size").is_none());
+    assert_eq!(block["type"], "Gain");
+}
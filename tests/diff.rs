@@ -0,0 +1,196 @@
+mod common;
+
+use common::parse;
+use rustylink::diff::{ChangeKind, diff_systems, to_html};
+use rustylink::model::System;
+
+const OLD_ROOT: &str = r#"<System>
+  <Block BlockType="Inport" Name="In" SID="1"><P Name="Position">[20, 20, 50, 34]</P></Block>
+  <Block BlockType="Gain" Name="K" SID="2">
+    <P Name="Position">[100, 10, 130, 40]</P>
+    <P Name="Gain">2</P>
+  </Block>
+  <Block BlockType="SubSystem" Name="Filter" SID="3"><P Name="Position">[200, 10, 260, 50]</P></Block>
+  <Block BlockType="Terminator" Name="T" SID="4"><P Name="Position">[300, 20, 320, 40]</P></Block>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+  <Line><P Name="Src">2#out:1</P><P Name="Dst">3#in:1</P></Line>
+</System>"#;
+
+const NEW_ROOT: &str = r#"<System>
+  <Block BlockType="Inport" Name="In" SID="1"><P Name="Position">[20, 20, 50, 34]</P></Block>
+  <Block BlockType="Gain" Name="K" SID="2">
+    <P Name="Position">[100, 10, 130, 40]</P>
+    <P Name="Gain">3</P>
+  </Block>
+  <Block BlockType="SubSystem" Name="LowPass" SID="3"><P Name="Position">[200, 10, 260, 50]</P></Block>
+  <Block BlockType="Scope" Name="S" SID="5"><P Name="Position">[300, 20, 330, 50]</P></Block>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+  <Line><P Name="Src">2#out:1</P><P Name="Dst">5#in:1</P></Line>
+</System>"#;
+
+const OLD_INNER: &str = r#"<System>
+  <Block BlockType="Inport" Name="u" SID="10"><P Name="Position">[20, 20, 50, 34]</P></Block>
+  <Block BlockType="Outport" Name="y" SID="11"><P Name="Position">[120, 20, 150, 34]</P></Block>
+</System>"#;
+
+const NEW_INNER: &str = r#"<System>
+  <Block BlockType="Inport" Name="u" SID="10"><P Name="Position">[20, 20, 50, 34]</P></Block>
+  <Block BlockType="Outport" Name="y" SID="11">
+    <P Name="Position">[120, 20, 150, 34]</P>
+    <P Name="Port">1</P>
+  </Block>
+</System>"#;
+
+fn model(root: &str, inner: &str) -> System {
+    let mut root = parse(root);
+    root.blocks[2].subsystem = Some(Box::new(parse(inner)));
+    root
+}
+
+#[test]
+fn identical_models_have_no_differences() {
+    let old = model(OLD_ROOT, OLD_INNER);
+    let diff = diff_systems(&old, &old);
+    assert!(diff.is_empty());
+    assert!(to_html(&diff).contains("No differences."));
+}
+
+#[test]
+fn blocks_and_connections_are_compared() {
+    let diff = diff_systems(&model(OLD_ROOT, OLD_INNER), &model(NEW_ROOT, NEW_INNER));
+    let summary: Vec<(&str, ChangeKind)> = diff
+        .blocks
+        .iter()
+        .map(|c| (c.path.as_str(), c.kind))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("T", ChangeKind::Removed),
+            ("K", ChangeKind::Modified),
+            ("LowPass", ChangeKind::Modified),
+            ("LowPass/y", ChangeKind::Modified),
+            ("S", ChangeKind::Added),
+        ]
+    );
+
+    let gain = &diff.blocks[1].parameters;
+    assert_eq!(gain.len(), 1);
+    assert_eq!(gain[0].name, "Gain");
+    assert_eq!(gain[0].old.as_deref(), Some("2"));
+    assert_eq!(gain[0].new.as_deref(), Some("3"));
+
+    // Renames are matched by SID.
+    let renamed = &diff.blocks[2];
+    assert_eq!(renamed.old_path.as_deref(), Some("Filter"));
+    assert_eq!(renamed.parameters[0].name, "Name");
+    assert_eq!(diff.blocks[3].old_path.as_deref(), Some("Filter/y"));
+    assert_eq!(diff.blocks[3].parameters[0].old, None);
+
+    let conns: Vec<(&str, &str, ChangeKind)> = diff
+        .connections
+        .iter()
+        .map(|c| (c.src.as_str(), c.dst.as_str(), c.kind))
+        .collect();
+    assert_eq!(
+        conns,
+        [
+            ("K:1", "Filter:1", ChangeKind::Removed),
+            ("K:1", "S:1", ChangeKind::Added),
+        ]
+    );
+    // Both connections leave the same port, so they name the same line
+    assert_eq!(diff.connections[0].line, diff.connections[1].line);
+    assert_eq!(diff.changed_systems(), ["", "LowPass"]);
+
+    let json = serde_json::to_value(&diff).unwrap();
+    assert_eq!(json["blocks"][0]["blockType"], "Terminator");
+    assert_eq!(json["blocks"][0]["kind"], "Removed");
+    assert!(json["blocks"][1].get("oldPath").is_none());
+}
+
+#[test]
+fn html_report_shows_both_sides() {
+    let diff = diff_systems(&model(OLD_ROOT, OLD_INNER), &model(NEW_ROOT, NEW_INNER));
+    let html = to_html(&diff);
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<h2>(root)</h2>"));
+    assert!(html.contains("<h2>LowPass</h2>"));
+    // Two diagrams per changed level, the old one of the renamed subsystem
+    // found under its old name.
+    assert_eq!(html.matches("<svg").count(), 4);
+    assert!(!html.contains("Not present."));
+    // Removed, added and modified highlights.
+    assert_eq!(html.matches("fill=\"#f08c8c\"").count(), 1);
+    assert_eq!(html.matches("fill=\"#8cdc8c\"").count(), 1);
+    // K and the subsystem on both sides, y on both sides.
+    assert_eq!(html.matches("fill=\"#fac86e\"").count(), 6);
+    assert!(html.contains("<td>Gain</td><td>2</td><td>3</td>"));
+    assert!(html.contains("K:1 &rarr; S:1"));
+}
+
+// MDL-derived models: no SIDs, lines refer to blocks by name.
+const MDL_OLD: &str = r#"<System>
+  <Block BlockType="Inport" Name="In1"><P Name="Position">[20, 20, 50, 34]</P></Block>
+  <Block BlockType="Gain" Name="Gain"><P Name="Position">[100, 10, 130, 40]</P></Block>
+  <Block BlockType="Gain" Name="Feedback"><P Name="Position">[100, 200, 130, 230]</P></Block>
+  <Block BlockType="Outport" Name="Out1"><P Name="Position">[200, 20, 230, 34]</P></Block>
+  <Block BlockType="Terminator" Name="T"><P Name="Position">[400, 400, 420, 420]</P></Block>
+  <Line><P Name="Src">In1#out:1</P><P Name="Dst">Gain#in:1</P></Line>
+  <Line><P Name="Src">Gain#out:1</P><P Name="Dst">Out1#in:1</P></Line>
+</System>"#;
+
+const MDL_NEW: &str = r#"<System>
+  <Block BlockType="Inport" Name="In1"><P Name="Position">[20, 20, 50, 34]</P></Block>
+  <Block BlockType="Gain" Name="Amplify">
+    <P Name="Position">[110, 10, 140, 40]</P>
+    <P Name="Gain">4</P>
+  </Block>
+  <Block BlockType="Gain" Name="Feedback2"><P Name="Position">[100, 200, 130, 230]</P></Block>
+  <Block BlockType="Outport" Name="Out1"><P Name="Position">[200, 20, 230, 34]</P></Block>
+  <Block BlockType="Terminator" Name="Sink"><P Name="Position">[20, 400, 40, 420]</P></Block>
+  <Line><P Name="Src">In1#out:1</P><P Name="Dst">Amplify#in:1</P></Line>
+  <Line><P Name="Src">Amplify#out:1</P><P Name="Dst">Out1#in:1</P></Line>
+</System>"#;
+
+#[test]
+fn blocks_without_sids_are_matched_by_structure() {
+    let diff = diff_systems(&parse(MDL_OLD), &parse(MDL_NEW));
+    let summary: Vec<(&str, Option<&str>, ChangeKind)> = diff
+        .blocks
+        .iter()
+        .map(|c| (c.path.as_str(), c.old_path.as_deref(), c.kind))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("T", None, ChangeKind::Removed),
+            ("Amplify", Some("Gain"), ChangeKind::Modified),
+            ("Feedback2", Some("Feedback"), ChangeKind::Modified),
+            ("Sink", None, ChangeKind::Added),
+        ]
+    );
+
+    // Renamed and slightly moved, but wired the same way.
+    let amplify = &diff.blocks[1];
+    let confidence = amplify.confidence.unwrap();
+    assert!((0.5..1.0).contains(&confidence), "{confidence}");
+    let names: Vec<&str> = amplify.parameters.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["Name", "Position", "Gain"]);
+    // A similar name in place scores higher than a new name on a new spot.
+    assert!(diff.blocks[2].confidence.unwrap() > confidence);
+
+    // The connections follow the matched blocks.
+    assert!(diff.connections.is_empty());
+
+    let json = serde_json::to_value(&diff).unwrap();
+    assert!(json["blocks"][1]["confidence"].is_number());
+    assert!(json["blocks"][0].get("confidence").is_none());
+    assert!(to_html(&diff).contains("% match)"));
+}
+
+#[test]
+fn blocks_with_sids_are_never_paired_by_structure() {
+    let diff = diff_systems(&model(OLD_ROOT, OLD_INNER), &model(NEW_ROOT, NEW_INNER));
+    assert!(diff.blocks.iter().all(|c| c.confidence.is_none()));
+}
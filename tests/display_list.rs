@@ -0,0 +1,116 @@
+use rustylink::color::Rgba;
+use rustylink::display_list::{Align, DisplayList, Primitive, Stroke, arrow_head};
+
+fn list() -> DisplayList {
+    let mut list = DisplayList::new().with_background(Rgba::rgb(255, 255, 255));
+    list.push(Primitive::Rect {
+        rect: [0.0, 0.0, 30.0, 20.0],
+        corner_radius: 4.0,
+        fill: Some(Rgba::rgb(200, 220, 255)),
+        stroke: Some(Stroke::new(1.0, Rgba::rgb(90, 90, 90))),
+        title: Some("A & B".into()),
+    });
+    list.push(Primitive::Polyline {
+        points: vec![(30.0, 10.0), (100.0, 10.0)],
+        stroke: Stroke::new(1.5, Rgba::rgb(0, 0, 0)).dashed(4.0, 3.0),
+        arrow: true,
+    });
+    list.push(Primitive::Text {
+        pos: (15.0, 20.0),
+        offset: (0.0, 2.0),
+        text: "first\nsecond".into(),
+        size: 10.0,
+        color: Rgba::rgb(0, 0, 0),
+        anchor: [Align::Center, Align::Min],
+    });
+    list
+}
+
+#[test]
+fn bounds_cover_all_primitives() {
+    assert_eq!(DisplayList::new().bounds(), None);
+    assert_eq!(list().bounds(), Some([0.0, 0.0, 100.0, 22.0]));
+}
+
+#[test]
+fn arrow_heads_point_along_the_last_segment() {
+    let head = arrow_head(&[(0.0, 0.0), (10.0, 0.0)], 4.0).unwrap();
+    assert_eq!(head, [(10.0, 0.0), (6.0, 2.0), (6.0, -2.0)]);
+    assert!(arrow_head(&[(1.0, 1.0)], 4.0).is_none());
+}
+
+#[test]
+fn svg_backend_writes_every_primitive() {
+    let svg = list().to_svg(10.0);
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(r#"viewBox="-10 -10 120 42""#));
+    assert!(svg.contains(r##"fill="#c8dcff""##));
+    assert!(svg.contains(r#" rx="4""#));
+    assert!(svg.contains("<title>A &amp; B</title>"));
+    assert!(svg.contains(r#"stroke-dasharray="4 3""#));
+    assert!(svg.contains("<polygon"));
+    // One element per line of text, stacked downwards
+    assert!(svg.contains(r#"y="22" font-size="10""#));
+    assert!(svg.contains(r#"y="34" font-size="10""#));
+    assert!(svg.contains(">second</text>"));
+}
+
+#[test]
+fn svg_backend_writes_ellipses_and_polygons() {
+    let mut list = DisplayList::new();
+    list.push(Primitive::Ellipse {
+        rect: [0.0, 0.0, 20.0, 10.0],
+        fill: Some(Rgba::rgb(255, 255, 255)),
+        stroke: Some(Stroke::new(1.0, Rgba::rgb(0, 0, 0))),
+    });
+    list.push(Primitive::Polygon {
+        points: vec![(30.0, 0.0), (50.0, 5.0), (30.0, 10.0)],
+        fill: None,
+        stroke: Some(Stroke::new(1.0, Rgba::rgb(0, 0, 0))),
+    });
+    assert_eq!(list.bounds(), Some([0.0, 0.0, 50.0, 10.0]));
+    let svg = list.to_svg(0.0);
+    assert!(svg.contains(r##"<ellipse cx="10" cy="5" rx="10" ry="5" fill="#ffffff""##));
+    assert!(svg.contains(r#"<polygon points="30,0 50,5 30,10" fill="none""#));
+}
+
+#[cfg(feature = "egui")]
+#[test]
+fn png_backend_and_system_view_layout() {
+    use camino::Utf8Path;
+    use eframe::egui;
+    use rustylink::egui_app::SystemView;
+
+    let png = list().to_png(10.0, 2.0).unwrap();
+    let image = resvg::tiny_skia::Pixmap::decode_png(&png).unwrap();
+    assert_eq!((image.width(), image.height()), (240, 84));
+
+    let xml = r#"<System>
+  <Block BlockType="Inport" Name="In" SID="1"><P Name="Position">[0, 0, 30, 20]</P></Block>
+  <Block BlockType="Gain" Name="K" SID="2"><P Name="Position">[100, 0, 130, 20]</P></Block>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let system =
+        rustylink::block::parse_system_shallow(doc.root_element(), Utf8Path::new("")).unwrap();
+    // Laid out without an egui context
+    let list = SystemView::new(&system, "export").display_list(&egui::Visuals::light());
+    let rects = list
+        .primitives
+        .iter()
+        .filter(|p| matches!(p, Primitive::Rect { .. }))
+        .count();
+    assert_eq!(rects, 1);
+    // The Gain is drawn as a triangle pointing at its output
+    assert!(list.primitives.iter().any(|p| matches!(
+        p,
+        Primitive::Polygon { points, .. } if points.len() == 3 && points[2] == (130.0, 10.0)
+    )));
+    assert!(list.primitives.iter().any(|p| matches!(
+        p,
+        Primitive::Polyline { points, arrow: true, .. }
+            if points.first() == Some(&(30.0, 10.0)) && points.last() == Some(&(100.0, 10.0))
+    )));
+    let svg = list.to_svg(20.0);
+    assert!(svg.contains(">In</text>") && svg.contains(">K</text>"));
+}
@@ -0,0 +1,128 @@
+use camino::Utf8PathBuf;
+use rustylink::doctor::{Status, diagnose};
+use std::io::{Cursor, Write};
+
+const ROOT_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Reference" Name="PI" SID="1">
+    <P Name="SourceBlock">ctrllib/PI</P>
+  </Block>
+  <Block BlockType="ModelReference" Name="Plant" SID="2">
+    <P Name="ModelNameDialog">plant.slx</P>
+  </Block>
+  <Block BlockType="S-Function" Name="Driver" SID="3">
+    <P Name="FunctionName">can_driver</P>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="4"/>
+</System>
+"#;
+
+const CORE_PROPERTIES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+<cp:coreProperties xmlns:cp="http://schemas.openxmlformats.org/package/2006/metadata/core-properties" xmlns:dcterms="http://purl.org/dc/terms/">
+  <dcterms:modified>2026-03-03T18:07:05Z</dcterms:modified>
+  <cp:lastModifiedBy>jdoe</cp:lastModifiedBy>
+  <cp:version>R2025b</cp:version>
+</cp:coreProperties>
+"#;
+
+fn build_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        for (name, data) in files {
+            zip.start_file(
+                *name,
+                zip::write::FileOptions::default()
+                    .compression_method(zip::CompressionMethod::Stored),
+            )
+            .unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    buf.into_inner()
+}
+
+fn model() -> Vec<u8> {
+    build_zip(&[
+        ("metadata/coreProperties.xml", CORE_PROPERTIES.as_bytes()),
+        ("simulink/blockdiagram.xml", b"<ModelInformation/>"),
+        ("simulink/systems/system_root.xml", ROOT_XML.as_bytes()),
+    ])
+}
+
+#[test]
+fn missing_dependencies_are_warnings() {
+    let diagnosis = diagnose(&model(), &[]);
+    let status: Vec<_> = diagnosis
+        .checks
+        .iter()
+        .map(|c| (c.name, c.status))
+        .collect();
+    assert_eq!(
+        status,
+        [
+            ("archive", Status::Ok),
+            ("release", Status::Ok),
+            ("protection", Status::Ok),
+            ("system", Status::Ok),
+            ("libraries", Status::Warning),
+            ("model-references", Status::Warning),
+            ("s-functions", Status::Warning),
+        ]
+    );
+    assert_eq!(diagnosis.status(), Status::Warning);
+    let check = |name| diagnosis.checks.iter().find(|c| c.name == name).unwrap();
+    assert_eq!(
+        check("release").summary,
+        "saved with R2025b on 2026-03-03 by jdoe"
+    );
+    assert_eq!(
+        check("libraries").details,
+        ["ctrllib.slx (1 linked block(s))"]
+    );
+    assert_eq!(check("model-references").details, ["plant (used by Plant)"]);
+    assert!(check("s-functions").hint.is_some());
+
+    // Found in the search directories
+    let dir = tempfile::tempdir().unwrap();
+    let dir_path = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).unwrap();
+    std::fs::write(dir_path.join("ctrllib.slx"), b"").unwrap();
+    std::fs::write(dir_path.join("plant.slx"), b"").unwrap();
+    let diagnosis = diagnose(&model(), &[dir_path]);
+    let check = |name| diagnosis.checks.iter().find(|c| c.name == name).unwrap();
+    assert_eq!(check("libraries").status, Status::Ok);
+    assert_eq!(check("model-references").status, Status::Ok);
+}
+
+#[test]
+fn damaged_archives_are_errors() {
+    let diagnosis = diagnose(b"<?xml version=\"1.0\"?><ModelInformation/>", &[]);
+    assert_eq!(diagnosis.checks.len(), 1);
+    assert_eq!(diagnosis.checks[0].status, Status::Error);
+    assert!(diagnosis.checks[0].summary.starts_with("not a ZIP archive"));
+
+    // A flipped byte in the stored system XML fails its checksum
+    let mut bytes = model();
+    let at = bytes.windows(9).position(|w| w == b"BlockType").unwrap();
+    bytes[at] = b'b';
+    let diagnosis = diagnose(&bytes, &[]);
+    let archive = &diagnosis.checks[0];
+    assert_eq!(archive.status, Status::Error);
+    assert_eq!(archive.details.len(), 1);
+    assert!(archive.details[0].starts_with("simulink/systems/system_root.xml: "));
+    assert_eq!(diagnosis.status(), Status::Error);
+
+    let bytes = build_zip(&[("simulink/blockdiagram.xml", b"<ModelInformation/>")]);
+    let diagnosis = diagnose(&bytes, &[]);
+    assert_eq!(
+        diagnosis.checks[0].details,
+        ["simulink/systems/system_root.xml: missing"]
+    );
+    let system = diagnosis
+        .checks
+        .iter()
+        .find(|c| c.name == "system")
+        .unwrap();
+    assert_eq!(system.status, Status::Error);
+}
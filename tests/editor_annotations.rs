@@ -0,0 +1,175 @@
+use indexmap::IndexMap;
+use rustylink::editor::operations::{
+    add_annotation, create_annotation, delete_annotations, set_annotation_font,
+    set_annotation_interpreter, update_annotation,
+};
+use rustylink::editor::state::EditorState;
+use rustylink::editor::{AnnotationEditorState, EditorHistory};
+use rustylink::generator::system_xml::generate_system_xml;
+use rustylink::model::System;
+use std::collections::BTreeMap;
+
+fn make_empty_system() -> System {
+    System {
+        properties: IndexMap::new(),
+        blocks: Vec::new(),
+        lines: Vec::new(),
+        annotations: Vec::new(),
+        chart: None,
+    }
+}
+
+fn reparse(system: &System) -> System {
+    let xml = generate_system_xml(system);
+    let doc = roxmltree::Document::parse(&xml).unwrap();
+    rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new("")).unwrap()
+}
+
+#[test]
+fn test_add_annotation_assigns_sid_and_roundtrips() {
+    let mut system = make_empty_system();
+    let ann = create_annotation("Hello", 10, 20, 130, 50);
+    add_annotation(&mut system, ann);
+    assert_eq!(system.annotations[0].sid.as_deref(), Some("1"));
+
+    let mut ann = create_annotation("<b>Bold</b> note", 0, 0, 100, 20);
+    set_annotation_interpreter(&mut ann, Some("rich"));
+    set_annotation_font(&mut ann, Some("Arial"), Some(14), Some("bold"));
+    add_annotation(&mut system, ann);
+    assert_eq!(system.annotations[1].sid.as_deref(), Some("2"));
+
+    let parsed = reparse(&system);
+    assert_eq!(parsed.annotations.len(), 2);
+    let a = &parsed.annotations[0];
+    assert_eq!(a.text.as_deref(), Some("Hello"));
+    assert_eq!(a.position.as_deref(), Some("[10, 20, 130, 50]"));
+    let b = &parsed.annotations[1];
+    assert_eq!(b.text.as_deref(), Some("<b>Bold</b> note"));
+    assert_eq!(b.interpreter.as_deref(), Some("rich"));
+    assert_eq!(b.font_name.as_deref(), Some("Arial"));
+    assert_eq!(b.font_size, Some(14));
+    assert_eq!(b.font_weight.as_deref(), Some("bold"));
+}
+
+#[test]
+fn test_annotation_commands_undo_redo() {
+    let mut system = make_empty_system();
+    let mut history = EditorHistory::new(10);
+
+    history.push(add_annotation(
+        &mut system,
+        create_annotation("A", 0, 0, 10, 10),
+    ));
+    history.push(add_annotation(
+        &mut system,
+        create_annotation("B", 0, 20, 10, 30),
+    ));
+
+    let mut edited = system.annotations[0].clone();
+    set_annotation_font(&mut edited, None, Some(20), None);
+    history.push(update_annotation(&mut system, 0, edited).unwrap());
+    assert_eq!(system.annotations[0].font_size, Some(20));
+
+    history.push(delete_annotations(&mut system, &[1]));
+    assert_eq!(system.annotations.len(), 1);
+
+    assert!(history.undo(&mut system));
+    assert_eq!(system.annotations.len(), 2);
+    assert_eq!(system.annotations[1].text.as_deref(), Some("B"));
+
+    assert!(history.undo(&mut system));
+    assert_eq!(system.annotations[0].font_size, None);
+    assert!(!system.annotations[0].properties.contains_key("FontSize"));
+
+    assert!(history.redo(&mut system));
+    assert_eq!(system.annotations[0].font_size, Some(20));
+
+    assert!(history.undo(&mut system));
+    assert!(history.undo(&mut system));
+    assert!(history.undo(&mut system));
+    assert!(system.annotations.is_empty());
+}
+
+#[test]
+fn test_update_annotation_out_of_range() {
+    let mut system = make_empty_system();
+    assert!(update_annotation(&mut system, 0, create_annotation("x", 0, 0, 1, 1)).is_none());
+}
+
+#[test]
+fn test_annotation_editor_interpreter_conversion() {
+    let mut editor = AnnotationEditorState::default();
+    editor.open_new(0, 0);
+    editor.text = "a < b\nnext".to_string();
+    editor.set_interpreter("rich");
+    assert_eq!(editor.text, "a &lt; b<br/>next");
+    editor.set_interpreter("off");
+    assert_eq!(editor.text, "a < b\nnext");
+
+    editor.text = "<html><head><style>p { margin: 0 }</style></head><body><p>One</p><p>Two &amp; three</p></body></html>".to_string();
+    editor.interpreter = "rich".to_string();
+    editor.set_interpreter("tex");
+    assert_eq!(editor.text, "One\nTwo & three");
+}
+
+#[test]
+fn test_annotation_editor_wrap_selection() {
+    let mut editor = AnnotationEditorState::default();
+    editor.open_new(0, 0);
+    editor.set_interpreter("rich");
+    editor.text = "héllo world".to_string();
+    editor.selection = Some((6, 11));
+    editor.wrap_selection("b");
+    assert_eq!(editor.text, "héllo <b>world</b>");
+    editor.wrap_selection("i");
+    assert_eq!(editor.text, "<i>héllo <b>world</b></i>");
+}
+
+#[test]
+fn test_editor_state_annotation_workflow() {
+    let mut state = EditorState::new(
+        make_empty_system(),
+        vec![],
+        BTreeMap::new(),
+        BTreeMap::new(),
+    );
+    state.annotation_editor.open_new(40, 50);
+    state.annotation_editor.text = "Note".to_string();
+    state.annotation_editor.bold = true;
+    state.apply_annotation_editor();
+    assert!(state.dirty);
+    assert_eq!(state.annotation_editor.annotation_index, Some(0));
+
+    let ann = &state.current_system().unwrap().annotations[0];
+    assert_eq!(ann.text.as_deref(), Some("Note"));
+    assert_eq!(ann.position.as_deref(), Some("[40, 50, 160, 80]"));
+    assert_eq!(ann.font_weight.as_deref(), Some("bold"));
+    assert_eq!(
+        ann.properties.get("FontWeight").map(String::as_str),
+        Some("bold")
+    );
+
+    state.annotation_editor.text = "Edited".to_string();
+    state.apply_annotation_editor();
+    assert_eq!(
+        state.current_system().unwrap().annotations[0]
+            .text
+            .as_deref(),
+        Some("Edited")
+    );
+    state.undo();
+    assert_eq!(
+        state.current_system().unwrap().annotations[0]
+            .text
+            .as_deref(),
+        Some("Note")
+    );
+
+    state.open_annotation_editor(0);
+    assert!(state.annotation_editor.bold);
+    state.delete_annotation(0);
+    assert!(state.current_system().unwrap().annotations.is_empty());
+    assert!(!state.annotation_editor.open);
+    state.undo();
+    assert_eq!(state.current_system().unwrap().annotations.len(), 1);
+}
@@ -0,0 +1,95 @@
+#![cfg(feature = "egui")]
+
+use rustylink::editor::block_catalog::get_block_catalog;
+use rustylink::editor::operations::{add_block, block_template, create_catalog_block};
+use rustylink::generator::system_xml::generate_system_xml;
+use rustylink::model::{BlockChildKind, System};
+
+fn catalog_block(block_type: &str) -> rustylink::model::Block {
+    let entry = get_block_catalog()
+        .iter()
+        .find(|e| e.block_type == block_type)
+        .unwrap();
+    create_catalog_block(entry, 100, 50)
+}
+
+#[test]
+fn inserted_blocks_get_template_parameters_and_size() {
+    let gain = catalog_block("Gain");
+    assert_eq!(gain.properties.get("Gain").map(String::as_str), Some("1"));
+    assert_eq!(gain.position.as_deref(), Some("[100, 50, 130, 82]"));
+    assert_eq!(
+        gain.properties.get("Position").map(String::as_str),
+        Some("[100, 50, 130, 82]")
+    );
+
+    let sum = catalog_block("Sum");
+    assert_eq!(sum.properties.get("Inputs").map(String::as_str), Some("++"));
+    let constant = catalog_block("Constant");
+    assert_eq!(constant.value.as_deref(), Some("1"));
+
+    // Types without a template keep the plain default block.
+    assert!(block_template("Abs").is_none());
+    let abs = catalog_block("Abs");
+    assert_eq!(abs.position.as_deref(), Some("[100, 50, 130, 80]"));
+}
+
+#[test]
+fn template_parameters_are_written_before_port_properties() {
+    let mut system = System {
+        properties: Default::default(),
+        blocks: Vec::new(),
+        lines: Vec::new(),
+        annotations: Vec::new(),
+        chart: None,
+    };
+    add_block(&mut system, catalog_block("Saturation"));
+    let block = &system.blocks[0];
+    let param_at = |name: &str| {
+        block
+            .child_order
+            .iter()
+            .position(|k| *k == BlockChildKind::P(name.to_string()))
+            .unwrap()
+    };
+    let ports_at = block
+        .child_order
+        .iter()
+        .position(|k| *k == BlockChildKind::PortProperties)
+        .unwrap();
+    assert!(param_at("UpperLimit") < param_at("LowerLimit"));
+    assert!(param_at("LowerLimit") < ports_at);
+
+    let xml = generate_system_xml(&system);
+    assert!(xml.contains(r#"<P Name="UpperLimit">0.5</P>"#), "{xml}");
+    assert!(xml.contains(r#"<P Name="LowerLimit">-0.5</P>"#), "{xml}");
+}
+
+#[test]
+fn entry_parameters_override_the_template() {
+    let mut entry = get_block_catalog()
+        .iter()
+        .find(|e| e.block_type == "Gain")
+        .unwrap()
+        .clone();
+    entry.display_name = "Half".to_string();
+    entry
+        .parameters
+        .insert("Gain".to_string(), "0.5".to_string());
+    entry
+        .parameters
+        .insert("SaturateOnIntegerOverflow".to_string(), "on".to_string());
+    let block = create_catalog_block(&entry, 0, 0);
+    assert_eq!(block.name, "Half");
+    assert_eq!(
+        block.properties.get("Gain").map(String::as_str),
+        Some("0.5")
+    );
+    assert_eq!(
+        block
+            .properties
+            .get("SaturateOnIntegerOverflow")
+            .map(String::as_str),
+        Some("on")
+    );
+}
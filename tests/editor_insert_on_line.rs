@@ -0,0 +1,97 @@
+#![cfg(feature = "egui")]
+
+mod common;
+
+use common::parse;
+use rustylink::editor::block_catalog::get_block_catalog;
+use rustylink::editor::operations::{EditorHistory, insert_block_on_line};
+use rustylink::model::System;
+
+fn model() -> System {
+    parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="In" SID="1">
+    <PortCounts out="1"/>
+    <P Name="Position">[0, 93, 30, 107]</P>
+  </Block>
+  <Block BlockType="Outport" Name="Out" SID="7">
+    <PortCounts in="1"/>
+    <P Name="Position">[300, 193, 330, 207]</P>
+  </Block>
+  <Line>
+    <P Name="Name">speed</P>
+    <P Name="Src">1#out:1</P>
+    <P Name="Points">[100, 0; 0, 100]</P>
+    <P Name="Dst">7#in:1</P>
+  </Line>
+</System>"#,
+    )
+}
+
+fn entry(block_type: &str) -> &'static rustylink::editor::BlockCatalogEntry {
+    get_block_catalog()
+        .iter()
+        .find(|e| e.block_type == block_type)
+        .unwrap()
+}
+
+#[test]
+fn inserting_splits_the_line_through_the_block() {
+    let mut system = model();
+    let mut history = EditorHistory::new(10);
+    history.push(insert_block_on_line(&mut system, 0, entry("Gain")).unwrap());
+
+    assert_eq!(system.blocks.len(), 3);
+    let gain = &system.blocks[2];
+    assert_eq!(gain.sid.as_deref(), Some("8"));
+    assert_eq!(gain.properties.get("Gain").map(String::as_str), Some("1"));
+    // Centered on the middle of the 370-long path, at (130, 185).
+    assert_eq!(gain.position.as_deref(), Some("[115, 169, 145, 201]"));
+
+    assert_eq!(system.lines.len(), 2);
+    let first = &system.lines[0];
+    assert_eq!(first.name.as_deref(), Some("speed"));
+    assert_eq!(first.dst.as_ref().unwrap().sid, "8");
+    assert_eq!(
+        first.properties.get("Dst").map(String::as_str),
+        Some("8#in:1")
+    );
+    let keys: Vec<_> = first.properties.keys().map(String::as_str).collect();
+    assert_eq!(keys, ["Name", "Src", "Points", "Dst"]);
+    let second = &system.lines[1];
+    assert_eq!(
+        second.properties.get("Src").map(String::as_str),
+        Some("8#out:1")
+    );
+    assert_eq!(
+        second.properties.get("Dst").map(String::as_str),
+        Some("7#in:1")
+    );
+    // Each segment runs level into its destination port after its last bend.
+    let end = |x: i32, y: i32, line: &rustylink::model::Line| {
+        line.points
+            .iter()
+            .fold((x, y), |(x, y), p| (x + p.x, y + p.y))
+    };
+    assert_eq!(end(30, 100, first).1, 185);
+    assert_eq!(end(145, 185, second).1, 200);
+
+    assert!(history.undo(&mut system));
+    assert_eq!(system.blocks.len(), 2);
+    assert_eq!(system.lines.len(), 1);
+    assert_eq!(
+        system.lines[0].properties.get("Points").map(String::as_str),
+        Some("[100, 0; 0, 100]")
+    );
+    assert!(history.redo(&mut system));
+    assert_eq!(system.lines.len(), 2);
+}
+
+#[test]
+fn blocks_without_ports_and_unconnected_lines_are_rejected() {
+    let mut system = model();
+    assert!(insert_block_on_line(&mut system, 0, entry("Constant")).is_err());
+    system.lines[0].dst = None;
+    assert!(insert_block_on_line(&mut system, 0, entry("Gain")).is_err());
+    assert!(insert_block_on_line(&mut system, 3, entry("Gain")).is_err());
+}
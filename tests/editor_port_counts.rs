@@ -0,0 +1,150 @@
+#![cfg(feature = "egui")]
+
+mod common;
+
+use common::parse;
+use rustylink::editor::operations::{
+    EditorHistory, insert_port, order_ports, remove_port, set_port_count,
+};
+use rustylink::model::System;
+use rustylink::transform::PortOrder;
+
+fn model() -> System {
+    parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="A" SID="1"/>
+  <Block BlockType="Inport" Name="B" SID="2"/>
+  <Block BlockType="Inport" Name="C" SID="3"/>
+  <Block BlockType="Sum" Name="Sum" SID="4">
+    <PortCounts in="3" out="1"/>
+    <P Name="Inputs">|+-+</P>
+  </Block>
+  <Block BlockType="Outport" Name="Out" SID="5"/>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">4#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">2#out:1</P>
+    <P Name="Dst">4#in:2</P>
+  </Line>
+  <Line>
+    <P Name="Src">3#out:1</P>
+    <Branch>
+      <P Name="Dst">4#in:3</P>
+    </Branch>
+    <Branch>
+      <P Name="Dst">5#in:1</P>
+    </Branch>
+  </Line>
+</System>"#,
+    )
+}
+
+fn sum_inputs(system: &System) -> (Option<u32>, &str) {
+    let sum = &system.blocks[3];
+    (
+        sum.port_counts.as_ref().and_then(|c| c.ins),
+        sum.properties.get("Inputs").unwrap(),
+    )
+}
+
+#[test]
+fn removing_a_port_shifts_later_lines_and_signs() {
+    let mut system = model();
+    let mut history = EditorHistory::new(10);
+    history.push(remove_port(&mut system, 3, "in", 2).unwrap());
+
+    assert_eq!(sum_inputs(&system), (Some(2), "|++"));
+    // The line on the removed port is gone; port 3 moved to port 2.
+    assert_eq!(system.lines.len(), 2);
+    let branch = &system.lines[1].branches[0];
+    assert_eq!(branch.dst.as_ref().unwrap().port_index, 2);
+    assert_eq!(
+        branch.properties.get("Dst").map(String::as_str),
+        Some("4#in:2")
+    );
+
+    assert!(history.undo(&mut system));
+    assert_eq!(sum_inputs(&system), (Some(3), "|+-+"));
+    assert_eq!(system.lines.len(), 3);
+    assert_eq!(
+        system.lines[1].properties.get("Dst").map(String::as_str),
+        Some("4#in:2")
+    );
+    assert_eq!(
+        system.lines[2].branches[0].dst.as_ref().unwrap().port_index,
+        3
+    );
+
+    assert!(history.redo(&mut system));
+    assert_eq!(system.lines.len(), 2);
+    assert_eq!(sum_inputs(&system), (Some(2), "|++"));
+}
+
+#[test]
+fn shrinking_drops_only_the_branch_on_the_removed_port() {
+    let mut system = model();
+    set_port_count(&mut system, 3, "in", 2).unwrap();
+
+    assert_eq!(sum_inputs(&system), (Some(2), "|+-"));
+    assert_eq!(system.lines.len(), 3);
+    let branches = &system.lines[2].branches;
+    assert_eq!(branches.len(), 1);
+    assert_eq!(branches[0].dst.as_ref().unwrap().sid, "5");
+}
+
+#[test]
+fn inserting_and_growing_add_ports() {
+    let mut system = model();
+    insert_port(&mut system, 3, "in", 1).unwrap();
+    assert_eq!(sum_inputs(&system), (Some(4), "|++-+"));
+    assert_eq!(system.lines[0].dst.as_ref().unwrap().port_index, 2);
+    assert_eq!(
+        system.lines[1].properties.get("Dst").map(String::as_str),
+        Some("4#in:3")
+    );
+
+    set_port_count(&mut system, 3, "in", 5).unwrap();
+    assert_eq!(sum_inputs(&system), (Some(5), "|++-++"));
+
+    assert!(remove_port(&mut system, 3, "in", 6).is_err());
+    assert!(set_port_count(&mut system, 0, "enable", 1).is_err());
+}
+
+#[test]
+fn ordering_subsystem_ports_is_undoable() {
+    let mut system = parse(
+        r#"<System>
+  <Block BlockType="SubSystem" Name="S" SID="1">
+    <PortCounts in="2"/>
+    <System>
+      <Block BlockType="Inport" Name="a" SID="2">
+        <P Name="Position">[20, 100, 50, 114]</P>
+      </Block>
+      <Block BlockType="Inport" Name="b" SID="3">
+        <P Name="Position">[20, 20, 50, 34]</P>
+        <P Name="Port">2</P>
+      </Block>
+    </System>
+  </Block>
+  <Block BlockType="Constant" Name="c" SID="4"/>
+  <Line>
+    <P Name="Src">4#out:1</P>
+    <P Name="Dst">1#in:2</P>
+  </Line>
+</System>"#,
+    );
+    let mut history = EditorHistory::new(10);
+    history.push(order_ports(&mut system, 0, PortOrder::ByPosition).unwrap());
+    let dst = |system: &System| system.lines[0].dst.as_ref().unwrap().port_index;
+    let port = |system: &System| {
+        let inner = system.blocks[0].subsystem.as_deref().unwrap();
+        inner.blocks[1].properties.get("Port").cloned()
+    };
+    assert_eq!((dst(&system), port(&system).as_deref()), (1, Some("1")));
+
+    assert!(history.undo(&mut system));
+    assert_eq!((dst(&system), port(&system).as_deref()), (2, Some("2")));
+    assert!(order_ports(&mut system, 2, PortOrder::ByPosition).is_err());
+}
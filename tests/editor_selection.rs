@@ -1,7 +1,7 @@
 use indexmap::IndexMap;
 use rustylink::editor::operations::create_default_block;
 use rustylink::editor::selection::{EditorSelection, SelectionRect};
-use rustylink::model::{EndpointRef, Line, Point, System};
+use rustylink::model::{EndpointRef, Line, LineId, Point, PortKind, System};
 
 fn make_test_system() -> System {
     let mut sys = System {
@@ -30,12 +30,12 @@ fn make_test_system() -> System {
         zorder: None,
         src: Some(EndpointRef {
             sid: "1".to_string(),
-            port_type: "out".to_string(),
+            port_type: PortKind::Out,
             port_index: 1,
         }),
         dst: Some(EndpointRef {
             sid: "2".to_string(),
-            port_type: "in".to_string(),
+            port_type: PortKind::In,
             port_index: 1,
         }),
         points: vec![Point { x: 130, y: 115 }, Point { x: 200, y: 115 }],
@@ -179,3 +179,30 @@ fn test_selection_width_height() {
     assert_eq!(rect.width(), 100.0);
     assert_eq!(rect.height(), 60.0);
 }
+
+#[test]
+fn line_ids_survive_removal_of_other_lines() {
+    let mut sys = make_test_system();
+    let mut second = sys.lines[0].clone();
+    second.src.as_mut().unwrap().sid = "2".to_string();
+    second.dst.as_mut().unwrap().sid = "3".to_string();
+    sys.lines.push(second);
+
+    let id = sys.lines[1].id().unwrap();
+    assert_eq!(id, LineId::from("2#out:1"));
+    assert_eq!(sys.line_index(&id), Some(1));
+
+    let mut sel = EditorSelection::new();
+    sel.select_line(1);
+    let ids = sel.selected_line_ids(&sys);
+    sys.lines.remove(0);
+    sel.reselect_lines(&sys, &ids);
+    assert_eq!(sel.selected_lines, [0]);
+    assert_eq!(sys.line(&id).and_then(|l| l.dst.as_ref()).unwrap().sid, "3");
+
+    // Lines without a source are named by their destination
+    sys.lines[0].src = None;
+    assert_eq!(sys.lines[0].id().unwrap().as_str(), ">3#in:1");
+    sys.lines[0].dst = None;
+    assert!(sys.lines[0].id().is_none());
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "egui")]
+
+mod common;
+
+use common::parse;
+use rustylink::editor::{EditorState, SignalValues};
+use rustylink::model::System;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+fn model() -> System {
+    parse(
+        r#"<System>
+  <Block BlockType="Constant" Name="C" SID="1">
+    <P Name="Value">2</P>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="2">
+    <P Name="Gain">3</P>
+  </Block>
+  <Block BlockType="Display" Name="D" SID="3"/>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">2#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">2#out:1</P>
+    <P Name="Dst">3#in:1</P>
+  </Line>
+</System>"#,
+    )
+}
+
+fn settle(state: &mut EditorState) {
+    let start = Instant::now();
+    while state.simulation.poll() {
+        assert!(start.elapsed() < Duration::from_secs(10));
+        std::thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[test]
+fn runs_go_through_the_host_runner() {
+    let mut state = EditorState::new(model(), vec![], BTreeMap::new(), BTreeMap::new());
+    assert!(!state.simulation.is_available());
+    state.simulation.run(&state.app.root);
+    assert!(!state.simulation.is_running());
+
+    state.set_simulation_runner(|system: &System, stop_time: f64| {
+        anyhow::ensure!(stop_time > 0.0, "stop time must be positive");
+        let mut values = SignalValues::new();
+        values.insert(("1".to_string(), 1), 2.0);
+        values.insert(("2".to_string(), 1), 2.0 * system.blocks.len() as f64);
+        Ok(values)
+    });
+    assert!(state.simulation.is_available());
+    state.simulation.run(&state.app.root);
+    settle(&mut state);
+    assert_eq!(state.simulation.error, None);
+    let lines = state.app.root.lines.clone();
+    assert_eq!(state.simulation.input_value(&lines, "3", 1), Some(6.0));
+    assert_eq!(
+        state.simulation.value(lines[0].src.as_ref().unwrap()),
+        Some(2.0)
+    );
+    assert_eq!(state.simulation.input_value(&lines, "3", 2), None);
+
+    // A failed run keeps the previous values.
+    state.simulation.stop_time = 0.0;
+    state.simulation.run(&state.app.root);
+    settle(&mut state);
+    assert_eq!(
+        state.simulation.error.as_deref(),
+        Some("stop time must be positive")
+    );
+    assert_eq!(state.simulation.input_value(&lines, "3", 1), Some(6.0));
+}
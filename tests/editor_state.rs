@@ -2,7 +2,7 @@ use indexmap::IndexMap;
 use rustylink::editor::state::{
     BlockBrowserState, CodeEditorState, DragMode, EditorState, resolve_subsystem_by_vec_mut,
 };
-use rustylink::model::System;
+use rustylink::model::{CommentMode, System};
 use std::collections::BTreeMap;
 
 fn make_empty_system() -> System {
@@ -202,6 +202,39 @@ fn test_comment_selection() {
     assert!(state.current_system().unwrap().blocks[0].commented);
 }
 
+#[test]
+fn test_comment_through_selection() {
+    let mut sys = make_empty_system();
+    let block =
+        rustylink::editor::operations::create_default_block("Gain", "Gain1", 100, 100, 1, 1);
+    sys.blocks.push(block);
+    let mut state = EditorState::new(sys, vec![], BTreeMap::new(), BTreeMap::new());
+    let block = |state: &EditorState| state.current_system().unwrap().blocks[0].clone();
+
+    state.selection.select_block(0);
+    state.comment_selection();
+    state.comment_through_selection();
+    assert_eq!(block(&state).comment_mode(), CommentMode::Through);
+    assert_eq!(
+        block(&state)
+            .properties
+            .get("Commented")
+            .map(String::as_str),
+        Some("through")
+    );
+
+    state.comment_selection();
+    assert_eq!(block(&state).comment_mode(), CommentMode::Off);
+    assert!(!block(&state).properties.contains_key("Commented"));
+
+    state.undo();
+    assert_eq!(block(&state).comment_mode(), CommentMode::Through);
+    state.undo();
+    assert_eq!(block(&state).comment_mode(), CommentMode::Out);
+    state.redo();
+    assert!(block(&state).comment_through);
+}
+
 #[test]
 fn test_copy_paste() {
     let mut sys = make_empty_system();
@@ -221,3 +254,29 @@ fn test_copy_paste() {
     assert_eq!(pasted.name, "Gain1_copy");
     assert!(pasted.sid.is_none());
 }
+
+#[test]
+fn test_rotate_selection_writes_block_rotation() {
+    use rustylink::model::BlockOrientation;
+
+    let mut sys = make_empty_system();
+    sys.blocks
+        .push(rustylink::editor::operations::create_default_block(
+            "Gain", "Gain1", 100, 100, 1, 1,
+        ));
+    let mut state = EditorState::new(sys, vec![], BTreeMap::new(), BTreeMap::new());
+
+    state.selection.select_block(0);
+    state.rotate_selection();
+    let block = &state.current_system().unwrap().blocks[0];
+    assert_eq!(block.orientation, BlockOrientation::Down);
+    assert_eq!(
+        block.properties.get("BlockRotation").map(String::as_str),
+        Some("90")
+    );
+
+    state.undo();
+    let block = &state.current_system().unwrap().blocks[0];
+    assert_eq!(block.orientation, BlockOrientation::Right);
+    assert!(!block.properties.contains_key("BlockRotation"));
+}
@@ -4,7 +4,7 @@ use rustylink::editor::{
     compute_line_colors, contrast_color, get_block_code, hash_color, is_code_block,
     is_subsystem_block, set_block_code,
 };
-use rustylink::model::{Block, NameLocation, ValueKind};
+use rustylink::model::{Block, BlockOrientation, NameLocation, PropertyBag, ValueKind};
 use std::collections::HashMap;
 
 #[test]
@@ -45,12 +45,13 @@ fn test_is_code_block() {
         position: None,
         zorder: None,
         commented: false,
+        comment_through: false,
         name_location: NameLocation::Bottom,
         value: None,
         value_kind: ValueKind::Unknown,
         value_rows: None,
         value_cols: None,
-        properties: IndexMap::new(),
+        properties: PropertyBag::new(),
         ref_properties: std::collections::BTreeSet::new(),
         system_ref: None,
         mask: None,
@@ -60,6 +61,7 @@ fn test_is_code_block() {
         annotations: Vec::new(),
         child_order: Vec::new(),
         block_mirror: None,
+        orientation: BlockOrientation::Right,
         background_color: None,
         instance_data: None,
         c_function: None,
@@ -67,11 +69,16 @@ fn test_is_code_block() {
         show_name: None,
         font_size: None,
         font_weight: None,
+        foreground_color: None,
+        drop_shadow: false,
+        font_name: None,
         mask_display_text: None,
         current_setting: None,
         library_source: None,
         library_block_path: None,
+        library_overrides: None,
         dashboard_binding: None,
+        workspace_values: Default::default(),
     };
     assert!(is_code_block(&block));
     block.is_matlab_function = false;
@@ -91,12 +98,13 @@ fn test_is_subsystem_block() {
         position: None,
         zorder: None,
         commented: false,
+        comment_through: false,
         name_location: NameLocation::Bottom,
         value: None,
         value_kind: ValueKind::Unknown,
         value_rows: None,
         value_cols: None,
-        properties: IndexMap::new(),
+        properties: PropertyBag::new(),
         ref_properties: std::collections::BTreeSet::new(),
         system_ref: None,
         mask: None,
@@ -112,6 +120,7 @@ fn test_is_subsystem_block() {
         annotations: Vec::new(),
         child_order: Vec::new(),
         block_mirror: None,
+        orientation: BlockOrientation::Right,
         background_color: None,
         instance_data: None,
         c_function: None,
@@ -119,11 +128,16 @@ fn test_is_subsystem_block() {
         show_name: None,
         font_size: None,
         font_weight: None,
+        foreground_color: None,
+        drop_shadow: false,
+        font_name: None,
         mask_display_text: None,
         current_setting: None,
         library_source: None,
         library_block_path: None,
+        library_overrides: None,
         dashboard_binding: None,
+        workspace_values: Default::default(),
     };
     assert!(is_subsystem_block(&block));
     block.subsystem = None;
@@ -161,12 +175,12 @@ fn test_compute_line_colors_single() {
         zorder: None,
         src: Some(rustylink::model::EndpointRef {
             sid: "1".to_string(),
-            port_type: "out".to_string(),
+            port_type: rustylink::model::PortKind::Out,
             port_index: 1,
         }),
         dst: Some(rustylink::model::EndpointRef {
             sid: "2".to_string(),
-            port_type: "in".to_string(),
+            port_type: rustylink::model::PortKind::In,
             port_index: 1,
         }),
         points: Vec::new(),
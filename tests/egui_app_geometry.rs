@@ -1,6 +1,10 @@
 #![cfg(feature = "egui")]
 
-use rustylink::egui_app::{PortSide, parse_block_rect, port_anchor_pos, port_indicator_positions};
+use eframe::egui::{Pos2, Rect};
+use rustylink::egui_app::{
+    PortFrame, PortSide, endpoint_pos_in_frame, parse_block_rect, port_anchor_pos,
+    port_indicator_positions, port_indicator_positions_in_frame,
+};
 use rustylink::model::Block;
 
 #[test]
@@ -13,6 +17,7 @@ fn test_ports_and_rect() {
         position: Some("[10, 20, 50, 60]".into()),
         zorder: None,
         commented: false,
+        comment_through: false,
         name_location: rustylink::model::NameLocation::Bottom,
         is_matlab_function: false,
         properties: Default::default(),
@@ -30,6 +35,9 @@ fn test_ports_and_rect() {
         show_name: None,
         font_size: None,
         font_weight: None,
+        foreground_color: None,
+        drop_shadow: false,
+        font_name: None,
         mask_display_text: None,
         value: None,
         value_kind: rustylink::model::ValueKind::Unknown,
@@ -37,9 +45,12 @@ fn test_ports_and_rect() {
         value_cols: None,
         current_setting: None,
         block_mirror: None,
+        orientation: rustylink::model::BlockOrientation::Right,
         library_source: None,
         library_block_path: None,
+        library_overrides: None,
         dashboard_binding: None,
+        workspace_values: Default::default(),
         child_order: vec![],
     };
     let r = parse_block_rect(&b).unwrap();
@@ -58,6 +69,7 @@ fn test_port_indicator_positions_count_and_sides() {
         position: Some("[10, 20, 50, 60]".into()),
         zorder: None,
         commented: false,
+        comment_through: false,
         name_location: rustylink::model::NameLocation::Bottom,
         is_matlab_function: false,
         properties: Default::default(),
@@ -75,6 +87,9 @@ fn test_port_indicator_positions_count_and_sides() {
         show_name: None,
         font_size: None,
         font_weight: None,
+        foreground_color: None,
+        drop_shadow: false,
+        font_name: None,
         mask_display_text: None,
         value: None,
         value_kind: rustylink::model::ValueKind::Unknown,
@@ -82,9 +97,12 @@ fn test_port_indicator_positions_count_and_sides() {
         value_cols: None,
         current_setting: None,
         block_mirror: None,
+        orientation: rustylink::model::BlockOrientation::Right,
         library_source: None,
         library_block_path: None,
+        library_overrides: None,
         dashboard_binding: None,
+        workspace_values: Default::default(),
         child_order: vec![],
     };
     let r = parse_block_rect(&b).unwrap();
@@ -99,3 +117,327 @@ fn test_port_indicator_positions_count_and_sides() {
     assert!(ins_m.iter().all(|p| (p.x - r.right()).abs() < 1e-6));
     assert!(outs_m.iter().all(|p| (p.x - r.left()).abs() < 1e-6));
 }
+
+#[test]
+fn test_port_positions_follow_block_rotation() {
+    use rustylink::model::{BlockOrientation, EndpointRef, PortKind};
+
+    let r = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(40.0, 20.0));
+    let down = PortFrame {
+        mirrored: false,
+        orientation: BlockOrientation::Down,
+    };
+    let (ins, outs) = port_indicator_positions_in_frame(r, 2, 1, down, &[]);
+    assert!(ins.iter().all(|p| p.y.abs() < 1e-6));
+    assert!(ins[0].x < ins[1].x);
+    assert!((outs[0].y - r.bottom()).abs() < 1e-6);
+
+    let up_mirrored = PortFrame {
+        mirrored: true,
+        orientation: BlockOrientation::Up,
+    };
+    let ep = EndpointRef {
+        sid: "1".to_string(),
+        port_type: PortKind::In,
+        port_index: 1,
+    };
+    let p = endpoint_pos_in_frame(r, &ep, Some(1), up_mirrored);
+    assert_eq!(p, Pos2::new(20.0, 0.0));
+
+    let left = PortFrame {
+        mirrored: false,
+        orientation: BlockOrientation::Left,
+    };
+    assert!(left.flips_sides());
+    let (ins, _) = port_indicator_positions_in_frame(r, 1, 1, left, &[]);
+    assert!((ins[0].x - r.right()).abs() < 1e-6);
+}
+
+#[test]
+fn test_block_rotation_and_orientation_are_parsed() {
+    use rustylink::model::BlockOrientation;
+
+    let xml = r#"<System>
+  <Block BlockType="Gain" Name="A" SID="1">
+    <P Name="BlockRotation">270</P>
+  </Block>
+  <Block BlockType="Gain" Name="B" SID="2">
+    <P Name="Orientation">left</P>
+  </Block>
+  <Block BlockType="Gain" Name="C" SID="3"/>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let system =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    assert_eq!(system.blocks[0].orientation, BlockOrientation::Up);
+    assert_eq!(system.blocks[1].orientation, BlockOrientation::Left);
+    assert_eq!(system.blocks[2].orientation, BlockOrientation::Right);
+}
+
+#[test]
+fn test_special_ports_anchor_on_top_and_bottom_edges() {
+    use rustylink::model::{BlockOrientation, EndpointRef, PortKind};
+
+    let r = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(40.0, 20.0));
+    let ep = |port_type: PortKind| EndpointRef {
+        sid: "1".to_string(),
+        port_type,
+        port_index: 1,
+    };
+    let right = PortFrame::default();
+    for kind in [PortKind::Trigger, PortKind::Enable, PortKind::IfAction] {
+        assert_eq!(
+            endpoint_pos_in_frame(r, &ep(kind), Some(3), right),
+            Pos2::new(20.0, 0.0)
+        );
+    }
+    assert_eq!(
+        endpoint_pos_in_frame(r, &ep(PortKind::State), None, right),
+        Pos2::new(20.0, 20.0)
+    );
+
+    // Mirroring keeps the top edge; rotation turns it with the block
+    let mirrored = PortFrame {
+        mirrored: true,
+        orientation: BlockOrientation::Right,
+    };
+    assert_eq!(
+        endpoint_pos_in_frame(r, &ep(PortKind::Trigger), None, mirrored),
+        Pos2::new(20.0, 0.0)
+    );
+    let down = PortFrame {
+        mirrored: false,
+        orientation: BlockOrientation::Down,
+    };
+    assert_eq!(
+        endpoint_pos_in_frame(r, &ep(PortKind::Trigger), None, down),
+        Pos2::new(40.0, 10.0)
+    );
+}
+
+fn parse_blocks(xml: &str) -> Vec<Block> {
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+        .unwrap()
+        .blocks
+}
+
+fn assert_near(a: Pos2, b: Pos2) {
+    assert!((a - b).length() < 1e-3, "{a:?} != {b:?}");
+}
+
+#[test]
+fn test_anchor_strategies_follow_block_shapes() {
+    use rustylink::block_types::BlockTypeConfig;
+    use rustylink::builtin_libraries::virtual_library::BlockShape;
+    use rustylink::egui_app::{AnchorStrategy, PortAnchors};
+    use rustylink::model::{EndpointRef, PortKind};
+
+    let blocks = parse_blocks(
+        r#"<System>
+  <Block BlockType="Sum" Name="Round" SID="1"><P Name="Inputs">|+-</P></Block>
+  <Block BlockType="Sum" Name="Three" SID="2"><P Name="Inputs">+-+</P></Block>
+  <Block BlockType="Sum" Name="Rect" SID="3">
+    <P Name="IconShape">rectangular</P><P Name="Inputs">++</P>
+  </Block>
+  <Block BlockType="Sum" Name="Down" SID="4">
+    <P Name="Inputs">|+-</P><P Name="BlockRotation">90</P>
+  </Block>
+  <Block BlockType="Mux" Name="Mux" SID="5"/>
+</System>"#,
+    );
+    let circle = BlockTypeConfig {
+        shape: BlockShape::Circle,
+        ..Default::default()
+    };
+    let r = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(20.0, 20.0));
+
+    // `|+-`: empty top slot, inputs on the left and at the bottom
+    let round = PortAnchors::new(&blocks[0], &circle);
+    assert_eq!(
+        round.strategy,
+        AnchorStrategy::Round {
+            slots: vec![false, true, true]
+        }
+    );
+    let (ins, outs) = round.indicator_positions(r, 2, 1);
+    assert_near(ins[0], Pos2::new(0.0, 10.0));
+    assert_near(ins[1], Pos2::new(10.0, 20.0));
+    assert_near(outs[0], Pos2::new(20.0, 10.0));
+    let ep = EndpointRef {
+        sid: "1".to_string(),
+        port_type: PortKind::In,
+        port_index: 2,
+    };
+    assert_near(round.endpoint_pos(r, &ep, Some(2)), Pos2::new(10.0, 20.0));
+
+    // Three signs fill top, left and bottom
+    let (ins, _) = PortAnchors::new(&blocks[1], &circle).indicator_positions(r, 3, 1);
+    assert_near(ins[0], Pos2::new(10.0, 0.0));
+    assert_near(ins[1], Pos2::new(0.0, 10.0));
+    assert_near(ins[2], Pos2::new(10.0, 20.0));
+
+    // Rectangular Sums keep the edge layout
+    let rect = PortAnchors::new(&blocks[2], &circle);
+    assert_eq!(rect.strategy, AnchorStrategy::Edges);
+    let (ins, _) = rect.indicator_positions(r, 2, 1);
+    assert!(ins.iter().all(|p| p.x.abs() < 1e-6));
+
+    // Rotation turns the outline: inputs on top and to the right
+    let (ins, outs) = PortAnchors::new(&blocks[3], &circle).indicator_positions(r, 2, 1);
+    assert_near(ins[0], Pos2::new(10.0, 0.0));
+    assert_near(ins[1], Pos2::new(0.0, 10.0));
+    assert_near(outs[0], Pos2::new(10.0, 20.0));
+
+    // Bars split their edge evenly
+    let bar = PortAnchors::of(&blocks[4]);
+    assert_eq!(bar.strategy, AnchorStrategy::Bar);
+    let tall = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(5.0, 40.0));
+    let (ins, _) = bar.indicator_positions(tall, 2, 1);
+    assert_near(ins[0], Pos2::new(0.0, 10.0));
+    assert_near(ins[1], Pos2::new(0.0, 30.0));
+}
+
+#[test]
+fn test_block_outlines_follow_orientation_and_port_counts() {
+    use rustylink::egui_app::{block_outline, get_block_type_cfg};
+
+    let blocks = parse_blocks(
+        r#"<System>
+  <Block BlockType="Gain" Name="Right" SID="1"/>
+  <Block BlockType="Gain" Name="Down" SID="2"><P Name="BlockRotation">90</P></Block>
+  <Block BlockType="Mux" Name="Mux" SID="3"><PortCounts in="3" out="1"/></Block>
+  <Block BlockType="Demux" Name="Demux" SID="4"><PortCounts in="1" out="2"/></Block>
+  <Block BlockType="Inport" Name="In" SID="5"/>
+</System>"#,
+    );
+    let r = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(40.0, 20.0));
+    let outline = |b: &Block| block_outline(b, &get_block_type_cfg(b), r).unwrap();
+
+    let tri = outline(&blocks[0]);
+    assert_eq!(tri.len(), 3);
+    assert_near(tri[2], Pos2::new(40.0, 10.0));
+    let tri = outline(&blocks[1]);
+    assert_near(tri[2], Pos2::new(20.0, 20.0));
+
+    // Mux narrows towards its single output, Demux towards its input
+    let mux = outline(&blocks[2]);
+    assert_eq!(mux.len(), 4);
+    assert_near(mux[0], Pos2::new(0.0, 0.0));
+    assert_near(mux[2], Pos2::new(40.0, 15.0));
+    assert_near(mux[3], Pos2::new(40.0, 5.0));
+    let demux = outline(&blocks[3]);
+    assert_near(demux[0], Pos2::new(40.0, 0.0));
+    assert_near(demux[3], Pos2::new(0.0, 5.0));
+
+    assert!(block_outline(&blocks[4], &get_block_type_cfg(&blocks[4]), r).is_none());
+}
+
+#[test]
+fn test_sum_params_follow_the_sign_list() {
+    use rustylink::egui_app::{AnchorStrategy, PortAnchors};
+    use rustylink::model::SumSign::{Minus, Plus, Spacer};
+
+    let blocks = parse_blocks(
+        r#"<System>
+  <Block BlockType="Sum" Name="Round" SID="1"><P Name="Inputs">+|-+</P></Block>
+  <Block BlockType="Sum" Name="Count" SID="2"><P Name="Inputs">3</P></Block>
+  <Block BlockType="Sum" Name="Rect" SID="3">
+    <P Name="IconShape">rectangular</P><P Name="Inputs">2</P>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="4"/>
+</System>"#,
+    );
+    let round = blocks[0].sum_params().unwrap();
+    assert_eq!(round.signs, [Plus, Spacer, Minus, Plus]);
+    assert_eq!(round.port_signs(), [Plus, Minus, Plus]);
+    assert!(!round.rectangular);
+    assert_eq!(
+        blocks[1].sum_params().unwrap().signs,
+        [Spacer, Plus, Plus, Plus]
+    );
+    let rect = blocks[2].sum_params().unwrap();
+    assert!(rect.rectangular);
+    assert_eq!(rect.signs, [Plus, Plus]);
+    assert!(blocks[3].sum_params().is_none());
+
+    // Ports sit in the slots of their signs: top, (left empty), and two
+    // more down to the bottom
+    let anchors = PortAnchors::of(&blocks[0]);
+    assert_eq!(
+        anchors.strategy,
+        AnchorStrategy::Round {
+            slots: vec![true, false, true, true]
+        }
+    );
+    let r = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(20.0, 20.0));
+    let (ins, _) = anchors.indicator_positions(r, 3, 1);
+    assert_near(ins[0], Pos2::new(10.0, 0.0));
+    assert_near(ins[2], Pos2::new(10.0, 20.0));
+    assert!(ins[1].x < 10.0 && ins[1].y > 10.0);
+}
+
+#[test]
+fn test_control_ports_spread_over_the_top_edge() {
+    use rustylink::builtin_libraries::virtual_library::PortPlacement;
+    use rustylink::egui_app::{PortAnchors, control_port_symbol};
+    use rustylink::model::{EndpointRef, PortKind};
+
+    let mut blocks = parse_blocks(
+        r#"<System>
+  <Block BlockType="SubSystem" Name="Both" SID="1">
+    <PortCounts in="1" out="1" enable="1" trigger="1"/>
+  </Block>
+  <Block BlockType="SubSystem" Name="Action" SID="2"/>
+</System>"#,
+    );
+    let doc = roxmltree::Document::parse(
+        r#"<System>
+  <Block BlockType="ActionPort" Name="Action" SID="3"/>
+  <Block BlockType="Inport" Name="In1" SID="4"/>
+</System>"#,
+    )
+    .unwrap();
+    let inner =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    blocks[1].subsystem = Some(Box::new(inner));
+    assert_eq!(
+        blocks[0].control_ports(),
+        [PortKind::Enable, PortKind::Trigger]
+    );
+    assert_eq!(blocks[1].control_ports(), [PortKind::IfAction]);
+
+    let r = Rect::from_min_max(Pos2::new(0.0, 0.0), Pos2::new(30.0, 20.0));
+    let anchors = PortAnchors::of(&blocks[0]);
+    let ep = |kind: PortKind| EndpointRef {
+        sid: "1".to_string(),
+        port_type: kind,
+        port_index: 1,
+    };
+    // Enable left of trigger instead of on top of each other
+    assert_near(
+        anchors.endpoint_pos(r, &ep(PortKind::Enable), None),
+        Pos2::new(9.0, 0.0),
+    );
+    assert_near(
+        anchors.endpoint_pos(r, &ep(PortKind::Trigger), None),
+        Pos2::new(21.0, 0.0),
+    );
+    let positions = anchors.control_positions(r);
+    assert_eq!(positions.len(), 2);
+
+    // Symbols sit inside the block, below their port
+    let (kind, at) = &positions[0];
+    let pulse = control_port_symbol(kind, *at, PortPlacement::Top, 10.0).unwrap();
+    assert_eq!(pulse.len(), 6);
+    assert!(pulse.iter().all(|p| p.y > 0.0 && (p.x - at.x).abs() <= 5.0));
+    assert!(control_port_symbol(&PortKind::IfAction, *at, PortPlacement::Top, 10.0).is_none());
+
+    // The Action subsystem's single port is centred
+    assert_near(
+        PortAnchors::of(&blocks[1]).control_positions(r)[0].1,
+        Pos2::new(15.0, 0.0),
+    );
+}
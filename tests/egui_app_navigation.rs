@@ -1,7 +1,7 @@
 #![cfg(feature = "egui")]
 
 use rustylink::egui_app::{
-    collect_subsystems_paths, resolve_subsystem_by_path, resolve_subsystem_by_vec,
+    child_subsystems, collect_subsystems_paths, resolve_subsystem_by_path, resolve_subsystem_by_vec,
 };
 use rustylink::model::{Block, System};
 
@@ -21,6 +21,7 @@ fn simple_system() -> System {
         position: Some("[100, 100, 160, 140]".into()),
         zorder: None,
         commented: false,
+        comment_through: false,
         name_location: rustylink::model::NameLocation::Bottom,
         is_matlab_function: false,
         properties: Default::default(),
@@ -38,6 +39,9 @@ fn simple_system() -> System {
         show_name: None,
         font_size: None,
         font_weight: None,
+        foreground_color: None,
+        drop_shadow: false,
+        font_name: None,
         mask_display_text: None,
         value: None,
         value_kind: rustylink::model::ValueKind::Unknown,
@@ -45,9 +49,12 @@ fn simple_system() -> System {
         value_cols: None,
         current_setting: None,
         block_mirror: None,
+        orientation: rustylink::model::BlockOrientation::Right,
         library_source: None,
         library_block_path: None,
+        library_overrides: None,
         dashboard_binding: None,
+        workspace_values: Default::default(),
         child_order: vec![],
     };
     System {
@@ -73,3 +80,33 @@ fn test_collect_paths() {
     let paths = collect_subsystems_paths(&root);
     assert_eq!(paths, vec![vec!["Child".to_string()]]);
 }
+
+#[test]
+fn child_subsystems_include_charts() {
+    let mut root = simple_system();
+    let mut chart = root.blocks[0].clone();
+    chart.name = "Chart".into();
+    chart.subsystem.as_mut().unwrap().chart = Some(rustylink::model::Chart {
+        id: Some(1),
+        name: None,
+        eml_name: None,
+        script: None,
+        inputs: vec![],
+        outputs: vec![],
+        properties: Default::default(),
+    });
+    root.blocks.push(chart);
+    let mut gain = root.blocks[0].clone();
+    gain.block_type = "Gain".into();
+    gain.subsystem = None;
+    root.blocks.push(gain);
+
+    let children: Vec<_> = child_subsystems(&root)
+        .map(|(b, sub)| (b.name.as_str(), sub.chart.is_some()))
+        .collect();
+    assert_eq!(children, [("Child", false), ("Chart", true)]);
+    assert_eq!(
+        collect_subsystems_paths(&root),
+        vec![vec!["Child".to_string()]]
+    );
+}
@@ -80,7 +80,7 @@ fn block_type_registry_contains_matrix_library_icons() {
     for b in rustylink::builtin_libraries::matrix_library::BLOCKS {
         if let Some(icon) = b.icon {
             assert_eq!(
-                r.get(b.name).and_then(|c| c.icon),
+                r.get(b.name).and_then(|c| c.icon.clone()),
                 Some(IconSpec::Svg(icon)),
                 "registry entry for {}",
                 b.name,
@@ -372,3 +372,27 @@ fn icon_lookup_matrix_square_newline_in_source_block() {
     let cfg = rustylink::egui_app::get_block_type_cfg(&b);
     assert_eq!(cfg.icon, Some(IconSpec::Svg("matrix/matrix_square.svg")));
 }
+
+#[test]
+fn icon_dir_registers_user_svgs_by_relative_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let svg = br#"<svg xmlns="http://www.w3.org/2000/svg" width="4" height="4"/>"#;
+    std::fs::create_dir(dir.path().join("acme_lib")).unwrap();
+    std::fs::write(dir.path().join("acme_lib").join("Valve.svg"), svg).unwrap();
+    std::fs::write(dir.path().join("AcmeGainIconTest.svg"), svg).unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "not an icon").unwrap();
+
+    assert_eq!(rustylink::register_icon_dir(dir.path()).unwrap(), 2);
+
+    let mut blk =
+        rustylink::editor::operations::create_default_block("SubSystem", "Valve", 0, 0, 1, 1);
+    blk.library_block_path = Some("acme_lib/Valve".to_string());
+    let cfg = rustylink::egui_app::get_block_type_cfg(&blk);
+    assert_eq!(cfg.icon, Some(IconSpec::SvgBytes(svg.as_slice().into())));
+    assert!(cfg.known);
+
+    let blk =
+        rustylink::editor::operations::create_default_block("AcmeGainIconTest", "G", 0, 0, 1, 1);
+    let cfg = rustylink::egui_app::get_block_type_cfg(&blk);
+    assert!(matches!(cfg.icon, Some(IconSpec::SvgBytes(_))));
+}
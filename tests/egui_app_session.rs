@@ -0,0 +1,287 @@
+use eframe::egui::Vec2;
+use rustylink::block_path::BlockPath;
+use rustylink::egui_app::session::{
+    SEARCH_HISTORY_LEN, ViewerSession, push_search_history, session_key,
+};
+use rustylink::egui_app::{
+    Bookmark, ClickAction, SubsystemApp, UpdateResponse, apply_update_response,
+};
+use rustylink::model::LineId;
+use std::collections::HashMap;
+
+#[derive(Default)]
+struct MemoryStorage(HashMap<String, String>);
+
+impl eframe::Storage for MemoryStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), value);
+    }
+
+    fn flush(&mut self) {}
+}
+
+const MODEL: &str = r#"<System>
+  <Block BlockType="Gain" Name="Gain" SID="1"><P Name="Position">[0, 0, 30, 30]</P></Block>
+  <Block BlockType="SubSystem" Name="Sub" SID="2"><P Name="Position">[100, 0, 130, 30]</P>
+    <System>
+      <Block BlockType="Gain" Name="Inner" SID="3"><P Name="Position">[0, 0, 30, 30]</P></Block>
+      <Block BlockType="Gain" Name="Inner2" SID="4"><P Name="Position">[100, 0, 130, 30]</P></Block>
+      <Line><P Name="Src">3#out:1</P><P Name="Dst">4#in:1</P></Line>
+    </System>
+  </Block>
+</System>"#;
+
+fn app() -> SubsystemApp {
+    let doc = roxmltree::Document::parse(MODEL).unwrap();
+    let root =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    let mut app = SubsystemApp::new(root, Vec::new(), Default::default(), Default::default());
+    app.set_session_source("model.slx", MODEL.as_bytes());
+    app
+}
+
+/// Pretend a frame was rendered with the given view.
+fn show(app: &mut SubsystemApp, zoom: f32, pan: Vec2) {
+    app.zoom = zoom;
+    app.pan = pan;
+    app.reset_view = false;
+}
+
+#[test]
+fn session_keys_depend_on_path_and_contents() {
+    let a = session_key("a.slx", b"one");
+    assert_eq!(a, session_key("a.slx", b"one"));
+    assert_ne!(a, session_key("a.slx", b"two"));
+    assert_ne!(a, session_key("b.slx", b"one"));
+}
+
+#[test]
+fn search_history_is_deduplicated_and_bounded() {
+    let mut history = Vec::new();
+    push_search_history(&mut history, "gain");
+    push_search_history(&mut history, "  ");
+    push_search_history(&mut history, "sub");
+    push_search_history(&mut history, " gain ");
+    assert_eq!(history, ["gain", "sub"]);
+    for i in 0..30 {
+        push_search_history(&mut history, &i.to_string());
+    }
+    assert_eq!(history.len(), SEARCH_HISTORY_LEN);
+    assert_eq!(history[0], "29");
+}
+
+#[test]
+fn views_are_remembered_per_subsystem() {
+    let mut app = app();
+    show(&mut app, 2.0, Vec2::new(10.0, 20.0));
+    let sub = app.current_system().unwrap().blocks[1].clone();
+    assert!(app.open_block_if_subsystem(&sub));
+    assert!(app.reset_view, "first visit fits the view");
+
+    show(&mut app, 3.0, Vec2::new(-5.0, 0.0));
+    app.go_up();
+    assert!(!app.reset_view);
+    assert_eq!((app.zoom, app.pan), (2.0, Vec2::new(10.0, 20.0)));
+
+    app.navigate_to_path(vec!["Sub".to_string()]);
+    assert_eq!((app.zoom, app.pan), (3.0, Vec2::new(-5.0, 0.0)));
+}
+
+#[test]
+fn session_round_trips_through_storage() {
+    let mut app = app();
+    show(&mut app, 1.5, Vec2::new(4.0, 8.0));
+    app.navigate_to_path(vec!["Sub".to_string()]);
+    show(&mut app, 2.5, Vec2::new(1.0, 2.0));
+    app.record_search("inner");
+    app.block_view = Some(rustylink::egui_app::BlockDialog {
+        title: "Inner".into(),
+        block: app.current_system().unwrap().blocks[0].clone(),
+        open: true,
+    });
+    app.signal_view = Some(rustylink::egui_app::SignalDialog {
+        title: "<signal>".into(),
+        line_idx: 0,
+        open: true,
+    });
+
+    let mut storage = MemoryStorage::default();
+    eframe::App::save(&mut app, &mut storage);
+    assert_eq!(storage.0.len(), 1);
+
+    let mut restored = self::app();
+    assert!(restored.restore_session(&storage));
+    assert_eq!(restored.path, ["Sub"]);
+    assert_eq!((restored.zoom, restored.pan), (2.5, Vec2::new(1.0, 2.0)));
+    assert_eq!(restored.search_history, ["inner"]);
+    assert_eq!(
+        restored.block_view.as_ref().map(|d| d.block.name.as_str()),
+        Some("Inner")
+    );
+    assert_eq!(restored.signal_view.as_ref().map(|d| d.line_idx), Some(0));
+    restored.go_up();
+    assert_eq!((restored.zoom, restored.pan), (1.5, Vec2::new(4.0, 8.0)));
+
+    // A changed model gets a different key and starts fresh.
+    let mut edited = self::app();
+    edited.set_session_source("model.slx", b"changed");
+    assert!(!edited.restore_session(&storage));
+    assert!(edited.path.is_empty());
+}
+
+#[test]
+fn stale_session_parts_are_skipped() {
+    let mut app = app();
+    app.apply_session(ViewerSession {
+        version: 1,
+        path: vec!["Missing".to_string()],
+        open_block: Some("99".to_string()),
+        open_signal: Some(7),
+        ..Default::default()
+    });
+    assert!(app.path.is_empty());
+    assert!(app.block_view.is_none());
+    assert!(app.signal_view.is_none());
+    assert!(app.reset_view);
+
+    assert!(ViewerSession::from_json(r#"{"version":2,"path":[],"views":{},"open_block":null,"open_signal":null,"search_history":[]}"#).is_none());
+}
+
+#[test]
+fn bookmarks_jump_back_and_survive_model_edits() {
+    let mut app = app();
+    let sub: BlockPath = "Sub".parse().unwrap();
+    assert!(app.add_bookmark(Bookmark::block_at(&sub.child("Inner2")).unwrap()));
+    assert!(
+        !app.add_bookmark(
+            Bookmark::block_at(&sub.child("Inner2"))
+                .unwrap()
+                .with_label("again")
+        )
+    );
+    assert!(app.bookmark_current_subsystem());
+
+    // Opening a signal dialog records it as recent.
+    let sub_block = app.current_system().unwrap().blocks[1].clone();
+    app.open_block_if_subsystem(&sub_block);
+    let line = app.current_system().unwrap().lines[0].clone();
+    apply_update_response(
+        &mut app,
+        &UpdateResponse::Signal {
+            action: ClickAction::Secondary,
+            line_idx: 0,
+            line,
+            handled: false,
+        },
+    );
+    assert_eq!(app.recent_signals.len(), 1);
+    assert_eq!(app.recent_signals[0].label, "Inner:1");
+
+    assert!(app.go_to_bookmark(&app.bookmarks[1].clone()));
+    assert!(app.path.is_empty());
+    assert!(app.go_to_bookmark(&app.bookmarks[0].clone()));
+    assert_eq!(app.path, ["Sub"]);
+    assert_eq!(app.selected_block_sids.iter().collect::<Vec<_>>(), ["4"]);
+    assert!(app.go_to_bookmark(&app.recent_signals[0].clone()));
+    assert_eq!(app.selected_line_indices.iter().collect::<Vec<_>>(), [&0]);
+    assert!(!app.go_to_bookmark(&Bookmark::subsystem(vec!["Missing".to_string()])));
+
+    let mut storage = MemoryStorage::default();
+    eframe::App::save(&mut app, &mut storage);
+
+    // Bookmarks are keyed by path only; those set up by a host are kept.
+    let mut edited = self::app();
+    edited.set_session_source("model.slx", b"changed");
+    edited.add_bookmark(Bookmark::subsystem(vec!["Sub".to_string()]).with_label("From test"));
+    assert!(edited.restore_bookmarks(&storage));
+    let labels: Vec<_> = edited.bookmarks.iter().map(|b| b.label.as_str()).collect();
+    assert_eq!(labels, ["From test", "/Sub/Inner2", "Root"]);
+    assert_eq!(edited.recent_signals, app.recent_signals);
+    assert!(app.remove_bookmark(0).is_some());
+    assert_eq!(app.bookmarks.len(), 1);
+
+    let mut other = self::app();
+    other.set_session_source("other.slx", MODEL.as_bytes());
+    assert!(!other.restore_bookmarks(&storage));
+}
+
+#[test]
+fn annotation_links_open_subsystems_or_go_to_the_host() {
+    let mut app = app();
+    assert!(app.open_link("matlab:open_system('model/Sub')"));
+    assert_eq!(app.path, ["Sub"]);
+    assert!(app.open_link("open_system('model/Gain')"));
+    assert!(app.path.is_empty());
+    assert_eq!(app.selected_block_sids.iter().collect::<Vec<_>>(), ["1"]);
+
+    assert!(!app.open_link("open_system('model/Missing')"));
+    assert!(!app.open_link("myapp://run"));
+    app.set_link_handler(|app, link| {
+        app.search_query = link.to_string();
+        link.starts_with("myapp:")
+    });
+    assert!(app.open_link("myapp://run"));
+    assert_eq!(app.search_query, "myapp://run");
+    assert!(!app.open_link("https://example.com"));
+}
+
+#[test]
+fn events_are_collected_once_taken() {
+    use eframe::egui;
+    use rustylink::egui_app::ViewerEvent;
+
+    let mut app = app();
+    let ctx = egui::Context::default();
+    let frame = |app: &mut SubsystemApp| {
+        let input = egui::RawInput {
+            screen_rect: Some(egui::Rect::from_min_size(
+                egui::Pos2::ZERO,
+                Vec2::new(800.0, 600.0),
+            )),
+            ..Default::default()
+        };
+        let _ = ctx.run(input, |ctx| {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                rustylink::egui_app::update(app, ui);
+            });
+        });
+    };
+
+    app.navigate_to_path(vec!["Sub".to_string()]);
+    assert!(app.take_events().is_empty());
+
+    app.go_up();
+    // Reset by the next frame.
+    app.zoom = 2.0;
+    app.reset_view = true;
+    frame(&mut app);
+    let events = app.take_events();
+    assert!(matches!(
+        events.first(),
+        Some(ViewerEvent::SubsystemEntered { path }) if path.is_empty()
+    ));
+    assert!(
+        events
+            .iter()
+            .any(|e| matches!(e, ViewerEvent::ViewChanged { .. }))
+    );
+    assert!(app.take_events().is_empty());
+}
+
+#[test]
+fn lines_are_selected_and_opened_by_id() {
+    let mut app = app();
+    let id = LineId::from("3#out:1");
+    assert!(!app.select_line(&id));
+    app.navigate_to_path(vec!["Sub".to_string()]);
+    assert!(app.select_line(&id));
+    assert_eq!(app.selected_line_ids(), std::slice::from_ref(&id));
+    assert!(app.open_signal_dialog(&id));
+    assert_eq!(app.signal_view.as_ref().map(|d| d.line_idx), Some(0));
+    assert!(!app.open_signal_dialog(&LineId::from("4#out:1")));
+}
@@ -2,8 +2,9 @@
 
 use eframe::egui::Color32;
 
-use rustylink::egui_app::highlight_query_job;
+use rustylink::egui_app::text::code_theme_names;
 use rustylink::egui_app::text::{annotation_to_plain_text, annotation_to_rich_text};
+use rustylink::egui_app::{CodeStyle, c_syntax_job, highlight_query_job, matlab_syntax_job_styled};
 
 #[test]
 fn test_highlight_job() {
@@ -11,6 +12,54 @@ fn test_highlight_job() {
     assert!(job.sections.len() >= 1);
 }
 
+#[test]
+fn test_c_syntax_job_colors_keywords() {
+    let code = "int y = u0 + 1; // sum\nreturn;\n";
+    let job = c_syntax_job(code);
+    assert_eq!(job.text, code);
+    let color_of = |needle: &str| {
+        let start = code.find(needle).unwrap();
+        job.sections
+            .iter()
+            .find(|s| s.byte_range.contains(&start))
+            .unwrap()
+            .format
+            .color
+    };
+    assert_ne!(color_of("int"), color_of("y"));
+    assert_ne!(color_of("// sum"), color_of("y"));
+}
+
+#[test]
+fn test_code_style_follows_dark_mode_and_sets_font_size() {
+    let script = "function y = f(u)\ny = u;\nend\n";
+    let style = CodeStyle {
+        font_size: 20.0,
+        ..Default::default()
+    };
+    let light = matlab_syntax_job_styled(script, &style, false);
+    let dark = matlab_syntax_job_styled(script, &style, true);
+    assert!(light.sections.iter().all(|s| s.format.font_id.size == 20.0));
+    let colors = |job: &eframe::egui::text::LayoutJob| {
+        job.sections
+            .iter()
+            .map(|s| s.format.color)
+            .collect::<Vec<_>>()
+    };
+    assert_ne!(colors(&light), colors(&dark));
+
+    // An explicit theme ignores the dark mode.
+    let fixed = CodeStyle {
+        theme: Some(style.theme_name(true).to_string()),
+        ..style.clone()
+    };
+    assert_eq!(
+        colors(&matlab_syntax_job_styled(script, &fixed, false)),
+        colors(&dark)
+    );
+    assert!(code_theme_names().contains(&style.theme_name(true)));
+}
+
 #[test]
 fn test_annotation_to_plain_text_removes_blank_lines() {
     let html = r#"
@@ -70,3 +119,89 @@ fn test_annotation_rich_text_detects_styles() {
     let plain = annotation_to_plain_text(html, Some("rich"));
     assert_eq!(plain, "Hello world\n\nDone");
 }
+
+#[test]
+fn test_annotation_lists_links_and_headings() {
+    let html = r#"<html><body>
+<h2>Modes</h2>
+<ul>
+  <li>Idle</li>
+  <li>Run <a href="https://example.com/run">details</a></li>
+</ul>
+<ol start="3">
+  <li><s>old</s> <font color="green" size="5">new</font></li>
+  <li><code>x = 1</code></li>
+</ol>
+</body></html>"#;
+    let parsed = annotation_to_rich_text(html, Some("rich"));
+    assert_eq!(
+        parsed.to_plain_text(),
+        "Modes\n• Idle\n• Run details\n3. old new\n4. x = 1"
+    );
+
+    let heading = &parsed.lines[0];
+    assert!(heading.is_bold());
+    assert!(heading.spans[0].style.font_size_px > 12.0);
+
+    let link = parsed.lines[2]
+        .spans
+        .iter()
+        .find(|s| s.text == "details")
+        .unwrap();
+    assert_eq!(link.style.link.as_deref(), Some("https://example.com/run"));
+    assert!(link.style.underline);
+
+    let item = &parsed.lines[3];
+    assert!(
+        item.spans
+            .iter()
+            .any(|s| s.text == "old" && s.style.strikethrough)
+    );
+    let new = item.spans.iter().find(|s| s.text == "new").unwrap();
+    assert_eq!(new.style.color, Some(Color32::from_rgb(0, 128, 0)));
+    assert_eq!(new.style.font_size_px, 18.0);
+    assert!(parsed.lines[4].spans.iter().any(|s| s.style.monospace));
+
+    // Char indices follow the text of the layout job
+    let style = eframe::egui::Style::default();
+    let job = parsed.to_layout_job(&style, 1.0, 12.0);
+    let start = job.text[..job.text.find("details").unwrap()]
+        .chars()
+        .count();
+    assert_eq!(parsed.link_at(start), Some("https://example.com/run"));
+    assert_eq!(parsed.link_at(start + 6), Some("https://example.com/run"));
+    assert_eq!(parsed.link_at(start + 7), None);
+    assert_eq!(parsed.link_at(0), None);
+    let section = job
+        .sections
+        .iter()
+        .find(|s| &job.text[s.byte_range.clone()] == "details")
+        .unwrap();
+    assert_eq!(section.format.color, style.visuals.hyperlink_color);
+}
+
+#[test]
+fn test_annotation_block_style_applies_to_layout_job() {
+    use rustylink::egui_app::text::{apply_annotation_style, font_id_for_name};
+
+    let annotation = rustylink::model::Annotation {
+        foreground_color: Some(rustylink::color::Rgba::rgb(0, 0, 255)),
+        font_name: Some("Courier".to_string()),
+        font_size: Some(24),
+        ..Default::default()
+    };
+    let parsed = annotation_to_rich_text("Plain note", None);
+    let style = eframe::egui::Style::default();
+    let base = style.visuals.text_color();
+    let mut job = parsed.to_layout_job(&style, 1.0, 12.0);
+    apply_annotation_style(
+        &mut job,
+        &annotation,
+        base,
+        style.visuals.strong_text_color(),
+    );
+    let fmt = &job.sections[0].format;
+    assert_eq!(fmt.color, Color32::from_rgb(0, 0, 255));
+    assert_eq!(fmt.font_id, font_id_for_name(Some("Courier"), 24.0));
+    assert_eq!(fmt.font_id.family, eframe::egui::FontFamily::Monospace);
+}
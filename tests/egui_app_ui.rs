@@ -28,3 +28,242 @@ fn property_values_are_cleaned() {
         vec![("Key Name".to_string(), "value 1".to_string())]
     );
 }
+
+fn styled_app() -> rustylink::egui_app::SubsystemApp {
+    let xml = r#"<System>
+  <Block BlockType="Gain" Name="Gain" SID="1"/>
+  <Block BlockType="Gain" Name="Other" SID="2"/>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let root =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    rustylink::egui_app::SubsystemApp::new(root, Vec::new(), Default::default(), Default::default())
+}
+
+#[test]
+fn block_styles_resolve_by_sid_or_path() {
+    use eframe::egui::Color32;
+    use rustylink::egui_app::StyleOverride;
+
+    let mut app = styled_app();
+    let blocks = app.current_system().unwrap().blocks.clone();
+    assert!(app.block_style(&blocks[0]).is_none());
+
+    let green = StyleOverride {
+        tint: Some(Color32::from_rgba_unmultiplied(0, 200, 0, 128)),
+        badge: Some("✓".to_string()),
+        ..Default::default()
+    };
+    let red = StyleOverride {
+        border: Some(Color32::RED),
+        ..Default::default()
+    };
+    app.set_block_style("/Gain", green.clone());
+    app.set_block_style("2", red.clone());
+    assert_eq!(app.block_style(&blocks[0]), Some(&green));
+    assert_eq!(app.block_style(&blocks[1]), Some(&red));
+
+    // SID keys win over path keys.
+    app.set_block_style("1", red.clone());
+    assert_eq!(app.block_style(&blocks[0]), Some(&red));
+
+    app.clear_block_style("1");
+    assert_eq!(app.block_style(&blocks[0]), Some(&green));
+    app.clear_block_styles();
+    assert!(app.block_style(&blocks[0]).is_none());
+    assert!(app.block_style(&blocks[1]).is_none());
+}
+
+#[test]
+fn blend_tint_weights_by_alpha() {
+    use eframe::egui::Color32;
+    use rustylink::egui_app::colors::blend_tint;
+
+    let base = Color32::from_rgb(200, 200, 200);
+    assert_eq!(blend_tint(base, Color32::TRANSPARENT), base);
+    assert_eq!(
+        blend_tint(base, Color32::from_rgb(0, 100, 0)),
+        Color32::from_rgb(0, 100, 0)
+    );
+    let half = blend_tint(base, Color32::from_rgba_unmultiplied(0, 0, 0, 128));
+    assert!((98..=102).contains(&half.r()), "{half:?}");
+}
+
+#[test]
+fn overlays_switch_and_export() {
+    use rustylink::overlay::MetricOverlay;
+
+    let mut app = styled_app();
+    let blocks = app.current_system().unwrap().blocks.clone();
+    assert!(app.active_overlay().is_none());
+    assert!(app.overlay_color(&blocks[0]).is_none());
+
+    let load = MetricOverlay::new("Load", [("Gain".to_string(), 1.0)].into());
+    let errors = MetricOverlay::new("Errors", [("Other".to_string(), 3.0)].into());
+    app.set_overlay(load.clone());
+    app.set_overlay(errors);
+    assert_eq!(app.overlay_names().collect::<Vec<_>>(), ["Errors", "Load"]);
+    assert_eq!(app.active_overlay().map(|o| o.name.as_str()), Some("Load"));
+    assert_eq!(app.overlay_color(&blocks[0]), load.color_for("Gain"));
+    assert!(app.overlay_color(&blocks[1]).is_none());
+
+    assert!(!app.set_active_overlay(Some("Missing")));
+    assert!(app.set_active_overlay(Some("Errors")));
+    assert!(app.overlay_color(&blocks[0]).is_none());
+    assert!(app.overlay_color(&blocks[1]).is_some());
+
+    let path = std::env::temp_dir().join(format!("rustylink_overlay_{}.svg", std::process::id()));
+    app.export_overlay_svg(&path).unwrap();
+    let svg = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(svg.contains("Errors"));
+
+    app.remove_overlay("Errors");
+    assert!(app.active_overlay().is_none());
+    app.clear_overlays();
+    assert_eq!(app.overlay_names().count(), 0);
+}
+
+#[test]
+fn keyboard_focus_moves_and_survives_going_up() {
+    use rustylink::focus_nav::FocusDirection;
+
+    let xml = r#"<System>
+  <Block BlockType="Gain" Name="Gain" SID="1"><P Name="Position">[0, 0, 30, 30]</P></Block>
+  <Block BlockType="SubSystem" Name="Sub" SID="2"><P Name="Position">[100, 0, 130, 30]</P>
+    <System>
+      <Block BlockType="Gain" Name="Inner" SID="3"><P Name="Position">[0, 0, 30, 30]</P></Block>
+    </System>
+  </Block>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let root =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    let mut app = rustylink::egui_app::SubsystemApp::new(
+        root,
+        Vec::new(),
+        Default::default(),
+        Default::default(),
+    );
+
+    assert!(app.move_focus(FocusDirection::Right));
+    assert_eq!(app.focused_block_sid.as_deref(), Some("1"));
+    assert!(app.move_focus(FocusDirection::Right));
+    assert_eq!(app.focused_block().map(|b| b.name.as_str()), Some("Sub"));
+    assert!(!app.move_focus(FocusDirection::Right));
+
+    let sub = app.focused_block().cloned().unwrap();
+    assert!(app.open_block_if_subsystem(&sub));
+    assert!(app.focused_block_sid.is_none());
+    app.go_up();
+    assert_eq!(app.focused_block_sid.as_deref(), Some("2"));
+}
+
+#[test]
+fn c_function_code_is_applied_to_model_and_dialog() {
+    let xml = r#"<System>
+  <Block BlockType="CFunction" Name="Lookup" SID="4">
+    <P Name="OutputCode">y = u * 2;</P>
+    <P Name="StartCode"></P>
+  </Block>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let root =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    let mut app = rustylink::egui_app::SubsystemApp::new(
+        root,
+        Vec::new(),
+        Default::default(),
+        Default::default(),
+    );
+    let block = app.root.blocks[0].clone();
+    app.block_view = Some(rustylink::egui_app::BlockDialog {
+        title: "Lookup".into(),
+        block: block.clone(),
+        open: true,
+    });
+
+    let mut code = block.c_function.clone().unwrap();
+    code.output_code = Some("y = u * 3;".into());
+    code.terminate_code = Some("free(buf);".into());
+    assert!(app.set_c_function_code(&block, code));
+
+    let updated = &app.root.blocks[0];
+    assert_eq!(
+        updated.c_function.as_ref().unwrap().output_code.as_deref(),
+        Some("y = u * 3;")
+    );
+    let dialog = &app.block_view.as_ref().unwrap().block;
+    assert_eq!(
+        dialog.properties.get("OutputCode").map(String::as_str),
+        Some("y = u * 3;")
+    );
+    let generated = rustylink::generator::system_xml::generate_system_xml(&app.root);
+    assert!(generated.contains(r#"<P Name="OutputCode">y = u * 3;</P>"#));
+    assert!(generated.contains(r#"<P Name="TerminateCode">free(buf);</P>"#));
+}
+
+#[test]
+fn context_menu_entries_nest_and_filter() {
+    use rustylink::egui_app::{BlockContextMenuItem, ContextMenuKind};
+
+    let gain = create_default_block("Gain", "G", 0, 0, 0, 0);
+    let scope = create_default_block("Scope", "S", 0, 0, 0, 0);
+    let plot = BlockContextMenuItem::submenu(
+        "Plot",
+        vec![
+            BlockContextMenuItem::action("Open", |_| {})
+                .with_filter(|b| b.block_type == "Scope")
+                .with_icon("📈"),
+            BlockContextMenuItem::separator(),
+            BlockContextMenuItem::check("Docked", |_| true, |_, _| {})
+                .with_filter(|b| b.block_type == "Scope")
+                .with_enabled(|_| false),
+        ],
+    );
+    assert!(plot.is_shown(&scope));
+    // Separators alone do not keep a submenu visible.
+    assert!(!plot.is_shown(&gain));
+    let ContextMenuKind::Submenu(items) = &plot.kind else {
+        panic!("expected a submenu");
+    };
+    assert_eq!(items[0].text(), "📈 Open");
+    assert!(!(items[2].enabled)(&scope));
+
+    let mut app = styled_app();
+    app.add_block_context_menu_item("Print", |_| true, |_| {});
+    app.add_block_context_menu_entry(plot);
+    assert_eq!(app.block_menu_items.len(), 2);
+    assert!(matches!(
+        app.block_menu_items[0].kind,
+        ContextMenuKind::Action(_)
+    ));
+}
+
+#[test]
+fn double_click_handlers_run_by_block_type_first() {
+    let mut app = styled_app();
+    let gain = app.root.blocks[0].clone();
+    let scope = create_default_block("Scope", "S", 0, 0, 0, 0);
+    assert!(!app.run_block_double_click_handlers(&gain));
+
+    app.set_block_click_handler(|app, block| {
+        app.search_query = format!("any {}", block.name);
+        false
+    });
+    app.set_block_double_click_handler("Scope", |app, block| {
+        app.search_query = format!("scope {}", block.name);
+        true
+    });
+    assert!(app.run_block_double_click_handlers(&scope));
+    assert_eq!(app.search_query, "scope S");
+    assert!(!app.run_block_double_click_handlers(&gain));
+    assert_eq!(app.search_query, "any Gain");
+
+    app.clear_block_double_click_handler("Scope");
+    assert!(!app.run_block_double_click_handlers(&scope));
+    assert_eq!(app.search_query, "any S");
+}
@@ -0,0 +1,238 @@
+use camino::Utf8Path;
+use rustylink::cancel::CancellationToken;
+use rustylink::egui_app::loader::{library_search_paths, load_model, load_model_with_progress};
+use rustylink::egui_app::viewer::{
+    RECENT_FILES_LEN, SETTINGS_KEY, ViewerApp, is_model_file, push_recent_file,
+};
+use rustylink::parser::ParsePhase;
+use std::io::Write;
+use std::path::Path;
+
+const ROOT_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Gain" Name="K" SID="1"><P Name="Position">[0, 0, 30, 30]</P></Block>
+  <Block BlockType="SubSystem" Name="Sub" SID="2"><P Name="Position">[100, 0, 130, 30]</P>
+    <System>
+      <Block BlockType="Reference" Name="F" SID="3">
+        <P Name="Position">[0, 0, 30, 30]</P>
+        <P Name="SourceBlock">MissingLib/Filter</P>
+      </Block>
+    </System>
+  </Block>
+</System>
+"#;
+
+fn write_model(path: &Path) {
+    write_model_xml(path, ROOT_XML);
+}
+
+fn write_model_xml(path: &Path, xml: &str) {
+    let file = std::fs::File::create(path).unwrap();
+    let mut zip = zip::ZipWriter::new(file);
+    zip.start_file(
+        "simulink/systems/system_root.xml",
+        zip::write::FileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(xml.as_bytes()).unwrap();
+    zip.finish().unwrap();
+}
+
+#[test]
+fn recent_files_are_deduplicated_and_trimmed() {
+    let mut recent = Vec::new();
+    for i in 0..RECENT_FILES_LEN + 3 {
+        push_recent_file(&mut recent, &format!("m{i}.slx"));
+    }
+    assert_eq!(recent.len(), RECENT_FILES_LEN);
+    assert_eq!(recent[0], format!("m{}.slx", RECENT_FILES_LEN + 2));
+
+    push_recent_file(&mut recent, "m5.slx");
+    assert_eq!(recent.len(), RECENT_FILES_LEN);
+    assert_eq!(recent[0], "m5.slx");
+    assert_eq!(recent.iter().filter(|p| *p == "m5.slx").count(), 1);
+}
+
+#[test]
+fn model_files_by_extension() {
+    assert!(is_model_file(Path::new("/a/model.slx")));
+    assert!(is_model_file(Path::new("Model.SLX")));
+    assert!(is_model_file(Path::new("system_root.xml")));
+    assert!(!is_model_file(Path::new("notes.txt")));
+    assert!(!is_model_file(Path::new("slx")));
+}
+
+#[test]
+fn load_model_reports_missing_library() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("m.slx");
+    write_model(&path);
+    let path = Utf8Path::from_path(&path).unwrap();
+
+    let lib_paths = library_search_paths(path, &[]);
+    assert_eq!(lib_paths, [path.parent().unwrap()]);
+    let model = load_model(path, &lib_paths).unwrap();
+    assert_eq!(model.root.blocks.len(), 2);
+    assert_eq!(model.missing_libraries, ["MissingLib"]);
+    assert_eq!(model.unresolved_blocks.len(), 1);
+    let unresolved = &model.unresolved_blocks[0];
+    assert_eq!(unresolved.host, "/Sub/F");
+    assert_eq!(unresolved.block, "Filter");
+    assert!(unresolved.library_missing);
+
+    let app = model.into_app(vec!["Sub".to_string()]);
+    assert_eq!(app.path, ["Sub"]);
+}
+
+#[test]
+fn open_tracks_recent_files_and_errors() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("m.slx");
+    write_model(&path);
+    let path = Utf8Path::from_path(&path).unwrap();
+
+    let mut app = ViewerApp::new(Vec::new());
+    assert!(app.open(path, vec!["Missing".to_string()], None));
+    // Unknown subsystems fall back to the root.
+    assert!(app.viewer.as_ref().unwrap().path.is_empty());
+    assert_eq!(app.recent_files, [path.as_str()]);
+
+    let missing = dir.path().join("missing.slx");
+    assert!(!app.open(Utf8Path::from_path(&missing).unwrap(), Vec::new(), None));
+    let error = app.error.as_ref().unwrap();
+    assert_eq!(error.path.as_deref(), Utf8Path::from_path(&missing));
+    assert!(error.message.contains("missing.slx"));
+    // The previously opened model stays open.
+    assert!(app.viewer.is_some());
+    assert_eq!(app.recent_files.len(), 1);
+}
+
+fn wait_for_load(app: &mut ViewerApp) {
+    let start = std::time::Instant::now();
+    while !app.poll_load(None) {
+        assert!(start.elapsed() < std::time::Duration::from_secs(10));
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    assert!(!app.is_loading());
+}
+
+#[test]
+fn background_load_swaps_model_or_reports_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("m.slx");
+    write_model(&path);
+    let path = Utf8Path::from_path(&path).unwrap();
+    let ctx = eframe::egui::Context::default();
+
+    let mut app = ViewerApp::new(Vec::new());
+    assert!(!app.poll_load(None));
+    app.open_in_background(path, vec!["Sub".to_string()], true, &ctx);
+    assert!(app.is_loading());
+    wait_for_load(&mut app);
+    assert_eq!(app.viewer.as_ref().unwrap().path, ["Sub"]);
+    assert!(app.error.is_none());
+
+    // A broken file keeps the current model and reports the failure.
+    let broken = dir.path().join("broken.slx");
+    std::fs::write(&broken, b"not a zip").unwrap();
+    let broken = Utf8Path::from_path(&broken).unwrap();
+    app.open_in_background(broken, Vec::new(), true, &ctx);
+    wait_for_load(&mut app);
+    assert_eq!(app.viewer.as_ref().unwrap().path, ["Sub"]);
+    let error = app.error.clone().unwrap();
+    assert_eq!(error.path.as_deref(), Some(broken));
+    assert!(!error.message.is_empty());
+    assert_eq!(app.recent_files, [path.as_str()]);
+}
+
+#[test]
+fn load_model_reports_progress() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("m.slx");
+    write_model(&path);
+    let path = Utf8Path::from_path(&path).unwrap();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let model =
+        load_model_with_progress(path, &[], Some(sender), &CancellationToken::new()).unwrap();
+    assert_eq!(model.root.blocks.len(), 2);
+    let last = receiver.into_iter().last().unwrap();
+    assert_eq!(last.phase, ParsePhase::Done);
+    assert!(last.files_parsed > 0);
+}
+
+#[test]
+fn comparing_shows_changes_side_by_side() {
+    let dir = tempfile::tempdir().unwrap();
+    let new = dir.path().join("new.slx");
+    let old = dir.path().join("old.slx");
+    write_model(&new);
+    write_model_xml(
+        &old,
+        &ROOT_XML
+            .replace(r#"SID="1">"#, r#"SID="1"><P Name="Gain">2</P>"#)
+            .replace(
+                r#"<Block BlockType="Reference""#,
+                r#"<Block BlockType="Gain" Name="G" SID="4"/><Block BlockType="Reference""#,
+            ),
+    );
+    let mut app = ViewerApp::new(Vec::new());
+    assert!(!app.compare_with(Utf8Path::from_path(&old).unwrap()));
+    assert!(app.open(Utf8Path::from_path(&new).unwrap(), Vec::new(), None));
+    assert!(app.compare_with(Utf8Path::from_path(&old).unwrap()));
+
+    let view = app.diff.as_mut().unwrap();
+    let paths: Vec<_> = view.diff().blocks.iter().map(|c| c.path.as_str()).collect();
+    assert_eq!(paths, ["K", "Sub/G"]);
+    assert_eq!(view.system, "");
+    view.select_previous();
+    assert_eq!(view.selected, Some(1));
+    assert_eq!(view.system, "Sub");
+    let (old_level, new_level) = view.levels();
+    assert_eq!(old_level.unwrap().blocks.len(), 2);
+    assert_eq!(new_level.unwrap().blocks.len(), 1);
+    view.select_next();
+    assert_eq!((view.selected, view.system.as_str()), (Some(0), ""));
+
+    // Opening another model ends the comparison.
+    assert!(app.open(Utf8Path::from_path(&old).unwrap(), Vec::new(), None));
+    assert!(app.diff.is_none());
+}
+
+#[derive(Default)]
+struct MemoryStorage(std::collections::HashMap<String, String>);
+
+impl eframe::Storage for MemoryStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), value);
+    }
+
+    fn flush(&mut self) {}
+}
+
+#[test]
+fn settings_are_kept_across_runs() {
+    let mut app = ViewerApp::new(Vec::new());
+    app.settings.dark_mode = true;
+    app.settings.code_style.theme = Some("Solarized (dark)".to_string());
+    app.settings.code_style.font_size = 18.0;
+    let mut storage = MemoryStorage::default();
+    eframe::App::save(&mut app, &mut storage);
+
+    let mut restored = ViewerApp::new(Vec::new());
+    restored.restore(&storage);
+    assert_eq!(restored.settings, app.settings);
+
+    // Settings saved by older versions lack fields; those get defaults.
+    storage.0.insert(
+        SETTINGS_KEY.to_string(),
+        r#"{"dark_mode":true}"#.to_string(),
+    );
+    restored.restore(&storage);
+    assert!(restored.settings.dark_mode);
+    assert_eq!(restored.settings.code_style.font_size, 14.0);
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "egui")]
+
+mod common;
+
+use common::parse;
+use eframe::egui::{self, Event, MouseWheelUnit, Pos2, RawInput, Rect, Vec2};
+use rustylink::egui_app::{SystemView, SystemViewState};
+use rustylink::model::System;
+
+fn model() -> System {
+    parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="In" SID="1">
+    <P Name="Position">[0, 0, 30, 20]</P>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="2">
+    <P Name="Position">[100, 0, 130, 20]</P>
+  </Block>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">2#in:1</P>
+  </Line>
+</System>"#,
+    )
+}
+
+/// Runs one frame with two views side by side and returns the block hovered
+/// in each and the left view's center.
+fn frame(
+    ctx: &egui::Context,
+    left: &System,
+    right: &System,
+    events: Vec<Event>,
+) -> (Option<usize>, Option<usize>, Pos2) {
+    let input = RawInput {
+        screen_rect: Some(Rect::from_min_size(Pos2::ZERO, Vec2::new(800.0, 400.0))),
+        events,
+        ..Default::default()
+    };
+    let mut out = (None, None, Pos2::ZERO);
+    let _ = ctx.run(input, |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.columns(2, |cols| {
+                let l = SystemView::new(left, "left").show(&mut cols[0]);
+                let r = SystemView::new(right, "right").show(&mut cols[1]);
+                out = (
+                    l.hovered_block,
+                    r.hovered_block,
+                    l.transform.to_screen(Pos2::new(115.0, 10.0)),
+                );
+            });
+        });
+    });
+    out
+}
+
+#[test]
+fn views_keep_their_own_zoom_and_pan() {
+    let ctx = egui::Context::default();
+    let (left, right) = (model(), model());
+    let (_, _, gain_center) = frame(&ctx, &left, &right, Vec::new());
+
+    let (hovered, other, _) = frame(&ctx, &left, &right, vec![Event::PointerMoved(gain_center)]);
+    assert_eq!(hovered, Some(1));
+    assert_eq!(other, None);
+
+    frame(
+        &ctx,
+        &left,
+        &right,
+        vec![Event::MouseWheel {
+            unit: MouseWheelUnit::Line,
+            delta: Vec2::new(0.0, 2.0),
+            modifiers: egui::Modifiers::NONE,
+        }],
+    );
+    let zoomed = SystemViewState::load(&ctx, egui::Id::new("left"));
+    assert!(zoomed.zoom > 1.0);
+    assert_eq!(
+        SystemViewState::load(&ctx, egui::Id::new("right")),
+        SystemViewState::default()
+    );
+    // Zooming keeps the block under the pointer.
+    let (hovered, _, _) = frame(&ctx, &left, &right, Vec::new());
+    assert_eq!(hovered, Some(1));
+}
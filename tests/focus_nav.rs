@@ -0,0 +1,53 @@
+use rustylink::focus_nav::{FocusDirection, connected_sids, first_block, neighbor};
+
+fn system() -> rustylink::model::System {
+    // In(1) -> Gain(2) -> Out(3), with a branch from Gain to Scope(4) below.
+    // Note(5) is unconnected, above Gain.
+    let xml = r#"<System>
+  <Block BlockType="Inport" Name="In" SID="1"><P Name="Position">[0, 100, 30, 120]</P></Block>
+  <Block BlockType="Gain" Name="Gain" SID="2"><P Name="Position">[100, 100, 130, 120]</P></Block>
+  <Block BlockType="Outport" Name="Out" SID="3"><P Name="Position">[200, 100, 230, 120]</P></Block>
+  <Block BlockType="Scope" Name="Scope" SID="4"><P Name="Position">[200, 200, 230, 220]</P></Block>
+  <Block BlockType="Constant" Name="Note" SID="5"><P Name="Position">[100, 0, 130, 20]</P></Block>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+  <Line><P Name="Src">2#out:1</P>
+    <Branch><P Name="Dst">3#in:1</P></Branch>
+    <Branch><P Name="Dst">4#in:1</P></Branch>
+  </Line>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new("")).unwrap()
+}
+
+#[test]
+fn first_block_is_top_left() {
+    let sys = system();
+    assert_eq!(first_block(&sys).unwrap().name, "In");
+}
+
+#[test]
+fn connected_sids_include_branches() {
+    let sys = system();
+    let gain: Vec<&str> = connected_sids(&sys.lines, "2").into_iter().collect();
+    assert_eq!(gain, ["1", "3", "4"]);
+    assert!(connected_sids(&sys.lines, "5").is_empty());
+}
+
+#[test]
+fn arrows_follow_connections() {
+    let sys = system();
+    assert_eq!(neighbor(&sys, "1", FocusDirection::Right), Some("2"));
+    assert_eq!(neighbor(&sys, "2", FocusDirection::Right), Some("3"));
+    assert_eq!(neighbor(&sys, "2", FocusDirection::Left), Some("1"));
+    assert_eq!(neighbor(&sys, "3", FocusDirection::Left), Some("2"));
+    assert_eq!(neighbor(&sys, "3", FocusDirection::Down), Some("4"));
+    assert_eq!(neighbor(&sys, "1", FocusDirection::Left), None);
+}
+
+#[test]
+fn unconnected_blocks_are_reachable_spatially() {
+    let sys = system();
+    assert_eq!(neighbor(&sys, "2", FocusDirection::Up), Some("5"));
+    assert_eq!(neighbor(&sys, "5", FocusDirection::Down), Some("2"));
+    assert_eq!(neighbor(&sys, "missing", FocusDirection::Down), None);
+}
@@ -0,0 +1,228 @@
+use rustylink::generator::archive::WriteOptions;
+use rustylink::generator::thumbnail::ThumbnailOptions;
+use rustylink::model::{SlxArchive, SlxContent, THUMBNAIL_PATH};
+use std::io::{Cursor, Read, Write};
+
+const ROOT_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Constant" Name="C" SID="1">
+    <P Name="Position">[20, 20, 50, 50]</P>
+    <P Name="BackgroundColor">yellow</P>
+  </Block>
+  <Block BlockType="Outport" Name="Out1" SID="2">
+    <P Name="Position">[120, 25, 150, 45]</P>
+  </Block>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">2#in:1</P>
+  </Line>
+</System>
+"#;
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="xml" ContentType="application/xml"/></Types>"#;
+const PACKAGE_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Target="simulink/blockdiagram.xml" Type="http://schemas.mathworks.com/simulink/2010/relationships/blockDiagram"/></Relationships>"#;
+
+fn build_slx(extra: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        let mut files: Vec<(&str, &[u8])> = vec![
+            ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+            ("_rels/.rels", PACKAGE_RELS.as_bytes()),
+            ("simulink/systems/system_root.xml", ROOT_XML.as_bytes()),
+        ];
+        files.extend_from_slice(extra);
+        for (name, data) in files {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    buf.into_inner()
+}
+
+fn read_entry(bytes: &[u8], name: &str) -> Option<Vec<u8>> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let mut f = zip.by_name(name).ok()?;
+    let mut data = Vec::new();
+    f.read_to_end(&mut data).unwrap();
+    Some(data)
+}
+
+fn write(archive: &SlxArchive, options: &WriteOptions) -> Vec<u8> {
+    let mut out = Cursor::new(Vec::new());
+    archive.write_to_with_options(&mut out, options).unwrap();
+    out.into_inner()
+}
+
+#[test]
+fn default_write_does_not_add_thumbnail() {
+    let archive = SlxArchive::from_reader(Cursor::new(build_slx(&[]))).unwrap();
+    let out = write(&archive, &WriteOptions::default());
+    assert!(read_entry(&out, THUMBNAIL_PATH).is_none());
+}
+
+#[test]
+fn thumbnail_is_added_with_package_metadata() {
+    let archive = SlxArchive::from_reader(Cursor::new(build_slx(&[]))).unwrap();
+    let options = WriteOptions {
+        thumbnail: Some(ThumbnailOptions {
+            width: 64,
+            height: 48,
+        }),
+        ..Default::default()
+    };
+    let out = write(&archive, &options);
+
+    let png = read_entry(&out, THUMBNAIL_PATH).expect("thumbnail written");
+    assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+    assert_eq!(&png[16..20], &64u32.to_be_bytes());
+    assert_eq!(&png[20..24], &48u32.to_be_bytes());
+    // The image data inflates to one filter byte plus 64 RGBA pixels per row.
+    let idat_len = u32::from_be_bytes(png[33..37].try_into().unwrap()) as usize;
+    assert_eq!(&png[37..41], b"IDAT");
+    let mut pixels = Vec::new();
+    flate2::read::ZlibDecoder::new(&png[41..41 + idat_len])
+        .read_to_end(&mut pixels)
+        .unwrap();
+    assert_eq!(pixels.len(), (1 + 64 * 4) * 48);
+    assert!(idat_len < pixels.len() / 4);
+
+    let types = String::from_utf8(read_entry(&out, "[Content_Types].xml").unwrap()).unwrap();
+    assert!(types.contains(r#"Extension="png""#));
+    let rels = String::from_utf8(read_entry(&out, "_rels/.rels").unwrap()).unwrap();
+    assert!(rels.contains("metadata/thumbnail"));
+    assert!(rels.contains(THUMBNAIL_PATH));
+
+    // The regenerated archive still reads back.
+    let reread = SlxArchive::from_reader(Cursor::new(out)).unwrap();
+    assert!(reread.assets().thumbnail().is_some());
+}
+
+#[test]
+fn oversized_thumbnail_is_an_error() {
+    let archive = SlxArchive::from_reader(Cursor::new(build_slx(&[]))).unwrap();
+    let options = WriteOptions {
+        thumbnail: Some(ThumbnailOptions {
+            width: 70_000,
+            height: 70_000,
+        }),
+        ..Default::default()
+    };
+    let err = archive
+        .write_to_with_options(&mut Cursor::new(Vec::new()), &options)
+        .unwrap_err();
+    assert!(format!("{err:#}").contains("too large"), "{err:#}");
+}
+
+#[test]
+fn existing_thumbnail_is_replaced_in_place() {
+    let stale: &[u8] = b"not a png";
+    let archive =
+        SlxArchive::from_reader(Cursor::new(build_slx(&[(THUMBNAIL_PATH, stale)]))).unwrap();
+    let options = WriteOptions {
+        thumbnail: Some(ThumbnailOptions::default()),
+        ..Default::default()
+    };
+    let out = write(&archive, &options);
+    let png = read_entry(&out, THUMBNAIL_PATH).unwrap();
+    assert!(png.starts_with(b"\x89PNG"));
+    let rels = String::from_utf8(read_entry(&out, "_rels/.rels").unwrap()).unwrap();
+    assert_eq!(rels, PACKAGE_RELS);
+}
+
+#[test]
+fn unknown_members_are_copied_through_by_default() {
+    let archive = SlxArchive::from_reader(Cursor::new(build_slx(&[
+        ("slprj/sim/varcache/model/tmwinternal.mat", b"\x00\x01cache"),
+        ("custom/notes.txt", b"keep me"),
+    ])))
+    .unwrap();
+    assert_eq!(
+        archive.nonessential_entry_paths(),
+        vec![
+            "slprj/sim/varcache/model/tmwinternal.mat",
+            "custom/notes.txt"
+        ]
+    );
+    let out = write(&archive, &WriteOptions::default());
+    assert_eq!(
+        read_entry(&out, "slprj/sim/varcache/model/tmwinternal.mat").unwrap(),
+        b"\x00\x01cache"
+    );
+    assert_eq!(read_entry(&out, "custom/notes.txt").unwrap(), b"keep me");
+}
+
+#[test]
+fn strip_nonessential_drops_members_and_their_package_references() {
+    let types = r#"<Types><Default Extension="xml" ContentType="application/xml"/><Override PartName="/custom/notes.txt" ContentType="text/plain"/></Types>"#;
+    let rels = r#"<Relationships><Relationship Id="rId1" Target="simulink/blockdiagram.xml" Type="bd"/><Relationship Id="rId2" Target="/custom/notes.txt" Type="custom"/></Relationships>"#;
+    let mut archive = SlxArchive::from_reader(Cursor::new(build_slx(&[
+        ("slprj/leftover.dat", b"junk"),
+        ("custom/notes.txt", b"notes"),
+    ])))
+    .unwrap();
+    for entry in &mut archive.entries {
+        match entry.path.as_str() {
+            "[Content_Types].xml" => entry.content = SlxContent::Raw(types.as_bytes().to_vec()),
+            "_rels/.rels" => entry.content = SlxContent::Raw(rels.as_bytes().to_vec()),
+            _ => {}
+        }
+    }
+    let options = WriteOptions {
+        strip_nonessential: true,
+        ..Default::default()
+    };
+    let out = write(&archive, &options);
+    assert!(read_entry(&out, "slprj/leftover.dat").is_none());
+    assert!(read_entry(&out, "custom/notes.txt").is_none());
+    assert!(read_entry(&out, "simulink/systems/system_root.xml").is_some());
+
+    let types = String::from_utf8(read_entry(&out, "[Content_Types].xml").unwrap()).unwrap();
+    assert!(!types.contains("notes.txt"));
+    assert!(types.contains(r#"Extension="xml""#));
+    let rels = String::from_utf8(read_entry(&out, "_rels/.rels").unwrap()).unwrap();
+    assert!(!rels.contains("notes.txt"));
+    assert!(rels.contains("simulink/blockdiagram.xml"));
+}
+
+const CHART_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<chart id="18">
+  <P Name="name">Logic/MATLAB Function</P>
+  <eml>
+    <P Name="name">fcn</P>
+  </eml>
+  <Children>
+    <state SSID="1">
+      <P Name="labelString">eML_blk_kernel()</P>
+      <eml>
+        <P Name="isEML">1</P>
+        <P Name="script">function y = fcn(u)
+y = u;</P>
+      </eml>
+    </state>
+  </Children>
+</chart>
+"#;
+
+#[test]
+fn edited_chart_scripts_are_written_back() {
+    let slx = build_slx(&[("simulink/stateflow/chart_18.xml", CHART_XML.as_bytes())]);
+    let mut archive = SlxArchive::from_reader(Cursor::new(slx)).unwrap();
+    let (mut charts, _) = archive.parse_charts();
+    assert_eq!(archive.update_chart_scripts(&charts).unwrap(), 0);
+
+    let script = "function y = fcn(u)\nif u < 0 && u > -1\n  y = 0;\nend";
+    charts.get_mut(&18).unwrap().script = Some(script.to_string());
+    assert_eq!(archive.update_chart_scripts(&charts).unwrap(), 1);
+    assert!(archive.set_chart_script(7, script).is_err());
+
+    let bytes = write(&archive, &WriteOptions::default());
+    let xml =
+        String::from_utf8(read_entry(&bytes, "simulink/stateflow/chart_18.xml").unwrap()).unwrap();
+    assert!(xml.contains("if u &lt; 0 &amp;&amp; u &gt; -1"));
+    assert!(xml.contains(r#"<P Name="isEML">1</P>"#));
+    let reread = SlxArchive::from_reader(Cursor::new(bytes)).unwrap();
+    assert_eq!(reread.parse_charts().0[&18].script.as_deref(), Some(script));
+}
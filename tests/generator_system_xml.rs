@@ -1,6 +1,8 @@
 use indexmap::IndexMap;
 use rustylink::generator::system_xml::generate_system_xml;
-use rustylink::model::{Block, NameLocation, PortCounts, System, ValueKind};
+use rustylink::model::{
+    Block, BlockOrientation, NameLocation, PortCounts, PropertyBag, System, ValueKind,
+};
 
 #[test]
 fn test_simple_system_roundtrip() {
@@ -34,6 +36,7 @@ fn test_block_with_port_counts() {
             position: Some("[10, 20, 50, 60]".into()),
             zorder: Some("1".into()),
             commented: false,
+            comment_through: false,
             name_location: NameLocation::Bottom,
             is_matlab_function: false,
             value: None,
@@ -41,7 +44,7 @@ fn test_block_with_port_counts() {
             value_rows: None,
             value_cols: None,
             properties: {
-                let mut m = IndexMap::new();
+                let mut m = PropertyBag::new();
                 m.insert("Position".into(), "[10, 20, 50, 60]".into());
                 m.insert("ZOrder".into(), "1".into());
                 m
@@ -50,6 +53,7 @@ fn test_block_with_port_counts() {
             port_counts: Some(PortCounts {
                 ins: Some(1),
                 outs: Some(1),
+                special: Vec::new(),
             }),
             ports: vec![],
             subsystem: None,
@@ -63,12 +67,18 @@ fn test_block_with_port_counts() {
             show_name: None,
             font_size: None,
             font_weight: None,
+            foreground_color: None,
+            drop_shadow: false,
+            font_name: None,
             mask_display_text: None,
             current_setting: None,
             block_mirror: None,
+            orientation: BlockOrientation::Right,
             library_source: None,
             library_block_path: None,
+            library_overrides: None,
             dashboard_binding: None,
+            workspace_values: Default::default(),
             child_order: vec![],
         }],
         lines: vec![],
@@ -82,3 +92,38 @@ fn test_block_with_port_counts() {
     assert!(xml.contains("Name=\"G1\""));
     assert!(xml.contains("SID=\"5\""));
 }
+
+#[test]
+fn test_special_port_counts_and_endpoints_roundtrip() {
+    use rustylink::model::PortKind;
+
+    let xml = r#"<System>
+  <Block BlockType="SubSystem" Name="Triggered" SID="2">
+    <PortCounts in="1" out="1" enable="1" trigger="1"/>
+  </Block>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">2#trigger:1</P>
+  </Line>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let system =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    let counts = system.blocks[0].port_counts.as_ref().unwrap();
+    assert_eq!(counts.count(&PortKind::Trigger), Some(1));
+    assert_eq!(counts.count(&PortKind::Enable), Some(1));
+    assert_eq!(counts.count(&PortKind::IfAction), None);
+    let dst = system.lines[0].dst.as_ref().unwrap();
+    assert_eq!(dst.port_type, PortKind::Trigger);
+    assert!(dst.port_type.is_control());
+    assert_eq!(PortKind::from("lconn"), PortKind::LConn);
+    assert_eq!(
+        PortKind::from("custom"),
+        PortKind::Other("custom".to_string())
+    );
+
+    let generated = generate_system_xml(&system);
+    assert!(generated.contains(r#"<PortCounts in="1" out="1" enable="1" trigger="1"/>"#));
+    assert!(generated.contains(r#"<P Name="Dst">2#trigger:1</P>"#));
+}
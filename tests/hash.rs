@@ -0,0 +1,54 @@
+mod common;
+
+use common::parse;
+use rustylink::model::System;
+
+fn model(gain: &str, position: &str, zoom: &str, dst: &str) -> System {
+    parse(&format!(
+        r#"<System>
+  <P Name="ZoomFactor">{zoom}</P>
+  <Block BlockType="Inport" Name="In" SID="2">
+    <P Name="Position">[10, 10, 30, 30]</P>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="10">
+    <P Name="Gain">{gain}</P>
+    <P Name="Position">{position}</P>
+    <P Name="ZOrder">3</P>
+  </Block>
+  <Block BlockType="Outport" Name="Out" SID="9"/>
+  <Block BlockType="Outport" Name="Out2" SID="11"/>
+  <Line>
+    <P Name="Src">2#out:1</P>
+    <P Name="Dst">10#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">10#out:1</P>
+    <P Name="Points">[20, 0]</P>
+    <P Name="Dst">{dst}#in:1</P>
+  </Line>
+</System>"#
+    ))
+}
+
+#[test]
+fn layout_changes_keep_the_structural_hash() {
+    let original = model("2", "[100, 10, 130, 40]", "100", "9");
+    let mut moved = model("2", "[200, 50, 230, 80]", "150", "9");
+    moved.lines[1].points.clear();
+    moved.blocks.swap(0, 2);
+
+    assert_eq!(original.structural_hash(), moved.structural_hash());
+    assert_ne!(original.full_hash(), moved.full_hash());
+    assert_eq!(original.full_hash(), original.clone().full_hash());
+}
+
+#[test]
+fn parameter_and_connection_changes_change_the_structural_hash() {
+    let original = model("2", "[100, 10, 130, 40]", "100", "9");
+    let retuned = model("3", "[100, 10, 130, 40]", "100", "9");
+    let rewired = model("2", "[100, 10, 130, 40]", "100", "11");
+
+    assert_ne!(original.structural_hash(), retuned.structural_hash());
+    assert_ne!(original.structural_hash(), rewired.structural_hash());
+    assert_ne!(retuned.structural_hash(), rewired.structural_hash());
+}
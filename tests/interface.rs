@@ -0,0 +1,139 @@
+use camino::Utf8Path;
+use rustylink::interface::{ChangeKind, ModelInterface, PortDirection, check_compat};
+
+fn interface(blocks: &str) -> ModelInterface {
+    let xml = format!("<System>{}</System>", blocks);
+    let doc = roxmltree::Document::parse(&xml).unwrap();
+    let system =
+        rustylink::block::parse_system_shallow(doc.root_element(), Utf8Path::new("")).unwrap();
+    ModelInterface::from_system(&system)
+}
+
+const V1: &str = r#"
+  <Block BlockType="Inport" Name="speed" SID="1">
+    <P Name="OutDataTypeStr">single</P>
+  </Block>
+  <Block BlockType="Inport" Name="mode" SID="2">
+    <P Name="Port">2</P>
+    <P Name="OutDataTypeStr">uint8</P>
+    <P Name="PortDimensions">[2 2]</P>
+  </Block>
+  <Block BlockType="Inport" Name="enable" SID="3"><P Name="Port">3</P></Block>
+  <Block BlockType="Outport" Name="torque" SID="4"/>
+  <Block BlockType="Gain" Name="K" SID="5"/>
+"#;
+
+#[test]
+fn reads_root_ports() {
+    let iface = interface(V1);
+    let names: Vec<&str> = iface.inputs.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["speed", "mode", "enable"]);
+    assert_eq!(iface.inputs[1].port, 2);
+    assert_eq!(iface.inputs[1].data_type.as_deref(), Some("uint8"));
+    assert_eq!(iface.inputs[1].dimensions, Some(vec![2, 2]));
+    assert_eq!(iface.inputs[2].data_type, None);
+    assert_eq!(iface.outputs.len(), 1);
+}
+
+#[test]
+fn identical_interfaces_are_compatible() {
+    let compat = check_compat(&interface(V1), &interface(V1));
+    assert!(compat.changes.is_empty());
+    assert!(!compat.is_breaking());
+}
+
+#[test]
+fn added_outputs_are_optional_and_inputs_required() {
+    let new = format!(
+        r#"{V1}
+  <Block BlockType="Outport" Name="status" SID="6"><P Name="Port">2</P></Block>"#
+    );
+    let compat = check_compat(&interface(V1), &interface(&new));
+    assert_eq!(compat.changes.len(), 1);
+    let change = &compat.changes[0];
+    assert_eq!(change.direction, PortDirection::Output);
+    assert_eq!(
+        change.change,
+        ChangeKind::Added {
+            port: 2,
+            optional: true
+        }
+    );
+    assert!(!compat.is_breaking());
+
+    let new = format!(
+        r#"{V1}
+  <Block BlockType="Inport" Name="reset" SID="6"><P Name="Port">4</P></Block>"#
+    );
+    let compat = check_compat(&interface(V1), &interface(&new));
+    assert!(compat.is_breaking());
+    assert_eq!(
+        compat.changes[0].change,
+        ChangeKind::Added {
+            port: 4,
+            optional: false
+        }
+    );
+}
+
+#[test]
+fn removals_renumbering_and_type_changes_break() {
+    let new = r#"
+  <Block BlockType="Inport" Name="speed" SID="1">
+    <P Name="OutDataTypeStr">double</P>
+  </Block>
+  <Block BlockType="Inport" Name="enable" SID="3"><P Name="Port">2</P></Block>
+  <Block BlockType="Outport" Name="torque" SID="4">
+    <P Name="PortDimensions">3</P>
+  </Block>
+"#;
+    let compat = check_compat(&interface(V1), &interface(new));
+    let changes: Vec<(&str, &ChangeKind, bool)> = compat
+        .changes
+        .iter()
+        .map(|c| (c.name.as_str(), &c.change, c.breaking))
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            (
+                "speed",
+                &ChangeKind::TypeChange {
+                    old: Some("single".into()),
+                    new: Some("double".into())
+                },
+                true
+            ),
+            ("mode", &ChangeKind::Removed { port: 2 }, true),
+            ("enable", &ChangeKind::Renumbered { old: 3, new: 2 }, true),
+            (
+                "torque",
+                &ChangeKind::WidthChange {
+                    old: None,
+                    new: Some(vec![3])
+                },
+                true
+            ),
+        ]
+    );
+    assert_eq!(compat.breaking().count(), 4);
+}
+
+#[test]
+fn inheriting_on_inputs_is_not_breaking() {
+    let new = V1
+        .replace(r#"<P Name="OutDataTypeStr">uint8</P>"#, "")
+        .replace(
+            r#"<P Name="PortDimensions">[2 2]</P>"#,
+            r#"<P Name="PortDimensions">-1</P>"#,
+        );
+    let compat = check_compat(&interface(V1), &interface(&new));
+    assert_eq!(compat.changes.len(), 2);
+    assert!(!compat.is_breaking());
+
+    // The same relaxation on an output changes what callers receive.
+    let old =
+        r#"<Block BlockType="Outport" Name="y" SID="1"><P Name="OutDataTypeStr">int16</P></Block>"#;
+    let new = r#"<Block BlockType="Outport" Name="y" SID="1"/>"#;
+    assert!(check_compat(&interface(old), &interface(new)).is_breaking());
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use camino::Utf8Path;
+use common::parse;
+use rustylink::inventory::Inventory;
+
+const MODEL: &str = r#"<System>
+  <Block BlockType="Inport" Name="In" SID="1"/>
+  <Block BlockType="SubSystem" Name="Ctrl" SID="2">
+    <System>
+      <Block BlockType="Gain" Name="K" SID="3"><P Name="Gain">2</P></Block>
+    </System>
+  </Block>
+  <Block BlockType="Outport" Name="Out" SID="4"/>
+  <Line>
+    <P Name="Name">u</P>
+    <P Name="Src">1#out:1</P>
+    <Branch><P Name="Dst">2#in:1</P></Branch>
+    <Branch><P Name="Dst">4#in:1</P></Branch>
+  </Line>
+</System>"#;
+
+#[test]
+fn flattens_blocks_parameters_and_branched_lines() {
+    let inventory = Inventory::from_system("Plant", &parse(MODEL));
+    let paths: Vec<(&str, &str, u32)> = inventory
+        .blocks
+        .iter()
+        .map(|b| (b.path.as_str(), b.system.as_str(), b.depth))
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            ("In", "", 0),
+            ("Ctrl", "", 0),
+            ("Out", "", 0),
+            ("Ctrl/K", "Ctrl", 1)
+        ]
+    );
+    assert!(inventory.blocks.iter().all(|b| b.model == "Plant"));
+
+    let gain = inventory
+        .parameters
+        .iter()
+        .find(|p| p.block_path == "Ctrl/K")
+        .unwrap();
+    assert_eq!((gain.name.as_str(), gain.value.as_str()), ("Gain", "2"));
+
+    let dsts: Vec<&str> = inventory
+        .connections
+        .iter()
+        .map(|c| c.dst_sid.as_str())
+        .collect();
+    assert_eq!(dsts, ["2", "4"]);
+    assert!(
+        inventory
+            .connections
+            .iter()
+            .all(|c| c.src_sid == "1" && c.line_name.as_deref() == Some("u"))
+    );
+}
+
+#[cfg(feature = "arrow")]
+#[test]
+fn writes_parquet_tables() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let inventory = Inventory::from_system("Plant", &parse(MODEL));
+    let batch = rustylink::inventory::arrow::blocks_batch(&inventory).unwrap();
+    assert_eq!(batch.num_rows(), 4);
+    assert_eq!(batch.schema().field(5).name(), "block_type");
+
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = Utf8Path::from_path(tmp.path()).unwrap().join("out");
+    let files = rustylink::inventory::arrow::write_parquet(&inventory, &dir).unwrap();
+    let names: Vec<&str> = files.iter().filter_map(|p| p.file_name()).collect();
+    assert_eq!(
+        names,
+        [
+            "blocks.parquet",
+            "parameters.parquet",
+            "connections.parquet"
+        ]
+    );
+    let reader = SerializedFileReader::new(std::fs::File::open(&files[2]).unwrap()).unwrap();
+    assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_tables_answer_where_used_and_hierarchy_queries() {
+    use rustylink::inventory::sqlite;
+
+    let linked = r#"<System>
+  <Block BlockType="SubSystem" Name="Outer" SID="1">
+    <System>
+      <Block BlockType="SubSystem" Name="Inner" SID="2">
+        <System>
+          <Block BlockType="Reference" Name="PI" SID="3">
+            <P Name="SourceBlock">CtrlLib/PI</P>
+          </Block>
+        </System>
+      </Block>
+    </System>
+  </Block>
+</System>"#;
+    let mut conn = sqlite::rusqlite::Connection::open_in_memory().unwrap();
+    sqlite::write(&mut conn, &Inventory::from_system("Plant", &parse(MODEL))).unwrap();
+    sqlite::write(&mut conn, &Inventory::from_system("Drive", &parse(linked))).unwrap();
+    // Writing a model again replaces its rows.
+    sqlite::write(&mut conn, &Inventory::from_system("Drive", &parse(linked))).unwrap();
+
+    let count = |sql: &str| -> i64 { conn.query_row(sql, [], |r| r.get(0)).unwrap() };
+    assert_eq!(count("SELECT COUNT(*) FROM blocks"), 7);
+    assert_eq!(count("SELECT COUNT(*) FROM lines"), 2);
+    assert_eq!(
+        sqlite::where_used(&conn, "CtrlLib/PI").unwrap(),
+        [("Drive".to_string(), "Outer/Inner/PI".to_string())]
+    );
+
+    let below_outer: Vec<(String, i64)> = conn
+        .prepare(
+            "SELECT d.path, h.distance FROM hierarchy h
+             JOIN blocks a ON a.id = h.ancestor_id JOIN blocks d ON d.id = h.descendant_id
+             WHERE a.model = 'Drive' AND a.path = 'Outer' ORDER BY h.distance",
+        )
+        .unwrap()
+        .query_map([], |r| Ok((r.get(0)?, r.get(1)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        below_outer,
+        [
+            ("Outer/Inner".to_string(), 1),
+            ("Outer/Inner/PI".to_string(), 2)
+        ]
+    );
+    let gain: String = conn
+        .query_row(
+            "SELECT p.value FROM params p JOIN blocks b ON b.id = p.block_id
+             WHERE b.path = 'Ctrl/K' AND p.name = 'Gain'",
+            [],
+            |r| r.get(0),
+        )
+        .unwrap();
+    assert_eq!(gain, "2");
+}
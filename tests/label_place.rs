@@ -87,3 +87,125 @@ fn deterministic_results() {
     assert_eq!(r1.rect.min, r2.rect.min);
     assert_eq!(r1.rect.max, r2.rect.max);
 }
+
+#[test]
+fn place_all_avoids_overlaps_and_line_crossings() {
+    // Two close parallel lines: neither label may overlap the other or sit
+    // across a line, and the result must be deterministic.
+    let upper = vec![Vec2f::new(0.0, 0.0), Vec2f::new(100.0, 0.0)];
+    let lower = vec![Vec2f::new(0.0, 20.0), Vec2f::new(100.0, 20.0)];
+    let meas = FixedMeasurer(60.0, 12.0);
+    let reqs = [
+        LabelRequest {
+            polyline: &upper,
+            text: "A",
+        },
+        LabelRequest {
+            polyline: &lower,
+            text: "B",
+        },
+    ];
+    let cfg = Config {
+        expand_factor: 1.0,
+        ..Default::default()
+    };
+    let res = place_all(&reqs, &meas, cfg, &[]);
+    assert_eq!(res.len(), 2);
+    let a = res[0].as_ref().unwrap();
+    let b = res[1].as_ref().unwrap();
+    assert!(!a.intersected && !b.intersected);
+    assert!(!a.rect.intersects(b.rect));
+    for r in [a.rect, b.rect] {
+        for y in [0.0, 20.0] {
+            assert!(
+                !(r.min.y < y && r.max.y > y),
+                "label crosses a line at y={y}: {r:?}"
+            );
+        }
+    }
+    assert_eq!(
+        place_all(&reqs, &meas, cfg, &[])[0].as_ref().unwrap().rect,
+        a.rect
+    );
+}
+
+#[test]
+fn place_all_skips_degenerate_polylines() {
+    let single = vec![Vec2f::new(0.0, 0.0)];
+    let meas = FixedMeasurer(10.0, 10.0);
+    let reqs = [LabelRequest {
+        polyline: &single,
+        text: "X",
+    }];
+    assert!(place_all(&reqs, &meas, Config::default(), &[])[0].is_none());
+}
+
+#[test]
+fn zoom_bucket_groups_nearby_zoom_levels() {
+    assert_eq!(zoom_bucket(1.0), zoom_bucket(1.1));
+    assert_ne!(zoom_bucket(1.0), zoom_bucket(1.25));
+    assert_ne!(zoom_bucket(1.0), zoom_bucket(0.9));
+    assert!(zoom_bucket(4.0) > zoom_bucket(2.0));
+    assert_eq!(zoom_bucket(0.0), zoom_bucket(-1.0));
+}
+
+#[test]
+fn placement_cache_hits_only_on_matching_key() {
+    let mut cache = PlacementCache::default();
+    assert!(cache.get(0, 1).is_none());
+    let label = CachedLabel {
+        line_index: 3,
+        text: "sig".to_string(),
+        rect: RectF::from_min_max(Vec2f::new(0.0, 0.0), Vec2f::new(10.0, 5.0)),
+        horizontal: true,
+        font_size: 10.0,
+    };
+    cache.store(0, 1, vec![label.clone()]);
+    assert_eq!(cache.get(0, 1), Some(&[label][..]));
+    assert!(cache.get(1, 1).is_none());
+    assert!(cache.get(0, 2).is_none());
+    cache.clear();
+    assert!(cache.get(0, 1).is_none());
+}
+
+#[cfg(feature = "egui")]
+#[test]
+fn viewer_places_labels_jointly_when_enabled() {
+    use eframe::egui;
+    use rustylink::egui_app::SubsystemApp;
+
+    let xml = r#"<System>
+  <Block BlockType="Inport" Name="A" SID="1"><P Name="Position">[0, 0, 30, 20]</P></Block>
+  <Block BlockType="Inport" Name="B" SID="2"><P Name="Position">[0, 30, 30, 50]</P></Block>
+  <Block BlockType="Outport" Name="C" SID="3"><P Name="Position">[200, 0, 230, 20]</P></Block>
+  <Block BlockType="Outport" Name="D" SID="4"><P Name="Position">[200, 30, 230, 50]</P></Block>
+  <Line><P Name="Name">alpha</P><P Name="Src">1#out:1</P><P Name="Dst">3#in:1</P></Line>
+  <Line><P Name="Name">beta</P><P Name="Src">2#out:1</P><P Name="Dst">4#in:1</P></Line>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let root =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    let mut app = SubsystemApp::new(root, Vec::new(), Default::default(), Default::default());
+    app.joint_label_placement = true;
+
+    let ctx = egui::Context::default();
+    let input = egui::RawInput {
+        screen_rect: Some(egui::Rect::from_min_size(
+            egui::Pos2::ZERO,
+            egui::Vec2::new(800.0, 600.0),
+        )),
+        ..Default::default()
+    };
+    let _ = ctx.run(input, |ctx| {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            rustylink::egui_app::update(&mut app, ui);
+        });
+    });
+
+    let labels = app.view_cache.label_placements.labels();
+    let mut texts: Vec<&str> = labels.iter().map(|l| l.text.as_str()).collect();
+    texts.sort();
+    assert_eq!(texts, ["alpha", "beta"]);
+    assert!(!labels[0].rect.intersects(labels[1].rect));
+}
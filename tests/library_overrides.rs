@@ -0,0 +1,128 @@
+mod common;
+
+use camino::Utf8PathBuf;
+use common::parse;
+use rustylink::model::{Block, MaskParamType};
+use rustylink::parser::{FsSource, SimulinkParser};
+use std::io::Write;
+
+/// A masked PI controller with a gain and a nested saturation.
+const LIBRARY_ROOT: &str = r#"<System>
+  <Block BlockType="SubSystem" Name="PI" SID="1">
+    <PortCounts in="1" out="1"/>
+    <Mask>
+      <MaskParameter Name="Kp" Type="edit"><Value>1</Value></MaskParameter>
+      <MaskParameter Name="Ki" Type="edit"><Value>0</Value></MaskParameter>
+    </Mask>
+    <System>
+      <Block BlockType="Gain" Name="P" SID="2"><P Name="Gain">Kp</P></Block>
+      <Block BlockType="SubSystem" Name="Limit/Clamp" SID="3">
+        <System>
+          <Block BlockType="Saturate" Name="Sat" SID="4">
+            <P Name="UpperLimit">1</P>
+          </Block>
+        </System>
+      </Block>
+    </System>
+  </Block>
+</System>"#;
+
+const INSTANCE: &str = r#"<System>
+  <Block BlockType="Reference" Name="Speed PI" SID="10">
+    <P Name="SourceBlock">CtrlLib/PI</P>
+    <P Name="SourceType">SubSystem</P>
+    <InstanceData>
+      <P Name="Kp">5</P>
+    </InstanceData>
+    <LinkData>
+      <DialogParameters BlockName="P"><P Name="Gain">2*Kp</P></DialogParameters>
+      <DialogParameters BlockName="Limit//Clamp/Sat"><P Name="UpperLimit">10</P></DialogParameters>
+    </LinkData>
+  </Block>
+  <Block BlockType="Reference" Name="Plain PI" SID="11">
+    <P Name="SourceBlock">CtrlLib/PI</P>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="12">
+    <InstanceData><P Name="ContentPreviewEnabled">off</P></InstanceData>
+  </Block>
+</System>"#;
+
+fn mask_value(block: &Block, name: &str) -> Option<String> {
+    let param = block
+        .mask
+        .as_ref()?
+        .parameters
+        .iter()
+        .find(|p| p.name == name)?;
+    assert!(matches!(param.param_type, MaskParamType::Edit));
+    param.value.clone()
+}
+
+#[test]
+fn link_data_and_instance_data_become_overrides() {
+    let system = parse(INSTANCE);
+    let overrides = system.blocks[0].library_overrides.as_ref().unwrap();
+    assert_eq!(overrides.parameters["Kp"], "5");
+    let paths: Vec<&str> = overrides.blocks.keys().map(String::as_str).collect();
+    assert_eq!(paths, ["P", "Limit//Clamp/Sat"]);
+    assert_eq!(overrides.blocks["P"]["Gain"], "2*Kp");
+
+    // Links without overrides and unlinked blocks have none.
+    assert!(system.blocks[1].library_overrides.is_none());
+    assert!(system.blocks[2].library_overrides.is_none());
+}
+
+#[test]
+fn overrides_are_applied_over_the_library_template() {
+    let template = parse(LIBRARY_ROOT).blocks.remove(0);
+    let mut instance = parse(INSTANCE).blocks.remove(0);
+    assert!(instance.instantiate_library_block(&template).is_empty());
+
+    assert_eq!(mask_value(&instance, "Kp").as_deref(), Some("5"));
+    assert_eq!(mask_value(&instance, "Ki").as_deref(), Some("0"));
+    let inner = instance.subsystem.as_deref().unwrap();
+    assert_eq!(inner.blocks[0].properties["Gain"], "2*Kp");
+    let sat = &inner.blocks[1].subsystem.as_deref().unwrap().blocks[0];
+    assert_eq!(sat.properties["UpperLimit"], "10");
+    assert_eq!(instance.port_counts.as_ref().unwrap().ins, Some(1));
+
+    // The template itself is untouched.
+    let template_inner = template.subsystem.as_deref().unwrap();
+    assert_eq!(template_inner.blocks[0].properties["Gain"], "Kp");
+    assert_eq!(mask_value(&template, "Kp").as_deref(), Some("1"));
+}
+
+#[test]
+fn unknown_override_targets_are_reported() {
+    let template = parse(LIBRARY_ROOT).blocks.remove(0);
+    let mut instance = parse(&INSTANCE.replace("BlockName=\"P\"", "BlockName=\"I\""))
+        .blocks
+        .remove(0);
+    assert_eq!(instance.instantiate_library_block(&template), ["I"]);
+}
+
+#[test]
+fn resolving_references_applies_overrides() {
+    let tmp = tempfile::tempdir().unwrap();
+    let dir = Utf8PathBuf::from_path_buf(tmp.path().to_path_buf()).unwrap();
+    let file = std::fs::File::create(dir.join("CtrlLib.slx")).unwrap();
+    let mut zip = zip::ZipWriter::new(file);
+    zip.start_file(
+        "simulink/systems/system_root.xml",
+        zip::write::FileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(LIBRARY_ROOT.as_bytes()).unwrap();
+    zip.finish().unwrap();
+
+    let mut system = parse(INSTANCE);
+    SimulinkParser::<FsSource>::resolve_library_references(&mut system, &[dir]).unwrap();
+
+    let (speed, plain) = (&system.blocks[0], &system.blocks[1]);
+    assert_eq!(speed.library_source.as_deref(), Some("CtrlLib"));
+    assert_eq!(mask_value(speed, "Kp").as_deref(), Some("5"));
+    assert_eq!(mask_value(plain, "Kp").as_deref(), Some("1"));
+    let gain = |b: &Block| b.subsystem.as_deref().unwrap().blocks[0].properties["Gain"].clone();
+    assert_eq!(gain(speed), "2*Kp");
+    assert_eq!(gain(plain), "Kp");
+}
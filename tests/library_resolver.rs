@@ -194,6 +194,7 @@ fn resolve_virtual_simulink_logic_and_bit() {
             position: None,
             zorder: None,
             commented: false,
+            comment_through: false,
             name_location: Default::default(),
             is_matlab_function: false,
             value: None,
@@ -201,7 +202,7 @@ fn resolve_virtual_simulink_logic_and_bit() {
             value_rows: None,
             value_cols: None,
             properties: {
-                let mut m = IndexMap::new();
+                let mut m = rustylink::model::PropertyBag::new();
                 m.insert(
                     "SourceBlock".to_string(),
                     "simulink/Logic and Bit/SomeBlock".to_string(),
@@ -222,12 +223,18 @@ fn resolve_virtual_simulink_logic_and_bit() {
             show_name: None,
             font_size: None,
             font_weight: None,
+            foreground_color: None,
+            drop_shadow: false,
+            font_name: None,
             mask_display_text: None,
             current_setting: None,
             block_mirror: None,
+            orientation: rustylink::model::BlockOrientation::Right,
             library_source: None,
             library_block_path: None,
+            library_overrides: None,
             dashboard_binding: None,
+            workspace_values: Default::default(),
             child_order: vec![],
         }],
         lines: Vec::new(),
@@ -405,6 +412,7 @@ fn resolve_virtual_matrix_library_blocks() {
                 position: None,
                 zorder: None,
                 commented: false,
+                comment_through: false,
                 name_location: Default::default(),
                 is_matlab_function: false,
                 value: None,
@@ -412,7 +420,7 @@ fn resolve_virtual_matrix_library_blocks() {
                 value_rows: None,
                 value_cols: None,
                 properties: {
-                    let mut m = IndexMap::new();
+                    let mut m = rustylink::model::PropertyBag::new();
                     m.insert(
                         "SourceBlock".to_string(),
                         "matrix_library/IsTriangular".to_string(),
@@ -433,12 +441,18 @@ fn resolve_virtual_matrix_library_blocks() {
                 show_name: None,
                 font_size: None,
                 font_weight: None,
+                foreground_color: None,
+                drop_shadow: false,
+                font_name: None,
                 mask_display_text: None,
                 current_setting: None,
                 block_mirror: None,
+                orientation: rustylink::model::BlockOrientation::Right,
                 library_source: None,
                 library_block_path: None,
+                library_overrides: None,
                 dashboard_binding: None,
+                workspace_values: Default::default(),
                 child_order: vec![],
             },
             Block {
@@ -449,6 +463,7 @@ fn resolve_virtual_matrix_library_blocks() {
                 position: None,
                 zorder: None,
                 commented: false,
+                comment_through: false,
                 name_location: Default::default(),
                 is_matlab_function: false,
                 value: None,
@@ -456,7 +471,7 @@ fn resolve_virtual_matrix_library_blocks() {
                 value_rows: None,
                 value_cols: None,
                 properties: {
-                    let mut m = IndexMap::new();
+                    let mut m = rustylink::model::PropertyBag::new();
                     m.insert(
                         "SourceBlock".to_string(),
                         "matrix_library/IdentityMatrix".to_string(),
@@ -477,12 +492,18 @@ fn resolve_virtual_matrix_library_blocks() {
                 show_name: None,
                 font_size: None,
                 font_weight: None,
+                foreground_color: None,
+                drop_shadow: false,
+                font_name: None,
                 mask_display_text: None,
                 current_setting: None,
                 block_mirror: None,
+                orientation: rustylink::model::BlockOrientation::Right,
                 library_source: None,
                 library_block_path: None,
+                library_overrides: None,
                 dashboard_binding: None,
+                workspace_values: Default::default(),
                 child_order: vec![],
             },
             Block {
@@ -493,6 +514,7 @@ fn resolve_virtual_matrix_library_blocks() {
                 position: None,
                 zorder: None,
                 commented: false,
+                comment_through: false,
                 name_location: Default::default(),
                 is_matlab_function: false,
                 value: None,
@@ -500,7 +522,7 @@ fn resolve_virtual_matrix_library_blocks() {
                 value_rows: None,
                 value_cols: None,
                 properties: {
-                    let mut m = IndexMap::new();
+                    let mut m = rustylink::model::PropertyBag::new();
                     m.insert(
                         "SourceBlock".to_string(),
                         "matrix_library/PermuteColumns".to_string(),
@@ -521,12 +543,18 @@ fn resolve_virtual_matrix_library_blocks() {
                 show_name: None,
                 font_size: None,
                 font_weight: None,
+                foreground_color: None,
+                drop_shadow: false,
+                font_name: None,
                 mask_display_text: None,
                 current_setting: None,
                 block_mirror: None,
+                orientation: rustylink::model::BlockOrientation::Right,
                 library_source: None,
                 library_block_path: None,
+                library_overrides: None,
                 dashboard_binding: None,
+                workspace_values: Default::default(),
                 child_order: vec![],
             },
         ],
@@ -0,0 +1,382 @@
+mod common;
+
+use common::parse;
+use rustylink::lint::policy::counts_per_subsystem;
+use rustylink::lint::{
+    Finding, Gate, LintConfig, Ruleset, Severity, findings_at, lint, lint_with, to_junit,
+};
+use rustylink::model::System;
+
+fn model(gain: &str) -> System {
+    parse(&format!(
+        r#"<System>
+  <Block BlockType="SubSystem" Name="Ctrl" SID="1">
+    <System>
+      <Block BlockType="Gain" Name="K" SID="2">
+        <P Name="Gain">{gain}</P>
+      </Block>
+    </System>
+  </Block>
+  <Block BlockType="Gain" Name="Ctrl2" SID="3">
+    <P Name="Gain">Kp</P>
+  </Block>
+</System>"#
+    ))
+}
+
+#[test]
+fn findings_are_reported_at_blocks_and_their_subsystems() {
+    let findings = lint(&model("2.5"));
+    assert_eq!(findings.len(), 1);
+    assert_eq!(findings[0].block, "Ctrl/K");
+    assert_eq!(findings[0].severity, Severity::Information);
+    assert_eq!(findings[0].source, "magic-number");
+
+    assert_eq!(findings_at(&findings, "Ctrl/K").count(), 1);
+    assert_eq!(findings_at(&findings, "Ctrl").count(), 1);
+    // A block whose name only starts like the subsystem's is not inside it.
+    assert_eq!(findings_at(&findings, "Ctrl2").count(), 0);
+    assert!(lint(&model("Kp")).is_empty());
+}
+
+#[test]
+fn gates_count_warnings_and_listed_rules() {
+    let finding = |severity, source: &str| Finding {
+        block: "Ctrl/K".to_string(),
+        severity,
+        source: source.to_string(),
+        message: String::new(),
+    };
+    let findings = [
+        finding(Severity::Warning, "rate"),
+        finding(Severity::Warning, "rate"),
+        finding(Severity::Information, "magic-number"),
+    ];
+    assert_eq!(Gate::default().check(&findings), None);
+    let gate = |max_warnings, fail_on: &[&str]| Gate {
+        max_warnings,
+        fail_on: fail_on.iter().map(|r| r.to_string()).collect(),
+    };
+    assert_eq!(gate(Some(2), &[]).check(&findings), None);
+    assert_eq!(
+        gate(Some(1), &[]).check(&findings).as_deref(),
+        Some("2 warning(s), at most 1 allowed")
+    );
+    // Information only fails when its rule is listed
+    assert_eq!(
+        gate(None, &["magic-number"]).check(&findings).as_deref(),
+        Some("1 magic-number finding(s)")
+    );
+    assert_eq!(gate(None, &["bus"]).check(&findings), None);
+    assert_eq!(
+        Gate::default()
+            .check(&[finding(Severity::Error, "rate")])
+            .as_deref(),
+        Some("1 error(s)")
+    );
+}
+
+#[test]
+fn junit_reports_every_rule_and_system_level() {
+    let system = model("2.5");
+    let rules = LintConfig::default().rules();
+    let xml = to_junit(&system, &lint(&system), &rules, "plant");
+    let doc = roxmltree::Document::parse(&xml).unwrap();
+    let root = doc.root_element();
+    assert_eq!(root.attribute("tests"), Some("4"));
+    assert_eq!(root.attribute("failures"), Some("1"));
+    let suites: Vec<_> = root
+        .children()
+        .filter(|n| n.is_element())
+        .map(|n| n.attribute("name").unwrap())
+        .collect();
+    assert_eq!(suites, ["rate", "magic-number"]);
+
+    let cases: Vec<_> = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("testcase"))
+        .collect();
+    assert_eq!(cases.len(), 4);
+    let failed: Vec<_> = cases
+        .iter()
+        .filter(|c| c.children().any(|n| n.has_tag_name("failure")))
+        .collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].attribute("classname"), Some("magic-number"));
+    assert_eq!(failed[0].attribute("name"), Some("plant/Ctrl"));
+    let failure = failed[0].first_element_child().unwrap();
+    assert_eq!(failure.attribute("type"), Some("information"));
+    assert!(
+        failure
+            .text()
+            .unwrap()
+            .starts_with("Ctrl/K: information: Gain = 2.5")
+    );
+}
+
+#[test]
+fn naming_rules_report_mismatches_outside_excluded_paths() {
+    let system = parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="u_speed" SID="1"/>
+  <Block BlockType="Inport" Name="torque" SID="2"/>
+  <Block BlockType="SubSystem" Name="Legacy" SID="3">
+    <System>
+      <Block BlockType="Inport" Name="In1" SID="4"/>
+    </System>
+  </Block>
+  <Line>
+    <P Name="Name">speed</P>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">3#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Name">sig_torque</P>
+    <P Name="Src">2#out:1</P>
+    <P Name="Dst">3#in:1</P>
+  </Line>
+</System>"#,
+    );
+    let config: LintConfig = serde_json::from_str(
+        r#"{"naming": [
+            {"name": "inport-prefix", "applies_to": "inports", "pattern": "^u_",
+             "severity": "error", "exclude": ["Legacy"]},
+            {"name": "signal-prefix", "applies_to": "signals", "pattern": "^sig_",
+             "message": "guideline 4.2"}
+        ]}"#,
+    )
+    .unwrap();
+    assert_eq!(
+        config.rules(),
+        ["rate", "magic-number", "inport-prefix", "signal-prefix"]
+    );
+    let findings: Vec<_> = lint_with(&system, &config)
+        .into_iter()
+        .filter(|f| f.source != "magic-number")
+        .map(|f| (f.source, f.block, f.severity, f.message))
+        .collect();
+    assert_eq!(
+        findings,
+        [
+            (
+                "inport-prefix".to_string(),
+                "torque".to_string(),
+                Severity::Error,
+                r#"Inport "torque" does not match ^u_"#.to_string()
+            ),
+            (
+                "signal-prefix".to_string(),
+                "u_speed".to_string(),
+                Severity::Warning,
+                r#"signal "speed": guideline 4.2"#.to_string()
+            ),
+        ]
+    );
+
+    let invalid = serde_json::from_str::<LintConfig>(
+        r#"{"naming": [{"name": "x", "applies_to": "blocks", "pattern": "("}]}"#,
+    );
+    assert!(invalid.is_err());
+}
+
+#[test]
+fn maab_ruleset_reports_guideline_violations() {
+    let system = parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="1st in" SID="1">
+    <P Name="Position">[20, 100, 50, 114]</P>
+  </Block>
+  <Block BlockType="Inport" Name="u2" SID="2">
+    <P Name="Position">[20, 20, 50, 34]</P>
+    <P Name="Port">2</P>
+  </Block>
+  <Block BlockType="Outport" Name="y" SID="3">
+    <P Name="Position">[300, 50, 330, 64]</P>
+  </Block>
+  <Block BlockType="Integrator" Name="Int" SID="4">
+    <P Name="Position">[150, 20, 180, 50]</P>
+  </Block>
+  <Block BlockType="ToWorkspace" Name="Log" SID="5">
+    <P Name="Position">[150, 100, 180, 130]</P>
+  </Block>
+  <Block BlockType="SubSystem" Name="Ctrl_" SID="6">
+    <System/>
+  </Block>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">4#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">2#out:1</P>
+    <P Name="Dst">5#in:1</P>
+  </Line>
+</System>"#,
+    );
+    let config: LintConfig = serde_json::from_str(r#"{"rulesets": ["maab"]}"#).unwrap();
+    assert_eq!(config.rules().len(), 8);
+    assert_eq!("maab".parse::<Ruleset>().unwrap(), Ruleset::Maab);
+    let findings: Vec<_> = lint_with(&system, &config)
+        .into_iter()
+        .filter(|f| Ruleset::Maab.rules().contains(&f.source.as_str()))
+        .map(|f| (f.source, f.block, f.severity))
+        .collect();
+    let expected = [
+        ("jc_0211", "1st in", Severity::Warning),
+        ("jm_0001", "Int", Severity::Warning),
+        ("hd_0001", "Log", Severity::Warning),
+        ("jc_0201", "Ctrl_", Severity::Warning),
+        ("db_0042", "u2", Severity::Warning),
+        ("db_0032", "u2", Severity::Information),
+    ]
+    .map(|(rule, block, severity)| (rule.to_string(), block.to_string(), severity));
+    assert_eq!(findings, expected);
+    assert!(
+        lint_with(&system, &LintConfig::default())
+            .iter()
+            .all(|f| !f.source.contains('_'))
+    );
+}
+
+#[test]
+fn block_policy_reports_forbidden_unlisted_blocks_and_required_properties() {
+    let system = parse(
+        r#"<System>
+  <Block BlockType="MATLABFcn" Name="Fcn" SID="1"/>
+  <Block BlockType="Gain" Name="K" SID="2"/>
+  <Block BlockType="SubSystem" Name="Ctrl" SID="3">
+    <System>
+      <Block BlockType="Gain" Name="K2" SID="4">
+        <P Name="SaturateOnIntegerOverflow">off</P>
+      </Block>
+      <Block BlockType="Scope" Name="Scope" SID="5"/>
+    </System>
+  </Block>
+  <Block BlockType="SubSystem" Name="Test" SID="6">
+    <System>
+      <Block BlockType="Scope" Name="Scope" SID="7"/>
+    </System>
+  </Block>
+</System>"#,
+    );
+    let config: LintConfig = serde_json::from_str(
+        r#"{"blocks": {
+            "forbidden": [
+                {"type": "MATLABFcn", "severity": "error", "remedy": "use a MATLAB Function block"},
+                {"type": "Scope", "exclude": ["Test"]}
+            ],
+            "allowed": {"types": ["Gain", "SubSystem", "Scope"], "severity": "information"},
+            "required": [{"type": "Gain", "property": "SaturateOnIntegerOverflow", "value": "on"}]
+        }}"#,
+    )
+    .unwrap();
+    assert!(config.rules().contains(&"unlisted-block".to_string()));
+    let findings = lint_with(&system, &config);
+    let found: Vec<_> = findings
+        .iter()
+        .map(|f| (f.source.as_str(), f.block.as_str(), f.severity))
+        .collect();
+    assert_eq!(
+        found,
+        [
+            ("forbidden-block", "Fcn", Severity::Error),
+            ("unlisted-block", "Fcn", Severity::Information),
+            ("required-property", "K", Severity::Warning),
+            ("required-property", "Ctrl/K2", Severity::Warning),
+            ("forbidden-block", "Ctrl/Scope", Severity::Warning),
+        ]
+    );
+    assert_eq!(
+        findings[0].message,
+        "MATLABFcn blocks are not allowed; use a MATLAB Function block"
+    );
+    assert_eq!(
+        findings[3].message,
+        "SaturateOnIntegerOverflow is off, it must be on"
+    );
+    let counts = counts_per_subsystem(&findings);
+    assert_eq!(counts.get(""), Some(&3));
+    assert_eq!(counts.get("Ctrl"), Some(&2));
+    assert_eq!(counts.len(), 2);
+
+    let typo =
+        serde_json::from_str::<LintConfig>(r#"{"blocks": {"forbidden": [{"typ": "Scope"}]}}"#);
+    assert!(typo.is_err());
+}
+
+#[cfg(feature = "egui")]
+#[test]
+fn editor_lints_in_the_background_after_edits() {
+    use rustylink::editor::{EditorCommand, EditorState};
+    use std::collections::BTreeMap;
+    use std::time::{Duration, Instant};
+
+    fn settle(state: &mut EditorState) {
+        let start = Instant::now();
+        while state.lint.poll(&state.app.root, state.history.revision()) {
+            assert!(start.elapsed() < Duration::from_secs(10));
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    let mut state = EditorState::new(model("Kp"), vec![], BTreeMap::new(), BTreeMap::new());
+    settle(&mut state);
+    assert!(state.lint.findings.is_empty());
+
+    // An edit the history records.
+    state.app.root.blocks[1]
+        .properties
+        .insert("Gain".to_string(), "3".to_string());
+    state.history.push(EditorCommand::Batch(Vec::new()));
+    settle(&mut state);
+    assert_eq!(
+        state.lint.worst_at("Ctrl2"),
+        Some((Severity::Information, 1))
+    );
+    assert_eq!(state.lint.worst_at("Ctrl"), None);
+}
+
+#[test]
+fn pattern_rules_report_matches_at_their_first_block() {
+    let system = parse(
+        r#"<System>
+  <Block BlockType="Gain" Name="K" SID="1"/>
+  <Block BlockType="Saturate" Name="Sat" SID="2"/>
+  <Block BlockType="SubSystem" Name="Legacy" SID="3">
+    <System>
+      <Block BlockType="Gain" Name="K" SID="4"/>
+      <Block BlockType="Saturate" Name="Sat" SID="5"/>
+      <Line><P Name="Src">4#out:1</P><P Name="Dst">5#in:1</P></Line>
+    </System>
+  </Block>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+</System>"#,
+    );
+    let config: LintConfig = serde_json::from_str(
+        r#"{"patterns": [
+            {"name": "gain-saturate", "pattern": "Gain -> Saturate", "exclude": ["Legacy"],
+             "message": "fold the gain into the limits"}
+        ]}"#,
+    )
+    .unwrap();
+    assert!(config.rules().contains(&"gain-saturate".to_string()));
+    let findings: Vec<_> = lint_with(&system, &config)
+        .into_iter()
+        .filter(|f| f.source == "gain-saturate")
+        .collect();
+    assert_eq!(
+        findings,
+        [Finding {
+            block: "K".to_string(),
+            severity: Severity::Warning,
+            source: "gain-saturate".to_string(),
+            message:
+                "Blocks \"K\", \"Sat\" match pattern gain-saturate: fold the gain into the limits"
+                    .to_string(),
+        }]
+    );
+
+    let invalid = serde_json::from_str::<LintConfig>(
+        r#"{"patterns": [{"name": "x", "pattern": "Gain ->"}]}"#,
+    );
+    assert!(invalid.is_err());
+}
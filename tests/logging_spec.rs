@@ -0,0 +1,119 @@
+mod common;
+
+use common::parse;
+use rustylink::logging_spec::{LOGGING_SPEC_VERSION, logging_spec};
+use rustylink::mat_file::{MatValue, read_mat};
+use rustylink::model::System;
+
+fn model() -> System {
+    let mut root = parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="speed" SID="1">
+    <PortProperties>
+      <Port Type="out" Index="1">
+        <P Name="Name">v</P>
+        <P Name="DataLogging">on</P>
+        <P Name="DataLoggingDecimateData">on</P>
+        <P Name="DataLoggingDecimation">10</P>
+        <P Name="DataLoggingLimitDataPoints">on</P>
+        <P Name="DataLoggingMaxPoints">5000</P>
+      </Port>
+    </PortProperties>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="2">
+    <PortProperties>
+      <Port Type="out" Index="1">
+        <P Name="TestPoint">on</P>
+        <P Name="DataLoggingDecimation">10</P>
+        <P Name="DataLoggingMaxPoints">5000</P>
+      </Port>
+    </PortProperties>
+  </Block>
+  <Block BlockType="Gain" Name="Unlogged" SID="3">
+    <PortProperties>
+      <Port Type="out" Index="1"><P Name="DataLogging">off</P></Port>
+    </PortProperties>
+  </Block>
+  <Block BlockType="SubSystem" Name="Ctrl" SID="4"/>
+  <Line>
+    <P Name="Name">scaled</P>
+    <P Name="Src">2#out:1</P>
+    <P Name="Dst">3#in:1</P>
+  </Line>
+</System>"#,
+    );
+    let inner = parse(
+        r#"<System>
+  <Block BlockType="Sum" Name="Err" SID="5">
+    <PortProperties>
+      <Port Type="out" Index="1">
+        <P Name="DataLogging">on</P>
+        <P Name="DataLoggingNameMode">Custom</P>
+        <P Name="DataLoggingName">ctrl_error</P>
+        <P Name="DataLoggingSampleTime">0.01</P>
+      </Port>
+    </PortProperties>
+  </Block>
+  <Block BlockType="Gain" Name="P" SID="6">
+    <PortProperties>
+      <Port Type="out" Index="1"><P Name="DataLogging">on</P></Port>
+    </PortProperties>
+  </Block>
+</System>"#,
+    );
+    root.blocks[3].subsystem = Some(Box::new(inner));
+    root
+}
+
+#[test]
+fn collects_logged_signals_and_test_points() {
+    let spec = logging_spec(&model(), "Plant");
+    assert_eq!(spec.version, LOGGING_SPEC_VERSION);
+    assert_eq!(spec.model, "Plant");
+    let names: Vec<&str> = spec
+        .signals
+        .iter()
+        .map(|s| s.logging_name.as_str())
+        .collect();
+    assert_eq!(names, ["v", "scaled", "ctrl_error", "Ctrl/P:1"]);
+
+    let speed = &spec.signals[0];
+    assert!(speed.logged && !speed.test_point);
+    assert_eq!((speed.decimation, speed.max_points), (10, Some(5000)));
+    assert_eq!(speed.sample_time, "-1");
+
+    // Decimation and limits only apply when enabled.
+    let gain = &spec.signals[1];
+    assert!(!gain.logged && gain.test_point);
+    assert_eq!(gain.signal, "scaled");
+    assert_eq!((gain.decimation, gain.max_points), (1, None));
+
+    let err = &spec.signals[2];
+    assert_eq!(err.block, "Ctrl/Err");
+    assert_eq!(err.signal, "");
+    assert_eq!(err.sample_time, "0.01");
+}
+
+#[test]
+fn spec_exports_as_json_and_mat() {
+    let spec = logging_spec(&model(), "Plant");
+    let json: serde_json::Value = serde_json::from_str(&spec.to_json().unwrap()).unwrap();
+    assert_eq!(json["signals"][0]["loggingName"], "v");
+    assert_eq!(json["signals"][0]["maxPoints"], 5000);
+    assert_eq!(json["signals"][1]["maxPoints"], serde_json::Value::Null);
+
+    let vars = read_mat(&spec.to_mat()).unwrap();
+    let root = &vars["loggingSpec"];
+    assert_eq!(
+        root.field("model").and_then(MatValue::as_str),
+        Some("Plant")
+    );
+    let Some(MatValue::Struct { dims, elements }) = root.field("signals") else {
+        panic!("signals is not a struct array");
+    };
+    assert_eq!(dims, &[1, 4]);
+    assert_eq!(elements[2]["loggingName"].as_str(), Some("ctrl_error"));
+    assert_eq!(elements[0]["maxPoints"].as_scalar(), Some(5000.0));
+    assert_eq!(elements[1]["maxPoints"].as_scalar(), Some(f64::INFINITY));
+    assert_eq!(elements[1]["testPoint"].as_scalar(), Some(1.0));
+}
@@ -1,6 +1,5 @@
-use indexmap::IndexMap;
 use rustylink::mask_eval::evaluate_mask_display;
-use rustylink::model::{Block, Mask, MaskParamType, MaskParameter};
+use rustylink::model::{Block, Mask, MaskParamType, MaskParameter, PropertyBag};
 
 #[test]
 fn test_eval_simple() {
@@ -12,9 +11,10 @@ fn test_eval_simple() {
         position: None,
         zorder: None,
         commented: false,
+        comment_through: false,
         name_location: rustylink::model::NameLocation::Bottom,
         is_matlab_function: false,
-        properties: IndexMap::new(),
+        properties: PropertyBag::new(),
         ref_properties: Default::default(),
         port_counts: None,
         ports: vec![],
@@ -47,6 +47,9 @@ fn test_eval_simple() {
         show_name: None,
         font_size: None,
         font_weight: None,
+        foreground_color: None,
+        drop_shadow: false,
+        font_name: None,
         mask_display_text: None,
         value: None,
         value_kind: rustylink::model::ValueKind::Unknown,
@@ -54,9 +57,12 @@ fn test_eval_simple() {
         value_cols: None,
         current_setting: None,
         block_mirror: None,
+        orientation: rustylink::model::BlockOrientation::Right,
         library_source: None,
         library_block_path: None,
+        library_overrides: None,
         dashboard_binding: None,
+        workspace_values: Default::default(),
         child_order: vec![],
     };
     evaluate_mask_display(&mut block);
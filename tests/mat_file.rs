@@ -0,0 +1,207 @@
+use rustylink::mask_eval::resolve_numeric_parameter;
+use rustylink::mat_file::{MatValue, read_mat, read_mat_with_limits};
+use rustylink::model::SlxArchive;
+use rustylink::parser::{LimitExceeded, LimitKind, ParseLimits};
+use rustylink::workspace::ModelWorkspace;
+use std::io::{Cursor, Write};
+
+// ── Tiny little-endian MAT v5 writer for test fixtures ─────────────────────
+
+fn element(ty: u32, payload: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&ty.to_le_bytes());
+    out.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    out.extend_from_slice(payload);
+    while out.len() % 8 != 0 {
+        out.push(0);
+    }
+    out
+}
+
+fn matrix(class: u32, dims: &[i32], name: &str, data: &[Vec<u8>]) -> Vec<u8> {
+    let mut body = element(6, &[class.to_le_bytes(), 0u32.to_le_bytes()].concat());
+    let dims: Vec<u8> = dims.iter().flat_map(|d| d.to_le_bytes()).collect();
+    body.extend(element(5, &dims));
+    body.extend(element(1, name.as_bytes()));
+    for d in data {
+        body.extend_from_slice(d);
+    }
+    element(14, &body)
+}
+
+fn doubles(name: &str, dims: &[i32], values: &[f64]) -> Vec<u8> {
+    let raw: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    matrix(6, dims, name, &[element(9, &raw)])
+}
+
+fn chars(name: &str, s: &str) -> Vec<u8> {
+    let raw: Vec<u8> = s.encode_utf16().flat_map(|c| c.to_le_bytes()).collect();
+    matrix(4, &[1, s.len() as i32], name, &[element(4, &raw)])
+}
+
+fn mat_file(vars: &[Vec<u8>]) -> Vec<u8> {
+    let mut header = b"MATLAB 5.0 MAT-file, created by rustylink tests".to_vec();
+    header.resize(116, b' ');
+    header.extend_from_slice(&[0; 8]);
+    header.extend_from_slice(&0x0100u16.to_le_bytes());
+    header.extend_from_slice(b"IM");
+    for v in vars {
+        header.extend_from_slice(v);
+    }
+    header
+}
+
+fn compressed(var: &[u8]) -> Vec<u8> {
+    let mut enc = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::default());
+    enc.write_all(var).unwrap();
+    let z = enc.finish().unwrap();
+    let mut out = Vec::new();
+    out.extend_from_slice(&15u32.to_le_bytes());
+    out.extend_from_slice(&(z.len() as u32).to_le_bytes());
+    out.extend_from_slice(&z);
+    out
+}
+
+fn params_struct() -> Vec<u8> {
+    // struct with fields "gain" (scalar) and "name" (char)
+    let field_len = 8i32;
+    let mut names = Vec::new();
+    for f in ["gain", "name"] {
+        let mut n = f.as_bytes().to_vec();
+        n.resize(field_len as usize, 0);
+        names.extend(n);
+    }
+    matrix(
+        2,
+        &[1, 1],
+        "params",
+        &[
+            element(5, &field_len.to_le_bytes()),
+            element(1, &names),
+            doubles("", &[1, 1], &[4.0]),
+            chars("", "ctrl"),
+        ],
+    )
+}
+
+#[test]
+fn reads_doubles_chars_and_structs() {
+    let data = mat_file(&[
+        doubles("K", &[1, 1], &[2.5]),
+        doubles("M", &[2, 2], &[1.0, 3.0, 2.0, 4.0]),
+        chars("label", "hello"),
+        params_struct(),
+    ]);
+    let vars = read_mat(&data).unwrap();
+    assert_eq!(
+        vars.keys().collect::<Vec<_>>(),
+        vec!["K", "M", "label", "params"]
+    );
+    assert_eq!(vars["K"].as_scalar(), Some(2.5));
+    assert_eq!(vars["M"].to_matlab_literal().as_deref(), Some("[1 2;3 4]"));
+    assert_eq!(vars["label"].as_str(), Some("hello"));
+    let params = &vars["params"];
+    assert_eq!(params.field("gain").and_then(|v| v.as_scalar()), Some(4.0));
+    assert_eq!(params.field("name").and_then(|v| v.as_str()), Some("ctrl"));
+}
+
+#[test]
+fn reads_compressed_variables() {
+    let data = mat_file(&[compressed(&doubles("Ts", &[1, 1], &[0.01]))]);
+    let vars = read_mat(&data).unwrap();
+    assert_eq!(vars["Ts"].as_scalar(), Some(0.01));
+}
+
+#[test]
+fn rejects_non_mat_data() {
+    assert!(read_mat(b"not a mat file").is_err());
+}
+
+#[test]
+fn workspace_resolves_parameters_from_archive() {
+    let mat = mat_file(&[
+        compressed(&params_struct()),
+        doubles("table", &[1, 3], &[10.0, 20.0, 30.0]),
+        doubles("K", &[1, 1], &[0.5]),
+    ]);
+    let root = r#"<System>
+  <Block BlockType="Gain" Name="G" SID="1"><P Name="Gain">params.gain</P></Block>
+  <Block BlockType="SubSystem" Name="Masked" SID="2">
+    <P Name="Position">[0, 0, 30, 30]</P>
+    <Mask><MaskParameter Name="k" Type="edit"><Value>-table(3)</Value></MaskParameter></Mask>
+  </Block>
+</System>"#;
+    let mut buf = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        let files = [
+            ("simulink/systems/system_root.xml", root.as_bytes()),
+            ("simulink/modelWorkspace.mat", &mat),
+        ];
+        for (name, data) in files {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    let archive = SlxArchive::from_reader(Cursor::new(buf.into_inner())).unwrap();
+    let ws = ModelWorkspace::from_archive(&archive).unwrap();
+
+    assert_eq!(ws.resolve_numeric("K"), Some(0.5));
+    assert_eq!(ws.resolve_numeric("-params.gain"), Some(-4.0));
+    assert_eq!(ws.resolve_numeric("table(2)"), Some(20.0));
+    assert_eq!(ws.resolve_numeric("3"), Some(3.0));
+    assert_eq!(ws.resolve_numeric("-(-(K))"), Some(0.5));
+    assert_eq!(ws.resolve_numeric("missing"), None);
+    assert!(matches!(ws.get("params.name"), Some(MatValue::Char(s)) if s == "ctrl"));
+    // Deep nesting neither overflows the stack nor resolves
+    assert_eq!(ws.resolve_numeric(&"-".repeat(1_000_000)), None);
+    assert_eq!(ws.resolve_numeric(&"(".repeat(1_000_000)), None);
+    let wrapped = format!("{}K{}", "(".repeat(100_000), ")".repeat(100_000));
+    assert_eq!(ws.resolve_numeric(&wrapped), Some(0.5));
+
+    // Parsing the archive resolves block and mask parameters
+    let root = archive.root_system().unwrap();
+    let gain = &root.blocks[0];
+    assert_eq!(gain.workspace_values.get("Gain"), Some(&4.0));
+    assert_eq!(resolve_numeric_parameter(gain, "Gain", &ws), Some(4.0));
+    let masked = &root.blocks[1];
+    assert_eq!(masked.workspace_values.get("k"), Some(&-30.0));
+    assert!(!masked.workspace_values.contains_key("Position"));
+}
+
+#[test]
+fn rejects_corrupt_dimensions_nesting_and_sizes() {
+    // Dimensions whose product overflows, and counts the data cannot hold
+    let huge = [i32::MAX, i32::MAX, i32::MAX];
+    assert!(read_mat(&mat_file(&[matrix(1, &huge, "c", &[])])).is_err());
+    assert!(read_mat(&mat_file(&[matrix(1, &[1000, 1000], "c", &[])])).is_err());
+    let field = [element(5, &8i32.to_le_bytes()), element(1, b"gain\0\0\0\0")];
+    assert!(read_mat(&mat_file(&[matrix(2, &[1, 1 << 30], "s", &field)])).is_err());
+
+    // Cells nested deeper than the limit
+    let mut nested = doubles("", &[1, 1], &[1.0]);
+    for _ in 0..20 {
+        nested = matrix(1, &[1, 1], "", &[nested]);
+    }
+    let limits = ParseLimits {
+        max_depth: 10,
+        ..ParseLimits::default()
+    };
+    let data = mat_file(&[nested]);
+    let err = read_mat_with_limits(&data, &limits).unwrap_err();
+    let exceeded = err.downcast_ref::<LimitExceeded>().unwrap();
+    assert_eq!(exceeded.kind, LimitKind::Depth);
+    assert!(read_mat(&data).is_ok());
+
+    // Compressed variables inflating past the size limit
+    let data = mat_file(&[compressed(&doubles("big", &[1, 4096], &[0.0; 4096]))]);
+    let limits = ParseLimits {
+        max_file_size: 1024,
+        ..ParseLimits::default()
+    };
+    let err = read_mat_with_limits(&data, &limits).unwrap_err();
+    let exceeded = err.downcast_ref::<LimitExceeded>().unwrap();
+    assert_eq!(exceeded.kind, LimitKind::FileSize);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::parse;
+use rustylink::model::System;
+use serde_json::Value;
+
+const MODEL: &str = r#"<System>
+  <P Name="ZoomFactor">100</P>
+  <Block BlockType="Inport" Name="In" SID="1"/>
+  <Block BlockType="SubSystem" Name="Ctrl/A" SID="2">
+    <System>
+      <Block BlockType="Gain" Name="K" SID="3"><P Name="Gain">2</P></Block>
+      <Annotation SID="4"><P Name="Name">note</P></Annotation>
+    </System>
+  </Block>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">2#in:1</P>
+  </Line>
+</System>"#;
+
+fn records(system: &System) -> Vec<Value> {
+    let mut out = Vec::new();
+    rustylink::ndjson::write_records(system, &mut out).unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.ends_with('\n'));
+    text.lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn writes_one_record_per_item_with_its_system_path() {
+    let records = records(&parse(MODEL));
+    let summary: Vec<(&str, &str)> = records
+        .iter()
+        .map(|r| (r["kind"].as_str().unwrap(), r["path"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("system", ""),
+            ("block", ""),
+            ("block", ""),
+            ("line", ""),
+            ("system", "Ctrl//A"),
+            ("block", "Ctrl//A"),
+            ("annotation", "Ctrl//A"),
+        ]
+    );
+    assert_eq!(records[0]["properties"]["ZoomFactor"], "100");
+    assert_eq!(records[3]["src"]["sid"], "1");
+    assert_eq!(records[5]["name"], "K");
+    assert_eq!(records[5]["properties"]["Gain"], "2");
+}
+
+#[test]
+fn subsystem_content_is_not_repeated_in_block_records() {
+    let records = records(&parse(MODEL));
+    let sub = &records[2];
+    assert_eq!(sub["type"], "SubSystem");
+    assert!(sub.get("subsystem").is_none());
+}
@@ -0,0 +1,67 @@
+use rustylink::color::Rgba;
+use rustylink::overlay::{Gradient, MetricOverlay, block_path, render_svg};
+use std::collections::HashMap;
+
+fn overlay(values: &[(&str, f32)]) -> MetricOverlay {
+    let values: HashMap<String, f32> = values.iter().map(|(k, v)| (k.to_string(), *v)).collect();
+    MetricOverlay::new("CPU load", values)
+}
+
+#[test]
+fn gradient_interpolates_between_stops() {
+    let g = Gradient::new([(1.0, Rgba::rgb(255, 255, 255)), (0.0, Rgba::rgb(0, 0, 0))]);
+    assert_eq!(g.color_at(0.0), Rgba::rgb(0, 0, 0));
+    assert_eq!(g.color_at(0.5), Rgba::rgb(128, 128, 128));
+    assert_eq!(g.color_at(2.0), Rgba::rgb(255, 255, 255));
+    assert_eq!(g.color_at(f32::NAN), Rgba::rgb(0, 0, 0));
+}
+
+#[test]
+fn overlay_range_defaults_to_data() {
+    let o = overlay(&[("/A", 2.0), ("Sub/B", 6.0), ("C", f32::NAN)]);
+    assert_eq!(o.value_range(), Some((2.0, 6.0)));
+    assert_eq!(o.value("A"), Some(2.0));
+    assert_eq!(o.color_for("A"), Some(o.gradient.color_at(0.0)));
+    assert_eq!(o.color_for("/Sub/B"), Some(o.gradient.color_at(1.0)));
+    assert_eq!(o.color_for("Missing"), None);
+
+    let o = o.with_range(0.0, 8.0).with_gradient(Gradient::coverage());
+    assert_eq!(o.value_range(), Some((0.0, 8.0)));
+    assert_eq!(o.color_for("A"), Some(Gradient::coverage().color_at(0.25)));
+}
+
+#[test]
+fn block_paths_join_system_path() {
+    assert_eq!(block_path(&[], "Gain"), "Gain");
+    assert_eq!(
+        block_path(&["Ctrl".to_string(), "Inner".to_string()], "Gain"),
+        "Ctrl/Inner/Gain"
+    );
+}
+
+#[test]
+fn svg_export_colors_blocks_and_draws_legend() {
+    let xml = r#"<System>
+  <Block BlockType="Gain" Name="Hot" SID="1"><P Name="Position">[0, 0, 30, 30]</P></Block>
+  <Block BlockType="Gain" Name="Cold &amp; idle" SID="2"><P Name="Position">[100, 0, 130, 30]</P></Block>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let system =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    let o = overlay(&[("Hot", 10.0), ("Cold & idle", 0.0)]);
+
+    let svg = render_svg(&system, &[], Some(&o));
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.contains(&format!(r#"fill="{}""#, o.gradient.color_at(1.0).to_hex())));
+    assert!(svg.contains(&format!(r#"fill="{}""#, o.gradient.color_at(0.0).to_hex())));
+    assert!(svg.contains("Cold &amp; idle"));
+    assert!(svg.contains("<polyline"));
+    assert!(svg.contains("CPU load"));
+    assert!(svg.contains("linearGradient"));
+
+    let plain = render_svg(&system, &[], None);
+    assert!(!plain.contains("linearGradient"));
+    assert!(plain.contains(r##"fill="#ffffff""##));
+}
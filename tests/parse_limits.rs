@@ -0,0 +1,273 @@
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use rustylink::parser::{
+    ContentSource, LimitExceeded, LimitKind, ParseLimits, ParserOptions, SimulinkParser, ZipSource,
+};
+use std::collections::HashMap;
+use std::io::{Cursor, Write};
+
+struct MemSource {
+    files: HashMap<String, String>,
+}
+
+impl ContentSource for MemSource {
+    fn read_to_string(&mut self, path: &Utf8Path) -> Result<String> {
+        self.files
+            .get(path.as_str())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("not found: {}", path))
+    }
+    fn list_dir(&mut self, path: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+        let prefix = path.as_str().trim_end_matches('/').to_string() + "/";
+        Ok(self
+            .files
+            .keys()
+            .filter(|k| k.starts_with(&prefix))
+            .map(Utf8PathBuf::from)
+            .collect())
+    }
+}
+
+fn systems(files: &[(&str, String)]) -> MemSource {
+    MemSource {
+        files: files
+            .iter()
+            .map(|(name, xml)| (format!("/simulink/systems/{name}.xml"), xml.clone()))
+            .collect(),
+    }
+}
+
+fn ref_block(sid: usize, target: &str) -> String {
+    format!(
+        r#"<Block BlockType="SubSystem" Name="S{sid}" SID="{sid}"><System Ref="{target}"/></Block>"#
+    )
+}
+
+fn limit_kind(err: &anyhow::Error) -> Option<LimitKind> {
+    err.downcast_ref::<LimitExceeded>().map(|e| e.kind)
+}
+
+fn zip_archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for (name, data) in files {
+        zip.start_file(*name, zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(data).unwrap();
+    }
+    zip.finish().unwrap().into_inner()
+}
+
+#[test]
+fn deep_xml_nesting_is_rejected() {
+    let depth = 50;
+    let xml = "<System><Block BlockType=\"SubSystem\" Name=\"S\">".repeat(depth)
+        + &"</Block></System>".repeat(depth);
+    let limits = ParseLimits {
+        max_depth: 64,
+        ..Default::default()
+    };
+    let err = limits.parse_xml(&xml, "deep.xml").unwrap_err();
+    assert_eq!(limit_kind(&err), Some(LimitKind::Depth));
+    assert_eq!(
+        err.to_string(),
+        "deep.xml exceeds the nesting depth limit of 64"
+    );
+    assert!(ParseLimits::default().parse_xml(&xml, "deep.xml").is_ok());
+}
+
+#[test]
+fn node_limit_and_dtd_entities_are_rejected() {
+    let limits = ParseLimits {
+        max_xml_nodes: 100,
+        ..Default::default()
+    };
+    let xml = format!("<System>{}</System>", "<P Name=\"x\">1</P>".repeat(100));
+    let err = limits.parse_xml(&xml, "wide.xml").unwrap_err();
+    assert_eq!(limit_kind(&err), Some(LimitKind::XmlNodes));
+
+    let laughs = r#"<?xml version="1.0"?>
+<!DOCTYPE lolz [<!ENTITY lol "lol"><!ENTITY lol2 "&lol;&lol;&lol;&lol;">]>
+<System><P Name="x">&lol2;</P></System>"#;
+    assert!(ParseLimits::default().parse_xml(laughs, "dtd.xml").is_err());
+}
+
+#[test]
+fn self_referencing_system_is_left_unlinked() {
+    let source = systems(&[
+        (
+            "system_root",
+            format!("<System>{}</System>", ref_block(1, "system_1")),
+        ),
+        (
+            "system_1",
+            format!("<System>{}</System>", ref_block(2, "system_1")),
+        ),
+    ]);
+    let mut parser = SimulinkParser::new("/", source);
+    let root = parser
+        .parse_system_file("/simulink/systems/system_root.xml")
+        .unwrap();
+    let sub = root.blocks[0].subsystem.as_ref().unwrap();
+    assert_eq!(sub.blocks[0].name, "S2");
+    assert!(sub.blocks[0].subsystem.is_none());
+}
+
+#[test]
+fn reference_fan_out_hits_linked_block_limit() {
+    // Each level references the next one four times: 4^8 blocks at the bottom.
+    let mut files = vec![(
+        "system_root".to_string(),
+        format!("<System>{}</System>", ref_block(1, "system_1")),
+    )];
+    for level in 1..=8 {
+        let blocks: String = (0..4)
+            .map(|i| ref_block(level * 10 + i, &format!("system_{}", level + 1)))
+            .collect();
+        files.push((
+            format!("system_{level}"),
+            format!("<System>{blocks}</System>"),
+        ));
+    }
+    files.push((
+        "system_9".to_string(),
+        r#"<System><Block BlockType="Gain" Name="K" SID="99"/></System>"#.to_string(),
+    ));
+    let files: Vec<(&str, String)> = files.iter().map(|(n, x)| (n.as_str(), x.clone())).collect();
+    let options = ParserOptions {
+        limits: ParseLimits {
+            max_linked_blocks: 10_000,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut parser = SimulinkParser::with_options("/", systems(&files), options);
+    let err = parser
+        .parse_system_file("/simulink/systems/system_root.xml")
+        .unwrap_err();
+    assert_eq!(limit_kind(&err), Some(LimitKind::LinkedBlocks));
+}
+
+#[test]
+fn zip_bomb_members_are_rejected() {
+    let zeros = vec![0u8; 2 * 1024 * 1024];
+    let archive = zip_archive(&[("simulink/systems/system_root.xml", &zeros)]);
+    // Deflate shrinks the zeros to a few kilobytes.
+    assert!(archive.len() < 64 * 1024);
+    let limits = ParseLimits {
+        max_file_size: 1024 * 1024,
+        ..Default::default()
+    };
+    let mut source = ZipSource::with_limits(Cursor::new(archive), limits).unwrap();
+    let err = source
+        .read_to_string(Utf8Path::new("simulink/systems/system_root.xml"))
+        .unwrap_err();
+    assert_eq!(limit_kind(&err), Some(LimitKind::FileSize));
+}
+
+#[test]
+fn total_uncompressed_size_is_bounded() {
+    let part = vec![b' '; 600 * 1024];
+    let archive = zip_archive(&[("a.xml", &part), ("b.xml", &part)]);
+    let limits = ParseLimits {
+        max_total_size: 1024 * 1024,
+        ..Default::default()
+    };
+    let mut source = ZipSource::with_limits(Cursor::new(archive), limits).unwrap();
+    assert!(source.read_bytes(Utf8Path::new("a.xml")).is_ok());
+    let err = source.read_bytes(Utf8Path::new("b.xml")).unwrap_err();
+    assert_eq!(limit_kind(&err), Some(LimitKind::TotalSize));
+}
+
+fn parse_with(source: MemSource, limits: ParseLimits) -> Result<rustylink::model::System> {
+    let options = ParserOptions {
+        limits,
+        ..Default::default()
+    };
+    SimulinkParser::with_options("/", source, options)
+        .parse_system_file("/simulink/systems/system_root.xml")
+}
+
+/// `system_root` references `system_1`, which references `system_2` and so
+/// on down to a gain in `system_{depth}`.
+fn reference_chain(depth: usize) -> MemSource {
+    let mut files = vec![(
+        "system_root".to_string(),
+        format!("<System>{}</System>", ref_block(1, "system_1")),
+    )];
+    for level in 1..depth {
+        files.push((
+            format!("system_{level}"),
+            format!(
+                "<System>{}</System>",
+                ref_block(level + 1, &format!("system_{}", level + 1))
+            ),
+        ));
+    }
+    files.push((
+        format!("system_{depth}"),
+        r#"<System><Block BlockType="Gain" Name="K" SID="99"/></System>"#.to_string(),
+    ));
+    let files: Vec<(&str, String)> = files.iter().map(|(n, x)| (n.as_str(), x.clone())).collect();
+    systems(&files)
+}
+
+#[test]
+fn subsystem_depth_is_bounded_separately_from_xml_depth() {
+    let limits = ParseLimits {
+        max_subsystem_depth: 4,
+        ..Default::default()
+    };
+    assert!(parse_with(reference_chain(4), limits).is_ok());
+    let err = parse_with(reference_chain(5), limits).unwrap_err();
+    assert_eq!(limit_kind(&err), Some(LimitKind::SubsystemDepth));
+
+    // A shallow XML depth limit does not restrict linked subsystems.
+    let limits = ParseLimits {
+        max_depth: 8,
+        ..Default::default()
+    };
+    assert!(parse_with(reference_chain(20), limits).is_ok());
+}
+
+#[test]
+fn block_count_of_system_files_is_bounded() {
+    let gains: String = (0..10)
+        .map(|i| format!(r#"<Block BlockType="Gain" Name="K{i}" SID="{i}"/>"#))
+        .collect();
+    let source = || {
+        systems(&[
+            (
+                "system_root",
+                format!("<System>{}</System>", ref_block(100, "system_1")),
+            ),
+            ("system_1", format!("<System>{gains}</System>")),
+        ])
+    };
+    let limits = |max_blocks| ParseLimits {
+        max_blocks,
+        ..Default::default()
+    };
+    assert!(parse_with(source(), limits(11)).is_ok());
+    let err = parse_with(source(), limits(10)).unwrap_err();
+    assert_eq!(limit_kind(&err), Some(LimitKind::Blocks));
+    assert_eq!(
+        err.to_string(),
+        "/simulink/systems/system_root.xml exceeds the block count limit of 10"
+    );
+}
+
+#[test]
+fn total_xml_bytes_are_bounded_for_any_source() {
+    let source = reference_chain(3);
+    let total: usize = source.files.values().map(String::len).sum();
+    let limits = |max_total_xml_bytes| ParseLimits {
+        max_total_xml_bytes,
+        ..Default::default()
+    };
+    // The root file is read once more after preloading.
+    let root_len = source.files["/simulink/systems/system_root.xml"].len();
+    let needed = (total + root_len) as u64;
+    assert!(parse_with(reference_chain(3), limits(needed)).is_ok());
+    let err = parse_with(source, limits(needed - 1)).unwrap_err();
+    assert_eq!(limit_kind(&err), Some(LimitKind::TotalXmlBytes));
+}
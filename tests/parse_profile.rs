@@ -0,0 +1,111 @@
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use rustylink::parser::{ContentSource, ParseProfile, ParserOptions, ProfilePhase, SimulinkParser};
+use std::collections::HashMap;
+use std::time::Duration;
+
+struct MemSource {
+    files: HashMap<String, String>,
+}
+
+impl ContentSource for MemSource {
+    fn read_to_string(&mut self, path: &Utf8Path) -> Result<String> {
+        self.files
+            .get(path.as_str())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("not found: {}", path))
+    }
+    fn list_dir(&mut self, path: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+        let prefix = path.as_str().trim_end_matches('/').to_string() + "/";
+        Ok(self
+            .files
+            .keys()
+            .filter(|k| k.starts_with(&prefix))
+            .map(Utf8PathBuf::from)
+            .collect())
+    }
+}
+
+fn model() -> MemSource {
+    let mut files = HashMap::new();
+    files.insert(
+        "/simulink/systems/system_root.xml".to_string(),
+        r#"<System>
+  <Block BlockType="SubSystem" Name="Sub" SID="1"><System Ref="system_1"/></Block>
+</System>"#
+            .to_string(),
+    );
+    files.insert(
+        "/simulink/systems/system_1.xml".to_string(),
+        r#"<System><Block BlockType="Gain" Name="K" SID="2"/></System>"#.to_string(),
+    );
+    files.insert(
+        "/simulink/stateflow/chart_7.xml".to_string(),
+        r#"<chart id="7"><P Name="name">Sub/Chart</P></chart>"#.to_string(),
+    );
+    MemSource { files }
+}
+
+#[test]
+fn profiling_is_off_by_default() {
+    let mut parser = SimulinkParser::new("/", model());
+    parser
+        .parse_system_file("/simulink/systems/system_root.xml")
+        .unwrap();
+    assert!(parser.profile().is_none());
+}
+
+#[test]
+fn profile_records_every_phase() {
+    let mut parser = SimulinkParser::with_options(
+        "/",
+        model(),
+        ParserOptions {
+            profile: true,
+            ..Default::default()
+        },
+    );
+    let mut system = parser
+        .parse_system_file("/simulink/systems/system_root.xml")
+        .unwrap();
+    parser.resolve_libraries(&mut system, &[]).unwrap();
+
+    let profile = parser.profile().unwrap();
+    // Two preloaded systems plus the root file itself.
+    assert_eq!(profile.get(ProfilePhase::XmlParse).calls, 3);
+    assert_eq!(profile.get(ProfilePhase::ShallowParse).calls, 3);
+    assert_eq!(profile.get(ProfilePhase::ChartParse).calls, 1);
+    assert_eq!(profile.get(ProfilePhase::Linking).calls, 1);
+    assert_eq!(profile.get(ProfilePhase::LibraryResolution).calls, 1);
+    // Two directory listings, one chart, two systems and the root.
+    assert_eq!(profile.get(ProfilePhase::ZipRead).calls, 6);
+
+    let json = profile.to_json();
+    for key in [
+        "zip_read",
+        "xml_parse",
+        "shallow_parse",
+        "chart_parse",
+        "linking",
+        "library_resolution",
+    ] {
+        assert!(json["phases"][key]["ms"].is_number(), "{key}");
+    }
+}
+
+#[test]
+fn summary_table_lists_phases_and_shares() {
+    let mut profile = ParseProfile::default();
+    profile.record(ProfilePhase::XmlParse, Duration::from_millis(30));
+    profile.record(ProfilePhase::XmlParse, Duration::from_millis(30));
+    profile.record(ProfilePhase::Linking, Duration::from_millis(40));
+    assert_eq!(profile.total(), Duration::from_millis(100));
+    assert_eq!(profile.time(ProfilePhase::ChartParse, || 7), 7);
+
+    let table = profile.summary_table();
+    let xml = table.lines().find(|l| l.starts_with("XML parse")).unwrap();
+    assert!(xml.contains("60.00"), "{xml}");
+    assert!(xml.ends_with(" 2"), "{xml}");
+    assert!(table.lines().any(|l| l.starts_with("library resolution")));
+    assert!(table.lines().last().unwrap().starts_with("total"));
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use camino::{Utf8Path, Utf8PathBuf};
+use rustylink::parser::{ContentSource, ParsePhase, ParseProgress, SimulinkParser};
+use std::collections::HashMap;
+
+struct MemSource {
+    files: HashMap<String, String>,
+}
+
+impl ContentSource for MemSource {
+    fn read_to_string(&mut self, path: &Utf8Path) -> Result<String> {
+        self.files
+            .get(path.as_str())
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("not found: {}", path))
+    }
+    fn list_dir(&mut self, path: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+        let prefix = path.as_str().trim_end_matches('/').to_string() + "/";
+        Ok(self
+            .files
+            .keys()
+            .filter(|k| k.starts_with(&prefix))
+            .map(Utf8PathBuf::from)
+            .collect())
+    }
+}
+
+fn model() -> MemSource {
+    let mut files = HashMap::new();
+    files.insert(
+        "/simulink/systems/system_root.xml".to_string(),
+        r#"<System>
+  <Block BlockType="SubSystem" Name="Sub" SID="1"><System Ref="system_1"/></Block>
+</System>"#
+            .to_string(),
+    );
+    files.insert(
+        "/simulink/systems/system_1.xml".to_string(),
+        r#"<System><Block BlockType="Gain" Name="K" SID="2"/></System>"#.to_string(),
+    );
+    files.insert(
+        "/simulink/stateflow/chart_7.xml".to_string(),
+        r#"<chart id="7"><P Name="name">Sub/Chart</P></chart>"#.to_string(),
+    );
+    MemSource { files }
+}
+
+#[test]
+fn progress_reports_phases_and_file_counts() {
+    let mut parser = SimulinkParser::new("/", model());
+    let updates = parser.progress_channel();
+    let system = parser
+        .parse_system_file("/simulink/systems/system_root.xml")
+        .unwrap();
+    assert!(system.blocks[0].subsystem.is_some());
+    drop(parser);
+    let updates: Vec<ParseProgress> = updates.into_iter().collect();
+
+    let mut phases: Vec<ParsePhase> = updates.iter().map(|u| u.phase).collect();
+    phases.dedup();
+    assert_eq!(
+        phases,
+        [
+            ParsePhase::Charts,
+            ParsePhase::Systems,
+            ParsePhase::Linking,
+            ParsePhase::Done
+        ]
+    );
+    // One chart, two preloaded systems and the root file itself.
+    let last = updates.last().unwrap();
+    assert_eq!((last.files_parsed, last.files_discovered), (4, 4));
+    assert_eq!(last.fraction(), 1.0);
+    assert!(updates.iter().any(
+        |u| u.current_file.as_deref() == Some(Utf8Path::new("/simulink/stateflow/chart_7.xml"))
+    ));
+    assert!(
+        updates
+            .windows(2)
+            .all(|w| w[0].files_parsed <= w[1].files_parsed)
+    );
+    assert_eq!(last.to_string(), "Done (4/4 files)");
+}
+
+#[test]
+fn counts_restart_for_each_parse() {
+    let mut parser = SimulinkParser::new("/", model());
+    let updates = parser.progress_channel();
+    for _ in 0..2 {
+        parser
+            .parse_system_file("/simulink/systems/system_root.xml")
+            .unwrap();
+    }
+    drop(parser);
+    let done: Vec<ParseProgress> = updates
+        .into_iter()
+        .filter(|u| u.phase == ParsePhase::Done)
+        .collect();
+    assert_eq!(done.len(), 2);
+    // Charts and systems are loaded once; the second parse reads the root.
+    assert_eq!(done[1].files_discovered, 1);
+    assert_eq!(done[1].files_parsed, 1);
+}
+
+#[test]
+fn progress_without_listener_does_not_fail() {
+    let mut parser = SimulinkParser::new("/", model());
+    drop(parser.progress_channel());
+    assert!(
+        parser
+            .parse_system_file("/simulink/systems/system_root.xml")
+            .is_ok()
+    );
+}
@@ -0,0 +1,97 @@
+use rustylink::parser::{ParserPool, ZipSource};
+use std::io::{Cursor, Write};
+use std::sync::Arc;
+
+type Source = ZipSource<Cursor<Vec<u8>>>;
+
+const ROOT: &str = "simulink/systems/system_root.xml";
+
+fn slx(gain: &str) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        let files = [
+            (
+                ROOT,
+                r#"<System>
+  <Block BlockType="SubSystem" Name="Ctrl" SID="1"><System Ref="system_1"/></Block>
+</System>"#
+                    .to_string(),
+            ),
+            (
+                "simulink/systems/system_1.xml",
+                format!(r#"<System><Block BlockType="Gain" Name="{gain}" SID="2"/></System>"#),
+            ),
+        ];
+        for (name, data) in files {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    buf.into_inner()
+}
+
+fn open(pool: &ParserPool<Source>, id: &str, gain: &str) {
+    let bytes = slx(gain);
+    pool.open(id, || ZipSource::new(Cursor::new(bytes)))
+        .unwrap();
+}
+
+#[test]
+fn caches_parsers_and_systems_per_model() {
+    let pool = ParserPool::new();
+    open(&pool, "a", "K1");
+    open(&pool, "b", "K2");
+    // Opening again keeps the existing parser.
+    pool.open("a", || panic!("source of an open model requested"))
+        .unwrap();
+    assert_eq!(pool.len(), 2);
+    // The source is created without holding the pool lock.
+    let bytes = slx("K3");
+    pool.open("c", || {
+        assert_eq!(pool.len(), 2);
+        ZipSource::new(Cursor::new(bytes))
+    })
+    .unwrap();
+    assert!(pool.remove("c"));
+
+    let a = pool.system("a", ROOT).unwrap();
+    assert!(Arc::ptr_eq(&a, &pool.system("a", ROOT).unwrap()));
+    assert_eq!(a.block_at("Ctrl/K1").unwrap().sid.as_deref(), Some("2"));
+    let b = pool.system("b", ROOT).unwrap();
+    assert!(b.block_at("Ctrl/K2").is_some());
+
+    assert!(pool.remove("a"));
+    assert!(!pool.remove("a"));
+    assert!(pool.system("a", ROOT).is_err());
+    let mut ids = pool.ids();
+    ids.sort();
+    assert_eq!(ids, ["b"]);
+}
+
+#[test]
+fn shares_one_parse_between_threads() {
+    let pool = ParserPool::new();
+    open(&pool, "m", "K");
+    let systems: Vec<_> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..8)
+            .map(|_| scope.spawn(|| pool.system("m", ROOT).unwrap()))
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+    assert!(systems.iter().all(|s| Arc::ptr_eq(s, &systems[0])));
+
+    let parser = pool.parser("m").unwrap();
+    assert!(parser.read().unwrap().get_charts().is_empty());
+}
+
+#[test]
+fn parse_errors_are_not_cached() {
+    let pool = ParserPool::<Source>::new();
+    open(&pool, "m", "K");
+    assert!(pool.system("m", "simulink/systems/missing.xml").is_err());
+    assert!(pool.system("m", ROOT).is_ok());
+    assert!(pool.system("unknown", ROOT).is_err());
+}
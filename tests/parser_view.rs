@@ -0,0 +1,107 @@
+use camino::Utf8Path;
+use rustylink::parser::view::{ModelStats, SystemView, scan_slx};
+use std::io::Write;
+
+const ROOT: &str = r#"<System>
+  <P Name="Location">[0, 0, 800, 600]</P>
+  <Block BlockType="Gain" Name="K" SID="1">
+    <P Name="Gain">2</P>
+  </Block>
+  <Block BlockType="SubSystem" Name="Inline" SID="2">
+    <System>
+      <Block BlockType="Inport" Name="In1" SID="3"/>
+      <Block BlockType="Reference" Name="Lib" SID="4">
+        <P Name="SourceBlock">mylib/Filter</P>
+      </Block>
+      <Line><P Name="Src">3#out:1</P><P Name="Dst">4#in:1</P></Line>
+    </System>
+  </Block>
+  <Block BlockType="SubSystem" Name="Sub" SID="5"><System Ref="system_5"/></Block>
+  <Line>
+    <P Name="Name">sig</P>
+    <P Name="Src">1#out:1</P>
+    <Branch><P Name="Dst">2#in:1</P></Branch>
+    <Branch><P Name="Dst">5#in:1</P></Branch>
+  </Line>
+  <Annotation SID="6"/>
+</System>"#;
+
+#[test]
+fn views_borrow_blocks_lines_and_properties() {
+    let doc = roxmltree::Document::parse(ROOT).unwrap();
+    let system = SystemView::from_document(&doc).unwrap();
+    assert_eq!(system.property("Location"), Some("[0, 0, 800, 600]"));
+    assert_eq!(system.blocks.len(), 3);
+    assert_eq!(system.annotation_count, 1);
+
+    let gain = &system.blocks[0];
+    assert_eq!(
+        (gain.block_type, gain.name, gain.sid),
+        ("Gain", "K", Some("1"))
+    );
+    assert_eq!(gain.property("Gain"), Some("2"));
+
+    let inline = system.blocks[1].subsystem.as_ref().unwrap();
+    assert_eq!(
+        inline.blocks[1].property("SourceBlock"),
+        Some("mylib/Filter")
+    );
+    assert_eq!(system.blocks[2].system_ref, Some("system_5"));
+    assert!(system.blocks[2].subsystem.is_none());
+
+    let line = system.lines[0];
+    assert_eq!(
+        (line.name, line.src, line.dst_count),
+        (Some("sig"), Some("1#out:1"), 2)
+    );
+}
+
+#[test]
+fn materialize_matches_owned_parse() {
+    let doc = roxmltree::Document::parse(ROOT).unwrap();
+    let view = SystemView::from_document(&doc).unwrap();
+    let owned = view.materialize(Utf8Path::new("")).unwrap();
+    let direct =
+        rustylink::block::parse_system_shallow(doc.root_element(), Utf8Path::new("")).unwrap();
+    assert_eq!(
+        serde_json::to_value(&owned).unwrap(),
+        serde_json::to_value(&direct).unwrap()
+    );
+    assert_eq!(owned.blocks.len(), view.blocks.len());
+}
+
+#[test]
+fn scan_slx_counts_all_system_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("model.slx");
+    let mut zip = zip::ZipWriter::new(std::fs::File::create(&path).unwrap());
+    for (name, xml) in [
+        ("simulink/systems/system_root.xml", ROOT),
+        (
+            "simulink/systems/system_5.xml",
+            r#"<System><Block BlockType="Gain" Name="K2" SID="7"/></System>"#,
+        ),
+        ("simulink/systems/systems_manifest.xml", "<Systems/>"),
+    ] {
+        zip.start_file(name, zip::write::FileOptions::default())
+            .unwrap();
+        zip.write_all(xml.as_bytes()).unwrap();
+    }
+    zip.finish().unwrap();
+
+    let stats = scan_slx(Utf8Path::from_path(&path).unwrap()).unwrap();
+    assert_eq!(stats.systems, 2);
+    assert_eq!(stats.blocks, 6);
+    assert_eq!(stats.lines, 2);
+    assert_eq!(stats.annotations, 1);
+    assert_eq!(stats.block_types["Gain"], 2);
+    assert_eq!(stats.block_types["SubSystem"], 2);
+    assert_eq!(stats.library_links["mylib/Filter"], 1);
+    assert_eq!(stats.max_inline_depth, 1);
+
+    let mut total = ModelStats::default();
+    total.merge(&stats);
+    total.merge(&stats);
+    assert_eq!(total.blocks, 12);
+    assert_eq!(total.block_types["Gain"], 4);
+}
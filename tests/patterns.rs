@@ -0,0 +1,154 @@
+mod common;
+
+use common::parse;
+use rustylink::patterns::{PatternGraph, find, search};
+
+/// Gain -> Saturate -> Outport at the root and inside `Ctrl`, where the
+/// saturation sits behind a commented-through Gain; a commented-out Gain
+/// feeds a second saturation.
+const MODEL_XML: &str = r#"<System>
+  <Block BlockType="Gain" Name="K" SID="1"><P Name="Gain">2</P></Block>
+  <Block BlockType="Saturate" Name="Sat" SID="2"/>
+  <Block BlockType="Outport" Name="y" SID="3"/>
+  <Block BlockType="Gain" Name="Off" SID="4"><P Name="Commented">on</P></Block>
+  <Block BlockType="Saturate" Name="Sat2" SID="5"/>
+  <Block BlockType="Outport" Name="y2" SID="6"><P Name="Port">2</P></Block>
+  <Block BlockType="SubSystem" Name="Ctrl" SID="7">
+    <System>
+      <Block BlockType="Gain" Name="Kp" SID="8"/>
+      <Block BlockType="Gain" Name="Skip" SID="9"><P Name="Commented">through</P></Block>
+      <Block BlockType="Saturate" Name="Limit" SID="10"/>
+      <Block BlockType="Outport" Name="u" SID="11"/>
+      <Line><P Name="Src">8#out:1</P><P Name="Dst">9#in:1</P></Line>
+      <Line><P Name="Src">9#out:1</P><P Name="Dst">10#in:1</P></Line>
+      <Line><P Name="Src">10#out:1</P><P Name="Dst">11#in:1</P></Line>
+    </System>
+  </Block>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+  <Line><P Name="Src">2#out:1</P><P Name="Dst">3#in:1</P></Line>
+  <Line><P Name="Src">4#out:1</P><P Name="Dst">5#in:1</P></Line>
+  <Line><P Name="Src">5#out:1</P><P Name="Dst">6#in:1</P></Line>
+</System>"#;
+
+#[test]
+fn finds_chains_built_in_code_or_parsed() {
+    let system = parse(MODEL_XML);
+    let built = PatternGraph::new()
+        .block("g", "Gain")
+        .block("s", "Saturate")
+        .block("y", "Outport")
+        .connect("g", "s")
+        .connect("s", "y");
+    let matches = find(&system, &built);
+    let blocks: Vec<_> = matches.iter().map(|m| m.blocks.join(" ")).collect();
+    assert_eq!(blocks, ["K Sat y", "Ctrl/Kp Ctrl/Limit Ctrl/u"]);
+    assert_eq!(matches[1].system, "Ctrl");
+    assert_eq!(matches[1].block(&built, "s"), Some("Ctrl/Limit"));
+
+    let parsed: PatternGraph = "g: Gain -> s: Saturate -> y: Outport".parse().unwrap();
+    assert_eq!(parsed, built);
+    let anonymous: PatternGraph = "Gain -> Saturate -> Outport".parse().unwrap();
+    assert_eq!(find(&system, &anonymous), matches);
+
+    let with_gain: PatternGraph = "Gain[Gain=2] -> Saturate".parse().unwrap();
+    assert_eq!(find(&system, &with_gain).len(), 1);
+    let second_output: PatternGraph = "Saturate -> Outport[Port=2]".parse().unwrap();
+    assert_eq!(find(&system, &second_output)[0].blocks, ["Sat2", "y2"]);
+    let commented: PatternGraph = "Gain -> Saturate -> Outport[Port=2]".parse().unwrap();
+    assert!(find(&system, &commented).is_empty());
+}
+
+#[test]
+fn matches_ports_and_reports_symmetric_matches_once() {
+    let system = parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="u" SID="1"/>
+  <Block BlockType="Gain" Name="Kp" SID="2"/>
+  <Block BlockType="Gain" Name="Ki" SID="3"/>
+  <Block BlockType="DiscreteIntegrator" Name="I" SID="4"/>
+  <Block BlockType="Sum" Name="Sum" SID="5"/>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <Branch><P Name="Dst">2#in:1</P></Branch>
+    <Branch><P Name="Dst">3#in:1</P></Branch>
+  </Line>
+  <Line><P Name="Src">2#out:1</P><P Name="Dst">5#in:1</P></Line>
+  <Line><P Name="Src">3#out:1</P><P Name="Dst">4#in:1</P></Line>
+  <Line><P Name="Src">4#out:1</P><P Name="Dst">5#in:2</P></Line>
+</System>"#,
+    );
+    let pi: PatternGraph = "u: * -> Gain -1:1-> sum: Sum
+        u -> Gain -> DiscreteIntegrator -*:2-> sum"
+        .parse()
+        .unwrap();
+    let matches = find(&system, &pi);
+    assert_eq!(matches.len(), 1);
+    assert_eq!(matches[0].blocks, ["u", "Kp", "Sum", "Ki", "I"]);
+    let swapped: PatternGraph = "Gain -> s: Sum; DiscreteIntegrator -1:1-> s"
+        .parse()
+        .unwrap();
+    assert!(find(&system, &swapped).is_empty());
+
+    // The two gains fed by u match either way round
+    let symmetric: PatternGraph = "u: Inport -> Gain; u -> Gain".parse().unwrap();
+    assert_eq!(find(&system, &symmetric).len(), 1);
+
+    for invalid in [
+        "",
+        "a: Gain; a: Sum",
+        "Gain => Sum",
+        "Gain -0:1-> Sum",
+        "Gain[Gain]",
+    ] {
+        assert!(invalid.parse::<PatternGraph>().is_err(), "{invalid}");
+    }
+}
+
+#[test]
+fn block_types_may_contain_dashes() {
+    let system = parse(
+        r#"<System>
+  <Block BlockType="S-Function" Name="sfun" SID="1"/>
+  <Block BlockType="Outport" Name="y" SID="2"/>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+</System>"#,
+    );
+    let pattern: PatternGraph = "S-Function -> Outport".parse().unwrap();
+    assert_eq!(pattern.nodes[0].block_type.as_deref(), Some("S-Function"));
+    assert_eq!(find(&system, &pattern)[0].blocks, ["sfun", "y"]);
+
+    // A dash before a port number still starts an arrow.
+    let ported: PatternGraph = "f: S-Function-1:1-> Outport".parse().unwrap();
+    assert_eq!(ported.nodes[0].block_type.as_deref(), Some("S-Function"));
+    assert_eq!(find(&system, &ported).len(), 1);
+}
+
+#[test]
+fn search_follows_wires_and_stops_at_its_step_limit() {
+    // 300 Gains in a chain
+    let mut xml = String::from("<System>");
+    for i in 1..=300 {
+        xml += &format!(r#"<Block BlockType="Gain" Name="K{i}" SID="{i}"/>"#);
+    }
+    for i in 1..300 {
+        xml += &format!(
+            r#"<Line><P Name="Src">{i}#out:1</P><P Name="Dst">{}#in:1</P></Line>"#,
+            i + 1
+        );
+    }
+    xml += "</System>";
+    let system = parse(&xml);
+
+    // Every block after the first is taken from the wires of the one before
+    let chain: PatternGraph = "Gain -> Gain -> Gain -> Gain -> Gain".parse().unwrap();
+    let result = search(&system, &chain, 2000);
+    assert!(result.complete);
+    assert_eq!(result.matches.len(), 296);
+    assert_eq!(result.matches[0].blocks, ["K1", "K2", "K3", "K4", "K5"]);
+
+    // Unconnected blocks would try 300^4 combinations
+    let unconnected: PatternGraph = "a: Gain; b: Gain; c: Gain; d: Gain".parse().unwrap();
+    let result = search(&system, &unconnected, 10_000);
+    assert!(!result.complete);
+    assert!(!result.matches.is_empty());
+}
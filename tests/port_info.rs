@@ -0,0 +1,96 @@
+mod common;
+
+use common::parse;
+use rustylink::port_info::{attached_line, hover_text, port_data_type, port_name};
+
+const SYSTEM: &str = r#"<System>
+  <Block BlockType="Constant" Name="C" SID="1"><P Name="OutDataTypeStr">single</P></Block>
+  <Block BlockType="Gain" Name="G" SID="2"><P Name="OutDataTypeStr">Inherit: Same as input</P></Block>
+  <Block BlockType="Scope" Name="S" SID="3"/>
+  <Block BlockType="Terminator" Name="T" SID="4"/>
+  <Line>
+    <P Name="Name">speed</P>
+    <P Name="Src">1#out:1</P>
+    <Branch><P Name="Dst">2#in:1</P></Branch>
+    <Branch><P Name="Dst">3#in:1</P></Branch>
+  </Line>
+  <Line><P Name="Src">2#out:1</P><P Name="Dst">4#in:1</P></Line>
+</System>"#;
+
+#[test]
+fn attached_line_follows_sources_and_branches() {
+    let system = parse(SYSTEM);
+    assert_eq!(attached_line(&system.lines, "1", 1, false), Some(0));
+    assert_eq!(attached_line(&system.lines, "3", 1, true), Some(0));
+    assert_eq!(attached_line(&system.lines, "4", 1, true), Some(1));
+    assert_eq!(attached_line(&system.lines, "1", 2, false), None);
+    assert_eq!(attached_line(&system.lines, "1", 1, true), None);
+}
+
+#[test]
+fn data_types_are_declared_or_traced_from_the_source() {
+    let system = parse(SYSTEM);
+    let block = |sid: &str| {
+        system
+            .blocks
+            .iter()
+            .find(|b| b.sid.as_deref() == Some(sid))
+            .unwrap()
+    };
+    let dt = |sid: &str, is_input: bool| {
+        port_data_type(&system.blocks, &system.lines, block(sid), 1, is_input)
+    };
+    assert_eq!(dt("1", false).as_deref(), Some("single"));
+    assert_eq!(dt("3", true).as_deref(), Some("single"));
+    // Inherited output types are not resolved.
+    assert_eq!(dt("2", false), None);
+    assert_eq!(dt("4", true), None);
+}
+
+#[test]
+fn hover_text_lists_name_type_and_signal() {
+    let system = parse(SYSTEM);
+    let scope = &system.blocks[2];
+    assert_eq!(
+        hover_text(&system.blocks, &system.lines, scope, 1, true),
+        "In1 (In 1)\nType: single\nSignal: speed"
+    );
+    let gain = &system.blocks[1];
+    assert_eq!(
+        hover_text(&system.blocks, &system.lines, gain, 1, false),
+        "Out1 (Out 1)\nType: inherited\nSignal: line 2"
+    );
+    let terminator = &system.blocks[3];
+    assert!(
+        hover_text(&system.blocks, &system.lines, terminator, 2, true).ends_with("unconnected")
+    );
+}
+
+#[test]
+fn subsystem_ports_use_inner_port_blocks() {
+    let mut system = parse(
+        r#"<System>
+  <Block BlockType="SubSystem" Name="Sub" SID="1"/>
+</System>"#,
+    );
+    let inner = parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="ref"><P Name="OutDataTypeStr">int16</P></Block>
+  <Block BlockType="Inport" Name="meas"><P Name="Port">2</P></Block>
+  <Block BlockType="Outport" Name="err"><P Name="OutDataTypeStr">Inherit: auto</P></Block>
+</System>"#,
+    );
+    system.blocks[0].subsystem = Some(Box::new(inner));
+    let sub = &system.blocks[0];
+    assert_eq!(port_name(sub, 1, true).as_deref(), Some("ref"));
+    assert_eq!(port_name(sub, 2, true).as_deref(), Some("meas"));
+    assert_eq!(port_name(sub, 1, false).as_deref(), Some("err"));
+    assert_eq!(
+        port_data_type(&system.blocks, &system.lines, sub, 1, true).as_deref(),
+        Some("int16")
+    );
+    assert_eq!(
+        port_data_type(&system.blocks, &system.lines, sub, 1, false),
+        None
+    );
+}
@@ -0,0 +1,191 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use rustylink::parser::split_source_block_reference;
+use rustylink::project::{Impact, ModelRole, Usage, Workspace};
+use std::io::{Cursor, Write};
+
+fn write_model(dir: &Utf8Path, name: &str, blocks: &str) {
+    let xml = format!("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<System>\n{blocks}</System>\n");
+    let mut buf = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        zip.start_file(
+            "simulink/systems/system_root.xml",
+            zip::write::FileOptions::default(),
+        )
+        .unwrap();
+        zip.write_all(xml.as_bytes()).unwrap();
+        zip.finish().unwrap();
+    }
+    std::fs::write(dir.join(format!("{name}.slx")), buf.into_inner()).unwrap();
+}
+
+/// `main` links `ctrllib/PI` and `missinglib/X` and references `plant`,
+/// which links `ctrllib/PI` too.
+fn project(dir: &Utf8Path, plant_gain: &str) {
+    write_model(
+        dir,
+        "main",
+        r#"  <Block BlockType="Reference" Name="PI" SID="1"><P Name="SourceBlock">ctrllib/PI</P></Block>
+  <Block BlockType="Reference" Name="X" SID="2"><P Name="SourceBlock">missinglib/X</P></Block>
+  <Block BlockType="Reference" Name="G" SID="3"><P Name="SourceBlock">simulink/Math
+Operations/Gain</P></Block>
+  <Block BlockType="ModelReference" Name="Plant" SID="4"><P Name="ModelNameDialog">plant.slx</P></Block>
+"#,
+    );
+    write_model(
+        &dir.join("lib"),
+        "ctrllib",
+        r#"  <Block BlockType="SubSystem" Name="PI" SID="1"/>
+  <Block BlockType="Gain" Name="Lead/Lag" SID="2"/>
+"#,
+    );
+    write_model(
+        dir,
+        "plant",
+        &format!(
+            r#"  <Block BlockType="SubSystem" Name="Ctrl" SID="1">
+    <System>
+      <Block BlockType="Reference" Name="PI" SID="2"><P Name="SourceBlock">ctrllib/PI</P></Block>
+    </System>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="3"><P Name="Gain">{plant_gain}</P></Block>
+"#
+        ),
+    );
+}
+
+fn tempdir() -> (tempfile::TempDir, Utf8PathBuf) {
+    let dir = tempfile::tempdir().unwrap();
+    let path = Utf8PathBuf::from_path_buf(dir.path().to_path_buf()).unwrap();
+    std::fs::create_dir(path.join("lib")).unwrap();
+    (dir, path)
+}
+
+#[test]
+fn workspace_loads_libraries_and_referenced_models_once() {
+    let (_guard, dir) = tempdir();
+    project(&dir, "2");
+    let workspace = Workspace::open(&dir.join("main.slx"), &[dir.join("lib")]).unwrap();
+    let models: Vec<_> = workspace
+        .models()
+        .map(|m| (m.name.as_str(), m.role))
+        .collect();
+    assert_eq!(
+        models,
+        [
+            ("main", ModelRole::Main),
+            ("ctrllib", ModelRole::Library),
+            ("plant", ModelRole::Referenced),
+        ]
+    );
+    assert_eq!(workspace.main().name, "main");
+    assert_eq!(workspace.missing, ["missinglib"]);
+    assert_eq!(
+        workspace.model("ctrllib").unwrap().path,
+        dir.join("lib/ctrllib.slx")
+    );
+    let pi = workspace.library_block("ctrllib/PI").unwrap();
+    assert_eq!(pi.block_type, "SubSystem");
+    assert!(workspace.library_block("missinglib/X").is_none());
+    // A `/` in a block name is escaped as `//`
+    let lead_lag = workspace.library_block("ctrllib/Lead//Lag").unwrap();
+    assert_eq!(lead_lag.block_type, "Gain");
+    assert!(workspace.library_block("ctrllib/Lead/Lag").is_none());
+    assert_eq!(
+        split_source_block_reference("ctrllib/Lead//Lag"),
+        Some(("ctrllib".to_string(), "Lead//Lag".to_string()))
+    );
+    assert_eq!(
+        split_source_block_reference("simulink/Discrete/Unit Delay"),
+        Some(("simulink/Discrete".to_string(), "Unit Delay".to_string()))
+    );
+
+    let usage = |model: &str, path: &str| Usage {
+        model: model.to_string(),
+        path: path.to_string(),
+    };
+    assert_eq!(
+        workspace.where_used("ctrllib/PI"),
+        [usage("main", "PI"), usage("plant", "Ctrl/PI")]
+    );
+    assert_eq!(workspace.where_used("plant"), [usage("main", "Plant")]);
+    assert!(workspace.where_used("ctrllib/PID").is_empty());
+}
+
+#[test]
+fn workspaces_diff_model_by_model() {
+    let (_old_guard, old_dir) = tempdir();
+    let (_new_guard, new_dir) = tempdir();
+    project(&old_dir, "2");
+    project(&new_dir, "3");
+    let open = |dir: &Utf8Path| Workspace::open(&dir.join("main.slx"), &[dir.join("lib")]).unwrap();
+    let old = open(&old_dir);
+    assert!(old.diff(&old).is_empty());
+
+    let diff = old.diff(&open(&new_dir));
+    assert!(diff.added.is_empty() && diff.removed.is_empty());
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].0, "plant");
+    assert_eq!(diff.changed[0].1.blocks[0].path, "K");
+
+    // Dropping the library from the search path drops it from the workspace
+    let without_lib = Workspace::open(&new_dir.join("main.slx"), &[]).unwrap();
+    let diff = old.diff(&without_lib);
+    assert_eq!(diff.removed, ["ctrllib"]);
+    assert_eq!(without_lib.missing, ["ctrllib", "missinglib"]);
+}
+
+#[test]
+fn library_changes_reach_models_through_links_and_references() {
+    let (_guard, dir) = tempdir();
+    project(&dir, "2");
+    // `ctrllib/PI` gains an inner block, and `toplib/Outer` wraps a link to it
+    write_model(
+        &dir.join("lib"),
+        "ctrllib",
+        r#"  <Block BlockType="SubSystem" Name="PI" SID="1">
+    <System><Block BlockType="Gain" Name="Kp" SID="2"/></System>
+  </Block>
+"#,
+    );
+    write_model(
+        &dir.join("lib"),
+        "toplib",
+        r#"  <Block BlockType="SubSystem" Name="Outer" SID="1">
+    <System>
+      <Block BlockType="Reference" Name="Inner" SID="2"><P Name="SourceBlock">ctrllib/PI</P></Block>
+    </System>
+  </Block>
+"#,
+    );
+    write_model(
+        &dir,
+        "system",
+        r#"  <Block BlockType="ModelReference" Name="Main" SID="1"><P Name="ModelName">main</P></Block>
+  <Block BlockType="Reference" Name="Wrapped" SID="2"><P Name="SourceBlock">toplib/Outer</P></Block>
+"#,
+    );
+    let workspace = Workspace::open(&dir.join("system.slx"), &[dir.join("lib")]).unwrap();
+    let impact = |model: &str, path: &str, through: &str| Impact {
+        model: model.to_string(),
+        path: path.to_string(),
+        through: through.to_string(),
+    };
+    assert_eq!(
+        workspace.impacted_models(&"ctrllib/PI/Kp".parse().unwrap()),
+        [
+            impact("main", "PI", "ctrllib/PI"),
+            impact("toplib", "Outer/Inner", "ctrllib/PI"),
+            impact("plant", "Ctrl/PI", "ctrllib/PI"),
+            impact("system", "Main", "main"),
+            impact("system", "Wrapped", "toplib/Outer"),
+            impact("main", "Plant", "plant"),
+        ]
+    );
+    // A sibling whose name only starts like the changed block is unaffected
+    assert!(
+        workspace
+            .impacted_models(&"ctrllib/P".parse().unwrap())
+            .is_empty()
+    );
+}
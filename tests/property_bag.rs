@@ -0,0 +1,88 @@
+use rustylink::model::PropertyBag;
+
+fn bag(pairs: &[(&str, &str)]) -> PropertyBag {
+    pairs
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn keys(bag: &PropertyBag) -> Vec<&str> {
+    bag.keys().map(String::as_str).collect()
+}
+
+#[test]
+fn insert_keeps_first_position() {
+    let mut props = bag(&[("Position", "[0, 0, 30, 30]"), ("ZOrder", "1")]);
+    assert_eq!(props.insert("Gain".into(), "2".into()), None);
+    assert_eq!(
+        props.insert("Position".into(), "[5, 5, 35, 35]".into()),
+        Some("[0, 0, 30, 30]".to_string())
+    );
+    assert_eq!(keys(&props), ["Position", "ZOrder", "Gain"]);
+    assert_eq!(props["Position"], "[5, 5, 35, 35]");
+    assert_eq!(props.len(), 3);
+    assert!(props.contains_key("Gain"));
+    assert_eq!(props.get("Missing"), None);
+}
+
+#[test]
+fn removal_variants() {
+    let mut props = bag(&[("A", "1"), ("B", "2"), ("C", "3"), ("D", "4")]);
+    assert_eq!(props.shift_remove("B"), Some("2".to_string()));
+    assert_eq!(keys(&props), ["A", "C", "D"]);
+    assert_eq!(props.swap_remove("A"), Some("1".to_string()));
+    assert_eq!(keys(&props), ["D", "C"]);
+    assert_eq!(props.swap_remove("A"), None);
+
+    props.retain(|k, _| k != "C");
+    assert_eq!(keys(&props), ["D"]);
+    props.clear();
+    assert!(props.is_empty());
+}
+
+#[test]
+fn get_or_insert_with_only_inserts_missing() {
+    let mut props = bag(&[("SystemName", "Top")]);
+    props.get_or_insert_with("SystemName", || "Other".to_string());
+    props
+        .get_or_insert_with("Tag", || "t".to_string())
+        .push('2');
+    assert_eq!(props["SystemName"], "Top");
+    assert_eq!(props["Tag"], "t2");
+}
+
+#[test]
+fn serde_preserves_order() {
+    let props = bag(&[("Zeta", "1"), ("Alpha", "2"), ("Mid", "3")]);
+    let json = serde_json::to_string(&props).unwrap();
+    assert_eq!(json, r#"{"Zeta":"1","Alpha":"2","Mid":"3"}"#);
+    let back: PropertyBag = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, props);
+
+    let bytes = bincode::serde::encode_to_vec(&props, bincode::config::standard()).unwrap();
+    let (decoded, _): (PropertyBag, usize) =
+        bincode::serde::decode_from_slice(&bytes, bincode::config::standard()).unwrap();
+    assert_eq!(keys(&decoded), ["Zeta", "Alpha", "Mid"]);
+}
+
+#[test]
+fn parsed_block_keeps_xml_order() {
+    let xml = r#"<System>
+  <Block BlockType="Gain" Name="K" SID="1">
+    <P Name="ZOrder">3</P>
+    <P Name="Gain">2</P>
+    <P Name="Position">[0, 0, 30, 30]</P>
+  </Block>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let system =
+        rustylink::block::parse_system_shallow(doc.root_element(), camino::Utf8Path::new(""))
+            .unwrap();
+    let props = &system.blocks[0].properties;
+    let order: Vec<&str> = keys(props)
+        .into_iter()
+        .filter(|k| ["ZOrder", "Gain", "Position"].contains(k))
+        .collect();
+    assert_eq!(order, ["ZOrder", "Gain", "Position"]);
+}
@@ -0,0 +1,103 @@
+use camino::Utf8PathBuf;
+use rustylink::parser::{ModelProtectedError, ProtectionKind, SimulinkParser, ZipSource};
+use std::io::{Cursor, Write};
+
+const ROOT_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Gain" Name="G" SID="1"/>
+</System>
+"#;
+
+const GRAPHICAL_INTERFACE: &str = r#"{"GraphicalInterface":{"ExternalFileReferences":[{"Path":"m/B","Reference":"mylib/Block","SID":"3","Type":"LIBRARY_BLOCK"}],"PreCompExecutionDomainType":"Unset","SimulinkSubDomainType":"Simulink","SolverName":"FixedStepDiscrete"}}"#;
+
+fn build_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut buf = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        for (name, data) in files {
+            zip.start_file(
+                *name,
+                zip::write::FileOptions::default()
+                    .compression_method(zip::CompressionMethod::Stored),
+            )
+            .unwrap();
+            zip.write_all(data).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    buf.into_inner()
+}
+
+/// Set the "encrypted" general purpose flag on every member named `name`.
+fn mark_encrypted(bytes: &mut [u8], name: &str) {
+    let name = name.as_bytes();
+    for i in 0..bytes.len().saturating_sub(46) {
+        let (flag_off, name_off) = match &bytes[i..i + 4] {
+            b"PK\x03\x04" => (6, 30),
+            b"PK\x01\x02" => (8, 46),
+            _ => continue,
+        };
+        if bytes[i + name_off..].starts_with(name) {
+            bytes[i + flag_off] |= 1;
+        }
+    }
+}
+
+#[test]
+fn regular_model_is_not_protected() {
+    let bytes = build_zip(&[("simulink/systems/system_root.xml", ROOT_XML.as_bytes())]);
+    let mut src = ZipSource::new(Cursor::new(bytes)).unwrap();
+    assert!(src.protection().is_none());
+}
+
+#[test]
+fn protected_model_reports_interface_metadata() {
+    let bytes = build_zip(&[
+        ("protectedModel/model.bin", b"\x00\x01\x02"),
+        (
+            "simulink/graphicalInterface.json",
+            GRAPHICAL_INTERFACE.as_bytes(),
+        ),
+    ]);
+    let mut src = ZipSource::new(Cursor::new(bytes.clone())).unwrap();
+    let protected = src.protection().expect("protected");
+    assert_eq!(protected.kind, ProtectionKind::ProtectedModel);
+    let gi = protected.interface.as_ref().expect("interface metadata");
+    assert_eq!(gi.library_names(), vec!["mylib".to_string()]);
+
+    // Parsing surfaces the dedicated error instead of "file not found".
+    let mut parser = SimulinkParser::new("", ZipSource::new(Cursor::new(bytes)).unwrap());
+    let err = parser
+        .parse_system_file(Utf8PathBuf::from("simulink/systems/system_root.xml"))
+        .unwrap_err();
+    let protected = err
+        .downcast_ref::<ModelProtectedError>()
+        .expect("ModelProtectedError");
+    assert!(
+        protected
+            .readable_parts
+            .contains(&"simulink/graphicalInterface.json".to_string())
+    );
+}
+
+#[test]
+fn encrypted_members_are_detected() {
+    let mut bytes = build_zip(&[
+        ("simulink/systems/system_root.xml", ROOT_XML.as_bytes()),
+        ("simulink/systems/system_5.xml", ROOT_XML.as_bytes()),
+        ("metadata/thumbnail.png", b"\x89PNG\r\n\x1a\n"),
+    ]);
+    mark_encrypted(&mut bytes, "simulink/systems/system_5.xml");
+    let mut src = ZipSource::new(Cursor::new(bytes)).unwrap();
+    let protected = src.protection().expect("encrypted parts");
+    assert_eq!(protected.kind, ProtectionKind::EncryptedParts);
+    assert_eq!(
+        protected.encrypted_parts,
+        vec!["simulink/systems/system_5.xml".to_string()]
+    );
+    assert!(protected.interface.is_none());
+    assert!(protected.to_string().contains("1 encrypted part"));
+
+    // Readable assets are still extracted.
+    assert_eq!(src.extract_assets().unwrap().len(), 1);
+}
@@ -0,0 +1,322 @@
+mod common;
+
+use anyhow::{Result, anyhow};
+use camino::{Utf8Path, Utf8PathBuf};
+use common::parse;
+use rustylink::cosmetic::CosmeticFilter;
+use rustylink::parser::ContentSource;
+use rustylink::report::{
+    BlockParameterDefaults, MexPlatform, OverrideOptions, model_callbacks, overrides_to_csv,
+    parameter_overrides, sfunction_dependencies,
+};
+use std::collections::HashMap;
+
+const DEFAULTS: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<BlockDiagramDefaults>
+  <BlockParameterDefaults>
+    <Block BlockType="Gain">
+      <P Name="Gain">1</P>
+      <P Name="Multiplication">Element-wise(K.*u)</P>
+    </Block>
+    <Block BlockType="Saturate">
+      <P Name="UpperLimit">0.5</P>
+      <P Name="LowerLimit">-0.5</P>
+    </Block>
+  </BlockParameterDefaults>
+</BlockDiagramDefaults>"#;
+
+fn model() -> rustylink::model::System {
+    let mut root = parse(
+        r#"<System>
+  <Block BlockType="Gain" Name="K" SID="1">
+    <P Name="Position">[0, 0, 30, 30]</P>
+    <P Name="Gain">1</P>
+  </Block>
+  <Block BlockType="SubSystem" Name="Ctrl" SID="2"><P Name="Position">[50, 0, 80, 30]</P></Block>
+</System>"#,
+    );
+    let inner = parse(
+        r#"<System>
+  <Block BlockType="Gain" Name="Kp" SID="3">
+    <P Name="Position">[0, 0, 30, 30]</P>
+    <P Name="Gain">2.5</P>
+  </Block>
+  <Block BlockType="Saturate" Name="Limit, upper" SID="4">
+    <P Name="UpperLimit">0.5</P>
+    <P Name="LowerLimit">-1</P>
+  </Block>
+</System>"#,
+    );
+    root.blocks[1].subsystem = Some(Box::new(inner));
+    root
+}
+
+#[test]
+fn defaults_are_parsed_per_block_type() {
+    let defaults = BlockParameterDefaults::parse(DEFAULTS).unwrap();
+    assert_eq!(defaults.get("Gain", "Gain"), Some("1"));
+    assert_eq!(defaults.get("Saturate", "LowerLimit"), Some("-0.5"));
+    assert_eq!(defaults.get("Gain", "Missing"), None);
+    assert_eq!(defaults.get("Sum", "Inputs"), None);
+}
+
+#[test]
+fn overrides_are_grouped_by_subsystem() {
+    let defaults = BlockParameterDefaults::parse(DEFAULTS).unwrap();
+    let report = parameter_overrides(&model(), &defaults, OverrideOptions::default());
+    let paths: Vec<&str> = report.iter().map(|g| g.path.as_str()).collect();
+    assert_eq!(paths, ["", "Ctrl"]);
+
+    // Only cosmetic parameters differ at the root.
+    let root: Vec<&str> = report[0]
+        .overrides
+        .iter()
+        .map(|o| o.parameter.as_str())
+        .collect();
+    assert_eq!(root, ["Position", "Position"]);
+
+    let ctrl = &report[1].overrides;
+    let gain = ctrl.iter().find(|o| o.parameter == "Gain").unwrap();
+    assert_eq!(gain.block, "Kp");
+    assert_eq!(gain.value, "2.5");
+    assert_eq!(gain.default.as_deref(), Some("1"));
+    let lower = ctrl.iter().find(|o| o.parameter == "LowerLimit").unwrap();
+    assert_eq!(lower.default.as_deref(), Some("-0.5"));
+    assert!(!ctrl.iter().any(|o| o.parameter == "UpperLimit"));
+}
+
+#[test]
+fn cosmetic_parameters_can_be_ignored() {
+    let defaults = BlockParameterDefaults::parse(DEFAULTS).unwrap();
+    let options = OverrideOptions {
+        cosmetic: Some(CosmeticFilter::default()),
+    };
+    let report = parameter_overrides(&model(), &defaults, options);
+    assert_eq!(report.len(), 1);
+    assert_eq!(report[0].path, "Ctrl");
+    let params: Vec<&str> = report[0]
+        .overrides
+        .iter()
+        .map(|o| o.parameter.as_str())
+        .collect();
+    assert_eq!(params, ["Gain", "LowerLimit"]);
+}
+
+#[test]
+fn csv_quotes_fields() {
+    let defaults = BlockParameterDefaults::parse(DEFAULTS).unwrap();
+    let options = OverrideOptions {
+        cosmetic: Some(CosmeticFilter::default()),
+    };
+    let csv = overrides_to_csv(&parameter_overrides(&model(), &defaults, options));
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "subsystem,block,block_type,parameter,value,default",
+            "Ctrl,Kp,Gain,Gain,2.5,1",
+            "Ctrl,\"Limit, upper\",Saturate,LowerLimit,-1,-0.5",
+        ]
+    );
+}
+
+struct MemSource(HashMap<&'static str, &'static str>);
+
+impl ContentSource for MemSource {
+    fn read_to_string(&mut self, path: &Utf8Path) -> Result<String> {
+        self.0
+            .get(path.as_str())
+            .map(|s| s.to_string())
+            .ok_or_else(|| anyhow!("Missing {}", path))
+    }
+    fn list_dir(&mut self, _path: &Utf8Path) -> Result<Vec<Utf8PathBuf>> {
+        Ok(Vec::new())
+    }
+}
+
+#[test]
+fn defaults_fall_back_to_blockdiagram_xml() {
+    let blockdiagram = r#"<ModelInformation><Model>
+  <BlockParameterDefaults><Block BlockType="Gain"><P Name="Gain">1</P></Block></BlockParameterDefaults>
+</Model></ModelInformation>"#;
+    let mut source = MemSource(HashMap::from([("simulink/blockdiagram.xml", blockdiagram)]));
+    let defaults = BlockParameterDefaults::from_source(&mut source).unwrap();
+    assert_eq!(defaults.get("Gain", "Gain"), Some("1"));
+
+    let mut empty = MemSource(HashMap::new());
+    assert!(
+        BlockParameterDefaults::from_source(&mut empty)
+            .unwrap()
+            .by_type
+            .is_empty()
+    );
+}
+
+#[test]
+fn model_callbacks_are_read_from_blockdiagram_xml() {
+    let blockdiagram = r#"<ModelInformation><Model>
+  <P Name="PreLoadFcn">addpath('lib')</P>
+  <P Name="InitFcn"></P>
+  <P Name="StopFcn">save results</P>
+  <P Name="SolverName">ode45</P>
+  <System><P Name="OpenFcn">not a model callback</P></System>
+</Model></ModelInformation>"#;
+    let mut source = MemSource(HashMap::from([("simulink/blockdiagram.xml", blockdiagram)]));
+    let callbacks = model_callbacks(&mut source).unwrap();
+    let names: Vec<&str> = callbacks.iter().map(|(name, _)| name).collect();
+    assert_eq!(names, ["PreLoadFcn", "StopFcn"]);
+    assert_eq!(callbacks.get("PreLoadFcn"), Some("addpath('lib')"));
+
+    let mut empty = MemSource(HashMap::new());
+    assert!(model_callbacks(&mut empty).unwrap().is_empty());
+}
+
+#[test]
+fn all_callbacks_lists_block_and_mask_code() {
+    let mut root = parse(
+        r#"<System>
+  <Block BlockType="Gain" Name="K" SID="1">
+    <P Name="InitFcn">disp('init')</P>
+    <P Name="OpenFcn"></P>
+  </Block>
+  <Block BlockType="Fcn" Name="Interp" SID="2">
+    <P Name="MATLABFcn">sin</P>
+  </Block>
+  <Block BlockType="SubSystem" Name="Ctrl" SID="3"/>
+</System>"#,
+    );
+    let inner = parse(
+        r#"<System>
+  <Block BlockType="SubSystem" Name="Masked" SID="4">
+    <P Name="StartFcn">tic</P>
+    <Mask>
+      <Initialization>k = 2;</Initialization>
+      <MaskParameter Name="k" Type="edit">
+        <Value>1</Value>
+        <Callback>validate(k)</Callback>
+      </MaskParameter>
+    </Mask>
+  </Block>
+</System>"#,
+    );
+    root.blocks[2].subsystem = Some(Box::new(inner));
+
+    let all = root.all_callbacks();
+    let sites: Vec<(&str, &str, &str)> = all
+        .iter()
+        .map(|s| (s.path.as_str(), s.name.as_str(), s.code.as_str()))
+        .collect();
+    assert_eq!(
+        sites,
+        [
+            ("K", "InitFcn", "disp('init')"),
+            ("Ctrl/Masked", "StartFcn", "tic"),
+            ("Ctrl/Masked", "MaskInitialization", "k = 2;"),
+            ("Ctrl/Masked", "k:Callback", "validate(k)"),
+        ]
+    );
+}
+
+#[test]
+fn s_function_parameters_are_split() {
+    let root = parse(
+        r#"<System>
+  <Block BlockType="S-Function" Name="Plant" SID="1">
+    <P Name="FunctionName">plant_sfun</P>
+    <P Name="Parameters">K, [1, 2; 3, 4], 'a, b', x', f(1, 2)</P>
+    <P Name="SFunctionModules">'plant_core util'</P>
+  </Block>
+  <Block BlockType="M-S-Function" Name="Logic" SID="2">
+    <P Name="FunctionName">logic_msfun</P>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="3"/>
+</System>"#,
+    );
+    let plant = root.blocks[0].s_function().unwrap();
+    assert_eq!(plant.function_name, "plant_sfun");
+    assert_eq!(
+        plant.parameters,
+        ["K", "[1, 2; 3, 4]", "'a, b'", "x'", "f(1, 2)"]
+    );
+    assert_eq!(plant.modules, ["plant_core", "util"]);
+    assert!(!plant.matlab);
+    let logic = root.blocks[1].s_function().unwrap();
+    assert!(logic.matlab && logic.parameters.is_empty() && logic.modules.is_empty());
+    assert!(root.blocks[2].s_function().is_none());
+}
+
+#[test]
+fn if_and_switch_case_conditions_label_the_outputs() {
+    let root = parse(
+        r#"<System>
+  <Block BlockType="If" Name="If" SID="1">
+    <P Name="IfExpression">u1 &gt; 0</P>
+    <P Name="ElseIfExpressions">u2 == 1, max(u1, u2) &lt; 3</P>
+  </Block>
+  <Block BlockType="If" Name="NoElse" SID="2"><P Name="ShowElse">off</P></Block>
+  <Block BlockType="SwitchCase" Name="Switch" SID="3">
+    <P Name="CaseConditions">{1, [2, 3]}</P>
+  </Block>
+  <Block BlockType="SwitchCase" Name="NoDefault" SID="4">
+    <P Name="ShowDefaultCase">off</P>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="5"/>
+</System>"#,
+    );
+    let labels = |i: usize| root.blocks[i].condition_labels();
+    assert_eq!(
+        labels(0).unwrap(),
+        [
+            "if(u1 > 0)",
+            "elseif(u2 == 1)",
+            "elseif(max(u1, u2) < 3)",
+            "else"
+        ]
+    );
+    assert_eq!(labels(1).unwrap(), ["if(u1 > 0)"]);
+    assert_eq!(labels(2).unwrap(), ["case 1:", "case {2, 3}:", "default:"]);
+    assert_eq!(labels(3).unwrap(), ["case 1:"]);
+    assert!(labels(4).is_none());
+}
+
+#[test]
+fn s_function_dependencies_are_grouped_by_file() {
+    let mut root = parse(
+        r#"<System>
+  <Block BlockType="S-Function" Name="A" SID="1"><P Name="FunctionName">ctrl</P></Block>
+  <Block BlockType="M-S-Function" Name="B" SID="2"><P Name="FunctionName">logic</P></Block>
+  <Block BlockType="S-Function" Name="Unnamed" SID="3"/>
+  <Block BlockType="SubSystem" Name="Sub" SID="4"/>
+</System>"#,
+    );
+    let inner = parse(
+        r#"<System>
+  <Block BlockType="S-Function" Name="C" SID="5">
+    <P Name="FunctionName">ctrl</P>
+    <P Name="SFunctionModules">ctrl_impl</P>
+  </Block>
+</System>"#,
+    );
+    root.blocks[3].subsystem = Some(Box::new(inner));
+
+    let deps = sfunction_dependencies(&root);
+    assert_eq!(deps.len(), 2);
+    assert_eq!(deps[0].function_name, "ctrl");
+    assert_eq!(deps[0].blocks, ["A", "Sub/C"]);
+    assert_eq!(deps[0].modules, ["ctrl_impl"]);
+    assert_eq!(deps[0].expected_file(MexPlatform::Win64), "ctrl.mexw64");
+    assert_eq!(deps[0].expected_file(MexPlatform::Glnxa64), "ctrl.mexa64");
+    assert_eq!(deps[0].expected_file(MexPlatform::Maca64), "ctrl.mexmaca64");
+    assert_eq!(deps[1].expected_file(MexPlatform::Maci64), "logic.m");
+
+    let dir = std::env::temp_dir().join(format!("rustylink-sfun-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("ctrl.mexa64"), b"").unwrap();
+    let dirs = [Utf8PathBuf::from_path_buf(dir.clone()).unwrap()];
+    assert_eq!(
+        deps[0].find(&dirs, MexPlatform::Glnxa64),
+        Some(dirs[0].join("ctrl.mexa64"))
+    );
+    assert_eq!(deps[0].find(&dirs, MexPlatform::Win64), None);
+    std::fs::remove_dir_all(dir).unwrap();
+}
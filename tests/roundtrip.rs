@@ -0,0 +1,218 @@
+//! Golden-file round-trip tests.
+//!
+//! Every fixture in `tests/fixtures/roundtrip/` — an `.slx` file or an
+//! unpacked archive directory — is parsed, regenerated through the
+//! generator and parsed again; the diff engine must find no differences,
+//! and regenerating twice must give the same archive members. The parsed
+//! model is also checked against `<fixture>.golden.json`, a summary of
+//! block paths, block types and connections.
+//!
+//! Add a fixture by dropping it into the directory and running
+//! `RUSTYLINK_BLESS=1 cargo test --test roundtrip` to write its golden file;
+//! see `tests/fixtures/roundtrip/README.md`.
+
+use anyhow::{Context, Result};
+use rustylink::analysis::connections;
+use rustylink::diff::diff_systems;
+use rustylink::model::{SlxArchive, System};
+use rustylink::parser::{SimulinkParser, ZipSource};
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Write};
+use std::path::{Path, PathBuf};
+
+const FIXTURE_DIR: &str = "tests/fixtures/roundtrip";
+/// Fixtures kept elsewhere in the repository for other tests.
+const EXTRA_FIXTURES: &[&str] = &["Simulink_UI_Test.slx"];
+const BLESS_VAR: &str = "RUSTYLINK_BLESS";
+
+fn fixtures() -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(FIXTURE_DIR)
+        .expect("fixture directory")
+        .map(|entry| entry.unwrap().path())
+        .filter(|p| p.is_dir() || p.extension().is_some_and(|e| e == "slx"))
+        .collect();
+    paths.sort();
+    paths.extend(EXTRA_FIXTURES.iter().map(PathBuf::from));
+    paths
+}
+
+/// The archive bytes of a fixture, zipping unpacked directories in memory.
+fn archive_bytes(fixture: &Path) -> Result<Vec<u8>> {
+    if !fixture.is_dir() {
+        return std::fs::read(fixture).with_context(|| format!("Read {}", fixture.display()));
+    }
+    let mut files = Vec::new();
+    collect_files(fixture, &mut files)?;
+    files.sort();
+    let mut zip = zip::ZipWriter::new(Cursor::new(Vec::new()));
+    for file in files {
+        let name = file
+            .strip_prefix(fixture)?
+            .to_string_lossy()
+            .replace('\\', "/");
+        zip.start_file(name, zip::write::FileOptions::default())?;
+        zip.write_all(&std::fs::read(&file)?)?;
+    }
+    Ok(zip.finish()?.into_inner())
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn parse(bytes: &[u8]) -> Result<System> {
+    let source = ZipSource::new(Cursor::new(bytes))?;
+    SimulinkParser::new("", source).parse_system_file("simulink/systems/system_root.xml")
+}
+
+fn regenerate(bytes: &[u8]) -> Result<Vec<u8>> {
+    let archive = SlxArchive::from_reader(Cursor::new(bytes))?;
+    let mut out = Cursor::new(Vec::new());
+    archive.write_to(&mut out)?;
+    Ok(out.into_inner())
+}
+
+fn members(bytes: &[u8]) -> Result<BTreeMap<String, Vec<u8>>> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes))?;
+    let mut members = BTreeMap::new();
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i)?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data)?;
+        members.insert(file.name().to_string(), data);
+    }
+    Ok(members)
+}
+
+/// Golden summary: the type of every block by path, and every connection as
+/// `system: src -> dst` with SIDs and ports (`<root>` for the top level).
+fn summary(system: &System) -> serde_json::Value {
+    fn walk(
+        system: &System,
+        prefix: &str,
+        blocks: &mut BTreeMap<String, String>,
+        lines: &mut Vec<String>,
+    ) {
+        let level = if prefix.is_empty() { "<root>" } else { prefix };
+        for c in connections(system) {
+            lines.push(format!(
+                "{}: {}#{}:{} -> {}#{}:{}",
+                level,
+                c.src.sid,
+                c.src.port_type,
+                c.src.port_index,
+                c.dst.sid,
+                c.dst.port_type,
+                c.dst.port_index
+            ));
+        }
+        for block in &system.blocks {
+            let path = match prefix {
+                "" => block.name.clone(),
+                _ => format!("{}/{}", prefix, block.name),
+            };
+            blocks.insert(path.clone(), block.block_type.clone());
+            if let Some(sub) = &block.subsystem {
+                walk(sub, &path, blocks, lines);
+            }
+        }
+    }
+    let mut blocks = BTreeMap::new();
+    let mut lines = Vec::new();
+    walk(system, "", &mut blocks, &mut lines);
+    lines.sort();
+    serde_json::json!({ "blocks": blocks, "connections": lines })
+}
+
+fn golden_path(fixture: &Path) -> PathBuf {
+    let name = fixture.file_stem().unwrap().to_string_lossy();
+    let dir = if fixture.starts_with(FIXTURE_DIR) {
+        fixture.parent().unwrap()
+    } else {
+        Path::new(FIXTURE_DIR)
+    };
+    dir.join(format!("{name}.golden.json"))
+}
+
+fn check_golden(fixture: &Path, system: &System) -> Result<()> {
+    let path = golden_path(fixture);
+    let actual = serde_json::to_string_pretty(&summary(system))? + "\n";
+    if std::env::var_os(BLESS_VAR).is_some() {
+        std::fs::write(&path, &actual)?;
+        return Ok(());
+    }
+    let expected = std::fs::read_to_string(&path).with_context(|| {
+        format!(
+            "Missing {}; run with {}=1 to create it",
+            path.display(),
+            BLESS_VAR
+        )
+    })?;
+    anyhow::ensure!(
+        expected == actual,
+        "parsed model differs from {} (run with {}=1 to update):\n{}",
+        path.display(),
+        BLESS_VAR,
+        actual
+    );
+    Ok(())
+}
+
+fn check_fixture(fixture: &Path) -> Result<()> {
+    let bytes = archive_bytes(fixture)?;
+    let original = parse(&bytes).context("parse fixture")?;
+    check_golden(fixture, &original)?;
+
+    let regenerated = regenerate(&bytes).context("regenerate")?;
+    let reparsed = parse(&regenerated).context("parse regenerated archive")?;
+    let diff = diff_systems(&original, &reparsed);
+    anyhow::ensure!(
+        diff.is_empty(),
+        "regenerated model differs:\n{}",
+        serde_json::to_string_pretty(&diff)?
+    );
+
+    let again = members(&regenerate(&regenerated)?)?;
+    for (name, data) in members(&regenerated)? {
+        anyhow::ensure!(
+            again.get(&name) == Some(&data),
+            "{name} changes when regenerating a second time"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn fixtures_round_trip() {
+    let fixtures = fixtures();
+    assert!(fixtures.len() > EXTRA_FIXTURES.len(), "no fixtures found");
+    let failures: Vec<String> = fixtures
+        .iter()
+        .filter_map(|fixture| {
+            check_fixture(fixture)
+                .err()
+                .map(|e| format!("{}: {:#}", fixture.display(), e))
+        })
+        .collect();
+    assert!(failures.is_empty(), "{}", failures.join("\n\n"));
+}
+
+#[test]
+fn unpacked_fixture_matches_its_files() {
+    let fixture = Path::new(FIXTURE_DIR).join("subsystem_ref");
+    let members = members(&archive_bytes(&fixture).unwrap()).unwrap();
+    assert!(members.contains_key("simulink/systems/system_root.xml"));
+    assert!(members.contains_key("[Content_Types].xml"));
+    assert_eq!(
+        golden_path(&fixture),
+        Path::new(FIXTURE_DIR).join("subsystem_ref.golden.json")
+    );
+}
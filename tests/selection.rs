@@ -0,0 +1,104 @@
+use camino::Utf8Path;
+use rustylink::model::System;
+use rustylink::selection::{Arg, Field, parse, select};
+use serde_json::json;
+
+fn model() -> System {
+    let xml = r#"<System>
+  <P Name="ZoomFactor">100</P>
+  <Block BlockType="Inport" Name="In" SID="1"/>
+  <Block BlockType="SubSystem" Name="Ctrl" SID="2">
+    <PortCounts in="1"/>
+    <System>
+      <Block BlockType="Gain" Name="K" SID="3">
+        <P Name="Gain">2</P>
+        <P Name="SaturateOnIntegerOverflow">off</P>
+      </Block>
+      <Block BlockType="Gain" Name="K/2" SID="4"><P Name="Gain">0.5</P></Block>
+    </System>
+  </Block>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    rustylink::block::parse_system_shallow(doc.root_element(), Utf8Path::new("")).unwrap()
+}
+
+#[test]
+fn parses_arguments_and_nested_selections() {
+    let fields =
+        parse(r#"blocks(type: "Gain", name: "a \"b\""), { path properties(Gain) }"#).unwrap();
+    assert_eq!(
+        fields,
+        [Field {
+            name: "blocks".into(),
+            args: vec![
+                Arg::Filter("type".into(), "Gain".into()),
+                Arg::Filter("name".into(), "a \"b\"".into()),
+            ],
+            selection: vec![
+                Field {
+                    name: "path".into(),
+                    args: vec![],
+                    selection: vec![],
+                },
+                Field {
+                    name: "properties".into(),
+                    args: vec![Arg::Name("Gain".into())],
+                    selection: vec![],
+                },
+            ],
+        }]
+    );
+    for bad in [
+        "",
+        "blocks {",
+        "blocks(type: Gain)",
+        "blocks) x",
+        "blocks(type: \"x",
+    ] {
+        assert!(parse(bad).is_err(), "{bad}");
+    }
+}
+
+#[test]
+fn selects_block_fields_and_filters() {
+    let system = model();
+    let selected = select(
+        &system,
+        r#"blocks(type:"Gain"){ path sid properties(Gain) }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        selected,
+        json!({"blocks": [
+            {"path": "Ctrl/K", "sid": "3", "properties": {"Gain": "2"}},
+            {"path": "Ctrl/K//2", "sid": "4", "properties": {"Gain": "0.5"}},
+        ]})
+    );
+
+    // Filters on parameters; `block` by path; root system fields.
+    let selected = select(
+        &system,
+        r#"blocks(Gain: "0.5") { name } block(path: "Ctrl") { type port_counts } properties"#,
+    )
+    .unwrap();
+    assert_eq!(selected["blocks"], json!([{"name": "K/2"}]));
+    assert_eq!(selected["block"]["type"], "SubSystem");
+    assert_eq!(selected["block"]["port_counts"]["ins"], 1);
+    assert_eq!(selected["properties"], json!({"ZoomFactor": "100"}));
+}
+
+#[test]
+fn selects_inside_nested_json() {
+    let system = model();
+    let selected = select(
+        &system,
+        r#"block(path: "Ctrl") { subsystem { blocks(name: "K") { name properties(Gain) } } }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        selected["block"]["subsystem"],
+        json!({"blocks": [{"name": "K", "properties": {"Gain": "2"}}]})
+    );
+    let missing = select(&system, r#"block(path: "Nope") { name }"#).unwrap();
+    assert_eq!(missing, json!({"block": null}));
+}
@@ -0,0 +1,187 @@
+#![cfg(feature = "server")]
+
+use axum::body::{Body, to_bytes};
+use axum::http::{Request, StatusCode};
+use rustylink::server::{
+    FULL_HASH_HEADER, MODEL_ID_HEADER, STRUCTURAL_HASH_HEADER, ServerState, router,
+};
+use serde_json::{Value, json};
+use std::io::{Cursor, Write};
+use tower::ServiceExt;
+
+fn slx(gain: &str) -> Vec<u8> {
+    let xml = format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Inport" Name="In" SID="1"/>
+  <Block BlockType="SubSystem" Name="Sub" SID="2">
+    <System>
+      <Block BlockType="Gain" Name="K" SID="3"><P Name="Gain">{gain}</P></Block>
+    </System>
+  </Block>
+  <Block BlockType="Gain" Name="K2" SID="4"/>
+</System>
+"#
+    );
+    let mut buf = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        zip.start_file(
+            "simulink/systems/system_root.xml",
+            zip::write::FileOptions::default(),
+        )
+        .unwrap();
+        zip.write_all(xml.as_bytes()).unwrap();
+        zip.finish().unwrap();
+    }
+    buf.into_inner()
+}
+
+async fn send(app: &axum::Router, request: Request<Body>) -> (StatusCode, Option<String>, Value) {
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let id = response
+        .headers()
+        .get(MODEL_ID_HEADER)
+        .map(|v| v.to_str().unwrap().to_string());
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let json = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body).unwrap()
+    };
+    (status, id, json)
+}
+
+fn post(uri: &str, body: impl Into<Body>) -> Request<Body> {
+    Request::post(uri).body(body.into()).unwrap()
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::get(uri).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn parse_and_query() {
+    let app = router(ServerState::new());
+    let (status, id, model) = send(&app, post("/parse", slx("2"))).await;
+    assert_eq!(status, StatusCode::OK);
+    let id = id.unwrap();
+    assert_eq!(model["blocks"][1]["name"], "Sub");
+
+    // The same content maps to the same cached model.
+    let (_, again, _) = send(&app, post("/parse", slx("2"))).await;
+    assert_eq!(again.as_deref(), Some(id.as_str()));
+
+    let (status, _, block) = send(&app, get(&format!("/model/{id}/query?path=Sub/K"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(block["properties"]["Gain"], "2");
+
+    let (_, _, gains) = send(&app, get(&format!("/model/{id}/query?type=Gain"))).await;
+    let paths: Vec<&str> = gains
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|m| m["path"].as_str().unwrap())
+        .collect();
+    assert_eq!(paths, ["Sub/K", "K2"]);
+
+    let (_, _, root) = send(&app, get(&format!("/model/{id}/query"))).await;
+    assert_eq!(root, model);
+
+    let (status, _, err) = send(&app, get(&format!("/model/{id}/query?path=Nope"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(err["error"], "No block at Nope");
+
+    let (status, _, _) = send(
+        &app,
+        Request::delete(format!("/model/{id}"))
+            .body(Body::empty())
+            .unwrap(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _, _) = send(&app, get(&format!("/model/{id}/query"))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn query_selects_fields() {
+    let app = router(ServerState::new());
+    let (_, id, _) = send(&app, post("/parse", slx("2"))).await;
+    let id = id.unwrap();
+    // blocks(type:"Gain"){path properties(Gain)}
+    let select = "blocks(type:%22Gain%22)%7Bpath%20properties(Gain)%7D";
+    let (status, _, selected) =
+        send(&app, get(&format!("/model/{id}/query?select={select}"))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        selected,
+        json!({"blocks": [
+            {"path": "Sub/K", "properties": {"Gain": "2"}},
+            {"path": "K2", "properties": {}},
+        ]})
+    );
+
+    let (status, _, err) = send(&app, get(&format!("/model/{id}/query?select=blocks%7B"))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(err["error"].as_str().unwrap().starts_with("Expected '}'"));
+}
+
+#[tokio::test]
+async fn diff_cached_models() {
+    let app = router(ServerState::new());
+    let (_, old, _) = send(&app, post("/parse", slx("2"))).await;
+    let (_, new, _) = send(&app, post("/parse", slx("3"))).await;
+    let body = json!({ "old": old.unwrap(), "new": new.unwrap() }).to_string();
+    let request = Request::post("/diff")
+        .header("content-type", "application/json")
+        .body(Body::from(body))
+        .unwrap();
+    let (status, _, diff) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(diff["blocks"][0]["path"], "Sub/K");
+    assert_eq!(diff["blocks"][0]["parameters"][0]["new"], "3");
+}
+
+#[tokio::test]
+async fn equal_models_diff_by_hash() {
+    let app = router(ServerState::new());
+    // Different archive bytes, so a different ID, holding the same model.
+    let mut commented = Cursor::new(slx("2"));
+    {
+        let mut zip = zip::ZipWriter::new_append(&mut commented).unwrap();
+        zip.set_comment("re-saved");
+        zip.finish().unwrap();
+    }
+
+    let mut hashes = Vec::new();
+    let mut ids = Vec::new();
+    for bytes in [slx("2"), commented.into_inner()] {
+        let response = app.clone().oneshot(post("/parse", bytes)).await.unwrap();
+        let header = |name| response.headers()[name].to_str().unwrap().to_string();
+        hashes.push((header(STRUCTURAL_HASH_HEADER), header(FULL_HASH_HEADER)));
+        ids.push(header(MODEL_ID_HEADER));
+    }
+    assert_ne!(ids[0], ids[1]);
+    assert_eq!(hashes[0], hashes[1]);
+    assert_eq!(hashes[0].0.len(), 16);
+
+    let body = json!({ "old": ids[0], "new": ids[1] }).to_string();
+    let request = Request::post("/diff")
+        .header("content-type", "application/json")
+        .body(Body::from(body))
+        .unwrap();
+    let (status, _, diff) = send(&app, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(diff, json!({ "blocks": [], "connections": [] }));
+}
+
+#[tokio::test]
+async fn invalid_uploads_are_rejected() {
+    let app = router(ServerState::new());
+    let (status, id, err) = send(&app, post("/parse", "not a zip")).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(id.is_none());
+    assert!(err["error"].as_str().unwrap().contains("zip"));
+}
@@ -0,0 +1,148 @@
+use rustylink::service::{
+    METHOD_NOT_FOUND, PARSE_ERROR, Service, read_message, run, write_message,
+};
+use serde_json::{Value, json};
+use std::io::{Cursor, Write};
+use tempfile::tempdir;
+
+const ROOT_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Reference" Name="F" SID="1">
+    <P Name="SourceBlock">Lib/Filter</P>
+    <P Name="SourceType">SubSystem</P>
+  </Block>
+  <Block BlockType="SubSystem" Name="Sub" SID="2">
+    <System>
+      <Block BlockType="Gain" Name="K" SID="3"><P Name="Gain">2.5</P></Block>
+      <Block BlockType="Reference" Name="F2" SID="4"><P Name="SourceBlock">Lib/Filter</P></Block>
+    </System>
+  </Block>
+</System>
+"#;
+
+fn write_model(path: &std::path::Path) {
+    let file = std::fs::File::create(path).unwrap();
+    let mut zip = zip::ZipWriter::new(file);
+    zip.start_file(
+        "simulink/systems/system_root.xml",
+        zip::write::FileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(ROOT_XML.as_bytes()).unwrap();
+    zip.finish().unwrap();
+}
+
+fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params })
+}
+
+#[test]
+fn requests_are_answered() {
+    let dir = tempdir().unwrap();
+    let model = dir.path().join("m.slx");
+    write_model(&model);
+    std::fs::write(dir.path().join("Lib.slx"), b"").unwrap();
+    let file = model.to_str().unwrap();
+
+    let mut service = Service::new();
+    let (response, notifications) =
+        service.handle(&request(1, "model/open", json!({ "file": file })));
+    assert_eq!(response.unwrap()["result"]["blocks"], 4);
+    assert_eq!(notifications.len(), 1);
+    let diagnostics = &notifications[0]["params"]["diagnostics"];
+    assert_eq!(notifications[0]["method"], "model/publishDiagnostics");
+    assert_eq!(diagnostics[0]["block"], "Sub/K");
+    assert_eq!(diagnostics[0]["source"], "magic-number");
+
+    let (response, _) = service.handle(&request(
+        2,
+        "block/hover",
+        json!({ "file": file, "block": "Sub/K" }),
+    ));
+    let hover = &response.unwrap()["result"];
+    assert_eq!(hover["type"], "Gain");
+    assert_eq!(hover["parameters"]["Gain"], "2.5");
+
+    let (response, _) = service.handle(&request(
+        3,
+        "block/definition",
+        json!({ "file": file, "block": "F" }),
+    ));
+    let definition = &response.unwrap()["result"];
+    assert_eq!(
+        definition["file"],
+        dir.path().join("Lib.slx").to_str().unwrap()
+    );
+    assert_eq!(definition["block"], "Filter");
+
+    let (response, _) = service.handle(&request(
+        4,
+        "block/whereUsed",
+        json!({ "source": "Lib/Filter" }),
+    ));
+    let response = response.unwrap();
+    let uses: Vec<&str> = response["result"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|u| u["block"].as_str().unwrap())
+        .collect();
+    assert_eq!(uses, ["F", "Sub/F2"]);
+
+    let (response, _) = service.handle(&request(
+        5,
+        "block/hover",
+        json!({ "file": file, "block": "Missing" }),
+    ));
+    assert_eq!(response.unwrap()["error"]["message"], "No block at Missing");
+
+    let (response, _) = service.handle(&request(6, "block/rename", json!({})));
+    assert_eq!(response.unwrap()["error"]["code"], METHOD_NOT_FOUND);
+
+    // Notifications (no id) get no response.
+    let (response, _) = service
+        .handle(&json!({ "jsonrpc": "2.0", "method": "model/close", "params": { "file": file } }));
+    assert!(response.is_none());
+    let (response, _) = service.handle(&request(
+        7,
+        "block/hover",
+        json!({ "file": file, "block": "F" }),
+    ));
+    assert!(
+        response.unwrap()["error"]["message"]
+            .as_str()
+            .unwrap()
+            .ends_with("is not open")
+    );
+}
+
+#[test]
+fn stdio_framing() {
+    let mut input = Vec::new();
+    write_message(&mut input, &request(1, "initialize", json!({}))).unwrap();
+    // Line-delimited JSON is accepted as well.
+    input.extend_from_slice(b"{\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"shutdown\"}\n");
+    input.extend_from_slice(b"not json\n");
+    write_message(&mut input, &json!({ "jsonrpc": "2.0", "method": "exit" })).unwrap();
+    write_message(&mut input, &request(3, "initialize", json!({}))).unwrap();
+
+    let mut output = Vec::new();
+    run(Cursor::new(input), &mut output).unwrap();
+
+    let mut reader = Cursor::new(output);
+    let mut messages = Vec::new();
+    while let Some(text) = read_message(&mut reader).unwrap() {
+        messages.push(serde_json::from_str::<Value>(&text).unwrap());
+    }
+    // Nothing is answered after `exit`.
+    assert_eq!(messages.len(), 3);
+    assert_eq!(messages[0]["id"], 1);
+    assert!(
+        messages[0]["result"]["capabilities"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("block/whereUsed"))
+    );
+    assert_eq!(messages[1]["id"], 2);
+    assert_eq!(messages[2]["error"]["code"], PARSE_ERROR);
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use common::parse;
+use rustylink::signal_kind::{SignalKind, infer_line_kinds};
+
+#[test]
+fn line_kinds_are_inferred_from_sources() {
+    let system = parse(
+        r#"<System>
+  <Block BlockType="Mux" Name="Mux" SID="1"><P Name="Inputs">[2 3]</P></Block>
+  <Block BlockType="Gain" Name="Gain" SID="2"/>
+  <Block BlockType="BusCreator" Name="Bus" SID="3"/>
+  <Block BlockType="If" Name="If" SID="4"/>
+  <Block BlockType="SubSystem" Name="Action" SID="5"/>
+  <Block BlockType="Sum" Name="Sum" SID="6"/>
+  <Block BlockType="Outport" Name="Out" SID="7"/>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+  <Line><P Name="Src">2#out:1</P><P Name="Dst">7#in:1</P></Line>
+  <Line><P Name="Src">3#out:1</P><P Name="Dst">7#in:1</P></Line>
+  <Line><P Name="Src">4#out:1</P><P Name="Dst">5#ifaction</P></Line>
+  <Line><P Name="Src">6#out:1</P><P Name="Dst">7#in:1</P></Line>
+</System>"#,
+    );
+    let kinds = infer_line_kinds(&system.blocks, &system.lines);
+    assert_eq!(
+        kinds,
+        vec![
+            SignalKind::Vector(5),
+            SignalKind::Vector(5),
+            SignalKind::Bus,
+            SignalKind::Control,
+            SignalKind::Scalar,
+        ]
+    );
+}
+
+#[test]
+fn inport_dimensions_and_constant_literals_set_width() {
+    let system = parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="In" SID="1"><P Name="PortDimensions">[2 2]</P></Block>
+  <Block BlockType="Constant" Name="C" SID="2"><P Name="Value">[1.5 2 3]</P></Block>
+  <Block BlockType="Inport" Name="In2" SID="3"><P Name="PortDimensions">-1</P></Block>
+  <Block BlockType="Outport" Name="Out" SID="4"/>
+  <Block BlockType="Inport" Name="Huge" SID="5"><P Name="PortDimensions">[100000 100000]</P></Block>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">4#in:1</P></Line>
+  <Line><P Name="Src">2#out:1</P><P Name="Dst">4#in:1</P></Line>
+  <Line><P Name="Src">3#out:1</P><P Name="Dst">4#in:1</P></Line>
+  <Line><P Name="Src">5#out:1</P><P Name="Dst">4#in:1</P></Line>
+</System>"#,
+    );
+    let kinds = infer_line_kinds(&system.blocks, &system.lines);
+    assert_eq!(
+        kinds,
+        vec![
+            SignalKind::Vector(4),
+            SignalKind::Vector(3),
+            SignalKind::Scalar,
+            SignalKind::Scalar
+        ]
+    );
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use common::parse;
+use rustylink::stable_json;
+
+const MODEL: &str = r#"<System>
+  <P Name="ZoomFactor">100</P>
+  <P Name="Location">[0, 0, 800, 600]</P>
+  <Block BlockType="Gain" Name="K" SID="10">
+    <P Name="Gain">2</P>
+    <P Name="Position">[100, 10, 130, 40]</P>
+  </Block>
+  <Block BlockType="Inport" Name="In" SID="2"/>
+  <Block BlockType="Outport" Name="Out" SID="9"/>
+  <Line>
+    <P Name="Src">10#out:1</P>
+    <P Name="Dst">9#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">2#out:1</P>
+    <P Name="Dst">10#in:1</P>
+  </Line>
+</System>"#;
+
+/// The same model as saved by another version: blocks, lines and system
+/// properties in a different order. The `<P>` order within a block is kept
+/// in `child_order`, so it stays the same.
+const REORDERED: &str = r#"<System>
+  <P Name="Location">[0, 0, 800, 600]</P>
+  <P Name="ZoomFactor">100</P>
+  <Block BlockType="Outport" Name="Out" SID="9"/>
+  <Block BlockType="Inport" Name="In" SID="2"/>
+  <Block BlockType="Gain" Name="K" SID="10">
+    <P Name="Gain">2</P>
+    <P Name="Position">[100, 10, 130, 40]</P>
+  </Block>
+  <Line>
+    <P Name="Src">2#out:1</P>
+    <P Name="Dst">10#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">10#out:1</P>
+    <P Name="Dst">9#in:1</P>
+  </Line>
+</System>"#;
+
+#[test]
+fn blocks_are_sorted_by_numeric_sid_and_keys_by_name() {
+    let value = stable_json::to_value(&parse(MODEL)).unwrap();
+    let sids: Vec<&str> = value["blocks"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|b| b["sid"].as_str().unwrap())
+        .collect();
+    assert_eq!(sids, ["2", "9", "10"]);
+    let srcs: Vec<&str> = value["lines"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|l| l["src"]["sid"].as_str().unwrap())
+        .collect();
+    assert_eq!(srcs, ["2", "10"]);
+    let keys: Vec<&String> = value["properties"].as_object().unwrap().keys().collect();
+    assert_eq!(keys, ["Location", "ZoomFactor"]);
+}
+
+#[test]
+fn reordered_models_give_identical_output() {
+    let a = stable_json::to_string_pretty(&parse(MODEL)).unwrap();
+    let b = stable_json::to_string_pretty(&parse(REORDERED)).unwrap();
+    assert_eq!(a, b);
+    // The plain output follows document order.
+    assert_ne!(
+        serde_json::to_string_pretty(&parse(MODEL)).unwrap(),
+        serde_json::to_string_pretty(&parse(REORDERED)).unwrap()
+    );
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::parse;
+use rustylink::model::SlxArchive;
+use rustylink::stimulus::{
+    harness_model, root_inports, root_outports, stimulus_csv, stimulus_json,
+};
+use std::io::Cursor;
+
+fn model() -> rustylink::model::System {
+    parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="pos, xy" SID="2">
+    <P Name="Port">2</P>
+    <P Name="PortDimensions">[2 1]</P>
+    <P Name="OutDataTypeStr">single</P>
+  </Block>
+  <Block BlockType="Inport" Name="speed" SID="1">
+    <P Name="OutDataTypeStr">Inherit: auto</P>
+    <P Name="SampleTime">0.01</P>
+  </Block>
+  <Block BlockType="Inport" Name="1mode" SID="3">
+    <P Name="Port">3</P>
+    <P Name="OutDataTypeStr">boolean</P>
+    <P Name="PortDimensions">-1</P>
+  </Block>
+  <Block BlockType="Outport" Name="y2" SID="5"><P Name="Port">2</P></Block>
+  <Block BlockType="Outport" Name="y1" SID="4"/>
+</System>"#,
+    )
+}
+
+#[test]
+fn inports_are_read_in_port_order() {
+    let inports = root_inports(&model());
+    let names: Vec<&str> = inports.iter().map(|i| i.name.as_str()).collect();
+    assert_eq!(names, ["speed", "pos, xy", "1mode"]);
+    assert_eq!(inports[0].data_type, "double");
+    assert_eq!(inports[0].sample_time.as_deref(), Some("0.01"));
+    assert_eq!(inports[1].data_type, "single");
+    assert_eq!(inports[1].dimensions, [2, 1]);
+    assert_eq!(inports[1].width(), 2);
+    assert_eq!(inports[1].field_name(), "pos__xy");
+    assert_eq!(inports[2].dimensions, [1]);
+    assert_eq!(inports[2].field_name(), "x1mode");
+    assert_eq!(root_outports(&model()), ["y1", "y2"]);
+}
+
+#[test]
+fn csv_template_has_a_column_per_element() {
+    let csv = stimulus_csv(&root_inports(&model()));
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "time,speed,\"pos, xy(1)\",\"pos, xy(2)\",1mode",
+            "0,0,0,0,0"
+        ]
+    );
+}
+
+#[test]
+fn json_template_matches_from_workspace_format() {
+    let json = stimulus_json(&root_inports(&model()));
+    assert_eq!(json["interface"][1]["dataType"], "single");
+    assert_eq!(
+        json["interface"][1]["dimensions"],
+        serde_json::json!([2, 1])
+    );
+    let pos = &json["stimulus"]["pos__xy"];
+    assert_eq!(pos["time"], serde_json::json!([0.0]));
+    assert_eq!(pos["signals"]["values"], serde_json::json!([[0, 0]]));
+    assert_eq!(pos["signals"]["dimensions"], serde_json::json!([2, 1]));
+    assert_eq!(
+        json["stimulus"]["speed"]["signals"]["dimensions"],
+        serde_json::json!(1)
+    );
+}
+
+#[test]
+fn harness_wires_from_workspace_blocks_to_the_model() {
+    let root = model();
+    let archive = harness_model("plant", &root_inports(&root), &root_outports(&root)).unwrap();
+    let mut out = Cursor::new(Vec::new());
+    archive.write_to(&mut out).unwrap();
+    let archive = SlxArchive::from_reader(Cursor::new(out.into_inner())).unwrap();
+    let harness = archive.root_system().unwrap();
+
+    let model_block = &harness.blocks[0];
+    assert_eq!(model_block.block_type, "ModelReference");
+    assert_eq!(model_block.properties["ModelName"], "plant");
+    let counts = model_block.port_counts.as_ref().unwrap();
+    assert_eq!((counts.ins, counts.outs), (Some(3), Some(2)));
+
+    let sources: Vec<&rustylink::model::Block> = harness
+        .blocks
+        .iter()
+        .filter(|b| b.block_type == "FromWorkspace")
+        .collect();
+    assert_eq!(sources.len(), 3);
+    assert_eq!(sources[1].properties["VariableName"], "stimulus.pos__xy");
+    assert_eq!(sources[0].properties["SampleTime"], "0.01");
+    assert!(!sources[0].properties.contains_key("Interpolate"));
+    assert_eq!(sources[2].properties["Interpolate"], "off");
+
+    let dst_ports: Vec<(String, u32)> = harness
+        .lines
+        .iter()
+        .filter_map(|l| l.dst.as_ref())
+        .map(|d| (d.sid.clone(), d.port_index))
+        .collect();
+    assert_eq!(
+        dst_ports,
+        [
+            ("1".to_string(), 1),
+            ("1".to_string(), 2),
+            ("1".to_string(), 3),
+            ("5".to_string(), 1),
+            ("6".to_string(), 1),
+        ]
+    );
+    let outports: Vec<&str> = harness
+        .blocks
+        .iter()
+        .filter(|b| b.block_type == "Outport")
+        .map(|b| b.name.as_str())
+        .collect();
+    assert_eq!(outports, ["y1", "y2"]);
+}
@@ -0,0 +1,745 @@
+use camino::Utf8Path;
+use rustylink::generator::system_xml::generate_system_xml;
+use rustylink::model::{SlxArchive, SlxContent, System};
+use rustylink::transform::{
+    AnonymizeOptions, PortOrder, anonymize, anonymize_archive, disable_link, disable_link_in,
+    extract_to_library, name_signals, new_library, order_ports, order_ports_in, restore_link,
+    restore_link_in,
+};
+use std::io::{Cursor, Read, Write};
+
+const ROOT_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="SubSystem" Name="A" SID="1">
+    <PortCounts in="1" out="1"/>
+    <P Name="Position">[100, 100, 160, 140]</P>
+    <P Name="ZOrder">1</P>
+    <P Name="TreatAsAtomicUnit">on</P>
+    <System Ref="system_1"/>
+  </Block>
+  <Block BlockType="SubSystem" Name="B" SID="2">
+    <PortCounts in="1" out="1"/>
+    <P Name="Position">[100, 200, 160, 240]</P>
+    <P Name="ZOrder">2</P>
+    <System Ref="system_2"/>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="3">
+    <P Name="Position">[200, 100, 230, 130]</P>
+  </Block>
+</System>
+"#;
+
+/// In -> Gain -> nested subsystem -> Out, with the nested system in `inner`.
+fn filter_xml(base: u32, inner: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Inport" Name="u" SID="{a}"/>
+  <Block BlockType="Gain" Name="G" SID="{b}"/>
+  <Block BlockType="SubSystem" Name="N" SID="{c}">
+    <System Ref="{inner}"/>
+  </Block>
+  <Block BlockType="Outport" Name="y" SID="{d}"/>
+  <Line><P Name="Src">{a}#out:1</P><P Name="Dst">{b}#in:1</P></Line>
+  <Line><P Name="Src">{b}#out:1</P><P Name="Dst">{c}#in:1</P></Line>
+  <Line><P Name="Src">{c}#out:1</P><P Name="Dst">{d}#in:1</P></Line>
+</System>
+"#,
+        a = base,
+        b = base + 1,
+        c = base + 2,
+        d = base + 3,
+    )
+}
+
+const INNER_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Inport" Name="x" SID="50"/>
+  <Block BlockType="Outport" Name="z" SID="51"/>
+  <Line><P Name="Src">50#out:1</P><P Name="Dst">51#in:1</P></Line>
+</System>
+"#;
+
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes" ?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+  <Relationship Id="system_1" Target="system_1.xml" Type="http://schemas.mathworks.com/simulink/2010/relationships/system"/>
+  <Relationship Id="system_2" Target="system_2.xml" Type="http://schemas.mathworks.com/simulink/2010/relationships/system"/>
+</Relationships>
+"#;
+
+fn model() -> SlxArchive {
+    archive([
+        ("simulink/systems/system_root.xml", ROOT_XML.to_string()),
+        (
+            "simulink/systems/_rels/system_root.xml.rels",
+            ROOT_RELS.to_string(),
+        ),
+        ("simulink/systems/system_1.xml", filter_xml(10, "system_3")),
+        ("simulink/systems/system_2.xml", filter_xml(20, "system_4")),
+        ("simulink/systems/system_3.xml", INNER_XML.to_string()),
+        ("simulink/systems/system_4.xml", INNER_XML.to_string()),
+    ])
+}
+
+fn archive<const N: usize>(files: [(&str, String); N]) -> SlxArchive {
+    let mut buf = Cursor::new(Vec::new());
+    {
+        let mut zip = zip::ZipWriter::new(&mut buf);
+        for (name, data) in files {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    buf.set_position(0);
+    SlxArchive::from_reader(buf).unwrap()
+}
+
+fn roundtrip(archive: &SlxArchive) -> (SlxArchive, Vec<u8>) {
+    let mut out = Cursor::new(Vec::new());
+    archive.write_to(&mut out).unwrap();
+    let bytes = out.into_inner();
+    (
+        SlxArchive::from_reader(Cursor::new(bytes.clone())).unwrap(),
+        bytes,
+    )
+}
+
+fn raw_text(archive: &SlxArchive, path: &str) -> String {
+    String::from_utf8(archive.get_raw(path).unwrap().to_vec()).unwrap()
+}
+
+#[test]
+fn extracts_subsystem_into_new_library() {
+    let mut model = model();
+    let mut lib = new_library("FilterLib").unwrap();
+    let result = extract_to_library(&mut model, &["A", "B"], &mut lib, "FilterLib").unwrap();
+    assert_eq!(result.source_block, "FilterLib/A");
+    assert_eq!(result.replaced, ["A", "B"]);
+
+    let (lib, bytes) = roundtrip(&lib);
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let mut blockdiagram = String::new();
+    zip.by_name("simulink/blockdiagram.xml")
+        .unwrap()
+        .read_to_string(&mut blockdiagram)
+        .unwrap();
+    assert!(blockdiagram.contains("<Library>"));
+
+    let block = &lib.root_system().unwrap().blocks[0];
+    assert_eq!(block.name, "A");
+    assert_eq!(block.block_type, "SubSystem");
+    assert_eq!(block.system_ref.as_deref(), Some("system_1"));
+    assert_eq!(block.properties["TreatAsAtomicUnit"], "on");
+    let top = lib.get_system("simulink/systems/system_1.xml").unwrap();
+    assert_eq!(top.blocks[2].system_ref.as_deref(), Some("system_2"));
+    assert!(lib.get_system("simulink/systems/system_2.xml").is_some());
+    assert!(raw_text(&lib, "simulink/systems/_rels/system_root.xml.rels").contains("system_1.xml"));
+    assert!(raw_text(&lib, "simulink/systems/_rels/system_1.xml.rels").contains("system_2.xml"));
+
+    let (model, _) = roundtrip(&model);
+    let root = model.root_system().unwrap();
+    for (block, position) in root.blocks[..2]
+        .iter()
+        .zip(["[100, 100, 160, 140]", "[100, 200, 160, 240]"])
+    {
+        assert_eq!(block.block_type, "Reference");
+        assert_eq!(block.properties["SourceBlock"], "FilterLib/A");
+        assert_eq!(block.properties["SourceType"], "SubSystem");
+        assert_eq!(block.properties["Position"], position);
+        assert!(!block.properties.contains_key("TreatAsAtomicUnit"));
+        assert!(block.system_ref.is_none());
+        assert_eq!(block.port_counts.as_ref().unwrap().ins, Some(1));
+    }
+    assert_eq!(root.blocks[2].block_type, "Gain");
+    let systems: Vec<&str> = model
+        .entries
+        .iter()
+        .filter(|e| matches!(e.content, SlxContent::SystemXml(_)))
+        .map(|e| e.path.as_str())
+        .collect();
+    assert_eq!(systems, ["simulink/systems/system_root.xml"]);
+    assert!(!raw_text(&model, "simulink/systems/_rels/system_root.xml.rels").contains("system_1"));
+}
+
+#[test]
+fn extracts_into_existing_library() {
+    let mut lib = new_library("FilterLib").unwrap();
+    extract_to_library(&mut model(), &["A"], &mut lib, "FilterLib").unwrap();
+    let (mut lib, _) = roundtrip(&lib);
+
+    let mut model = model();
+    let result = extract_to_library(&mut model, &["B"], &mut lib, "FilterLib").unwrap();
+    assert_eq!(result.source_block, "FilterLib/B");
+
+    let root = lib.root_system().unwrap();
+    let names: Vec<&str> = root.blocks.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, ["A", "B"]);
+    assert_eq!(root.blocks[1].system_ref.as_deref(), Some("system_3"));
+    assert_ne!(root.blocks[0].sid, root.blocks[1].sid);
+    // Stacked below the first library block.
+    assert_eq!(
+        root.blocks[1].position.as_deref(),
+        Some("[100, 180, 160, 220]")
+    );
+    let top = lib.get_system("simulink/systems/system_3.xml").unwrap();
+    assert_eq!(top.blocks[2].system_ref.as_deref(), Some("system_4"));
+    // `A` is still a subsystem in this model.
+    assert!(model.get_system("simulink/systems/system_1.xml").is_some());
+}
+
+#[test]
+fn rejects_invalid_extractions() {
+    let mut lib = new_library("FilterLib").unwrap();
+    extract_to_library(&mut model(), &["A"], &mut lib, "FilterLib").unwrap();
+    let err = extract_to_library(&mut model(), &["A"], &mut lib, "FilterLib").unwrap_err();
+    assert!(err.to_string().contains("already contains"));
+
+    let mut lib = new_library("FilterLib").unwrap();
+    assert!(extract_to_library(&mut model(), &["K"], &mut lib, "FilterLib").is_err());
+    assert!(extract_to_library(&mut model(), &["Missing"], &mut lib, "FilterLib").is_err());
+    assert!(extract_to_library(&mut model(), &["A", "A/N"], &mut lib, "FilterLib").is_err());
+    assert!(extract_to_library(&mut model(), &[], &mut lib, "FilterLib").is_err());
+
+    assert!(new_library("1bad").is_err());
+    assert!(new_library("bad name").is_err());
+}
+
+const LINKED_XML: &str = r#"<System>
+  <Block BlockType="Reference" Name="Speed PI" SID="10">
+    <P Name="Position">[100, 100, 160, 140]</P>
+    <P Name="SourceBlock">CtrlLib/PI</P>
+    <P Name="SourceType">SubSystem</P>
+    <P Name="LibraryVersion">1.4</P>
+    <InstanceData>
+      <P Name="Kp">5</P>
+      <P Name="ContentPreviewEnabled">on</P>
+    </InstanceData>
+    <LinkData>
+      <DialogParameters BlockName="P"><P Name="Gain">2*Kp</P></DialogParameters>
+    </LinkData>
+  </Block>
+</System>"#;
+
+const PI_LIBRARY_XML: &str = r#"<System>
+  <Block BlockType="SubSystem" Name="PI" SID="1">
+    <PortCounts in="1" out="1"/>
+    <Mask>
+      <MaskParameter Name="Kp" Type="edit"><Value>1</Value></MaskParameter>
+    </Mask>
+    <System>
+      <Block BlockType="Gain" Name="P" SID="2"><P Name="Gain">Kp</P></Block>
+    </System>
+  </Block>
+</System>"#;
+
+fn parse_system(xml: &str) -> System {
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    rustylink::block::parse_system_shallow(doc.root_element(), Utf8Path::new("")).unwrap()
+}
+
+#[test]
+fn disables_and_restores_library_link() {
+    let template = parse_system(PI_LIBRARY_XML).blocks.remove(0);
+    let mut system = parse_system(LINKED_XML);
+    let block = &mut system.blocks[0];
+    assert!(disable_link(block).is_err(), "content not resolved");
+    block.instantiate_library_block(&template);
+
+    disable_link(block).unwrap();
+    assert_eq!(block.block_type, "SubSystem");
+    let names: Vec<&str> = block.properties.keys().map(String::as_str).collect();
+    assert_eq!(
+        names,
+        [
+            "Position",
+            "AncestorBlock",
+            "LibraryVersion",
+            "ContentPreviewEnabled"
+        ]
+    );
+    assert_eq!(block.properties["AncestorBlock"], "CtrlLib/PI");
+    assert!(block.instance_data.is_none() && block.link_data.is_none());
+    let xml = generate_system_xml(&system);
+    assert!(xml.contains(r#"<Block BlockType="SubSystem" Name="Speed PI" SID="10">"#));
+    assert!(xml.contains("<Mask>") && xml.contains("<System>"));
+    assert!(!xml.contains("SourceBlock") && !xml.contains("InstanceData"));
+
+    // The disabled link parses back the same way.
+    let mut block = parse_system(&xml).blocks.remove(0);
+    let inner = block.subsystem.as_deref().unwrap();
+    assert_eq!(inner.blocks[0].properties["Gain"], "2*Kp");
+    assert!(disable_link(&mut block).is_err(), "already disabled");
+
+    restore_link(&mut block).unwrap();
+    assert_eq!(block.block_type, "Reference");
+    assert_eq!(block.properties["SourceBlock"], "CtrlLib/PI");
+    assert_eq!(block.properties["SourceType"], "SubSystem");
+    assert_eq!(block.properties["LibraryVersion"], "1.4");
+    assert!(!block.properties.contains_key("AncestorBlock"));
+    assert!(block.subsystem.is_none() && block.mask.is_none());
+    assert_eq!(block.library_source.as_deref(), Some("CtrlLib"));
+    assert_eq!(block.instance_data.as_ref().unwrap().properties["Kp"], "5");
+    assert_eq!(
+        block.library_overrides.as_ref().unwrap().parameters["Kp"],
+        "5"
+    );
+    assert!(restore_link(&mut block).is_err(), "already linked");
+}
+
+#[test]
+fn disables_and_restores_link_in_archive() {
+    let mut lib = new_library("FilterLib").unwrap();
+    let mut model = model();
+    extract_to_library(&mut model, &["A", "B"], &mut lib, "FilterLib").unwrap();
+    let (lib, _) = roundtrip(&lib);
+    let (mut model, _) = roundtrip(&model);
+
+    disable_link_in(&mut model, "B", &lib).unwrap();
+    let (model, _) = roundtrip(&model);
+    let root = model.root_system().unwrap();
+    let block = &root.blocks[1];
+    assert_eq!(block.block_type, "SubSystem");
+    assert_eq!(block.properties["AncestorBlock"], "FilterLib/A");
+    assert_eq!(block.system_ref.as_deref(), Some("system_1"));
+    assert_eq!(root.blocks[0].block_type, "Reference");
+
+    // The copied blocks get SIDs of their own and the lines follow them.
+    let top = model.get_system("simulink/systems/system_1.xml").unwrap();
+    let sids: Vec<&str> = top.blocks.iter().filter_map(|b| b.sid.as_deref()).collect();
+    assert_eq!(sids, ["4", "5", "6", "9"]);
+    assert_eq!(top.lines[0].properties["Src"], "4#out:1");
+    assert_eq!(top.lines[2].properties["Dst"], "9#in:1");
+    assert_eq!(top.blocks[2].system_ref.as_deref(), Some("system_2"));
+    let nested = model.get_system("simulink/systems/system_2.xml").unwrap();
+    assert_eq!(nested.lines[0].properties["Src"], "7#out:1");
+    assert!(
+        raw_text(&model, "simulink/systems/_rels/system_root.xml.rels").contains("system_1.xml")
+    );
+    assert!(raw_text(&model, "simulink/systems/_rels/system_1.xml.rels").contains("system_2.xml"));
+    assert!(disable_link_in(&mut model.clone(), "K", &lib).is_err());
+
+    let mut model = model;
+    restore_link_in(&mut model, "B").unwrap();
+    let (model, _) = roundtrip(&model);
+    let block = &model.root_system().unwrap().blocks[1];
+    assert_eq!(block.block_type, "Reference");
+    assert_eq!(block.properties["SourceBlock"], "FilterLib/A");
+    assert!(block.system_ref.is_none());
+    assert_eq!(block.port_counts.as_ref().unwrap().outs, Some(1));
+    let systems = model
+        .entries
+        .iter()
+        .filter(|e| matches!(e.content, SlxContent::SystemXml(_)))
+        .count();
+    assert_eq!(systems, 1);
+    assert!(!raw_text(&model, "simulink/systems/_rels/system_root.xml.rels").contains("system_1"));
+}
+
+const SECRET_XML: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Inport" Name="throttle_demand" SID="1">
+    <PortProperties>
+      <Port Type="out" Index="1"><P Name="Name">pedal</P></Port>
+    </PortProperties>
+  </Block>
+  <Block BlockType="Goto" Name="Publish" SID="2">
+    <P Name="GotoTag">pedal</P>
+    <P Name="Description">Feeds the torque map</P>
+  </Block>
+  <Block BlockType="From" Name="Subscribe" SID="3">
+    <P Name="GotoTag">pedal</P>
+  </Block>
+  <Block BlockType="CFunction" Name="TorqueLookup" SID="4">
+    <P Name="OutputCode">y = secret_map(u);</P>
+    <P Name="InitFcn">load_calibration</P>
+  </Block>
+  <Block BlockType="SubSystem" Name="Controller" SID="5">
+    <P Name="OpenFcn">open_doc</P>
+    <Mask>
+      <Help>Proprietary controller</Help>
+      <Initialization>k = 42;</Initialization>
+      <Display>disp('ACME')</Display>
+      <MaskParameter Name="k" Type="edit">
+        <Prompt>Gain</Prompt>
+        <Value>1</Value>
+        <Callback>check_k</Callback>
+      </MaskParameter>
+    </Mask>
+    <System>
+      <Block BlockType="Gain" Name="Kp" SID="6"/>
+      <Block BlockType="Gain" Name="Ki" SID="7"/>
+      <Annotation SID="8"><P Name="Name">ACME confidential</P></Annotation>
+    </System>
+  </Block>
+  <Line>
+    <P Name="Name">pedal</P>
+    <P Name="Src">1#out:1</P>
+    <Branch><P Name="Dst">2#in:1</P></Branch>
+    <Branch><P Name="Name">torque_req</P><P Name="Dst">4#in:1</P></Branch>
+  </Line>
+  <Annotation SID="9"><P Name="Name">Do not share</P></Annotation>
+</System>
+"#;
+
+fn secret_model() -> SlxArchive {
+    archive([("simulink/systems/system_root.xml", SECRET_XML.to_string())])
+}
+
+#[test]
+fn anonymizes_names_and_strips_content() {
+    let mut model = secret_model();
+    let root = model
+        .get_system_mut("simulink/systems/system_root.xml")
+        .unwrap();
+    let result = anonymize(root, &AnonymizeOptions::default());
+
+    let names: Vec<&str> = root.blocks.iter().map(|b| b.name.as_str()).collect();
+    assert_eq!(names, ["In1", "Goto1", "From1", "Block1", "Subsystem1"]);
+    let sub = root.blocks[4].subsystem.as_ref().unwrap();
+    assert_eq!(sub.blocks[0].name, "Gain1");
+    assert_eq!(sub.blocks[1].name, "Gain2");
+    assert!(sub.annotations.is_empty() && root.annotations.is_empty());
+    assert_eq!(result.renamed_blocks["Controller/Ki"], "Subsystem1/Gain2");
+
+    // Goto/From pairs, port names and line names stay consistent.
+    let tag = root.blocks[1].properties.get("GotoTag").unwrap();
+    assert_eq!(tag, "signal1");
+    assert_eq!(root.blocks[2].properties.get("GotoTag"), Some(tag));
+    assert_eq!(root.blocks[0].ports[0].properties["Name"], "signal1");
+    assert_eq!(root.lines[0].name.as_deref(), Some("signal1"));
+    assert_eq!(root.lines[0].properties["Name"], "signal1");
+    assert_eq!(root.lines[0].branches[1].name.as_deref(), Some("signal2"));
+    assert_eq!(result.renamed_signals["torque_req"], "signal2");
+
+    let cfun = &root.blocks[3];
+    assert_eq!(
+        cfun.properties.get("OutputCode").map(String::as_str),
+        Some("")
+    );
+    assert!(!cfun.properties.contains_key("InitFcn"));
+    assert!(!root.blocks[1].properties.contains_key("Description"));
+    let controller = &root.blocks[4];
+    assert!(!controller.properties.contains_key("OpenFcn"));
+    let mask = controller.mask.as_ref().unwrap();
+    assert!(mask.help.is_none() && mask.initialization.is_none() && mask.display.is_none());
+    assert!(mask.parameters[0].callback.is_none());
+    assert_eq!(mask.parameters[0].value.as_deref(), Some("1"));
+
+    assert_eq!(result.removed_annotations, 2);
+    assert_eq!(result.removed_callbacks, 3);
+    assert_eq!(result.stripped_code, 3);
+
+    // Nothing confidential survives regeneration.
+    let (_, bytes) = roundtrip(&model);
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let mut xml = String::new();
+    zip.by_name("simulink/systems/system_root.xml")
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    for secret in [
+        "throttle",
+        "pedal",
+        "torque",
+        "secret_map",
+        "calibration",
+        "ACME",
+        "share",
+        "check_k",
+        "Controller",
+        "Kp",
+    ] {
+        assert!(!xml.contains(secret), "{secret} left in {xml}");
+    }
+}
+
+#[test]
+fn anonymize_renames_signals_listed_by_bus_blocks() {
+    let xml = r#"<System>
+  <Block BlockType="Inport" Name="a" SID="1"/>
+  <Block BlockType="Inport" Name="b" SID="2"/>
+  <Block BlockType="BusCreator" Name="Bundle" SID="3">
+    <P Name="InputSignals">{'speed','wheel'}</P>
+  </Block>
+  <Block BlockType="BusSelector" Name="Pick" SID="4">
+    <P Name="InputSignals">{'speed',{'wheel',{'slip'}}}</P>
+    <P Name="OutputSignals">speed,wheel.slip</P>
+  </Block>
+  <Line><P Name="Name">speed</P><P Name="Src">1#out:1</P><P Name="Dst">3#in:1</P></Line>
+  <Line><P Name="Name">wheel</P><P Name="Src">2#out:1</P><P Name="Dst">3#in:2</P></Line>
+  <Line><P Name="Src">3#out:1</P><P Name="Dst">4#in:1</P></Line>
+</System>"#;
+    let mut model = archive([("simulink/systems/system_root.xml", xml.to_string())]);
+    let root = model
+        .get_system_mut("simulink/systems/system_root.xml")
+        .unwrap();
+    let result = anonymize(root, &AnonymizeOptions::default());
+
+    let speed = &result.renamed_signals["speed"];
+    let wheel = &result.renamed_signals["wheel"];
+    let slip = &result.renamed_signals["slip"];
+    assert_eq!(root.lines[0].name.as_ref(), Some(speed));
+    assert_eq!(root.lines[1].name.as_ref(), Some(wheel));
+    let property = |block: usize, name: &str| root.blocks[block].properties[name].clone();
+    assert_eq!(
+        property(2, "InputSignals"),
+        format!("{{'{speed}','{wheel}'}}")
+    );
+    assert_eq!(
+        property(3, "InputSignals"),
+        format!("{{'{speed}',{{'{wheel}',{{'{slip}'}}}}}}")
+    );
+    assert_eq!(
+        property(3, "OutputSignals"),
+        format!("{speed},{wheel}.{slip}")
+    );
+
+    let (_, bytes) = roundtrip(&model);
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).unwrap();
+    let mut xml = String::new();
+    zip.by_name("simulink/systems/system_root.xml")
+        .unwrap()
+        .read_to_string(&mut xml)
+        .unwrap();
+    for secret in ["speed", "wheel", "slip"] {
+        assert!(!xml.contains(secret), "{secret} left in {xml}");
+    }
+}
+
+#[test]
+fn anonymize_respects_options() {
+    let mut model = secret_model();
+    let root = model
+        .get_system_mut("simulink/systems/system_root.xml")
+        .unwrap();
+    let options = AnonymizeOptions {
+        rename_blocks: false,
+        strip_code: false,
+        ..Default::default()
+    };
+    let result = anonymize(root, &options);
+    assert!(result.renamed_blocks.is_empty());
+    assert_eq!(root.blocks[0].name, "throttle_demand");
+    assert_eq!(
+        root.blocks[3]
+            .properties
+            .get("OutputCode")
+            .map(String::as_str),
+        Some("y = secret_map(u);")
+    );
+    assert!(!root.blocks[3].properties.contains_key("InitFcn"));
+}
+
+#[test]
+fn anonymizes_archive_with_referenced_systems() {
+    let mut model = model();
+    model.entries.push(rustylink::model::SlxArchiveEntry {
+        path: "simulink/stateflow/chart_1.xml".to_string(),
+        content: SlxContent::Raw(
+            br#"<chart id="1"><P Name="name">A/N</P><P Name="script">secret()</P></chart>"#
+                .to_vec(),
+        ),
+        compressed: true,
+    });
+    model.entries.push(rustylink::model::SlxArchiveEntry {
+        path: "metadata/coreProperties.xml".to_string(),
+        content: SlxContent::Raw(
+            br#"<cp:coreProperties xmlns:cp="cp" xmlns:dc="dc"><dc:creator>Jane Roe</dc:creator><cp:revision>3</cp:revision></cp:coreProperties>"#
+                .to_vec(),
+        ),
+        compressed: true,
+    });
+    let result = anonymize_archive(&mut model, &AnonymizeOptions::default()).unwrap();
+
+    // Paths run through the referenced system files.
+    assert_eq!(result.renamed_blocks["A"], "Subsystem1");
+    assert_eq!(result.renamed_blocks["B/N/x"], "Subsystem2/Subsystem1/In1");
+    let inner = model.get_system("simulink/systems/system_4.xml").unwrap();
+    assert_eq!(inner.blocks[1].name, "Out1");
+
+    let chart = raw_text(&model, "simulink/stateflow/chart_1.xml");
+    assert_eq!(
+        chart,
+        r#"<chart id="1"><P Name="name">Subsystem1/Subsystem1</P><P Name="script"></P></chart>"#
+    );
+    let core = raw_text(&model, "metadata/coreProperties.xml");
+    assert!(!core.contains("Jane") && core.contains("<cp:revision>3</cp:revision>"));
+}
+
+/// Subsystem `S` whose Inport 3 sits above Inport 1, fed by two lines, one
+/// of them branching.
+const PORTS_XML: &str = r#"<System>
+  <Block BlockType="SubSystem" Name="S" SID="1">
+    <PortCounts in="2" out="1"/>
+    <P Name="Position">[200, 50, 260, 130]</P>
+    <System>
+      <Block BlockType="Inport" Name="a" SID="2">
+        <P Name="Position">[20, 100, 50, 114]</P>
+      </Block>
+      <Block BlockType="Inport" Name="b" SID="3">
+        <P Name="Position">[20, 20, 50, 34]</P>
+        <P Name="Port">3</P>
+      </Block>
+      <Block BlockType="Outport" Name="y" SID="4">
+        <P Name="Position">[300, 50, 330, 64]</P>
+      </Block>
+    </System>
+  </Block>
+  <Block BlockType="Constant" Name="c1" SID="5"/>
+  <Block BlockType="Constant" Name="c2" SID="6"/>
+  <Block BlockType="Terminator" Name="t" SID="7"/>
+  <Line>
+    <P Name="Src">5#out:1</P>
+    <P Name="Dst">1#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">6#out:1</P>
+    <Branch>
+      <P Name="Dst">1#in:3</P>
+    </Branch>
+    <Branch>
+      <P Name="Dst">7#in:1</P>
+    </Branch>
+  </Line>
+</System>"#;
+
+#[test]
+fn orders_subsystem_ports_and_follows_their_lines() {
+    let mut system = parse_system(PORTS_XML);
+    let changes = order_ports(&mut system, PortOrder::ByPosition);
+    let summary: Vec<_> = changes
+        .iter()
+        .map(|c| (c.block.as_str(), c.old_port, c.new_port, c.moved))
+        .collect();
+    assert_eq!(summary, [("S/b", 3, 1, false), ("S/a", 1, 2, false)]);
+    let xml = generate_system_xml(&system);
+    assert!(xml.contains(r#"<P Name="Dst">1#in:2</P>"#));
+    assert!(xml.contains(r#"<P Name="Dst">1#in:1</P>"#));
+    assert!(!xml.contains("1#in:3"));
+    let inner = system.blocks[0].subsystem.as_deref().unwrap();
+    assert_eq!(inner.blocks[1].properties["Port"], "1");
+    assert_eq!(inner.blocks[0].properties["Port"], "2");
+    assert!(order_ports(&mut system, PortOrder::ByPosition).is_empty());
+
+    // Keeping the numbers moves the blocks instead
+    let mut system = parse_system(PORTS_XML);
+    let changes = order_ports(&mut system, PortOrder::ByNumber);
+    let summary: Vec<_> = changes
+        .iter()
+        .map(|c| (c.block.as_str(), c.old_port, c.new_port, c.moved))
+        .collect();
+    assert_eq!(summary, [("S/a", 1, 1, true), ("S/b", 3, 2, true)]);
+    let inner = system.blocks[0].subsystem.as_deref().unwrap();
+    assert_eq!(
+        inner.blocks[0].position.as_deref(),
+        Some("[20, 20, 50, 34]")
+    );
+    assert_eq!(
+        inner.blocks[1].position.as_deref(),
+        Some("[20, 100, 50, 114]")
+    );
+    let xml = generate_system_xml(&system);
+    assert!(xml.contains(r#"<P Name="Dst">1#in:1</P>"#));
+    assert!(xml.contains(r#"<P Name="Dst">1#in:2</P>"#));
+}
+
+#[test]
+fn orders_ports_of_subsystems_in_their_own_files() {
+    let root = r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="SubSystem" Name="S" SID="1">
+    <PortCounts in="2"/>
+    <System Ref="system_1"/>
+  </Block>
+  <Block BlockType="Constant" Name="c" SID="5"/>
+  <Line><P Name="Src">5#out:1</P><P Name="Dst">1#in:2</P></Line>
+</System>
+"#;
+    let inner = r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Inport" Name="a" SID="2">
+    <P Name="Position">[20, 100, 50, 114]</P>
+  </Block>
+  <Block BlockType="Inport" Name="b" SID="3">
+    <P Name="Position">[20, 20, 50, 34]</P>
+    <P Name="Port">2</P>
+  </Block>
+</System>
+"#;
+    let mut model = archive([
+        ("simulink/systems/system_root.xml", root.to_string()),
+        ("simulink/systems/system_1.xml", inner.to_string()),
+    ]);
+    let changes = order_ports_in(&mut model, PortOrder::ByPosition).unwrap();
+    assert_eq!(changes.len(), 2);
+    assert_eq!(changes[0].block, "S/b");
+    let (model, _) = roundtrip(&model);
+    let root = model
+        .get_system("simulink/systems/system_root.xml")
+        .unwrap();
+    assert_eq!(root.lines[0].properties["Dst"], "1#in:1");
+    let inner = model.get_system("simulink/systems/system_1.xml").unwrap();
+    assert_eq!(inner.blocks[0].properties["Port"], "2");
+}
+
+#[test]
+fn names_signals_into_outports_to_workspace_and_logging() {
+    let mut system = parse_system(
+        r#"<System>
+  <Block BlockType="Inport" Name="wheel speed" SID="1"/>
+  <Block BlockType="Gain" Name="2x" SID="2">
+    <PortProperties>
+      <Port Type="out" Index="1"><P Name="DataLogging">on</P></Port>
+    </PortProperties>
+  </Block>
+  <Block BlockType="Demux" Name="split" SID="3">
+    <PortCounts out="2"/>
+  </Block>
+  <Block BlockType="Outport" Name="out1" SID="4"/>
+  <Block BlockType="ToWorkspace" Name="log" SID="5"/>
+  <Block BlockType="Gain" Name="g" SID="6"/>
+  <Block BlockType="Outport" Name="out2" SID="7"/>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">4#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">2#out:1</P>
+    <P Name="Dst">6#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">3#out:2</P>
+    <P Name="Dst">5#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Src">3#out:1</P>
+    <P Name="Dst">6#in:1</P>
+  </Line>
+  <Line>
+    <P Name="Name">split_2</P>
+    <P Name="Src">6#out:1</P>
+    <P Name="Dst">7#in:1</P>
+  </Line>
+</System>"#,
+    );
+    let named = name_signals(&mut system);
+    let summary: Vec<_> = named
+        .iter()
+        .map(|n| (n.block.as_str(), n.port, n.name.as_str()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("wheel speed", 1, "wheel_speed"),
+            ("2x", 1, "s_2x"),
+            ("split", 2, "split_2_2"),
+        ]
+    );
+    assert_eq!(system.lines[3].name, None);
+    assert_eq!(system.lines[4].name.as_deref(), Some("split_2"));
+    let xml = generate_system_xml(&system);
+    assert!(xml.contains(r#"<P Name="Name">wheel_speed</P>"#));
+    assert!(name_signals(&mut system).is_empty());
+}
@@ -0,0 +1,110 @@
+#![cfg(feature = "tui")]
+
+mod common;
+
+use common::parse;
+use ratatui::Terminal;
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::KeyCode;
+use rustylink::model::System;
+use rustylink::tui::{Browser, Focus};
+
+/// Root with a Gain and subsystem `Ctrl`, which holds subsystem `Inner`
+/// with a Saturation.
+fn model() -> System {
+    let inner = parse(
+        r#"<System>
+  <Block BlockType="Saturate" Name="Limit" SID="4"><P Name="UpperLimit">10</P></Block>
+</System>"#,
+    );
+    let mut ctrl = parse(
+        r#"<System>
+  <Block BlockType="Inport" Name="u" SID="2"/>
+  <Block BlockType="SubSystem" Name="Inner" SID="3"/>
+</System>"#,
+    );
+    ctrl.blocks[1].subsystem = Some(Box::new(inner));
+    let mut root = parse(
+        r#"<System>
+  <Block BlockType="Gain" Name="K" SID="1"><P Name="Gain">2.5</P></Block>
+  <Block BlockType="SubSystem" Name="Ctrl" SID="5"/>
+</System>"#,
+    );
+    root.blocks[1].subsystem = Some(Box::new(ctrl));
+    root
+}
+
+fn screen(browser: &mut Browser) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(100, 12)).unwrap();
+    terminal.draw(|frame| browser.draw(frame)).unwrap();
+    let buffer = terminal.backend().buffer();
+    (0..buffer.area.height)
+        .map(|y| {
+            (0..buffer.area.width)
+                .map(|x| buffer[(x, y)].symbol())
+                .collect::<String>()
+                + "\n"
+        })
+        .collect()
+}
+
+#[test]
+fn tree_navigation_and_parameters() {
+    let root = model();
+    let mut browser = Browser::new(&root, "plant");
+    let names = |b: &Browser| -> Vec<String> {
+        b.tree_rows()
+            .into_iter()
+            .map(|(depth, _, name)| format!("{depth}{name}"))
+            .collect()
+    };
+    assert_eq!(names(&browser), ["0plant", "1Ctrl"]);
+    assert_eq!(browser.selected_block().unwrap().name, "K");
+    let text = screen(&mut browser);
+    assert!(text.contains("K (Gain)") && text.contains("Ctrl (SubSystem)"));
+    assert!(text.contains("Gain") && text.contains("2.5"));
+
+    // Down to Ctrl, expand it, then show its blocks
+    browser.handle_key(KeyCode::Down);
+    browser.handle_key(KeyCode::Right);
+    assert_eq!(names(&browser), ["0plant", "1Ctrl", "2Inner"]);
+    browser.handle_key(KeyCode::Enter);
+    assert_eq!(browser.focus, Focus::Blocks);
+    assert_eq!(browser.selected_block().unwrap().name, "u");
+
+    // Enter on a subsystem block opens it
+    browser.handle_key(KeyCode::Down);
+    browser.handle_key(KeyCode::Enter);
+    assert_eq!(browser.current_path(), [1, 1]);
+    assert_eq!(browser.selected_block().unwrap().name, "Limit");
+    assert!(screen(&mut browser).contains("UpperLimit"));
+
+    // Collapsing twice goes up to the parent
+    browser.handle_key(KeyCode::Tab);
+    browser.handle_key(KeyCode::Left);
+    assert_eq!(browser.current_path(), [1]);
+    browser.handle_key(KeyCode::Left);
+    assert_eq!(names(&browser), ["0plant", "1Ctrl"]);
+    assert!(!browser.handle_key(KeyCode::Char('q')));
+}
+
+#[test]
+fn search_jumps_to_nested_blocks() {
+    let root = model();
+    let mut browser = Browser::new(&root, "plant");
+    for key in "/satur".chars() {
+        browser.handle_key(KeyCode::Char(key));
+    }
+    assert_eq!(browser.prompt.as_deref(), Some("satur"));
+    assert!(screen(&mut browser).contains("/satur"));
+    browser.handle_key(KeyCode::Enter);
+    assert_eq!(browser.results, Some(vec![(vec![1, 1], 0)]));
+    assert!(screen(&mut browser).contains("Ctrl/Inner/Limit"));
+    assert_eq!(browser.selected_block().unwrap().name, "Limit");
+
+    // The collapsed tree opens down to the result
+    browser.handle_key(KeyCode::Enter);
+    assert!(browser.results.is_none());
+    assert_eq!(browser.current_path(), [1, 1]);
+    assert_eq!(browser.selected_block().unwrap().name, "Limit");
+}
@@ -0,0 +1,65 @@
+#![cfg(feature = "testing")]
+
+mod common;
+
+use common::parse;
+use rustylink::model::System;
+use rustylink::testing::{DEFAULT_SIZE, Tolerance, check_baseline, compare_images, render_system};
+
+fn model(gain_x: i32) -> System {
+    parse(&format!(
+        r#"<System>
+  <Block BlockType="Inport" Name="In" SID="1">
+    <P Name="Position">[0, 0, 30, 20]</P>
+  </Block>
+  <Block BlockType="Gain" Name="K" SID="2">
+    <P Name="Position">[{gain_x}, 40, {}, 60]</P>
+  </Block>
+  <Line>
+    <P Name="Src">1#out:1</P>
+    <P Name="Dst">2#in:1</P>
+  </Line>
+</System>"#,
+        gain_x + 30
+    ))
+}
+
+#[test]
+fn gain_matches_baseline() {
+    let image = render_system(&model(100), DEFAULT_SIZE);
+    check_baseline(
+        &image,
+        concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/visual/gain.png"
+        ),
+        Tolerance::default(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn renderings_are_deterministic_and_detect_moved_blocks() {
+    let first = render_system(&model(100), DEFAULT_SIZE);
+    let second = render_system(&model(100), DEFAULT_SIZE);
+    let diff = compare_images(&first, &second, Tolerance::default()).unwrap();
+    assert_eq!(diff.differing_pixels, 0);
+    // Something was drawn on the white background
+    assert!(
+        first
+            .data()
+            .chunks_exact(4)
+            .any(|p| p[..3] != [255, 255, 255])
+    );
+
+    let moved = render_system(&model(160), DEFAULT_SIZE);
+    let diff = compare_images(&first, &moved, Tolerance::default()).unwrap();
+    assert!(!diff.is_within(Tolerance::default()), "{diff:?}");
+}
+
+#[test]
+fn images_of_different_sizes_do_not_compare() {
+    let small = render_system(&model(100), DEFAULT_SIZE / 2.0);
+    let large = render_system(&model(100), DEFAULT_SIZE);
+    assert!(compare_images(&small, &large, Tolerance::default()).is_err());
+}
@@ -0,0 +1,186 @@
+use camino::{Utf8Path, Utf8PathBuf};
+use rustylink::model::System;
+use rustylink::watch::{
+    OperationResult, WatchOperation, content_fingerprint, format_summary, load_model,
+    parse_operations, referenced_libraries, run_operations, watch,
+};
+use std::io::Write;
+use std::sync::mpsc;
+use std::time::Duration;
+use tempfile::tempdir;
+
+fn root_xml(gain: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="utf-8"?>
+<System>
+  <Block BlockType="Inport" Name="In" SID="1"><P Name="Position">[20, 20, 50, 34]</P></Block>
+  <Block BlockType="Gain" Name="K" SID="2">
+    <P Name="Position">[100, 10, 130, 40]</P>
+    <P Name="Gain">{gain}</P>
+  </Block>
+  <Block BlockType="Outport" Name="Out" SID="3"><P Name="Position">[200, 20, 230, 34]</P></Block>
+  <Line><P Name="Src">1#out:1</P><P Name="Dst">2#in:1</P></Line>
+  <Line><P Name="Src">2#out:1</P><P Name="Dst">3#in:1</P></Line>
+</System>
+"#
+    )
+}
+
+fn write_model(path: &Utf8Path, gain: &str, modified: &str) {
+    write_archive(path, modified, root_xml(gain));
+}
+
+fn write_archive(path: &Utf8Path, modified: &str, root: String) {
+    let tmp = path.with_extension("tmp");
+    {
+        let file = std::fs::File::create(&tmp).unwrap();
+        let mut zip = zip::ZipWriter::new(file);
+        let files = [
+            (
+                "docProps/core.xml",
+                format!("<modified>{modified}</modified>"),
+            ),
+            ("simulink/systems/system_root.xml", root),
+        ];
+        for (name, data) in files {
+            zip.start_file(name, zip::write::FileOptions::default())
+                .unwrap();
+            zip.write_all(data.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+    // Saved like an editor does: write a new file, then rename it over the old one.
+    std::fs::rename(&tmp, path).unwrap();
+}
+
+fn temp_path(dir: &tempfile::TempDir, name: &str) -> Utf8PathBuf {
+    Utf8PathBuf::from_path_buf(dir.path().join(name)).unwrap()
+}
+
+#[test]
+fn operations_are_parsed() {
+    assert_eq!(
+        parse_operations("lint, render").unwrap(),
+        [WatchOperation::Lint, WatchOperation::Render]
+    );
+    assert_eq!(
+        parse_operations("clones,").unwrap(),
+        [WatchOperation::Clones]
+    );
+    assert!(parse_operations("lint,format").is_err());
+    assert!(parse_operations("").is_err());
+}
+
+#[test]
+fn operations_report_pass_and_fail() {
+    let dir = tempdir().unwrap();
+    let model = temp_path(&dir, "m.slx");
+    write_model(&model, "2.5", "1");
+    let system = load_model(&model).unwrap();
+    let ops = [
+        WatchOperation::Lint,
+        WatchOperation::Render,
+        WatchOperation::Clones,
+    ];
+    let results = run_operations(&model, &system, &ops);
+    assert_eq!(
+        results[0],
+        OperationResult {
+            operation: WatchOperation::Lint,
+            passed: false,
+            summary: "0 rate issue(s), 1 magic number(s)".to_string(),
+        }
+    );
+    assert!(results[1].passed);
+    let svg = std::fs::read_to_string(model.with_extension("svg")).unwrap();
+    assert!(svg.starts_with("<svg"));
+    assert_eq!(results[2].summary, "0 clone group(s)");
+    let summary = format_summary(&results);
+    assert!(summary.starts_with("FAIL  lint: 0 rate issue(s), 1 magic number(s) | render: wrote "));
+    assert!(summary.ends_with(" | clones: 0 clone group(s)"));
+
+    write_model(&model, "1", "2");
+    let system = load_model(&model).unwrap();
+    let summary = format_summary(&run_operations(&model, &system, &ops[..1]));
+    assert_eq!(summary, "PASS  lint: ok");
+}
+
+#[test]
+fn fingerprint_ignores_package_metadata() {
+    let dir = tempdir().unwrap();
+    let model = temp_path(&dir, "m.slx");
+    write_model(&model, "2", "1");
+    let first = content_fingerprint(&model).unwrap();
+    write_model(&model, "2", "2");
+    assert_eq!(content_fingerprint(&model).unwrap(), first);
+    write_model(&model, "3", "2");
+    assert_ne!(content_fingerprint(&model).unwrap(), first);
+}
+
+#[test]
+fn libraries_are_found_next_to_the_model() {
+    let dir = tempdir().unwrap();
+    std::fs::write(dir.path().join("Lib.slx"), b"").unwrap();
+    let xml = r#"<System>
+  <Block BlockType="Reference" Name="A" SID="1"><P Name="SourceBlock">Lib/Filter</P></Block>
+  <Block BlockType="Reference" Name="B" SID="2"><P Name="SourceBlock">Lib/Other</P></Block>
+  <Block BlockType="Reference" Name="C" SID="3"><P Name="SourceBlock">Missing/X</P></Block>
+  <Block BlockType="Reference" Name="D" SID="4"><P Name="SourceBlock">simulink/Discrete/Unit Delay</P></Block>
+</System>"#;
+    let doc = roxmltree::Document::parse(xml).unwrap();
+    let system: System =
+        rustylink::block::parse_system_shallow(doc.root_element(), Utf8Path::new("")).unwrap();
+    let libs = referenced_libraries(&temp_path(&dir, "m.slx"), &system);
+    assert_eq!(libs, [temp_path(&dir, "Lib.slx")]);
+}
+
+#[test]
+fn watch_reruns_after_save() {
+    let dir = tempdir().unwrap();
+    let model = temp_path(&dir, "m.slx");
+    write_model(&model, "2.5", "1");
+
+    let (tx, rx) = mpsc::channel();
+    let watched = model.clone();
+    std::thread::spawn(move || {
+        let _ = watch(&watched, &[WatchOperation::Lint], |line| {
+            let _ = tx.send(line.to_string());
+        });
+    });
+    let timeout = Duration::from_secs(10);
+    let first = rx.recv_timeout(timeout).unwrap();
+    assert!(first.starts_with("FAIL  lint:"), "{first}");
+
+    write_model(&model, "1", "2");
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "PASS  lint: ok");
+}
+
+#[test]
+fn watch_reruns_after_library_change() {
+    let dir = tempdir().unwrap();
+    let model = temp_path(&dir, "m.slx");
+    let library = temp_path(&dir, "Lib.slx");
+    let root = root_xml("1").replace(
+        "</System>",
+        r#"  <Block BlockType="Reference" Name="F" SID="4"><P Name="SourceBlock">Lib/Filter</P></Block>
+</System>"#,
+    );
+    write_archive(&model, "1", root);
+    write_model(&library, "1", "1");
+
+    let (tx, rx) = mpsc::channel();
+    let watched = model.clone();
+    std::thread::spawn(move || {
+        let _ = watch(&watched, &[WatchOperation::Lint], |line| {
+            let _ = tx.send(line.to_string());
+        });
+    });
+    let timeout = Duration::from_secs(10);
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "PASS  lint: ok");
+
+    // A metadata-only save of the library changes nothing
+    write_model(&library, "1", "2");
+    assert!(rx.recv_timeout(Duration::from_secs(1)).is_err());
+    write_model(&library, "3", "3");
+    assert_eq!(rx.recv_timeout(timeout).unwrap(), "PASS  lint: ok");
+}